@@ -0,0 +1,295 @@
+//! Shared procedural noise.
+//!
+//! Integer hashing for seeded randomness plus 3D Perlin / simplex / value noise
+//! and fractal (fBm) sums. WGSL ports of these functions live next to the
+//! plugins that use them and must stay in sync (all arithmetic on `u32` wraps
+//! the same way in WGSL).
+
+/// Hashes three integers into a well-mixed `u32`.
+pub fn hash(x: u32, y: u32, z: u32) -> u32 {
+    let mut v = x.wrapping_mul(73856093) ^ y.wrapping_mul(19349663) ^ z.wrapping_mul(83492791);
+    v ^= v >> 16;
+    v = v.wrapping_mul(0x7feb_352d);
+    v ^= v >> 15;
+    v = v.wrapping_mul(0x846c_a68b);
+    v ^ (v >> 16)
+}
+
+/// Maps a hash to `[0, 1)` using its top 24 bits.
+pub fn unit(v: u32) -> f32 {
+    (v >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// `unit(hash(x, y, z))`.
+pub fn hash_unit(x: u32, y: u32, z: u32) -> f32 {
+    unit(hash(x, y, z))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseType {
+    /// Gradient noise on a cubic lattice.
+    Perlin,
+    /// Gradient noise on a simplex lattice (fewer axis-aligned artifacts).
+    Simplex,
+    /// Interpolated random lattice values (blockier).
+    Value,
+}
+
+fn lattice(ix: i32, iy: i32, iz: i32, seed: u32) -> u32 {
+    hash(
+        ix as u32,
+        iy as u32,
+        (iz as u32) ^ seed.wrapping_mul(0x9e37_79b9),
+    )
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Dot product with one of the 12 cube-edge gradients picked by `h`.
+fn grad(h: u32, x: f32, y: f32, z: f32) -> f32 {
+    let h = h & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn wrap(i: i32, period: u32) -> i32 {
+    if period == 0 {
+        i
+    } else {
+        i.rem_euclid(period.min(i32::MAX as u32) as i32)
+    }
+}
+
+/// Cubic-lattice noise (Perlin or value); `period` wraps x / y lattice cells (0 = none).
+fn lattice_noise(kind: NoiseType, [x, y, z]: [f32; 3], seed: u32, period: [u32; 2]) -> f32 {
+    let (fx, fy, fz) = (x.floor(), y.floor(), z.floor());
+    let (tx, ty, tz) = (x - fx, y - fy, z - fz);
+    let (ix, iy, iz) = (fx as i32, fy as i32, fz as i32);
+    let (u, v, w) = (fade(tx), fade(ty), fade(tz));
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let h = lattice(
+            wrap(ix.wrapping_add(dx), period[0]),
+            wrap(iy.wrapping_add(dy), period[1]),
+            iz.wrapping_add(dz),
+            seed,
+        );
+        match kind {
+            NoiseType::Value => unit(h) * 2.0 - 1.0,
+            _ => grad(h, tx - dx as f32, ty - dy as f32, tz - dz as f32),
+        }
+    };
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    let n = lerp(lerp(x00, x10, v), lerp(x01, x11, v), w);
+    match kind {
+        NoiseType::Value => n,
+        _ => n * 0.982,
+    }
+}
+
+/// 3D simplex noise (Gustavson's formulation).
+fn simplex(p: [f32; 3], seed: u32) -> f32 {
+    const F3: f32 = 1.0 / 3.0;
+    const G3: f32 = 1.0 / 6.0;
+
+    let s = (p[0] + p[1] + p[2]) * F3;
+    let (i, j, k) = ((p[0] + s).floor(), (p[1] + s).floor(), (p[2] + s).floor());
+    let t = (i + j + k) * G3;
+    let x0 = [p[0] - (i - t), p[1] - (j - t), p[2] - (k - t)];
+
+    // 単体内のどの四面体にいるかで 2 番目・3 番目の頂点を決める
+    let (o1, o2) = if x0[0] >= x0[1] {
+        if x0[1] >= x0[2] {
+            ([1, 0, 0], [1, 1, 0])
+        } else if x0[0] >= x0[2] {
+            ([1, 0, 0], [1, 0, 1])
+        } else {
+            ([0, 0, 1], [1, 0, 1])
+        }
+    } else if x0[1] < x0[2] {
+        ([0, 0, 1], [0, 1, 1])
+    } else if x0[0] < x0[2] {
+        ([0, 1, 0], [0, 1, 1])
+    } else {
+        ([0, 1, 0], [1, 1, 0])
+    };
+
+    let (i, j, k) = (i as i32, j as i32, k as i32);
+    let mut n = 0.0;
+    for (offset, corner) in [
+        ([0, 0, 0], 0.0),
+        (o1, G3),
+        (o2, 2.0 * G3),
+        ([1, 1, 1], 3.0 * G3),
+    ] {
+        let d = [
+            x0[0] - offset[0] as f32 + corner,
+            x0[1] - offset[1] as f32 + corner,
+            x0[2] - offset[2] as f32 + corner,
+        ];
+        let t = 0.6 - d[0] * d[0] - d[1] * d[1] - d[2] * d[2];
+        if t > 0.0 {
+            let h = lattice(
+                i.wrapping_add(offset[0]),
+                j.wrapping_add(offset[1]),
+                k.wrapping_add(offset[2]),
+                seed,
+            );
+            n += t * t * t * t * grad(h, d[0], d[1], d[2]);
+        }
+    }
+    32.0 * n
+}
+
+/// Single-octave noise in roughly `[-1, 1]`.
+///
+/// With a non-zero `period` the result repeats every `period` units along x / y.
+/// Cubic-lattice noise wraps its lattice; simplex noise, whose lattice is skewed,
+/// is cross-faded between the four periodic copies instead.
+pub fn noise3(kind: NoiseType, p: [f32; 3], seed: u32, period: [u32; 2]) -> f32 {
+    match kind {
+        NoiseType::Perlin | NoiseType::Value => lattice_noise(kind, p, seed, period),
+        NoiseType::Simplex if period == [0, 0] => simplex(p, seed),
+        NoiseType::Simplex => {
+            let (tx, ty) = (period[0].max(1) as f32, period[1].max(1) as f32);
+            let x = if period[0] == 0 {
+                p[0]
+            } else {
+                p[0].rem_euclid(tx)
+            };
+            let y = if period[1] == 0 {
+                p[1]
+            } else {
+                p[1].rem_euclid(ty)
+            };
+            let (u, v) = (
+                if period[0] == 0 { 0.0 } else { x / tx },
+                if period[1] == 0 { 0.0 } else { y / ty },
+            );
+            let n = |dx: f32, dy: f32| simplex([x - dx, y - dy, p[2]], seed);
+            lerp(
+                lerp(n(0.0, 0.0), n(tx, 0.0), u),
+                lerp(n(0.0, ty), n(tx, ty), u),
+                v,
+            )
+        }
+    }
+}
+
+/// Fractal Brownian motion: a sum of noise octaves, Blender Noise Texture style.
+#[derive(Clone, Copy, Debug)]
+pub struct Fbm {
+    pub kind: NoiseType,
+    /// Octaves beyond the first; the fractional part fades the last one in.
+    pub detail: f32,
+    /// Amplitude multiplier per octave (gain).
+    pub roughness: f32,
+    /// Frequency multiplier per octave.
+    pub lacunarity: f32,
+    pub seed: u32,
+    /// Tile period in base-octave lattice units along x / y (0 = not tiled).
+    pub period: [u32; 2],
+}
+
+/// Highest supported `Fbm::detail`.
+pub const MAX_DETAIL: f32 = 15.0;
+
+impl Fbm {
+    /// Fractal noise at `p`, normalized to roughly `[-1, 1]`.
+    pub fn sample(&self, p: [f32; 3]) -> f32 {
+        let finite = |v: f32, fallback: f32| if v.is_finite() { v } else { fallback };
+        let detail = finite(self.detail, 0.0).clamp(0.0, MAX_DETAIL);
+        let roughness = finite(self.roughness, 0.5).clamp(0.0, 1.0);
+        let lacunarity = finite(self.lacunarity, 2.0).clamp(0.0, 1000.0);
+
+        let octave = |o: u32, freq: f32| {
+            // タイル時は各オクターブの周期を整数に丸めて継ぎ目をなくす
+            let (scale, period) = if self.period == [0, 0] {
+                ([freq; 2], [0; 2])
+            } else {
+                let period = self.period.map(|t| {
+                    if t == 0 {
+                        0
+                    } else {
+                        (t as f32 * freq).round().max(1.0) as u32
+                    }
+                });
+                let scale: [f32; 2] = std::array::from_fn(|a| {
+                    if self.period[a] == 0 {
+                        freq
+                    } else {
+                        period[a] as f32 / self.period[a] as f32
+                    }
+                });
+                (scale, period)
+            };
+            let q = [p[0] * scale[0], p[1] * scale[1], p[2] * freq];
+            noise3(self.kind, q, self.seed.wrapping_add(o), period)
+        };
+
+        let mut freq = 1.0;
+        let mut amp = 1.0;
+        let mut max_amp = 0.0;
+        let mut sum = 0.0;
+        let whole = detail.floor() as u32;
+        for o in 0..=whole {
+            sum += octave(o, freq) * amp;
+            max_amp += amp;
+            amp *= roughness;
+            freq *= lacunarity;
+        }
+
+        let rmd = detail - whole as f32;
+        if rmd > 0.0 {
+            let last = octave(whole + 1, freq) * amp;
+            let with_last = (sum + last) / (max_amp + amp);
+            lerp(sum / max_amp, with_last, rmd)
+        } else {
+            sum / max_amp
+        }
+    }
+}
+
+/// Output levels shared by the noise generators, applied to values in `[0, 1]`.
+#[derive(Clone, Copy, Debug)]
+pub struct Remap {
+    /// Gain around mid gray (1 = unchanged).
+    pub contrast: f32,
+    /// Offset added after contrast.
+    pub brightness: f32,
+    pub invert: bool,
+}
+
+impl Remap {
+    pub fn apply(&self, v: f32) -> f32 {
+        let contrast = if self.contrast.is_finite() {
+            self.contrast
+        } else {
+            1.0
+        };
+        let brightness = if self.brightness.is_finite() {
+            self.brightness
+        } else {
+            0.0
+        };
+        let v = ((v - 0.5) * contrast + 0.5 + brightness).clamp(0.0, 1.0);
+        if self.invert { 1.0 - v } else { v }
+    }
+}
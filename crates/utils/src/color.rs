@@ -0,0 +1,128 @@
+//! Color-space helpers shared by the color plugins.
+//!
+//! AE hands effects gamma-encoded working-space values; these helpers treat them
+//! as sRGB. OKLab / OKLCH follow Björn Ottosson's reference implementation.
+
+use after_effects::PixelF32;
+
+/// sRGB transfer function: encoded -> linear.
+#[inline]
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// sRGB transfer function: linear -> encoded.
+#[inline]
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Rec. 709 luma of the (encoded) color channels.
+#[inline]
+pub fn luminance(px: PixelF32) -> f32 {
+    0.2126 * px.red + 0.7152 * px.green + 0.0722 * px.blue
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    /// Hue in radians.
+    pub h: f32,
+}
+
+impl Oklab {
+    pub fn from_linear_srgb([r, g, b]: [f32; 3]) -> Self {
+        let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+        let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+        let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+        let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    pub fn to_linear_srgb(self) -> [f32; 3] {
+        let l = self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b;
+        let m = self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b;
+        let s = self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b;
+        let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+        [
+            4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+            -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+        ]
+    }
+
+    /// From gamma-encoded sRGB channels.
+    pub fn from_srgb(rgb: [f32; 3]) -> Self {
+        Self::from_linear_srgb(rgb.map(srgb_to_linear))
+    }
+
+    /// To gamma-encoded sRGB channels (not clamped).
+    pub fn to_srgb(self) -> [f32; 3] {
+        self.to_linear_srgb().map(linear_to_srgb)
+    }
+
+    pub fn to_oklch(self) -> Oklch {
+        Oklch {
+            l: self.l,
+            c: self.a.hypot(self.b),
+            h: self.b.atan2(self.a),
+        }
+    }
+
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            l: lerp(self.l, other.l, t),
+            a: lerp(self.a, other.a, t),
+            b: lerp(self.b, other.b, t),
+        }
+    }
+}
+
+impl Oklch {
+    pub fn to_oklab(self) -> Oklab {
+        Oklab {
+            l: self.l,
+            a: self.c * self.h.cos(),
+            b: self.c * self.h.sin(),
+        }
+    }
+
+    /// Interpolates along the shorter hue arc.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let tau = std::f32::consts::TAU;
+        let mut dh = (other.h - self.h).rem_euclid(tau);
+        if dh > std::f32::consts::PI {
+            dh -= tau;
+        }
+        Self {
+            l: lerp(self.l, other.l, t),
+            c: lerp(self.c, other.c, t),
+            h: self.h + dh * t,
+        }
+    }
+}
+
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
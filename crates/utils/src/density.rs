@@ -0,0 +1,33 @@
+//! Tone-driven density maps for the point distributions (stippling, scatter).
+
+use after_effects::PixelF32;
+
+use crate::color::luminance;
+use crate::finite_or;
+use crate::image::ImageBuf;
+
+/// Density in 0..1 of one pixel: its brightness (or darkness when `bright` is
+/// false) weighted by alpha.
+fn tone(px: PixelF32, bright: bool) -> f32 {
+    let lum = finite_or(luminance(px), 0.0).clamp(0.0, 1.0);
+    let tone = if bright { lum } else { 1.0 - lum };
+    tone * finite_or(px.alpha, 0.0).clamp(0.0, 1.0)
+}
+
+/// Density of `image` on a `width`×`height` grid anchored at its top-left
+/// pixel; cells outside the image repeat the edge.
+pub fn density_map(image: &ImageBuf, width: usize, height: usize, bright: bool) -> Vec<f32> {
+    (0..width * height)
+        .map(|i| {
+            let px = image.get_clamped((i % width) as isize, (i / width) as isize);
+            tone(px, bright)
+        })
+        .collect()
+}
+
+/// Density of the cell containing (`x`, `y`), clamped to the map.
+pub fn density_at(density: &[f32], width: usize, height: usize, x: f32, y: f32) -> f32 {
+    let xi = (x.max(0.0) as usize).min(width - 1);
+    let yi = (y.max(0.0) as usize).min(height - 1);
+    density[yi * width + xi]
+}
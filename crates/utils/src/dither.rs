@@ -0,0 +1,213 @@
+//! Ordered-dithering threshold maps shared by the quantizing plugins.
+
+use std::sync::OnceLock;
+
+/// Side of the tiled [`blue_noise`] map.
+pub const BLUE_NOISE_SIZE: usize = 64;
+
+/// Bayer threshold in 0..1 for a `2^order` square matrix.
+pub fn bayer(x: usize, y: usize, order: u32) -> f32 {
+    let mut v = 0usize;
+    for i in 0..order {
+        let (xb, yb) = ((x >> i) & 1, (y >> i) & 1);
+        let shift = 2 * (order - 1 - i);
+        v |= (((xb ^ yb) << 1) | yb) << shift;
+    }
+    (v as f32 + 0.5) / (1usize << (2 * order)) as f32
+}
+
+/// Tiled blue-noise threshold map (void-and-cluster, built once).
+pub fn blue_noise() -> &'static [f32] {
+    static MAP: OnceLock<Vec<f32>> = OnceLock::new();
+    MAP.get_or_init(|| {
+        const N: usize = BLUE_NOISE_SIZE;
+        const SIGMA: f32 = 1.5;
+
+        // トーラス上のガウスカーネルを事前計算
+        let wrap = |d: usize| d.min(N - d) as f32;
+        let kernel: Vec<f32> = (0..N * N)
+            .map(|i| {
+                let (dx, dy) = (wrap(i % N), wrap(i / N));
+                (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+            })
+            .collect();
+
+        // 最もエネルギーの低い点（最大の空隙）に順に点を置き、置いた順位を閾値にする
+        let mut energy = vec![0.0f32; N * N];
+        let mut rank = vec![0.0f32; N * N];
+        let mut placed = vec![false; N * N];
+        for r in 0..N * N {
+            let Some(i) = (0..N * N)
+                .filter(|&i| !placed[i])
+                .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            else {
+                break;
+            };
+            placed[i] = true;
+            rank[i] = (r as f32 + 0.5) / (N * N) as f32;
+            let (px, py) = (i % N, i / N);
+            for (j, e) in energy.iter_mut().enumerate() {
+                let dx = (j % N + N - px) % N;
+                let dy = (j / N + N - py) % N;
+                *e += kernel[dy * N + dx];
+            }
+        }
+        rank
+    })
+}
+
+/// Gaussian width in texels of the [`void_and_cluster`] energy filter.
+const CLUSTER_SIGMA: f32 = 1.5;
+
+/// Seeded, tileable `size`×`size` blue-noise threshold map (Ulichney's
+/// void-and-cluster), row-major ranks in 0..1.
+///
+/// Roughly `size⁴` work, so callers should build it once and keep it.
+pub fn void_and_cluster(size: usize, seed: u32) -> Vec<f32> {
+    let n = size.max(1);
+    let count = n * n;
+    let rank_value = |r: usize| (r as f32 + 0.5) / count as f32;
+
+    // 初期パターン: 約 1 割の点をシードから散らす
+    let mut field = Energy::new(n);
+    let initial = (count / 10).max(1);
+    let mut state = u64::from(seed) ^ 0x9e37_79b9_7f4a_7c15;
+    let mut placed = 0;
+    while placed < initial {
+        let i = (splitmix64(&mut state) % count as u64) as usize;
+        if !field.bits[i] {
+            field.toggle(i);
+            placed += 1;
+        }
+    }
+
+    // 最も密な点を最大の空隙へ移し、動かなくなるまで緩和する
+    for _ in 0..count {
+        let Some(cluster) = field.tightest_cluster() else {
+            break;
+        };
+        field.toggle(cluster);
+        let Some(void) = field.largest_void() else {
+            break;
+        };
+        field.toggle(void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0.0; count];
+    // 初期パターンの点は密な順に取り除き、上の順位から割り当てる
+    let mut removal = field.clone();
+    for r in (0..initial).rev() {
+        let Some(i) = removal.tightest_cluster() else {
+            break;
+        };
+        removal.toggle(i);
+        rank[i] = rank_value(r);
+    }
+    // 残りは最大の空隙から順に埋める
+    for r in initial..count {
+        let Some(i) = field.largest_void() else {
+            break;
+        };
+        field.toggle(i);
+        rank[i] = rank_value(r);
+    }
+    rank
+}
+
+/// Interleaved gradient noise (Jimenez 2014) in 0..1 at pixel `(x, y)`.
+pub fn interleaved_gradient(x: f32, y: f32) -> f32 {
+    (52.982_92 * (0.067_110_56 * x + 0.005_837_15 * y).fract()).fract()
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Binary pattern on a torus with its Gaussian-filtered density, plus the
+/// emptiest unset and densest set texel of every row for fast lookups.
+#[derive(Clone)]
+struct Energy {
+    n: usize,
+    radius: usize,
+    kernel: Vec<f32>,
+    bits: Vec<bool>,
+    energy: Vec<f32>,
+    voids: Vec<Option<usize>>,
+    clusters: Vec<Option<usize>>,
+}
+
+impl Energy {
+    fn new(n: usize) -> Self {
+        // 3σ で打ち切り、トーラスで同じ画素を二度数えない範囲に収める
+        let radius = ((3.0 * CLUSTER_SIGMA).ceil() as usize).min((n - 1) / 2);
+        let side = 2 * radius + 1;
+        let kernel = (0..side * side)
+            .map(|i| {
+                let dx = (i % side) as f32 - radius as f32;
+                let dy = (i / side) as f32 - radius as f32;
+                (-(dx * dx + dy * dy) / (2.0 * CLUSTER_SIGMA * CLUSTER_SIGMA)).exp()
+            })
+            .collect();
+        let mut field = Self {
+            n,
+            radius,
+            kernel,
+            bits: vec![false; n * n],
+            energy: vec![0.0; n * n],
+            voids: vec![None; n],
+            clusters: vec![None; n],
+        };
+        for y in 0..n {
+            field.refresh_row(y);
+        }
+        field
+    }
+
+    fn toggle(&mut self, i: usize) {
+        let (n, r) = (self.n, self.radius);
+        self.bits[i] = !self.bits[i];
+        let sign = if self.bits[i] { 1.0 } else { -1.0 };
+        let (px, py) = (i % n, i / n);
+        let side = 2 * r + 1;
+        for ky in 0..side {
+            let y = (py + n + ky - r) % n;
+            for kx in 0..side {
+                let x = (px + n + kx - r) % n;
+                self.energy[y * n + x] += sign * self.kernel[ky * side + kx];
+            }
+            self.refresh_row(y);
+        }
+    }
+
+    fn refresh_row(&mut self, y: usize) {
+        let row = y * self.n..(y + 1) * self.n;
+        let by_energy = |&a: &usize, &b: &usize| self.energy[a].total_cmp(&self.energy[b]);
+        self.voids[y] = row.clone().filter(|&i| !self.bits[i]).min_by(by_energy);
+        self.clusters[y] = row.filter(|&i| self.bits[i]).max_by(by_energy);
+    }
+
+    /// Unset texel with the lowest energy.
+    fn largest_void(&self) -> Option<usize> {
+        self.voids
+            .iter()
+            .flatten()
+            .copied()
+            .min_by(|&a, &b| self.energy[a].total_cmp(&self.energy[b]))
+    }
+
+    /// Set texel with the highest energy.
+    fn tightest_cluster(&self) -> Option<usize> {
+        self.clusters
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(|&a, &b| self.energy[a].total_cmp(&self.energy[b]))
+    }
+}
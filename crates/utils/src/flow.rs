@@ -0,0 +1,338 @@
+//! Dense optical flow and the RG motion vector encoding shared by the
+//! motion-aware plugins.
+
+use after_effects::PixelF32;
+
+use crate::color::luminance;
+use crate::finite_or;
+use crate::image::ImageBuf;
+
+pub const MAX_LEVELS: u32 = 8;
+pub const MAX_WINDOW_RADIUS: u32 = 16;
+pub const MAX_ITERATIONS: u32 = 10;
+/// Coarsest pyramid level is kept at least this many pixels on its short side.
+const MIN_LEVEL_SIZE: usize = 8;
+
+/// Settings of [`lucas_kanade`].
+#[derive(Clone, Copy, Debug)]
+pub struct LucasKanade {
+    /// Pyramid levels; each halves the resolution and doubles the trackable motion.
+    pub levels: u32,
+    /// Neighbourhood assumed to move together, in pixels per level.
+    pub window_radius: u32,
+    /// Refinement passes per level.
+    pub iterations: u32,
+}
+
+/// RG encoding of motion vector (`vx`, `vy`): 0.5 = still, 0 / 1 = ∓`scale` pixels.
+pub fn encode(vx: f32, vy: f32, scale: f32) -> (f32, f32) {
+    let e = |v: f32| finite_or(v / scale * 0.5 + 0.5, 0.5).clamp(0.0, 1.0);
+    (e(vx), e(vy))
+}
+
+/// Motion vector in pixels of an RG encoded pixel (see [`encode`]).
+pub fn decode(px: PixelF32, scale: f32) -> (f32, f32) {
+    let d = |c: f32| (finite_or(c, 0.5).clamp(0.0, 1.0) - 0.5) * 2.0 * scale;
+    (d(px.red), d(px.green))
+}
+
+/// Decoded vector of `map` at pixel (`x`, `y`) of a `width`×`height` layer,
+/// stretching maps of another size.
+pub fn vector_at(
+    map: &ImageBuf,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    scale: f32,
+) -> (f32, f32) {
+    if map.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mx = x * map.width / width.max(1);
+    let my = y * map.height / height.max(1);
+    decode(map.get_clamped(mx as isize, my as isize), scale)
+}
+
+/// Single-channel image.
+#[derive(Clone)]
+struct Plane {
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+
+impl Plane {
+    /// Premultiplied luminance of `image`, resized to `width`×`height` by
+    /// nearest neighbour when the sizes differ.
+    fn luma(image: &ImageBuf, width: usize, height: usize) -> Self {
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mx = x * image.width / width.max(1);
+                let my = y * image.height / height.max(1);
+                let px = image.get_clamped(mx as isize, my as isize);
+                let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+                data.push(finite_or(luminance(px), 0.0).clamp(-1e3, 1e3) * a);
+            }
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    fn at(&self, x: isize, y: isize) -> f32 {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.data[y * self.width + x]
+    }
+
+    fn bilinear(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (xi, yi) = (x0 as isize, y0 as isize);
+        let top = self.at(xi, yi) + (self.at(xi + 1, yi) - self.at(xi, yi)) * tx;
+        let bottom = self.at(xi, yi + 1) + (self.at(xi + 1, yi + 1) - self.at(xi, yi + 1)) * tx;
+        top + (bottom - top) * ty
+    }
+
+    /// Half-resolution reduction with a 5-tap binomial low-pass, so fine
+    /// texture does not alias into false motion on the coarse levels.
+    fn reduce(&self) -> Self {
+        const TAPS: [f32; 5] = [1.0 / 16.0, 4.0 / 16.0, 6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0];
+        let (width, height) = (self.width.div_ceil(2), self.height.div_ceil(2));
+        let mut rows = Vec::with_capacity(width * self.height);
+        for y in 0..self.height as isize {
+            for x in 0..width as isize {
+                let s: f32 = (0..5)
+                    .map(|i| TAPS[i] * self.at(2 * x + i as isize - 2, y))
+                    .sum();
+                rows.push(s);
+            }
+        }
+        let rows = Self {
+            width,
+            height: self.height,
+            data: rows,
+        };
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let s: f32 = (0..5)
+                    .map(|i| TAPS[i] * rows.at(x, 2 * y + i as isize - 2))
+                    .sum();
+                data.push(s);
+            }
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Triangle-weighted sum over the window of radius about `r` (a box of half
+    /// the radius applied twice). Unlike a plain box its frequency response is
+    /// never negative, which keeps the per-pixel iteration from oscillating.
+    fn window_sum(&self, r: usize) -> Self {
+        let half = r.div_ceil(2);
+        self.box_sum(half).box_sum(half)
+    }
+
+    /// Sum over the (2r+1)² box around every pixel, edges clamped.
+    fn box_sum(&self, r: usize) -> Self {
+        let (w, h) = (self.width, self.height);
+        let pass = |src: &[f32], len: usize, stride: usize, lines: usize, step: usize| {
+            let mut out = vec![0.0; src.len()];
+            for line in 0..lines {
+                let base = line * step;
+                let at = |i: isize| src[base + i.clamp(0, len as isize - 1) as usize * stride];
+                // 窓をずらしながら差分で和を更新する
+                let mut sum: f32 = (-(r as isize)..=r as isize).map(at).sum();
+                for i in 0..len {
+                    out[base + i * stride] = sum;
+                    sum += at(i as isize + r as isize + 1) - at(i as isize - r as isize);
+                }
+            }
+            out
+        };
+        let rows = pass(&self.data, w, 1, h, w);
+        let data = pass(&rows, h, w, w, 1);
+        Self {
+            width: w,
+            height: h,
+            data,
+        }
+    }
+}
+
+/// Per-pixel motion vectors in pixels.
+#[derive(Clone, Debug)]
+pub struct Flow {
+    pub width: usize,
+    pub height: usize,
+    pub vectors: Vec<(f32, f32)>,
+}
+
+impl Flow {
+    pub fn zero(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            vectors: vec![(0.0, 0.0); width * height],
+        }
+    }
+
+    /// 3×3 median of each component, removing isolated outliers before they
+    /// are propagated to the finer levels.
+    fn median(&self) -> Self {
+        let (w, h) = (self.width as isize, self.height as isize);
+        let mut vectors = Vec::with_capacity(self.vectors.len());
+        for y in 0..h {
+            for x in 0..w {
+                let mut us = [0.0f32; 9];
+                let mut vs = [0.0f32; 9];
+                for (k, (dx, dy)) in (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .enumerate()
+                {
+                    let sx = (x + dx).clamp(0, w - 1) as usize;
+                    let sy = (y + dy).clamp(0, h - 1) as usize;
+                    (us[k], vs[k]) = self.vectors[sy * self.width + sx];
+                }
+                us.sort_by(f32::total_cmp);
+                vs.sort_by(f32::total_cmp);
+                vectors.push((us[4], vs[4]));
+            }
+        }
+        Self {
+            width: self.width,
+            height: self.height,
+            vectors,
+        }
+    }
+
+    /// Field of the next finer level (`width`×`height`), vectors doubled.
+    fn upsample(&self, width: usize, height: usize) -> Self {
+        let mut vectors = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let sx = (x / 2).min(self.width - 1);
+                let sy = (y / 2).min(self.height - 1);
+                let (u, v) = self.vectors[sy * self.width + sx];
+                vectors.push((u * 2.0, v * 2.0));
+            }
+        }
+        Self {
+            width,
+            height,
+            vectors,
+        }
+    }
+}
+
+/// Largest per-iteration update, in pixels of the current level.
+const MAX_STEP: f32 = 1.0;
+/// Smallest mean structure tensor eigenvalue (luma² per pixel) that is trusted.
+const MIN_EIGEN: f32 = 1e-5;
+
+/// Lucas-Kanade refinement of `field` on one pyramid level.
+fn refine(current: &Plane, previous: &Plane, field: &mut Flow, radius: usize, iterations: u32) {
+    let (w, h) = (current.width, current.height);
+    let n = w * h;
+    // 勾配は現在フレーム側で一度だけ計算し、構造テンソルを窓で集計する
+    let mut ix = vec![0.0; n];
+    let mut iy = vec![0.0; n];
+    for y in 0..h as isize {
+        for x in 0..w as isize {
+            let i = y as usize * w + x as usize;
+            ix[i] = (current.at(x + 1, y) - current.at(x - 1, y)) * 0.5;
+            iy[i] = (current.at(x, y + 1) - current.at(x, y - 1)) * 0.5;
+        }
+    }
+    let plane = |data: Vec<f32>| Plane {
+        width: w,
+        height: h,
+        data,
+    };
+    let gxx = plane(ix.iter().map(|g| g * g).collect()).window_sum(radius);
+    let gxy = plane(ix.iter().zip(&iy).map(|(a, b)| a * b).collect()).window_sum(radius);
+    let gyy = plane(iy.iter().map(|g| g * g).collect()).window_sum(radius);
+    // 窓の重みの合計 (固有値の閾値を画素あたりに揃えるため)
+    let area = ((2 * radius.div_ceil(2) + 1) as f32).powi(4);
+
+    for _ in 0..iterations {
+        let mut bx = vec![0.0; n];
+        let mut by = vec![0.0; n];
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                let (u, v) = field.vectors[i];
+                let it = previous.bilinear(x as f32 + u, y as f32 + v) - current.data[i];
+                bx[i] = ix[i] * it;
+                by[i] = iy[i] * it;
+            }
+        }
+        let bx = plane(bx).window_sum(radius);
+        let by = plane(by).window_sum(radius);
+        for i in 0..n {
+            let (a, b, d) = (gxx.data[i], gxy.data[i], gyy.data[i]);
+            // 構造テンソルの最小固有値が小さい (平坦・一方向のみの模様) 所は
+            // 動きが決まらないので、粗い階層の推定をそのまま残す
+            let min_eigen = 0.5 * (a + d - ((a - d) * (a - d) + 4.0 * b * b).sqrt());
+            if !(min_eigen.is_finite() && min_eigen > MIN_EIGEN * area) {
+                continue;
+            }
+            let det = a * d - b * b;
+            // 一回の更新は 1px までに抑え、線形近似が外れた所での発散を防ぐ
+            let du = (-(d * bx.data[i] - b * by.data[i]) / det).clamp(-MAX_STEP, MAX_STEP);
+            let dv = (-(a * by.data[i] - b * bx.data[i]) / det).clamp(-MAX_STEP, MAX_STEP);
+            if du.is_finite() && dv.is_finite() {
+                let (u, v) = field.vectors[i];
+                let limit = (w.max(h)) as f32;
+                field.vectors[i] = ((u + du).clamp(-limit, limit), (v + dv).clamp(-limit, limit));
+            }
+        }
+    }
+}
+
+/// Dense pyramidal Lucas-Kanade flow: the motion since `previous` per pixel of
+/// `current`, in pixels (`previous` is resized to `current` if needed).
+pub fn lucas_kanade(current: &ImageBuf, previous: &ImageBuf, p: &LucasKanade) -> Flow {
+    let (width, height) = (current.width, current.height);
+    let mut cur = vec![Plane::luma(current, width, height)];
+    let mut prev = vec![Plane::luma(previous, width, height)];
+    let levels = p.levels.clamp(1, MAX_LEVELS) as usize;
+    if width == 0 || height == 0 {
+        return Flow::zero(width, height);
+    }
+    while cur.len() < levels {
+        let last = &cur[cur.len() - 1];
+        if last.width.min(last.height) / 2 < MIN_LEVEL_SIZE {
+            break;
+        }
+        let next_cur = last.reduce();
+        let next_prev = prev[prev.len() - 1].reduce();
+        cur.push(next_cur);
+        prev.push(next_prev);
+    }
+
+    let radius = p.window_radius.clamp(1, MAX_WINDOW_RADIUS) as usize;
+    let iterations = p.iterations.clamp(1, MAX_ITERATIONS);
+    let coarsest = &cur[cur.len() - 1];
+    let mut field = Flow::zero(coarsest.width, coarsest.height);
+    for level in (0..cur.len()).rev() {
+        if field.width != cur[level].width || field.height != cur[level].height {
+            field = field.upsample(cur[level].width, cur[level].height);
+        }
+        refine(&cur[level], &prev[level], &mut field, radius, iterations);
+        field = field.median();
+    }
+    // 現在→前フレームの対応を、前フレームからの動きに反転する
+    for f in field.vectors.iter_mut() {
+        *f = (-f.0, -f.1);
+    }
+    field
+}
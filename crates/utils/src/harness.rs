@@ -0,0 +1,262 @@
+//! Host-independent driver for plugin render cores.
+//!
+//! Plugins opt in by implementing [`RenderCore`] for the per-pixel / per-frame
+//! logic that `do_render` uses, and adding `examples/harness.rs`:
+//!
+//! ```ignore
+//! fn main() {
+//!     utils::harness::main::<my_plugin::Core>();
+//! }
+//! ```
+//!
+//! `cargo xtask fuzz <plugin>` and `cargo xtask profile <plugin>` then run that
+//! example without After Effects.
+
+use after_effects::PixelF32;
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::image::ImageBuf;
+
+/// The part of a plugin's render that does not touch the AE host.
+pub trait RenderCore {
+    type Params: Debug;
+
+    /// Draws a parameter set. Values should stay inside the ranges declared in
+    /// `params_setup` (AE clamps them), but favour the extremes.
+    fn random_params(rng: &mut Rng) -> Self::Params;
+
+    /// Renders `input` into `output` (same size as `input`).
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf);
+}
+
+/// Small PCG32 generator so the harness needs no extra dependencies.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self(seed.wrapping_add(0x853c_49e6_748f_ea9b));
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.0;
+        self.0 = old
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Uniform in `0.0..1.0`.
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.unit() < p
+    }
+
+    /// Uniform integer in `min..=max`.
+    pub fn int(&mut self, min: i32, max: i32) -> i32 {
+        let span = (max as i64 - min as i64 + 1).max(1) as u64;
+        (min as i64 + (self.next_u32() as u64 % span) as i64) as i32
+    }
+
+    /// Slider-style value in `min..=max`; a quarter of the draws hit one of the bounds.
+    pub fn slider(&mut self, min: f32, max: f32) -> f32 {
+        match self.next_u32() % 8 {
+            0 => min,
+            1 => max,
+            _ => min + (max - min) * self.unit(),
+        }
+    }
+
+    /// Integer slider / popup value in `min..=max`, biased towards the bounds.
+    pub fn popup(&mut self, min: i32, max: i32) -> i32 {
+        match self.next_u32() % 8 {
+            0 => min,
+            1 => max,
+            _ => self.int(min, max),
+        }
+    }
+
+    fn pixel(&mut self, kind: Fill) -> PixelF32 {
+        let v = |rng: &mut Rng| match kind {
+            Fill::Random => rng.unit(),
+            Fill::Zero => 0.0,
+            Fill::NaN => f32::NAN,
+            Fill::Infinite => {
+                if rng.chance(0.5) {
+                    f32::INFINITY
+                } else {
+                    f32::NEG_INFINITY
+                }
+            }
+            Fill::OutOfRange => (rng.unit() - 0.5) * 2.0e6,
+            Fill::Mixed => match rng.next_u32() % 5 {
+                0 => f32::NAN,
+                1 => f32::INFINITY,
+                2 => -1.0,
+                3 => 1.0e30,
+                _ => rng.unit(),
+            },
+        };
+        PixelF32 {
+            red: v(self),
+            green: v(self),
+            blue: v(self),
+            alpha: v(self),
+        }
+    }
+}
+
+/// Input contents used by the fuzzer; 32bpc layers can carry any of these.
+#[derive(Clone, Copy, Debug)]
+enum Fill {
+    Random,
+    Zero,
+    NaN,
+    Infinite,
+    OutOfRange,
+    Mixed,
+}
+
+const FILLS: [Fill; 6] = [
+    Fill::Random,
+    Fill::Zero,
+    Fill::NaN,
+    Fill::Infinite,
+    Fill::OutOfRange,
+    Fill::Mixed,
+];
+
+fn random_size(rng: &mut Rng) -> (usize, usize) {
+    match rng.next_u32() % 6 {
+        0 => (0, 0),
+        1 => (1, 1),
+        2 => (1, rng.int(2, 64) as usize),
+        3 => (rng.int(2, 64) as usize, 1),
+        4 => (0, rng.int(1, 8) as usize),
+        _ => (rng.int(2, 96) as usize, rng.int(2, 96) as usize),
+    }
+}
+
+/// Renders `iterations` random cases, reporting the first panic (including overflow
+/// panics from debug builds). Returns `false` when a case failed.
+pub fn fuzz<C: RenderCore>(iterations: u32, seed: u64) -> bool {
+    // Keep the default hook quiet; failures are reported with their inputs below.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut rng = Rng::new(seed);
+    let mut failure = None;
+    for case in 0..iterations {
+        let params = C::random_params(&mut rng);
+        let (w, h) = random_size(&mut rng);
+        let fill = FILLS[rng.next_u32() as usize % FILLS.len()];
+        let frame = match rng.next_u32() % 4 {
+            0 => 0,
+            1 => u32::MAX,
+            _ => rng.next_u32() % 10_000,
+        };
+
+        let mut input = ImageBuf::new(w, h);
+        for px in &mut input.data {
+            *px = rng.pixel(fill);
+        }
+        let mut output = ImageBuf::new(w, h);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            C::render(&params, frame, &input, &mut output)
+        }));
+        if let Err(payload) = result {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "<non-string panic>".to_string());
+            failure = Some(format!(
+                "case {case} (seed {seed}): panic: {msg}\n  size: {w}x{h}, input: {fill:?}, frame: {frame}\n  params: {params:#?}"
+            ));
+            break;
+        }
+    }
+
+    panic::set_hook(hook);
+    match failure {
+        Some(report) => {
+            eprintln!("{report}");
+            false
+        }
+        None => {
+            println!("{iterations} cases passed (seed {seed})");
+            true
+        }
+    }
+}
+
+/// Renders `frames` frames of a `width`×`height` random image with one fixed
+/// parameter set, printing per-frame timings. Used as the workload for
+/// `cargo xtask profile`.
+pub fn profile<C: RenderCore>(frames: u32, width: usize, height: usize, seed: u64) {
+    let mut rng = Rng::new(seed);
+    let params = C::random_params(&mut rng);
+    let mut input = ImageBuf::new(width, height);
+    for px in &mut input.data {
+        *px = rng.pixel(Fill::Random);
+    }
+    let mut output = ImageBuf::new(width, height);
+    println!("profiling {frames} frame(s) at {width}x{height}\n  params: {params:?}");
+
+    let mut total = std::time::Duration::ZERO;
+    for frame in 0..frames {
+        let start = std::time::Instant::now();
+        C::render(&params, frame, &input, &mut output);
+        total += start.elapsed();
+    }
+    let frames = frames.max(1);
+    println!(
+        "total {:.1} ms, {:.2} ms/frame",
+        total.as_secs_f64() * 1e3,
+        total.as_secs_f64() * 1e3 / frames as f64
+    );
+}
+
+/// Entry point for `examples/harness.rs`.
+///
+/// - `harness fuzz [--iterations N] [--seed S]`
+/// - `harness profile [--frames N] [--width W] [--height H] [--seed S]`
+pub fn main<C: RenderCore>() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+
+    let ok = match args.first().map(String::as_str) {
+        Some("fuzz") => fuzz::<C>(
+            flag("--iterations").unwrap_or(2000) as u32,
+            flag("--seed").unwrap_or(0x5eed),
+        ),
+        Some("profile") => {
+            profile::<C>(
+                flag("--frames").unwrap_or(10) as u32,
+                flag("--width").unwrap_or(1920) as usize,
+                flag("--height").unwrap_or(1080) as usize,
+                flag("--seed").unwrap_or(0x5eed),
+            );
+            true
+        }
+        _ => {
+            eprintln!(
+                "usage: harness fuzz [--iterations N] [--seed S]\n       harness profile [--frames N] [--width W] [--height H] [--seed S]"
+            );
+            false
+        }
+    };
+    std::process::exit(if ok { 0 } else { 1 });
+}
@@ -0,0 +1,69 @@
+//! Per-instance cache of recently rendered states for the effects that feed
+//! each frame into the next (feedback, simulations, phosphor decay).
+//!
+//! The cache lives in sequence data and is written during render, so those
+//! effects set `OutFlags2::MutableRenderSequenceDataSlower`: under Multi-Frame
+//! Rendering AE otherwise hands every render thread a copy and drops the writes.
+
+use std::collections::VecDeque;
+
+use crate::sampling::Downsample;
+
+/// States kept per sequence: the previous frame and the current one, so
+/// re-rendering a frame still finds its predecessor.
+const HISTORY_FRAMES: usize = 2;
+
+/// Render geometry a state is only valid for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HistoryKey {
+    pub width: usize,
+    pub height: usize,
+    pub downsample: Downsample,
+    pub time_step: i32,
+}
+
+/// Recently rendered states, newest last.
+pub struct History<T> {
+    key: Option<HistoryKey>,
+    frames: VecDeque<(i32, T)>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            frames: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> History<T> {
+    pub fn clear(&mut self) {
+        self.key = None;
+        self.frames.clear();
+    }
+
+    /// State of the frame one `time_step` before `time`, if it was rendered with `key`.
+    pub fn previous(&self, key: HistoryKey, time: i32) -> Option<&T> {
+        if self.key != Some(key) {
+            return None;
+        }
+        let previous = time.checked_sub(key.time_step)?;
+        self.frames
+            .iter()
+            .find(|(t, _)| *t == previous)
+            .map(|(_, state)| state)
+    }
+
+    pub fn store(&mut self, key: HistoryKey, time: i32, state: T) {
+        if self.key != Some(key) {
+            self.clear();
+            self.key = Some(key);
+        }
+        self.frames.retain(|(t, _)| *t != time);
+        self.frames.push_back((time, state));
+        while self.frames.len() > HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+    }
+}
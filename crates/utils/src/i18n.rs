@@ -0,0 +1,72 @@
+//! Minimal localization layer for user-visible plugin strings.
+//!
+//! Each plugin's `src/strings.rs` is generated by `cargo xtask localize` from
+//! `locales/<lang>.toml` and exposes `tr("English text")`, which looks the text up
+//! for [`current`] and falls back to English when no translation exists. Names
+//! built with `format!` are extracted as their template (`"Light {} Type"`);
+//! translate them with [`fill`].
+
+use after_effects as ae;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    En,
+    Ja,
+}
+
+impl Language {
+    /// Parses a locale tag such as `ja`, `ja_JP.UTF-8` or `en-US`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let lang = tag
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match lang.as_str() {
+            "en" => Some(Self::En),
+            "ja" => Some(Self::Ja),
+            _ => None,
+        }
+    }
+}
+
+/// UI language, resolved once per process: `AOD_AE_LANG` as an override, then
+/// the host's language (`PF_AppGetLanguage`), then the usual locale variables
+/// when running outside AE, defaulting to English.
+pub fn current() -> Language {
+    static LANGUAGE: OnceLock<Language> = OnceLock::new();
+    *LANGUAGE.get_or_init(|| {
+        env_language(&["AOD_AE_LANG"])
+            .or_else(host_language)
+            .or_else(|| env_language(&["LC_ALL", "LC_MESSAGES", "LANG"]))
+            .unwrap_or(Language::En)
+    })
+}
+
+fn env_language(keys: &[&str]) -> Option<Language> {
+    keys.iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find_map(|tag| Language::from_tag(&tag))
+}
+
+/// Language of the AE UI (e.g. `ja_JP`); `None` outside a host.
+fn host_language() -> Option<Language> {
+    let tag = ae::pf::suites::App::new().ok()?.language().ok()?;
+    Language::from_tag(&tag)
+}
+
+/// Substitutes `value` for the first `{}` of a translated template, for names
+/// built at runtime such as `fill(tr("Light {} Type"), n)`.
+pub fn fill(template: &str, value: impl std::fmt::Display) -> String {
+    template.replacen("{}", &value.to_string(), 1)
+}
+
+/// Looks `text` up in a generated `(english, translated)` table.
+pub fn lookup(table: &[(&'static str, &'static str)], text: &'static str) -> &'static str {
+    table
+        .iter()
+        .find(|(en, _)| *en == text)
+        .map(|(_, translated)| *translated)
+        .unwrap_or(text)
+}
@@ -0,0 +1,142 @@
+use after_effects as ae;
+
+use ae::PixelF32;
+use ae::pf::Layer;
+
+use crate::ToPixel;
+
+pub const TRANSPARENT: PixelF32 = PixelF32 {
+    alpha: 0.0,
+    red: 0.0,
+    green: 0.0,
+    blue: 0.0,
+};
+
+/// Reads a pixel of any bit depth as 32bpc float.
+pub fn read_pixel_f32(
+    layer: &Layer,
+    world_type: ae::aegp::WorldType,
+    x: usize,
+    y: usize,
+) -> PixelF32 {
+    match world_type {
+        ae::aegp::WorldType::U8 => layer.as_pixel8(x, y).to_pixel32(),
+        ae::aegp::WorldType::U15 => layer.as_pixel16(x, y).to_pixel32(),
+        ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => *layer.as_pixel32(x, y),
+    }
+}
+
+/// Host-independent RGBA float image, used by plugins that need whole-frame access
+/// (neighbourhood filters, GPU upload) instead of per-pixel `iterate`.
+#[derive(Clone, Debug)]
+pub struct ImageBuf {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<PixelF32>,
+}
+
+impl ImageBuf {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![TRANSPARENT; width * height],
+        }
+    }
+
+    /// Copies the layer into a float buffer (8/16bpc are normalized to 0..1).
+    pub fn from_layer(layer: &Layer) -> Self {
+        let width = layer.width();
+        let height = layer.height();
+        let world_type = layer.world_type();
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(read_pixel_f32(layer, world_type, x, y));
+            }
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Writes the buffer into `layer`, converting to its bit depth.
+    /// Output pixels outside the buffer become transparent.
+    pub fn write_to_layer(&self, layer: &mut Layer) -> Result<(), ae::Error> {
+        let world_type = layer.world_type();
+        let progress_final = layer.height() as i32;
+        layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = self.get(x as isize, y as isize);
+            match world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => dst.set_from_f32(px),
+            }
+            Ok(())
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    #[inline]
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    /// Pixel at (x, y), or transparent outside the image.
+    #[inline]
+    pub fn get(&self, x: isize, y: isize) -> PixelF32 {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return TRANSPARENT;
+        }
+        self.data[self.index(x as usize, y as usize)]
+    }
+
+    /// Pixel at (x, y) with coordinates clamped to the edge.
+    #[inline]
+    pub fn get_clamped(&self, x: isize, y: isize) -> PixelF32 {
+        if self.is_empty() {
+            return TRANSPARENT;
+        }
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.data[self.index(x, y)]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, px: PixelF32) {
+        let i = self.index(x, y);
+        self.data[i] = px;
+    }
+
+    /// Interleaved RGBA floats (the layout used for GPU storage buffers).
+    pub fn to_rgba(&self) -> Vec<f32> {
+        self.data
+            .iter()
+            .flat_map(|p| [p.red, p.green, p.blue, p.alpha])
+            .collect()
+    }
+
+    /// Builds an image from interleaved RGBA floats.
+    pub fn from_rgba(width: usize, height: usize, rgba: &[f32]) -> Self {
+        let data = rgba
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|c| PixelF32 {
+                red: c[0],
+                green: c[1],
+                blue: c[2],
+                alpha: c[3],
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+}
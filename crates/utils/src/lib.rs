@@ -2,6 +2,48 @@ use ae::sys::{PF_Pixel, PF_PixelFloat};
 use ae::{Pixel8, Pixel16, PixelF32};
 use after_effects as ae;
 
+pub mod blur;
+pub mod color;
+pub mod complex;
+pub mod density;
+pub mod dither;
+pub mod flow;
+pub mod harness;
+pub mod history;
+pub mod i18n;
+pub mod image;
+pub mod mask;
+pub mod normal;
+pub mod poisson;
+pub mod projection;
+pub mod regions;
+pub mod sampling;
+pub mod tensor;
+pub mod ui;
+
+/// Floating-point types accepted by [`finite_or`].
+pub trait Float: Copy {
+    fn is_finite(self) -> bool;
+}
+
+impl Float for f32 {
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+}
+
+impl Float for f64 {
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+
+/// `v`, or `fallback` when `v` is NaN or infinite (e.g. from a degenerate
+/// parameter combination).
+pub fn finite_or<T: Float>(v: T, fallback: T) -> T {
+    if v.is_finite() { v } else { fallback }
+}
+
 pub trait ToPixel {
     fn to_pixel32(&self) -> PixelF32;
     fn to_pixel16(&self) -> Pixel16;
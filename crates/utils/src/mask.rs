@@ -0,0 +1,133 @@
+//! Binary pixel masks: Euclidean distance transform and Zhang–Suen thinning.
+
+/// 1D squared Euclidean distance transform (Felzenszwalb & Huttenlocher);
+/// f64 keeps `q²` exact on wide layers.
+fn edt_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let n = f.len();
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    for q in 1..n {
+        if !f[q].is_finite() {
+            continue;
+        }
+        if !f[v[k]].is_finite() {
+            v[k] = q;
+            continue;
+        }
+        loop {
+            let p = v[k];
+            let s = ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2 * q - 2 * p) as f64;
+            if s <= z[k] && k > 0 {
+                k -= 1;
+            } else {
+                k += 1;
+                v[k] = q;
+                z[k] = s;
+                z[k + 1] = f64::INFINITY;
+                break;
+            }
+        }
+    }
+    k = 0;
+    for (q, out) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let p = v[k];
+        let dq = q as f64 - p as f64;
+        *out = if f[p].is_finite() {
+            dq * dq + f[p]
+        } else {
+            f64::INFINITY
+        };
+    }
+}
+
+/// Squared Euclidean distance of every pixel to the nearest marked pixel
+/// (infinite when nothing is marked).
+pub fn distance_field(marks: &[bool], width: usize, height: usize) -> Vec<f64> {
+    let mut dist: Vec<f64> = marks
+        .iter()
+        .map(|&m| if m { 0.0 } else { f64::INFINITY })
+        .collect();
+    let n = width.max(height);
+    let (mut f, mut d) = (vec![0.0; n], vec![0.0; n]);
+    let (mut v, mut z) = (vec![0usize; n], vec![0.0f64; n + 1]);
+    for x in 0..width {
+        for y in 0..height {
+            f[y] = dist[y * width + x];
+        }
+        edt_1d(&f[..height], &mut d[..height], &mut v, &mut z);
+        for y in 0..height {
+            dist[y * width + x] = d[y];
+        }
+    }
+    for row in dist.chunks_mut(width) {
+        f[..width].copy_from_slice(row);
+        edt_1d(&f[..width], &mut d[..width], &mut v, &mut z);
+        row.copy_from_slice(&d[..width]);
+    }
+    dist
+}
+
+/// Zhang–Suen thinning: peels `mask` down to a one-pixel wide, 8-connected
+/// skeleton that keeps the topology of the shape. Pixels outside the layer
+/// count as background.
+pub fn thin(mask: &[bool], width: usize, height: usize) -> Vec<bool> {
+    let mut mask = mask.to_vec();
+    if width == 0 || height == 0 {
+        return mask;
+    }
+    let at = |m: &[bool], x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && m[y as usize * width + x as usize]
+    };
+    // 毎パスで前景画素だけを走査する
+    let mut active: Vec<usize> = (0..mask.len()).filter(|&i| mask[i]).collect();
+    let mut removed = Vec::new();
+    loop {
+        let mut changed = false;
+        for step in 0..2 {
+            removed.clear();
+            for &i in &active {
+                let (x, y) = ((i % width) as isize, (i / width) as isize);
+                // P2..P9: 上から時計回り
+                let n = [
+                    at(&mask, x, y - 1),
+                    at(&mask, x + 1, y - 1),
+                    at(&mask, x + 1, y),
+                    at(&mask, x + 1, y + 1),
+                    at(&mask, x, y + 1),
+                    at(&mask, x - 1, y + 1),
+                    at(&mask, x - 1, y),
+                    at(&mask, x - 1, y - 1),
+                ];
+                let neighbours = n.iter().filter(|&&v| v).count();
+                let transitions = (0..8).filter(|&k| !n[k] && n[(k + 1) % 8]).count();
+                let (p2, p4, p6, p8) = (n[0], n[2], n[4], n[6]);
+                // 第 1 パスは南東側、第 2 パスは北西側の境界を削る
+                let corner = if step == 0 {
+                    !(p4 && p6 && (p2 || p8))
+                } else {
+                    !(p2 && p8 && (p4 || p6))
+                };
+                if (2..=6).contains(&neighbours) && transitions == 1 && corner {
+                    removed.push(i);
+                }
+            }
+            for &i in &removed {
+                mask[i] = false;
+            }
+            changed |= !removed.is_empty();
+            active.retain(|&i| mask[i]);
+        }
+        if !changed {
+            return mask;
+        }
+    }
+}
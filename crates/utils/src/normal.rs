@@ -0,0 +1,32 @@
+//! Tangent-space normal map pixels shared by the shading plugins.
+
+use after_effects::PixelF32;
+
+use crate::finite_or;
+
+/// Green channel convention of a normal map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalFormat {
+    /// Green points up the layer.
+    OpenGl,
+    /// Green points down the layer.
+    DirectX,
+}
+
+/// Unit normal of an encoded pixel with +Y up the layer, or `None` for
+/// transparent or degenerate pixels; normals facing away are folded onto the
+/// surface.
+pub fn decode(px: PixelF32, format: NormalFormat) -> Option<[f32; 3]> {
+    if finite_or(px.alpha, 0.0) <= 0.0 {
+        return None;
+    }
+    let x = finite_or(px.red, 0.5) * 2.0 - 1.0;
+    let y = finite_or(px.green, 0.5) * 2.0 - 1.0;
+    let z = (finite_or(px.blue, 1.0) * 2.0 - 1.0).max(0.0);
+    let y = match format {
+        NormalFormat::OpenGl => y,
+        NormalFormat::DirectX => -y,
+    };
+    let l = (x * x + y * y + z * z).sqrt();
+    (l > 1e-6 && l.is_finite()).then(|| [x / l, y / l, z / l])
+}
@@ -0,0 +1,57 @@
+//! Neighbour grid for Poisson disk dart throwing: candidates are accepted when
+//! no earlier point lies within their (possibly local) radius.
+
+/// Accepted points bucketed into square cells, so a candidate is only tested
+/// against the points of the cells its radius reaches.
+pub struct PoissonGrid {
+    cell: f32,
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<(f32, f32)>>,
+}
+
+impl PoissonGrid {
+    /// Grid over a `width`×`height` area; `cell` should be the smallest radius
+    /// used, which keeps every cell to a handful of points.
+    pub fn new(width: f32, height: f32, cell: f32) -> Self {
+        let (gw, gh) = (
+            (width / cell).ceil() as usize + 1,
+            (height / cell).ceil() as usize + 1,
+        );
+        Self {
+            cell,
+            width: gw,
+            height: gh,
+            cells: vec![Vec::new(); gw * gh],
+        }
+    }
+
+    fn cell_of(&self, x: f32, y: f32) -> (usize, usize) {
+        (
+            ((x / self.cell) as usize).min(self.width - 1),
+            ((y / self.cell) as usize).min(self.height - 1),
+        )
+    }
+
+    /// Whether no point lies within `r` of (`x`, `y`).
+    pub fn is_free(&self, x: f32, y: f32, r: f32) -> bool {
+        let (cx, cy) = self.cell_of(x, y);
+        let reach = (r / self.cell).ceil() as isize;
+        let (gw, gh) = (self.width as isize, self.height as isize);
+        for gy in (cy as isize - reach).max(0)..=(cy as isize + reach).min(gh - 1) {
+            for gx in (cx as isize - reach).max(0)..=(cx as isize + reach).min(gw - 1) {
+                for &(qx, qy) in &self.cells[gy as usize * self.width + gx as usize] {
+                    if (qx - x).powi(2) + (qy - y).powi(2) < r * r {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn insert(&mut self, x: f32, y: f32) {
+        let (cx, cy) = self.cell_of(x, y);
+        self.cells[cy * self.width + cx].push((x, y));
+    }
+}
@@ -0,0 +1,174 @@
+//! Camera projections between image planes and view rays, for the
+//! projection-converting and lens plugins.
+//!
+//! Rays are unit vectors in camera space with +x right, +y down and +z
+//! forward. Image-plane points are in pixels relative to the frame center.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Radial mapping of a fisheye lens from the angle off-axis to the image radius.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FisheyeModel {
+    /// `r = f θ` (equal angles, most common).
+    Equidistant,
+    /// `r = 2f sin(θ/2)` (equal areas).
+    Equisolid,
+    /// `r = 2f tan(θ/2)` (conformal).
+    Stereographic,
+    /// `r = f sin θ` (limited to a hemisphere).
+    Orthographic,
+}
+
+impl FisheyeModel {
+    /// Widest field of view (radians) the model can represent.
+    pub fn max_fov(self) -> f32 {
+        match self {
+            FisheyeModel::Equidistant | FisheyeModel::Equisolid => TAU,
+            // 立体射影は θ = π で無限遠に飛ぶので手前で止める
+            FisheyeModel::Stereographic => TAU * 0.95,
+            FisheyeModel::Orthographic => PI,
+        }
+    }
+
+    /// Image radius of a ray `theta` radians off-axis, for unit focal length.
+    pub fn radius(self, theta: f32) -> Option<f32> {
+        match self {
+            FisheyeModel::Equidistant => Some(theta),
+            FisheyeModel::Equisolid => Some(2.0 * (theta * 0.5).sin()),
+            FisheyeModel::Stereographic => (theta < PI).then(|| 2.0 * (theta * 0.5).tan()),
+            FisheyeModel::Orthographic => (theta <= FRAC_PI_2).then(|| theta.sin()),
+        }
+    }
+
+    /// Angle off-axis of image radius `r` (unit focal length).
+    pub fn theta(self, r: f32) -> Option<f32> {
+        match self {
+            FisheyeModel::Equidistant => (r <= PI).then_some(r),
+            FisheyeModel::Equisolid => (r <= 2.0).then(|| 2.0 * (r * 0.5).asin()),
+            FisheyeModel::Stereographic => Some(2.0 * (r * 0.5).atan()),
+            FisheyeModel::Orthographic => (r <= 1.0).then(|| r.asin()),
+        }
+    }
+}
+
+/// How a frame maps view directions onto the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Pinhole camera with focal length `f` in pixels.
+    Rectilinear { f: f32 },
+    /// Fisheye with focal length `f` in pixels.
+    Fisheye { model: FisheyeModel, f: f32 },
+    /// Full sphere: longitude across the width, latitude across the height.
+    Equirectangular { width: f32, height: f32 },
+}
+
+impl Projection {
+    /// Rectilinear projection whose horizontal field of view `fov` (radians)
+    /// spans `width` pixels.
+    pub fn rectilinear(fov: f32, width: f32) -> Self {
+        let half = (fov * 0.5).clamp(1e-3, FRAC_PI_2 - 1e-3);
+        Projection::Rectilinear {
+            f: width * 0.5 / half.tan(),
+        }
+    }
+
+    /// Fisheye projection whose horizontal field of view `fov` (radians) spans
+    /// `width` pixels.
+    pub fn fisheye(model: FisheyeModel, fov: f32, width: f32) -> Self {
+        let half = (fov * 0.5).clamp(1e-3, model.max_fov() * 0.5);
+        let r = model.radius(half).unwrap_or(1.0).max(1e-6);
+        Projection::Fisheye {
+            model,
+            f: width * 0.5 / r,
+        }
+    }
+
+    /// View ray through image-plane point `(x, y)`, or `None` outside the
+    /// projection's domain.
+    pub fn ray(&self, (x, y): (f32, f32)) -> Option<[f32; 3]> {
+        match *self {
+            Projection::Rectilinear { f } => Some(normalize([x, y, f])),
+            Projection::Fisheye { model, f } => {
+                let r = x.hypot(y);
+                let theta = model.theta(r / f)?;
+                let (sin, cos) = theta.sin_cos();
+                let (dx, dy) = if r > 0.0 { (x / r, y / r) } else { (0.0, 0.0) };
+                Some([sin * dx, sin * dy, cos])
+            }
+            Projection::Equirectangular { width, height } => {
+                let lon = x / width * TAU;
+                let lat = y / height * PI;
+                if lat.abs() > FRAC_PI_2 {
+                    return None;
+                }
+                let (sin_lon, cos_lon) = lon.sin_cos();
+                let (sin_lat, cos_lat) = lat.sin_cos();
+                Some([cos_lat * sin_lon, sin_lat, cos_lat * cos_lon])
+            }
+        }
+    }
+
+    /// Image-plane point of view ray `d`, or `None` where the projection cannot
+    /// show it (behind a pinhole camera, beyond a fisheye's range).
+    pub fn project(&self, d: [f32; 3]) -> Option<(f32, f32)> {
+        match *self {
+            Projection::Rectilinear { f } => {
+                (d[2] > 1e-6).then(|| (f * d[0] / d[2], f * d[1] / d[2]))
+            }
+            Projection::Fisheye { model, f } => {
+                let theta = d[2].clamp(-1.0, 1.0).acos();
+                let r = f * model.radius(theta)?;
+                let s = d[0].hypot(d[1]);
+                if s > 0.0 {
+                    Some((r * d[0] / s, r * d[1] / s))
+                } else {
+                    (theta < FRAC_PI_2).then_some((0.0, 0.0))
+                }
+            }
+            Projection::Equirectangular { width, height } => {
+                let lon = d[0].atan2(d[2]);
+                let lat = d[1].clamp(-1.0, 1.0).asin();
+                Some((lon / TAU * width, lat / PI * height))
+            }
+        }
+    }
+}
+
+/// Camera orientation as a rotation matrix (rows), built from yaw (turning
+/// right), pitch (tilting up) and roll (clockwise), applied roll first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orientation([[f32; 3]; 3]);
+
+impl Orientation {
+    pub const IDENTITY: Self = Self([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    /// Angles in radians.
+    pub fn from_yaw_pitch_roll(yaw: f32, pitch: f32, roll: f32) -> Self {
+        let (sy, cy) = yaw.sin_cos();
+        let (sp, cp) = pitch.sin_cos();
+        let (sr, cr) = roll.sin_cos();
+        let yaw = [[cy, 0.0, sy], [0.0, 1.0, 0.0], [-sy, 0.0, cy]];
+        let pitch = [[1.0, 0.0, 0.0], [0.0, cp, -sp], [0.0, sp, cp]];
+        let roll = [[cr, -sr, 0.0], [sr, cr, 0.0], [0.0, 0.0, 1.0]];
+        Self(mul(mul(yaw, pitch), roll))
+    }
+
+    /// Camera-space ray to world space.
+    pub fn apply(&self, d: [f32; 3]) -> [f32; 3] {
+        let m = &self.0;
+        [0, 1, 2].map(|i| m[i][0] * d[0] + m[i][1] * d[1] + m[i][2] * d[2])
+    }
+}
+
+fn mul(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    [0, 1, 2].map(|i| [0, 1, 2].map(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len > 0.0 {
+        v.map(|c| c / len)
+    } else {
+        [0.0, 0.0, 1.0]
+    }
+}
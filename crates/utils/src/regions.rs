@@ -0,0 +1,103 @@
+//! Label maps shared by the segmentation plugins: every pixel carries a region
+//! id `0, 1, 2, …`, rendered as mean colors, unique id colors or boundaries.
+
+use after_effects::PixelF32;
+
+use crate::image::ImageBuf;
+use crate::sampling;
+
+/// How a label map is turned into pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionOutput {
+    /// Each region filled with its mean color.
+    MeanColor,
+    /// Each region filled with its [`id_color`].
+    RegionId,
+    /// The layer with one-pixel region boundaries drawn over it.
+    Boundaries,
+}
+
+/// Unique, 8-bit exact color of region `id`: `id + 1` as a 24-bit RGB value,
+/// so no region is pure black. Tools that split regions by color see every
+/// region as its own.
+pub fn id_color(id: u32) -> [f32; 3] {
+    let v = id.wrapping_add(1);
+    [v >> 16, v >> 8, v].map(|c| (c & 0xff) as f32 / 255.0)
+}
+
+/// Whether the pixel at `i` has a right or lower neighbour in another region.
+fn is_boundary(labels: &[u32], width: usize, height: usize, i: usize) -> bool {
+    let (x, y) = (i % width, i / width);
+    (x + 1 < width && labels[i + 1] != labels[i])
+        || (y + 1 < height && labels[i + width] != labels[i])
+}
+
+/// Renders `labels` (one per pixel of `input`) as `output`; `boundary` is the
+/// encoded boundary color.
+pub fn render_labels(
+    input: &ImageBuf,
+    labels: &[u32],
+    output: RegionOutput,
+    boundary: [f32; 3],
+) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() || labels.len() != input.data.len() {
+        return out;
+    }
+    let finite = |v: f32| if v.is_finite() { v } else { 0.0 };
+
+    match output {
+        RegionOutput::MeanColor => {
+            let regions = labels.iter().max().map_or(0, |&l| l as usize + 1);
+            let premul = sampling::premultiply(input);
+            let mut sums = vec![[0.0f64; 5]; regions];
+            for (i, &l) in labels.iter().enumerate() {
+                let px = premul.data[i];
+                let s = &mut sums[l as usize];
+                s[0] += finite(px.red) as f64;
+                s[1] += finite(px.green) as f64;
+                s[2] += finite(px.blue) as f64;
+                s[3] += finite(px.alpha) as f64;
+                s[4] += 1.0;
+            }
+            for (i, &l) in labels.iter().enumerate() {
+                let s = sums[l as usize];
+                let px = PixelF32 {
+                    red: (s[0] / s[4]) as f32,
+                    green: (s[1] / s[4]) as f32,
+                    blue: (s[2] / s[4]) as f32,
+                    alpha: (s[3] / s[4]) as f32,
+                };
+                out.data[i] = sampling::unpremultiply(px);
+            }
+        }
+        RegionOutput::RegionId => {
+            for (i, &l) in labels.iter().enumerate() {
+                let [red, green, blue] = id_color(l);
+                out.data[i] = PixelF32 {
+                    red,
+                    green,
+                    blue,
+                    alpha: 1.0,
+                };
+            }
+        }
+        RegionOutput::Boundaries => {
+            let color = boundary.map(finite);
+            for i in 0..labels.len() {
+                out.data[i] = if is_boundary(labels, width, height, i) {
+                    PixelF32 {
+                        red: color[0],
+                        green: color[1],
+                        blue: color[2],
+                        alpha: 1.0,
+                    }
+                } else {
+                    input.data[i]
+                };
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,122 @@
+//! Smoothed structure tensor of an image, for orientation-aware filters.
+
+use crate::color::luminance;
+use crate::image::ImageBuf;
+
+/// Symmetric 2x2 tensor `[[e, f], [f, g]]` built from image gradients.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StructureTensor {
+    pub e: f32,
+    pub f: f32,
+    pub g: f32,
+}
+
+/// Local orientation derived from a [`StructureTensor`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Orientation {
+    /// Unit vector along the edge (minor eigenvector), `(1, 0)` in flat areas.
+    pub tangent: (f32, f32),
+    /// `0` (isotropic) ..= `1` (strongly oriented).
+    pub anisotropy: f32,
+}
+
+impl StructureTensor {
+    /// Eigenvalues `(major, minor)`.
+    pub fn eigenvalues(&self) -> (f32, f32) {
+        let root = ((self.e - self.g).powi(2) + 4.0 * self.f * self.f).sqrt();
+        let mean = (self.e + self.g) * 0.5;
+        (mean + root * 0.5, mean - root * 0.5)
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        let (l1, l2) = self.eigenvalues();
+        let (tx, ty) = (l1 - self.e, -self.f);
+        let len = tx.hypot(ty);
+        let tangent = if len > 1e-12 {
+            (tx / len, ty / len)
+        } else {
+            (1.0, 0.0)
+        };
+        let sum = l1 + l2;
+        let anisotropy = if sum > 1e-12 { (l1 - l2) / sum } else { 0.0 };
+        Orientation {
+            tangent,
+            anisotropy,
+        }
+    }
+}
+
+/// Per-pixel structure tensor of the image luminance (Sobel gradients), smoothed with
+/// a Gaussian of `sigma` pixels. Row-major, `width * height` entries.
+pub fn structure_tensor(image: &ImageBuf, sigma: f32) -> Vec<StructureTensor> {
+    let (w, h) = (image.width, image.height);
+    let lum: Vec<f32> = image.data.iter().map(|p| luminance(*p)).collect();
+    let at = |x: isize, y: isize| {
+        let x = x.clamp(0, w as isize - 1) as usize;
+        let y = y.clamp(0, h as isize - 1) as usize;
+        lum[y * w + x]
+    };
+
+    let mut tensors = Vec::with_capacity(w * h);
+    for y in 0..h as isize {
+        for x in 0..w as isize {
+            let gx = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x - 1, y)
+                - at(x - 1, y + 1))
+                * 0.25;
+            let gy = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x, y - 1)
+                - at(x + 1, y - 1))
+                * 0.25;
+            tensors.push(StructureTensor {
+                e: gx * gx,
+                f: gx * gy,
+                g: gy * gy,
+            });
+        }
+    }
+    gaussian_smooth(&mut tensors, w, h, sigma);
+    tensors
+}
+
+/// Separable Gaussian blur of the tensor field (edges clamped).
+fn gaussian_smooth(field: &mut [StructureTensor], w: usize, h: usize, sigma: f32) {
+    if sigma <= 0.0 || !sigma.is_finite() || field.is_empty() {
+        return;
+    }
+    let radius = (sigma * 3.0).ceil() as isize;
+    let kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let norm: f32 = kernel.iter().sum();
+
+    let mut tmp = field.to_vec();
+    let pass = |src: &[StructureTensor], dst: &mut [StructureTensor], horizontal: bool| {
+        for y in 0..h {
+            for x in 0..w {
+                let mut acc = StructureTensor::default();
+                for (k, weight) in kernel.iter().enumerate() {
+                    let d = k as isize - radius;
+                    let (sx, sy) = if horizontal {
+                        ((x as isize + d).clamp(0, w as isize - 1) as usize, y)
+                    } else {
+                        (x, (y as isize + d).clamp(0, h as isize - 1) as usize)
+                    };
+                    let t = src[sy * w + sx];
+                    acc.e += t.e * weight;
+                    acc.f += t.f * weight;
+                    acc.g += t.g * weight;
+                }
+                dst[y * w + x] = StructureTensor {
+                    e: acc.e / norm,
+                    f: acc.f / norm,
+                    g: acc.g / norm,
+                };
+            }
+        }
+    };
+    pass(field, &mut tmp, true);
+    pass(&tmp, field, false);
+}
@@ -0,0 +1,57 @@
+//! Dynamic parameter UI shared by the effects that show controls per mode.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::OnceLock;
+
+use ae::aegp::suites::{DynamicStream, PFInterface, Stream, Utility};
+use ae::aegp::{DynamicStreamFlags, PluginId};
+use ae::{Error, InData, ParamUIFlags, Parameters};
+use after_effects as ae;
+
+/// AEGP id the effect registers once to reach its own parameter streams.
+fn plugin_id() -> Result<PluginId, Error> {
+    static ID: OnceLock<PluginId> = OnceLock::new();
+    if let Some(id) = ID.get() {
+        return Ok(*id);
+    }
+    let id = Utility::new()?.register_with_aegp("AOD")?;
+    Ok(*ID.get_or_init(|| id))
+}
+
+/// Shows or hides parameter `id`, touching the host only when its state changes.
+///
+/// After Effects ignores `ParamUIFlags::INVISIBLE`, so there the parameter stream
+/// gets the AEGP `DynamicStreamFlags::Hidden` flag; Premiere has no AEGP and
+/// keeps using the UI flag.
+pub fn set_param_visible<P>(
+    in_data: &InData,
+    params: &mut Parameters<P>,
+    id: P,
+    visible: bool,
+) -> Result<(), Error>
+where
+    P: Copy + Eq + Hash + Debug,
+{
+    if in_data.is_premiere() {
+        let mut param = params.get_mut(id)?;
+        if param.ui_flags().contains(ParamUIFlags::INVISIBLE) == visible {
+            param.set_ui_flag(ParamUIFlags::INVISIBLE, !visible);
+            param.update_param_ui()?;
+        }
+        return Ok(());
+    }
+
+    let index = params.index(id).ok_or(Error::InvalidIndex)? as i32;
+    let plugin_id = plugin_id()?;
+    let effect = PFInterface::new()?.new_effect_for_effect(in_data.effect_ref(), plugin_id)?;
+    let stream = Stream::new()?.new_effect_stream_by_index(&effect, plugin_id, index)?;
+    let dynamic = DynamicStream::new()?;
+    let hidden = dynamic
+        .dynamic_stream_flags(&stream)?
+        .contains(DynamicStreamFlags::Hidden);
+    if hidden == visible {
+        dynamic.set_dynamic_stream_flag(&stream, DynamicStreamFlags::Hidden, false, !visible)?;
+    }
+    Ok(())
+}
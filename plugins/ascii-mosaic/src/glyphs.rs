@@ -0,0 +1,85 @@
+//! Built-in 5x7 bitmap glyph atlas.
+//!
+//! Each row is 5 bits wide with the most significant bit on the left. Glyphs
+//! are drawn in a 6x8 cell, leaving one blank column and row as spacing.
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+/// Cell size in glyph pixels, including the spacing column / row.
+pub const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+pub const CELL_HEIGHT: usize = GLYPH_HEIGHT + 1;
+
+pub type Glyph = [u8; GLYPH_HEIGHT];
+
+/// Printable glyphs of the atlas.
+#[rustfmt::skip]
+pub const ATLAS: &[(char, Glyph)] = &[
+    (' ', [0, 0, 0, 0, 0, 0, 0]),
+    ('.', [0, 0, 0, 0, 0, 0b01100, 0b01100]),
+    (',', [0, 0, 0, 0, 0b01100, 0b00100, 0b01000]),
+    ('\'', [0b01100, 0b00100, 0b01000, 0, 0, 0, 0]),
+    ('`', [0b01000, 0b00100, 0b00010, 0, 0, 0, 0]),
+    ('^', [0b00100, 0b01010, 0b10001, 0, 0, 0, 0]),
+    (':', [0, 0b01100, 0b01100, 0, 0b01100, 0b01100, 0]),
+    (';', [0, 0b01100, 0b01100, 0, 0b01100, 0b00100, 0b01000]),
+    ('-', [0, 0, 0, 0b11111, 0, 0, 0]),
+    ('_', [0, 0, 0, 0, 0, 0, 0b11111]),
+    ('~', [0, 0, 0b01000, 0b10101, 0b00010, 0, 0]),
+    ('=', [0, 0, 0b11111, 0, 0b11111, 0, 0]),
+    ('+', [0, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0, 0b00100]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0, 0b00100]),
+    ('|', [0b00100; GLYPH_HEIGHT]),
+    ('/', [0, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0]),
+    ('\\', [0, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000]),
+    ('i', [0b00100, 0, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('l', [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('*', [0, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0]),
+    ('x', [0, 0, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001]),
+    ('o', [0, 0, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+    ('$', [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100]),
+    ('&', [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('@', [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110]),
+    // シェード用のブロック文字（ドットパターンで濃度を表す）
+    ('░', [0b10001, 0, 0b00100, 0, 0b10001, 0, 0b00100]),
+    ('▒', [0b10101, 0b01010, 0b10101, 0b01010, 0b10101, 0b01010, 0b10101]),
+    ('▓', [0b11011, 0b01110, 0b11011, 0b01110, 0b11011, 0b01110, 0b11011]),
+    ('█', [0b11111; GLYPH_HEIGHT]),
+];
+
+/// Looks up `c`, falling back to a blank glyph.
+pub fn glyph(c: char) -> Glyph {
+    ATLAS
+        .iter()
+        .find(|(g, _)| *g == c)
+        .map(|(_, bits)| *bits)
+        .unwrap_or([0; GLYPH_HEIGHT])
+}
+
+/// Whether glyph pixel (`x`, `y`) of the 6x8 cell is inked.
+pub fn ink(glyph: &Glyph, x: usize, y: usize) -> bool {
+    x < GLYPH_WIDTH && y < GLYPH_HEIGHT && (glyph[y] >> (GLYPH_WIDTH - 1 - x)) & 1 == 1
+}
+
+/// Fraction of the 6x8 cell covered by ink.
+pub fn coverage(glyph: &Glyph) -> f32 {
+    let inked: u32 = glyph.iter().map(|row| row.count_ones()).sum();
+    inked as f32 / (CELL_WIDTH * CELL_HEIGHT) as f32
+}
@@ -0,0 +1,467 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+use utils::ui;
+
+mod glyphs;
+mod strings;
+
+use glyphs::{CELL_HEIGHT, CELL_WIDTH, Glyph};
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    CellSize,
+    Ramp,
+    InvertRamp,
+    ColorMode,
+    ForegroundColor,
+    BackgroundColor,
+    TransparentBackground,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Tiles the frame into cells drawn as glyphs picked by brightness from a built-in bitmap font.";
+
+/// Upper bound of `Cell Size` (full-resolution pixels).
+const MAX_CELL_SIZE: f32 = 200.0;
+/// Supersampling per axis when rasterizing glyphs (anti-aliased edges).
+const SUPERSAMPLE: usize = 4;
+
+const STANDARD_RAMP: &str = " .:-=+*#%@";
+const BLOCK_RAMP: &str = " ░▒▓█";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ramp {
+    /// Ten classic characters from space to `@`.
+    Standard,
+    /// Every text glyph of the atlas ordered by ink coverage.
+    Detailed,
+    /// Shade blocks.
+    Blocks,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Monochrome,
+    /// Glyphs take the average color of their cell.
+    Colored,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Cell Size: cell width; the height follows the 6:8 glyph cell
+        params.add(
+            Params::CellSize,
+            tr("Cell Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(2.0);
+                d.set_valid_max(MAX_CELL_SIZE);
+                d.set_slider_min(4.0);
+                d.set_slider_max(48.0);
+                d.set_default(12.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Ramp: glyphs ordered from dark to bright
+        params.add(
+            Params::Ramp,
+            tr("Ramp"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Standard"), tr("Detailed"), tr("Blocks")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::InvertRamp,
+            tr("Invert Ramp"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Color Mode: Monochrome uses Foreground Color (see update_color_visibility)
+        params.add_with_flags(
+            Params::ColorMode,
+            tr("Color Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Monochrome"), tr("Colored Glyphs")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::ForegroundColor,
+            tr("Foreground Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::BackgroundColor,
+            tr("Background Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Transparent Background: only the glyphs stay opaque
+        params.add_with_flags(
+            Params::TransparentBackground,
+            tr("Transparent Background"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_AsciiMosaic - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_color_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::ColorMode | Params::TransparentBackground
+                ) =>
+            {
+                update_color_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_color_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let colored = params.get(Params::ColorMode)?.as_popup()?.value() == 2;
+    let transparent = params
+        .get(Params::TransparentBackground)?
+        .as_checkbox()?
+        .value();
+
+    let mut set = |id: Params, hidden: bool| ui::set_param_visible(in_data, params, id, !hidden);
+    set(Params::ForegroundColor, colored)?;
+    set(Params::BackgroundColor, transparent)?;
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // セルサイズはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let fg = params
+            .get(Params::ForegroundColor)?
+            .as_color()?
+            .float_value()?;
+        let bg = params
+            .get(Params::BackgroundColor)?
+            .as_color()?
+            .float_value()?;
+        let settings = MosaicParams {
+            cell_size: ds.length(params.get(Params::CellSize)?.as_float_slider()?.value() as f32),
+            ramp: match params.get(Params::Ramp)?.as_popup()?.value() {
+                2 => Ramp::Detailed,
+                3 => Ramp::Blocks,
+                _ => Ramp::Standard,
+            },
+            invert_ramp: params.get(Params::InvertRamp)?.as_checkbox()?.value(),
+            color_mode: match params.get(Params::ColorMode)?.as_popup()?.value() {
+                2 => ColorMode::Colored,
+                _ => ColorMode::Monochrome,
+            },
+            foreground: [fg.red, fg.green, fg.blue],
+            background: [bg.red, bg.green, bg.blue],
+            transparent_background: params
+                .get(Params::TransparentBackground)?
+                .as_checkbox()?
+                .value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = mosaic(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct MosaicParams {
+    /// Cell width in pixels (already scaled for downsampling).
+    pub cell_size: f32,
+    pub ramp: Ramp,
+    pub invert_ramp: bool,
+    pub color_mode: ColorMode,
+    pub foreground: [f32; 3],
+    pub background: [f32; 3],
+    pub transparent_background: bool,
+}
+
+/// Glyphs of `ramp` from dark (no ink) to bright (most ink).
+fn ramp_glyphs(ramp: Ramp, invert: bool) -> Vec<Glyph> {
+    let mut ramp: Vec<Glyph> = match ramp {
+        Ramp::Standard => STANDARD_RAMP.chars().map(glyphs::glyph).collect(),
+        Ramp::Blocks => BLOCK_RAMP.chars().map(glyphs::glyph).collect(),
+        Ramp::Detailed => {
+            let mut all: Vec<Glyph> = glyphs::ATLAS
+                .iter()
+                .filter(|(c, _)| c.is_ascii())
+                .map(|(_, g)| *g)
+                .collect();
+            all.sort_by(|a, b| glyphs::coverage(a).total_cmp(&glyphs::coverage(b)));
+            all
+        }
+    };
+    if invert {
+        ramp.reverse();
+    }
+    ramp
+}
+
+/// Anti-aliased ink coverage of the rectangle `[u0, u1) x [v0, v1)` in glyph
+/// cell units (0..6 x 0..8).
+fn ink_coverage(glyph: &Glyph, u0: f32, v0: f32, u1: f32, v1: f32) -> f32 {
+    let mut hits = 0;
+    for sy in 0..SUPERSAMPLE {
+        let v = v0 + (v1 - v0) * (sy as f32 + 0.5) / SUPERSAMPLE as f32;
+        for sx in 0..SUPERSAMPLE {
+            let u = u0 + (u1 - u0) * (sx as f32 + 0.5) / SUPERSAMPLE as f32;
+            if u >= 0.0 && v >= 0.0 && glyphs::ink(glyph, u as usize, v as usize) {
+                hits += 1;
+            }
+        }
+    }
+    hits as f32 / (SUPERSAMPLE * SUPERSAMPLE) as f32
+}
+
+fn mosaic(input: &ImageBuf, p: &MosaicParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+
+    let cell_w = if p.cell_size.is_nan() {
+        2.0
+    } else {
+        p.cell_size.clamp(1.0, MAX_CELL_SIZE)
+    };
+    let cell_h = cell_w * CELL_HEIGHT as f32 / CELL_WIDTH as f32;
+    let cols = (w as f32 / cell_w).ceil() as usize;
+    let rows = (h as f32 / cell_h).ceil() as usize;
+    let cell_of = |x: usize, y: usize| {
+        let cx = ((x as f32 / cell_w) as usize).min(cols - 1);
+        let cy = ((y as f32 / cell_h) as usize).min(rows - 1);
+        cy * cols + cx
+    };
+
+    // セルごとの平均色（プリマルチプライド）
+    let premul = sampling::premultiply(input);
+    let mut sums = vec![[0.0f32; 5]; cols * rows];
+    for y in 0..h {
+        for x in 0..w {
+            let px = premul.data[premul.index(x, y)];
+            let s = &mut sums[cell_of(x, y)];
+            s[0] += px.red;
+            s[1] += px.green;
+            s[2] += px.blue;
+            s[3] += px.alpha;
+            s[4] += 1.0;
+        }
+    }
+    let cells: Vec<PixelF32> = sums
+        .iter()
+        .map(|s| {
+            let n = s[4].max(1.0);
+            sampling::unpremultiply(PixelF32 {
+                red: s[0] / n,
+                green: s[1] / n,
+                blue: s[2] / n,
+                alpha: s[3] / n,
+            })
+        })
+        .collect();
+
+    let ramp = ramp_glyphs(p.ramp, p.invert_ramp);
+    let finite = |v: f32| if v.is_finite() { v } else { 0.0 };
+    let glyph_of: Vec<&Glyph> = cells
+        .iter()
+        .map(|c| {
+            // 透明部分は暗い扱いにして空白寄りの文字にする
+            let level = finite(luminance(*c) * c.alpha).clamp(0.0, 1.0);
+            let index = ((level * ramp.len() as f32) as usize).min(ramp.len() - 1);
+            &ramp[index]
+        })
+        .collect();
+
+    let su = CELL_WIDTH as f32 / cell_w;
+    let sv = CELL_HEIGHT as f32 / cell_h;
+    for y in 0..h {
+        for x in 0..w {
+            let i = cell_of(x, y);
+            let (cx, cy) = (i % cols, i / cols);
+            let u0 = (x as f32 - cx as f32 * cell_w) * su;
+            let v0 = (y as f32 - cy as f32 * cell_h) * sv;
+            let ink = ink_coverage(glyph_of[i], u0, v0, u0 + su, v0 + sv);
+
+            let cell = cells[i];
+            let fg = match p.color_mode {
+                ColorMode::Monochrome => p.foreground,
+                ColorMode::Colored => [cell.red, cell.green, cell.blue],
+            };
+            let alpha = finite(cell.alpha).clamp(0.0, 1.0);
+            let px = if p.transparent_background {
+                PixelF32 {
+                    red: finite(fg[0]),
+                    green: finite(fg[1]),
+                    blue: finite(fg[2]),
+                    alpha: ink * alpha,
+                }
+            } else {
+                let mix = |b: f32, f: f32| finite(b + (f - b) * ink);
+                PixelF32 {
+                    red: mix(p.background[0], fg[0]),
+                    green: mix(p.background[1], fg[1]),
+                    blue: mix(p.background[2], fg[2]),
+                    alpha,
+                }
+            };
+            out.set(x, y, px);
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct AsciiMosaic;
+
+impl RenderCore for AsciiMosaic {
+    type Params = MosaicParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        MosaicParams {
+            cell_size: rng.slider(2.0, MAX_CELL_SIZE),
+            ramp: match rng.popup(1, 3) {
+                2 => Ramp::Detailed,
+                3 => Ramp::Blocks,
+                _ => Ramp::Standard,
+            },
+            invert_ramp: rng.chance(0.5),
+            color_mode: if rng.chance(0.5) {
+                ColorMode::Monochrome
+            } else {
+                ColorMode::Colored
+            },
+            foreground: [rng.unit(), rng.unit(), rng.unit()],
+            background: [rng.unit(), rng.unit(), rng.unit()],
+            transparent_background: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = mosaic(input, params);
+    }
+}
@@ -0,0 +1,559 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, luminance, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Levels,
+    BlackClip,
+    WhiteClip,
+    WhiteBalance,
+    SmoothingWindow,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: the analysis of recently rendered frames.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Automatically corrects levels and white balance.";
+
+/// Upper bound of `Black Clip` / `White Clip` (percent).
+const MAX_CLIP: f32 = 20.0;
+const MAX_WINDOW: i32 = 120;
+/// Analyses kept per sequence (a little more than the widest window).
+const HISTORY_FRAMES: usize = 2 * MAX_WINDOW as usize;
+/// Histogram bins between the darkest and brightest value of a frame.
+const BINS: usize = 4096;
+/// Smallest white - black span, so flat frames are not blown up.
+const MIN_RANGE: f32 = 1e-3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Levels {
+    Off,
+    /// One black and white point from the luma, keeping the hue.
+    Luminance,
+    /// Separate points per channel (also neutralizes color casts).
+    PerChannel,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add_with_flags(
+            Params::Levels,
+            tr("Levels"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Off"), tr("Luminance"), tr("Per Channel")]);
+                d.set_default(2);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Black / White Clip: percentage of pixels pushed below black / above white
+        params.add(
+            Params::BlackClip,
+            tr("Black Clip"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_CLIP);
+                d.set_slider_min(0.0);
+                d.set_slider_max(5.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::WhiteClip,
+            tr("White Clip"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_CLIP);
+                d.set_slider_min(0.0);
+                d.set_slider_max(5.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // White Balance: amount of the gray-world correction (averages to neutral)
+        params.add(
+            Params::WhiteBalance,
+            tr("White Balance"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Smoothing Window: frames averaged with the ones rendered just before (1 = per frame)
+        params.add(
+            Params::SmoothingWindow,
+            tr("Smoothing Window"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_WINDOW);
+                d.set_slider_min(1);
+                d.set_slider_max(30);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_AutoLevels - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn levels(params: &Parameters<Params>) -> Result<Levels, Error> {
+    Ok(match params.get(Params::Levels)?.as_popup()?.value() {
+        1 => Levels::Off,
+        3 => Levels::PerChannel,
+        _ => Levels::Luminance,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let on = levels(params)? != Levels::Off;
+    for id in [Params::BlackClip, Params::WhiteClip] {
+        ui::set_param_visible(in_data, params, id, on)?;
+    }
+    Ok(())
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // 解析結果は再計算できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(plugin.in_data, &input, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup => {
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&plugin.in_data, plugin.params)?;
+            }
+
+            ae::Command::UserChangedParam { param_index } => {
+                // 設定が変わったら過去の解析結果は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+                if plugin.params.type_at(param_index) == Params::Levels {
+                    update_param_visibility(&plugin.in_data, plugin.params)?;
+                }
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+
+                if let Some(in_layer) = in_layer_opt
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    let input = ImageBuf::from_layer(&in_layer);
+                    self.do_render(in_data, &input, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let settings = AutoLevelsParams {
+            levels: levels(params)?,
+            black_clip: params.get(Params::BlackClip)?.as_float_slider()?.value() as f32 / 100.0,
+            white_clip: params.get(Params::WhiteClip)?.as_float_slider()?.value() as f32 / 100.0,
+            white_balance: params.get(Params::WhiteBalance)?.as_float_slider()?.value() as f32
+                / 100.0,
+            smoothing_window: params.get(Params::SmoothingWindow)?.as_slider()?.value() as u32,
+        };
+
+        let key = HistoryKey {
+            downsample: ds,
+            time_step: in_data.time_step(),
+        };
+        let time = in_data.current_time();
+        let analysis = analyze(input, &settings);
+
+        // 直前にレンダリングされた窓内のフレームの解析結果と平均する
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.store(key, time, analysis);
+        let smoothed = history.smoothed(key, time, settings.smoothing_window);
+        drop(history);
+
+        apply(input, &smoothed.unwrap_or(analysis), &settings).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Render settings the cached analyses are only comparable for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct HistoryKey {
+    downsample: Downsample,
+    time_step: i32,
+}
+
+/// Analyses of recently rendered frames by layer time.
+#[derive(Default)]
+struct History {
+    key: Option<HistoryKey>,
+    frames: BTreeMap<i32, Analysis>,
+}
+
+impl History {
+    fn clear(&mut self) {
+        self.key = None;
+        self.frames.clear();
+    }
+
+    fn store(&mut self, key: HistoryKey, time: i32, analysis: Analysis) {
+        if self.key != Some(key) {
+            self.clear();
+            self.key = Some(key);
+        }
+        self.frames.insert(time, analysis);
+        // 現在時刻から最も遠いものから捨てる
+        while self.frames.len() > HISTORY_FRAMES {
+            let (&first, _) = self.frames.first_key_value().unwrap_or((&time, &analysis));
+            let (&last, _) = self.frames.last_key_value().unwrap_or((&time, &analysis));
+            let farthest = if time - first > last - time {
+                first
+            } else {
+                last
+            };
+            self.frames.remove(&farthest);
+        }
+    }
+
+    /// Mean of the cached analyses of the `window` frames ending at `time`.
+    fn smoothed(&self, key: HistoryKey, time: i32, window: u32) -> Option<Analysis> {
+        if self.key != Some(key) || key.time_step <= 0 {
+            return None;
+        }
+        let window = window.clamp(1, MAX_WINDOW as u32) as i32;
+        let start = time.saturating_sub((window - 1).saturating_mul(key.time_step));
+        let frames: Vec<&Analysis> = self
+            .frames
+            .range(start..=time)
+            .filter(|(t, _)| (time - **t) % key.time_step == 0)
+            .map(|(_, a)| a)
+            .collect();
+        Analysis::mean(&frames)
+    }
+}
+
+#[derive(Debug)]
+pub struct AutoLevelsParams {
+    pub levels: Levels,
+    /// 0..1 fraction of pixels clipped to black.
+    pub black_clip: f32,
+    /// 0..1 fraction of pixels clipped to white.
+    pub white_clip: f32,
+    /// 0..1 amount of the gray-world white balance.
+    pub white_balance: f32,
+    /// Frames averaged, counting the current one.
+    pub smoothing_window: u32,
+}
+
+/// Correction measured on one frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Analysis {
+    /// Linear-light gains of the white balance.
+    gains: [f32; 3],
+    /// Encoded black point per channel (all equal for [`Levels::Luminance`]).
+    black: [f32; 3],
+    /// Encoded white point per channel.
+    white: [f32; 3],
+}
+
+impl Analysis {
+    const IDENTITY: Self = Self {
+        gains: [1.0; 3],
+        black: [0.0; 3],
+        white: [1.0; 3],
+    };
+
+    fn mean(frames: &[&Analysis]) -> Option<Self> {
+        if frames.is_empty() {
+            return None;
+        }
+        let n = frames.len() as f32;
+        let avg = |f: fn(&Analysis) -> [f32; 3]| -> [f32; 3] {
+            std::array::from_fn(|c| frames.iter().map(|a| f(a)[c]).sum::<f32>() / n)
+        };
+        Some(Self {
+            gains: avg(|a| a.gains),
+            black: avg(|a| a.black),
+            white: avg(|a| a.white),
+        })
+    }
+}
+
+/// Encoded color with non-finite channels as 0 and the white balance applied
+/// in linear light.
+fn balanced(px: PixelF32, gains: [f32; 3]) -> [f32; 3] {
+    let rgb = [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0));
+    std::array::from_fn(|c| linear_to_srgb(srgb_to_linear(rgb[c]) * gains[c]))
+}
+
+/// Values at the `low` and `1 - high` quantiles of `values` weighted by
+/// `weights`, from a histogram between their extremes.
+fn percentiles(values: &[f32], weights: &[f32], low: f32, high: f32) -> Option<(f32, f32)> {
+    let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+    let mut total = 0.0;
+    for (&v, &w) in values.iter().zip(weights) {
+        if w > 0.0 {
+            min = min.min(v);
+            max = max.max(v);
+            total += w;
+        }
+    }
+    if total <= 0.0 || max <= min {
+        return (total > 0.0).then_some((min, max));
+    }
+    let scale = BINS as f32 / (max - min);
+    let mut hist = vec![0.0f32; BINS];
+    for (&v, &w) in values.iter().zip(weights) {
+        if w > 0.0 {
+            hist[(((v - min) * scale) as usize).min(BINS - 1)] += w;
+        }
+    }
+    // 累積がしきい値を超えたビンの中で線形補間する
+    let quantile = |q: f32| {
+        let target = q.clamp(0.0, 1.0) * total;
+        let mut acc = 0.0;
+        for (i, &c) in hist.iter().enumerate() {
+            if c > 0.0 && acc + c >= target {
+                let f = ((target - acc) / c).clamp(0.0, 1.0);
+                return min + (i as f32 + f) / scale;
+            }
+            acc += c;
+        }
+        max
+    };
+    Some((quantile(low), quantile(1.0 - high)))
+}
+
+pub fn analyze(input: &ImageBuf, p: &AutoLevelsParams) -> Analysis {
+    let weights: Vec<f32> = input
+        .data
+        .iter()
+        .map(|px| finite_or(px.alpha, 0.0).clamp(0.0, 1.0))
+        .collect();
+    let total: f32 = weights.iter().sum();
+    if total <= 0.0 {
+        return Analysis::IDENTITY;
+    }
+
+    // グレーワールド: 線形光での各チャンネル平均が揃うようにゲインを掛ける
+    let mut means = [0.0f32; 3];
+    for (px, &w) in input.data.iter().zip(&weights) {
+        let rgb = [px.red, px.green, px.blue].map(|c| srgb_to_linear(finite_or(c, 0.0)));
+        for c in 0..3 {
+            means[c] += rgb[c] * w;
+        }
+    }
+    let gray = 0.2126 * means[0] + 0.7152 * means[1] + 0.0722 * means[2];
+    let amount = finite_or(p.white_balance, 0.0).clamp(0.0, 1.0);
+    let gains = means.map(|m| {
+        let gain = if m > 0.0 && gray > 0.0 { gray / m } else { 1.0 };
+        1.0 + (gain.clamp(0.1, 10.0) - 1.0) * amount
+    });
+
+    let low = finite_or(p.black_clip, 0.0).clamp(0.0, MAX_CLIP / 100.0);
+    let high = finite_or(p.white_clip, 0.0).clamp(0.0, MAX_CLIP / 100.0);
+    let colors: Vec<[f32; 3]> = input.data.iter().map(|&px| balanced(px, gains)).collect();
+    let points = |values: Vec<f32>| percentiles(&values, &weights, low, high).unwrap_or((0.0, 1.0));
+    let (black, white) = match p.levels {
+        Levels::Off => ([0.0; 3], [1.0; 3]),
+        Levels::Luminance => {
+            let luma = colors
+                .iter()
+                .map(|c| {
+                    luminance(PixelF32 {
+                        red: c[0],
+                        green: c[1],
+                        blue: c[2],
+                        alpha: 1.0,
+                    })
+                })
+                .collect();
+            let (b, w) = points(luma);
+            ([b; 3], [w; 3])
+        }
+        Levels::PerChannel => {
+            let mut black = [0.0; 3];
+            let mut white = [1.0; 3];
+            for c in 0..3 {
+                (black[c], white[c]) = points(colors.iter().map(|rgb| rgb[c]).collect());
+            }
+            (black, white)
+        }
+    };
+    Analysis {
+        gains,
+        black,
+        white,
+    }
+}
+
+pub fn apply(input: &ImageBuf, a: &Analysis, p: &AutoLevelsParams) -> ImageBuf {
+    let mut out = ImageBuf::new(input.width, input.height);
+    for (dst, &src) in out.data.iter_mut().zip(&input.data) {
+        let rgb = balanced(src, a.gains);
+        let level = |c: usize| {
+            if p.levels == Levels::Off {
+                return rgb[c];
+            }
+            let range = (a.white[c] - a.black[c]).max(MIN_RANGE);
+            (rgb[c] - a.black[c]) / range
+        };
+        *dst = PixelF32 {
+            red: level(0),
+            green: level(1),
+            blue: level(2),
+            alpha: src.alpha,
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness renders frames independently, so there is no history to
+/// smooth over.
+pub struct AutoLevels;
+
+impl RenderCore for AutoLevels {
+    type Params = AutoLevelsParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        AutoLevelsParams {
+            levels: match rng.popup(1, 3) {
+                1 => Levels::Off,
+                3 => Levels::PerChannel,
+                _ => Levels::Luminance,
+            },
+            black_clip: rng.slider(0.0, MAX_CLIP / 100.0),
+            white_clip: rng.slider(0.0, MAX_CLIP / 100.0),
+            white_balance: rng.slider(0.0, 1.0),
+            smoothing_window: rng.int(1, MAX_WINDOW) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = apply(input, &analyze(input, params), params);
+    }
+}
@@ -0,0 +1,375 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Radius,
+    ColorSigma,
+    Iterations,
+    GuideLayer,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Smooths surfaces while preserving edges.";
+
+/// Upper bound of `Radius` (full-resolution pixels).
+const MAX_RADIUS: f32 = 100.0;
+const MAX_ITERATIONS: i32 = 5;
+/// Checkout id of the guide layer (the input uses 0).
+const GUIDE_CHECKOUT_ID: i32 = 1;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(30.0);
+                d.set_default(5.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Color Sigma: RGBA distance (0-1 scale) still treated as the same surface
+        params.add(
+            Params::ColorSigma,
+            tr("Color Sigma"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.001);
+                d.set_valid_max(10.0);
+                d.set_slider_min(0.001);
+                d.set_slider_max(0.5);
+                d.set_default(0.1);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Iterations: repeated horizontal + vertical passes hide the axis-aligned
+        // artifacts of the separable approximation
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_ITERATIONS);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Guide Layer: edges are taken from this layer instead of the input
+        params.add(Params::GuideLayer, tr("Guide Layer"), LayerDef::new())?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_BilateralSmooth - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではガイドレイヤーをパラメータから直接取得する
+                let guide = params
+                    .checkout_at(
+                        Params::GuideLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, guide.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::GuideLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        GUIDE_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let guide = cb
+                    .checkout_layer_pixels(GUIDE_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, guide.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(GUIDE_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        guide: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 半径はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = BilateralParams {
+            radius: ds.length(params.get(Params::Radius)?.as_float_slider()?.value() as f32),
+            color_sigma: params.get(Params::ColorSigma)?.as_float_slider()?.value() as f32,
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as usize,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = bilateral(&input, guide, &settings);
+
+        let progress_final = out_layer.height() as i32;
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let (x, y) = (x as usize, y as usize);
+            let px = if x < output.width && y < output.height {
+                output.data[output.index(x, y)]
+            } else {
+                utils::image::TRANSPARENT
+            };
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BilateralParams {
+    /// Spatial radius in pixels (already scaled for downsampling).
+    pub radius: f32,
+    /// Range sigma over straight RGBA distance.
+    pub color_sigma: f32,
+    pub iterations: usize,
+}
+
+/// Edge-stopping features per pixel: straight RGBA of `guide` (stretched to
+/// `width` x `height`), with non-finite channels treated as 0.
+fn guide_features(guide: &ImageBuf, width: usize, height: usize) -> Vec<[f32; 4]> {
+    let finite = |v: f32| if v.is_finite() { v } else { 0.0 };
+    let mut features = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            // ガイドレイヤーのサイズが異なる場合は引き伸ばして対応させる
+            let gx = x * guide.width / width.max(1);
+            let gy = y * guide.height / height.max(1);
+            let px = guide.get_clamped(gx as isize, gy as isize);
+            features.push([
+                finite(px.red),
+                finite(px.green),
+                finite(px.blue),
+                finite(px.alpha),
+            ]);
+        }
+    }
+    features
+}
+
+/// One 1D joint bilateral pass along x (`horizontal`) or y over premultiplied `image`.
+fn bilateral_pass(
+    image: &ImageBuf,
+    features: &[[f32; 4]],
+    spatial: &[f32],
+    inv_range: f32,
+    horizontal: bool,
+) -> ImageBuf {
+    let (w, h) = (image.width as isize, image.height as isize);
+    let r = spatial.len() as isize - 1;
+    let mut out = ImageBuf::new(image.width, image.height);
+
+    for y in 0..h {
+        for x in 0..w {
+            let center = features[image.index(x as usize, y as usize)];
+            let mut acc = [0.0f32; 4];
+            let mut total = 0.0f32;
+            for k in -r..=r {
+                let (qx, qy) = if horizontal { (x + k, y) } else { (x, y + k) };
+                if qx < 0 || qy < 0 || qx >= w || qy >= h {
+                    continue;
+                }
+                let i = image.index(qx as usize, qy as usize);
+                let f = features[i];
+                let d2: f32 = (0..4)
+                    .map(|c| (f[c] - center[c]) * (f[c] - center[c]))
+                    .sum();
+                let wt = spatial[k.unsigned_abs()] * (-d2 * inv_range).exp();
+                if wt <= 0.0 {
+                    continue;
+                }
+                let px = image.data[i];
+                acc[0] += px.red * wt;
+                acc[1] += px.green * wt;
+                acc[2] += px.blue * wt;
+                acc[3] += px.alpha * wt;
+                total += wt;
+            }
+            // 中心画素の重みは常に 1 なので total > 0
+            out.set(
+                x as usize,
+                y as usize,
+                PixelF32 {
+                    red: acc[0] / total,
+                    green: acc[1] / total,
+                    blue: acc[2] / total,
+                    alpha: acc[3] / total,
+                },
+            );
+        }
+    }
+    out
+}
+
+/// Separable joint bilateral filter: alternating horizontal and vertical 1D
+/// passes, each stopping at edges of the guide (the input itself by default).
+/// Cost is linear in the radius, so large radii stay usable at 4K.
+fn bilateral(input: &ImageBuf, guide: Option<&ImageBuf>, p: &BilateralParams) -> ImageBuf {
+    let radius = if p.radius.is_nan() {
+        0.0
+    } else {
+        p.radius.clamp(0.0, MAX_RADIUS)
+    };
+    let color_sigma = if p.color_sigma.is_nan() {
+        0.1
+    } else {
+        p.color_sigma.clamp(0.001, 10.0)
+    };
+
+    let mut image = sampling::premultiply(input);
+    let r = radius.round() as usize;
+    if r > 0 && !input.is_empty() {
+        let features = guide_features(
+            guide.filter(|g| !g.is_empty()).unwrap_or(input),
+            input.width,
+            input.height,
+        );
+        // 半径を 2σ とするガウス重み
+        let sigma = (radius * 0.5).max(0.5);
+        let spatial: Vec<f32> = (0..=r)
+            .map(|k| (-((k * k) as f32) / (2.0 * sigma * sigma)).exp())
+            .collect();
+        let inv_range = 1.0 / (2.0 * color_sigma * color_sigma);
+
+        for _ in 0..p.iterations.clamp(1, MAX_ITERATIONS as usize) {
+            image = bilateral_pass(&image, &features, &spatial, inv_range, true);
+            image = bilateral_pass(&image, &features, &spatial, inv_range, false);
+        }
+    }
+
+    for px in image.data.iter_mut() {
+        *px = sampling::unpremultiply(*px);
+    }
+    image
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so a guide is only exercised on odd frames
+/// (the input itself, passed explicitly).
+pub struct BilateralSmooth;
+
+impl RenderCore for BilateralSmooth {
+    type Params = BilateralParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        BilateralParams {
+            radius: rng.slider(0.0, MAX_RADIUS),
+            color_sigma: rng.slider(0.001, 10.0),
+            iterations: rng.popup(1, MAX_ITERATIONS) as usize,
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let guide = (frame % 2 == 1).then_some(input);
+        *output = bilateral(input, guide, params);
+    }
+}
@@ -0,0 +1,422 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{hash, unit};
+use utils::ToPixel;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Intensity,
+    BlockSize,
+    SizeVariation,
+    Probability,
+    MaxDisplacement,
+    ChannelSplit,
+    LineRepeat,
+    Seed,
+    AnimateSeed,
+    HoldFrames,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Displaces random macroblocks for a digital glitch look.";
+
+/// Maximum number of times a block may be split into quarters.
+const MAX_SUBDIVISIONS: u32 = 2;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Intensity: scales probability and displacement, meant to be keyframed
+        params.add(
+            Params::Intensity,
+            tr("Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BlockSize,
+            tr("Block Size"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(1024);
+                d.set_slider_min(4);
+                d.set_slider_max(256);
+                d.set_default(32);
+            }),
+        )?;
+
+        // Size Variation: chance of splitting a block into quarters (up to twice)
+        params.add(
+            Params::SizeVariation,
+            tr("Size Variation"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.4);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Probability,
+            tr("Probability"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.3);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::MaxDisplacement,
+            tr("Max Displacement"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(4000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(500.0);
+                d.set_default(64.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::ChannelSplit,
+            tr("Channel Split"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(8.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Line Repeat: chance that a glitched block smears a single row instead
+        params.add(
+            Params::LineRepeat,
+            tr("Line Repeat"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.2);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        params.add(
+            Params::AnimateSeed,
+            tr("Animate Seed"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+                d.set_label(tr("Per Frame"));
+            }),
+        )?;
+
+        // Hold Frames: keep each random pattern for this many frames
+        params.add(
+            Params::HoldFrames,
+            tr("Hold Frames"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(1000);
+                d.set_slider_min(1);
+                d.set_slider_max(30);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_BlockGlitch - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+        let ds = Downsample::from_in_data(&in_data);
+        let length = |v: f64| ds.length(v as f32);
+
+        let settings = BlockGlitchParams {
+            intensity: params.get(Params::Intensity)?.as_float_slider()?.value() as f32 / 100.0,
+            block_size: length(params.get(Params::BlockSize)?.as_slider()?.value() as f64),
+            size_variation: params
+                .get(Params::SizeVariation)?
+                .as_float_slider()?
+                .value() as f32,
+            probability: params.get(Params::Probability)?.as_float_slider()?.value() as f32,
+            max_displacement: length(
+                params
+                    .get(Params::MaxDisplacement)?
+                    .as_float_slider()?
+                    .value(),
+            ),
+            channel_split: length(params.get(Params::ChannelSplit)?.as_float_slider()?.value()),
+            line_repeat: params.get(Params::LineRepeat)?.as_float_slider()?.value() as f32,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            animate_seed: params.get(Params::AnimateSeed)?.as_checkbox()?.value(),
+            hold_frames: params.get(Params::HoldFrames)?.as_slider()?.value() as u32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let frame = in_data.current_frame() as u32;
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = glitch_pixel(&input, x as usize, y as usize, &settings, frame);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BlockGlitchParams {
+    /// 0..1; scales probability, displacement and channel split.
+    pub intensity: f32,
+    /// Block size in render pixels.
+    pub block_size: f32,
+    pub size_variation: f32,
+    pub probability: f32,
+    /// Render pixels.
+    pub max_displacement: f32,
+    /// Render pixels.
+    pub channel_split: f32,
+    pub line_repeat: f32,
+    pub seed: u32,
+    /// Re-roll the blocks every `hold_frames` frames instead of holding them.
+    pub animate_seed: bool,
+    pub hold_frames: u32,
+}
+
+/// A (possibly subdivided) macroblock containing a pixel.
+struct Block {
+    key: u32,
+    top: usize,
+}
+
+fn block_at(x: usize, y: usize, size: usize, variation: f32, seed: u32) -> Block {
+    let mut size = size;
+    let mut level = 0;
+    loop {
+        let (cx, cy) = ((x / size) as u32, (y / size) as u32);
+        let key = hash(hash(seed, level, 0xb10c), cx, cy);
+        // 確率的に 4 分割して、マクロブロックの大きさをばらつかせる
+        if level < MAX_SUBDIVISIONS && size >= 4 && unit(hash(key, 1, 0)) < variation {
+            size /= 2;
+            level += 1;
+            continue;
+        }
+        return Block {
+            key,
+            top: cy as usize * size,
+        };
+    }
+}
+
+fn glitch_pixel(
+    input: &ImageBuf,
+    x: usize,
+    y: usize,
+    p: &BlockGlitchParams,
+    frame: u32,
+) -> PixelF32 {
+    let original = input.get(x as isize, y as isize);
+    let intensity = finite_or(p.intensity, 0.0).clamp(0.0, 1.0);
+    let probability = finite_or(p.probability, 0.0).clamp(0.0, 1.0) * intensity;
+    if probability <= 0.0 || input.is_empty() {
+        return original;
+    }
+
+    let seed = if p.animate_seed {
+        hash(p.seed, frame / p.hold_frames.max(1), 0x5eed)
+    } else {
+        p.seed
+    };
+    let size = finite_or(p.block_size, 1.0).clamp(1.0, 1e6) as usize;
+    let variation = finite_or(p.size_variation, 0.0);
+    let block = block_at(x, y, size, variation, seed);
+    if unit(hash(block.key, 2, 0)) >= probability {
+        return original;
+    }
+
+    let max = finite_or(p.max_displacement, 0.0).clamp(0.0, 1e6) * intensity;
+    let offset = |salt: u32| ((unit(hash(block.key, 3, salt)) * 2.0 - 1.0) * max).round() as isize;
+    let (dx, dy) = (offset(0), offset(1) / 4);
+
+    // ライン反復：ブロック内のある 1 行を縦に引き伸ばす
+    let sy = if unit(hash(block.key, 4, 0)) < finite_or(p.line_repeat, 0.0) {
+        block.top as isize + dy
+    } else {
+        y as isize + dy
+    };
+    let sx = x as isize + dx;
+
+    let split = finite_or(p.channel_split, 0.0).clamp(0.0, 1e6) * intensity;
+    let split = (split * unit(hash(block.key, 5, 0))).round() as isize;
+    let r = input.get_clamped(sx + split, sy);
+    let g = input.get_clamped(sx, sy);
+    let b = input.get_clamped(sx - split, sy);
+
+    PixelF32 {
+        red: r.red,
+        green: g.green,
+        blue: b.blue,
+        alpha: r.alpha.max(g.alpha).max(b.alpha),
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct BlockGlitch;
+
+impl RenderCore for BlockGlitch {
+    type Params = BlockGlitchParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        BlockGlitchParams {
+            intensity: rng.slider(0.0, 1.0),
+            block_size: rng.int(2, 1024) as f32,
+            size_variation: rng.slider(0.0, 1.0),
+            probability: rng.slider(0.0, 1.0),
+            max_displacement: rng.slider(0.0, 4000.0),
+            channel_split: rng.slider(0.0, 1000.0),
+            line_repeat: rng.slider(0.0, 1.0),
+            seed: rng.popup(0, 10000) as u32,
+            animate_seed: rng.chance(0.5),
+            hold_frames: rng.int(1, 1000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        for y in 0..input.height {
+            for x in 0..input.width {
+                output.set(x, y, glitch_pixel(input, x, y, params, frame));
+            }
+        }
+    }
+}
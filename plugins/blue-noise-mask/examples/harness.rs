@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<blue_noise_mask::BlueNoiseMask>();
+}
@@ -0,0 +1,447 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use ae::pf::*;
+use utils::dither::{interleaved_gradient, void_and_cluster};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Pattern,
+    Resolution,
+    Seed,
+    TexelSize,
+    Channels,
+    Animate,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: void-and-cluster maps already built for this instance.
+#[derive(Default)]
+struct Instance {
+    maps: Mutex<Vec<(MapKey, Arc<[f32]>)>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Generates tileable blue-noise threshold masks.";
+
+/// Tile sizes offered by `Resolution`, in texels per side.
+const RESOLUTIONS: [usize; 5] = [16, 32, 64, 128, 256];
+/// Maps kept per sequence: one per RGB channel.
+const MAX_CACHED_MAPS: usize = 3;
+/// Per-frame value offset; the golden ratio keeps successive frames decorrelated.
+const GOLDEN_RATIO: f64 = 0.618_033_988_749_894_8;
+/// Texel offset between the interleaved-gradient channels (Jimenez's frame step).
+const CHANNEL_SHIFT: f32 = 5.588_238;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Tileable void-and-cluster map.
+    BlueNoise,
+    /// Closed-form interleaved gradient noise; needs no precomputation.
+    InterleavedGradient,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channels {
+    Mono,
+    /// Independent masks in red, green and blue.
+    Rgb,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Pattern: Resolution and Seed only apply to blue noise
+        params.add_with_flags(
+            Params::Pattern,
+            tr("Pattern"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Blue Noise"), tr("Interleaved Gradient")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Resolution: side of the repeating tile in texels (larger tiles take longer to build once)
+        params.add(
+            Params::Resolution,
+            tr("Resolution"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("16"), tr("32"), tr("64"), tr("128"), tr("256")]);
+                d.set_default(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        // Texel Size: pixels per mask texel
+        params.add(
+            Params::TexelSize,
+            tr("Texel Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(64.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(16.0);
+                d.set_default(1.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Channels: RGB writes three decorrelated masks
+        params.add(
+            Params::Channels,
+            tr("Channels"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Mono"), tr("RGB")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Animate: shifts thresholds by the golden ratio every frame for temporal dithering
+        params.add(
+            Params::Animate,
+            tr("Animate"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_BlueNoiseMask - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn pattern(params: &Parameters<Params>) -> Result<Pattern, Error> {
+    Ok(match params.get(Params::Pattern)?.as_popup()?.value() {
+        2 => Pattern::InterleavedGradient,
+        _ => Pattern::BlueNoise,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let blue_noise = pattern(params)? == Pattern::BlueNoise;
+    for id in [Params::Resolution, Params::Seed] {
+        ui::set_param_visible(in_data, params, id, blue_noise)?;
+    }
+    Ok(())
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // マップはシードから作り直せるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        _in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        self.do_render(plugin.in_data, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&plugin.in_data, plugin.params)?;
+            }
+
+            ae::Command::UserChangedParam { param_index }
+                if plugin.params.type_at(param_index) == Params::Pattern =>
+            {
+                update_param_visibility(&plugin.in_data, plugin.params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                if let Some(mut out_layer) = cb.checkout_output()? {
+                    self.do_render(in_data, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let resolution = params.get(Params::Resolution)?.as_popup()?.value() as usize;
+        let settings = MaskParams {
+            pattern: pattern(params)?,
+            resolution: RESOLUTIONS[resolution.clamp(1, RESOLUTIONS.len()) - 1],
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            texel_size: ds.length(params.get(Params::TexelSize)?.as_float_slider()?.value() as f32),
+            channels: match params.get(Params::Channels)?.as_popup()?.value() {
+                2 => Channels::Rgb,
+                _ => Channels::Mono,
+            },
+            animate: params.get(Params::Animate)?.as_checkbox()?.value(),
+            frame: in_data.current_frame() as i32,
+        };
+
+        let maps = self.maps(&settings);
+        let (width, height) = (out_layer.width(), out_layer.height());
+        blue_noise_mask(width, height, &settings, &maps).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+
+    /// Maps for `p`, building only the ones not cached yet.
+    fn maps(&self, p: &MaskParams) -> Vec<Arc<[f32]>> {
+        // 構築中はロックを保持し、並列レンダリングで同じマップを二重に作らない
+        let mut cache = self.maps.lock().unwrap_or_else(|e| e.into_inner());
+        map_keys(p)
+            .into_iter()
+            .map(|key| {
+                if let Some(pos) = cache.iter().position(|(k, _)| *k == key) {
+                    // 最近使ったものを末尾へ
+                    let entry = cache.remove(pos);
+                    cache.push(entry);
+                } else {
+                    cache.push((key, key.build()));
+                    if cache.len() > MAX_CACHED_MAPS {
+                        cache.remove(0);
+                    }
+                }
+                cache[cache.len() - 1].1.clone()
+            })
+            .collect()
+    }
+}
+
+/// Identifies one void-and-cluster map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MapKey {
+    size: usize,
+    seed: u32,
+}
+
+impl MapKey {
+    fn build(self) -> Arc<[f32]> {
+        void_and_cluster(self.size, self.seed).into()
+    }
+}
+
+/// Maps needed by `p`: one per output channel for blue noise, none otherwise.
+fn map_keys(p: &MaskParams) -> Vec<MapKey> {
+    if p.pattern != Pattern::BlueNoise {
+        return Vec::new();
+    }
+    let channels = match p.channels {
+        Channels::Mono => 1,
+        Channels::Rgb => 3,
+    };
+    (0..channels)
+        .map(|c| MapKey {
+            size: p.resolution.clamp(1, RESOLUTIONS[RESOLUTIONS.len() - 1]),
+            seed: p.seed.wrapping_add(c),
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct MaskParams {
+    pub pattern: Pattern,
+    /// Blue-noise tile side in texels.
+    pub resolution: usize,
+    pub seed: u32,
+    /// Render pixels per texel.
+    pub texel_size: f32,
+    pub channels: Channels,
+    pub animate: bool,
+    pub frame: i32,
+}
+
+/// Builds the blue-noise maps `p` needs without any caching.
+pub fn mask_maps(p: &MaskParams) -> Vec<Arc<[f32]>> {
+    map_keys(p).into_iter().map(MapKey::build).collect()
+}
+
+/// Renders the mask; `maps` are the blue-noise tiles from [`mask_maps`]
+/// (or a cache of them), one per channel.
+pub fn blue_noise_mask(
+    width: usize,
+    height: usize,
+    p: &MaskParams,
+    maps: &[Arc<[f32]>],
+) -> ImageBuf {
+    let mut out = ImageBuf::new(width, height);
+    let texel = finite_or(p.texel_size, 1.0).max(1e-3);
+    let offset = if p.animate {
+        (p.frame as f64 * GOLDEN_RATIO).rem_euclid(1.0) as f32
+    } else {
+        0.0
+    };
+    let channels = match p.channels {
+        Channels::Mono => 1,
+        Channels::Rgb => 3,
+    };
+
+    for y in 0..height {
+        let ty = (y as f32 / texel).floor();
+        for x in 0..width {
+            let tx = (x as f32 / texel).floor();
+            let value = |c: usize| {
+                let v = match p.pattern {
+                    Pattern::BlueNoise => maps.get(c).or(maps.first()).map_or(0.5, |map| {
+                        // タイルの一辺はマップの長さから求める
+                        let n = map.len().isqrt().max(1) as i64;
+                        let (mx, my) = ((tx as i64).rem_euclid(n), (ty as i64).rem_euclid(n));
+                        map[(my * n + mx) as usize]
+                    }),
+                    Pattern::InterleavedGradient => {
+                        let shift = c as f32 * CHANNEL_SHIFT;
+                        interleaved_gradient(tx + shift, ty + shift)
+                    }
+                };
+                (v + offset).fract()
+            };
+            let (red, green, blue) = if channels == 1 {
+                let v = value(0);
+                (v, v, v)
+            } else {
+                (value(0), value(1), value(2))
+            };
+            out.set(
+                x,
+                y,
+                PixelF32 {
+                    red,
+                    green,
+                    blue,
+                    alpha: 1.0,
+                },
+            );
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no sequence data, so maps are rebuilt for every frame;
+/// resolutions above 64 are left out to keep fuzz runs short.
+pub struct BlueNoiseMask;
+
+impl RenderCore for BlueNoiseMask {
+    type Params = MaskParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        MaskParams {
+            pattern: if rng.chance(0.5) {
+                Pattern::BlueNoise
+            } else {
+                Pattern::InterleavedGradient
+            },
+            resolution: RESOLUTIONS[rng.popup(1, 3) as usize - 1],
+            seed: rng.popup(0, 10000) as u32,
+            texel_size: rng.slider(1.0, 16.0),
+            channels: if rng.chance(0.5) {
+                Channels::Mono
+            } else {
+                Channels::Rgb
+            },
+            animate: rng.chance(0.5),
+            frame: 0,
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let params = MaskParams {
+            frame: frame as i32,
+            ..params.clone()
+        };
+        let maps = mask_maps(&params);
+        *output = blue_noise_mask(input.width, input.height, &params, &maps);
+    }
+}
@@ -0,0 +1,2 @@
+#[cfg(feature = "gpu_wgpu")]
+pub mod wgpu;
@@ -0,0 +1,241 @@
+use after_effects as ae;
+use bytemuck::{Pod, Zeroable};
+use std::sync::Mutex;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::*;
+
+/// Uniforms shared with `shaders/compute.wgsl` (`Params`). Keep the layout in sync
+/// and padded to 16 bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+pub struct RenderParams {
+    pub width: u32,
+    pub height: u32,
+    pub blades: u32,
+    /// Gather radius in pixels (largest circle of confusion, rounded up).
+    pub reach: u32,
+    pub rotation: f32,
+    pub curvature: f32,
+    pub _pad: [f32; 2],
+}
+
+pub struct WgpuContext {
+    pub device: Device,
+    pub queue: Queue,
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    // AE renders frames concurrently (MFR); serialize submissions on the shared queue.
+    lock: Mutex<()>,
+}
+
+impl WgpuContext {
+    pub fn new() -> Result<Self, ae::Error> {
+        let power_preference =
+            wgpu::PowerPreference::from_env().unwrap_or(PowerPreference::HighPerformance);
+        let mut instance_desc = InstanceDescriptor::default();
+        if instance_desc.backends.contains(Backends::DX12)
+            && instance_desc.flags.contains(InstanceFlags::VALIDATION)
+        {
+            instance_desc.backends.remove(Backends::DX12);
+        }
+
+        let instance = Instance::new(&instance_desc);
+        let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+            power_preference,
+            ..Default::default()
+        }))
+        .map_err(|_| ae::Error::BadCallbackParameter)?;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(&DeviceDescriptor {
+            label: None,
+            required_features: adapter.features(),
+            required_limits: adapter.limits(),
+            experimental_features: ExperimentalFeatures::disabled(),
+            memory_hints: MemoryHints::Performance,
+            trace: Trace::Off,
+        }))
+        .map_err(|_| ae::Error::BadCallbackParameter)?;
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("bokeh_blur_compute"),
+            source: ShaderSource::Wgsl(include_str!("shaders/compute.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                storage_entry(0, true),
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(2, false),
+                storage_entry(3, true),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("bokeh_blur_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Blurs interleaved premultiplied RGBA `input` with per-pixel
+    /// `(circle of confusion, depth)` pairs in `coc`, and returns the RGBA output.
+    pub fn render(
+        &self,
+        params: &RenderParams,
+        input: &[f32],
+        coc: &[f32],
+    ) -> Result<Vec<f32>, ae::Error> {
+        let out_bytes = calc_out_bytes(params.width, params.height)?;
+        if out_bytes == 0
+            || std::mem::size_of_val(input) < out_bytes as usize
+            || std::mem::size_of_val(coc) * 2 < out_bytes as usize
+        {
+            return Err(ae::Error::BadCallbackParameter);
+        }
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| ae::Error::BadCallbackParameter)?;
+
+        let input_buf = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("input"),
+            contents: bytemuck::cast_slice(input),
+            usage: BufferUsages::STORAGE,
+        });
+        let params_buf = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("params"),
+            contents: bytemuck::bytes_of(params),
+            usage: BufferUsages::UNIFORM,
+        });
+        let coc_buf = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("coc"),
+            contents: bytemuck::cast_slice(coc),
+            usage: BufferUsages::STORAGE,
+        });
+        let output_buf = self.device.create_buffer(&BufferDescriptor {
+            label: Some("output"),
+            size: out_bytes,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buf = self.device.create_buffer(&BufferDescriptor {
+            label: Some("staging"),
+            size: out_bytes,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: input_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: params_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: output_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: coc_buf.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(dispatch_dim(params.width), dispatch_dim(params.height), 1);
+        }
+        encoder.copy_buffer_to_buffer(&output_buf, 0, &staging_buf, 0, out_bytes);
+        self.queue.submit(Some(encoder.finish()));
+
+        read_back(&self.device, &staging_buf)
+    }
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn read_back(device: &Device, staging: &Buffer) -> Result<Vec<f32>, ae::Error> {
+    let slice = staging.slice(..);
+    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device
+        .poll(PollType::wait_indefinitely())
+        .map_err(|_| ae::Error::BadCallbackParameter)?;
+    match pollster::block_on(receiver.receive()) {
+        Some(Ok(())) => {}
+        _ => return Err(ae::Error::BadCallbackParameter),
+    }
+    let data = slice.get_mapped_range();
+    let out = bytemuck::cast_slice::<u8, f32>(&data).to_vec();
+    drop(data);
+    staging.unmap();
+    Ok(out)
+}
+
+fn dispatch_dim(size: u32) -> u32 {
+    size.div_ceil(16)
+}
+
+fn calc_out_bytes(out_w: u32, out_h: u32) -> Result<u64, ae::Error> {
+    let pixels = (out_w as u64)
+        .checked_mul(out_h as u64)
+        .ok_or(ae::Error::BadCallbackParameter)?;
+    let bytes = pixels
+        .checked_mul(4)
+        .and_then(|v| v.checked_mul(std::mem::size_of::<f32>() as u64))
+        .ok_or(ae::Error::BadCallbackParameter)?;
+    Ok(bytes)
+}
@@ -0,0 +1,100 @@
+// Keep in sync with `RenderParams` in ../mod.rs and `Aperture` / `bokeh_pixel` in lib.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    blades: u32,
+    reach: u32,
+    rotation: f32,
+    curvature: f32,
+    _pad: vec2<f32>,
+};
+
+// Premultiplied, highlight-boosted RGBA.
+@group(0) @binding(0) var<storage, read> input: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+// (circle of confusion, depth) per pixel.
+@group(0) @binding(3) var<storage, read> coc: array<vec2<f32>>;
+
+const PI: f32 = 3.14159265358979;
+const TAU: f32 = 6.28318530717959;
+
+fn rem_euclid(x: f32, m: f32) -> f32 {
+    let r = x - m * trunc(x / m);
+    return select(r, r + m, r < 0.0);
+}
+
+fn radius_at(theta: f32) -> f32 {
+    let seg = TAU / f32(params.blades);
+    let t = rem_euclid(theta - params.rotation, seg) - seg * 0.5;
+    let polygon = cos(seg * 0.5) / cos(t);
+    return polygon + (1.0 - polygon) * params.curvature;
+}
+
+fn coverage(dx: f32, dy: f32, r: f32) -> f32 {
+    if (dx == 0.0 && dy == 0.0) {
+        return 1.0;
+    }
+    let d = length(vec2<f32>(dx, dy));
+    if (d > r + 0.5) {
+        return 0.0;
+    }
+    return clamp(r * radius_at(atan2(dy, dx)) - d + 0.5, 0.0, 1.0);
+}
+
+fn unpremultiply(px: vec4<f32>) -> vec4<f32> {
+    let a = clamp(px.a, 0.0, 1.0);
+    if (a <= 0.0) {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(max(px.rgb / a, vec3<f32>(0.0)), a);
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+
+    let n = f32(params.blades);
+    let polygon = 0.5 * n * sin(TAU / n);
+    let area = polygon + (PI - polygon) * params.curvature;
+
+    let here = coc[i];
+    let reach = i32(params.reach);
+    var acc = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var dy = -reach; dy <= reach; dy++) {
+        let qy = i32(gid.y) + dy;
+        if (qy < 0 || qy >= i32(params.height)) {
+            continue;
+        }
+        for (var dx = -reach; dx <= reach; dx++) {
+            let qx = i32(gid.x) + dx;
+            if (qx < 0 || qx >= i32(params.width)) {
+                continue;
+            }
+            let j = u32(qy) * params.width + u32(qx);
+            let there = coc[j];
+            // 奥の画素は手前の画素の上に、その画素自身のボケ以上には広がらない
+            var r = there.x;
+            if (there.y > here.y) {
+                r = min(r, here.x);
+            }
+            let cover = coverage(f32(dx), f32(dy), r);
+            if (cover <= 0.0) {
+                continue;
+            }
+            let w = cover / (area * (r + 0.5) * (r + 0.5));
+            acc += input[j] * w;
+            total += w;
+        }
+    }
+
+    var out = vec4<f32>(0.0);
+    if (total > 0.0) {
+        out = acc / total;
+    }
+    output[i] = unpremultiply(out);
+}
@@ -0,0 +1,576 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::color::luminance;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Radius,
+    BladeCount,
+    BladeRotation,
+    BladeCurvature,
+    HighlightThreshold,
+    HighlightGain,
+    DepthLayer,
+    FocalDepth,
+    InvertDepth,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Applies a depth-aware lens blur with a bladed aperture.";
+
+/// Upper bound of `Radius` (full-resolution pixels).
+const MAX_RADIUS: f32 = 100.0;
+const MIN_BLADES: u32 = 3;
+const MAX_BLADES: u32 = 16;
+/// Checkout id of the depth layer (the input uses 0).
+const DEPTH_CHECKOUT_ID: i32 = 1;
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Radius: circle of confusion at full defocus, in pixels
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(50.0);
+                d.set_default(10.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BladeCount,
+            tr("Blade Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_BLADES as i32);
+                d.set_valid_max(MAX_BLADES as i32);
+                d.set_slider_min(MIN_BLADES as i32);
+                d.set_slider_max(MAX_BLADES as i32);
+                d.set_default(6);
+            }),
+        )?;
+
+        params.add(
+            Params::BladeRotation,
+            tr("Blade Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Blade Curvature: 0 = straight blades, 1 = circular aperture
+        params.add(
+            Params::BladeCurvature,
+            tr("Blade Curvature"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Highlight Threshold / Gain: pixels brighter than the threshold are
+        // boosted before blurring so they bloom into visible aperture shapes
+        params.add(
+            Params::HighlightThreshold,
+            tr("Highlight Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.8);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::HighlightGain,
+            tr("Highlight Gain"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Depth Layer: luminance 0 = near, 1 = far; the blur radius scales
+        // with the distance from Focal Depth. Without a layer the blur is uniform.
+        params.add(Params::DepthLayer, tr("Depth Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::FocalDepth,
+            tr("Focal Depth"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::InvertDepth,
+            tr("Invert Depth"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_BokehBlur - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では深度レイヤーをパラメータから直接取得する
+                let depth = params
+                    .checkout_at(
+                        Params::DepthLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, depth.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::DepthLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        DEPTH_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let depth = cb
+                    .checkout_layer_pixels(DEPTH_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, depth.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(DEPTH_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        depth: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 半径はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = BokehParams {
+            radius: ds.length(params.get(Params::Radius)?.as_float_slider()?.value() as f32),
+            blades: params.get(Params::BladeCount)?.as_slider()?.value() as u32,
+            rotation: params
+                .get(Params::BladeRotation)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            curvature: params
+                .get(Params::BladeCurvature)?
+                .as_float_slider()?
+                .value() as f32,
+            highlight_threshold: params
+                .get(Params::HighlightThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            highlight_gain: params
+                .get(Params::HighlightGain)?
+                .as_float_slider()?
+                .value() as f32,
+            focal_depth: params.get(Params::FocalDepth)?.as_float_slider()?.value() as f32,
+            invert_depth: params.get(Params::InvertDepth)?.as_checkbox()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let (image, coc) = prepare(&input, depth, &settings);
+        let aperture = Aperture::new(&settings);
+
+        #[cfg(feature = "gpu_wgpu")]
+        if let Some(ctx) = wgpu_context() {
+            let gpu_params = RenderParams {
+                width: image.width as u32,
+                height: image.height as u32,
+                blades: aperture.blades,
+                reach: reach(&settings),
+                rotation: aperture.rotation,
+                curvature: aperture.curvature,
+                _pad: [0.0; 2],
+            };
+            let coc_flat: Vec<f32> = coc.iter().flatten().copied().collect();
+            if let Ok(rgba) = ctx.render(&gpu_params, &image.to_rgba(), &coc_flat) {
+                return ImageBuf::from_rgba(image.width, image.height, &rgba)
+                    .write_to_layer(&mut out_layer);
+            }
+            // Fall through to the CPU path when the GPU dispatch fails.
+        }
+
+        let progress_final = out_layer.height() as i32;
+        let out_world_type = out_layer.world_type();
+        let reach = reach(&settings) as isize;
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let (x, y) = (x as usize, y as usize);
+            let px = if x < image.width && y < image.height {
+                bokeh_pixel(&image, &coc, &aperture, reach, x, y)
+            } else {
+                utils::image::TRANSPARENT
+            };
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BokehParams {
+    /// Circle of confusion at full defocus in pixels (already scaled for downsampling).
+    pub radius: f32,
+    pub blades: u32,
+    /// Aperture rotation in radians.
+    pub rotation: f32,
+    /// 0 = straight blades, 1 = circular aperture.
+    pub curvature: f32,
+    pub highlight_threshold: f32,
+    pub highlight_gain: f32,
+    pub focal_depth: f32,
+    pub invert_depth: bool,
+}
+
+/// Gather radius in pixels: the largest circle of confusion plus the
+/// anti-aliased edge.
+fn reach(p: &BokehParams) -> u32 {
+    let r = if p.radius.is_nan() { 0.0 } else { p.radius };
+    r.clamp(0.0, MAX_RADIUS).ceil() as u32 + 1
+}
+
+/// Aperture polygon with optionally rounded blades.
+struct Aperture {
+    blades: u32,
+    rotation: f32,
+    curvature: f32,
+    /// Area of the unit-radius aperture.
+    area: f32,
+}
+
+impl Aperture {
+    fn new(p: &BokehParams) -> Self {
+        use std::f32::consts::{PI, TAU};
+
+        let blades = p.blades.clamp(MIN_BLADES, MAX_BLADES);
+        let rotation = if p.rotation.is_finite() {
+            p.rotation.rem_euclid(TAU)
+        } else {
+            0.0
+        };
+        let curvature = if p.curvature.is_nan() {
+            0.0
+        } else {
+            p.curvature.clamp(0.0, 1.0)
+        };
+        let n = blades as f32;
+        let polygon = 0.5 * n * (TAU / n).sin();
+        Self {
+            blades,
+            rotation,
+            curvature,
+            area: polygon + (PI - polygon) * curvature,
+        }
+    }
+
+    /// Boundary distance of the unit aperture in direction `theta`.
+    fn radius_at(&self, theta: f32) -> f32 {
+        let seg = std::f32::consts::TAU / self.blades as f32;
+        let t = (theta - self.rotation).rem_euclid(seg) - seg * 0.5;
+        let polygon = (seg * 0.5).cos() / t.cos();
+        polygon + (1.0 - polygon) * self.curvature
+    }
+
+    /// Anti-aliased coverage of offset (`dx`, `dy`) by an aperture of radius `r`.
+    fn coverage(&self, dx: f32, dy: f32, r: f32) -> f32 {
+        if dx == 0.0 && dy == 0.0 {
+            return 1.0;
+        }
+        let d = dx.hypot(dy);
+        if d > r + 0.5 {
+            return 0.0;
+        }
+        (r * self.radius_at(dy.atan2(dx)) - d + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+/// Premultiplied, highlight-boosted copy of `input` and per-pixel
+/// `[circle of confusion, depth]`.
+fn prepare(
+    input: &ImageBuf,
+    depth: Option<&ImageBuf>,
+    p: &BokehParams,
+) -> (ImageBuf, Vec<[f32; 2]>) {
+    let finite = |v: f32, fallback: f32| if v.is_finite() { v } else { fallback };
+    let threshold = finite(p.highlight_threshold, 1.0).clamp(0.0, 1.0);
+    let gain = finite(p.highlight_gain, 0.0).max(0.0);
+    let radius = finite(p.radius, 0.0).clamp(0.0, MAX_RADIUS);
+    let focal = finite(p.focal_depth, 0.0).clamp(0.0, 1.0);
+
+    let mut boosted = ImageBuf::new(input.width, input.height);
+    for y in 0..input.height {
+        for x in 0..input.width {
+            let px = input.get(x as isize, y as isize);
+            let lum = luminance(px);
+            // しきい値を超えた分に応じて明るくする（HDR でも倍率は 1 + Gain まで）
+            let k = if lum > threshold {
+                1.0 + gain * ((lum - threshold) / (1.0 - threshold).max(1e-3)).min(1.0)
+            } else {
+                1.0
+            };
+            boosted.set(
+                x,
+                y,
+                PixelF32 {
+                    red: px.red * k,
+                    green: px.green * k,
+                    blue: px.blue * k,
+                    alpha: px.alpha,
+                },
+            );
+        }
+    }
+
+    let mut coc = Vec::with_capacity(input.width * input.height);
+    for y in 0..input.height {
+        for x in 0..input.width {
+            coc.push(match depth {
+                // 深度レイヤーのサイズが異なる場合は引き伸ばして対応させる
+                Some(map) if !map.is_empty() => {
+                    let mx = x * map.width / input.width.max(1);
+                    let my = y * map.height / input.height.max(1);
+                    let d = luminance(map.get_clamped(mx as isize, my as isize));
+                    let d = if d.is_nan() { 0.0 } else { d.clamp(0.0, 1.0) };
+                    let d = if p.invert_depth { 1.0 - d } else { d };
+                    [radius * (d - focal).abs(), d]
+                }
+                _ => [radius, 0.0],
+            });
+        }
+    }
+
+    (sampling::premultiply(&boosted), coc)
+}
+
+/// Scatter-as-gather bokeh at one pixel: every neighbour spreads its color over
+/// its own aperture, normalized by the aperture area, and the contributions
+/// covering (`x`, `y`) are averaged.
+fn bokeh_pixel(
+    image: &ImageBuf,
+    coc: &[[f32; 2]],
+    aperture: &Aperture,
+    reach: isize,
+    x: usize,
+    y: usize,
+) -> PixelF32 {
+    let [coc_p, depth_p] = coc[image.index(x, y)];
+    let mut acc = [0.0f32; 4];
+    let mut total = 0.0f32;
+
+    for dy in -reach..=reach {
+        let qy = y as isize + dy;
+        if qy < 0 || qy >= image.height as isize {
+            continue;
+        }
+        for dx in -reach..=reach {
+            let qx = x as isize + dx;
+            if qx < 0 || qx >= image.width as isize {
+                continue;
+            }
+            let i = image.index(qx as usize, qy as usize);
+            let [coc_q, depth_q] = coc[i];
+            // 奥の画素は手前の画素の上に、その画素自身のボケ以上には広がらない
+            let r = if depth_q > depth_p {
+                coc_q.min(coc_p)
+            } else {
+                coc_q
+            };
+            let cover = aperture.coverage(dx as f32, dy as f32, r);
+            if cover <= 0.0 {
+                continue;
+            }
+            let w = cover / (aperture.area * (r + 0.5) * (r + 0.5));
+            let px = image.data[i];
+            acc[0] += px.red * w;
+            acc[1] += px.green * w;
+            acc[2] += px.blue * w;
+            acc[3] += px.alpha * w;
+            total += w;
+        }
+    }
+
+    let out = if total > 0.0 {
+        acc.map(|v| v / total)
+    } else {
+        [0.0; 4]
+    };
+    sampling::unpremultiply(PixelF32 {
+        red: out[0],
+        green: out[1],
+        blue: out[2],
+        alpha: out[3],
+    })
+}
+
+/// Host-independent render core (see `utils::harness`), CPU path.
+///
+/// The harness has no second layer, so the input's own luminance is used as
+/// the depth map on odd frames.
+pub struct BokehBlur;
+
+impl RenderCore for BokehBlur {
+    type Params = BokehParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        BokehParams {
+            radius: rng.slider(0.0, MAX_RADIUS),
+            blades: rng.popup(MIN_BLADES as i32, MAX_BLADES as i32) as u32,
+            rotation: rng.slider(-3600.0, 3600.0).to_radians(),
+            curvature: rng.slider(0.0, 1.0),
+            highlight_threshold: rng.slider(0.0, 1.0),
+            highlight_gain: rng.slider(0.0, 100.0),
+            focal_depth: rng.slider(0.0, 1.0),
+            invert_depth: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let depth = (frame % 2 == 1).then_some(input);
+        let (image, coc) = prepare(input, depth, params);
+        let aperture = Aperture::new(params);
+        let reach = reach(params) as isize;
+        for y in 0..input.height {
+            for x in 0..input.width {
+                output.set(x, y, bokeh_pixel(&image, &coc, &aperture, reach, x, y));
+            }
+        }
+    }
+}
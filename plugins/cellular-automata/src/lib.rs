@@ -0,0 +1,789 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::history::{History, HistoryKey};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, Edge};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Rule,
+    Radius,
+    TimeStep,
+    GrowthCenter,
+    GrowthWidth,
+    StepsPerFrame,
+    CellSize,
+    SeedThreshold,
+    WrapEdges,
+    Output,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: automaton states of recently rendered frames.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History<Grid>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Runs cellular automata seeded from the layer.";
+
+/// Upper bound of `Steps Per Frame`.
+const MAX_STEPS_PER_FRAME: i32 = 100;
+/// Upper bound of `Cell Size` in full-resolution pixels.
+const MAX_CELL_SIZE: f32 = 32.0;
+/// Upper bound of the continuous-rule kernel radius in cells.
+const MAX_RADIUS: i32 = 16;
+/// Cell × kernel-tap updates spent at most when a frame is rendered without its
+/// predecessor (scrubbing, multi-frame rendering); longer runs are cut short.
+const CATCH_UP_BUDGET: u64 = 1_000_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// B3/S23
+    Life,
+    /// B36/S23
+    HighLife,
+    /// B2/S
+    Seeds,
+    /// B3678/S34678
+    DayAndNight,
+    /// B3/S12345
+    Maze,
+    /// Smooth ring kernel with a Gaussian growth function.
+    Lenia,
+    /// Disk / annulus fillings with Rafler's smooth birth and death intervals.
+    SmoothLife,
+}
+
+impl Rule {
+    /// Birth and survival neighbour counts as bit masks, `None` for continuous rules.
+    fn life_like(self) -> Option<(u16, u16)> {
+        let mask = |counts: &[u16]| counts.iter().fold(0u16, |m, c| m | (1 << c));
+        Some(match self {
+            Rule::Life => (mask(&[3]), mask(&[2, 3])),
+            Rule::HighLife => (mask(&[3, 6]), mask(&[2, 3])),
+            Rule::Seeds => (mask(&[2]), 0),
+            Rule::DayAndNight => (mask(&[3, 6, 7, 8]), mask(&[3, 4, 6, 7, 8])),
+            Rule::Maze => (mask(&[3]), mask(&[1, 2, 3, 4, 5])),
+            Rule::Lenia | Rule::SmoothLife => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Cell states as white on black.
+    Cells,
+    /// The input with its alpha multiplied by the cell states.
+    InputMatte,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Rule: continuous rules show their controls (see update_rule_visibility)
+        params.add_with_flags(
+            Params::Rule,
+            tr("Rule"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Life"),
+                    tr("HighLife"),
+                    tr("Seeds"),
+                    tr("Day & Night"),
+                    tr("Maze"),
+                    tr("Lenia"),
+                    tr("SmoothLife"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Radius: neighbourhood radius of the continuous rules in cells
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(2);
+                d.set_slider_max(MAX_RADIUS);
+                d.set_default(8);
+            }),
+        )?;
+
+        // Time Step: fraction of the growth applied per step (continuous rules)
+        params.add(
+            Params::TimeStep,
+            tr("Time Step"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.01);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.01);
+                d.set_slider_max(1.0);
+                d.set_default(0.1);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Growth Center / Width: neighbourhood density Lenia grows best at, and its tolerance
+        params.add(
+            Params::GrowthCenter,
+            tr("Growth Center"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.15);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::GrowthWidth,
+            tr("Growth Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.001);
+                d.set_valid_max(0.5);
+                d.set_slider_min(0.001);
+                d.set_slider_max(0.1);
+                d.set_default(0.015);
+                d.set_precision(4);
+            }),
+        )?;
+
+        params.add(
+            Params::StepsPerFrame,
+            tr("Steps Per Frame"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_STEPS_PER_FRAME);
+                d.set_slider_min(1);
+                d.set_slider_max(20);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Cell Size: automaton cell in full-resolution pixels
+        params.add(
+            Params::CellSize,
+            tr("Cell Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_CELL_SIZE);
+                d.set_slider_min(1.0);
+                d.set_slider_max(16.0);
+                d.set_default(4.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Seed Threshold: luminance above which the first frame's cells are alive
+        params.add(
+            Params::SeedThreshold,
+            tr("Seed Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::WrapEdges,
+            tr("Wrap Edges"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Cells"), tr("Input Matte")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_CellularAutomata - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn rule(params: &Parameters<Params>) -> Result<Rule, Error> {
+    Ok(match params.get(Params::Rule)?.as_popup()?.value() {
+        2 => Rule::HighLife,
+        3 => Rule::Seeds,
+        4 => Rule::DayAndNight,
+        5 => Rule::Maze,
+        6 => Rule::Lenia,
+        7 => Rule::SmoothLife,
+        _ => Rule::Life,
+    })
+}
+
+fn update_rule_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let rule = rule(params)?;
+    let continuous = rule.life_like().is_none();
+    for (id, visible) in [
+        (Params::Radius, continuous),
+        (Params::TimeStep, continuous),
+        (Params::GrowthCenter, rule == Rule::Lenia),
+        (Params::GrowthWidth, rule == Rule::Lenia),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // オートマトンの状態は再計算できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(plugin.in_data, &input, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup => {
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::UpdateParamsUi => {
+                update_rule_visibility(&plugin.in_data, plugin.params)?;
+            }
+
+            ae::Command::UserChangedParam { param_index } => {
+                // 設定が変わったら以前の状態は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+                if plugin.params.type_at(param_index) == Params::Rule {
+                    update_rule_visibility(&plugin.in_data, plugin.params)?;
+                }
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(mut out_layer)) = (in_layer_opt, out_layer_opt) {
+                    let input = ImageBuf::from_layer(&in_layer);
+                    self.do_render(in_data, &input, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let settings = AutomatonParams {
+            rule: rule(params)?,
+            radius: params.get(Params::Radius)?.as_slider()?.value() as u32,
+            time_step: params.get(Params::TimeStep)?.as_float_slider()?.value() as f32,
+            growth_center: params.get(Params::GrowthCenter)?.as_float_slider()?.value() as f32,
+            growth_width: params.get(Params::GrowthWidth)?.as_float_slider()?.value() as f32,
+            steps_per_frame: params.get(Params::StepsPerFrame)?.as_slider()?.value() as u32,
+            cell_size: ds.length(params.get(Params::CellSize)?.as_float_slider()?.value() as f32),
+            seed_threshold: params
+                .get(Params::SeedThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            wrap: params.get(Params::WrapEdges)?.as_checkbox()?.value(),
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::InputMatte,
+                _ => Output::Cells,
+            },
+        };
+
+        let time = in_data.current_time();
+        let time_step = in_data.time_step();
+        let (width, height) = grid_size(input.width, input.height, settings.cell_size);
+        let key = HistoryKey {
+            width,
+            height,
+            downsample: ds,
+            time_step,
+        };
+
+        let grid = {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            // レイヤーの先頭フレームでは常にリセットする
+            let previous = if time <= 0 {
+                None
+            } else {
+                history.previous(key, time).cloned()
+            };
+            let grid = match previous {
+                Some(previous) => advance(previous, &settings, steps_per_frame(&settings)),
+                None => {
+                    let frame = if time_step > 0 { time / time_step } else { 0 };
+                    simulate_from_seed(input, &settings, frame.max(0) as u64)
+                }
+            };
+            history.store(key, time, grid.clone());
+            grid
+        };
+
+        shade(&grid, input, &settings).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct AutomatonParams {
+    pub rule: Rule,
+    /// Kernel radius of the continuous rules in cells.
+    pub radius: u32,
+    /// Growth applied per step by the continuous rules.
+    pub time_step: f32,
+    /// Lenia growth function center (μ).
+    pub growth_center: f32,
+    /// Lenia growth function width (σ).
+    pub growth_width: f32,
+    pub steps_per_frame: u32,
+    /// Cell size in render pixels.
+    pub cell_size: f32,
+    /// Luminance above which cells are seeded alive.
+    pub seed_threshold: f32,
+    /// Toroidal neighbourhood instead of dead cells outside the layer.
+    pub wrap: bool,
+    pub output: Output,
+}
+
+/// Automaton grid: one state in [0, 1] per cell, row-major.
+#[derive(Clone, Debug)]
+struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<f32>,
+}
+
+impl Grid {
+    #[inline]
+    fn get(&self, x: isize, y: isize, wrap: bool) -> f32 {
+        let edge = if wrap { Edge::Repeat } else { Edge::None };
+        match (edge.wrap(x, self.width), edge.wrap(y, self.height)) {
+            (Some(x), Some(y)) => self.cells[y * self.width + x],
+            _ => 0.0,
+        }
+    }
+}
+
+fn cell_size(p: &AutomatonParams) -> f32 {
+    finite_or(p.cell_size, 1.0).clamp(1.0, MAX_CELL_SIZE)
+}
+
+fn steps_per_frame(p: &AutomatonParams) -> u64 {
+    p.steps_per_frame.clamp(1, MAX_STEPS_PER_FRAME as u32) as u64
+}
+
+/// Grid dimensions covering a `width`×`height` image with `cell`-pixel cells.
+fn grid_size(width: usize, height: usize, cell: f32) -> (usize, usize) {
+    let cell = finite_or(cell, 1.0).clamp(1.0, MAX_CELL_SIZE);
+    let n = |v: usize| ((v as f32 / cell).ceil() as usize).max(1);
+    (n(width), n(height))
+}
+
+/// Initial state from the input: alive above the threshold, continuous rules
+/// keep the luminance as the state.
+fn seed(input: &ImageBuf, p: &AutomatonParams) -> Grid {
+    let cell = cell_size(p);
+    let (width, height) = grid_size(input.width, input.height, cell);
+    let threshold = finite_or(p.seed_threshold, 0.5);
+    let binary = p.rule.life_like().is_some();
+    let mut cells = vec![0.0; width * height];
+    if !input.is_empty() {
+        for (i, c) in cells.iter_mut().enumerate() {
+            // セル中心のピクセルで判定する
+            let x = (((i % width) as f32 + 0.5) * cell) as isize;
+            let y = (((i / width) as f32 + 0.5) * cell) as isize;
+            let px = input.get_clamped(x, y);
+            let alpha = if px.alpha.is_finite() {
+                px.alpha.clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let lum = luminance(px);
+            if lum > threshold {
+                *c = if binary {
+                    (alpha >= 0.5) as u8 as f32
+                } else {
+                    (lum * alpha).clamp(0.0, 1.0)
+                };
+            }
+        }
+    }
+    Grid {
+        width,
+        height,
+        cells,
+    }
+}
+
+/// Weighted neighbourhood of a continuous rule: (dx, dy, weights).
+/// Lenia uses one ring kernel, SmoothLife an inner disk and an outer annulus;
+/// each set of weights sums to 1.
+struct Kernel {
+    taps: Vec<(isize, isize, [f32; 2])>,
+}
+
+impl Kernel {
+    fn new(rule: Rule, radius: u32) -> Self {
+        let r = radius.clamp(2, MAX_RADIUS as u32) as f32;
+        let ri = r / 3.0;
+        let n = r.ceil() as isize;
+        let mut taps = Vec::new();
+        for dy in -n..=n {
+            for dx in -n..=n {
+                let d = ((dx * dx + dy * dy) as f32).sqrt();
+                let w = match rule {
+                    Rule::Lenia => {
+                        // exp(4 - 1 / (ρ (1 - ρ))) のリング
+                        let rho = d / r;
+                        if rho > 0.0 && rho < 1.0 {
+                            [(4.0 - 1.0 / (rho * (1.0 - rho))).exp(), 0.0]
+                        } else {
+                            [0.0, 0.0]
+                        }
+                    }
+                    _ => {
+                        // 境界は 1 セル幅でアンチエイリアスする
+                        let inner = (ri + 0.5 - d).clamp(0.0, 1.0);
+                        let outer = (r + 0.5 - d).clamp(0.0, 1.0) - inner;
+                        [inner, outer.max(0.0)]
+                    }
+                };
+                if w[0] > 0.0 || w[1] > 0.0 {
+                    taps.push((dx, dy, w));
+                }
+            }
+        }
+        for k in 0..2 {
+            let total: f32 = taps.iter().map(|(_, _, w)| w[k]).sum();
+            if total > 0.0 {
+                taps.iter_mut().for_each(|(_, _, w)| w[k] /= total);
+            }
+        }
+        Self { taps }
+    }
+}
+
+fn step_life_like(grid: &Grid, (birth, survive): (u16, u16), wrap: bool) -> Grid {
+    let mut cells = Vec::with_capacity(grid.cells.len());
+    for y in 0..grid.height as isize {
+        for x in 0..grid.width as isize {
+            let mut count = 0u16;
+            for (dx, dy) in [
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ] {
+                count += (grid.get(x + dx, y + dy, wrap) >= 0.5) as u16;
+            }
+            let mask = if grid.get(x, y, wrap) >= 0.5 {
+                survive
+            } else {
+                birth
+            };
+            cells.push(((mask >> count) & 1) as f32);
+        }
+    }
+    Grid {
+        width: grid.width,
+        height: grid.height,
+        cells,
+    }
+}
+
+/// Logistic step of SmoothLife with width `alpha`.
+fn sigmoid(x: f32, a: f32, alpha: f32) -> f32 {
+    1.0 / (1.0 + (-(x - a) * 4.0 / alpha).exp())
+}
+
+/// SmoothLife transition of annulus filling `n` and disk filling `m`.
+fn smooth_life(n: f32, m: f32) -> f32 {
+    const B1: f32 = 0.278;
+    const B2: f32 = 0.365;
+    const D1: f32 = 0.267;
+    const D2: f32 = 0.445;
+    const ALPHA_N: f32 = 0.028;
+    const ALPHA_M: f32 = 0.147;
+    let alive = sigmoid(m, 0.5, ALPHA_M);
+    let lo = B1 * (1.0 - alive) + D1 * alive;
+    let hi = B2 * (1.0 - alive) + D2 * alive;
+    sigmoid(n, lo, ALPHA_N) * (1.0 - sigmoid(n, hi, ALPHA_N))
+}
+
+fn step_continuous(grid: &Grid, kernel: &Kernel, p: &AutomatonParams) -> Grid {
+    let dt = finite_or(p.time_step, 0.1).clamp(0.01, 1.0);
+    let mu = finite_or(p.growth_center, 0.15).clamp(0.0, 1.0);
+    let sigma = finite_or(p.growth_width, 0.015).clamp(0.001, 0.5);
+    let mut cells = Vec::with_capacity(grid.cells.len());
+    for y in 0..grid.height as isize {
+        for x in 0..grid.width as isize {
+            let mut u = [0.0f32; 2];
+            for &(dx, dy, w) in &kernel.taps {
+                let v = grid.get(x + dx, y + dy, p.wrap);
+                u[0] += v * w[0];
+                u[1] += v * w[1];
+            }
+            let growth = match p.rule {
+                Rule::Lenia => {
+                    let z = (u[0] - mu) / sigma;
+                    2.0 * (-0.5 * z * z).exp() - 1.0
+                }
+                _ => 2.0 * smooth_life(u[1], u[0]) - 1.0,
+            };
+            let v = grid.get(x, y, p.wrap) + dt * growth;
+            cells.push(v.clamp(0.0, 1.0));
+        }
+    }
+    Grid {
+        width: grid.width,
+        height: grid.height,
+        cells,
+    }
+}
+
+/// Advances `grid` by `steps` steps.
+fn advance(grid: Grid, p: &AutomatonParams, steps: u64) -> Grid {
+    let mut grid = grid;
+    match p.rule.life_like() {
+        Some(rule) => {
+            for _ in 0..steps {
+                grid = step_life_like(&grid, rule, p.wrap);
+            }
+        }
+        None => {
+            let kernel = Kernel::new(p.rule, p.radius);
+            for _ in 0..steps {
+                grid = step_continuous(&grid, &kernel, p);
+            }
+        }
+    }
+    grid
+}
+
+/// State of frame `frame` (0 = first) grown from the current input, cut short
+/// at `CATCH_UP_BUDGET`.
+fn simulate_from_seed(input: &ImageBuf, p: &AutomatonParams, frame: u64) -> Grid {
+    let grid = seed(input, p);
+    let taps = match p.rule.life_like() {
+        Some(_) => 8,
+        None => Kernel::new(p.rule, p.radius).taps.len() as u64,
+    };
+    let cost = (grid.cells.len() as u64).saturating_mul(taps).max(1);
+    let steps = steps_per_frame(p)
+        .saturating_mul(frame.saturating_add(1))
+        .min((CATCH_UP_BUDGET / cost).max(1));
+    advance(grid, p, steps)
+}
+
+/// State of `grid` at render pixel (`x`, `y`): nearest cell for Life-like rules
+/// (crisp cells), bilinear between cells for continuous ones.
+fn cell_value(grid: &Grid, x: usize, y: usize, cell: f32, p: &AutomatonParams) -> f32 {
+    let gx = (x as f32 + 0.5) / cell;
+    let gy = (y as f32 + 0.5) / cell;
+    if p.rule.life_like().is_some() {
+        return grid.get(gx as isize, gy as isize, p.wrap);
+    }
+    let (gx, gy) = (gx - 0.5, gy - 0.5);
+    let (x0, y0) = (gx.floor(), gy.floor());
+    let (tx, ty) = (gx - x0, gy - y0);
+    let (x0, y0) = (x0 as isize, y0 as isize);
+    // 境界外は端のセルを使い、縁が暗くならないようにする
+    let value = |x: isize, y: isize| {
+        let x = x.clamp(0, grid.width as isize - 1);
+        let y = y.clamp(0, grid.height as isize - 1);
+        grid.get(x, y, p.wrap)
+    };
+    let top = value(x0, y0) * (1.0 - tx) + value(x0 + 1, y0) * tx;
+    let bottom = value(x0, y0 + 1) * (1.0 - tx) + value(x0 + 1, y0 + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+fn shade(grid: &Grid, input: &ImageBuf, p: &AutomatonParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if grid.cells.is_empty() {
+        return output;
+    }
+    let cell = cell_size(p);
+
+    for y in 0..height {
+        for x in 0..width {
+            let v = cell_value(grid, x, y, cell, p);
+            let px = match p.output {
+                Output::Cells => PixelF32 {
+                    red: v,
+                    green: v,
+                    blue: v,
+                    alpha: 1.0,
+                },
+                Output::InputMatte => {
+                    let px = input.get(x as isize, y as isize);
+                    PixelF32 {
+                        alpha: px.alpha * v,
+                        ..px
+                    }
+                }
+            };
+            output.set(x, y, px);
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no sequence data, so every frame is grown from the input
+/// over `frame % 4 + 1` frames' worth of steps.
+pub struct CellularAutomata;
+
+impl RenderCore for CellularAutomata {
+    type Params = AutomatonParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        AutomatonParams {
+            rule: match rng.popup(1, 7) {
+                2 => Rule::HighLife,
+                3 => Rule::Seeds,
+                4 => Rule::DayAndNight,
+                5 => Rule::Maze,
+                6 => Rule::Lenia,
+                7 => Rule::SmoothLife,
+                _ => Rule::Life,
+            },
+            radius: rng.int(2, MAX_RADIUS) as u32,
+            time_step: rng.slider(0.01, 1.0),
+            growth_center: rng.slider(0.0, 1.0),
+            growth_width: rng.slider(0.001, 0.5),
+            steps_per_frame: rng.int(1, 4) as u32,
+            cell_size: rng.slider(1.0, MAX_CELL_SIZE),
+            seed_threshold: rng.slider(0.0, 1.0),
+            wrap: rng.chance(0.5),
+            output: if rng.chance(0.5) {
+                Output::Cells
+            } else {
+                Output::InputMatte
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let grid = simulate_from_seed(input, params, (frame % 4) as u64);
+        *output = shade(&grid, input, params);
+    }
+}
@@ -0,0 +1,104 @@
+// Keep in sync with `RenderParams` in ../mod.rs and `Keyer` in lib.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    space: u32,
+    spill_in_alpha: u32,
+    key: vec2<f32>,
+    tint: vec2<f32>,
+    key_scale: f32,
+    tolerance: f32,
+    softness: f32,
+    despill: f32,
+};
+
+@group(0) @binding(0) var<storage, read> input: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+const SPACE_YCBCR: u32 = 0u;
+
+fn finite_or(v: f32, fallback: f32) -> f32 {
+    // NaN / Inf は既定値に置き換える
+    if (v != v || abs(v) > 3.4e38) {
+        return fallback;
+    }
+    return v;
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if (c <= 0.04045) {
+        return c / 12.92;
+    }
+    return pow((c + 0.055) / 1.055, 2.4);
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if (c <= 0.0031308) {
+        return c * 12.92;
+    }
+    return 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+}
+
+fn cbrt(v: f32) -> f32 {
+    return sign(v) * pow(abs(v), 1.0 / 3.0);
+}
+
+// (luma, chroma 1, chroma 2) of gamma-encoded RGB.
+fn to_space(rgb: vec3<f32>) -> vec3<f32> {
+    if (params.space == SPACE_YCBCR) {
+        let y = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        return vec3<f32>(y, (rgb.b - y) / 1.8556, (rgb.r - y) / 1.5748);
+    }
+    let lin = vec3<f32>(srgb_to_linear(rgb.r), srgb_to_linear(rgb.g), srgb_to_linear(rgb.b));
+    let l = cbrt(0.41222146 * lin.r + 0.53633255 * lin.g + 0.051445995 * lin.b);
+    let m = cbrt(0.2119035 * lin.r + 0.6806995 * lin.g + 0.10739696 * lin.b);
+    let s = cbrt(0.08830246 * lin.r + 0.28171885 * lin.g + 0.6299787 * lin.b);
+    return vec3<f32>(
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    );
+}
+
+fn from_space(v: vec3<f32>) -> vec3<f32> {
+    if (params.space == SPACE_YCBCR) {
+        let r = v.x + 1.5748 * v.z;
+        let b = v.x + 1.8556 * v.y;
+        return vec3<f32>(r, (v.x - 0.2126 * r - 0.0722 * b) / 0.7152, b);
+    }
+    let l0 = v.x + 0.39633778 * v.y + 0.21580376 * v.z;
+    let m0 = v.x - 0.105561346 * v.y - 0.06385417 * v.z;
+    let s0 = v.x - 0.08948418 * v.y - 1.2914855 * v.z;
+    let l = l0 * l0 * l0;
+    let m = m0 * m0 * m0;
+    let s = s0 * s0 * s0;
+    return vec3<f32>(
+        linear_to_srgb(4.0767417 * l - 3.3077116 * m + 0.23096994 * s),
+        linear_to_srgb(-1.268438 * l + 2.6097574 * m - 0.34131938 * s),
+        linear_to_srgb(-0.0041960863 * l - 0.7034186 * m + 1.7076147 * s),
+    );
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+    let px = input[i];
+    let rgb = vec3<f32>(finite_or(px.r, 0.0), finite_or(px.g, 0.0), finite_or(px.b, 0.0));
+
+    let c = to_space(rgb);
+    let chroma = c.yz;
+    let d = length(chroma - params.key) * params.key_scale;
+    var matte = select(0.0, 1.0, d > params.tolerance);
+    if (params.softness > 0.0) {
+        matte = clamp((d - params.tolerance) / params.softness, 0.0, 1.0);
+    }
+    // スクリーン色の成分をティントの色味に置き換える
+    let spill = clamp(dot(chroma, params.key) * params.key_scale * params.key_scale, 0.0, 1.0);
+    let despilled = chroma + (params.tint - params.key) * (params.despill * spill);
+    let out = from_space(vec3<f32>(c.x, despilled));
+    output[i] = vec4<f32>(out, select(matte, spill, params.spill_in_alpha != 0u));
+}
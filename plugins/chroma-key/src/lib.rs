@@ -0,0 +1,584 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    ScreenColor,
+    ColorSpace,
+    Tolerance,
+    Softness,
+    EdgeErode,
+    EdgeFeather,
+    DespillAmount,
+    DespillTint,
+    Output,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Keys out a screen color by chroma distance.";
+
+/// Upper bound of `Edge Erode` / `Edge Feather` (full-resolution pixels).
+const MAX_EDGE: f32 = 100.0;
+/// Screen chroma below this counts as this much, so a gray screen still keys.
+const MIN_KEY_CHROMA: f32 = 1e-3;
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Rec. 709 CbCr of the encoded values: fast, matches broadcast keyers.
+    YCbCr,
+    /// OKLab a/b: perceptually even, steadier on dark and saturated screens.
+    Oklab,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Despilled layer with the matte in alpha.
+    Composite,
+    Matte,
+    /// How much of the screen color every pixel carries.
+    SpillMatte,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::ScreenColor,
+            tr("Screen Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 177,
+                    blue: 64,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Color Space: where the chroma distance to the screen color is measured
+        params.add(
+            Params::ColorSpace,
+            tr("Color Space"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("YCbCr"), tr("OKLab")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Tolerance / Softness: chroma distance relative to the screen chroma; fully keyed
+        // below Tolerance, opaque beyond Tolerance + Softness
+        params.add(
+            Params::Tolerance,
+            tr("Tolerance"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(2.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.35);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Softness,
+            tr("Softness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(2.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.25);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Edge Erode / Edge Feather: shrink and blur the matte, in pixels
+        params.add(
+            Params::EdgeErode,
+            tr("Edge Erode"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_EDGE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::EdgeFeather,
+            tr("Edge Feather"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_EDGE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::DespillAmount,
+            tr("Despill Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Despill Tint: the screen color cast is replaced by the hue of this color (gray removes it)
+        params.add(
+            Params::DespillTint,
+            tr("Despill Tint"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 128,
+                    green: 128,
+                    blue: 128,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Composite"), tr("Matte"), tr("Spill Matte")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ChromaKey - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // エッジ幅はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let screen = params.get(Params::ScreenColor)?.as_color()?.float_value()?;
+        let tint = params.get(Params::DespillTint)?.as_color()?.float_value()?;
+
+        let settings = ChromaKeyParams {
+            screen_color: [screen.red, screen.green, screen.blue],
+            space: match params.get(Params::ColorSpace)?.as_popup()?.value() {
+                2 => ColorSpace::Oklab,
+                _ => ColorSpace::YCbCr,
+            },
+            tolerance: params.get(Params::Tolerance)?.as_float_slider()?.value() as f32,
+            softness: params.get(Params::Softness)?.as_float_slider()?.value() as f32,
+            erode: ds.length(params.get(Params::EdgeErode)?.as_float_slider()?.value() as f32),
+            feather: ds.length(params.get(Params::EdgeFeather)?.as_float_slider()?.value() as f32),
+            despill: params
+                .get(Params::DespillAmount)?
+                .as_float_slider()?
+                .value() as f32,
+            despill_tint: [tint.red, tint.green, tint.blue],
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::Matte,
+                3 => Output::SpillMatte,
+                _ => Output::Composite,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        chroma_key(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ChromaKeyParams {
+    /// Encoded screen color.
+    pub screen_color: [f32; 3],
+    pub space: ColorSpace,
+    pub tolerance: f32,
+    pub softness: f32,
+    /// Matte erosion radius in render pixels.
+    pub erode: f32,
+    /// Matte blur radius in render pixels.
+    pub feather: f32,
+    pub despill: f32,
+    /// Encoded despill tint.
+    pub despill_tint: [f32; 3],
+    pub output: Output,
+}
+
+/// `(luma, chroma 1, chroma 2)` of encoded RGB.
+fn to_space(space: ColorSpace, [r, g, b]: [f32; 3]) -> [f32; 3] {
+    match space {
+        ColorSpace::YCbCr => {
+            let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+            [y, (b - y) / 1.8556, (r - y) / 1.5748]
+        }
+        ColorSpace::Oklab => {
+            let lab = Oklab::from_srgb([r, g, b]);
+            [lab.l, lab.a, lab.b]
+        }
+    }
+}
+
+/// Inverse of [`to_space`].
+fn from_space(space: ColorSpace, [y, c1, c2]: [f32; 3]) -> [f32; 3] {
+    match space {
+        ColorSpace::YCbCr => {
+            let r = y + 1.5748 * c2;
+            let b = y + 1.8556 * c1;
+            [r, (y - 0.2126 * r - 0.0722 * b) / 0.7152, b]
+        }
+        ColorSpace::Oklab => Oklab { l: y, a: c1, b: c2 }.to_srgb(),
+    }
+}
+
+/// Per-pixel keying, mirrored by `shaders/compute.wgsl`.
+struct Keyer {
+    space: ColorSpace,
+    key: [f32; 2],
+    tint: [f32; 2],
+    key_scale: f32,
+    tolerance: f32,
+    softness: f32,
+    despill: f32,
+}
+
+impl Keyer {
+    fn new(p: &ChromaKeyParams) -> Self {
+        let chroma = |rgb: [f32; 3]| {
+            let [_, c1, c2] = to_space(p.space, rgb.map(|c| finite_or(c, 0.0)));
+            [c1, c2]
+        };
+        let key = chroma(p.screen_color);
+        Self {
+            space: p.space,
+            key,
+            tint: chroma(p.despill_tint),
+            key_scale: 1.0 / key[0].hypot(key[1]).max(MIN_KEY_CHROMA),
+            tolerance: finite_or(p.tolerance, 0.0).max(0.0),
+            softness: finite_or(p.softness, 0.0).max(0.0),
+            despill: finite_or(p.despill, 0.0).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Despilled color, raw matte and spill amount of a pixel.
+    fn apply(&self, px: PixelF32) -> ([f32; 3], f32, f32) {
+        let rgb = [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0));
+        let [y, c1, c2] = to_space(self.space, rgb);
+        let [k1, k2] = self.key;
+        let d = (c1 - k1).hypot(c2 - k2) * self.key_scale;
+        let matte = if self.softness > 0.0 {
+            ((d - self.tolerance) / self.softness).clamp(0.0, 1.0)
+        } else if d > self.tolerance {
+            1.0
+        } else {
+            0.0
+        };
+        // スクリーン色の成分をティントの色味に置き換える
+        let spill = ((c1 * k1 + c2 * k2) * self.key_scale * self.key_scale).clamp(0.0, 1.0);
+        let shift = self.despill * spill;
+        let despilled = [
+            y,
+            c1 + (self.tint[0] - k1) * shift,
+            c2 + (self.tint[1] - k2) * shift,
+        ];
+        (from_space(self.space, despilled), matte, spill)
+    }
+}
+
+/// Despilled color of every pixel with the raw matte in alpha (the spill amount
+/// for the Spill Matte output); uses the GPU when available.
+fn key(input: &ImageBuf, p: &ChromaKeyParams) -> ImageBuf {
+    let keyer = Keyer::new(p);
+    let spill_in_alpha = p.output == Output::SpillMatte;
+
+    #[cfg(feature = "gpu_wgpu")]
+    if let Some(ctx) = wgpu_context() {
+        let gpu_params = RenderParams {
+            width: input.width as u32,
+            height: input.height as u32,
+            space: match keyer.space {
+                ColorSpace::YCbCr => 0,
+                ColorSpace::Oklab => 1,
+            },
+            spill_in_alpha: spill_in_alpha as u32,
+            key: keyer.key,
+            tint: keyer.tint,
+            key_scale: keyer.key_scale,
+            tolerance: keyer.tolerance,
+            softness: keyer.softness,
+            despill: keyer.despill,
+        };
+        if let Ok(rgba) = ctx.render(&gpu_params, &input.to_rgba()) {
+            return ImageBuf::from_rgba(input.width, input.height, &rgba);
+        }
+        // Fall through to the CPU path when the GPU dispatch fails.
+    }
+
+    let mut out = ImageBuf::new(input.width, input.height);
+    for (o, &px) in out.data.iter_mut().zip(&input.data) {
+        let ([red, green, blue], matte, spill) = keyer.apply(px);
+        *o = PixelF32 {
+            red,
+            green,
+            blue,
+            alpha: if spill_in_alpha { spill } else { matte },
+        };
+    }
+    out
+}
+
+/// Separable minimum over a `(2r + 1)²` square (edges clamped).
+fn erode(plane: &[f32], width: usize, height: usize, r: usize) -> Vec<f32> {
+    if r == 0 {
+        return plane.to_vec();
+    }
+    let pass = |src: &[f32], horizontal: bool| {
+        let mut out = vec![0.0; src.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let (pos, len) = if horizontal { (x, width) } else { (y, height) };
+                let range = pos.saturating_sub(r)..(pos + r + 1).min(len);
+                out[y * width + x] = range
+                    .map(|k| {
+                        if horizontal {
+                            src[y * width + k]
+                        } else {
+                            src[k * width + x]
+                        }
+                    })
+                    .fold(f32::INFINITY, f32::min);
+            }
+        }
+        out
+    };
+    pass(&pass(plane, true), false)
+}
+
+/// Separable Gaussian blur (σ = radius / 2) of a scalar plane (edges clamped).
+fn feather(plane: &[f32], width: usize, height: usize, radius: f32) -> Vec<f32> {
+    if radius <= 0.0 {
+        return plane.to_vec();
+    }
+    let r = radius.ceil() as isize;
+    let sigma = (radius * 0.5).max(0.5);
+    let kernel: Vec<f32> = (-r..=r)
+        .map(|k| (-((k * k) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    let pass = |src: &[f32], horizontal: bool| {
+        let mut out = vec![0.0; src.len()];
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let mut acc = 0.0;
+                for (k, &wt) in (-r..=r).zip(&kernel) {
+                    let (sx, sy) = if horizontal { (x + k, y) } else { (x, y + k) };
+                    let sx = sx.clamp(0, width as isize - 1) as usize;
+                    let sy = sy.clamp(0, height as isize - 1) as usize;
+                    acc += src[sy * width + sx] * wt;
+                }
+                out[y as usize * width + x as usize] = acc / total;
+            }
+        }
+        out
+    };
+    pass(&pass(plane, true), false)
+}
+
+fn chroma_key(input: &ImageBuf, p: &ChromaKeyParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+    let keyed = key(input, p);
+    if p.output == Output::SpillMatte {
+        for (o, k) in out.data.iter_mut().zip(&keyed.data) {
+            let s = finite_or(k.alpha, 0.0);
+            *o = PixelF32 {
+                red: s,
+                green: s,
+                blue: s,
+                alpha: 1.0,
+            };
+        }
+        return out;
+    }
+
+    let matte: Vec<f32> = keyed.data.iter().map(|k| finite_or(k.alpha, 1.0)).collect();
+    let erode_radius = finite_or(p.erode, 0.0).clamp(0.0, MAX_EDGE).round() as usize;
+    let matte = erode(&matte, width, height, erode_radius);
+    let matte = feather(
+        &matte,
+        width,
+        height,
+        finite_or(p.feather, 0.0).clamp(0.0, MAX_EDGE),
+    );
+
+    for (((o, &px), k), &m) in out
+        .data
+        .iter_mut()
+        .zip(&input.data)
+        .zip(&keyed.data)
+        .zip(&matte)
+    {
+        let alpha = finite_or(px.alpha, 0.0) * m;
+        *o = match p.output {
+            Output::Matte => PixelF32 {
+                red: alpha,
+                green: alpha,
+                blue: alpha,
+                alpha: 1.0,
+            },
+            _ => PixelF32 { alpha, ..*k },
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ChromaKey;
+
+impl RenderCore for ChromaKey {
+    type Params = ChromaKeyParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ChromaKeyParams {
+            screen_color: [rng.unit(), rng.unit(), rng.unit()],
+            space: if rng.chance(0.5) {
+                ColorSpace::YCbCr
+            } else {
+                ColorSpace::Oklab
+            },
+            tolerance: rng.slider(0.0, 2.0),
+            softness: rng.slider(0.0, 2.0),
+            erode: rng.slider(0.0, MAX_EDGE),
+            feather: rng.slider(0.0, MAX_EDGE),
+            despill: rng.slider(0.0, 1.0),
+            despill_tint: [rng.unit(), rng.unit(), rng.unit()],
+            output: match rng.popup(1, 3) {
+                2 => Output::Matte,
+                3 => Output::SpillMatte,
+                _ => Output::Composite,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = chroma_key(input, params);
+    }
+}
@@ -0,0 +1,405 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Filter};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Mode,
+    Center,
+    Angle,
+    RedShift,
+    GreenShift,
+    BlueShift,
+    SpectralSamples,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Adds radial or transverse chromatic aberration.";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Mode {
+    /// Channels are scaled about the center (shift in percent).
+    Radial,
+    /// Channels are offset along an angle (shift in pixels).
+    Transverse,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Mode: Radial uses Center, Transverse uses Angle (see update_mode_visibility)
+        params.add_with_flags(
+            Params::Mode,
+            tr("Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Radial"), tr("Transverse")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        params.add(
+            Params::Angle,
+            tr("Angle"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Shift: percent of the distance to Center (Radial) or pixels (Transverse)
+        params.add(
+            Params::RedShift,
+            tr("Red Shift"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(-10.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::GreenShift,
+            tr("Green Shift"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(-10.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::BlueShift,
+            tr("Blue Shift"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(-10.0);
+                d.set_slider_max(10.0);
+                d.set_default(-1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Spectral Samples: 3 = one sample per channel, more = smooth rainbow fringes
+        params.add(
+            Params::SpectralSamples,
+            tr("Spectral Samples"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(3);
+                d.set_valid_max(32);
+                d.set_slider_min(3);
+                d.set_slider_max(16);
+                d.set_default(7);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ChromaticAberration - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_mode_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Mode =>
+            {
+                update_mode_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_mode_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let radial = params.get(Params::Mode)?.as_popup()?.value() == 1;
+    for (id, visible) in [(Params::Center, radial), (Params::Angle, !radial)] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+        let ds = Downsample::from_in_data(&in_data);
+
+        let mode = match params.get(Params::Mode)?.as_popup()?.value() {
+            2 => Mode::Transverse,
+            _ => Mode::Radial,
+        };
+        let shift = [
+            params.get(Params::RedShift)?.as_float_slider()?.value() as f32,
+            params.get(Params::GreenShift)?.as_float_slider()?.value() as f32,
+            params.get(Params::BlueShift)?.as_float_slider()?.value() as f32,
+        ];
+        let settings = AberrationParams {
+            mode,
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            angle: params.get(Params::Angle)?.as_angle()?.value(),
+            // Radial はパーセントなので縮小率の影響を受けない
+            shift: match mode {
+                Mode::Radial => shift,
+                Mode::Transverse => shift.map(|s| ds.length(s)),
+            },
+            spectral_samples: params.get(Params::SpectralSamples)?.as_slider()?.value() as usize,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                _ => Filter::Lanczos3,
+            },
+        };
+
+        let input = sampling::premultiply(&ImageBuf::from_layer(&in_layer));
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = aberrate_pixel(&input, x as f32, y as f32, &settings);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct AberrationParams {
+    pub mode: Mode,
+    /// Radial center in render pixels.
+    pub center: (f32, f32),
+    /// Transverse direction in degrees.
+    pub angle: f32,
+    /// Per-channel (R, G, B) shift: percent for Radial, render pixels for Transverse.
+    pub shift: [f32; 3],
+    pub spectral_samples: usize,
+    pub filter: Filter,
+}
+
+/// RGB response of spectral position `t` (0 = red end, 1 = blue end); the
+/// responses of evenly spaced samples sum to the same total per channel.
+fn spectral_weight(t: f32) -> [f32; 3] {
+    [
+        (1.0 - 2.0 * t).max(0.0),
+        1.0 - (2.0 * t - 1.0).abs(),
+        (2.0 * t - 1.0).max(0.0),
+    ]
+}
+
+/// Shift at spectral position `t`, interpolated R -> G -> B.
+fn spectral_shift(shift: &[f32; 3], t: f32) -> f32 {
+    if t < 0.5 {
+        shift[0] + (shift[1] - shift[0]) * t * 2.0
+    } else {
+        shift[1] + (shift[2] - shift[1]) * (t * 2.0 - 1.0)
+    }
+}
+
+/// Aberrated straight-alpha pixel at (`x`, `y`) from a premultiplied `input`.
+fn aberrate_pixel(input: &ImageBuf, x: f32, y: f32, p: &AberrationParams) -> PixelF32 {
+    let n = p.spectral_samples.clamp(3, 64);
+    let (dir_x, dir_y) = {
+        let rad = p.angle.to_radians();
+        if rad.is_finite() {
+            (rad.cos(), rad.sin())
+        } else {
+            (1.0, 0.0)
+        }
+    };
+
+    let mut color = [0.0f32; 3];
+    let mut alpha = [0.0f32; 3];
+    let mut total = [0.0f32; 3];
+    for i in 0..n {
+        let t = i as f32 / (n - 1) as f32;
+        let weight = spectral_weight(t);
+        let s = spectral_shift(&p.shift, t);
+        let s = if s.is_finite() { s } else { 0.0 };
+
+        let (sx, sy) = match p.mode {
+            Mode::Radial => {
+                // 拡大率 (1 + s%) で中心から引き伸ばす
+                let scale = (1.0 + s / 100.0).max(0.01);
+                (
+                    p.center.0 + (x - p.center.0) / scale,
+                    p.center.1 + (y - p.center.1) / scale,
+                )
+            }
+            Mode::Transverse => (x - dir_x * s, y - dir_y * s),
+        };
+        let px = sampling::sample(input, sx, sy, p.filter);
+        let rgb = [px.red, px.green, px.blue];
+        for c in 0..3 {
+            color[c] += rgb[c] * weight[c];
+            alpha[c] += px.alpha * weight[c];
+            total[c] += weight[c];
+        }
+    }
+
+    let color: [f32; 3] = std::array::from_fn(|c| color[c] / total[c]);
+    let alpha: [f32; 3] = std::array::from_fn(|c| (alpha[c] / total[c]).clamp(0.0, 1.0));
+    let a = alpha[0].max(alpha[1]).max(alpha[2]);
+    let straight = |v: f32| if a > 0.0 { (v / a).max(0.0) } else { 0.0 };
+
+    PixelF32 {
+        red: straight(color[0]),
+        green: straight(color[1]),
+        blue: straight(color[2]),
+        alpha: a,
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ChromaticAberration;
+
+impl RenderCore for ChromaticAberration {
+    type Params = AberrationParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        AberrationParams {
+            mode: if rng.chance(0.5) {
+                Mode::Radial
+            } else {
+                Mode::Transverse
+            },
+            center: (rng.slider(-100.0, 300.0), rng.slider(-100.0, 300.0)),
+            angle: rng.slider(-360.0, 360.0),
+            shift: [
+                rng.slider(-100.0, 100.0),
+                rng.slider(-100.0, 100.0),
+                rng.slider(-100.0, 100.0),
+            ],
+            spectral_samples: rng.int(3, 32) as usize,
+            filter: if rng.chance(0.5) {
+                Filter::Bilinear
+            } else {
+                Filter::Lanczos3
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let input = sampling::premultiply(input);
+        for y in 0..input.height {
+            for x in 0..input.width {
+                output.set(x, y, aberrate_pixel(&input, x as f32, y as f32, params));
+            }
+        }
+    }
+}
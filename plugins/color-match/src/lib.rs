@@ -0,0 +1,402 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    ReferenceLayer,
+    Method,
+    Strength,
+    PreserveLuminance,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Matches the layer's colors to a reference layer.";
+
+/// Checkout id of the reference layer (the input uses 0).
+const REFERENCE_CHECKOUT_ID: i32 = 1;
+/// Histogram bins between the smallest and largest value of a channel.
+const BINS: usize = 1024;
+/// Smallest standard deviation scaled from, so flat channels stay flat.
+const MIN_DEVIATION: f32 = 1e-4;
+/// Largest deviation ratio, so near-flat channels are not blown up.
+const MAX_SCALE: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Matches the mean and standard deviation of each OKLab channel.
+    MeanDeviation,
+    /// Matches the whole distribution of each OKLab channel.
+    Histogram,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Reference Layer: without one the layer passes through unchanged
+        params.add(
+            Params::ReferenceLayer,
+            tr("Reference Layer"),
+            LayerDef::new(),
+        )?;
+
+        params.add(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Mean / Std Dev"), tr("Histogram")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Preserve Luminance: transfer only the chroma (OKLab a/b)
+        params.add(
+            Params::PreserveLuminance,
+            tr("Preserve Luminance"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ColorMatch - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では参照レイヤーをパラメータから直接取得する
+                let reference = params
+                    .checkout_at(
+                        Params::ReferenceLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_layer, reference.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::ReferenceLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        REFERENCE_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let reference = cb
+                    .checkout_layer_pixels(REFERENCE_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, reference.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(REFERENCE_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        reference: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = ColorMatchParams {
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                2 => Method::Histogram,
+                _ => Method::MeanDeviation,
+            },
+            strength: params.get(Params::Strength)?.as_float_slider()?.value() as f32 / 100.0,
+            preserve_luminance: params
+                .get(Params::PreserveLuminance)?
+                .as_checkbox()?
+                .value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        color_match(&input, reference, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ColorMatchParams {
+    pub method: Method,
+    /// 0..1 blend from the input to the matched colors.
+    pub strength: f32,
+    /// Keeps the input's OKLab lightness.
+    pub preserve_luminance: bool,
+}
+
+fn oklab_channels(px: PixelF32) -> [f32; 3] {
+    let lab = Oklab::from_srgb([px.red, px.green, px.blue].map(|c| finite_or(c, 0.0)));
+    [lab.l, lab.a, lab.b].map(|c| finite_or(c, 0.0))
+}
+
+/// Alpha-weighted distribution of one OKLab channel of an image.
+struct Distribution {
+    min: f32,
+    max: f32,
+    mean: f32,
+    deviation: f32,
+    /// Cumulative histogram at the bin edges (`BINS + 1` entries from 0 to 1).
+    cdf: Vec<f32>,
+}
+
+impl Distribution {
+    /// `None` when every pixel is transparent.
+    fn new(values: &[f32], weights: &[f32]) -> Option<Self> {
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let (mut min, mut max, mut sum) = (f32::INFINITY, f32::NEG_INFINITY, 0.0);
+        for (&v, &w) in values.iter().zip(weights) {
+            if w > 0.0 {
+                min = min.min(v);
+                max = max.max(v);
+                sum += v * w;
+            }
+        }
+        let mean = sum / total;
+        let variance = values
+            .iter()
+            .zip(weights)
+            .map(|(&v, &w)| (v - mean) * (v - mean) * w)
+            .sum::<f32>()
+            / total;
+
+        let span = (max - min).max(f32::MIN_POSITIVE);
+        let mut hist = vec![0.0f32; BINS];
+        for (&v, &w) in values.iter().zip(weights) {
+            if w > 0.0 {
+                hist[(((v - min) / span * BINS as f32) as usize).min(BINS - 1)] += w;
+            }
+        }
+        let mut cdf = Vec::with_capacity(BINS + 1);
+        let mut acc = 0.0;
+        cdf.push(0.0);
+        for &c in &hist {
+            acc += c;
+            cdf.push((acc / total).min(1.0));
+        }
+
+        Some(Self {
+            min,
+            max,
+            mean,
+            deviation: variance.max(0.0).sqrt(),
+            cdf,
+        })
+    }
+
+    /// Fraction of the weight below `v`.
+    fn rank(&self, v: f32) -> f32 {
+        if self.max <= self.min {
+            return 0.5;
+        }
+        let t = ((v - self.min) / (self.max - self.min)).clamp(0.0, 1.0) * BINS as f32;
+        let i = (t as usize).min(BINS - 1);
+        let f = t - i as f32;
+        self.cdf[i] + (self.cdf[i + 1] - self.cdf[i]) * f
+    }
+
+    /// Value below which fraction `q` of the weight lies.
+    fn quantile(&self, q: f32) -> f32 {
+        let q = q.clamp(0.0, 1.0);
+        // 累積が q に達する最初のビン境界を二分探索し、ビン内で線形補間する
+        let i = self.cdf.partition_point(|&c| c < q).clamp(1, BINS);
+        let (c0, c1) = (self.cdf[i - 1], self.cdf[i]);
+        let f = if c1 > c0 { (q - c0) / (c1 - c0) } else { 0.0 };
+        self.min + (self.max - self.min) * (i as f32 - 1.0 + f) / BINS as f32
+    }
+
+    fn transfer(&self, to: &Distribution, v: f32, method: Method) -> f32 {
+        match method {
+            Method::MeanDeviation => {
+                let scale = (to.deviation / self.deviation.max(MIN_DEVIATION)).min(MAX_SCALE);
+                to.mean + (v - self.mean) * scale
+            }
+            Method::Histogram => to.quantile(self.rank(v)),
+        }
+    }
+}
+
+fn distributions(image: &ImageBuf) -> Option<[Distribution; 3]> {
+    let labs: Vec<[f32; 3]> = image.data.iter().map(|&px| oklab_channels(px)).collect();
+    let weights: Vec<f32> = image
+        .data
+        .iter()
+        .map(|px| finite_or(px.alpha, 0.0).clamp(0.0, 1.0))
+        .collect();
+    let channel = |c: usize| {
+        let values: Vec<f32> = labs.iter().map(|lab| lab[c]).collect();
+        Distribution::new(&values, &weights)
+    };
+    Some([channel(0)?, channel(1)?, channel(2)?])
+}
+
+pub fn color_match(
+    input: &ImageBuf,
+    reference: Option<&ImageBuf>,
+    p: &ColorMatchParams,
+) -> ImageBuf {
+    let mut out = input.clone();
+    let strength = finite_or(p.strength, 1.0).clamp(0.0, 1.0);
+    let Some(reference) = reference else {
+        return out;
+    };
+    // どちらかが完全に透明なら統計が取れないので素通し
+    let (Some(from), Some(to)) = (distributions(input), distributions(reference)) else {
+        return out;
+    };
+    if strength <= 0.0 {
+        return out;
+    }
+
+    for (dst, &src) in out.data.iter_mut().zip(&input.data) {
+        let lab = oklab_channels(src);
+        let matched: [f32; 3] = std::array::from_fn(|c| {
+            if c == 0 && p.preserve_luminance {
+                lab[0]
+            } else {
+                from[c].transfer(&to[c], lab[c], p.method)
+            }
+        });
+        let mixed: [f32; 3] = std::array::from_fn(|c| lab[c] + (matched[c] - lab[c]) * strength);
+        let rgb = Oklab {
+            l: mixed[0],
+            a: mixed[1],
+            b: mixed[2],
+        }
+        .to_srgb()
+        .map(|c| finite_or(c, 0.0));
+        *dst = PixelF32 {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            alpha: src.alpha,
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames match against the input
+/// with its channels rotated.
+pub struct ColorMatch;
+
+impl RenderCore for ColorMatch {
+    type Params = ColorMatchParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ColorMatchParams {
+            method: match rng.popup(1, 2) {
+                2 => Method::Histogram,
+                _ => Method::MeanDeviation,
+            },
+            strength: rng.slider(0.0, 1.0),
+            preserve_luminance: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let mut rotated = input.clone();
+        for px in rotated.data.iter_mut() {
+            (px.red, px.green, px.blue) = (px.green, px.blue, px.red);
+        }
+        let reference = (frame % 2 == 1).then_some(&rotated);
+        *output = color_match(input, reference, params);
+    }
+}
@@ -0,0 +1,322 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Deficiency,
+    Severity,
+    Mode,
+    Strength,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Simulates color vision deficiencies and daltonizes colors.";
+
+/// Linear RGB to LMS cone responses (Viénot, Brettel and Mollon 1999).
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [0.080_944_45, -0.130_504_4, 0.116_721_1],
+    [-0.010_248_53, 0.054_019_33, -0.113_614_7],
+    [-0.000_365_297, -0.004_121_615, 0.693_511_4],
+];
+/// Spreads the information a viewer cannot see into channels they can (Fidaner et al.).
+const ERROR_SHIFT: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deficiency {
+    /// Missing L (red) cones.
+    Protanopia,
+    /// Missing M (green) cones.
+    Deuteranopia,
+    /// Missing S (blue) cones.
+    Tritanopia,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// How the layer looks to the viewer.
+    Simulate,
+    /// The layer corrected for the viewer.
+    Daltonize,
+    /// How the corrected layer looks to the viewer.
+    DaltonizeSimulated,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Deficiency,
+            tr("Deficiency"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Protanopia"), tr("Deuteranopia"), tr("Tritanopia")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Severity: blends toward the full dichromat response (below 100% approximates anomalous trichromacy)
+        params.add(
+            Params::Severity,
+            tr("Severity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Mode: Strength is hidden while only simulating
+        params.add_with_flags(
+            Params::Mode,
+            tr("Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Simulate"),
+                    tr("Daltonize"),
+                    tr("Daltonize and Simulate"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Strength: amount of the lost contrast shifted into visible channels
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(200.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ColorVision - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Mode =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn mode(params: &Parameters<Params>) -> Result<Mode, Error> {
+    Ok(match params.get(Params::Mode)?.as_popup()?.value() {
+        2 => Mode::Daltonize,
+        3 => Mode::DaltonizeSimulated,
+        _ => Mode::Simulate,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let daltonize = mode(params)? != Mode::Simulate;
+    ui::set_param_visible(in_data, params, Params::Strength, daltonize)?;
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = VisionParams {
+            deficiency: match params.get(Params::Deficiency)?.as_popup()?.value() {
+                2 => Deficiency::Deuteranopia,
+                3 => Deficiency::Tritanopia,
+                _ => Deficiency::Protanopia,
+            },
+            severity: params.get(Params::Severity)?.as_float_slider()?.value() as f32 / 100.0,
+            mode: mode(params)?,
+            strength: params.get(Params::Strength)?.as_float_slider()?.value() as f32 / 100.0,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        color_vision(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct VisionParams {
+    pub deficiency: Deficiency,
+    /// 0..1.
+    pub severity: f32,
+    pub mode: Mode,
+    /// Daltonization gain (1 = default).
+    pub strength: f32,
+}
+
+fn mul(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// Linear RGB as seen by the dichromat, blended by `severity`.
+fn simulate(rgb: [f32; 3], deficiency: Deficiency, severity: f32) -> [f32; 3] {
+    let [l, m, s] = mul(&RGB_TO_LMS, rgb);
+    // 欠けた錐体の応答を残り 2 つの錐体から推定する。1 型・2 型は白と青、
+    // 3 型は白と赤の原色を保つ平面へ射影する
+    let lms = match deficiency {
+        Deficiency::Protanopia => [2.02344 * m - 2.52581 * s, m, s],
+        Deficiency::Deuteranopia => [l, 0.494207 * l + 1.24827 * s, s],
+        Deficiency::Tritanopia => [l, m, -0.012_244_97 * l + 0.072_034_52 * m],
+    };
+    let seen = mul(&LMS_TO_RGB, lms);
+    std::array::from_fn(|c| rgb[c] + (seen[c] - rgb[c]) * severity)
+}
+
+fn daltonize(rgb: [f32; 3], deficiency: Deficiency, severity: f32, strength: f32) -> [f32; 3] {
+    let seen = simulate(rgb, deficiency, severity);
+    let error = std::array::from_fn(|c| rgb[c] - seen[c]);
+    let shift = mul(&ERROR_SHIFT, error);
+    std::array::from_fn(|c| rgb[c] + shift[c] * strength)
+}
+
+pub fn color_vision(input: &ImageBuf, p: &VisionParams) -> ImageBuf {
+    let mut out = input.clone();
+    let severity = finite_or(p.severity, 1.0).clamp(0.0, 1.0);
+    let strength = finite_or(p.strength, 1.0).clamp(0.0, 2.0);
+    for px in out.data.iter_mut() {
+        // 錐体の応答は光の量に比例するので線形 RGB で計算する
+        let rgb = [px.red, px.green, px.blue].map(|c| srgb_to_linear(finite_or(c, 0.0)));
+        let rgb = match p.mode {
+            Mode::Simulate => simulate(rgb, p.deficiency, severity),
+            Mode::Daltonize => daltonize(rgb, p.deficiency, severity, strength),
+            Mode::DaltonizeSimulated => simulate(
+                daltonize(rgb, p.deficiency, severity, strength),
+                p.deficiency,
+                severity,
+            ),
+        };
+        [px.red, px.green, px.blue] = rgb.map(|c| linear_to_srgb(c.max(0.0)));
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ColorVision;
+
+impl RenderCore for ColorVision {
+    type Params = VisionParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        VisionParams {
+            deficiency: match rng.popup(1, 3) {
+                2 => Deficiency::Deuteranopia,
+                3 => Deficiency::Tritanopia,
+                _ => Deficiency::Protanopia,
+            },
+            severity: rng.unit(),
+            mode: match rng.popup(1, 3) {
+                2 => Mode::Daltonize,
+                3 => Mode::DaltonizeSimulated,
+                _ => Mode::Simulate,
+            },
+            strength: rng.slider(0.0, 2.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = color_vision(input, params);
+    }
+}
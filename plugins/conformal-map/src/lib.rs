@@ -0,0 +1,436 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f64::consts::PI;
+
+use ae::pf::*;
+use utils::complex::C64;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Edge, Filter};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Map,
+    Exponent,
+    Center,
+    Scale,
+    Rotation,
+    EdgeMode,
+    Supersampling,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Warps the layer through conformal maps.";
+
+/// Upper bound of `Supersampling` (samples per axis).
+const MAX_SUPERSAMPLING: i32 = 8;
+const MAX_EXPONENT: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Map {
+    /// `ln(z) / π`: rings become horizontal bands.
+    Log,
+    /// `exp(π z)`: horizontal bands become rings.
+    Exp,
+    /// `z^n` with `Exponent` as n.
+    Power,
+    /// `(z + 1/z) / 2`: the unit circle collapses onto a slit.
+    Joukowski,
+    /// `sin(π z / 2)`.
+    Sine,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Map: Power shows Exponent (see update_exponent_visibility)
+        params.add_with_flags(
+            Params::Map,
+            tr("Map"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Log"),
+                    tr("Exp"),
+                    tr("Power"),
+                    tr("Joukowski"),
+                    tr("Sine"),
+                ]);
+                d.set_default(3);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Exponent,
+            tr("Exponent"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-MAX_EXPONENT);
+                d.set_valid_max(MAX_EXPONENT);
+                d.set_slider_min(-4.0);
+                d.set_slider_max(4.0);
+                d.set_default(2.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Scale: percent; the unit circle spans half the shorter side at 100
+        params.add(
+            Params::Scale,
+            tr("Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::EdgeMode,
+            tr("Edge Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("None"), tr("Clamp"), tr("Repeat"), tr("Mirror")]);
+                d.set_default(3);
+            }),
+        )?;
+
+        // Supersampling: samples per axis and pixel (the maps minify strongly near poles)
+        params.add(
+            Params::Supersampling,
+            tr("Supersampling"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_SUPERSAMPLING);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_SUPERSAMPLING);
+                d.set_default(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ConformalMap - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_exponent_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Map =>
+            {
+                update_exponent_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn map(params: &Parameters<Params>) -> Result<Map, Error> {
+    Ok(match params.get(Params::Map)?.as_popup()?.value() {
+        1 => Map::Log,
+        2 => Map::Exp,
+        4 => Map::Joukowski,
+        5 => Map::Sine,
+        _ => Map::Power,
+    })
+}
+
+fn update_exponent_visibility(
+    in_data: &InData,
+    params: &mut Parameters<Params>,
+) -> Result<(), Error> {
+    let power = map(params)? == Map::Power;
+
+    ui::set_param_visible(in_data, params, Params::Exponent, power)?;
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = ConformalParams {
+            map: map(params)?,
+            exponent: params.get(Params::Exponent)?.as_float_slider()?.value() as f32,
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            scale: params.get(Params::Scale)?.as_float_slider()?.value() as f32 / 100.0,
+            rotation: params
+                .get(Params::Rotation)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            edge: match params.get(Params::EdgeMode)?.as_popup()?.value() {
+                1 => Edge::None,
+                2 => Edge::Clamp,
+                4 => Edge::Mirror,
+                _ => Edge::Repeat,
+            },
+            supersampling: params.get(Params::Supersampling)?.as_slider()?.value() as u32,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = conformal(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ConformalParams {
+    pub map: Map,
+    /// n of the Power map.
+    pub exponent: f32,
+    /// Origin of the complex plane in render pixels.
+    pub center: (f32, f32),
+    /// Unit radius as a fraction of half the shorter side.
+    pub scale: f32,
+    /// Rotation of the output plane in radians.
+    pub rotation: f32,
+    pub edge: Edge,
+    /// Samples per axis and pixel.
+    pub supersampling: u32,
+    pub filter: Filter,
+}
+
+/// Output pixel -> complex plane -> map -> source pixel.
+struct Plane {
+    map: Map,
+    exponent: f64,
+    center: C64,
+    /// Pixels per unit.
+    unit: f64,
+    /// `e^{-iθ}`, undoing the output rotation.
+    rotation: C64,
+}
+
+impl Plane {
+    fn new(width: usize, height: usize, p: &ConformalParams) -> Self {
+        let (w, h) = (width as f64, height as f64);
+        let scale = finite_or(p.scale as f64, 1.0).clamp(0.01, 100.0);
+        let max = MAX_EXPONENT as f64;
+        Self {
+            map: p.map,
+            exponent: finite_or(p.exponent as f64, 2.0).clamp(-max, max),
+            center: C64::new(
+                finite_or(p.center.0 as f64, w * 0.5).clamp(-1e6, 1e6),
+                finite_or(p.center.1 as f64, h * 0.5).clamp(-1e6, 1e6),
+            ),
+            unit: (0.5 * w.min(h) * scale).max(1e-3),
+            rotation: C64::from_polar(1.0, -finite_or(p.rotation as f64, 0.0)),
+        }
+    }
+
+    fn apply(&self, z: C64) -> C64 {
+        match self.map {
+            Map::Log => z.ln().scale(1.0 / PI),
+            Map::Exp => z.scale(PI).exp(),
+            Map::Power => z.powf(self.exponent),
+            Map::Joukowski => (z + z.recip()).scale(0.5),
+            Map::Sine => z.scale(0.5 * PI).sin(),
+        }
+    }
+
+    /// Source position of output position (`x`, `y`), `None` at a pole.
+    fn source(&self, x: f64, y: f64) -> Option<(f32, f32)> {
+        let z = (C64::new(x, y) - self.center).scale(1.0 / self.unit) * self.rotation;
+        let w = self.apply(z);
+        let s = self.center + w.scale(self.unit);
+        s.is_finite()
+            .then(|| (s.re.clamp(-1e7, 1e7) as f32, s.im.clamp(-1e7, 1e7) as f32))
+    }
+}
+
+fn conformal(input: &ImageBuf, p: &ConformalParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let premul = sampling::premultiply(input);
+    let plane = Plane::new(width, height, p);
+    let edges = (p.edge, p.edge);
+    let n = p.supersampling.clamp(1, MAX_SUPERSAMPLING as u32);
+    let weight = 1.0 / (n * n) as f32;
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut acc = [0.0f32; 4];
+            for j in 0..n {
+                for i in 0..n {
+                    let ox = x as f64 + (i as f64 + 0.5) / n as f64 - 0.5;
+                    let oy = y as f64 + (j as f64 + 0.5) / n as f64 - 0.5;
+                    let Some((sx, sy)) = plane.source(ox, oy) else {
+                        continue;
+                    };
+                    let px = sampling::sample_edge(&premul, sx, sy, p.filter, edges);
+                    acc[0] += px.red * weight;
+                    acc[1] += px.green * weight;
+                    acc[2] += px.blue * weight;
+                    acc[3] += px.alpha * weight;
+                }
+            }
+            let px = PixelF32 {
+                red: acc[0],
+                green: acc[1],
+                blue: acc[2],
+                alpha: acc[3],
+            };
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ConformalMap;
+
+impl RenderCore for ConformalMap {
+    type Params = ConformalParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ConformalParams {
+            map: match rng.popup(1, 5) {
+                1 => Map::Log,
+                2 => Map::Exp,
+                4 => Map::Joukowski,
+                5 => Map::Sine,
+                _ => Map::Power,
+            },
+            exponent: rng.slider(-MAX_EXPONENT, MAX_EXPONENT),
+            center: (rng.slider(-100.0, 300.0), rng.slider(-100.0, 300.0)),
+            scale: rng.slider(1.0, 10000.0) / 100.0,
+            rotation: rng.slider(-720.0, 720.0).to_radians(),
+            edge: match rng.popup(1, 4) {
+                1 => Edge::None,
+                2 => Edge::Clamp,
+                4 => Edge::Mirror,
+                _ => Edge::Repeat,
+            },
+            supersampling: rng.int(1, MAX_SUPERSAMPLING) as u32,
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = conformal(input, params);
+    }
+}
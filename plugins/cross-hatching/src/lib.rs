@@ -0,0 +1,513 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::PI;
+
+use ae::pf::*;
+use noise::{Fbm, NoiseType};
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::tensor::structure_tensor;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    LineSpacing,
+    LineWidth,
+    Layers,
+    LayerAngle,
+    Angle,
+    FollowStructure,
+    StructureSmoothing,
+    InkColor,
+    Background,
+    PaperColor,
+    PaperTexture,
+    GrainSize,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Redraws the layer as cross-hatched ink strokes.";
+
+const MIN_SPACING: f32 = 1.0;
+const MAX_SPACING: f32 = 500.0;
+const MAX_WIDTH: f32 = 100.0;
+const MAX_LAYERS: i32 = 4;
+const MAX_SMOOTHING: f32 = 50.0;
+const MIN_GRAIN: f32 = 0.5;
+const MAX_GRAIN: f32 = 100.0;
+
+/// Stroke directions are quantized to this step so every direction is a set of
+/// straight, globally continuous lines (rotating the lines per pixel would
+/// scramble their phase far from the origin).
+const BIN_ANGLE: f32 = PI / 8.0;
+
+/// Fixed paper grain: a few octaves of value noise, seeded so every frame matches.
+const PAPER_GRAIN: Fbm = Fbm {
+    kind: NoiseType::Value,
+    detail: 3.0,
+    roughness: 0.5,
+    lacunarity: 2.0,
+    seed: 0x5eed,
+    period: [0, 0],
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    /// `Paper Color` with the grain.
+    Paper,
+    /// The layer itself, grained like the paper.
+    Input,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Line Spacing: distance between neighbouring strokes of one layer, in pixels
+        params.add(
+            Params::LineSpacing,
+            tr("Line Spacing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(MIN_SPACING);
+                d.set_valid_max(MAX_SPACING);
+                d.set_slider_min(2.0);
+                d.set_slider_max(30.0);
+                d.set_default(6.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Line Width: stroke width at full darkness, in pixels
+        params.add(
+            Params::LineWidth,
+            tr("Line Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_WIDTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Layers: stroke layers added one after another as the image darkens
+        params.add(
+            Params::Layers,
+            tr("Layers"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_LAYERS);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_LAYERS);
+                d.set_default(3);
+            }),
+        )?;
+
+        // Layer Angle: rotation of each further layer against the previous one
+        params.add(
+            Params::LayerAngle,
+            tr("Layer Angle"),
+            AngleDef::setup(|d| {
+                d.set_default(60.0);
+            }),
+        )?;
+
+        // Angle: stroke direction where the image has no structure to follow
+        params.add(
+            Params::Angle,
+            tr("Angle"),
+            AngleDef::setup(|d| {
+                d.set_default(45.0);
+            }),
+        )?;
+
+        params.add(
+            Params::FollowStructure,
+            tr("Follow Structure"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Structure Smoothing: Gaussian sigma of the structure tensor, in pixels
+        params.add(
+            Params::StructureSmoothing,
+            tr("Structure Smoothing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SMOOTHING);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(4.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::InkColor,
+            tr("Ink Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 24,
+                    green: 22,
+                    blue: 30,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Background,
+            tr("Background"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Paper"), tr("Input")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::PaperColor,
+            tr("Paper Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 246,
+                    green: 241,
+                    blue: 228,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Paper Texture: grain darkening the paper and breaking up the ink
+        params.add(
+            Params::PaperTexture,
+            tr("Paper Texture"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.3);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::GrainSize,
+            tr("Grain Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(MIN_GRAIN);
+                d.set_valid_max(MAX_GRAIN);
+                d.set_slider_min(MIN_GRAIN);
+                d.set_slider_max(10.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_CrossHatching - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 間隔・線幅・平滑化・粒の大きさはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let angle =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_angle()?.value().to_radians()) };
+        let color = |id| -> Result<[f32; 3], Error> {
+            let c = params.get(id)?.as_color()?.float_value()?;
+            Ok([c.red, c.green, c.blue])
+        };
+
+        let settings = HatchParams {
+            line_spacing: ds
+                .length(params.get(Params::LineSpacing)?.as_float_slider()?.value() as f32),
+            line_width: ds.length(params.get(Params::LineWidth)?.as_float_slider()?.value() as f32),
+            layers: params.get(Params::Layers)?.as_slider()?.value() as u32,
+            layer_angle: angle(Params::LayerAngle)?,
+            angle: angle(Params::Angle)?,
+            follow_structure: params
+                .get(Params::FollowStructure)?
+                .as_float_slider()?
+                .value() as f32,
+            structure_smoothing: ds.length(
+                params
+                    .get(Params::StructureSmoothing)?
+                    .as_float_slider()?
+                    .value() as f32,
+            ),
+            ink_color: color(Params::InkColor)?,
+            background: match params.get(Params::Background)?.as_popup()?.value() {
+                2 => Background::Input,
+                _ => Background::Paper,
+            },
+            paper_color: color(Params::PaperColor)?,
+            paper_texture: params.get(Params::PaperTexture)?.as_float_slider()?.value() as f32,
+            grain_size: ds.length(params.get(Params::GrainSize)?.as_float_slider()?.value() as f32),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        cross_hatch(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct HatchParams {
+    /// Stroke spacing in render pixels.
+    pub line_spacing: f32,
+    /// Stroke width at full darkness in render pixels.
+    pub line_width: f32,
+    pub layers: u32,
+    /// Rotation between successive layers, radians.
+    pub layer_angle: f32,
+    /// Stroke direction in flat areas, radians.
+    pub angle: f32,
+    pub follow_structure: f32,
+    /// Structure tensor sigma in render pixels.
+    pub structure_smoothing: f32,
+    /// Encoded ink color.
+    pub ink_color: [f32; 3],
+    pub background: Background,
+    /// Encoded paper color.
+    pub paper_color: [f32; 3],
+    pub paper_texture: f32,
+    /// Grain cell size in render pixels.
+    pub grain_size: f32,
+}
+
+/// Stroke direction per pixel as a doubled-angle unit vector, blending the
+/// structure tensor tangent with the fixed `angle` by anisotropy.
+fn stroke_field(input: &ImageBuf, p: &HatchParams) -> Vec<f32> {
+    let base = finite_or(p.angle, 0.0);
+    let follow = finite_or(p.follow_structure, 0.0).clamp(0.0, 1.0);
+    if follow <= 0.0 {
+        return vec![base; input.data.len()];
+    }
+    let sigma = finite_or(p.structure_smoothing, 0.0).clamp(0.0, MAX_SMOOTHING);
+    let (bc, bs) = ((2.0 * base).cos(), (2.0 * base).sin());
+    structure_tensor(input, sigma)
+        .iter()
+        .map(|t| {
+            let o = t.orientation();
+            let w = finite_or(o.anisotropy, 0.0).clamp(0.0, 1.0) * follow;
+            // 接線は向きの符号が不定なので角度を倍にしてから混ぜる
+            let theta = o.tangent.1.atan2(o.tangent.0);
+            let (c, s) = ((2.0 * theta).cos(), (2.0 * theta).sin());
+            let (c, s) = (bc + (c - bc) * w, bs + (s - bs) * w);
+            if c.hypot(s) < 1e-6 {
+                base
+            } else {
+                0.5 * s.atan2(c)
+            }
+        })
+        .collect()
+}
+
+/// Antialiased coverage at `p` of straight strokes along `angle`, `spacing`
+/// apart and `width` wide.
+fn stripes((x, y): (f32, f32), angle: f32, spacing: f32, width: f32) -> f32 {
+    let (s, c) = angle.sin_cos();
+    // 線に垂直な方向の座標で間隔ごとの縞を作る
+    let across = (-x * s + y * c) / spacing;
+    let dist = (across - across.round()).abs() * spacing;
+    (width * 0.5 - dist + 0.5).clamp(0.0, 1.0)
+}
+
+fn cross_hatch(input: &ImageBuf, p: &HatchParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let spacing = finite_or(p.line_spacing, 6.0).clamp(MIN_SPACING, MAX_SPACING);
+    let line_width = finite_or(p.line_width, 0.0).clamp(0.0, MAX_WIDTH);
+    let layers = p.layers.clamp(1, MAX_LAYERS as u32);
+    let layer_angle = finite_or(p.layer_angle, 0.0);
+    let texture = finite_or(p.paper_texture, 0.0).clamp(0.0, 1.0);
+    let grain_size = finite_or(p.grain_size, 2.0).clamp(MIN_GRAIN, MAX_GRAIN);
+    let ink = p.ink_color.map(|c| finite_or(c, 0.0));
+    let paper = p.paper_color.map(|c| finite_or(c, 1.0));
+    let directions = stroke_field(input, p);
+
+    for y in 0..height {
+        for x in 0..width {
+            let px = input.get(x as isize, y as isize);
+            let darkness = 1.0 - finite_or(luminance(px), 0.0).clamp(0.0, 1.0);
+            let (fx, fy) = (x as f32 + 0.5, y as f32 + 0.5);
+
+            // 暗さが層の閾値を超えた分だけ線を太らせ、層ごとに向きを回して重ねる
+            let direction = directions[y * width + x];
+            let mut clear = 1.0;
+            for k in 0..layers {
+                let grow = ((darkness - k as f32 / layers as f32) * layers as f32).clamp(0.0, 1.0);
+                let w = line_width * grow;
+                if w <= 0.0 {
+                    continue;
+                }
+                // 向きを段階化し、隣り合う 2 方向の縞を混ぜる
+                let bin = (direction + layer_angle * k as f32).rem_euclid(PI) / BIN_ANGLE;
+                let lower = bin.floor();
+                let t = bin - lower;
+                let t = t * t * (3.0 - 2.0 * t);
+                let coverage = |b: f32| stripes((fx, fy), b * BIN_ANGLE, spacing, w);
+                let coverage = coverage(lower) + (coverage(lower + 1.0) - coverage(lower)) * t;
+                clear *= 1.0 - coverage;
+            }
+            let mut coverage = 1.0 - clear;
+
+            // 紙の凹凸: 粒の明るい所はインクが乗らず、暗い所は紙が沈む
+            let grain = if texture > 0.0 {
+                PAPER_GRAIN.sample([fx / grain_size, fy / grain_size, 0.0]) * 0.5 + 0.5
+            } else {
+                0.5
+            };
+            let grain = finite_or(grain, 0.5).clamp(0.0, 1.0);
+            coverage *= 1.0 - texture * grain * grain;
+            let shade = 1.0 - texture * 0.25 * (1.0 - grain);
+
+            let ground = match p.background {
+                Background::Paper => paper,
+                Background::Input => [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0)),
+            };
+            let mix = |k: usize| {
+                let g = ground[k] * shade;
+                g + (ink[k] - g) * coverage
+            };
+            output.set(
+                x,
+                y,
+                PixelF32 {
+                    red: mix(0),
+                    green: mix(1),
+                    blue: mix(2),
+                    alpha: px.alpha,
+                },
+            );
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct CrossHatching;
+
+impl RenderCore for CrossHatching {
+    type Params = HatchParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        HatchParams {
+            line_spacing: rng.slider(MIN_SPACING, MAX_SPACING),
+            line_width: rng.slider(0.0, MAX_WIDTH),
+            layers: rng.popup(1, MAX_LAYERS) as u32,
+            layer_angle: rng.slider(-3600.0, 3600.0).to_radians(),
+            angle: rng.slider(-3600.0, 3600.0).to_radians(),
+            follow_structure: rng.slider(0.0, 1.0),
+            structure_smoothing: rng.slider(0.0, MAX_SMOOTHING),
+            ink_color: [rng.unit(), rng.unit(), rng.unit()],
+            background: if rng.chance(0.5) {
+                Background::Paper
+            } else {
+                Background::Input
+            },
+            paper_color: [rng.unit(), rng.unit(), rng.unit()],
+            paper_texture: rng.slider(0.0, 1.0),
+            grain_size: rng.slider(MIN_GRAIN, MAX_GRAIN),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = cross_hatch(input, params);
+    }
+}
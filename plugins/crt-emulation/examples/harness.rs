@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<crt_emulation::CrtEmulation>();
+}
@@ -0,0 +1,587 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::PI;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::harness::{RenderCore, Rng};
+use utils::history::{History, HistoryKey};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    LineCount,
+    ScanlineStrength,
+    Mask,
+    MaskScale,
+    MaskStrength,
+    Curvature,
+    BloomRadius,
+    BloomStrength,
+    Persistence,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: phosphor states of recently rendered frames.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History<ImageBuf>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Emulates a CRT display.";
+
+/// Upper bound of `Bloom Radius` (full-resolution pixels).
+const MAX_BLOOM_RADIUS: f32 = 100.0;
+const MAX_LINE_COUNT: i32 = 4320;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mask {
+    None,
+    /// Continuous vertical RGB stripes (Trinitron).
+    ApertureGrille,
+    /// RGB dots, alternate lines shifted by half a triad.
+    ShadowMask,
+    /// Stripes broken into slots, alternate triads shifted by half a line.
+    SlotMask,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Line Count: scanlines over the layer height; the mask pitch follows it,
+        // so the look is the same at any resolution
+        params.add(
+            Params::LineCount,
+            tr("Line Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(16);
+                d.set_valid_max(MAX_LINE_COUNT);
+                d.set_slider_min(120);
+                d.set_slider_max(1080);
+                d.set_default(240);
+            }),
+        )?;
+
+        params.add(
+            Params::ScanlineStrength,
+            tr("Scanline Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Mask,
+            tr("Mask"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("None"),
+                    tr("Aperture Grille"),
+                    tr("Shadow Mask"),
+                    tr("Slot Mask"),
+                ]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Mask Scale: triad width relative to the scanline pitch
+        params.add(
+            Params::MaskScale,
+            tr("Mask Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.25);
+                d.set_valid_max(8.0);
+                d.set_slider_min(0.25);
+                d.set_slider_max(4.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::MaskStrength,
+            tr("Mask Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.3);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Curvature: barrel distortion; the corners fall outside the tube
+        params.add(
+            Params::Curvature,
+            tr("Curvature"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.1);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::BloomRadius,
+            tr("Bloom Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_BLOOM_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(30.0);
+                d.set_default(8.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BloomStrength,
+            tr("Bloom Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(4.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.3);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Persistence: share of the previous frame's glow still lit (phosphor decay)
+        params.add(
+            Params::Persistence,
+            tr("Persistence"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(0.99);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.9);
+                d.set_default(0.3);
+                d.set_precision(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_CrtEmulation - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // 残光は再生成できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(plugin.in_data, &input, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup | ae::Command::UserChangedParam { .. } => {
+                // 設定が変わったら過去の残光は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let input = cb
+                    .checkout_layer_pixels(0)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+
+                if let Some(input) = input
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(in_data, &input, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = CrtParams {
+            line_count: params.get(Params::LineCount)?.as_slider()?.value() as u32,
+            scanline_strength: params
+                .get(Params::ScanlineStrength)?
+                .as_float_slider()?
+                .value() as f32,
+            mask: match params.get(Params::Mask)?.as_popup()?.value() {
+                1 => Mask::None,
+                3 => Mask::ShadowMask,
+                4 => Mask::SlotMask,
+                _ => Mask::ApertureGrille,
+            },
+            mask_scale: params.get(Params::MaskScale)?.as_float_slider()?.value() as f32,
+            mask_strength: params.get(Params::MaskStrength)?.as_float_slider()?.value() as f32,
+            curvature: params.get(Params::Curvature)?.as_float_slider()?.value() as f32,
+            bloom_radius: ds
+                .length(params.get(Params::BloomRadius)?.as_float_slider()?.value() as f32),
+            bloom_strength: params
+                .get(Params::BloomStrength)?
+                .as_float_slider()?
+                .value() as f32,
+        };
+        let persistence = params.get(Params::Persistence)?.as_float_slider()?.value() as f32;
+
+        let key = HistoryKey {
+            width: input.width,
+            height: input.height,
+            downsample: ds,
+            time_step: in_data.time_step(),
+        };
+        let time = in_data.current_time();
+
+        let state = {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            let mut state = crt(input, &settings);
+            if persistence > 0.0 {
+                if let Some(previous) = history.previous(key, time) {
+                    persist(&mut state, previous, persistence);
+                }
+                history.store(key, time, state.clone());
+            }
+            state
+        };
+
+        let mut output = state;
+        for px in output.data.iter_mut() {
+            *px = sampling::unpremultiply(*px);
+        }
+        output.write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CrtParams {
+    /// Scanlines over the layer height.
+    pub line_count: u32,
+    pub scanline_strength: f32,
+    pub mask: Mask,
+    /// Triad width in scanline pitches.
+    pub mask_scale: f32,
+    pub mask_strength: f32,
+    pub curvature: f32,
+    /// Bloom radius in pixels (already scaled for downsampling).
+    pub bloom_radius: f32,
+    pub bloom_strength: f32,
+}
+
+fn unit(v: f32) -> f32 {
+    if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) }
+}
+
+/// Per-channel mask transmission at source position (`x`, `y`), normalized so
+/// a flat field keeps its average brightness.
+fn mask_weights(mask: Mask, x: f32, y: f32, pitch: f32, scale: f32, strength: f32) -> [f32; 3] {
+    let sub = pitch * scale / 3.0;
+    // 1 サブピクセルが 1.5px を下回るとモアレになるので弱める
+    let strength = strength * (sub - 0.5).clamp(0.0, 1.0);
+    if mask == Mask::None || strength <= 0.0 {
+        return [1.0; 3];
+    }
+
+    let line = (y / pitch).floor();
+    let column = match mask {
+        Mask::ShadowMask if line.rem_euclid(2.0) >= 1.0 => x / sub + 1.5,
+        _ => x / sub,
+    };
+    let channel = column.floor().rem_euclid(3.0) as usize;
+    let dim = 1.0 - strength;
+    let mut weights = [dim; 3];
+    weights[channel.min(2)] = 1.0;
+
+    let mut average = (1.0 + 2.0 * dim) / 3.0;
+    if mask == Mask::SlotMask {
+        // 隣り合うトライアドで縦に半ライン分ずらしたスロットの切れ目
+        let triad = (column / 3.0).floor().rem_euclid(2.0);
+        let phase = (y / pitch + 0.5 * triad).fract();
+        const GAP: f32 = 0.2;
+        if phase < GAP {
+            weights = [dim; 3];
+        }
+        average = average * (1.0 - GAP) + dim * GAP;
+    }
+    weights.map(|w| w / average)
+}
+
+/// Curvature + scanlines: the electron beam image (premultiplied).
+fn beam(input: &ImageBuf, p: &CrtParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    let premul = sampling::premultiply(input);
+
+    let curvature = unit(p.curvature);
+    let pitch = h as f32 / p.line_count.clamp(1, MAX_LINE_COUNT as u32) as f32;
+    // 走査線の周期が 2px を下回るとエイリアスになるので弱める
+    let scan = unit(p.scanline_strength) * (pitch - 1.0).clamp(0.0, 1.0);
+    let aspect = w as f32 / h.max(1) as f32;
+
+    for y in 0..h {
+        for x in 0..w {
+            let u = (x as f32 + 0.5) / w as f32 * 2.0 - 1.0;
+            let v = (y as f32 + 0.5) / h as f32 * 2.0 - 1.0;
+            // 長辺基準の半径で樽型に歪め、左右の辺の中点が端に来るよう正規化する
+            let (ru, rv) = if aspect >= 1.0 {
+                (u, v / aspect)
+            } else {
+                (u * aspect, v)
+            };
+            let k = 1.0 + curvature * (ru * ru + rv * rv);
+            let norm = 1.0 + curvature * if aspect >= 1.0 { 1.0 } else { aspect * aspect };
+            let (su, sv) = (u * k / norm, v * k / norm);
+            if su.abs() > 1.0 || sv.abs() > 1.0 {
+                continue;
+            }
+            let sx = (su + 1.0) * 0.5 * w as f32;
+            let sy = (sv + 1.0) * 0.5 * h as f32;
+
+            let px = sampling::sample(&premul, sx - 0.5, sy - 0.5, Filter::Bilinear);
+            let profile = (PI * sy / pitch).sin().powi(2);
+            let s = 1.0 - scan + scan * 2.0 * profile;
+            let mask = mask_weights(p.mask, sx, sy, pitch, p.mask_scale, unit(p.mask_strength));
+            out.set(
+                x,
+                y,
+                PixelF32 {
+                    red: px.red * s * mask[0],
+                    green: px.green * s * mask[1],
+                    blue: px.blue * s * mask[2],
+                    alpha: px.alpha,
+                },
+            );
+        }
+    }
+    out
+}
+
+/// Separable Gaussian blur (σ = radius / 2) of a premultiplied image.
+fn gaussian_blur(image: &ImageBuf, radius: f32) -> ImageBuf {
+    let r = radius.ceil() as isize;
+    let sigma = (radius * 0.5).max(0.5);
+    let kernel: Vec<f32> = (-r..=r)
+        .map(|k| (-((k * k) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+
+    let pass = |src: &ImageBuf, horizontal: bool| {
+        let mut out = ImageBuf::new(src.width, src.height);
+        for y in 0..src.height as isize {
+            for x in 0..src.width as isize {
+                let mut acc = [0.0f32; 4];
+                for (k, &wt) in (-r..=r).zip(&kernel) {
+                    let px = if horizontal {
+                        src.get(x + k, y)
+                    } else {
+                        src.get(x, y + k)
+                    };
+                    acc[0] += px.red * wt;
+                    acc[1] += px.green * wt;
+                    acc[2] += px.blue * wt;
+                    acc[3] += px.alpha * wt;
+                }
+                out.set(
+                    x as usize,
+                    y as usize,
+                    PixelF32 {
+                        red: acc[0] / total,
+                        green: acc[1] / total,
+                        blue: acc[2] / total,
+                        alpha: acc[3] / total,
+                    },
+                );
+            }
+        }
+        out
+    };
+    pass(&pass(image, true), false)
+}
+
+/// Full CRT look without persistence (premultiplied).
+fn crt(input: &ImageBuf, p: &CrtParams) -> ImageBuf {
+    let mut image = beam(input, p);
+    let radius = if p.bloom_radius.is_nan() {
+        0.0
+    } else {
+        p.bloom_radius.clamp(0.0, MAX_BLOOM_RADIUS)
+    };
+    let strength = if p.bloom_strength.is_nan() {
+        0.0
+    } else {
+        p.bloom_strength.clamp(0.0, 4.0)
+    };
+
+    if radius > 0.0 && strength > 0.0 && !image.is_empty() {
+        let bloom = gaussian_blur(&image, radius);
+        for (px, b) in image.data.iter_mut().zip(&bloom.data) {
+            px.red += b.red * strength;
+            px.green += b.green * strength;
+            px.blue += b.blue * strength;
+            px.alpha = (px.alpha + b.alpha * strength).min(1.0);
+        }
+    }
+
+    let clean = |v: f32| if v.is_nan() { 0.0 } else { v.max(0.0) };
+    for px in image.data.iter_mut() {
+        *px = PixelF32 {
+            red: clean(px.red),
+            green: clean(px.green),
+            blue: clean(px.blue),
+            alpha: unit(px.alpha),
+        };
+    }
+    image
+}
+
+/// Phosphor decay: each channel keeps the brighter of the new frame and the
+/// previous state faded by `persistence`.
+fn persist(state: &mut ImageBuf, previous: &ImageBuf, persistence: f32) {
+    let keep = if persistence.is_nan() {
+        0.0
+    } else {
+        persistence.clamp(0.0, 0.99)
+    };
+    for (px, prev) in state.data.iter_mut().zip(&previous.data) {
+        px.red = px.red.max(prev.red * keep);
+        px.green = px.green.max(prev.green * keep);
+        px.blue = px.blue.max(prev.blue * keep);
+        px.alpha = px.alpha.max(prev.alpha * keep);
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no sequence data, so odd frames apply persistence against
+/// the frame's own result.
+pub struct CrtEmulation;
+
+impl RenderCore for CrtEmulation {
+    type Params = CrtParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        CrtParams {
+            line_count: rng.popup(16, MAX_LINE_COUNT) as u32,
+            scanline_strength: rng.slider(0.0, 1.0),
+            mask: match rng.popup(1, 4) {
+                1 => Mask::None,
+                3 => Mask::ShadowMask,
+                4 => Mask::SlotMask,
+                _ => Mask::ApertureGrille,
+            },
+            mask_scale: rng.slider(0.25, 8.0),
+            mask_strength: rng.slider(0.0, 1.0),
+            curvature: rng.slider(0.0, 1.0),
+            bloom_radius: rng.slider(0.0, MAX_BLOOM_RADIUS),
+            bloom_strength: rng.slider(0.0, 4.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let mut state = crt(input, params);
+        if frame % 2 == 1 {
+            let previous = state.clone();
+            persist(&mut state, &previous, 0.5);
+        }
+        for (dst, px) in output.data.iter_mut().zip(&state.data) {
+            *dst = sampling::unpremultiply(*px);
+        }
+    }
+}
@@ -0,0 +1,415 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{Fbm, NoiseType};
+use utils::ToPixel;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NoiseType,
+    Size,
+    Detail,
+    Evolution,
+    EvolutionSpeed,
+    Strength,
+    Iterations,
+    Filter,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Displaces the layer along a divergence-free curl-noise flow for smoke and ink looks.";
+
+/// Highest `Detail` (octaves beyond the first) offered for the potential field.
+const MAX_DETAIL: f32 = 8.0;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::NoiseType,
+            tr("Noise Type"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Perlin"), tr("Simplex"), tr("Value")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Size: base cell size of the flow in pixels
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(8.0);
+                d.set_slider_max(1000.0);
+                d.set_default(200.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Detail,
+            tr("Detail"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DETAIL);
+                d.set_slider_min(0.0);
+                d.set_slider_max(MAX_DETAIL);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Evolution,
+            tr("Evolution"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100000.0);
+                d.set_valid_max(100000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Evolution Speed: evolution added per second of layer time
+        params.add(
+            Params::EvolutionSpeed,
+            tr("Evolution Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(-2.0);
+                d.set_slider_max(2.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Strength: total advection length in pixels
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-10000.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(500.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Iterations: steps along the flow (more = curlier streaks)
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(64);
+                d.set_slider_min(1);
+                d.set_slider_max(32);
+                d.set_default(8);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Lanczos")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_CurlNoiseDistort - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+        let ds = Downsample::from_in_data(&in_data);
+        let seconds = in_data.current_time() as f64 / in_data.time_scale().max(1) as f64;
+
+        let evolution = params.get(Params::Evolution)?.as_float_slider()?.value();
+        let speed = params
+            .get(Params::EvolutionSpeed)?
+            .as_float_slider()?
+            .value();
+        let settings = CurlParams {
+            kind: match params.get(Params::NoiseType)?.as_popup()?.value() {
+                1 => NoiseType::Perlin,
+                3 => NoiseType::Value,
+                _ => NoiseType::Simplex,
+            },
+            size: ds.length(params.get(Params::Size)?.as_float_slider()?.value() as f32),
+            detail: params.get(Params::Detail)?.as_float_slider()?.value() as f32,
+            evolution: (evolution + speed * seconds) as f32,
+            strength: ds.length(params.get(Params::Strength)?.as_float_slider()?.value() as f32),
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as usize,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                2 => Filter::Lanczos3,
+                _ => Filter::Bilinear,
+            },
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+        };
+
+        let input = sampling::premultiply(&ImageBuf::from_layer(&in_layer));
+        let field = FlowField::new(input.width, input.height, &settings);
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = distort_pixel(&input, &field, x as f32, y as f32, &settings);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CurlParams {
+    pub kind: NoiseType,
+    /// Base cell size in render pixels.
+    pub size: f32,
+    pub detail: f32,
+    pub evolution: f32,
+    /// Total advection length in render pixels.
+    pub strength: f32,
+    pub iterations: usize,
+    pub filter: Filter,
+    pub seed: u32,
+}
+
+/// Per-pixel curl of a noise potential: `v = (dψ/dy, -dψ/dx)`, in cells per cell.
+struct FlowField {
+    width: usize,
+    height: usize,
+    velocity: Vec<(f32, f32)>,
+}
+
+impl FlowField {
+    fn new(width: usize, height: usize, p: &CurlParams) -> Self {
+        let size = finite_or(p.size, 200.0).max(1.0);
+        let fbm = Fbm {
+            kind: p.kind,
+            detail: finite_or(p.detail, 0.0).clamp(0.0, MAX_DETAIL),
+            roughness: 0.5,
+            lacunarity: 2.0,
+            seed: p.seed,
+            period: [0, 0],
+        };
+        let z = finite_or(p.evolution, 0.0);
+
+        // ポテンシャルを 1px 外側まで評価して中心差分で回転を取る
+        let (pw, ph) = (width + 2, height + 2);
+        let potential: Vec<f32> = (0..pw * ph)
+            .map(|i| {
+                let (x, y) = ((i % pw) as f32 - 0.5, (i / pw) as f32 - 0.5);
+                fbm.sample([x / size, y / size, z])
+            })
+            .collect();
+        let psi = |x: usize, y: usize| potential[y * pw + x];
+
+        let mut velocity = Vec::with_capacity(width * height);
+        for y in 1..=height {
+            for x in 1..=width {
+                let dx = (psi(x + 1, y) - psi(x - 1, y)) * 0.5 * size;
+                let dy = (psi(x, y + 1) - psi(x, y - 1)) * 0.5 * size;
+                velocity.push((dy, -dx));
+            }
+        }
+        Self {
+            width,
+            height,
+            velocity,
+        }
+    }
+
+    /// Bilinearly interpolated velocity at (`x`, `y`), clamped to the layer.
+    fn at(&self, x: f32, y: f32) -> (f32, f32) {
+        if self.velocity.is_empty() || !x.is_finite() || !y.is_finite() {
+            return (0.0, 0.0);
+        }
+        let x = x.clamp(0.0, (self.width - 1) as f32);
+        let y = y.clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let v = |x: usize, y: usize| self.velocity[y * self.width + x];
+        let lerp =
+            |a: (f32, f32), b: (f32, f32), t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        lerp(
+            lerp(v(x0, y0), v(x1, y0), tx),
+            lerp(v(x0, y1), v(x1, y1), tx),
+            ty,
+        )
+    }
+}
+
+/// Traces the flow backwards from (`x`, `y`) and samples the premultiplied `input` there.
+fn distort_pixel(input: &ImageBuf, field: &FlowField, x: f32, y: f32, p: &CurlParams) -> PixelF32 {
+    let iterations = p.iterations.clamp(1, 64);
+    let step = finite_or(p.strength, 0.0).clamp(-1e5, 1e5) / iterations as f32;
+
+    let (mut sx, mut sy) = (x, y);
+    for _ in 0..iterations {
+        let (vx, vy) = field.at(sx, sy);
+        sx -= vx * step;
+        sy -= vy * step;
+    }
+    sampling::unpremultiply(sampling::sample(input, sx, sy, p.filter))
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct CurlNoiseDistort;
+
+impl RenderCore for CurlNoiseDistort {
+    type Params = CurlParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        CurlParams {
+            kind: match rng.popup(1, 3) {
+                1 => NoiseType::Perlin,
+                3 => NoiseType::Value,
+                _ => NoiseType::Simplex,
+            },
+            size: rng.slider(1.0, 10000.0),
+            detail: rng.slider(0.0, MAX_DETAIL),
+            evolution: rng.slider(-100000.0, 100000.0),
+            strength: rng.slider(-10000.0, 10000.0),
+            iterations: rng.int(1, 64) as usize,
+            filter: if rng.chance(0.5) {
+                Filter::Bilinear
+            } else {
+                Filter::Lanczos3
+            },
+            seed: rng.popup(0, 10000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let input = sampling::premultiply(input);
+        let field = FlowField::new(input.width, input.height, params);
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let px = distort_pixel(&input, &field, x as f32, y as f32, params);
+                output.set(x, y, px);
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<curvature_map::CurvatureMap>();
+}
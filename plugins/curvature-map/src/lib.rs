@@ -0,0 +1,368 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::blur::blur;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, Edge};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Source,
+    Output,
+    Radius,
+    HeightDepth,
+    Contrast,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Derives a curvature mask from a height or normal map.";
+
+/// Upper bound of `Radius` (full-resolution pixels).
+const MAX_RADIUS: f32 = 200.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Luminance as height, white is high.
+    Height,
+    /// Normal map with green pointing up the layer.
+    NormalOpenGl,
+    /// Normal map with green pointing down the layer.
+    NormalDirectX,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Mid gray with convex areas brighter and concave areas darker.
+    Curvature,
+    /// White with concave areas darkened.
+    Cavity,
+    /// Black with convex edges brightened.
+    Edges,
+    /// Edges in red and cavities in green, for channel masks.
+    Both,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Source,
+            tr("Source"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Height"),
+                    tr("Normal OpenGL (Y+)"),
+                    tr("Normal DirectX (Y-)"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Curvature"),
+                    tr("Cavity"),
+                    tr("Edges"),
+                    tr("Edges (R) + Cavity (G)"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Radius: size of the features measured, in pixels
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.5);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(0.5);
+                d.set_slider_max(30.0);
+                d.set_default(3.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Height Depth: height of white over black for Height sources, in pixels
+        params.add(
+            Params::HeightDepth,
+            tr("Height Depth"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(10.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Contrast: gain on the curvature before it is clipped to the mask range
+        params.add(
+            Params::Contrast,
+            tr("Contrast"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(2000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_CurvatureMap - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = CurvatureParams {
+            source: match params.get(Params::Source)?.as_popup()?.value() {
+                2 => Source::NormalOpenGl,
+                3 => Source::NormalDirectX,
+                _ => Source::Height,
+            },
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::Cavity,
+                3 => Output::Edges,
+                4 => Output::Both,
+                _ => Output::Curvature,
+            },
+            radius: ds.length(slider(Params::Radius)?),
+            height_depth: ds.length(slider(Params::HeightDepth)?),
+            contrast: slider(Params::Contrast)? / 100.0,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        curvature_map(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CurvatureParams {
+    pub source: Source,
+    pub output: Output,
+    /// Render pixels.
+    pub radius: f32,
+    /// Render pixels of height from black to white.
+    pub height_depth: f32,
+    /// Gain, 1 = unchanged.
+    pub contrast: f32,
+}
+
+/// Signed curvature per pixel (convex positive) before the contrast gain.
+fn curvature(input: &ImageBuf, p: &CurvatureParams) -> Vec<f32> {
+    let (w, h) = (input.width, input.height);
+    let radius = finite_or(p.radius, 1.0).clamp(0.5, MAX_RADIUS);
+    let alpha = |px: PixelF32| finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+
+    match p.source {
+        Source::Height => {
+            // 高さと周囲の平均の差 (ラプラシアンの近似)、ピクセル単位の傾きに揃える
+            let depth = finite_or(p.height_depth, 0.0).clamp(0.0, 1e4);
+            let height: Vec<f32> = input
+                .data
+                .iter()
+                .map(|&px| finite_or(luminance(px), 0.0) * alpha(px))
+                .collect();
+            let around = blur(&height, w, h, radius, Edge::Clamp);
+            height
+                .iter()
+                .zip(&around)
+                .map(|(h, a)| (h - a) * depth / radius)
+                .collect()
+        }
+        Source::NormalOpenGl | Source::NormalDirectX => {
+            // 法線の xy 成分の発散: 凸部では外向きに広がる
+            let flip = if p.source == Source::NormalOpenGl {
+                -1.0
+            } else {
+                1.0
+            };
+            let (nx, ny): (Vec<f32>, Vec<f32>) = input
+                .data
+                .iter()
+                .map(|&px| {
+                    let a = alpha(px);
+                    let x = (finite_or(px.red, 0.5) * 2.0 - 1.0) * a;
+                    let y = (finite_or(px.green, 0.5) * 2.0 - 1.0) * a * flip;
+                    let z = (finite_or(px.blue, 1.0) * 2.0 - 1.0).max(0.0) * a + (1.0 - a);
+                    let l = (x * x + y * y + z * z).sqrt();
+                    if l > 1e-6 { (x / l, y / l) } else { (0.0, 0.0) }
+                })
+                .unzip();
+            let (nx, ny) = (
+                blur(&nx, w, h, radius * 0.5, Edge::Clamp),
+                blur(&ny, w, h, radius * 0.5, Edge::Clamp),
+            );
+            let step = radius.round().max(1.0) as isize;
+            let at = |plane: &[f32], x: isize, y: isize| {
+                let x = x.clamp(0, w as isize - 1) as usize;
+                let y = y.clamp(0, h as isize - 1) as usize;
+                plane[y * w + x]
+            };
+            let mut out = Vec::with_capacity(w * h);
+            for y in 0..h as isize {
+                for x in 0..w as isize {
+                    let dx = at(&nx, x + step, y) - at(&nx, x - step, y);
+                    let dy = at(&ny, x, y + step) - at(&ny, x, y - step);
+                    out.push((dx + dy) * 0.5);
+                }
+            }
+            out
+        }
+    }
+}
+
+pub fn curvature_map(input: &ImageBuf, p: &CurvatureParams) -> ImageBuf {
+    let mut out = ImageBuf::new(input.width, input.height);
+    if input.is_empty() {
+        return out;
+    }
+    let contrast = finite_or(p.contrast, 1.0).clamp(0.0, 20.0);
+    let field = curvature(input, p);
+
+    for ((dst, src), &c) in out.data.iter_mut().zip(&input.data).zip(&field) {
+        let s = finite_or(c * contrast, 0.0).clamp(-1.0, 1.0);
+        let (convex, concave) = (s.max(0.0), (-s).max(0.0));
+        let rgb = match p.output {
+            Output::Curvature => [0.5 + 0.5 * s; 3],
+            Output::Cavity => [1.0 - concave; 3],
+            Output::Edges => [convex; 3],
+            Output::Both => [convex, concave, 0.0],
+        };
+        *dst = PixelF32 {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            alpha: finite_or(src.alpha, 0.0).clamp(0.0, 1.0),
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct CurvatureMap;
+
+impl RenderCore for CurvatureMap {
+    type Params = CurvatureParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        CurvatureParams {
+            source: match rng.popup(1, 3) {
+                2 => Source::NormalOpenGl,
+                3 => Source::NormalDirectX,
+                _ => Source::Height,
+            },
+            output: match rng.popup(1, 4) {
+                2 => Output::Cavity,
+                3 => Output::Edges,
+                4 => Output::Both,
+                _ => Output::Curvature,
+            },
+            radius: rng.slider(0.5, MAX_RADIUS),
+            height_depth: rng.slider(0.0, 1000.0),
+            contrast: rng.slider(0.0, 20.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = curvature_map(input, params);
+    }
+}
@@ -0,0 +1,668 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    DepthLayer,
+    Decoding,
+    InvertDepth,
+    EncodedNear,
+    EncodedFar,
+    RangeStart,
+    RangeEnd,
+    Output,
+    FogColor,
+    FogAmount,
+    Falloff,
+    NearTint,
+    FarTint,
+    TintAmount,
+    MatteThreshold,
+    MatteSoftness,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Adds distance fog and mattes from a depth map layer.";
+
+/// Checkout id of the depth layer (the input uses 0).
+const DEPTH_CHECKOUT_ID: i32 = 1;
+/// Upper bound of the distance parameters (scene units).
+const MAX_DISTANCE: f32 = 1_000_000.0;
+/// Smallest distance the logarithmic decoding starts from.
+const MIN_LOG_NEAR: f32 = 1e-3;
+/// Rate of the exponential falloffs, normalized so fog reaches `Fog Amount`
+/// at `Range End`.
+const FALLOFF_RATE: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoding {
+    /// Values 0..1 spread evenly from `Encoded Near` to `Encoded Far`.
+    Linear,
+    /// Values 0..1 spread geometrically, keeping precision near the camera.
+    Logarithmic,
+    /// Values are distances themselves (float Z passes).
+    RawDistance,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    Fog,
+    NearMatte,
+    FarMatte,
+    RemappedDepth,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    Linear,
+    Exponential,
+    ExponentialSquared,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Depth Layer: without one the layer's own luminance is the depth
+        params.add(Params::DepthLayer, tr("Depth Layer"), LayerDef::new())?;
+
+        // Decoding: how depth layer values turn into distances (see decode)
+        params.add_with_flags(
+            Params::Decoding,
+            tr("Decoding"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Linear"), tr("Logarithmic"), tr("Raw Distance")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Invert Depth: for maps with white = near
+        params.add(
+            Params::InvertDepth,
+            tr("Invert Depth"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Encoded Near / Far: distances stored as 0 and 1 in the depth layer
+        params.add(
+            Params::EncodedNear,
+            tr("Encoded Near"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DISTANCE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1000.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::EncodedFar,
+            tr("Encoded Far"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DISTANCE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1000.0);
+                d.set_default(100.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Range Start / End: distances remapped to 0 and 1 for fog, tint and mattes
+        params.add(
+            Params::RangeStart,
+            tr("Range Start"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DISTANCE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1000.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::RangeEnd,
+            tr("Range End"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DISTANCE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1000.0);
+                d.set_default(100.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Output: switches between the fog and matte controls (see update_param_visibility)
+        params.add_with_flags(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Fog"),
+                    tr("Near Matte"),
+                    tr("Far Matte"),
+                    tr("Remapped Depth"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::FogColor,
+            tr("Fog Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 200,
+                    green: 210,
+                    blue: 222,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Fog Amount: fog opacity at Range End
+        params.add(
+            Params::FogAmount,
+            tr("Fog Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Falloff,
+            tr("Falloff"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Linear"), tr("Exponential"), tr("Exponential Squared")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Near Tint / Far Tint: multiplied in, blended by the remapped depth
+        params.add(
+            Params::NearTint,
+            tr("Near Tint"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::FarTint,
+            tr("Far Tint"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 170,
+                    green: 190,
+                    blue: 230,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::TintAmount,
+            tr("Tint Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Matte Threshold: remapped depth where the near and far mattes split
+        params.add(
+            Params::MatteThreshold,
+            tr("Matte Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::MatteSoftness,
+            tr("Matte Softness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.05);
+                d.set_precision(3);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_DepthFog - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::Decoding | Params::Output
+                ) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では深度レイヤーをパラメータから直接取得する
+                let depth = params
+                    .checkout_at(
+                        Params::DepthLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_layer, depth.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::DepthLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        DEPTH_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let depth = cb
+                    .checkout_layer_pixels(DEPTH_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, depth.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(DEPTH_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let encoded = params.get(Params::Decoding)?.as_popup()?.value() != 3;
+    let output = params.get(Params::Output)?.as_popup()?.value();
+    let (fog, matte) = (output == 1, output == 2 || output == 3);
+    for (id, visible) in [
+        (Params::InvertDepth, encoded),
+        (Params::EncodedNear, encoded),
+        (Params::EncodedFar, encoded),
+        (Params::FogColor, fog),
+        (Params::FogAmount, fog),
+        (Params::Falloff, fog),
+        (Params::NearTint, fog),
+        (Params::FarTint, fog),
+        (Params::TintAmount, fog),
+        (Params::MatteThreshold, matte),
+        (Params::MatteSoftness, matte),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        depth: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let float =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let color = |id| -> Result<[f32; 3], Error> {
+            let c = params.get(id)?.as_color()?.float_value()?;
+            Ok([c.red, c.green, c.blue])
+        };
+
+        let settings = DepthFogParams {
+            decoding: match params.get(Params::Decoding)?.as_popup()?.value() {
+                2 => Decoding::Logarithmic,
+                3 => Decoding::RawDistance,
+                _ => Decoding::Linear,
+            },
+            invert_depth: params.get(Params::InvertDepth)?.as_checkbox()?.value(),
+            encoded_near: float(Params::EncodedNear)?,
+            encoded_far: float(Params::EncodedFar)?,
+            range_start: float(Params::RangeStart)?,
+            range_end: float(Params::RangeEnd)?,
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::NearMatte,
+                3 => Output::FarMatte,
+                4 => Output::RemappedDepth,
+                _ => Output::Fog,
+            },
+            fog_color: color(Params::FogColor)?,
+            fog_amount: float(Params::FogAmount)?,
+            falloff: match params.get(Params::Falloff)?.as_popup()?.value() {
+                2 => Falloff::Exponential,
+                3 => Falloff::ExponentialSquared,
+                _ => Falloff::Linear,
+            },
+            near_tint: color(Params::NearTint)?,
+            far_tint: color(Params::FarTint)?,
+            tint_amount: float(Params::TintAmount)?,
+            matte_threshold: float(Params::MatteThreshold)?,
+            matte_softness: float(Params::MatteSoftness)?,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        depth_fog(&input, depth, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct DepthFogParams {
+    pub decoding: Decoding,
+    pub invert_depth: bool,
+    /// Distance stored as 0 by the Linear and Logarithmic decodings.
+    pub encoded_near: f32,
+    /// Distance stored as 1 by the Linear and Logarithmic decodings.
+    pub encoded_far: f32,
+    /// Distance remapped to 0.
+    pub range_start: f32,
+    /// Distance remapped to 1.
+    pub range_end: f32,
+    pub output: Output,
+    /// Encoded fog color.
+    pub fog_color: [f32; 3],
+    pub fog_amount: f32,
+    pub falloff: Falloff,
+    /// Encoded tint multiplied in at the near end.
+    pub near_tint: [f32; 3],
+    /// Encoded tint multiplied in at the far end.
+    pub far_tint: [f32; 3],
+    pub tint_amount: f32,
+    pub matte_threshold: f32,
+    pub matte_softness: f32,
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Distance of a depth layer value (luminance, so single-channel and gray
+/// passes read the same).
+fn decode(v: f32, p: &DepthFogParams) -> f32 {
+    let near = finite_or(p.encoded_near, 0.0).clamp(0.0, MAX_DISTANCE);
+    let far = finite_or(p.encoded_far, 1.0).clamp(0.0, MAX_DISTANCE);
+    match p.decoding {
+        Decoding::RawDistance => v,
+        Decoding::Linear | Decoding::Logarithmic => {
+            let v = v.clamp(0.0, 1.0);
+            let v = if p.invert_depth { 1.0 - v } else { v };
+            if p.decoding == Decoding::Linear {
+                lerp(near, far, v)
+            } else {
+                // near·(far/near)^v: 対数空間で線形補間する
+                let (near, far) = (near.max(MIN_LOG_NEAR), far.max(MIN_LOG_NEAR));
+                lerp(near.ln(), far.ln(), v).exp()
+            }
+        }
+    }
+}
+
+/// Distance remapped so `Range Start` is 0 and `Range End` is 1, clamped.
+fn remap(distance: f32, p: &DepthFogParams) -> f32 {
+    let start = finite_or(p.range_start, 0.0);
+    let end = finite_or(p.range_end, 1.0);
+    let span = end - start;
+    if span.abs() < f32::EPSILON {
+        return if distance >= end { 1.0 } else { 0.0 };
+    }
+    finite_or((distance - start) / span, 1.0).clamp(0.0, 1.0)
+}
+
+/// Fog opacity at remapped depth `t`, 0 at `t = 0` and 1 at `t = 1`.
+fn falloff(t: f32, falloff: Falloff) -> f32 {
+    let exp = |x: f32| (1.0 - (-FALLOFF_RATE * x).exp()) / (1.0 - (-FALLOFF_RATE).exp());
+    match falloff {
+        Falloff::Linear => t,
+        Falloff::Exponential => exp(t),
+        Falloff::ExponentialSquared => exp(t * t),
+    }
+}
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    if e1 <= e0 {
+        return if x < e0 { 0.0 } else { 1.0 };
+    }
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Remapped depth of every input pixel. Depth maps of another size are
+/// stretched; transparent depth pixels (sky, empty background) count as far.
+fn remapped_depth(input: &ImageBuf, depth: Option<&ImageBuf>, p: &DepthFogParams) -> Vec<f32> {
+    let (width, height) = (input.width, input.height);
+    let map = depth.filter(|m| !m.is_empty()).unwrap_or(input);
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let mx = x * map.width / width.max(1);
+            let my = y * map.height / height.max(1);
+            let px = map.get_clamped(mx as isize, my as isize);
+            out.push(if finite_or(px.alpha, 0.0) <= 0.0 {
+                1.0
+            } else {
+                remap(decode(finite_or(luminance(px), 0.0), p), p)
+            });
+        }
+    }
+    out
+}
+
+fn depth_fog(input: &ImageBuf, depth: Option<&ImageBuf>, p: &DepthFogParams) -> ImageBuf {
+    let mut out = ImageBuf::new(input.width, input.height);
+    if input.is_empty() {
+        return out;
+    }
+    let remapped = remapped_depth(input, depth, p);
+
+    let fog_color = p.fog_color.map(|c| finite_or(c, 0.0));
+    let fog_amount = finite_or(p.fog_amount, 0.0).clamp(0.0, 1.0);
+    let near_tint = p.near_tint.map(|c| finite_or(c, 1.0));
+    let far_tint = p.far_tint.map(|c| finite_or(c, 1.0));
+    let tint_amount = finite_or(p.tint_amount, 0.0).clamp(0.0, 1.0);
+    let threshold = finite_or(p.matte_threshold, 0.5).clamp(0.0, 1.0);
+    let softness = finite_or(p.matte_softness, 0.0).clamp(0.0, 1.0);
+
+    for ((o, &px), &t) in out.data.iter_mut().zip(&input.data).zip(&remapped) {
+        *o = match p.output {
+            Output::Fog => {
+                let fog = falloff(t, p.falloff) * fog_amount;
+                let grade = |c: usize, v: f32| {
+                    let tint = lerp(near_tint[c], far_tint[c], t);
+                    let v = v * lerp(1.0, tint, tint_amount);
+                    lerp(v, fog_color[c], fog)
+                };
+                PixelF32 {
+                    red: grade(0, px.red),
+                    green: grade(1, px.green),
+                    blue: grade(2, px.blue),
+                    alpha: px.alpha,
+                }
+            }
+            Output::NearMatte | Output::FarMatte => {
+                let far = smoothstep(threshold - softness * 0.5, threshold + softness * 0.5, t);
+                let m = if p.output == Output::FarMatte {
+                    far
+                } else {
+                    1.0 - far
+                };
+                PixelF32 {
+                    red: m,
+                    green: m,
+                    blue: m,
+                    alpha: 1.0,
+                }
+            }
+            Output::RemappedDepth => PixelF32 {
+                red: t,
+                green: t,
+                blue: t,
+                alpha: 1.0,
+            },
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use the input itself as the
+/// depth map.
+pub struct DepthFog;
+
+impl RenderCore for DepthFog {
+    type Params = DepthFogParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let mut color = || [rng.unit(), rng.unit(), rng.unit()];
+        let (fog_color, near_tint, far_tint) = (color(), color(), color());
+        DepthFogParams {
+            decoding: match rng.popup(1, 3) {
+                2 => Decoding::Logarithmic,
+                3 => Decoding::RawDistance,
+                _ => Decoding::Linear,
+            },
+            invert_depth: rng.chance(0.5),
+            encoded_near: rng.slider(0.0, MAX_DISTANCE),
+            encoded_far: rng.slider(0.0, MAX_DISTANCE),
+            range_start: rng.slider(0.0, MAX_DISTANCE),
+            range_end: rng.slider(0.0, MAX_DISTANCE),
+            output: match rng.popup(1, 4) {
+                2 => Output::NearMatte,
+                3 => Output::FarMatte,
+                4 => Output::RemappedDepth,
+                _ => Output::Fog,
+            },
+            fog_color,
+            fog_amount: rng.slider(0.0, 1.0),
+            falloff: match rng.popup(1, 3) {
+                2 => Falloff::Exponential,
+                3 => Falloff::ExponentialSquared,
+                _ => Falloff::Linear,
+            },
+            near_tint,
+            far_tint,
+            tint_amount: rng.slider(0.0, 1.0),
+            matte_threshold: rng.slider(0.0, 1.0),
+            matte_softness: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let depth = (frame % 2 == 1).then_some(input);
+        *output = depth_fog(input, depth, params);
+    }
+}
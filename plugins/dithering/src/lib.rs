@@ -0,0 +1,598 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::dither::{BLUE_NOISE_SIZE, bayer, blue_noise};
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Method,
+    Strength,
+    PixelSize,
+    Palette,
+    Levels,
+    ColorCount,
+    Color1,
+    Color2,
+    Color3,
+    Color4,
+    Color5,
+    Color6,
+    Color7,
+    Color8,
+}
+
+/// Palette color parameter ids, in UI order.
+const COLORS: [Params; 8] = [
+    Params::Color1,
+    Params::Color2,
+    Params::Color3,
+    Params::Color4,
+    Params::Color5,
+    Params::Color6,
+    Params::Color7,
+    Params::Color8,
+];
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Dithers the layer to a limited set of levels or colors.";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Method {
+    Bayer4,
+    Bayer8,
+    BlueNoise,
+    FloydSteinberg,
+    Atkinson,
+}
+
+#[derive(Clone, Debug)]
+pub enum Palette {
+    /// Evenly spaced levels per RGB channel.
+    Levels(u32),
+    /// Explicit sRGB colors, matched in OKLab.
+    Custom(Vec<[f32; 3]>),
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Bayer 4x4"),
+                    tr("Bayer 8x8"),
+                    tr("Blue Noise"),
+                    tr("Floyd-Steinberg"),
+                    tr("Atkinson"),
+                ]);
+                d.set_default(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(2.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Pixel Size: dither on a coarser grid for a pixel-art look
+        params.add(
+            Params::PixelSize,
+            tr("Pixel Size"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(64);
+                d.set_slider_min(1);
+                d.set_slider_max(16);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Palette: Levels shows the level count, Custom the color list (see update_palette_visibility)
+        params.add_with_flags(
+            Params::Palette,
+            tr("Palette"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Levels"), tr("Custom")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Levels,
+            tr("Levels"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(256);
+                d.set_slider_min(2);
+                d.set_slider_max(16);
+                d.set_default(4);
+            }),
+        )?;
+
+        params.add_with_flags(
+            Params::ColorCount,
+            tr("Color Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(COLORS.len() as i32);
+                d.set_slider_min(2);
+                d.set_slider_max(COLORS.len() as i32);
+                d.set_default(4);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // 初期パレットは 4 階調のグリーン系（ゲームボーイ風）
+        params.add(
+            Params::Color1,
+            tr("Color 1"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 15,
+                    green: 56,
+                    blue: 15,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color2,
+            tr("Color 2"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 48,
+                    green: 98,
+                    blue: 48,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color3,
+            tr("Color 3"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 139,
+                    green: 172,
+                    blue: 15,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color4,
+            tr("Color 4"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 155,
+                    green: 188,
+                    blue: 15,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color5,
+            tr("Color 5"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color6,
+            tr("Color 6"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color7,
+            tr("Color 7"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 0,
+                    blue: 77,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Color8,
+            tr("Color 8"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 41,
+                    green: 173,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Dithering - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_palette_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::Palette | Params::ColorCount
+                ) =>
+            {
+                update_palette_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_palette_visibility(
+    in_data: &InData,
+    params: &mut Parameters<Params>,
+) -> Result<(), Error> {
+    let custom = params.get(Params::Palette)?.as_popup()?.value() == 2;
+    let count = params.get(Params::ColorCount)?.as_slider()?.value() as usize;
+
+    let mut set = |id: Params, hidden: bool| ui::set_param_visible(in_data, params, id, !hidden);
+    set(Params::Levels, custom)?;
+    set(Params::ColorCount, !custom)?;
+    for (i, id) in COLORS.iter().enumerate() {
+        set(*id, !custom || i >= count)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+
+        let palette = if params.get(Params::Palette)?.as_popup()?.value() == 2 {
+            let count = params.get(Params::ColorCount)?.as_slider()?.value() as usize;
+            let mut colors = Vec::with_capacity(count);
+            for id in COLORS.iter().take(count) {
+                let c = params.get(*id)?.as_color()?.float_value()?;
+                colors.push([c.red, c.green, c.blue]);
+            }
+            Palette::Custom(colors)
+        } else {
+            Palette::Levels(params.get(Params::Levels)?.as_slider()?.value() as u32)
+        };
+        let pixel_size = params.get(Params::PixelSize)?.as_slider()?.value() as f32;
+        let settings = DitherParams {
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                1 => Method::Bayer4,
+                2 => Method::Bayer8,
+                4 => Method::FloydSteinberg,
+                5 => Method::Atkinson,
+                _ => Method::BlueNoise,
+            },
+            strength: params.get(Params::Strength)?.as_float_slider()?.value() as f32,
+            pixel_size: ds.length(pixel_size).round().max(1.0) as usize,
+            palette,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = dither(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct DitherParams {
+    pub method: Method,
+    /// Dither amplitude; 1 spans one palette step.
+    pub strength: f32,
+    /// Dither cell size in render pixels.
+    pub pixel_size: usize,
+    pub palette: Palette,
+}
+
+/// Nearest-color lookup for a [`Palette`].
+enum Quantizer {
+    Levels(f32),
+    Custom(Vec<([f32; 3], Oklab)>),
+}
+
+impl Quantizer {
+    fn new(palette: &Palette) -> Self {
+        match palette {
+            Palette::Levels(n) => Self::Levels((*n).clamp(2, 65536) as f32 - 1.0),
+            Palette::Custom(colors) if !colors.is_empty() => {
+                Self::Custom(colors.iter().map(|c| (*c, Oklab::from_srgb(*c))).collect())
+            }
+            Palette::Custom(_) => Self::Levels(1.0),
+        }
+    }
+
+    /// Typical distance between neighbouring palette entries, used to scale ordered dither.
+    fn spread(&self) -> f32 {
+        match self {
+            Self::Levels(steps) => 1.0 / steps,
+            Self::Custom(colors) => 1.0 / (colors.len().max(2) - 1) as f32,
+        }
+    }
+
+    fn nearest(&self, rgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            Self::Levels(steps) => rgb.map(|v| (v.clamp(0.0, 1.0) * steps).round() / steps),
+            Self::Custom(colors) => {
+                let lab = Oklab::from_srgb(rgb.map(|v| v.clamp(0.0, 1.0)));
+                let dist = |o: &Oklab| {
+                    (o.l - lab.l).powi(2) + (o.a - lab.a).powi(2) + (o.b - lab.b).powi(2)
+                };
+                colors
+                    .iter()
+                    .min_by(|a, b| dist(&a.1).total_cmp(&dist(&b.1)))
+                    .map_or(rgb, |c| c.0)
+            }
+        }
+    }
+}
+
+/// Dithers `input` on a grid of `pixel_size` cells; alpha is averaged but not quantized.
+pub fn dither(input: &ImageBuf, p: &DitherParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut output = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return output;
+    }
+
+    let size = p.pixel_size.clamp(1, w.max(h));
+    let (gw, gh) = (w.div_ceil(size), h.div_ceil(size));
+    let quantizer = Quantizer::new(&p.palette);
+    let strength = if p.strength.is_nan() {
+        0.0
+    } else {
+        p.strength.clamp(0.0, 2.0)
+    };
+
+    // セルごとの平均色（NaN は 0 として扱う）
+    let clean = |v: f32| if v.is_nan() { 0.0 } else { v };
+    let mut cells = vec![[0.0f32; 4]; gw * gh];
+    for (ci, cell) in cells.iter_mut().enumerate() {
+        let (cx, cy) = (ci % gw * size, ci / gw * size);
+        let (x1, y1) = ((cx + size).min(w), (cy + size).min(h));
+        let mut sum = [0.0f32; 4];
+        for y in cy..y1 {
+            for x in cx..x1 {
+                let px = input.get(x as isize, y as isize);
+                sum[0] += clean(px.red);
+                sum[1] += clean(px.green);
+                sum[2] += clean(px.blue);
+                sum[3] += clean(px.alpha);
+            }
+        }
+        let n = ((x1 - cx) * (y1 - cy)) as f32;
+        *cell = sum.map(|s| s / n);
+    }
+
+    let mut result = vec![[0.0f32; 3]; gw * gh];
+    match p.method {
+        Method::Bayer4 | Method::Bayer8 | Method::BlueNoise => {
+            let amplitude = strength * quantizer.spread();
+            let noise = matches!(p.method, Method::BlueNoise).then(blue_noise);
+            for (ci, (cell, out)) in cells.iter().zip(result.iter_mut()).enumerate() {
+                let (gx, gy) = (ci % gw, ci / gw);
+                let t = match (p.method, noise) {
+                    (Method::Bayer4, _) => bayer(gx % 4, gy % 4, 2),
+                    (_, Some(map)) => {
+                        map[(gy % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + gx % BLUE_NOISE_SIZE]
+                    }
+                    _ => bayer(gx % 8, gy % 8, 3),
+                };
+                let bias = (t - 0.5) * amplitude;
+                *out = quantizer.nearest([cell[0] + bias, cell[1] + bias, cell[2] + bias]);
+            }
+        }
+        Method::FloydSteinberg | Method::Atkinson => {
+            // (dx, dy, weight)：dx は走査方向に対する向き
+            let taps: &[(isize, usize, f32)] = match p.method {
+                Method::FloydSteinberg => &[
+                    (1, 0, 7.0 / 16.0),
+                    (-1, 1, 3.0 / 16.0),
+                    (0, 1, 5.0 / 16.0),
+                    (1, 1, 1.0 / 16.0),
+                ],
+                _ => &[
+                    (1, 0, 1.0 / 8.0),
+                    (2, 0, 1.0 / 8.0),
+                    (-1, 1, 1.0 / 8.0),
+                    (0, 1, 1.0 / 8.0),
+                    (1, 1, 1.0 / 8.0),
+                    (0, 2, 1.0 / 8.0),
+                ],
+            };
+            let mut work: Vec<[f32; 3]> = cells.iter().map(|c| [c[0], c[1], c[2]]).collect();
+            for gy in 0..gh {
+                // 蛇行走査で方向性のアーティファクトを抑える
+                let reverse = gy % 2 == 1;
+                for i in 0..gw {
+                    let gx = if reverse { gw - 1 - i } else { i };
+                    let v = work[gy * gw + gx].map(|c| c.clamp(0.0, 1.0));
+                    let q = quantizer.nearest(v);
+                    result[gy * gw + gx] = q;
+
+                    let err: [f32; 3] = std::array::from_fn(|c| (v[c] - q[c]) * strength);
+                    for &(dx, dy, weight) in taps {
+                        let nx = gx as isize + if reverse { -dx } else { dx };
+                        let ny = gy + dy;
+                        if nx < 0 || nx as usize >= gw || ny >= gh {
+                            continue;
+                        }
+                        let target = &mut work[ny * gw + nx as usize];
+                        for c in 0..3 {
+                            target[c] += err[c] * weight;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for y in 0..h {
+        for x in 0..w {
+            let ci = (y / size) * gw + x / size;
+            let rgb = result[ci];
+            output.set(
+                x,
+                y,
+                PixelF32 {
+                    red: rgb[0],
+                    green: rgb[1],
+                    blue: rgb[2],
+                    alpha: cells[ci][3],
+                },
+            );
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct Dithering;
+
+impl RenderCore for Dithering {
+    type Params = DitherParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        DitherParams {
+            method: match rng.popup(1, 5) {
+                1 => Method::Bayer4,
+                2 => Method::Bayer8,
+                4 => Method::FloydSteinberg,
+                5 => Method::Atkinson,
+                _ => Method::BlueNoise,
+            },
+            strength: rng.slider(0.0, 2.0),
+            pixel_size: rng.int(1, 64) as usize,
+            palette: if rng.chance(0.5) {
+                let count = rng.int(2, COLORS.len() as i32) as usize;
+                Palette::Custom(
+                    (0..count)
+                        .map(|_| [rng.unit(), rng.unit(), rng.unit()])
+                        .collect(),
+                )
+            } else {
+                Palette::Levels(rng.int(2, 256) as u32)
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = dither(input, params);
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<domain_warp_noise::DomainWarpNoise>();
+}
@@ -0,0 +1,541 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{Fbm, MAX_DETAIL, NoiseType, Remap, hash};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NoiseType,
+    Size,
+    Detail,
+    Roughness,
+    Lacunarity,
+    WarpDepth,
+    WarpAmount,
+    WarpScale,
+    Evolution,
+    EvolutionSpeed,
+    Tileable,
+    Output,
+    Contrast,
+    Brightness,
+    Invert,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Generates domain-warped fBm noise.";
+
+/// Upper bound of `Warp Depth`; each level adds two fBm evaluations per pixel.
+const MAX_WARP_DEPTH: i32 = 4;
+/// Hash salt deriving the seeds of the warp fields.
+const WARP_SALT: u32 = 0x3a7f;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    Value,
+    /// Final value in R, first and last warp magnitudes in G and B.
+    Layers,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::NoiseType,
+            tr("Noise Type"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Perlin"), tr("Simplex"), tr("Value")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Size: base octave cell size in pixels
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(4.0);
+                d.set_slider_max(1000.0);
+                d.set_default(200.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Detail,
+            tr("Detail"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DETAIL);
+                d.set_slider_min(0.0);
+                d.set_slider_max(MAX_DETAIL);
+                d.set_default(4.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Roughness,
+            tr("Roughness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Lacunarity,
+            tr("Lacunarity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(4.0);
+                d.set_default(2.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Warp Depth: how many times the noise is fed back into its own coordinates
+        params.add(
+            Params::WarpDepth,
+            tr("Warp Depth"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(MAX_WARP_DEPTH);
+                d.set_slider_min(0);
+                d.set_slider_max(MAX_WARP_DEPTH);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Warp Amount: displacement of each warp level in base cells
+        params.add(
+            Params::WarpAmount,
+            tr("Warp Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(8.0);
+                d.set_default(2.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Warp Scale: frequency of the warp fields relative to the base noise
+        params.add(
+            Params::WarpScale,
+            tr("Warp Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.01);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.1);
+                d.set_slider_max(4.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Evolution,
+            tr("Evolution"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100000.0);
+                d.set_valid_max(100000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Evolution Speed: evolution added per second of layer time
+        params.add(
+            Params::EvolutionSpeed,
+            tr("Evolution Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(-2.0);
+                d.set_slider_max(2.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Tileable: rounds Size and Warp Scale so the layer holds whole periods
+        params.add(
+            Params::Tileable,
+            tr("Tileable"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Value"), tr("Layers")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Contrast: gain around mid gray in percent
+        params.add(
+            Params::Contrast,
+            tr("Contrast"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Brightness,
+            tr("Brightness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(-100.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Invert,
+            tr("Invert"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_DomainWarpNoise - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer: _,
+                out_layer,
+            } => {
+                self.do_render(in_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let Some(out_layer) = out_layer_opt {
+                    self.do_render(in_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let seconds = in_data.current_time() as f64 / in_data.time_scale().max(1) as f64;
+        let slider = |id| -> Result<f64, Error> { Ok(params.get(id)?.as_float_slider()?.value()) };
+
+        let evolution = slider(Params::Evolution)?;
+        let speed = slider(Params::EvolutionSpeed)?;
+        let settings = WarpParams {
+            kind: match params.get(Params::NoiseType)?.as_popup()?.value() {
+                2 => NoiseType::Simplex,
+                3 => NoiseType::Value,
+                _ => NoiseType::Perlin,
+            },
+            size: ds.length(slider(Params::Size)? as f32),
+            detail: slider(Params::Detail)? as f32,
+            roughness: slider(Params::Roughness)? as f32,
+            lacunarity: slider(Params::Lacunarity)? as f32,
+            depth: params.get(Params::WarpDepth)?.as_slider()?.value() as u32,
+            amount: slider(Params::WarpAmount)? as f32,
+            warp_scale: slider(Params::WarpScale)? as f32,
+            evolution: (evolution + speed * seconds) as f32,
+            tileable: params.get(Params::Tileable)?.as_checkbox()?.value(),
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::Layers,
+                _ => Output::Value,
+            },
+            remap: Remap {
+                contrast: slider(Params::Contrast)? as f32 / 100.0,
+                brightness: slider(Params::Brightness)? as f32 / 100.0,
+                invert: params.get(Params::Invert)?.as_checkbox()?.value(),
+            },
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+        };
+
+        let (width, height) = (out_layer.width(), out_layer.height());
+        domain_warp(width, height, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct WarpParams {
+    pub kind: NoiseType,
+    /// Base cell size in render pixels.
+    pub size: f32,
+    pub detail: f32,
+    pub roughness: f32,
+    pub lacunarity: f32,
+    /// Warp levels applied before the final lookup.
+    pub depth: u32,
+    /// Displacement per warp level in base cells.
+    pub amount: f32,
+    /// Warp field frequency relative to the base noise.
+    pub warp_scale: f32,
+    /// Position along the third noise axis.
+    pub evolution: f32,
+    pub tileable: bool,
+    pub output: Output,
+    pub remap: Remap,
+    pub seed: u32,
+}
+
+/// fBm sampled at `scale` times the base cell frequency; when tiled the scale
+/// is rounded so the field still repeats `period` times across the layer.
+struct Field {
+    fbm: Fbm,
+    scale: [f32; 2],
+}
+
+impl Field {
+    fn new(p: &WarpParams, seed: u32, scale: f32, period: [u32; 2]) -> Self {
+        let (scale, period) = if period == [0, 0] {
+            ([scale; 2], period)
+        } else {
+            let scaled = period.map(|t| (t as f32 * scale).round().clamp(1.0, 65536.0) as u32);
+            (
+                std::array::from_fn(|a| scaled[a] as f32 / period[a] as f32),
+                scaled,
+            )
+        };
+        Self {
+            fbm: Fbm {
+                kind: p.kind,
+                detail: p.detail,
+                roughness: p.roughness,
+                lacunarity: p.lacunarity,
+                seed,
+                period,
+            },
+            scale,
+        }
+    }
+
+    fn sample(&self, [x, y]: [f32; 2], z: f32) -> f32 {
+        let v = self.fbm.sample([x * self.scale[0], y * self.scale[1], z]);
+        if v.is_nan() { 0.0 } else { v.clamp(-1.0, 1.0) }
+    }
+}
+
+pub fn domain_warp(width: usize, height: usize, p: &WarpParams) -> ImageBuf {
+    let mut out = ImageBuf::new(width, height);
+    let size = finite_or(p.size, 200.0).max(0.01);
+    let period = if p.tileable {
+        let cells = |n: usize| (n as f32 / size).round().clamp(1.0, 65536.0) as u32;
+        [cells(width), cells(height)]
+    } else {
+        [0, 0]
+    };
+    // タイル時はレイヤー幅にちょうど period セルが収まるように拡大率を合わせる
+    let cell = |n: usize, t: u32| {
+        if t == 0 {
+            size
+        } else {
+            n.max(1) as f32 / t as f32
+        }
+    };
+    let (cell_x, cell_y) = (cell(width, period[0]), cell(height, period[1]));
+
+    let depth = p.depth.min(MAX_WARP_DEPTH as u32);
+    let amount = finite_or(p.amount, 0.0).clamp(-1000.0, 1000.0);
+    let warp_scale = finite_or(p.warp_scale, 1.0).clamp(0.01, 100.0);
+    let z = finite_or(p.evolution, 0.0);
+    let base = Field::new(p, p.seed, 1.0, period);
+    // 各段は x / y 用に別シードの fBm を 2 本持つ
+    let warps: Vec<[Field; 2]> = (0..depth)
+        .map(|level| {
+            std::array::from_fn(|axis| {
+                let seed = hash(p.seed, level * 2 + axis as u32, WARP_SALT);
+                Field::new(p, seed, warp_scale, period)
+            })
+        })
+        .collect();
+
+    for y in 0..height {
+        for x in 0..width {
+            let q = [(x as f32 + 0.5) / cell_x, (y as f32 + 0.5) / cell_y];
+            let mut offset = [0.0f32; 2];
+            let (mut first, mut last) = (0.0, 0.0);
+            for (level, fields) in warps.iter().enumerate() {
+                // 前段のずれを加えた座標でノイズを引き、次段のずれにする
+                let warped = [q[0] + amount * offset[0], q[1] + amount * offset[1]];
+                offset = [fields[0].sample(warped, z), fields[1].sample(warped, z)];
+                let magnitude =
+                    (offset[0].hypot(offset[1]) * std::f32::consts::FRAC_1_SQRT_2).min(1.0);
+                if level == 0 {
+                    first = magnitude;
+                }
+                last = magnitude;
+            }
+            let value =
+                0.5 + 0.5 * base.sample([q[0] + amount * offset[0], q[1] + amount * offset[1]], z);
+
+            let [red, green, blue] = match p.output {
+                Output::Value => [value; 3],
+                Output::Layers => [value, first, last],
+            }
+            .map(|v| p.remap.apply(v));
+            out.set(
+                x,
+                y,
+                PixelF32 {
+                    red,
+                    green,
+                    blue,
+                    alpha: 1.0,
+                },
+            );
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct DomainWarpNoise;
+
+impl RenderCore for DomainWarpNoise {
+    type Params = WarpParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        WarpParams {
+            kind: match rng.popup(1, 3) {
+                2 => NoiseType::Simplex,
+                3 => NoiseType::Value,
+                _ => NoiseType::Perlin,
+            },
+            size: rng.slider(1.0, 10000.0),
+            detail: rng.slider(0.0, MAX_DETAIL),
+            roughness: rng.slider(0.0, 1.0),
+            lacunarity: rng.slider(0.0, 1000.0),
+            depth: rng.int(0, MAX_WARP_DEPTH) as u32,
+            amount: rng.slider(-1000.0, 1000.0),
+            warp_scale: rng.slider(0.01, 100.0),
+            evolution: rng.slider(-100000.0, 100000.0),
+            tileable: rng.chance(0.5),
+            output: if rng.chance(0.5) {
+                Output::Value
+            } else {
+                Output::Layers
+            },
+            remap: Remap {
+                contrast: rng.slider(0.0, 10.0),
+                brightness: rng.slider(-1.0, 1.0),
+                invert: rng.chance(0.5),
+            },
+            seed: rng.popup(0, 10000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = domain_warp(input.width, input.height, params);
+    }
+}
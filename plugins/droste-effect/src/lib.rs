@@ -0,0 +1,428 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f64::consts::TAU;
+
+use ae::pf::*;
+use utils::complex::C64;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Edge, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Center,
+    InnerRadius,
+    OuterRadius,
+    Periodicity,
+    Branches,
+    Rotation,
+    Zoom,
+    Supersampling,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Creates a recursive Droste spiral zoom.";
+
+/// Upper bound of `Supersampling` (samples per axis).
+const MAX_SUPERSAMPLING: i32 = 8;
+const MAX_PERIODICITY: i32 = 8;
+const MAX_BRANCHES: i32 = 8;
+/// Nested copies looked through where the layer is transparent.
+const MAX_LEVELS: usize = 8;
+/// Smallest outer / inner radius ratio (keeps the log period away from zero).
+const MIN_RATIO: f64 = 1.01;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Inner Radius: where the nested copy of the layer begins, in pixels
+        params.add(
+            Params::InnerRadius,
+            tr("Inner Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(1000.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Outer Radius: the copy repeats each time the radius grows by Outer / Inner
+        params.add(
+            Params::OuterRadius,
+            tr("Outer Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(2000.0);
+                d.set_default(400.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Periodicity: copies met along one turn (negative reverses the spiral)
+        params.add(
+            Params::Periodicity,
+            tr("Periodicity"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(-MAX_PERIODICITY);
+                d.set_valid_max(MAX_PERIODICITY);
+                d.set_slider_min(-MAX_PERIODICITY);
+                d.set_slider_max(MAX_PERIODICITY);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Branches: interleaved spiral arms (0 = concentric, no spiral)
+        params.add(
+            Params::Branches,
+            tr("Branches"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(-MAX_BRANCHES);
+                d.set_valid_max(MAX_BRANCHES);
+                d.set_slider_min(-MAX_BRANCHES);
+                d.set_slider_max(MAX_BRANCHES);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Zoom: percent; zooming by Outer / Inner loops back to the same picture
+        params.add(
+            Params::Zoom,
+            tr("Zoom"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.01);
+                d.set_valid_max(1000000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(1000.0);
+                d.set_default(100.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Supersampling: samples per axis and pixel (the center is heavily minified)
+        params.add(
+            Params::Supersampling,
+            tr("Supersampling"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_SUPERSAMPLING);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_SUPERSAMPLING);
+                d.set_default(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_DrosteEffect - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = DrosteParams {
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            inner_radius: ds
+                .length(params.get(Params::InnerRadius)?.as_float_slider()?.value() as f32),
+            outer_radius: ds
+                .length(params.get(Params::OuterRadius)?.as_float_slider()?.value() as f32),
+            periodicity: params.get(Params::Periodicity)?.as_slider()?.value(),
+            branches: params.get(Params::Branches)?.as_slider()?.value(),
+            rotation: params
+                .get(Params::Rotation)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            zoom: params.get(Params::Zoom)?.as_float_slider()?.value() as f32 / 100.0,
+            supersampling: params.get(Params::Supersampling)?.as_slider()?.value() as u32,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = droste(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct DrosteParams {
+    /// Center in render pixels.
+    pub center: (f32, f32),
+    /// Radii of the repeated annulus in render pixels.
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub periodicity: i32,
+    pub branches: i32,
+    /// Rotation in radians.
+    pub rotation: f32,
+    /// Magnification (1 = unchanged).
+    pub zoom: f32,
+    /// Samples per axis and pixel.
+    pub supersampling: u32,
+    pub filter: Filter,
+}
+
+/// Output -> source mapping in log-polar space.
+struct Spiral {
+    center: C64,
+    inner: f64,
+    /// `ln(outer / inner)`: the scale period of the picture.
+    period: f64,
+    /// Output log -> source log multiplier (see [`Spiral::new`]).
+    twist: C64,
+    /// Added to the output log: zoom (real) and rotation (imaginary).
+    shift: C64,
+}
+
+impl Spiral {
+    fn new(width: usize, height: usize, p: &DrosteParams) -> Self {
+        let (w, h) = (width as f64, height as f64);
+        let r1 = finite_or(p.inner_radius as f64, 100.0).clamp(1e-3, 1e6);
+        let r2 = finite_or(p.outer_radius as f64, 400.0).clamp(1e-3, 1e6);
+        let (inner, outer) = (r1.min(r2), r1.max(r2).max(r1.min(r2) * MIN_RATIO));
+        let period = (outer / inner).ln();
+
+        // 出力の 1 周 (2πi) が元画像の格子 (p1 周 + p2 段の拡大) に一致するよう傾ける
+        let p1 = p.periodicity.clamp(-MAX_PERIODICITY, MAX_PERIODICITY) as f64;
+        let p2 = p.branches.clamp(-MAX_BRANCHES, MAX_BRANCHES) as f64;
+        let twist = if p1 == 0.0 && p2 == 0.0 {
+            C64::ONE
+        } else {
+            C64::new(p1, -p2 * period / TAU)
+        };
+        let zoom = finite_or(p.zoom as f64, 1.0).clamp(1e-4, 1e4);
+
+        Self {
+            center: C64::new(
+                finite_or(p.center.0 as f64, w * 0.5).clamp(-1e6, 1e6),
+                finite_or(p.center.1 as f64, h * 0.5).clamp(-1e6, 1e6),
+            ),
+            inner,
+            period,
+            twist,
+            shift: C64::new(-zoom.ln(), -finite_or(p.rotation as f64, 0.0)),
+        }
+    }
+
+    /// Source log coordinate of output point `z`, its real part wrapped into one period.
+    fn source_log(&self, z: C64) -> Option<C64> {
+        let w = ((z - self.center).scale(1.0 / self.inner).ln() + self.shift) * self.twist;
+        w.is_finite()
+            .then(|| C64::new(w.re.rem_euclid(self.period), w.im))
+    }
+
+    /// Source position of log coordinate `w`, `level` copies further out.
+    fn source(&self, w: C64, level: usize) -> (f32, f32) {
+        let w = C64::new(w.re + level as f64 * self.period, w.im);
+        let z = self.center + w.exp().scale(self.inner);
+        (z.re as f32, z.im as f32)
+    }
+}
+
+fn droste(input: &ImageBuf, p: &DrosteParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let premul = sampling::premultiply(input);
+    let spiral = Spiral::new(width, height, p);
+    let edges = (Edge::None, Edge::None);
+    let n = p.supersampling.clamp(1, MAX_SUPERSAMPLING as u32);
+    let weight = 1.0 / (n * n) as f32;
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut acc = [0.0f32; 4];
+            for j in 0..n {
+                for i in 0..n {
+                    let z = C64::new(
+                        x as f64 + (i as f64 + 0.5) / n as f64 - 0.5,
+                        y as f64 + (j as f64 + 0.5) / n as f64 - 0.5,
+                    );
+                    let Some(w) = spiral.source_log(z) else {
+                        continue;
+                    };
+                    // 透明な部分からはひと回り外側のコピー（入れ子の絵）を覗く
+                    let mut px = [0.0f32; 4];
+                    for level in 0..MAX_LEVELS {
+                        let (sx, sy) = spiral.source(w, level);
+                        if !sx.is_finite() || !sy.is_finite() {
+                            break;
+                        }
+                        let (sx, sy) = (sx.clamp(-1e7, 1e7), sy.clamp(-1e7, 1e7));
+                        let s = sampling::sample_edge(&premul, sx, sy, p.filter, edges);
+                        let k = 1.0 - px[3];
+                        px[0] += s.red * k;
+                        px[1] += s.green * k;
+                        px[2] += s.blue * k;
+                        px[3] += s.alpha.clamp(0.0, 1.0) * k;
+                        if px[3] >= 0.999 {
+                            break;
+                        }
+                    }
+                    for (a, v) in acc.iter_mut().zip(px) {
+                        *a += v * weight;
+                    }
+                }
+            }
+            let px = PixelF32 {
+                red: acc[0],
+                green: acc[1],
+                blue: acc[2],
+                alpha: acc[3],
+            };
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct DrosteEffect;
+
+impl RenderCore for DrosteEffect {
+    type Params = DrosteParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        DrosteParams {
+            center: (rng.slider(-100.0, 300.0), rng.slider(-100.0, 300.0)),
+            inner_radius: rng.slider(1.0, 10000.0),
+            outer_radius: rng.slider(1.0, 10000.0),
+            periodicity: rng.int(-MAX_PERIODICITY, MAX_PERIODICITY),
+            branches: rng.int(-MAX_BRANCHES, MAX_BRANCHES),
+            rotation: rng.slider(-720.0, 720.0).to_radians(),
+            zoom: rng.slider(0.01, 1000000.0) / 100.0,
+            supersampling: rng.int(1, MAX_SUPERSAMPLING) as u32,
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = droste(input, params);
+    }
+}
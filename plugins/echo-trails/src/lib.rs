@@ -0,0 +1,516 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::history::{History, HistoryKey};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Mode,
+    Echoes,
+    EchoTime,
+    StartingIntensity,
+    Decay,
+    Blend,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: accumulated feedback states for the Infinite Feedback mode.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History<ImageBuf>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Leaves echo trails of earlier frames.";
+
+/// Upper bound of echoes checked out per render (the current frame included).
+const MAX_ECHOES: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Composites `Echoes` frames checked out `Echo Time` apart.
+    Taps,
+    /// Composites the current frame over the decayed result of the previous one.
+    Feedback,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blend {
+    Add,
+    Screen,
+    Maximum,
+    /// Weighted mean of the taps.
+    Average,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Mode: Infinite Feedback hides the tap controls (see update_tap_visibility)
+        params.add_with_flags(
+            Params::Mode,
+            tr("Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Frame Taps"), tr("Infinite Feedback")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Echoes: frames composited, the current one included
+        params.add(
+            Params::Echoes,
+            tr("Echoes"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ECHOES as i32);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_ECHOES as i32);
+                d.set_default(4);
+            }),
+        )?;
+
+        // Echo Time: frames between taps (negative looks ahead)
+        params.add(
+            Params::EchoTime,
+            tr("Echo Time"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-600.0);
+                d.set_valid_max(600.0);
+                d.set_slider_min(-30.0);
+                d.set_slider_max(30.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::StartingIntensity,
+            tr("Starting Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Decay: intensity multiplier per echo (per frame in feedback mode)
+        params.add(
+            Params::Decay,
+            tr("Decay"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.6);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Blend,
+            tr("Blend"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Add"), tr("Screen"), tr("Maximum"), tr("Average")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_EchoTrails - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn mode(params: &Parameters<Params>) -> Result<Mode, Error> {
+    Ok(match params.get(Params::Mode)?.as_popup()?.value() {
+        2 => Mode::Feedback,
+        _ => Mode::Taps,
+    })
+}
+
+/// Frames checked out for the current mode.
+fn tap_count(params: &Parameters<Params>) -> Result<usize, Error> {
+    Ok(match mode(params)? {
+        Mode::Feedback => 1,
+        Mode::Taps => {
+            let n = params.get(Params::Echoes)?.as_slider()?.value() as usize;
+            n.clamp(1, MAX_ECHOES)
+        }
+    })
+}
+
+fn update_tap_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let feedback = mode(params)? == Mode::Feedback;
+    for id in [Params::Echoes, Params::EchoTime] {
+        ui::set_param_visible(in_data, params, id, !feedback)?;
+    }
+    Ok(())
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // フィードバックの蓄積は再生成できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        // 非 SmartRender では過去フレームを取得できないので現在フレームのみ
+        let frames = [ImageBuf::from_layer(in_layer)];
+        self.do_render(plugin.in_data, &frames, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup => {
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::UpdateParamsUi => {
+                update_tap_visibility(&plugin.in_data, plugin.params)?;
+            }
+
+            ae::Command::UserChangedParam { param_index } => {
+                // 設定が変わったら過去の蓄積は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+                if plugin.params.type_at(param_index) == Params::Mode {
+                    update_tap_visibility(&plugin.in_data, plugin.params)?;
+                }
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+                let taps = tap_count(plugin.params)?;
+                let echo_time = plugin
+                    .params
+                    .get(Params::EchoTime)?
+                    .as_float_slider()?
+                    .value();
+
+                // 現在フレームから Echo Time 間隔で過去フレームをチェックアウト
+                for k in 0..taps {
+                    let time = in_data.current_time()
+                        - (echo_time * k as f64 * in_data.time_step() as f64).round() as i32;
+                    if let Ok(in_result) = extra.callbacks().checkout_layer(
+                        0,
+                        k as i32,
+                        &req,
+                        time,
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    ) {
+                        let _ = extra.union_result_rect(in_result.result_rect.into());
+                        let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                    } else {
+                        return Err(Error::InterruptCancel);
+                    }
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let taps = tap_count(plugin.params)?;
+
+                let mut frames = Vec::with_capacity(taps);
+                for k in 0..taps {
+                    if let Some(layer) = cb.checkout_layer_pixels(k as u32)? {
+                        frames.push(ImageBuf::from_layer(&layer));
+                    }
+                }
+
+                if frames.len() == taps
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(in_data, &frames, &mut out_layer, plugin.params)?;
+                }
+
+                for k in 0..taps {
+                    cb.checkin_layer_pixels(k as u32)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        frames: &[ImageBuf],
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = EchoParams {
+            mode: mode(params)?,
+            starting_intensity: params
+                .get(Params::StartingIntensity)?
+                .as_float_slider()?
+                .value() as f32,
+            decay: params.get(Params::Decay)?.as_float_slider()?.value() as f32,
+            blend: match params.get(Params::Blend)?.as_popup()?.value() {
+                1 => Blend::Add,
+                3 => Blend::Maximum,
+                4 => Blend::Average,
+                _ => Blend::Screen,
+            },
+        };
+        let Some(current) = frames.first() else {
+            return Ok(());
+        };
+
+        let state = match settings.mode {
+            Mode::Taps => echo(frames, &settings),
+            Mode::Feedback => {
+                let key = HistoryKey {
+                    width: current.width,
+                    height: current.height,
+                    downsample: Downsample::from_in_data(&in_data),
+                    time_step: in_data.time_step(),
+                };
+                let time = in_data.current_time();
+
+                let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+                let state = feedback(current, history.previous(key, time), &settings);
+                history.store(key, time, state.clone());
+                state
+            }
+        };
+
+        to_display(&state).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct EchoParams {
+    pub mode: Mode,
+    /// Weight of the current frame.
+    pub starting_intensity: f32,
+    /// Weight multiplier per echo (per frame in feedback mode).
+    pub decay: f32,
+    pub blend: Blend,
+}
+
+/// Premultiplied linear-light copy of the (encoded, straight) `image`.
+fn to_linear(image: &ImageBuf) -> ImageBuf {
+    let mut out = sampling::premultiply(image);
+    for px in out.data.iter_mut() {
+        // premultiply 後なので一度ストレートに戻してから線形化する
+        let straight = sampling::unpremultiply(*px);
+        let a = px.alpha;
+        *px = PixelF32 {
+            red: srgb_to_linear(straight.red) * a,
+            green: srgb_to_linear(straight.green) * a,
+            blue: srgb_to_linear(straight.blue) * a,
+            alpha: a,
+        };
+    }
+    out
+}
+
+/// Encoded straight-alpha copy of a premultiplied linear `state`.
+fn to_display(state: &ImageBuf) -> ImageBuf {
+    let mut out = state.clone();
+    for px in out.data.iter_mut() {
+        let straight = sampling::unpremultiply(*px);
+        *px = PixelF32 {
+            red: linear_to_srgb(straight.red),
+            green: linear_to_srgb(straight.green),
+            blue: linear_to_srgb(straight.blue),
+            alpha: straight.alpha,
+        };
+    }
+    out
+}
+
+/// Composites weighted premultiplied linear `layers` with `blend`.
+fn composite(layers: &[(&ImageBuf, f32)], blend: Blend) -> ImageBuf {
+    let Some((first, _)) = layers.first() else {
+        return ImageBuf::new(0, 0);
+    };
+    let (width, height) = (first.width, first.height);
+    let mut out = ImageBuf::new(width, height);
+    let total: f32 = layers.iter().map(|(_, w)| w).sum();
+
+    for (i, dst) in out.data.iter_mut().enumerate() {
+        let (x, y) = ((i % width) as isize, (i / width) as isize);
+        let mut acc = [0.0f32; 4];
+        for (layer, w) in layers {
+            let px = layer.get(x, y);
+            let c = [px.red * w, px.green * w, px.blue * w, px.alpha * w];
+            for (a, c) in acc.iter_mut().zip(c) {
+                let c = if c.is_nan() { 0.0 } else { c.max(0.0) };
+                *a = match blend {
+                    Blend::Add | Blend::Average => *a + c,
+                    // スクリーンは 1 を超える HDR 値でも単調になるよう上限なしの式を使う
+                    Blend::Screen => *a + c - *a * c.min(1.0),
+                    Blend::Maximum => a.max(c),
+                };
+            }
+        }
+        if blend == Blend::Average {
+            let k = if total > 0.0 { 1.0 / total } else { 0.0 };
+            acc.iter_mut().for_each(|a| *a *= k);
+        }
+        let alpha = acc[3].clamp(0.0, 1.0);
+        *dst = PixelF32 {
+            red: acc[0],
+            green: acc[1],
+            blue: acc[2],
+            alpha,
+        };
+    }
+    out
+}
+
+/// Frame-tap echo of `frames` (index = increasing delay), premultiplied linear.
+fn echo(frames: &[ImageBuf], p: &EchoParams) -> ImageBuf {
+    let start = finite_or(p.starting_intensity, 1.0).clamp(0.0, 1.0);
+    let decay = finite_or(p.decay, 0.0).clamp(0.0, 1.0);
+    let linear: Vec<ImageBuf> = frames.iter().map(to_linear).collect();
+    let layers: Vec<(&ImageBuf, f32)> = linear
+        .iter()
+        .enumerate()
+        .map(|(k, image)| (image, start * decay.powi(k as i32)))
+        .collect();
+    composite(&layers, p.blend)
+}
+
+/// Next feedback state: `current` blended over the decayed `previous` state.
+fn feedback(current: &ImageBuf, previous: Option<&ImageBuf>, p: &EchoParams) -> ImageBuf {
+    let start = finite_or(p.starting_intensity, 1.0).clamp(0.0, 1.0);
+    let decay = finite_or(p.decay, 0.0).clamp(0.0, 1.0);
+    let current = to_linear(current);
+    match previous {
+        Some(previous) if previous.width == current.width && previous.height == current.height => {
+            composite(&[(&current, start), (previous, decay)], p.blend)
+        }
+        _ => composite(&[(&current, start)], p.blend),
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no access to other frames, so earlier frames are simulated
+/// by shifting the input one pixel to the right per echo.
+pub struct EchoTrails;
+
+impl RenderCore for EchoTrails {
+    type Params = EchoParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        EchoParams {
+            mode: if rng.chance(0.5) {
+                Mode::Taps
+            } else {
+                Mode::Feedback
+            },
+            starting_intensity: rng.slider(0.0, 1.0),
+            decay: rng.slider(0.0, 1.0),
+            blend: match rng.popup(1, 4) {
+                1 => Blend::Add,
+                3 => Blend::Maximum,
+                4 => Blend::Average,
+                _ => Blend::Screen,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let taps = 1 + frame as usize % MAX_ECHOES;
+        let frames: Vec<ImageBuf> = (0..taps)
+            .map(|k| {
+                let mut shifted = ImageBuf::new(input.width, input.height);
+                for y in 0..input.height {
+                    for x in 0..input.width {
+                        let px = input.get_clamped(x as isize - k as isize, y as isize);
+                        shifted.set(x, y, px);
+                    }
+                }
+                shifted
+            })
+            .collect();
+
+        let state = match params.mode {
+            Mode::Taps => echo(&frames, params),
+            Mode::Feedback => {
+                // 直前フレームの状態として 1 つずらした入力の蓄積を使う
+                let previous = (frame % 2 == 1).then(|| echo(&frames[..taps.min(2)], params));
+                feedback(&frames[0], previous.as_ref(), params)
+            }
+        };
+        *output = to_display(&state);
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<edge_aware_upscale::EdgeAwareUpscale>();
+}
@@ -0,0 +1,443 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::blur;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Edge, Filter};
+use utils::tensor::structure_tensor;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Scale,
+    Center,
+    Amount,
+    Radius,
+    Threshold,
+    EdgeGating,
+    HaloSuppression,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Upscales the layer with edge-aware sharpening.";
+
+/// Largest sharpening radius in render pixels.
+const MAX_RADIUS: f32 = 50.0;
+/// Anisotropy below which a region counts as flat (no coherent edge).
+const GATE_LOW: f32 = 0.15;
+/// Anisotropy at which an edge gets the full sharpening.
+const GATE_HIGH: f32 = 0.6;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Scale: magnification about Center in percent (100 = sharpen only)
+        params.add(
+            Params::Scale,
+            tr("Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(100.0);
+                d.set_valid_max(800.0);
+                d.set_slider_min(100.0);
+                d.set_slider_max(400.0);
+                d.set_default(200.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Amount: unsharp mask strength in percent
+        params.add(
+            Params::Amount,
+            tr("Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(500.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(300.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Radius: unsharp mask blur radius in pixels (after scaling)
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.1);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(0.1);
+                d.set_slider_max(10.0);
+                d.set_default(1.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Threshold: luminance detail (percent) below which nothing is sharpened
+        params.add(
+            Params::Threshold,
+            tr("Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Edge Gating: 0 = sharpen everywhere, 100 = only along coherent edges
+        params.add(
+            Params::EdgeGating,
+            tr("Edge Gating"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(80.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Halo Suppression: clamps overshoot to the range of nearby source pixels
+        params.add(
+            Params::HaloSuppression,
+            tr("Halo Suppression"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(75.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_EdgeAwareUpscale - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = UpscaleParams {
+            scale: slider(Params::Scale)? / 100.0,
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            amount: slider(Params::Amount)? / 100.0,
+            radius: ds.length(slider(Params::Radius)?),
+            threshold: slider(Params::Threshold)? / 100.0,
+            edge_gating: slider(Params::EdgeGating)? / 100.0,
+            halo_suppression: slider(Params::HaloSuppression)? / 100.0,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        upscale(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct UpscaleParams {
+    /// Magnification, 1..8.
+    pub scale: f32,
+    /// Render pixels.
+    pub center: (f32, f32),
+    /// Unsharp mask strength, 0..5.
+    pub amount: f32,
+    /// Render pixels.
+    pub radius: f32,
+    /// 0..1 luminance.
+    pub threshold: f32,
+    /// 0..1.
+    pub edge_gating: f32,
+    /// 0..1.
+    pub halo_suppression: f32,
+}
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Maps an output position to the source position it magnifies.
+#[derive(Clone, Copy)]
+struct Magnify {
+    scale: f32,
+    center: (f32, f32),
+}
+
+impl Magnify {
+    fn new(input: &ImageBuf, p: &UpscaleParams) -> Self {
+        Self {
+            scale: finite_or(p.scale, 1.0).clamp(1.0, 8.0),
+            center: (
+                finite_or(p.center.0, input.width as f32 * 0.5),
+                finite_or(p.center.1, input.height as f32 * 0.5),
+            ),
+        }
+    }
+
+    fn source(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (cx, cy) = self.center;
+        (cx + (x - cx) / self.scale, cy + (y - cy) / self.scale)
+    }
+
+    /// Lanczos magnification about the center; the layer keeps its size.
+    fn apply(&self, input: &ImageBuf) -> ImageBuf {
+        if self.scale == 1.0 {
+            return input.clone();
+        }
+        sampling::remap(input, Filter::Lanczos3, (Edge::None, Edge::None), |q| {
+            Some(self.source(q))
+        })
+    }
+}
+
+/// Per-channel (min, max) of the premultiplied source pixels within `reach`
+/// output pixels of the output pixel (`x`, `y`).
+fn source_range(
+    source: &ImageBuf,
+    magnify: &Magnify,
+    x: usize,
+    y: usize,
+    reach: f32,
+) -> ([f32; 3], [f32; 3]) {
+    let (sx, sy) = magnify.source((x as f32 + 0.5, y as f32 + 0.5));
+    let r = reach / magnify.scale;
+    let span = |v: f32, n: usize| {
+        let lo = ((v - 0.5 - r).floor() as isize).clamp(0, n as isize - 1);
+        let hi = ((v - 0.5 + r).ceil() as isize).clamp(0, n as isize - 1);
+        lo..=hi
+    };
+    let mut lo = [f32::INFINITY; 3];
+    let mut hi = [f32::NEG_INFINITY; 3];
+    for py in span(sy, source.height) {
+        for px in span(sx, source.width) {
+            let p = source.data[source.index(px as usize, py as usize)];
+            for (c, v) in [p.red, p.green, p.blue].into_iter().enumerate() {
+                lo[c] = lo[c].min(v);
+                hi[c] = hi[c].max(v);
+            }
+        }
+    }
+    (lo, hi)
+}
+
+pub fn upscale(input: &ImageBuf, p: &UpscaleParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut clean = input.clone();
+    for px in clean.data.iter_mut() {
+        px.red = finite_or(px.red, 0.0);
+        px.green = finite_or(px.green, 0.0);
+        px.blue = finite_or(px.blue, 0.0);
+        px.alpha = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+    }
+    let magnify = Magnify::new(&clean, p);
+    let base = magnify.apply(&clean);
+    let amount = finite_or(p.amount, 0.0).clamp(0.0, 5.0);
+    let suppression = finite_or(p.halo_suppression, 0.0).clamp(0.0, 1.0);
+    if (amount <= 0.0 && suppression <= 0.0) || base.is_empty() {
+        return base;
+    }
+
+    let radius = finite_or(p.radius, 1.0).clamp(0.1, MAX_RADIUS);
+    let threshold = finite_or(p.threshold, 0.0).clamp(0.0, 1.0);
+    let gating = finite_or(p.edge_gating, 0.0).clamp(0.0, 1.0);
+
+    // 透明部分の色を拾わないよう乗算済みアルファでぼかす
+    let source = sampling::premultiply(&clean);
+    let premul = sampling::premultiply(&base);
+    let planes: [Vec<f32>; 3] = [
+        premul.data.iter().map(|px| px.red).collect(),
+        premul.data.iter().map(|px| px.green).collect(),
+        premul.data.iter().map(|px| px.blue).collect(),
+    ];
+    let blurred = if amount > 0.0 {
+        planes
+            .clone()
+            .map(|plane| blur::gaussian(&plane, w, h, radius, Edge::Clamp))
+    } else {
+        planes.clone()
+    };
+
+    // 輪郭が一方向に揃っている所だけ強く掛け、平坦部やノイズは抑える
+    let gates: Vec<f32> = if gating > 0.0 && amount > 0.0 {
+        structure_tensor(&premul, radius.max(1.0))
+            .iter()
+            .map(|t| {
+                let edge = smoothstep(GATE_LOW, GATE_HIGH, t.orientation().anisotropy);
+                1.0 - gating + gating * edge
+            })
+            .collect()
+    } else {
+        vec![1.0; w * h]
+    };
+
+    let reach = radius.ceil().max(1.0);
+    let mut out = base.clone();
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let a = premul.data[i].alpha;
+            if a <= 0.0 {
+                continue;
+            }
+            let detail: [f32; 3] = std::array::from_fn(|c| planes[c][i] - blurred[c][i]);
+            let detail_lum = luminance(PixelF32 {
+                red: detail[0],
+                green: detail[1],
+                blue: detail[2],
+                alpha: 1.0,
+            })
+            .abs();
+            // しきい値以下の細かな差は柔らかく切り捨てる
+            let keep = if threshold > 0.0 {
+                smoothstep(threshold * 0.5, threshold, detail_lum)
+            } else {
+                1.0
+            };
+            let k = amount * keep * gates[i];
+
+            let mut sharp: [f32; 3] = std::array::from_fn(|c| planes[c][i] + detail[c] * k);
+            if suppression > 0.0 {
+                // 元画像の近傍の範囲に収め、Lanczos とシャープのリンギング (ハロー) を消す
+                let (lo, hi) = source_range(&source, &magnify, x, y, reach);
+                for c in 0..3 {
+                    let clamped = sharp[c].clamp(lo[c], hi[c]);
+                    sharp[c] += (clamped - sharp[c]) * suppression;
+                }
+            } else if k <= 0.0 {
+                continue;
+            }
+
+            let px = &mut out.data[i];
+            let straight = |v: f32| finite_or((v / a).max(0.0), 0.0);
+            px.red = straight(sharp[0]);
+            px.green = straight(sharp[1]);
+            px.blue = straight(sharp[2]);
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct EdgeAwareUpscale;
+
+impl RenderCore for EdgeAwareUpscale {
+    type Params = UpscaleParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        UpscaleParams {
+            scale: rng.slider(1.0, 8.0),
+            center: (rng.slider(-64.0, 320.0), rng.slider(-64.0, 320.0)),
+            amount: rng.slider(0.0, 5.0),
+            radius: rng.slider(0.1, 8.0),
+            threshold: rng.slider(0.0, 0.2),
+            edge_gating: rng.slider(0.0, 1.0),
+            halo_suppression: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = upscale(input, params);
+    }
+}
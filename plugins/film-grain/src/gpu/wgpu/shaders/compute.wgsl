@@ -0,0 +1,121 @@
+// Keep in sync with `RenderParams` in ../mod.rs, `Grain` in lib.rs and
+// `crates/noise` (hash, Perlin lattice noise).
+struct Params {
+    width: u32,
+    height: u32,
+    _pad0: u32,
+    _pad1: u32,
+    seeds0: vec4<u32>,
+    seeds1: vec4<u32>,
+    inv_size: vec4<f32>,
+    weights: vec2<f32>,
+    amount: f32,
+    chroma: f32,
+    response: vec3<f32>,
+    _pad2: f32,
+};
+
+@group(0) @binding(0) var<storage, read> input: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+const OCTAVE_SEED: u32 = 0x68e31da4u;
+const GRAIN_NORM: f32 = 3.2;
+const GRAIN_STRENGTH: f32 = 0.2;
+
+fn finite_or(v: f32, fallback: f32) -> f32 {
+    // NaN / Inf は既定値に置き換える
+    if (v != v || abs(v) > 3.4e38) {
+        return fallback;
+    }
+    return v;
+}
+
+fn hash(x: u32, y: u32, z: u32) -> u32 {
+    var v = (x * 73856093u) ^ (y * 19349663u) ^ (z * 83492791u);
+    v ^= v >> 16u;
+    v *= 0x7feb352du;
+    v ^= v >> 15u;
+    v *= 0x846ca68bu;
+    return v ^ (v >> 16u);
+}
+
+fn lattice(ix: i32, iy: i32, iz: i32, seed: u32) -> u32 {
+    return hash(u32(ix), u32(iy), u32(iz) ^ (seed * 0x9e3779b9u));
+}
+
+fn fade(t: vec3<f32>) -> vec3<f32> {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn grad(hv: u32, x: f32, y: f32, z: f32) -> f32 {
+    let h = hv & 15u;
+    let u = select(y, x, h < 8u);
+    var v = z;
+    if (h < 4u) {
+        v = y;
+    } else if (h == 12u || h == 14u) {
+        v = x;
+    }
+    return select(-u, u, (h & 1u) == 0u) + select(-v, v, (h & 2u) == 0u);
+}
+
+fn corner(i: vec3<i32>, t: vec3<f32>, d: vec3<i32>, seed: u32) -> f32 {
+    let h = lattice(i.x + d.x, i.y + d.y, i.z + d.z, seed);
+    let o = t - vec3<f32>(d);
+    return grad(h, o.x, o.y, o.z);
+}
+
+fn perlin(p: vec3<f32>, seed: u32) -> f32 {
+    let f = floor(p);
+    let t = p - f;
+    let i = vec3<i32>(f);
+    let u = fade(t);
+
+    let x00 = mix(corner(i, t, vec3<i32>(0, 0, 0), seed), corner(i, t, vec3<i32>(1, 0, 0), seed), u.x);
+    let x10 = mix(corner(i, t, vec3<i32>(0, 1, 0), seed), corner(i, t, vec3<i32>(1, 1, 0), seed), u.x);
+    let x01 = mix(corner(i, t, vec3<i32>(0, 0, 1), seed), corner(i, t, vec3<i32>(1, 0, 1), seed), u.x);
+    let x11 = mix(corner(i, t, vec3<i32>(0, 1, 1), seed), corner(i, t, vec3<i32>(1, 1, 1), seed), u.x);
+    return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z) * 0.982;
+}
+
+// Two-octave Perlin grain with roughly unit deviation.
+fn field(x: f32, y: f32, inv_size: f32, seed: u32) -> f32 {
+    let p = vec3<f32>(x * inv_size, y * inv_size, 0.5);
+    let n = perlin(p, seed) + 0.5 * perlin(vec3<f32>(p.xy * 2.0, 0.5), seed ^ OCTAVE_SEED);
+    return n * GRAIN_NORM;
+}
+
+fn channel(c: u32, x: f32, y: f32) -> f32 {
+    var g = field(x, y, params.inv_size[c], params.seeds0[c]) * params.weights.x;
+    if (params.weights.y > 0.0) {
+        g += field(x, y, params.inv_size[c], params.seeds1[c]) * params.weights.y;
+    }
+    return g;
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+    let px = input[i];
+    let rgb = vec3<f32>(finite_or(px.r, 0.0), finite_or(px.g, 0.0), finite_or(px.b, 0.0));
+    let x = f32(gid.x) + 0.5;
+    let y = f32(gid.y) + 0.5;
+
+    // シャドウ・中間調・ハイライトの重みは輝度の 2 次ベルンシュタイン基底
+    let l = clamp(dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722)), 0.0, 1.0);
+    let bands = vec3<f32>((1.0 - l) * (1.0 - l), 2.0 * l * (1.0 - l), l * l);
+    let scale = params.amount * GRAIN_STRENGTH * dot(bands, params.response);
+
+    let mono = channel(3u, x, y);
+    let k = params.chroma;
+    let norm = 1.0 / max(sqrt((1.0 - k) * (1.0 - k) + k * k), 1e-6);
+    var grain = vec3<f32>(0.0);
+    for (var c = 0u; c < 3u; c++) {
+        grain[c] = (mono * (1.0 - k) + channel(c, x, y) * k) * norm;
+    }
+    output[i] = vec4<f32>(rgb + grain * scale, px.a);
+}
@@ -0,0 +1,622 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{NoiseType, hash, noise3};
+use utils::blur;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, Edge};
+use utils::ui;
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Amount,
+    RedSize,
+    GreenSize,
+    BlueSize,
+    Shadows,
+    Midtones,
+    Highlights,
+    Chroma,
+    Motion,
+    HoldFrames,
+    Seed,
+    Halation,
+    HalationAmount,
+    HalationRadius,
+    HalationThreshold,
+    HalationColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Adds film grain and halation.";
+
+/// Upper bound of the grain sizes (full-resolution pixels).
+const MAX_GRAIN_SIZE: f32 = 20.0;
+/// Smallest grain size in render pixels, so heavy downsampling stays finite.
+const MIN_GRAIN_SIZE: f32 = 0.05;
+/// Upper bound of `Halation Radius` (full-resolution pixels).
+const MAX_HALATION_RADIUS: f32 = 200.0;
+/// Scales the two-octave Perlin sum to roughly unit deviation (matches the shader).
+const GRAIN_NORM: f32 = 3.2;
+/// Offset brightness at 100 % `Amount` and unit grain (matches the shader).
+const GRAIN_STRENGTH: f32 = 0.2;
+/// Seed offset of the finer octave (matches the shader).
+const OCTAVE_SEED: u32 = 0x68e3_1da4;
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    /// One grain pattern for the whole layer.
+    Static,
+    /// A new pattern every `Hold Frames` frames.
+    PerFrame,
+    /// Patterns cross-fade into each other over `Hold Frames` frames.
+    Smooth,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Amount,
+            tr("Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(200.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Red / Green / Blue Size: grain size per emulsion layer (full-resolution pixels)
+        for (id, name, default) in [
+            (Params::RedSize, tr("Red Size"), 1.2),
+            (Params::GreenSize, tr("Green Size"), 1.0),
+            (Params::BlueSize, tr("Blue Size"), 1.6),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(0.1);
+                    d.set_valid_max(MAX_GRAIN_SIZE);
+                    d.set_slider_min(0.2);
+                    d.set_slider_max(5.0);
+                    d.set_default(default);
+                    d.set_precision(2);
+                }),
+            )?;
+        }
+
+        // Shadows / Midtones / Highlights: grain strength per tonal range (percent)
+        for (id, name, default) in [
+            (Params::Shadows, tr("Shadows"), 80.0),
+            (Params::Midtones, tr("Midtones"), 100.0),
+            (Params::Highlights, tr("Highlights"), 40.0),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(0.0);
+                    d.set_valid_max(200.0);
+                    d.set_slider_min(0.0);
+                    d.set_slider_max(200.0);
+                    d.set_default(default);
+                    d.set_precision(1);
+                }),
+            )?;
+        }
+
+        // Chroma: 0 = the same grain in every channel, 100 = independent channels
+        params.add(
+            Params::Chroma,
+            tr("Chroma"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add_with_flags(
+            Params::Motion,
+            tr("Motion"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Static"), tr("Per Frame"), tr("Smooth")]);
+                d.set_default(2);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Hold Frames: frames per grain pattern (Smooth: frames per cross-fade)
+        params.add(
+            Params::HoldFrames,
+            tr("Hold Frames"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(1000);
+                d.set_slider_min(1);
+                d.set_slider_max(30);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        // Halation: red glow bleeding around highlights, added before the grain
+        params.add_with_flags(
+            Params::Halation,
+            tr("Halation"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+                d.set_label(tr("On"));
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::HalationAmount,
+            tr("Halation Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(200.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::HalationRadius,
+            tr("Halation Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_HALATION_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(60.0);
+                d.set_default(12.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Halation Threshold: luminance (percent) above which highlights glow
+        params.add(
+            Params::HalationThreshold,
+            tr("Halation Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(75.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::HalationColor,
+            tr("Halation Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 80,
+                    blue: 30,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_FilmGrain - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::Motion | Params::Halation
+                ) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let animated = params.get(Params::Motion)?.as_popup()?.value() != 1;
+    let halation = params.get(Params::Halation)?.as_checkbox()?.value();
+    for (id, visible) in [
+        (Params::HoldFrames, animated),
+        (Params::HalationAmount, halation),
+        (Params::HalationRadius, halation),
+        (Params::HalationThreshold, halation),
+        (Params::HalationColor, halation),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let float =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let color = params
+            .get(Params::HalationColor)?
+            .as_color()?
+            .float_value()?;
+
+        let settings = FilmGrainParams {
+            amount: float(Params::Amount)? / 100.0,
+            sizes: [
+                ds.length(float(Params::RedSize)?),
+                ds.length(float(Params::GreenSize)?),
+                ds.length(float(Params::BlueSize)?),
+            ],
+            response: [
+                float(Params::Shadows)? / 100.0,
+                float(Params::Midtones)? / 100.0,
+                float(Params::Highlights)? / 100.0,
+            ],
+            chroma: float(Params::Chroma)? / 100.0,
+            motion: match params.get(Params::Motion)?.as_popup()?.value() {
+                1 => Motion::Static,
+                3 => Motion::Smooth,
+                _ => Motion::PerFrame,
+            },
+            hold_frames: params.get(Params::HoldFrames)?.as_slider()?.value() as u32,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            halation: params.get(Params::Halation)?.as_checkbox()?.value(),
+            halation_amount: float(Params::HalationAmount)? / 100.0,
+            halation_radius: ds.length(float(Params::HalationRadius)?),
+            halation_threshold: float(Params::HalationThreshold)? / 100.0,
+            halation_color: [color.red, color.green, color.blue],
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let frame = in_data.current_frame() as u32;
+        film_grain(&input, &settings, frame).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FilmGrainParams {
+    /// 0..2 grain strength.
+    pub amount: f32,
+    /// Red, green, blue grain size in render pixels.
+    pub sizes: [f32; 3],
+    /// Shadows, midtones, highlights strength multipliers.
+    pub response: [f32; 3],
+    /// 0..1 mix from monochrome to per-channel grain.
+    pub chroma: f32,
+    pub motion: Motion,
+    pub hold_frames: u32,
+    pub seed: u32,
+    pub halation: bool,
+    pub halation_amount: f32,
+    /// Render pixels.
+    pub halation_radius: f32,
+    /// 0..1 luminance.
+    pub halation_threshold: f32,
+    /// Encoded glow color.
+    pub halation_color: [f32; 3],
+}
+
+/// Per-frame grain settings shared by the CPU path and the shader.
+struct Grain {
+    /// Seeds (red, green, blue, mono) of the current and the next pattern.
+    seeds: [[u32; 4]; 2],
+    /// Cross-fade weights of the two patterns (`w0² + w1² = 1`, so the
+    /// grain keeps its strength mid-fade).
+    weights: [f32; 2],
+    inv_size: [f32; 4],
+    amount: f32,
+    chroma: f32,
+    response: [f32; 3],
+}
+
+impl Grain {
+    fn new(p: &FilmGrainParams, frame: u32) -> Self {
+        let hold = p.hold_frames.max(1);
+        let (period, t) = match p.motion {
+            Motion::Static => (0, 0.0),
+            Motion::PerFrame => (frame / hold, 0.0),
+            Motion::Smooth => (frame / hold, (frame % hold) as f32 / hold as f32),
+        };
+        let seeds = |k: u32| {
+            let base = hash(p.seed, k, 0x5eed);
+            std::array::from_fn(|c| hash(base, c as u32, 0x9a1))
+        };
+        let (s, c) = (t * std::f32::consts::FRAC_PI_2).sin_cos();
+
+        let sizes = p
+            .sizes
+            .map(|s| finite_or(s, 1.0).clamp(MIN_GRAIN_SIZE, MAX_GRAIN_SIZE));
+        let mono = (sizes[0] + sizes[1] + sizes[2]) / 3.0;
+        let response = p.response.map(|r| finite_or(r, 1.0).clamp(0.0, 2.0));
+        Self {
+            seeds: [seeds(period), seeds(period.wrapping_add(1))],
+            weights: [c, s],
+            inv_size: [sizes[0], sizes[1], sizes[2], mono].map(|s| 1.0 / s),
+            amount: finite_or(p.amount, 0.0).clamp(0.0, 2.0),
+            chroma: finite_or(p.chroma, 0.0).clamp(0.0, 1.0),
+            response,
+        }
+    }
+
+    /// Two-octave Perlin grain with roughly unit deviation.
+    fn field(x: f32, y: f32, inv_size: f32, seed: u32) -> f32 {
+        let p = [x * inv_size, y * inv_size, 0.5];
+        let n = noise3(NoiseType::Perlin, p, seed, [0, 0])
+            + 0.5
+                * noise3(
+                    NoiseType::Perlin,
+                    [p[0] * 2.0, p[1] * 2.0, 0.5],
+                    seed ^ OCTAVE_SEED,
+                    [0, 0],
+                );
+        n * GRAIN_NORM
+    }
+
+    fn channel(&self, c: usize, x: f32, y: f32) -> f32 {
+        let mut g = Self::field(x, y, self.inv_size[c], self.seeds[0][c]) * self.weights[0];
+        if self.weights[1] > 0.0 {
+            g += Self::field(x, y, self.inv_size[c], self.seeds[1][c]) * self.weights[1];
+        }
+        g
+    }
+
+    fn apply(&self, px: PixelF32, x: usize, y: usize) -> PixelF32 {
+        let rgb = [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0));
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+
+        // シャドウ・中間調・ハイライトの重みは輝度の 2 次ベルンシュタイン基底
+        let l = (0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]).clamp(0.0, 1.0);
+        let bands = [(1.0 - l) * (1.0 - l), 2.0 * l * (1.0 - l), l * l];
+        let response: f32 = bands.iter().zip(&self.response).map(|(b, r)| b * r).sum();
+        let scale = self.amount * GRAIN_STRENGTH * response;
+
+        let mono = self.channel(3, x, y);
+        let k = self.chroma;
+        let norm = 1.0 / ((1.0 - k) * (1.0 - k) + k * k).sqrt().max(1e-6);
+        let grain: [f32; 3] =
+            std::array::from_fn(|c| (mono * (1.0 - k) + self.channel(c, x, y) * k) * norm);
+        PixelF32 {
+            red: rgb[0] + grain[0] * scale,
+            green: rgb[1] + grain[1] * scale,
+            blue: rgb[2] + grain[2] * scale,
+            alpha: px.alpha,
+        }
+    }
+}
+
+/// Adds the tinted glow of the highlights above the threshold, blurred by
+/// `Halation Radius`.
+fn halation(input: &ImageBuf, p: &FilmGrainParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let threshold = finite_or(p.halation_threshold, 0.75).clamp(0.0, 1.0);
+    let bright: Vec<f32> = input
+        .data
+        .iter()
+        .map(|&px| {
+            let l = finite_or(luminance(px), 0.0);
+            let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+            ((l - threshold) / (1.0 - threshold).max(1e-3)).clamp(0.0, 4.0) * a
+        })
+        .collect();
+    let radius = finite_or(p.halation_radius, 0.0).clamp(0.0, MAX_HALATION_RADIUS);
+    let glow = blur::blur(&bright, w, h, radius * 0.5, Edge::Clamp);
+    let amount = finite_or(p.halation_amount, 0.0).clamp(0.0, 2.0);
+    let color = p.halation_color.map(|c| finite_or(c, 0.0));
+
+    let mut out = input.clone();
+    for (px, g) in out.data.iter_mut().zip(glow) {
+        let g = g * amount;
+        px.red += color[0] * g;
+        px.green += color[1] * g;
+        px.blue += color[2] * g;
+    }
+    out
+}
+
+pub fn film_grain(input: &ImageBuf, p: &FilmGrainParams, frame: u32) -> ImageBuf {
+    let haloed;
+    let input = if p.halation {
+        haloed = halation(input, p);
+        &haloed
+    } else {
+        input
+    };
+    let grain = Grain::new(p, frame);
+
+    #[cfg(feature = "gpu_wgpu")]
+    if let Some(ctx) = wgpu_context() {
+        let gpu_params = RenderParams {
+            width: input.width as u32,
+            height: input.height as u32,
+            seeds0: grain.seeds[0],
+            seeds1: grain.seeds[1],
+            inv_size: grain.inv_size,
+            weights: grain.weights,
+            amount: grain.amount,
+            chroma: grain.chroma,
+            response: grain.response,
+            ..Default::default()
+        };
+        if let Ok(rgba) = ctx.render(&gpu_params, &input.to_rgba()) {
+            return ImageBuf::from_rgba(input.width, input.height, &rgba);
+        }
+        // Fall through to the CPU path when the GPU dispatch fails.
+    }
+
+    let mut out = ImageBuf::new(input.width, input.height);
+    for (i, (o, &px)) in out.data.iter_mut().zip(&input.data).enumerate() {
+        *o = grain.apply(px, i % input.width.max(1), i / input.width.max(1));
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct FilmGrain;
+
+impl RenderCore for FilmGrain {
+    type Params = FilmGrainParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        FilmGrainParams {
+            amount: rng.slider(0.0, 2.0),
+            sizes: [
+                rng.slider(0.1, MAX_GRAIN_SIZE),
+                rng.slider(0.1, MAX_GRAIN_SIZE),
+                rng.slider(0.1, MAX_GRAIN_SIZE),
+            ],
+            response: [
+                rng.slider(0.0, 2.0),
+                rng.slider(0.0, 2.0),
+                rng.slider(0.0, 2.0),
+            ],
+            chroma: rng.slider(0.0, 1.0),
+            motion: match rng.popup(1, 3) {
+                1 => Motion::Static,
+                3 => Motion::Smooth,
+                _ => Motion::PerFrame,
+            },
+            hold_frames: rng.int(1, 1000) as u32,
+            seed: rng.int(0, 10000) as u32,
+            halation: rng.chance(0.5),
+            halation_amount: rng.slider(0.0, 2.0),
+            halation_radius: rng.slider(0.0, 60.0),
+            halation_threshold: rng.slider(0.0, 1.0),
+            halation_color: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = film_grain(input, params, frame);
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<flow_field_advection::FlowFieldAdvection>();
+}
@@ -0,0 +1,493 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::history::{History, HistoryKey};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    VectorLayer,
+    VectorEncoding,
+    Strength,
+    Steps,
+    Decay,
+    Injection,
+    Accumulate,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: advected frames kept for temporal accumulation.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History<ImageBuf>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Advects the layer along a vector field over time.";
+
+/// Checkout id of the vector layer (the input uses 0).
+const VECTOR_CHECKOUT_ID: i32 = 1;
+/// Upper bound of advection steps per frame.
+const MAX_STEPS: i32 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// 0.5 is still; 0 / 1 are full speed in either direction.
+    Centered,
+    /// Channel values are used as-is (32bpc motion vectors).
+    Signed,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Vector Layer: R = x, G = y (positive green moves down)
+        params.add(Params::VectorLayer, tr("Vector Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::VectorEncoding,
+            tr("Vector Encoding"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Centered"), tr("Signed")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Strength: pixels moved per step for a unit vector
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(-20.0);
+                d.set_slider_max(20.0);
+                d.set_default(4.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Steps,
+            tr("Steps"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_STEPS);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_STEPS);
+                d.set_default(4);
+            }),
+        )?;
+
+        // Decay: fraction of the advected image lost per step
+        params.add(
+            Params::Decay,
+            tr("Decay"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.02);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Injection: fraction of the current input mixed back in per step
+        params.add(
+            Params::Injection,
+            tr("Injection"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.1);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Accumulate,
+            tr("Accumulate"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+                d.set_label(tr("Over Time"));
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Lanczos")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_FlowFieldAdvection - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // 蓄積状態は再生成できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        // 非 SmartRender ではベクトルレイヤーをパラメータから直接取得する
+        let vectors = plugin
+            .params
+            .checkout_at(
+                Params::VectorLayer,
+                Some(in_data.current_time()),
+                Some(in_data.time_step()),
+                Some(in_data.time_scale()),
+            )?
+            .as_layer()?
+            .value()
+            .map(|layer| ImageBuf::from_layer(&layer));
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(in_data, &input, vectors.as_ref(), out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup | ae::Command::UserChangedParam { .. } => {
+                // 設定が変わったら過去の蓄積は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = plugin.params.index(Params::VectorLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        VECTOR_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let input = cb
+                    .checkout_layer_pixels(0)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let vectors = cb
+                    .checkout_layer_pixels(VECTOR_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+
+                if let Some(input) = input
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(
+                        in_data,
+                        &input,
+                        vectors.as_ref(),
+                        &mut out_layer,
+                        plugin.params,
+                    )?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(VECTOR_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        vectors: Option<&ImageBuf>,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = AdvectParams {
+            encoding: match params.get(Params::VectorEncoding)?.as_popup()?.value() {
+                2 => Encoding::Signed,
+                _ => Encoding::Centered,
+            },
+            strength: ds.length(params.get(Params::Strength)?.as_float_slider()?.value() as f32),
+            steps: params.get(Params::Steps)?.as_slider()?.value() as usize,
+            decay: params.get(Params::Decay)?.as_float_slider()?.value() as f32,
+            injection: params.get(Params::Injection)?.as_float_slider()?.value() as f32,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                2 => Filter::Lanczos3,
+                _ => Filter::Bilinear,
+            },
+        };
+        let accumulate = params.get(Params::Accumulate)?.as_checkbox()?.value();
+
+        let source = sampling::premultiply(input);
+        let field = VectorField::new(vectors, source.width, source.height, &settings);
+        let key = HistoryKey {
+            width: source.width,
+            height: source.height,
+            downsample: ds,
+            time_step: in_data.time_step(),
+        };
+        let time = in_data.current_time();
+
+        let state = {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            let previous = if accumulate {
+                history.previous(key, time)
+            } else {
+                None
+            };
+            let state = advect(previous.unwrap_or(&source), &source, &field, &settings);
+            if accumulate {
+                history.store(key, time, state.clone());
+            }
+            state
+        };
+
+        let out_world_type = out_layer.world_type();
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = sampling::unpremultiply(state.get(x as isize, y as isize));
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct AdvectParams {
+    pub encoding: Encoding,
+    /// Pixels moved per step for a unit vector (render pixels).
+    pub strength: f32,
+    pub steps: usize,
+    /// Fraction of the advected image lost per step.
+    pub decay: f32,
+    /// Fraction of the current input mixed back in per step.
+    pub injection: f32,
+    pub filter: Filter,
+}
+
+/// Per-pixel displacement per step, read from the vector layer.
+struct VectorField {
+    width: usize,
+    v: Vec<[f32; 2]>,
+}
+
+impl VectorField {
+    fn new(map: Option<&ImageBuf>, width: usize, height: usize, p: &AdvectParams) -> Self {
+        // 巨大な値で遠方を参照し続けないよう 1 ステップの移動量を画像サイズで抑える
+        let limit = (width + height) as f32;
+        let decode = |c: f32| {
+            let v = match p.encoding {
+                Encoding::Centered => (c - 0.5) * 2.0,
+                Encoding::Signed => c,
+            };
+            let d = v * p.strength;
+            if d.is_nan() {
+                0.0
+            } else {
+                d.clamp(-limit, limit)
+            }
+        };
+
+        let mut v = vec![[0.0; 2]; width * height];
+        if let Some(map) = map.filter(|m| !m.is_empty()) {
+            for y in 0..height {
+                for x in 0..width {
+                    // ベクトルレイヤーのサイズが異なる場合は引き伸ばして対応させる
+                    let mx = x * map.width / width.max(1);
+                    let my = y * map.height / height.max(1);
+                    let px = map.get_clamped(mx as isize, my as isize);
+                    v[y * width + x] = [decode(px.red), decode(px.green)];
+                }
+            }
+        }
+        Self { width, v }
+    }
+
+    fn get(&self, x: usize, y: usize) -> [f32; 2] {
+        self.v[y * self.width + x]
+    }
+}
+
+/// Runs `p.steps` semi-Lagrangian advection steps starting from `state`,
+/// decaying it and injecting `source` each step. All images are premultiplied.
+fn advect(state: &ImageBuf, source: &ImageBuf, field: &VectorField, p: &AdvectParams) -> ImageBuf {
+    let decay = if p.decay.is_nan() {
+        0.0
+    } else {
+        p.decay.clamp(0.0, 1.0)
+    };
+    let injection = if p.injection.is_nan() {
+        0.0
+    } else {
+        p.injection.clamp(0.0, 1.0)
+    };
+
+    let mut current = state.clone();
+    let mut next = ImageBuf::new(source.width, source.height);
+    for _ in 0..p.steps.clamp(1, MAX_STEPS as usize) {
+        for y in 0..source.height {
+            for x in 0..source.width {
+                // 流れを遡った位置の値を持ってくる
+                let [vx, vy] = field.get(x, y);
+                let px = sampling::sample(&current, x as f32 - vx, y as f32 - vy, p.filter);
+                let src = source.get(x as isize, y as isize);
+                let mix = |a: f32, s: f32| {
+                    let v = a * (1.0 - decay);
+                    let v = v + (s - v) * injection;
+                    if v.is_nan() { 0.0 } else { v.max(0.0) }
+                };
+                next.set(
+                    x,
+                    y,
+                    PixelF32 {
+                        red: mix(px.red, src.red),
+                        green: mix(px.green, src.green),
+                        blue: mix(px.blue, src.blue),
+                        alpha: mix(px.alpha, src.alpha).min(1.0),
+                    },
+                );
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+    }
+    current
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no sequence data or second layer, so every frame starts
+/// from the input and the input's own RG channels drive the flow.
+pub struct FlowFieldAdvection;
+
+impl RenderCore for FlowFieldAdvection {
+    type Params = AdvectParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        AdvectParams {
+            encoding: if rng.chance(0.5) {
+                Encoding::Centered
+            } else {
+                Encoding::Signed
+            },
+            strength: rng.slider(-1000.0, 1000.0),
+            steps: rng.popup(1, MAX_STEPS) as usize,
+            decay: rng.slider(0.0, 1.0),
+            injection: rng.slider(0.0, 1.0),
+            filter: if rng.chance(0.5) {
+                Filter::Bilinear
+            } else {
+                Filter::Lanczos3
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let source = sampling::premultiply(input);
+        let field = VectorField::new(Some(input), source.width, source.height, params);
+        let state = advect(&source, &source, &field, params);
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let px = sampling::unpremultiply(state.get(x as isize, y as isize));
+                output.set(x, y, px);
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<flow_frame_blend::FlowFrameBlend>();
+}
@@ -0,0 +1,488 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::flow::{self, Flow, LucasKanade};
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Timing,
+    Speed,
+    SourceFrame,
+    Motion,
+    MotionLayer,
+    VectorScale,
+    MismatchThreshold,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Retimes the layer with motion-compensated frame blending.";
+
+/// Checkout ids of the source frames before / after the source time and of
+/// the motion layer.
+const EARLIER_CHECKOUT_ID: i32 = 0;
+const LATER_CHECKOUT_ID: i32 = 1;
+const MOTION_CHECKOUT_ID: i32 = 2;
+const MAX_SPEED: f32 = 1000.0;
+const MAX_SOURCE_FRAME: f32 = 1_000_000.0;
+const MAX_VECTOR_SCALE: f32 = 4096.0;
+/// Internal flow settings (see `utils::flow`).
+const INTERNAL_FLOW: LucasKanade = LucasKanade {
+    levels: 5,
+    window_radius: 4,
+    iterations: 3,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timing {
+    /// Source frame = current frame × `Speed`.
+    Speed,
+    /// Source frame given directly (keyframe it like Time Remap).
+    SourceFrame,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    /// Lucas-Kanade flow between the two source frames.
+    InternalFlow,
+    /// RG vectors of the Motion Layer at the later source frame.
+    VectorLayer,
+    /// Plain cross-fade.
+    FrameMix,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Timing: shows Speed or Source Frame (see update_param_visibility)
+        params.add_with_flags(
+            Params::Timing,
+            tr("Timing"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Speed"), tr("Source Frame")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Speed: playback speed in percent from the layer's first frame
+        params.add(
+            Params::Speed,
+            tr("Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SPEED);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Source Frame: fractional frame of the layer to show
+        params.add(
+            Params::SourceFrame,
+            tr("Source Frame"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-MAX_SOURCE_FRAME);
+                d.set_valid_max(MAX_SOURCE_FRAME);
+                d.set_slider_min(0.0);
+                d.set_slider_max(300.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Motion: shows the motion layer controls (see update_param_visibility)
+        params.add_with_flags(
+            Params::Motion,
+            tr("Motion"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Internal Flow"),
+                    tr("Motion Vector Layer"),
+                    tr("Frame Mix"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Motion Layer: RG = motion since the previous frame, 0.5 = still
+        params.add(Params::MotionLayer, tr("Motion Layer"), LayerDef::new())?;
+
+        // Vector Scale: displacement in pixels of a full-range (0 or 1) channel
+        params.add(
+            Params::VectorScale,
+            tr("Vector Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_VECTOR_SCALE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(256.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Mismatch Threshold: where the two warped frames differ by more than
+        // this, the nearer frame is shown instead of a ghosted mix
+        params.add(
+            Params::MismatchThreshold,
+            tr("Mismatch Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.2);
+                d.set_precision(3);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_FlowFrameBlend - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(params.type_at(param_index), Params::Timing | Params::Motion) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                mut out_layer,
+            } => {
+                // 非 SmartRender では別時刻のフレームを取得できないので素通しする
+                ImageBuf::from_layer(&in_layer).write_to_layer(&mut out_layer)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+                let settings = settings(in_data, params)?;
+                let (earlier, later, _) = source_frames(&settings, current_frame(in_data));
+
+                for (checkout_id, frame) in
+                    [(EARLIER_CHECKOUT_ID, earlier), (LATER_CHECKOUT_ID, later)]
+                {
+                    if let Ok(in_result) = extra.callbacks().checkout_layer(
+                        0,
+                        checkout_id,
+                        &req,
+                        frame_time(in_data, frame),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    ) {
+                        let _ = extra.union_result_rect(in_result.result_rect.into());
+                        let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                    } else {
+                        return Err(Error::InterruptCancel);
+                    }
+                }
+
+                if settings.motion == Motion::VectorLayer
+                    && let Some(index) = params.index(Params::MotionLayer)
+                {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        MOTION_CHECKOUT_ID,
+                        &req,
+                        frame_time(in_data, later),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let settings = settings(in_data, params)?;
+                let (_, _, t) = source_frames(&settings, current_frame(in_data));
+                let vector_layer = settings.motion == Motion::VectorLayer;
+
+                let earlier = cb
+                    .checkout_layer_pixels(EARLIER_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let later = cb
+                    .checkout_layer_pixels(LATER_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let vectors = if vector_layer {
+                    cb.checkout_layer_pixels(MOTION_CHECKOUT_ID as u32)?
+                        .map(|layer| ImageBuf::from_layer(&layer))
+                } else {
+                    None
+                };
+
+                if let (Some(earlier), Some(later)) = (&earlier, &later)
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    blend_frames(earlier, later, vectors.as_ref(), t, &settings)
+                        .write_to_layer(&mut out_layer)?;
+                }
+
+                cb.checkin_layer_pixels(EARLIER_CHECKOUT_ID as u32)?;
+                cb.checkin_layer_pixels(LATER_CHECKOUT_ID as u32)?;
+                if vector_layer {
+                    cb.checkin_layer_pixels(MOTION_CHECKOUT_ID as u32)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let speed = params.get(Params::Timing)?.as_popup()?.value() != 2;
+    let vector_layer = params.get(Params::Motion)?.as_popup()?.value() == 2;
+    for (id, visible) in [
+        (Params::Speed, speed),
+        (Params::SourceFrame, !speed),
+        (Params::MotionLayer, vector_layer),
+        (Params::VectorScale, vector_layer),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+fn settings(in_data: InData, params: &Parameters<Params>) -> Result<BlendParams, Error> {
+    // ベクトルの倍率はフル解像度基準なのでダウンサンプル率を掛ける
+    let ds = Downsample::from_in_data(&in_data);
+    Ok(BlendParams {
+        timing: match params.get(Params::Timing)?.as_popup()?.value() {
+            2 => Timing::SourceFrame,
+            _ => Timing::Speed,
+        },
+        speed: params.get(Params::Speed)?.as_float_slider()?.value() as f32,
+        source_frame: params.get(Params::SourceFrame)?.as_float_slider()?.value(),
+        motion: match params.get(Params::Motion)?.as_popup()?.value() {
+            2 => Motion::VectorLayer,
+            3 => Motion::FrameMix,
+            _ => Motion::InternalFlow,
+        },
+        vector_scale: ds.length(params.get(Params::VectorScale)?.as_float_slider()?.value() as f32),
+        mismatch_threshold: params
+            .get(Params::MismatchThreshold)?
+            .as_float_slider()?
+            .value() as f32,
+    })
+}
+
+/// Current layer frame (may be fractional on fields or odd time steps).
+fn current_frame(in_data: InData) -> f64 {
+    in_data.current_time() as f64 / in_data.time_step().max(1) as f64
+}
+
+/// Layer time of source `frame`.
+fn frame_time(in_data: InData, frame: i64) -> i32 {
+    (frame * in_data.time_step() as i64).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+#[derive(Debug)]
+pub struct BlendParams {
+    pub timing: Timing,
+    /// Playback speed in percent.
+    pub speed: f32,
+    pub source_frame: f64,
+    pub motion: Motion,
+    /// Render pixels per unit of the decoded motion vector.
+    pub vector_scale: f32,
+    pub mismatch_threshold: f32,
+}
+
+/// Source frames around the source time of `current_frame` and the position
+/// (0..1) between them.
+fn source_frames(p: &BlendParams, current_frame: f64) -> (i64, i64, f32) {
+    let source = match p.timing {
+        Timing::Speed => {
+            let speed = finite_or(p.speed, 100.0).clamp(0.0, MAX_SPEED) as f64;
+            current_frame * speed / 100.0
+        }
+        Timing::SourceFrame => p.source_frame,
+    };
+    let limit = MAX_SOURCE_FRAME as f64 * MAX_SPEED as f64;
+    let source = if source.is_finite() {
+        source.clamp(-limit, limit)
+    } else {
+        0.0
+    };
+    let earlier = source.floor();
+    let t = (source - earlier) as f32;
+    (earlier as i64, earlier as i64 + 1, t)
+}
+
+/// Interpolates premultiplied samples of the straight `earlier` / `later`
+/// frames at position `t` (0 = earlier, 1 = later).
+fn blend_frames(
+    earlier: &ImageBuf,
+    later: &ImageBuf,
+    vectors: Option<&ImageBuf>,
+    t: f32,
+    p: &BlendParams,
+) -> ImageBuf {
+    let (width, height) = (earlier.width, earlier.height);
+    let mut output = ImageBuf::new(width, height);
+    if earlier.is_empty() {
+        return output;
+    }
+    let t = finite_or(t, 0.0).clamp(0.0, 1.0);
+    if t <= 0.0 || later.is_empty() {
+        return earlier.clone();
+    }
+    let a = sampling::premultiply(earlier);
+    let b = sampling::premultiply(later);
+    let scale = finite_or(p.vector_scale, 0.0).clamp(0.0, MAX_VECTOR_SCALE);
+    let threshold = finite_or(p.mismatch_threshold, 0.0).clamp(0.0, 1.0);
+
+    // 後フレームの各画素について、前フレームからの動き
+    let motion = match p.motion {
+        Motion::InternalFlow => Some(flow::lucas_kanade(later, earlier, &INTERNAL_FLOW)),
+        Motion::VectorLayer => vectors.map(|map| {
+            let mut field = Flow::zero(width, height);
+            for y in 0..height {
+                for x in 0..width {
+                    field.vectors[y * width + x] = flow::vector_at(map, x, y, width, height, scale);
+                }
+            }
+            field
+        }),
+        Motion::FrameMix => None,
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let (fx, fy) = (x as f32, y as f32);
+            let (pa, pb) = match &motion {
+                Some(field) if field.width == width && field.height == height => {
+                    // 中間時刻の画素は前フレームから t、後フレームから 1 - t だけ離れている
+                    let (vx, vy) = field.vectors[y * width + x];
+                    (
+                        sampling::bilinear(&a, fx - vx * t, fy - vy * t),
+                        sampling::bilinear(&b, fx + vx * (1.0 - t), fy + vy * (1.0 - t)),
+                    )
+                }
+                _ => (
+                    a.get(x as isize, y as isize),
+                    b.get_clamped(x as isize, y as isize),
+                ),
+            };
+            let mismatch = (pa.red - pb.red)
+                .abs()
+                .max((pa.green - pb.green).abs())
+                .max((pa.blue - pb.blue).abs())
+                .max((pa.alpha - pb.alpha).abs());
+            // 不一致が閾値を超えたら近い方のフレームへ寄せてゴーストを防ぐ
+            let reject = if threshold > 0.0 {
+                ((mismatch - threshold) / threshold).clamp(0.0, 1.0)
+            } else if mismatch > 0.0 {
+                1.0
+            } else {
+                0.0
+            };
+            let reject = if p.motion == Motion::FrameMix {
+                0.0
+            } else {
+                finite_or(reject, 1.0)
+            };
+            let nearest = if t < 0.5 { 0.0 } else { 1.0 };
+            let w = t + (nearest - t) * reject;
+            let mix = |ca: f32, cb: f32| ca + (cb - ca) * w;
+            let px = PixelF32 {
+                red: mix(pa.red, pb.red),
+                green: mix(pa.green, pb.green),
+                blue: mix(pa.blue, pb.blue),
+                alpha: mix(pa.alpha, pb.alpha),
+            };
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no access to other frames, so the later source frame is
+/// simulated by the input shifted two pixels right and one down, and odd
+/// frames use the input itself as the motion layer.
+pub struct FlowFrameBlend;
+
+impl RenderCore for FlowFrameBlend {
+    type Params = BlendParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        BlendParams {
+            timing: if rng.chance(0.5) {
+                Timing::Speed
+            } else {
+                Timing::SourceFrame
+            },
+            speed: rng.slider(0.0, MAX_SPEED),
+            source_frame: rng.slider(-MAX_SOURCE_FRAME, MAX_SOURCE_FRAME) as f64,
+            motion: match rng.popup(1, 3) {
+                2 => Motion::VectorLayer,
+                3 => Motion::FrameMix,
+                _ => Motion::InternalFlow,
+            },
+            vector_scale: rng.slider(0.0, MAX_VECTOR_SCALE),
+            mismatch_threshold: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let mut later = ImageBuf::new(input.width, input.height);
+        for y in 0..input.height {
+            for x in 0..input.width {
+                later.set(x, y, input.get_clamped(x as isize - 2, y as isize - 1));
+            }
+        }
+        let (_, _, t) = source_frames(params, frame as f64);
+        let vectors = (frame % 2 == 1).then_some(input);
+        *output = blend_frames(input, &later, vectors, t, params);
+    }
+}
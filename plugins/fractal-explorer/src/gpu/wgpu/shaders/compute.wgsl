@@ -0,0 +1,63 @@
+// Keep in sync with `RenderParams` in ../mod.rs and `escape` in lib.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    kind: u32,
+    iterations: u32,
+    smooth_color: u32,
+    _pad: u32,
+    center_x: f32,
+    center_y: f32,
+    step: f32,
+    cos_r: f32,
+    sin_r: f32,
+    c_re: f32,
+    c_im: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<f32>;
+
+const KIND_JULIA: u32 = 1u;
+const BAILOUT2: f32 = 65536.0;
+const LN_BAILOUT: f32 = 5.5451774;
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+
+    let dx = (f32(gid.x) + 0.5 - f32(params.width) * 0.5) * params.step;
+    let dy = (f32(gid.y) + 0.5 - f32(params.height) * 0.5) * params.step;
+    let p = vec2<f32>(
+        params.center_x + dx * params.cos_r - dy * params.sin_r,
+        params.center_y - dx * params.sin_r - dy * params.cos_r,
+    );
+
+    var z = p;
+    var c = vec2<f32>(params.c_re, params.c_im);
+    if (params.kind != KIND_JULIA) {
+        z = vec2<f32>(0.0, 0.0);
+        c = p;
+    }
+
+    var result = -1.0;
+    for (var n = 0u; n < params.iterations; n++) {
+        z = vec2<f32>(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        let r2 = dot(z, z);
+        if (r2 > BAILOUT2) {
+            if (params.smooth_color != 0u) {
+                result = f32(n) + 1.0 - log2(0.5 * log(r2) / LN_BAILOUT);
+            } else {
+                result = f32(n);
+            }
+            break;
+        }
+    }
+    output[i] = result;
+}
@@ -0,0 +1,576 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::ui;
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Fractal,
+    CenterX,
+    CenterY,
+    Zoom,
+    Rotation,
+    JuliaReal,
+    JuliaImaginary,
+    Iterations,
+    SmoothColoring,
+    ColorCycle,
+    ColorOffset,
+    Color1,
+    Color2,
+    Color3,
+    InteriorColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Renders Mandelbrot and Julia fractals.";
+
+/// Height of the view in plane units at zoom 0.
+const VIEW_HEIGHT: f64 = 3.0;
+/// Zoom levels (doublings) before f64 runs out of mantissa.
+const MAX_ZOOM: f64 = 40.0;
+const MAX_ITERATIONS: i32 = 20000;
+/// Squared escape radius; a large radius keeps smooth coloring free of banding.
+const BAILOUT2: f64 = 65536.0;
+/// Entries of the precomputed cyclic gradient.
+const LUT_SIZE: usize = 1024;
+/// The f32 GPU path is used while a pixel spans at least this many ulps of the
+/// largest coordinate in view.
+const GPU_MIN_ULPS: f64 = 16.0;
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fractal {
+    /// z ← z² + c with c at the pixel and z starting at 0.
+    Mandelbrot,
+    /// z ← z² + c with z starting at the pixel and a fixed c.
+    Julia,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Fractal: the Julia c controls are shown only for Julia
+        params.add_with_flags(
+            Params::Fractal,
+            tr("Fractal"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Mandelbrot"), tr("Julia")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Center X / Y: point of the complex plane at the layer center (kept in f64)
+        for (id, name, default) in [
+            (Params::CenterX, tr("Center X"), -0.5),
+            (Params::CenterY, tr("Center Y"), 0.0),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-4.0);
+                    d.set_valid_max(4.0);
+                    d.set_slider_min(-2.0);
+                    d.set_slider_max(2.0);
+                    d.set_default(default);
+                    d.set_precision(6);
+                }),
+            )?;
+        }
+
+        // Zoom: each step doubles the magnification, so linear keyframes zoom at a constant rate
+        params.add(
+            Params::Zoom,
+            tr("Zoom"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_ZOOM as f32);
+                d.set_slider_min(0.0);
+                d.set_slider_max(30.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Julia Real / Imaginary: the constant c of the Julia set
+        for (id, name, default) in [
+            (Params::JuliaReal, tr("Julia Real"), -0.8),
+            (Params::JuliaImaginary, tr("Julia Imaginary"), 0.156),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-2.0);
+                    d.set_valid_max(2.0);
+                    d.set_slider_min(-1.0);
+                    d.set_slider_max(1.0);
+                    d.set_default(default);
+                    d.set_precision(4);
+                }),
+            )?;
+        }
+
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS);
+                d.set_slider_min(16);
+                d.set_slider_max(2000);
+                d.set_default(256);
+            }),
+        )?;
+
+        // Smooth Coloring: fractional escape counts instead of iteration bands
+        params.add(
+            Params::SmoothColoring,
+            tr("Smooth Coloring"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        // Color Cycle: iterations per loop through the three colors
+        params.add(
+            Params::ColorCycle,
+            tr("Color Cycle"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.1);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(200.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Color Offset: shifts the gradient along the cycle; animate it to cycle colors
+        params.add(
+            Params::ColorOffset,
+            tr("Color Offset"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-10000.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        for (id, name, [red, green, blue]) in [
+            (Params::Color1, tr("Color 1"), [10, 20, 80]),
+            (Params::Color2, tr("Color 2"), [240, 240, 220]),
+            (Params::Color3, tr("Color 3"), [230, 120, 20]),
+            (Params::InteriorColor, tr("Interior Color"), [0, 0, 0]),
+        ] {
+            params.add(
+                id,
+                name,
+                ColorDef::setup(|d| {
+                    d.set_default(Pixel8 {
+                        red,
+                        green,
+                        blue,
+                        alpha: 255,
+                    });
+                }),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_FractalExplorer - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Fractal =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer: _,
+                out_layer,
+            } => {
+                self.do_render(out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let Some(out_layer) = out_layer_opt {
+                    self.do_render(out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn fractal_kind(params: &Parameters<Params>) -> Result<Fractal, Error> {
+    Ok(match params.get(Params::Fractal)?.as_popup()?.value() {
+        2 => Fractal::Julia,
+        _ => Fractal::Mandelbrot,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let julia = fractal_kind(params)? == Fractal::Julia;
+    for id in [Params::JuliaReal, Params::JuliaImaginary] {
+        ui::set_param_visible(in_data, params, id, julia)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 表示範囲はレイヤーの高さに合わせるので、ダウンサンプル時も同じ構図になる
+        let slider = |id| -> Result<f64, Error> { Ok(params.get(id)?.as_float_slider()?.value()) };
+        let color = |id| -> Result<[f32; 3], Error> {
+            let c = params.get(id)?.as_color()?.float_value()?;
+            Ok([c.red, c.green, c.blue])
+        };
+        let settings = FractalParams {
+            kind: fractal_kind(params)?,
+            center: (slider(Params::CenterX)?, slider(Params::CenterY)?),
+            zoom: slider(Params::Zoom)?,
+            rotation: params.get(Params::Rotation)?.as_angle()?.value() as f64,
+            c: (slider(Params::JuliaReal)?, slider(Params::JuliaImaginary)?),
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as u32,
+            smooth: params.get(Params::SmoothColoring)?.as_checkbox()?.value(),
+            cycle: slider(Params::ColorCycle)? as f32,
+            offset: slider(Params::ColorOffset)? as f32 / 100.0,
+            colors: [
+                color(Params::Color1)?,
+                color(Params::Color2)?,
+                color(Params::Color3)?,
+            ],
+            interior: color(Params::InteriorColor)?,
+        };
+
+        let (width, height) = (out_layer.width(), out_layer.height());
+
+        #[cfg(feature = "gpu_wgpu")]
+        if let Some(view) = View::new(&settings, height)
+            && view.fits_f32(&settings, width, height)
+            && let Some(ctx) = wgpu_context()
+        {
+            let (c_re, c_im) = settings.c;
+            let gpu_params = RenderParams {
+                width: width as u32,
+                height: height as u32,
+                kind: (settings.kind == Fractal::Julia) as u32,
+                iterations: settings.iterations.clamp(1, MAX_ITERATIONS as u32),
+                smooth: settings.smooth as u32,
+                _pad: 0,
+                center_x: view.center.0 as f32,
+                center_y: view.center.1 as f32,
+                step: view.step as f32,
+                cos: view.cos as f32,
+                sin: view.sin as f32,
+                c_re: finite_or(c_re, 0.0) as f32,
+                c_im: finite_or(c_im, 0.0) as f32,
+                _pad1: [0.0; 3],
+            };
+            if let Ok(escapes) = ctx.render(&gpu_params) {
+                return colorize(&escapes, width, height, &settings).write_to_layer(&mut out_layer);
+            }
+            // Fall through to the CPU path when the GPU dispatch fails.
+        }
+
+        fractal(width, height, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FractalParams {
+    pub kind: Fractal,
+    /// Plane point at the layer center.
+    pub center: (f64, f64),
+    /// Doublings of magnification.
+    pub zoom: f64,
+    /// Degrees.
+    pub rotation: f64,
+    /// Julia constant.
+    pub c: (f64, f64),
+    pub iterations: u32,
+    pub smooth: bool,
+    /// Iterations per gradient loop.
+    pub cycle: f32,
+    /// Gradient shift in loops.
+    pub offset: f32,
+    /// Encoded gradient colors, looped in order.
+    pub colors: [[f32; 3]; 3],
+    pub interior: [f32; 3],
+}
+
+/// Pixel to plane mapping.
+struct View {
+    center: (f64, f64),
+    /// Plane units per pixel.
+    step: f64,
+    cos: f64,
+    sin: f64,
+}
+
+impl View {
+    fn new(p: &FractalParams, height: usize) -> Option<Self> {
+        if height == 0 {
+            return None;
+        }
+        let zoom = finite_or(p.zoom, 0.0).clamp(0.0, MAX_ZOOM);
+        let (sin, cos) = finite_or(p.rotation, 0.0).to_radians().sin_cos();
+        Some(Self {
+            center: (
+                finite_or(p.center.0, 0.0).clamp(-4.0, 4.0),
+                finite_or(p.center.1, 0.0).clamp(-4.0, 4.0),
+            ),
+            step: VIEW_HEIGHT * (-zoom).exp2() / height as f64,
+            cos,
+            sin,
+        })
+    }
+
+    /// Whether neighbouring pixels stay distinct in f32 (the GPU path).
+    #[cfg_attr(not(feature = "gpu_wgpu"), allow(dead_code))]
+    fn fits_f32(&self, p: &FractalParams, width: usize, height: usize) -> bool {
+        // 画面内で最も大きい座標の ulp より 1 画素が十分大きければ f32 で足りる
+        let reach = self.step * (width.max(height) as f64);
+        let mut largest = self.center.0.abs().max(self.center.1.abs()) + reach;
+        if p.kind == Fractal::Julia {
+            largest = largest.max(finite_or(p.c.0, 0.0).abs().max(finite_or(p.c.1, 0.0).abs()));
+        }
+        self.step >= largest.max(2.0) * f32::EPSILON as f64 * GPU_MIN_ULPS
+    }
+
+    fn point(&self, x: usize, y: usize, width: usize, height: usize) -> (f64, f64) {
+        let dx = (x as f64 + 0.5 - width as f64 * 0.5) * self.step;
+        let dy = (y as f64 + 0.5 - height as f64 * 0.5) * self.step;
+        // 画面の上方向を虚軸の正に向ける
+        (
+            self.center.0 + dx * self.cos - dy * self.sin,
+            self.center.1 - dx * self.sin - dy * self.cos,
+        )
+    }
+}
+
+/// Escape value of plane point `q`: the (smoothed) iteration at which the orbit
+/// leaves the bailout radius, or `-1` when it stays bounded. Must match
+/// `shaders/compute.wgsl`.
+fn escape(p: &FractalParams, q: (f64, f64), iterations: u32) -> f32 {
+    let (mut zr, mut zi, cr, ci) = match p.kind {
+        Fractal::Mandelbrot => (0.0, 0.0, q.0, q.1),
+        Fractal::Julia => (
+            q.0,
+            q.1,
+            finite_or(p.c.0, 0.0).clamp(-4.0, 4.0),
+            finite_or(p.c.1, 0.0).clamp(-4.0, 4.0),
+        ),
+    };
+    for n in 0..iterations {
+        (zr, zi) = (zr * zr - zi * zi + cr, 2.0 * zr * zi + ci);
+        let r2 = zr * zr + zi * zi;
+        if r2 > BAILOUT2 {
+            if !p.smooth {
+                return n as f32;
+            }
+            // 脱出半径で正規化し、脱出直後の値が n + 1 から連続するようにする
+            let ln_bailout = 0.5 * BAILOUT2.ln();
+            return (n as f64 + 1.0 - (0.5 * r2.ln() / ln_bailout).log2()) as f32;
+        }
+    }
+    -1.0
+}
+
+/// Cyclic gradient through the three colors, interpolated in OKLab.
+fn gradient_lut(colors: &[[f32; 3]; 3]) -> Vec<[f32; 3]> {
+    let stops = colors.map(|c| Oklab::from_srgb(c.map(|v| v.clamp(0.0, 1.0))));
+    (0..LUT_SIZE)
+        .map(|i| {
+            let t = i as f32 / LUT_SIZE as f32 * 3.0;
+            let k = (t as usize).min(2);
+            stops[k].lerp(stops[(k + 1) % 3], t - k as f32).to_srgb()
+        })
+        .collect()
+}
+
+/// Maps escape values (`-1` for interior) through the gradient.
+pub fn colorize(escapes: &[f32], width: usize, height: usize, p: &FractalParams) -> ImageBuf {
+    let mut out = ImageBuf::new(width, height);
+    let lut = gradient_lut(&p.colors);
+    let cycle = if p.cycle.is_finite() {
+        p.cycle.max(0.1)
+    } else {
+        32.0
+    };
+    let offset = if p.offset.is_finite() {
+        p.offset.rem_euclid(1.0)
+    } else {
+        0.0
+    };
+    for (dst, &mu) in out.data.iter_mut().zip(escapes) {
+        let [red, green, blue] = if mu.is_finite() && mu >= 0.0 {
+            // LUT の隣り合う 2 点を線形補間して帯を出さない
+            let t = (mu / cycle + offset).rem_euclid(1.0) * LUT_SIZE as f32;
+            let i = (t as usize).min(LUT_SIZE - 1);
+            let (a, b) = (lut[i], lut[(i + 1) % LUT_SIZE]);
+            let f = t - i as f32;
+            std::array::from_fn(|c| a[c] + (b[c] - a[c]) * f)
+        } else {
+            p.interior
+        };
+        *dst = PixelF32 {
+            red,
+            green,
+            blue,
+            alpha: 1.0,
+        };
+    }
+    out
+}
+
+/// CPU render in f64, valid down to the deepest zoom.
+pub fn fractal(width: usize, height: usize, p: &FractalParams) -> ImageBuf {
+    let Some(view) = View::new(p, height) else {
+        return ImageBuf::new(width, height);
+    };
+    let iterations = p.iterations.clamp(1, MAX_ITERATIONS as u32);
+    let mut escapes = vec![0.0; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            escapes[y * width + x] = escape(p, view.point(x, y, width, height), iterations);
+        }
+    }
+    colorize(&escapes, width, height, p)
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct FractalExplorer;
+
+impl RenderCore for FractalExplorer {
+    type Params = FractalParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        FractalParams {
+            kind: if rng.chance(0.5) {
+                Fractal::Mandelbrot
+            } else {
+                Fractal::Julia
+            },
+            center: (rng.slider(-2.0, 1.0) as f64, rng.slider(-1.5, 1.5) as f64),
+            zoom: rng.slider(0.0, MAX_ZOOM as f32) as f64,
+            rotation: rng.slider(-360.0, 360.0) as f64,
+            c: (rng.slider(-1.0, 1.0) as f64, rng.slider(-1.0, 1.0) as f64),
+            iterations: rng.int(1, 500) as u32,
+            smooth: rng.chance(0.5),
+            cycle: rng.slider(0.1, 200.0),
+            offset: rng.slider(-10.0, 10.0),
+            colors: std::array::from_fn(|_| [rng.unit(), rng.unit(), rng.unit()]),
+            interior: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = fractal(input.width, input.height, params);
+    }
+}
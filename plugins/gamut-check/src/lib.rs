@@ -0,0 +1,421 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::srgb_to_linear;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    WorkingSpace,
+    LinearWorkingSpace,
+    TargetGamut,
+    FlagAboveWhite,
+    Tolerance,
+    Marker,
+    MarkerColor,
+    StripeWidth,
+    AlphaMask,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Marks pixels outside a target color gamut.";
+
+/// CIE xy of the D65 white point shared by all spaces.
+const D65: (f64, f64) = (0.3127, 0.3290);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// sRGB curve.
+    Srgb,
+    /// Same primaries as sRGB, BT.1886 (gamma 2.4) curve.
+    Rec709,
+    /// P3 primaries with the sRGB curve.
+    DisplayP3,
+    /// BT.1886 (gamma 2.4) curve.
+    Rec2020,
+}
+
+impl ColorSpace {
+    /// Red, green and blue primaries in CIE xy.
+    fn primaries(self) -> [(f64, f64); 3] {
+        match self {
+            Self::Srgb | Self::Rec709 => [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)],
+            Self::DisplayP3 => [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            Self::Rec2020 => [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)],
+        }
+    }
+
+    fn to_linear(self, c: f32) -> f32 {
+        match self {
+            Self::Srgb | Self::DisplayP3 => srgb_to_linear(c),
+            // 負の値も符号を保って伸ばす
+            Self::Rec709 | Self::Rec2020 => c.signum() * c.abs().powf(2.4),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    /// Diagonal stripes of the marker color over the pixel.
+    Zebra,
+    Solid,
+    /// Leaves the colors alone (use with the alpha mask).
+    None,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        let spaces = ["sRGB", "Rec.709", "Display P3", "Rec.2020"];
+
+        // Working Space: the project's working color space
+        params.add(
+            Params::WorkingSpace,
+            tr("Working Space"),
+            PopupDef::setup(|d| {
+                d.set_options(&spaces);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Linear Working Space: values are already linear light (linearized project)
+        params.add(
+            Params::LinearWorkingSpace,
+            tr("Linear Working Space"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::TargetGamut,
+            tr("Target Gamut"),
+            PopupDef::setup(|d| {
+                d.set_options(&spaces);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Flag Above White: also flags values brighter than the target's white
+        params.add(
+            Params::FlagAboveWhite,
+            tr("Flag Above White"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        // Tolerance: allowed excursion beyond 0..1 in percent, to ignore rounding noise
+        params.add(
+            Params::Tolerance,
+            tr("Tolerance"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(5.0);
+                d.set_default(0.1);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Marker,
+            tr("Marker"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Zebra"), tr("Solid Color"), tr("None")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::MarkerColor,
+            tr("Marker Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 0,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Stripe Width: zebra stripe width in pixels
+        params.add(
+            Params::StripeWidth,
+            tr("Stripe Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(200.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(40.0);
+                d.set_default(6.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Alpha Mask: alpha becomes 1 outside the gamut and 0 inside
+        params.add(
+            Params::AlphaMask,
+            tr("Alpha Mask"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_GamutCheck - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn color_space(params: &Parameters<Params>, id: Params) -> Result<ColorSpace, Error> {
+    Ok(match params.get(id)?.as_popup()?.value() {
+        2 => ColorSpace::Rec709,
+        3 => ColorSpace::DisplayP3,
+        4 => ColorSpace::Rec2020,
+        _ => ColorSpace::Srgb,
+    })
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 縞の幅はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let color = params.get(Params::MarkerColor)?.as_color()?.float_value()?;
+        let settings = GamutParams {
+            working_space: color_space(params, Params::WorkingSpace)?,
+            linear: params
+                .get(Params::LinearWorkingSpace)?
+                .as_checkbox()?
+                .value(),
+            target: color_space(params, Params::TargetGamut)?,
+            flag_above_white: params.get(Params::FlagAboveWhite)?.as_checkbox()?.value(),
+            tolerance: params.get(Params::Tolerance)?.as_float_slider()?.value() as f32 / 100.0,
+            marker: match params.get(Params::Marker)?.as_popup()?.value() {
+                2 => Marker::Solid,
+                3 => Marker::None,
+                _ => Marker::Zebra,
+            },
+            marker_color: [color.red, color.green, color.blue],
+            stripe_width: ds
+                .length(params.get(Params::StripeWidth)?.as_float_slider()?.value() as f32),
+            alpha_mask: params.get(Params::AlphaMask)?.as_checkbox()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        gamut_check(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct GamutParams {
+    pub working_space: ColorSpace,
+    /// Input values are linear light rather than encoded.
+    pub linear: bool,
+    pub target: ColorSpace,
+    pub flag_above_white: bool,
+    /// Allowed excursion beyond 0..1.
+    pub tolerance: f32,
+    pub marker: Marker,
+    pub marker_color: [f32; 3],
+    /// Render pixels.
+    pub stripe_width: f32,
+    pub alpha_mask: bool,
+}
+
+type Mat3 = [[f64; 3]; 3];
+
+fn mul(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn invert(m: &Mat3) -> Mat3 {
+    let cof = |r: usize, c: usize| {
+        let (r0, r1) = ((r + 1) % 3, (r + 2) % 3);
+        let (c0, c1) = ((c + 1) % 3, (c + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let det = (0..3).map(|c| m[0][c] * cof(0, c)).sum::<f64>();
+    // 余因子の転置を行列式で割る
+    std::array::from_fn(|i| std::array::from_fn(|j| cof(j, i) / det))
+}
+
+/// Linear RGB to XYZ for `space`, scaled so RGB white maps to D65.
+fn rgb_to_xyz(space: ColorSpace) -> Mat3 {
+    let xyz = |(x, y): (f64, f64)| [x / y, 1.0, (1.0 - x - y) / y];
+    let p = space.primaries().map(xyz);
+    let m: Mat3 = std::array::from_fn(|i| std::array::from_fn(|j| p[j][i]));
+    let white = xyz(D65);
+    let inv = invert(&m);
+    let s: [f64; 3] = std::array::from_fn(|i| (0..3).map(|k| inv[i][k] * white[k]).sum());
+    std::array::from_fn(|i| std::array::from_fn(|j| m[i][j] * s[j]))
+}
+
+/// Linear working-space RGB to linear target RGB.
+fn conversion(from: ColorSpace, to: ColorSpace) -> [[f32; 3]; 3] {
+    mul(&invert(&rgb_to_xyz(to)), &rgb_to_xyz(from)).map(|row| row.map(|v| v as f32))
+}
+
+pub fn gamut_check(input: &ImageBuf, p: &GamutParams) -> ImageBuf {
+    let mut out = input.clone();
+    let m = conversion(p.working_space, p.target);
+    let tolerance = finite_or(p.tolerance, 0.001).clamp(0.0, 1.0);
+    let stripe = finite_or(p.stripe_width, 6.0).max(1.0);
+    let marker = p.marker_color.map(|c| finite_or(c, 0.0));
+    for (i, px) in out.data.iter_mut().enumerate() {
+        let rgb = [px.red, px.green, px.blue].map(|c| {
+            let c = finite_or(c, 0.0);
+            if p.linear {
+                c
+            } else {
+                p.working_space.to_linear(c)
+            }
+        });
+        let target = m.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+        let outside = target
+            .iter()
+            .any(|&c| c < -tolerance || (p.flag_above_white && c > 1.0 + tolerance));
+        if outside {
+            let (x, y) = ((i % input.width) as f32, (i / input.width) as f32);
+            // 右上がりの斜め縞の片側だけを塗る
+            let on = match p.marker {
+                Marker::Zebra => ((x + y + 1.0) / stripe).floor().rem_euclid(2.0) < 1.0,
+                Marker::Solid => true,
+                Marker::None => false,
+            };
+            if on {
+                [px.red, px.green, px.blue] = marker;
+                if !p.alpha_mask {
+                    px.alpha = 1.0;
+                }
+            }
+        }
+        if p.alpha_mask {
+            px.alpha = if outside { 1.0 } else { 0.0 };
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct GamutCheck;
+
+impl RenderCore for GamutCheck {
+    type Params = GamutParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let space = |rng: &mut Rng| match rng.popup(1, 4) {
+            2 => ColorSpace::Rec709,
+            3 => ColorSpace::DisplayP3,
+            4 => ColorSpace::Rec2020,
+            _ => ColorSpace::Srgb,
+        };
+        GamutParams {
+            working_space: space(rng),
+            linear: rng.chance(0.5),
+            target: space(rng),
+            flag_above_white: rng.chance(0.5),
+            tolerance: rng.slider(0.0, 0.05),
+            marker: match rng.popup(1, 3) {
+                2 => Marker::Solid,
+                3 => Marker::None,
+                _ => Marker::Zebra,
+            },
+            marker_color: [rng.unit(), rng.unit(), rng.unit()],
+            stripe_width: rng.slider(1.0, 40.0),
+            alpha_mask: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = gamut_check(input, params);
+    }
+}
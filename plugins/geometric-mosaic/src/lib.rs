@@ -0,0 +1,490 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::HashMap;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, unpremultiply};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    CellShape,
+    CellSize,
+    Rotation,
+    Origin,
+    CellColor,
+    Gap,
+    GapFill,
+    GapColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Pixelates the layer into square, hexagonal or triangular cells.";
+
+/// Upper bound of `Cell Size` (full-resolution pixels).
+const MAX_CELL_SIZE: f32 = 2000.0;
+/// Upper bound of `Gap` (full-resolution pixels).
+const MAX_GAP: f32 = 200.0;
+
+const SQRT_3: f32 = 1.732_050_8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellShape {
+    Square,
+    Hexagon,
+    Triangle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellColor {
+    Average,
+    CenterSample,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GapFill {
+    Color,
+    Transparent,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::CellShape,
+            tr("Cell Shape"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Square"), tr("Hexagon"), tr("Triangle")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Cell Size: square side, hexagon width across flats or triangle side
+        params.add(
+            Params::CellSize,
+            tr("Cell Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(2.0);
+                d.set_valid_max(MAX_CELL_SIZE);
+                d.set_slider_min(2.0);
+                d.set_slider_max(200.0);
+                d.set_default(24.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Origin: grid anchor and pivot of the rotation
+        params.add(
+            Params::Origin,
+            tr("Origin"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        params.add(
+            Params::CellColor,
+            tr("Cell Color"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Average"), tr("Center Sample")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Gap: mortar width between cells
+        params.add(
+            Params::Gap,
+            tr("Gap"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_GAP);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::GapFill,
+            tr("Gap Fill"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Color"), tr("Transparent")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::GapColor,
+            tr("Gap Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_GeometricMosaic - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // セルはフル解像度の正方ピクセル空間で組むので、縦横別のダウンサンプル率と
+        // ピクセル縦横比から描画ピクセル 1 つ分の大きさを求める
+        let ds = Downsample::from_in_data(&in_data);
+        let par = in_data.pixel_aspect_ratio();
+        let par = par.num as f32 / par.den.max(1) as f32;
+        let gap_color = params.get(Params::GapColor)?.as_color()?.float_value()?;
+
+        let settings = MosaicParams {
+            shape: match params.get(Params::CellShape)?.as_popup()?.value() {
+                2 => CellShape::Hexagon,
+                3 => CellShape::Triangle,
+                _ => CellShape::Square,
+            },
+            cell_size: params.get(Params::CellSize)?.as_float_slider()?.value() as f32,
+            rotation: params.get(Params::Rotation)?.as_angle()?.value(),
+            origin: ds.point(params.get(Params::Origin)?.as_point()?.value()),
+            cell_color: match params.get(Params::CellColor)?.as_popup()?.value() {
+                2 => CellColor::CenterSample,
+                _ => CellColor::Average,
+            },
+            gap: params.get(Params::Gap)?.as_float_slider()?.value() as f32,
+            gap_fill: match params.get(Params::GapFill)?.as_popup()?.value() {
+                2 => GapFill::Transparent,
+                _ => GapFill::Color,
+            },
+            gap_color: [gap_color.red, gap_color.green, gap_color.blue],
+            pixel_size: (par / ds.x, 1.0 / ds.y),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        mosaic(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct MosaicParams {
+    pub shape: CellShape,
+    /// Full-resolution square pixels.
+    pub cell_size: f32,
+    /// Degrees.
+    pub rotation: f32,
+    /// Grid origin in render pixels.
+    pub origin: (f32, f32),
+    pub cell_color: CellColor,
+    /// Full-resolution square pixels.
+    pub gap: f32,
+    pub gap_fill: GapFill,
+    pub gap_color: [f32; 3],
+    /// Size of one render pixel in full-resolution square pixels (pixel aspect / downsample).
+    pub pixel_size: (f32, f32),
+}
+
+/// Cell of the tiling containing a point: its lattice key, center and the
+/// distance from the point to the nearest cell edge.
+struct Cell {
+    key: (i32, i32, u8),
+    center: (f32, f32),
+    edge: f32,
+}
+
+/// Locates the cell around grid-space point `(u, v)` for cells of size `s`.
+fn cell_at(shape: CellShape, s: f32, u: f32, v: f32) -> Cell {
+    match shape {
+        CellShape::Square => {
+            let (i, j) = ((u / s).floor(), (v / s).floor());
+            let (fu, fv) = (u - i * s, v - j * s);
+            Cell {
+                key: (i as i32, j as i32, 0),
+                center: ((i + 0.5) * s, (j + 0.5) * s),
+                edge: fu.min(s - fu).min(fv).min(s - fv),
+            }
+        }
+        CellShape::Hexagon => {
+            // 尖った頂点が上下を向く六角形。幅 (対辺間) が s、外接半径は s/√3
+            let radius = s / SQRT_3;
+            let q = (SQRT_3 / 3.0 * u - v / 3.0) / radius;
+            let r = (2.0 / 3.0 * v) / radius;
+            let (x, z) = (q, r);
+            let y = -x - z;
+            let (mut rx, ry, mut rz) = (x.round(), y.round(), z.round());
+            let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+            if dx > dy && dx > dz {
+                rx = -ry - rz;
+            } else if dy <= dz {
+                rz = -rx - ry;
+            }
+            let cx = radius * SQRT_3 * (rx + rz / 2.0);
+            let cy = radius * 1.5 * rz;
+            let (du, dv) = (u - cx, v - cy);
+            let reach = du
+                .abs()
+                .max((0.5 * du + 0.5 * SQRT_3 * dv).abs())
+                .max((-0.5 * du + 0.5 * SQRT_3 * dv).abs());
+            Cell {
+                key: (rx as i32, rz as i32, 0),
+                center: (cx, cy),
+                edge: s / 2.0 - reach,
+            }
+        }
+        CellShape::Triangle => {
+            // 斜交格子 (s, 0), (s/2, h) の平行四辺形を 2 つの正三角形に分ける
+            let h = s * SQRT_3 / 2.0;
+            let q = v / h;
+            let p = u / s - q / 2.0;
+            let (i, j) = (p.floor(), q.floor());
+            let (fp, fq) = (p - i, q - j);
+            let (upper, bary, c) = if fp + fq < 1.0 {
+                (0, fp.min(fq).min(1.0 - fp - fq), 1.0 / 3.0)
+            } else {
+                (1, (1.0 - fp).min(1.0 - fq).min(fp + fq - 1.0), 2.0 / 3.0)
+            };
+            let (cp, cq) = (i + c, j + c);
+            Cell {
+                key: (i as i32, j as i32, upper),
+                center: (cp * s + cq * s / 2.0, cq * h),
+                edge: bary * h,
+            }
+        }
+    }
+}
+
+pub fn mosaic(input: &ImageBuf, p: &MosaicParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+
+    let (sx, sy) = (
+        finite_or(p.pixel_size.0, 1.0).clamp(1e-3, 1e3),
+        finite_or(p.pixel_size.1, 1.0).clamp(1e-3, 1e3),
+    );
+    let size = finite_or(p.cell_size, 2.0).clamp(1.0, MAX_CELL_SIZE);
+    let gap = finite_or(p.gap, 0.0).clamp(0.0, MAX_GAP).min(size);
+    let (sin, cos) = finite_or(p.rotation, 0.0).to_radians().sin_cos();
+    let origin = (finite_or(p.origin.0, 0.0), finite_or(p.origin.1, 0.0));
+
+    // 描画ピクセル → 正方ピクセル空間 → 回転したグリッド空間
+    let to_grid = |x: f32, y: f32| {
+        let (dx, dy) = ((x - origin.0) * sx, (y - origin.1) * sy);
+        (dx * cos + dy * sin, dy * cos - dx * sin)
+    };
+    let to_render = |u: f32, v: f32| {
+        let (dx, dy) = (u * cos - v * sin, u * sin + v * cos);
+        (origin.0 + dx / sx, origin.1 + dy / sy)
+    };
+
+    let cells: Vec<Cell> = (0..width * height)
+        .map(|i| {
+            let (u, v) = to_grid((i % width) as f32, (i / width) as f32);
+            cell_at(p.shape, size, u, v)
+        })
+        .collect();
+
+    // セルごとの乗算済み平均色
+    let mut average: HashMap<(i32, i32, u8), [f32; 5]> = HashMap::new();
+    if p.cell_color == CellColor::Average {
+        for (cell, px) in cells.iter().zip(&input.data) {
+            let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+            let c = |v: f32| finite_or(v, 0.0) * a;
+            let acc = average.entry(cell.key).or_insert([0.0; 5]);
+            acc[0] += c(px.red);
+            acc[1] += c(px.green);
+            acc[2] += c(px.blue);
+            acc[3] += a;
+            acc[4] += 1.0;
+        }
+    }
+
+    let gap_px = match p.gap_fill {
+        GapFill::Color => {
+            let c = p.gap_color.map(|v| finite_or(v, 0.0));
+            PixelF32 {
+                red: c[0],
+                green: c[1],
+                blue: c[2],
+                alpha: 1.0,
+            }
+        }
+        GapFill::Transparent => PixelF32::default(),
+    };
+    // 目地の縁を 1 描画ピクセル幅でアンチエイリアスする
+    let aa = (sx * sy).sqrt();
+
+    for (i, cell) in cells.iter().enumerate() {
+        let fill = match p.cell_color {
+            CellColor::Average => {
+                let acc = average[&cell.key];
+                let n = acc[4].max(1.0);
+                PixelF32 {
+                    red: acc[0] / n,
+                    green: acc[1] / n,
+                    blue: acc[2] / n,
+                    alpha: acc[3] / n,
+                }
+            }
+            CellColor::CenterSample => {
+                let (x, y) = to_render(cell.center.0, cell.center.1);
+                let px = input.get_clamped(finite_or(x, 0.0) as isize, finite_or(y, 0.0) as isize);
+                let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+                let c = |v: f32| finite_or(v, 0.0) * a;
+                PixelF32 {
+                    red: c(px.red),
+                    green: c(px.green),
+                    blue: c(px.blue),
+                    alpha: a,
+                }
+            }
+        };
+        let k = if gap > 0.0 {
+            ((cell.edge - gap / 2.0) / aa + 0.5).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let mix = |a: f32, b: f32| a * k + b * (1.0 - k);
+        out.data[i] = unpremultiply(PixelF32 {
+            red: mix(fill.red, gap_px.red),
+            green: mix(fill.green, gap_px.green),
+            blue: mix(fill.blue, gap_px.blue),
+            alpha: mix(fill.alpha, gap_px.alpha),
+        });
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct GeometricMosaic;
+
+impl RenderCore for GeometricMosaic {
+    type Params = MosaicParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        MosaicParams {
+            shape: match rng.popup(1, 3) {
+                2 => CellShape::Hexagon,
+                3 => CellShape::Triangle,
+                _ => CellShape::Square,
+            },
+            cell_size: rng.slider(2.0, MAX_CELL_SIZE),
+            rotation: rng.slider(-360.0, 360.0),
+            origin: (rng.slider(-100.0, 200.0), rng.slider(-100.0, 200.0)),
+            cell_color: if rng.chance(0.5) {
+                CellColor::Average
+            } else {
+                CellColor::CenterSample
+            },
+            gap: rng.slider(0.0, MAX_GAP),
+            gap_fill: if rng.chance(0.5) {
+                GapFill::Color
+            } else {
+                GapFill::Transparent
+            },
+            gap_color: [rng.unit(), rng.unit(), rng.unit()],
+            pixel_size: (rng.slider(0.25, 4.0), rng.slider(0.25, 4.0)),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = mosaic(input, params);
+    }
+}
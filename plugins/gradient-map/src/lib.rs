@@ -0,0 +1,565 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::hash_unit;
+use utils::ToPixel;
+use utils::color::{Oklab, luminance};
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    StopCount,
+    Stop1Color,
+    Stop1Position,
+    Stop2Color,
+    Stop2Position,
+    Stop3Color,
+    Stop3Position,
+    Stop4Color,
+    Stop4Position,
+    Stop5Color,
+    Stop5Position,
+    Stop6Color,
+    Stop6Position,
+    Interpolation,
+    Dither,
+    PreserveAlpha,
+}
+
+/// (color, position) parameter ids of each stop, in UI order.
+const STOPS: [(Params, Params); 6] = [
+    (Params::Stop1Color, Params::Stop1Position),
+    (Params::Stop2Color, Params::Stop2Position),
+    (Params::Stop3Color, Params::Stop3Position),
+    (Params::Stop4Color, Params::Stop4Position),
+    (Params::Stop5Color, Params::Stop5Position),
+    (Params::Stop6Color, Params::Stop6Position),
+];
+
+/// Entries of the precomputed gradient (interpolated linearly in between).
+const LUT_SIZE: usize = 1024;
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Maps luminance through a multi-stop gradient interpolated in OKLab or OKLCH.";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Interpolation {
+    Oklab,
+    Oklch,
+    Srgb,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Stop Count: stops above the count are hidden (see update_stop_visibility)
+        params.add_with_flags(
+            Params::StopCount,
+            tr("Stop Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(STOPS.len() as i32);
+                d.set_slider_min(2);
+                d.set_slider_max(STOPS.len() as i32);
+                d.set_default(3);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Stop1Color,
+            tr("Stop 1 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 16,
+                    green: 12,
+                    blue: 48,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Stop1Position,
+            tr("Stop 1 Position"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Stop2Color,
+            tr("Stop 2 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 214,
+                    green: 64,
+                    blue: 96,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Stop2Position,
+            tr("Stop 2 Position"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Stop3Color,
+            tr("Stop 3 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 236,
+                    blue: 170,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Stop3Position,
+            tr("Stop 3 Position"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Stop4Color,
+            tr("Stop 4 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Stop4Position,
+            tr("Stop 4 Position"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Stop5Color,
+            tr("Stop 5 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Stop5Position,
+            tr("Stop 5 Position"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Stop6Color,
+            tr("Stop 6 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Stop6Position,
+            tr("Stop 6 Position"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Interpolation,
+            tr("Interpolation"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("OKLab"), tr("OKLCH"), tr("sRGB")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Dither,
+            tr("Dither"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        params.add(
+            Params::PreserveAlpha,
+            tr("Preserve Alpha"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_GradientMap - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_stop_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::StopCount =>
+            {
+                update_stop_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_stop_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let count = params.get(Params::StopCount)?.as_slider()?.value() as usize;
+    for (i, (color, position)) in STOPS.iter().enumerate() {
+        for id in [*color, *position] {
+            ui::set_param_visible(in_data, params, id, i < count)?;
+        }
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+        let frame_num = in_data.current_frame() as u32;
+
+        let count = params.get(Params::StopCount)?.as_slider()?.value() as usize;
+        let mut stops = Vec::with_capacity(count);
+        for (color, position) in STOPS.iter().take(count) {
+            let c = params.get(*color)?.as_color()?.float_value()?;
+            let p = params.get(*position)?.as_float_slider()?.value() as f32;
+            stops.push(Stop {
+                position: p,
+                color: [c.red, c.green, c.blue],
+            });
+        }
+        let interpolation = match params.get(Params::Interpolation)?.as_popup()?.value() {
+            2 => Interpolation::Oklch,
+            3 => Interpolation::Srgb,
+            _ => Interpolation::Oklab,
+        };
+        let dither = params.get(Params::Dither)?.as_checkbox()?.value();
+        let preserve_alpha = params.get(Params::PreserveAlpha)?.as_checkbox()?.value();
+
+        let in_world_type = in_layer.world_type();
+        let out_world_type = out_layer.world_type();
+
+        // ディザ幅は出力の 1 LSB（float 出力では不要）
+        let dither_lsb = match out_world_type {
+            _ if !dither => 0.0,
+            ae::aegp::WorldType::U8 => 1.0 / ae::MAX_CHANNEL8 as f32,
+            ae::aegp::WorldType::U15 => 1.0 / ae::MAX_CHANNEL16 as f32,
+            ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => 0.0,
+        };
+        let map = GradientMapParams {
+            stops,
+            interpolation,
+            dither_lsb,
+            preserve_alpha,
+        }
+        .build();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let x = x as usize;
+            let y = y as usize;
+
+            let px = utils::image::read_pixel_f32(&in_layer, in_world_type, x, y);
+            let px = map.apply(px, x, y, frame_num);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stop {
+    pub position: f32,
+    /// Gamma-encoded RGB.
+    pub color: [f32; 3],
+}
+
+#[derive(Debug)]
+pub struct GradientMapParams {
+    pub stops: Vec<Stop>,
+    pub interpolation: Interpolation,
+    /// Dither amplitude (one output LSB), 0 to disable.
+    pub dither_lsb: f32,
+    pub preserve_alpha: bool,
+}
+
+impl GradientMapParams {
+    /// Bakes the gradient into a lookup table.
+    fn build(&self) -> GradientLut {
+        let mut stops = self.stops.clone();
+        stops.retain(|s| s.position.is_finite());
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+        let lut = (0..LUT_SIZE)
+            .map(|i| sample_stops(&stops, i as f32 / (LUT_SIZE - 1) as f32, self.interpolation))
+            .collect();
+        GradientLut {
+            lut,
+            dither_lsb: self.dither_lsb,
+            preserve_alpha: self.preserve_alpha,
+        }
+    }
+}
+
+struct GradientLut {
+    lut: Vec<[f32; 3]>,
+    dither_lsb: f32,
+    preserve_alpha: bool,
+}
+
+impl GradientLut {
+    fn apply(&self, px: PixelF32, x: usize, y: usize, frame: u32) -> PixelF32 {
+        let t = luminance(px);
+        // NaN は 0 として扱う
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let f = t * (LUT_SIZE - 1) as f32;
+        let i = (f as usize).min(LUT_SIZE - 2);
+        let s = f - i as f32;
+        let (a, b) = (self.lut[i], self.lut[i + 1]);
+        let mut rgb: [f32; 3] = std::array::from_fn(|c| a[c] + (b[c] - a[c]) * s);
+
+        if self.dither_lsb > 0.0 {
+            // 三角分布ノイズでバンディングを抑える（チャンネルごとに独立）
+            for (c, v) in rgb.iter_mut().enumerate() {
+                let n = hash_unit(
+                    x as u32,
+                    y as u32,
+                    frame.wrapping_mul(3).wrapping_add(c as u32),
+                );
+                let m = hash_unit(
+                    y as u32,
+                    x as u32,
+                    frame.wrapping_mul(7).wrapping_add(c as u32),
+                );
+                *v += (n + m - 1.0) * self.dither_lsb;
+            }
+        }
+
+        PixelF32 {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            alpha: if self.preserve_alpha { px.alpha } else { 1.0 },
+        }
+    }
+}
+
+/// Gradient color at `t` (0..1) for stops sorted by position.
+fn sample_stops(stops: &[Stop], t: f32, interpolation: Interpolation) -> [f32; 3] {
+    let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+        return [0.0; 3];
+    };
+    if t <= first.position {
+        return first.color;
+    }
+    if t >= last.position {
+        return last.color;
+    }
+    let Some(w) = stops.windows(2).find(|w| t <= w[1].position) else {
+        return last.color;
+    };
+    let (a, b) = (w[0], w[1]);
+    let span = b.position - a.position;
+    let s = if span > f32::EPSILON {
+        (t - a.position) / span
+    } else {
+        1.0
+    };
+
+    match interpolation {
+        Interpolation::Srgb => std::array::from_fn(|c| a.color[c] + (b.color[c] - a.color[c]) * s),
+        Interpolation::Oklab => Oklab::from_srgb(a.color)
+            .lerp(Oklab::from_srgb(b.color), s)
+            .to_srgb(),
+        Interpolation::Oklch => Oklab::from_srgb(a.color)
+            .to_oklch()
+            .lerp(Oklab::from_srgb(b.color).to_oklch(), s)
+            .to_oklab()
+            .to_srgb(),
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct GradientMap;
+
+impl RenderCore for GradientMap {
+    type Params = GradientMapParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let count = rng.int(2, STOPS.len() as i32) as usize;
+        let stops = (0..count)
+            .map(|_| Stop {
+                position: rng.slider(0.0, 1.0),
+                color: [rng.unit(), rng.unit(), rng.unit()],
+            })
+            .collect();
+        GradientMapParams {
+            stops,
+            interpolation: match rng.popup(1, 3) {
+                2 => Interpolation::Oklch,
+                3 => Interpolation::Srgb,
+                _ => Interpolation::Oklab,
+            },
+            dither_lsb: if rng.chance(0.5) { 1.0 / 255.0 } else { 0.0 },
+            preserve_alpha: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let map = params.build();
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let px = input.data[input.index(x, y)];
+                output.set(x, y, map.apply(px, x, y, frame));
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    TransitionCompletion,
+    WipeMap,
+    InvertMap,
+    Softness,
+    TargetLayer,
+    EdgeGlowWidth,
+    EdgeGlowIntensity,
+    EdgeGlowColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Wipes between layers in the order of a luminance map.";
+
+/// Checkout ids of the wipe map and target layers (the input uses 0).
+const WIPE_MAP_CHECKOUT_ID: i32 = 1;
+const TARGET_CHECKOUT_ID: i32 = 2;
+const MAX_GLOW: f32 = 100.0;
+/// Completion over which the edge glow fades in at the start and out at the
+/// end, so 0 % and 100 % show the plain layers.
+const GLOW_FADE: f32 = 0.05;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Transition Completion: percent; 0 shows the layer, 100 the target
+        params.add(
+            Params::TransitionCompletion,
+            tr("Transition Completion"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Wipe Map: dark areas wipe first; without one the layer's own luminance is used
+        params.add(Params::WipeMap, tr("Wipe Map"), LayerDef::new())?;
+
+        params.add(
+            Params::InvertMap,
+            tr("Invert Map"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Softness: width of the dissolving band in wipe map luminance
+        params.add(
+            Params::Softness,
+            tr("Softness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.1);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Target Layer: shown where the wipe has passed; without one the layer turns transparent
+        params.add(Params::TargetLayer, tr("Target Layer"), LayerDef::new())?;
+
+        // Edge Glow Width: reach of the glow around the wipe front in wipe map luminance
+        params.add(
+            Params::EdgeGlowWidth,
+            tr("Edge Glow Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Edge Glow Intensity: values above 1 go beyond white in 32bpc
+        params.add(
+            Params::EdgeGlowIntensity,
+            tr("Edge Glow Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_GLOW);
+                d.set_slider_min(0.0);
+                d.set_slider_max(4.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::EdgeGlowColor,
+            tr("Edge Glow Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 170,
+                    blue: 64,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_GradientWipe - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではワイプマップ・ターゲットレイヤーをパラメータから直接取得する
+                let mut layer = |id| -> Result<Option<ImageBuf>, Error> {
+                    Ok(params
+                        .checkout_at(
+                            id,
+                            Some(in_data.current_time()),
+                            Some(in_data.time_step()),
+                            Some(in_data.time_scale()),
+                        )?
+                        .as_layer()?
+                        .value()
+                        .map(|layer| ImageBuf::from_layer(&layer)))
+                };
+                let wipe_map = layer(Params::WipeMap)?;
+                let target = layer(Params::TargetLayer)?;
+                let layers = Layers {
+                    wipe_map: wipe_map.as_ref(),
+                    target: target.as_ref(),
+                };
+                self.do_render(in_layer, layers, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                for (id, checkout_id) in [
+                    (Params::WipeMap, WIPE_MAP_CHECKOUT_ID),
+                    (Params::TargetLayer, TARGET_CHECKOUT_ID),
+                ] {
+                    if let Some(index) = params.index(id) {
+                        let _ = extra.callbacks().checkout_layer(
+                            index as i32,
+                            checkout_id,
+                            &req,
+                            in_data.current_time(),
+                            in_data.time_step(),
+                            in_data.time_scale(),
+                        );
+                    }
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let wipe_map = cb
+                    .checkout_layer_pixels(WIPE_MAP_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let target = cb
+                    .checkout_layer_pixels(TARGET_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    let layers = Layers {
+                        wipe_map: wipe_map.as_ref(),
+                        target: target.as_ref(),
+                    };
+                    self.do_render(in_layer, layers, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(WIPE_MAP_CHECKOUT_ID as u32)?;
+                cb.checkin_layer_pixels(TARGET_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        layers: Layers,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let glow = params
+            .get(Params::EdgeGlowColor)?
+            .as_color()?
+            .float_value()?;
+        let settings = WipeParams {
+            completion: params
+                .get(Params::TransitionCompletion)?
+                .as_float_slider()?
+                .value() as f32
+                / 100.0,
+            invert_map: params.get(Params::InvertMap)?.as_checkbox()?.value(),
+            softness: params.get(Params::Softness)?.as_float_slider()?.value() as f32,
+            glow_width: params
+                .get(Params::EdgeGlowWidth)?
+                .as_float_slider()?
+                .value() as f32,
+            glow_intensity: params
+                .get(Params::EdgeGlowIntensity)?
+                .as_float_slider()?
+                .value() as f32,
+            glow_color: [glow.red, glow.green, glow.blue],
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        wipe(&input, layers, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Optional wipe map and target layers.
+#[derive(Clone, Copy, Default)]
+pub struct Layers<'a> {
+    pub wipe_map: Option<&'a ImageBuf>,
+    pub target: Option<&'a ImageBuf>,
+}
+
+#[derive(Debug)]
+pub struct WipeParams {
+    /// `0..=1`.
+    pub completion: f32,
+    pub invert_map: bool,
+    pub softness: f32,
+    pub glow_width: f32,
+    pub glow_intensity: f32,
+    /// Encoded glow color.
+    pub glow_color: [f32; 3],
+}
+
+/// Bilinear sample of `image` at input pixel (`x`, `y`), stretching images of
+/// another size over the layer.
+fn stretched(image: &ImageBuf, x: usize, y: usize, width: usize, height: usize) -> PixelF32 {
+    let sx = (x as f32 + 0.5) * image.width as f32 / width as f32 - 0.5;
+    let sy = (y as f32 + 0.5) * image.height as f32 / height as f32 - 0.5;
+    sampling::bilinear(image, sx, sy)
+}
+
+/// Target share (0 = layer, 1 = target) and edge glow at wipe map value `v`.
+fn transition(v: f32, p: &WipeParams) -> (f32, f32) {
+    let c = finite_or(p.completion, 0.0).clamp(0.0, 1.0);
+    let soft = finite_or(p.softness, 0.0).clamp(0.0, 1.0);
+    // 帯の幅ぶん進行を延ばし、0 % と 100 % で必ず切り替わり切るようにする
+    let front = c * (1.0 + soft);
+    let mix = if soft > 0.0 {
+        ((front - v) / soft).clamp(0.0, 1.0)
+    } else if v < c || c >= 1.0 {
+        1.0
+    } else {
+        0.0
+    };
+
+    let width = finite_or(p.glow_width, 0.0).clamp(0.0, 1.0);
+    let glow = if width > 0.0 {
+        let center = front - soft * 0.5;
+        let fade = (c / GLOW_FADE).min((1.0 - c) / GLOW_FADE).clamp(0.0, 1.0);
+        (1.0 - (v - center).abs() / width).max(0.0) * fade
+    } else {
+        0.0
+    };
+    (mix, glow)
+}
+
+fn wipe(input: &ImageBuf, layers: Layers, p: &WipeParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+    let map = layers.wipe_map.filter(|m| !m.is_empty()).unwrap_or(input);
+    // 合成は乗算済みアルファで行う（HDR 値はクランプしない）
+    let source = sampling::premultiply(input);
+    let target = layers
+        .target
+        .filter(|t| !t.is_empty())
+        .map(sampling::premultiply);
+    let intensity = finite_or(p.glow_intensity, 0.0).clamp(0.0, MAX_GLOW);
+    let glow_color = p.glow_color.map(|c| finite_or(c, 0.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let v = finite_or(luminance(stretched(map, x, y, width, height)), 0.0).clamp(0.0, 1.0);
+            let v = if p.invert_map { 1.0 - v } else { v };
+            let (mix, glow) = transition(v, p);
+
+            let a = source.data[i];
+            let b = target.as_ref().map_or(utils::image::TRANSPARENT, |t| {
+                stretched(t, x, y, width, height)
+            });
+            let g = glow * intensity;
+            let lerp = |a: f32, b: f32| a + (b - a) * mix;
+            let alpha = lerp(a.alpha, b.alpha);
+            out.data[i] = sampling::unpremultiply(PixelF32 {
+                red: lerp(a.red, b.red) + glow_color[0] * g,
+                green: lerp(a.green, b.green) + glow_color[1] * g,
+                blue: lerp(a.blue, b.blue) + glow_color[2] * g,
+                alpha: alpha + (1.0 - alpha) * g.min(1.0),
+            });
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no extra layers, so odd frames use the input itself as
+/// both the wipe map and the target layer.
+pub struct GradientWipe;
+
+impl RenderCore for GradientWipe {
+    type Params = WipeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        WipeParams {
+            completion: rng.slider(0.0, 1.0),
+            invert_map: rng.chance(0.5),
+            softness: rng.slider(0.0, 1.0),
+            glow_width: rng.slider(0.0, 1.0),
+            glow_intensity: rng.slider(0.0, MAX_GLOW),
+            glow_color: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let layers = if frame % 2 == 1 {
+            Layers {
+                wipe_map: Some(input),
+                target: Some(input),
+            }
+        } else {
+            Layers::default()
+        };
+        *output = wipe(input, layers, params);
+    }
+}
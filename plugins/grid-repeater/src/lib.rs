@@ -0,0 +1,402 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Columns,
+    Rows,
+    CellOrder,
+    TimeOffset,
+    Spacing,
+    Scale,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Tiles time-offset copies of the layer into a grid.";
+
+/// Upper bound of `Columns` and `Rows`; every cell may check out its own frame.
+const MAX_GRID: usize = 8;
+/// Largest box reduction applied to a frame before it is sampled into a cell.
+const MAX_REDUCTION: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellOrder {
+    /// Left to right, then the next row.
+    RowMajor,
+    /// Top to bottom, then the next column.
+    ColumnMajor,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        for (id, name) in [(Params::Columns, tr("Columns")), (Params::Rows, tr("Rows"))] {
+            params.add(
+                id,
+                name,
+                SliderDef::setup(|d| {
+                    d.set_valid_min(1);
+                    d.set_valid_max(MAX_GRID as i32);
+                    d.set_slider_min(1);
+                    d.set_slider_max(MAX_GRID as i32);
+                    d.set_default(4);
+                }),
+            )?;
+        }
+
+        // Cell Order: which way the time offset advances through the grid
+        params.add(
+            Params::CellOrder,
+            tr("Cell Order"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Rows First"), tr("Columns First")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Time Offset: frames added per cell, 0 = every cell shows the current frame
+        params.add(
+            Params::TimeOffset,
+            tr("Time Offset"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-600.0);
+                d.set_valid_max(600.0);
+                d.set_slider_min(-10.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Spacing: gap between neighbouring cells, in pixels
+        params.add(
+            Params::Spacing,
+            tr("Spacing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Scale: frame size in its cell, 100 = fit the cell keeping the aspect ratio
+        params.add(
+            Params::Scale,
+            tr("Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(400.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_GridRepeater - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では他の時間のフレームを取得できないので現在フレームのみ
+                let frames = [ImageBuf::from_layer(&in_layer)];
+                self.do_render(in_data, &frames, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+                let count = frame_count(params)?;
+                let offset = params.get(Params::TimeOffset)?.as_float_slider()?.value();
+
+                // セル k には現在時刻から k × Time Offset フレームずらした時間を割り当てる
+                for k in 0..count {
+                    let time = in_data.current_time()
+                        + (offset * k as f64 * in_data.time_step() as f64).round() as i32;
+                    if let Ok(in_result) = extra.callbacks().checkout_layer(
+                        0,
+                        k as i32,
+                        &req,
+                        time,
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    ) {
+                        let _ = extra.union_result_rect(in_result.result_rect.into());
+                        let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                    } else {
+                        return Err(Error::InterruptCancel);
+                    }
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let count = frame_count(params)?;
+
+                let mut frames = Vec::with_capacity(count);
+                for k in 0..count {
+                    if let Some(layer) = cb.checkout_layer_pixels(k as u32)? {
+                        frames.push(ImageBuf::from_layer(&layer));
+                    }
+                }
+
+                if frames.len() == count
+                    && let Some(out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(in_data, &frames, out_layer, params)?;
+                }
+
+                for k in 0..count {
+                    cb.checkin_layer_pixels(k as u32)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn grid_size(params: &Parameters<Params>) -> Result<(usize, usize), Error> {
+    let n = |id| -> Result<usize, Error> {
+        Ok((params.get(id)?.as_slider()?.value() as usize).clamp(1, MAX_GRID))
+    };
+    Ok((n(Params::Columns)?, n(Params::Rows)?))
+}
+
+/// Frames checked out per render: one per cell, or a single one without a time offset.
+fn frame_count(params: &Parameters<Params>) -> Result<usize, Error> {
+    let offset = params.get(Params::TimeOffset)?.as_float_slider()?.value();
+    let (columns, rows) = grid_size(params)?;
+    Ok(if offset == 0.0 { 1 } else { columns * rows })
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        frames: &[ImageBuf],
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let (columns, rows) = grid_size(params)?;
+
+        let settings = GridParams {
+            columns,
+            rows,
+            order: match params.get(Params::CellOrder)?.as_popup()?.value() {
+                2 => CellOrder::ColumnMajor,
+                _ => CellOrder::RowMajor,
+            },
+            spacing: ds.length(params.get(Params::Spacing)?.as_float_slider()?.value() as f32),
+            scale: params.get(Params::Scale)?.as_float_slider()?.value() as f32 / 100.0,
+        };
+
+        compose(frames, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct GridParams {
+    pub columns: usize,
+    pub rows: usize,
+    pub order: CellOrder,
+    /// Render pixels between cells.
+    pub spacing: f32,
+    /// Frame scale relative to fitting its cell.
+    pub scale: f32,
+}
+
+/// Box-filtered copy of the premultiplied `image`, `factor` pixels per side
+/// averaged into one (partial blocks at the edges average what they cover).
+fn reduce(image: &ImageBuf, factor: usize) -> ImageBuf {
+    if factor <= 1 {
+        return image.clone();
+    }
+    let (w, h) = (image.width.div_ceil(factor), image.height.div_ceil(factor));
+    let mut out = ImageBuf::new(w, h);
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = [0.0f32; 4];
+            let mut n = 0.0;
+            for sy in y * factor..((y + 1) * factor).min(image.height) {
+                for sx in x * factor..((x + 1) * factor).min(image.width) {
+                    let px = image.data[image.index(sx, sy)];
+                    sum[0] += px.red;
+                    sum[1] += px.green;
+                    sum[2] += px.blue;
+                    sum[3] += px.alpha;
+                    n += 1.0;
+                }
+            }
+            out.set(
+                x,
+                y,
+                PixelF32 {
+                    red: sum[0] / n,
+                    green: sum[1] / n,
+                    blue: sum[2] / n,
+                    alpha: sum[3] / n,
+                },
+            );
+        }
+    }
+    out
+}
+
+/// Lays `frames` out over a grid the size of the first frame; cell `k` (in
+/// `order`) shows `frames[k]`, or the first frame when there are fewer.
+pub fn compose(frames: &[ImageBuf], p: &GridParams) -> ImageBuf {
+    let Some(first) = frames.first() else {
+        return ImageBuf::new(0, 0);
+    };
+    let (w, h) = (first.width, first.height);
+    let mut out = ImageBuf::new(w, h);
+    if first.is_empty() {
+        return out;
+    }
+    let (columns, rows) = (p.columns.clamp(1, MAX_GRID), p.rows.clamp(1, MAX_GRID));
+    let spacing = finite_or(p.spacing, 0.0).max(0.0);
+    let scale = finite_or(p.scale, 1.0).clamp(0.01, 4.0);
+
+    let cell_w = (w as f32 - spacing * (columns - 1) as f32) / columns as f32;
+    let cell_h = (h as f32 - spacing * (rows - 1) as f32) / rows as f32;
+    if cell_w <= 0.0 || cell_h <= 0.0 {
+        return out;
+    }
+    // セルに収まる倍率 × Scale で縦横比を保ったまま中央に置く
+    let fit = (cell_w / w as f32).min(cell_h / h as f32) * scale;
+    let (frame_w, frame_h) = (w as f32 * fit, h as f32 * fit);
+
+    // 縮小時は整数倍の箱フィルタで先に縮めてから双線形で読み、エイリアスを抑える
+    let factor = ((1.0 / fit).floor() as usize).clamp(1, MAX_REDUCTION);
+    let reduced: Vec<ImageBuf> = frames
+        .iter()
+        .take(columns * rows)
+        .map(|frame| reduce(&sampling::premultiply(frame), factor))
+        .collect();
+    let step = fit * factor as f32;
+
+    for y in 0..h {
+        let fy = y as f32 + 0.5;
+        let row = (fy / (cell_h + spacing)).floor() as usize;
+        let local_y = fy - row as f32 * (cell_h + spacing);
+        if row >= rows || local_y >= cell_h {
+            continue;
+        }
+        for x in 0..w {
+            let fx = x as f32 + 0.5;
+            let column = (fx / (cell_w + spacing)).floor() as usize;
+            let local_x = fx - column as f32 * (cell_w + spacing);
+            if column >= columns || local_x >= cell_w {
+                continue;
+            }
+            // セル内でのフレーム座標、はみ出した部分はセルで切る
+            let u = local_x - (cell_w - frame_w) * 0.5;
+            let v = local_y - (cell_h - frame_h) * 0.5;
+            if u < 0.0 || v < 0.0 || u >= frame_w || v >= frame_h {
+                continue;
+            }
+            let k = match p.order {
+                CellOrder::RowMajor => row * columns + column,
+                CellOrder::ColumnMajor => column * rows + row,
+            };
+            let source = reduced.get(k).unwrap_or(&reduced[0]);
+            let px = sampling::bilinear(source, u / step - 0.5, v / step - 0.5);
+            out.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no access to other frames, so time-offset frames are
+/// simulated by shifting the input one pixel to the right per cell.
+pub struct GridRepeater;
+
+impl RenderCore for GridRepeater {
+    type Params = GridParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        GridParams {
+            columns: rng.int(1, MAX_GRID as i32) as usize,
+            rows: rng.int(1, MAX_GRID as i32) as usize,
+            order: if rng.chance(0.5) {
+                CellOrder::RowMajor
+            } else {
+                CellOrder::ColumnMajor
+            },
+            spacing: rng.slider(0.0, 100.0),
+            scale: rng.slider(0.01, 4.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let count = if frame % 2 == 1 {
+            params.columns * params.rows
+        } else {
+            1
+        };
+        let frames: Vec<ImageBuf> = (0..count)
+            .map(|k| {
+                let mut shifted = ImageBuf::new(input.width, input.height);
+                for y in 0..input.height {
+                    for x in 0..input.width {
+                        let px = input.get_clamped(x as isize - k as isize, y as isize);
+                        shifted.set(x, y, px);
+                    }
+                }
+                shifted
+            })
+            .collect();
+        *output = compose(&frames, params);
+    }
+}
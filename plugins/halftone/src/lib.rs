@@ -0,0 +1,454 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::color::luminance;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    ColorMode,
+    Pattern,
+    Frequency,
+    Angle,
+    Origin,
+    Softness,
+    InkColor,
+    PaperColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Renders dot, line or cross halftone screens per luminance, RGB or CMYK channel.";
+
+/// Classic screen angle offsets (degrees) for the C/R, M/G, Y/B and K screens.
+const SCREEN_ANGLES: [f32; 4] = [15.0, 75.0, 0.0, 45.0];
+
+#[derive(Clone, Copy, Debug)]
+pub enum ColorMode {
+    Luminance,
+    Rgb,
+    Cmyk,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Pattern {
+    Dot,
+    Line,
+    Cross,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::ColorMode,
+            tr("Color Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Luminance"), tr("RGB"), tr("CMYK")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Pattern,
+            tr("Pattern"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Dot"), tr("Line"), tr("Cross")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Frequency: screen cells per 100 px at full resolution
+        params.add(
+            Params::Frequency,
+            tr("Frequency"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.5);
+                d.set_valid_max(50.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(25.0);
+                d.set_default(8.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Angle,
+            tr("Angle"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Origin,
+            tr("Origin"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        params.add(
+            Params::Softness,
+            tr("Softness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.05);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Ink / Paper Color: used by the Luminance mode
+        params.add(
+            Params::InkColor,
+            tr("Ink Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::PaperColor,
+            tr("Paper Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Halftone - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+        let ds = Downsample::from_in_data(&in_data);
+
+        let frequency = params.get(Params::Frequency)?.as_float_slider()?.value() as f32;
+        let ink = params.get(Params::InkColor)?.as_color()?.float_value()?;
+        let paper = params.get(Params::PaperColor)?.as_color()?.float_value()?;
+        let settings = HalftoneParams {
+            mode: match params.get(Params::ColorMode)?.as_popup()?.value() {
+                2 => ColorMode::Rgb,
+                3 => ColorMode::Cmyk,
+                _ => ColorMode::Luminance,
+            },
+            pattern: match params.get(Params::Pattern)?.as_popup()?.value() {
+                2 => Pattern::Line,
+                3 => Pattern::Cross,
+                _ => Pattern::Dot,
+            },
+            cell: ds.length(100.0 / frequency.max(0.01)),
+            angle: params.get(Params::Angle)?.as_angle()?.value(),
+            origin: ds.point(params.get(Params::Origin)?.as_point()?.value()),
+            softness: params.get(Params::Softness)?.as_float_slider()?.value() as f32,
+            ink: [ink.red, ink.green, ink.blue],
+            paper: [paper.red, paper.green, paper.blue],
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = halftone_pixel(&input, x as f32, y as f32, &settings);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct HalftoneParams {
+    pub mode: ColorMode,
+    pub pattern: Pattern,
+    /// Screen cell size in render pixels.
+    pub cell: f32,
+    /// Base screen angle in degrees.
+    pub angle: f32,
+    /// Screen origin in render pixels.
+    pub origin: (f32, f32),
+    /// Edge softness as a fraction of the cell.
+    pub softness: f32,
+    pub ink: [f32; 3],
+    pub paper: [f32; 3],
+}
+
+/// One rotated screen: maps image pixels to cell coordinates and back.
+struct Screen {
+    cos: f32,
+    sin: f32,
+    cell: f32,
+    origin: (f32, f32),
+}
+
+impl Screen {
+    fn new(p: &HalftoneParams, offset_deg: f32) -> Self {
+        let rad = (p.angle + offset_deg).to_radians();
+        let rad = if rad.is_finite() { rad } else { 0.0 };
+        Self {
+            cos: rad.cos(),
+            sin: rad.sin(),
+            cell: if p.cell.is_finite() {
+                p.cell.max(1.0)
+            } else {
+                1.0
+            },
+            origin: p.origin,
+        }
+    }
+
+    fn to_cell(&self, x: f32, y: f32) -> (f32, f32) {
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        (
+            (dx * self.cos + dy * self.sin) / self.cell,
+            (-dx * self.sin + dy * self.cos) / self.cell,
+        )
+    }
+
+    fn to_image(&self, u: f32, v: f32) -> (f32, f32) {
+        (
+            self.origin.0 + (u * self.cos - v * self.sin) * self.cell,
+            self.origin.1 + (u * self.sin + v * self.cos) * self.cell,
+        )
+    }
+
+    /// Ink coverage (0..1) at pixel (`x`, `y`) for the channel picked by `amount`.
+    fn coverage(
+        &self,
+        input: &ImageBuf,
+        x: f32,
+        y: f32,
+        pattern: Pattern,
+        softness: f32,
+        amount: impl Fn(PixelF32) -> f32,
+    ) -> f32 {
+        let sample = |u: f32, v: f32| {
+            let (sx, sy) = self.to_image(u, v);
+            let a = amount(sampling::bilinear(input, sx, sy));
+            if a.is_nan() { 0.0 } else { a.clamp(0.0, 1.0) }
+        };
+        // 最低でも半ピクセル幅でアンチエイリアスする
+        let aa = (softness * 0.5).max(0.5 / self.cell);
+        let edge = |half_width: f32, d: f32| ((half_width - d) / (2.0 * aa) + 0.5).clamp(0.0, 1.0);
+
+        let (u, v) = self.to_cell(x, y);
+        let (iu, iv) = (u.floor(), v.floor());
+        match pattern {
+            Pattern::Dot => {
+                // 大きなドットは隣のセルにはみ出すので 3x3 近傍を見る
+                let mut cov = 0.0f32;
+                for dv in -1..=1 {
+                    for du in -1..=1 {
+                        let (cu, cv) = (iu + du as f32 + 0.5, iv + dv as f32 + 0.5);
+                        let r = std::f32::consts::FRAC_1_SQRT_2 * sample(cu, cv).sqrt();
+                        cov = cov.max(edge(r, (u - cu).hypot(v - cv)));
+                    }
+                }
+                cov
+            }
+            Pattern::Line => {
+                let a = sample(u, iv + 0.5);
+                edge(a * 0.5, (v - iv - 0.5).abs())
+            }
+            Pattern::Cross => {
+                let a = sample(iu + 0.5, iv + 0.5);
+                let width = 1.0 - (1.0 - a).sqrt();
+                let d = (u - iu - 0.5).abs().min((v - iv - 0.5).abs());
+                edge(width * 0.5, d)
+            }
+        }
+    }
+}
+
+fn halftone_pixel(input: &ImageBuf, x: f32, y: f32, p: &HalftoneParams) -> PixelF32 {
+    let alpha = input.get(x as isize, y as isize).alpha;
+    let cov = |offset: usize, amount: &dyn Fn(PixelF32) -> f32| {
+        Screen::new(p, SCREEN_ANGLES[offset]).coverage(input, x, y, p.pattern, p.softness, amount)
+    };
+
+    let rgb = match p.mode {
+        ColorMode::Luminance => {
+            let k = Screen::new(p, 0.0)
+                .coverage(input, x, y, p.pattern, p.softness, |px| 1.0 - luminance(px));
+            std::array::from_fn(|c| p.paper[c] + (p.ink[c] - p.paper[c]) * k)
+        }
+        ColorMode::Rgb => [
+            cov(0, &|px| px.red),
+            cov(1, &|px| px.green),
+            cov(2, &|px| px.blue),
+        ],
+        ColorMode::Cmyk => {
+            let key = |px: PixelF32| 1.0 - px.red.max(px.green).max(px.blue);
+            let ink = |px: PixelF32, c: f32| {
+                let k = key(px);
+                if k >= 1.0 {
+                    0.0
+                } else {
+                    (1.0 - c - k) / (1.0 - k)
+                }
+            };
+            let c = cov(0, &|px| ink(px, px.red));
+            let m = cov(1, &|px| ink(px, px.green));
+            let y = cov(2, &|px| ink(px, px.blue));
+            let k = cov(3, &key);
+            [
+                (1.0 - c) * (1.0 - k),
+                (1.0 - m) * (1.0 - k),
+                (1.0 - y) * (1.0 - k),
+            ]
+        }
+    };
+
+    PixelF32 {
+        red: rgb[0],
+        green: rgb[1],
+        blue: rgb[2],
+        alpha,
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct Halftone;
+
+impl RenderCore for Halftone {
+    type Params = HalftoneParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        HalftoneParams {
+            mode: match rng.popup(1, 3) {
+                2 => ColorMode::Rgb,
+                3 => ColorMode::Cmyk,
+                _ => ColorMode::Luminance,
+            },
+            pattern: match rng.popup(1, 3) {
+                2 => Pattern::Line,
+                3 => Pattern::Cross,
+                _ => Pattern::Dot,
+            },
+            cell: 100.0 / rng.slider(0.5, 50.0),
+            angle: rng.slider(-360.0, 360.0),
+            origin: (rng.slider(-100.0, 200.0), rng.slider(-100.0, 200.0)),
+            softness: rng.slider(0.0, 1.0),
+            ink: [rng.unit(), rng.unit(), rng.unit()],
+            paper: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        for y in 0..input.height {
+            for x in 0..input.width {
+                output.set(x, y, halftone_pixel(input, x as f32, y as f32, params));
+            }
+        }
+    }
+}
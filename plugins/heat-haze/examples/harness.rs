@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<heat_haze::HeatHaze>();
+}
@@ -0,0 +1,525 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{Fbm, NoiseType, hash};
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Filter};
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NoiseType,
+    Size,
+    Detail,
+    Evolution,
+    EvolutionSpeed,
+    Strength,
+    VerticalBias,
+    RiseSpeed,
+    MaskLayer,
+    MaskChannel,
+    InvertMask,
+    Filter,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Shimmers the layer like rising heat haze.";
+
+/// Highest `Detail` (octaves beyond the first) offered for the displacement noise.
+const MAX_DETAIL: f32 = 8.0;
+/// Checkout id of the mask layer (the input uses 0).
+const MASK_CHECKOUT_ID: i32 = 1;
+/// Salt that decorrelates the vertical displacement noise from the horizontal one.
+const AXIS_SALT: u32 = 0x4a2e;
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::NoiseType,
+            tr("Noise Type"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Perlin"), tr("Simplex"), tr("Value")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Size: base cell size of the noise in pixels
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(4.0);
+                d.set_slider_max(500.0);
+                d.set_default(60.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Detail,
+            tr("Detail"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DETAIL);
+                d.set_slider_min(0.0);
+                d.set_slider_max(MAX_DETAIL);
+                d.set_default(2.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Evolution,
+            tr("Evolution"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100000.0);
+                d.set_valid_max(100000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Evolution Speed: evolution added per second of layer time
+        params.add(
+            Params::EvolutionSpeed,
+            tr("Evolution Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(-4.0);
+                d.set_slider_max(4.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Strength: peak displacement in pixels
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-10000.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(10.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Vertical Bias: -1 = horizontal only, 0 = both axes, 1 = vertical only
+        params.add(
+            Params::VerticalBias,
+            tr("Vertical Bias"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(-1.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Rise Speed: upward drift of the noise in pixels per second
+        params.add(
+            Params::RiseSpeed,
+            tr("Rise Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-10000.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(-500.0);
+                d.set_slider_max(500.0);
+                d.set_default(60.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(Params::MaskLayer, tr("Mask Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::MaskChannel,
+            tr("Mask Channel"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Luminance"), tr("Alpha")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::InvertMask,
+            tr("Invert Mask"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_HeatHaze - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではマスクレイヤーをパラメータから直接取得する
+                let mask = params
+                    .checkout_at(
+                        Params::MaskLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, mask.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::MaskLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        MASK_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let mask = cb
+                    .checkout_layer_pixels(MASK_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, mask.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(MASK_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mask: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let seconds = in_data.current_time() as f64 / in_data.time_scale().max(1) as f64;
+
+        let evolution = params.get(Params::Evolution)?.as_float_slider()?.value();
+        let speed = params
+            .get(Params::EvolutionSpeed)?
+            .as_float_slider()?
+            .value();
+        let rise = params.get(Params::RiseSpeed)?.as_float_slider()?.value();
+        let settings = HazeParams {
+            kind: match params.get(Params::NoiseType)?.as_popup()?.value() {
+                1 => NoiseType::Perlin,
+                3 => NoiseType::Value,
+                _ => NoiseType::Simplex,
+            },
+            size: ds.length(params.get(Params::Size)?.as_float_slider()?.value() as f32),
+            detail: params.get(Params::Detail)?.as_float_slider()?.value() as f32,
+            evolution: (evolution + speed * seconds) as f32,
+            strength: ds.length(params.get(Params::Strength)?.as_float_slider()?.value() as f32),
+            vertical_bias: params.get(Params::VerticalBias)?.as_float_slider()?.value() as f32,
+            rise: ds.length((rise * seconds) as f32),
+            mask_alpha: params.get(Params::MaskChannel)?.as_popup()?.value() == 2,
+            invert_mask: params.get(Params::InvertMask)?.as_checkbox()?.value(),
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let field = noise_field(input.width, input.height, &settings);
+        let output = haze(&input, mask, &field, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct HazeParams {
+    pub kind: NoiseType,
+    /// Base cell size in render pixels.
+    pub size: f32,
+    pub detail: f32,
+    pub evolution: f32,
+    /// Peak displacement in render pixels.
+    pub strength: f32,
+    /// -1 = horizontal only, 0 = both axes, 1 = vertical only.
+    pub vertical_bias: f32,
+    /// Upward offset of the noise domain in render pixels (rise speed × time).
+    pub rise: f32,
+    /// Weight by the mask alpha instead of its luminance.
+    pub mask_alpha: bool,
+    pub invert_mask: bool,
+    pub filter: Filter,
+    pub seed: u32,
+}
+
+/// Two decorrelated fBm samples per pixel (roughly in [-1, 1]), uses the GPU when available.
+fn noise_field(width: usize, height: usize, p: &HazeParams) -> Vec<[f32; 2]> {
+    #[cfg(feature = "gpu_wgpu")]
+    if let Some(ctx) = wgpu_context() {
+        let gpu_params = RenderParams {
+            width: width as u32,
+            height: height as u32,
+            kind: match p.kind {
+                NoiseType::Perlin => 0,
+                NoiseType::Simplex => 1,
+                NoiseType::Value => 2,
+            },
+            seed: p.seed,
+            seed_y: hash(p.seed, 1, AXIS_SALT),
+            _pad: [0; 3],
+            size: finite_or(p.size, 60.0).max(1.0),
+            detail: finite_or(p.detail, 0.0).clamp(0.0, MAX_DETAIL),
+            evolution: finite_or(p.evolution, 0.0),
+            rise: finite_or(p.rise, 0.0),
+        };
+        if let Ok(flat) = ctx.render(&gpu_params) {
+            return flat.chunks_exact(2).map(|v| [v[0], v[1]]).collect();
+        }
+        // Fall through to the CPU path when the GPU dispatch fails.
+    }
+    noise_field_cpu(width, height, p)
+}
+
+fn noise_field_cpu(width: usize, height: usize, p: &HazeParams) -> Vec<[f32; 2]> {
+    let size = finite_or(p.size, 60.0).max(1.0);
+    let fbm = |seed| Fbm {
+        kind: p.kind,
+        detail: finite_or(p.detail, 0.0).clamp(0.0, MAX_DETAIL),
+        roughness: 0.5,
+        lacunarity: 2.0,
+        seed,
+        period: [0, 0],
+    };
+    let (fx, fy) = (fbm(p.seed), fbm(hash(p.seed, 1, AXIS_SALT)));
+    let z = finite_or(p.evolution, 0.0);
+    let rise = finite_or(p.rise, 0.0);
+
+    let mut field = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            // ノイズ領域を下にずらすことで模様が上へ昇っていく
+            let q = [(x as f32 + 0.5) / size, (y as f32 + 0.5 + rise) / size, z];
+            field.push([fx.sample(q), fy.sample(q)]);
+        }
+    }
+    field
+}
+
+/// Mask weight at (`x`, `y`) of a `width` x `height` layer; 1 without a mask.
+fn mask_weight(
+    mask: Option<&ImageBuf>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    p: &HazeParams,
+) -> f32 {
+    let w = match mask {
+        // マスクレイヤーのサイズが異なる場合は引き伸ばして対応させる
+        Some(map) if !map.is_empty() => {
+            let mx = x * map.width / width.max(1);
+            let my = y * map.height / height.max(1);
+            let px = map.get_clamped(mx as isize, my as isize);
+            let v = if p.mask_alpha {
+                px.alpha
+            } else {
+                luminance(px)
+            };
+            if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) }
+        }
+        _ => return 1.0,
+    };
+    if p.invert_mask { 1.0 - w } else { w }
+}
+
+/// Displaces `input` by the noise `field`, scaled per pixel by the mask.
+fn haze(input: &ImageBuf, mask: Option<&ImageBuf>, field: &[[f32; 2]], p: &HazeParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if field.len() != width * height {
+        return output;
+    }
+    let premul = sampling::premultiply(input);
+    let strength = finite_or(p.strength, 0.0).clamp(-1e5, 1e5);
+    let bias = finite_or(p.vertical_bias, 0.0).clamp(-1.0, 1.0);
+    // 正のバイアスで横成分を、負のバイアスで縦成分を弱める
+    let (ax, ay) = (1.0 - bias.max(0.0), 1.0 + bias.min(0.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let k = strength * mask_weight(mask, x, y, width, height, p);
+            let [nx, ny] = field[y * width + x];
+            let px = if k == 0.0 {
+                input.get(x as isize, y as isize)
+            } else {
+                let sx = x as f32 + nx * ax * k;
+                let sy = y as f32 + ny * ay * k;
+                sampling::unpremultiply(sampling::sample(&premul, sx, sy, p.filter))
+            };
+            output.set(x, y, px);
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct HeatHaze;
+
+impl RenderCore for HeatHaze {
+    type Params = HazeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        HazeParams {
+            kind: match rng.popup(1, 3) {
+                1 => NoiseType::Perlin,
+                3 => NoiseType::Value,
+                _ => NoiseType::Simplex,
+            },
+            size: rng.slider(1.0, 10000.0),
+            detail: rng.slider(0.0, MAX_DETAIL),
+            evolution: rng.slider(-100000.0, 100000.0),
+            strength: rng.slider(-10000.0, 10000.0),
+            vertical_bias: rng.slider(-1.0, 1.0),
+            rise: rng.slider(-10000.0, 10000.0),
+            mask_alpha: rng.chance(0.5),
+            invert_mask: rng.chance(0.5),
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+            seed: rng.popup(0, 10000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let mask = (frame % 2 == 1).then_some(input);
+        let field = noise_field_cpu(input.width, input.height, params);
+        *output = haze(input, mask, &field, params);
+    }
+}
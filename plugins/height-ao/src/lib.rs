@@ -0,0 +1,498 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    HeightLayer,
+    Output,
+    HeightDepth,
+    Radius,
+    Directions,
+    Samples,
+    Intensity,
+    Directionality,
+    LightDirection,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Generates ambient occlusion from a height map layer.";
+
+/// Checkout id of the height map layer (the input uses 0).
+const HEIGHT_CHECKOUT_ID: i32 = 1;
+/// Upper bound of `Radius` (full-resolution pixels).
+const MAX_RADIUS: f32 = 500.0;
+const MAX_DIRECTIONS: i32 = 32;
+const MAX_SAMPLES: i32 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Grayscale occlusion, white where unoccluded.
+    Matte,
+    /// The layer multiplied by the occlusion.
+    Multiply,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Height Layer: luminance as height; without one the layer itself is used
+        params.add(Params::HeightLayer, tr("Height Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Occlusion Matte"), tr("Multiply Over Layer")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Height Depth: height of white over black, in pixels
+        params.add(
+            Params::HeightDepth,
+            tr("Height Depth"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(2000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Radius: how far each direction is searched for occluders, in pixels
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(1.0);
+                d.set_slider_max(100.0);
+                d.set_default(20.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Directions,
+            tr("Directions"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_DIRECTIONS);
+                d.set_slider_min(2);
+                d.set_slider_max(MAX_DIRECTIONS);
+                d.set_default(8);
+            }),
+        )?;
+
+        // Samples: steps along each direction
+        params.add(
+            Params::Samples,
+            tr("Samples"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_SAMPLES);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_SAMPLES);
+                d.set_default(8);
+            }),
+        )?;
+
+        params.add(
+            Params::Intensity,
+            tr("Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(400.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Directionality: weights occluders on the Light Direction side, 0 = uniform sky
+        params.add(
+            Params::Directionality,
+            tr("Directionality"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LightDirection,
+            tr("Light Direction"),
+            AngleDef::setup(|d| {
+                d.set_default(-45.0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_HeightAo - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では高さレイヤーをパラメータから直接取得する
+                let heights = params
+                    .checkout_at(
+                        Params::HeightLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, heights.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::HeightLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        HEIGHT_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let heights = cb
+                    .checkout_layer_pixels(HEIGHT_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, heights.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(HEIGHT_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        heights: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 高さと半径はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = HeightAoParams {
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::Multiply,
+                _ => Output::Matte,
+            },
+            height_depth: ds.length(slider(Params::HeightDepth)?),
+            radius: ds.length(slider(Params::Radius)?),
+            directions: params.get(Params::Directions)?.as_slider()?.value() as u32,
+            samples: params.get(Params::Samples)?.as_slider()?.value() as u32,
+            intensity: slider(Params::Intensity)? / 100.0,
+            directionality: slider(Params::Directionality)? / 100.0,
+            light_direction: params.get(Params::LightDirection)?.as_angle()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        height_ao(&input, heights, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct HeightAoParams {
+    pub output: Output,
+    /// Render pixels of height from black to white.
+    pub height_depth: f32,
+    /// Render pixels.
+    pub radius: f32,
+    pub directions: u32,
+    pub samples: u32,
+    /// 0..4.
+    pub intensity: f32,
+    /// 0..1.
+    pub directionality: f32,
+    /// Degrees, 0 = right, clockwise on screen.
+    pub light_direction: f32,
+}
+
+/// Height in render pixels of every pixel (luminance times alpha times depth).
+struct HeightField {
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+
+impl HeightField {
+    fn new(map: &ImageBuf, depth: f32) -> Self {
+        let data = map
+            .data
+            .iter()
+            .map(|&px| {
+                let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+                finite_or(luminance(px), 0.0).clamp(0.0, 1.0) * a * depth
+            })
+            .collect();
+        Self {
+            width: map.width,
+            height: map.height,
+            data,
+        }
+    }
+
+    /// Bilinear height; `x`/`y` must lie inside the field.
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x as usize, y as usize);
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let row0 = &self.data[y0 * self.width..];
+        let row1 = &self.data[y1 * self.width..];
+        let top = row0[x0] + (row0[x1] - row0[x0]) * fx;
+        let bottom = row1[x0] + (row1[x1] - row1[x0]) * fx;
+        top + (bottom - top) * fy
+    }
+}
+
+/// 4x4 ordered dither used to rotate the sampling directions per pixel.
+const BAYER4: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+/// One march step of one direction, shared by every pixel of a jitter class.
+#[derive(Clone, Copy)]
+struct Step {
+    dx: f32,
+    dy: f32,
+    dist: f32,
+    falloff: f32,
+}
+
+/// Ambient visibility 0..1 at every pixel of the height field.
+fn occlusion(field: &HeightField, p: &HeightAoParams) -> Vec<f32> {
+    let (w, h) = (field.width, field.height);
+    let radius = finite_or(p.radius, 1.0).clamp(1.0, MAX_RADIUS);
+    let directions = p.directions.clamp(2, MAX_DIRECTIONS as u32) as usize;
+    let samples = p.samples.clamp(1, MAX_SAMPLES as u32) as usize;
+    let intensity = finite_or(p.intensity, 1.0).clamp(0.0, 4.0);
+    let directionality = finite_or(p.directionality, 0.0).clamp(0.0, 1.0);
+    let light = finite_or(p.light_direction, 0.0).to_radians();
+
+    // 4x4 画素ごとに回転と歩幅をずらし、少ないサンプルの縞をノイズに変える。
+    // ずらし量は 16 通りしかないので、方向と歩幅は先に表にしておく
+    let mut weights = Vec::with_capacity(BAYER4.len() * directions);
+    let mut steps = Vec::with_capacity(BAYER4.len() * directions * samples);
+    for &rank in &BAYER4 {
+        let jitter = (rank as f32 + 0.5) / BAYER4.len() as f32;
+        for d in 0..directions {
+            let phi = (d as f32 + jitter) / directions as f32 * std::f32::consts::TAU;
+            let (sin, cos) = phi.sin_cos();
+            // 方向性: 光の来る側の遮蔽ほど重く数える (平均が 1 になるよう π 倍)
+            let facing = (phi - light).cos().max(0.0) * std::f32::consts::PI;
+            weights.push(1.0 - directionality + directionality * facing);
+            for s in 0..samples {
+                let dist = (s as f32 + 0.5 + 0.5 * jitter) / samples as f32 * radius;
+                steps.push(Step {
+                    dx: cos * dist,
+                    dy: sin * dist,
+                    dist,
+                    falloff: 1.0 - (dist / radius).powi(2),
+                });
+            }
+        }
+    }
+
+    let (max_x, max_y) = ((w - 1) as f32, (h - 1) as f32);
+    let mut out = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let h0 = field.data[y * w + x];
+            let class = (y % 4) * 4 + x % 4;
+            let class_weights = &weights[class * directions..(class + 1) * directions];
+            let class_steps =
+                &steps[class * directions * samples..(class + 1) * directions * samples];
+            let (mut total, mut weight_sum) = (0.0f32, 0.0f32);
+            for (&weight, ray) in class_weights.iter().zip(class_steps.chunks_exact(samples)) {
+                let mut horizon = 0.0f32;
+                for step in ray {
+                    let sx = x as f32 + step.dx;
+                    let sy = y as f32 + step.dy;
+                    if sx < 0.0 || sy < 0.0 || sx > max_x || sy > max_y {
+                        break;
+                    }
+                    let rise = field.sample(sx, sy) - h0;
+                    if rise <= 0.0 {
+                        continue;
+                    }
+                    // 仰角の sin に、半径で消える減衰を掛ける
+                    let elevation = rise / (rise * rise + step.dist * step.dist).sqrt();
+                    horizon = horizon.max(elevation * step.falloff);
+                }
+                total += horizon * weight;
+                weight_sum += weight;
+            }
+            let occluded = if weight_sum > 0.0 {
+                total / weight_sum
+            } else {
+                0.0
+            };
+            out.push((1.0 - occluded * intensity).clamp(0.0, 1.0));
+        }
+    }
+    out
+}
+
+pub fn height_ao(input: &ImageBuf, heights: Option<&ImageBuf>, p: &HeightAoParams) -> ImageBuf {
+    let mut out = ImageBuf::new(input.width, input.height);
+    if input.is_empty() {
+        return out;
+    }
+    let depth = finite_or(p.height_depth, 0.0).clamp(0.0, 1e4);
+    // 高さレイヤーが無いか大きさが違う場合は入力自身 / 枠外を 0 として揃える
+    let map = match heights {
+        Some(map) if map.width == input.width && map.height == input.height => map.clone(),
+        Some(map) => {
+            let mut fitted = ImageBuf::new(input.width, input.height);
+            for y in 0..input.height {
+                for x in 0..input.width {
+                    fitted.set(x, y, map.get(x as isize, y as isize));
+                }
+            }
+            fitted
+        }
+        None => input.clone(),
+    };
+    let visibility = occlusion(&HeightField::new(&map, depth), p);
+
+    for ((dst, &src), &v) in out.data.iter_mut().zip(&input.data).zip(&visibility) {
+        let alpha = finite_or(src.alpha, 0.0).clamp(0.0, 1.0);
+        *dst = match p.output {
+            Output::Matte => PixelF32 {
+                red: v,
+                green: v,
+                blue: v,
+                alpha,
+            },
+            Output::Multiply => PixelF32 {
+                red: finite_or(src.red, 0.0) * v,
+                green: finite_or(src.green, 0.0) * v,
+                blue: finite_or(src.blue, 0.0) * v,
+                alpha,
+            },
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so the input's own luminance is the
+/// height map.
+pub struct HeightAo;
+
+impl RenderCore for HeightAo {
+    type Params = HeightAoParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        HeightAoParams {
+            output: if rng.chance(0.5) {
+                Output::Matte
+            } else {
+                Output::Multiply
+            },
+            height_depth: rng.slider(0.0, 2000.0),
+            radius: rng.slider(1.0, 64.0),
+            directions: rng.int(2, MAX_DIRECTIONS) as u32,
+            samples: rng.int(1, MAX_SAMPLES) as u32,
+            intensity: rng.slider(0.0, 4.0),
+            directionality: rng.slider(0.0, 1.0),
+            light_direction: rng.slider(-360.0, 360.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = height_ao(input, None, params);
+    }
+}
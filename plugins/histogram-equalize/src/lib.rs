@@ -0,0 +1,377 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Method,
+    TileSize,
+    ClipLimit,
+    Strength,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Equalizes the layer's lightness histogram.";
+
+/// Histogram bins over OKLab lightness 0..1.
+const BINS: usize = 256;
+/// Upper bound of `Tile Size` (full-resolution pixels).
+const MAX_TILE_SIZE: f32 = 4000.0;
+const MAX_CLIP_LIMIT: f32 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Global,
+    /// Contrast-limited adaptive equalization over a grid of tiles.
+    Clahe,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add_with_flags(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Global"), tr("CLAHE")]);
+                d.set_default(2);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::TileSize,
+            tr("Tile Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(8.0);
+                d.set_valid_max(MAX_TILE_SIZE);
+                d.set_slider_min(16.0);
+                d.set_slider_max(512.0);
+                d.set_default(128.0);
+                d.set_precision(0);
+            }),
+        )?;
+
+        // Clip Limit: caps each bin at this multiple of the average bin count
+        params.add(
+            Params::ClipLimit,
+            tr("Clip Limit"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_CLIP_LIMIT);
+                d.set_slider_min(1.0);
+                d.set_slider_max(10.0);
+                d.set_default(3.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_HistogramEqualize - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Method =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let clahe = params.get(Params::Method)?.as_popup()?.value() == 2;
+    for id in [Params::TileSize, Params::ClipLimit] {
+        ui::set_param_visible(in_data, params, id, clahe)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // タイルサイズはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = EqualizeParams {
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                1 => Method::Global,
+                _ => Method::Clahe,
+            },
+            tile_size: ds.length(params.get(Params::TileSize)?.as_float_slider()?.value() as f32),
+            clip_limit: params.get(Params::ClipLimit)?.as_float_slider()?.value() as f32,
+            strength: params.get(Params::Strength)?.as_float_slider()?.value() as f32 / 100.0,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        equalize(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct EqualizeParams {
+    pub method: Method,
+    /// Render pixels.
+    pub tile_size: f32,
+    /// Multiple of the average bin count each bin is clipped to.
+    pub clip_limit: f32,
+    /// 0..1 blend towards the equalized lightness.
+    pub strength: f32,
+}
+
+/// Lightness -> equalized lightness, as the cumulative histogram sampled at
+/// the bin edges (`BINS + 1` entries from 0 to 1).
+struct Mapping(Vec<f32>);
+
+impl Mapping {
+    fn identity() -> Self {
+        Self((0..=BINS).map(|i| i as f32 / BINS as f32).collect())
+    }
+
+    /// Clips the histogram at `clip` times the average bin count (when given),
+    /// spreads the excess evenly over all bins and accumulates.
+    fn from_histogram(hist: &[f32; BINS], clip: Option<f32>) -> Self {
+        let total: f32 = hist.iter().sum();
+        if total <= 0.0 {
+            return Self::identity();
+        }
+        let mut hist = *hist;
+        if let Some(clip) = clip {
+            let limit = clip * total / BINS as f32;
+            // 上限を超えた分を全ビンに均等に配り直す (配り直しで再び超えた分は捨てずに数回繰り返す)
+            for _ in 0..4 {
+                let excess: f32 = hist.iter().map(|&c| (c - limit).max(0.0)).sum();
+                if excess <= total * 1e-6 {
+                    break;
+                }
+                let share = excess / BINS as f32;
+                for c in hist.iter_mut() {
+                    *c = c.min(limit) + share;
+                }
+            }
+        }
+        let mut cdf = Vec::with_capacity(BINS + 1);
+        let mut acc = 0.0;
+        cdf.push(0.0);
+        for &c in &hist {
+            acc += c;
+            cdf.push(acc / total);
+        }
+        Self(cdf)
+    }
+
+    fn apply(&self, l: f32) -> f32 {
+        let t = l.clamp(0.0, 1.0) * BINS as f32;
+        let i = (t as usize).min(BINS - 1);
+        let f = t - i as f32;
+        self.0[i] + (self.0[i + 1] - self.0[i]) * f
+    }
+}
+
+pub fn equalize(input: &ImageBuf, p: &EqualizeParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+
+    let labs: Vec<Oklab> = input
+        .data
+        .iter()
+        .map(|px| Oklab::from_srgb([px.red, px.green, px.blue].map(|c| finite_or(c, 0.0))))
+        .collect();
+    let weights: Vec<f32> = input
+        .data
+        .iter()
+        .map(|px| finite_or(px.alpha, 0.0).clamp(0.0, 1.0))
+        .collect();
+    let bin = |l: f32| ((finite_or(l, 0.0).clamp(0.0, 1.0) * BINS as f32) as usize).min(BINS - 1);
+
+    // グローバルは画像全体を 1 枚のタイルとして扱い、クリップしない
+    let (tile, clip) = match p.method {
+        Method::Global => (w.max(h) as f32, None),
+        Method::Clahe => (
+            finite_or(p.tile_size, 128.0).clamp(2.0, MAX_TILE_SIZE),
+            Some(finite_or(p.clip_limit, 3.0).clamp(1.0, MAX_CLIP_LIMIT)),
+        ),
+    };
+    let (tx, ty) = (
+        (w as f32 / tile).ceil().max(1.0) as usize,
+        (h as f32 / tile).ceil().max(1.0) as usize,
+    );
+    let (tw, th) = (w as f32 / tx as f32, h as f32 / ty as f32);
+
+    let mut hists = vec![[0.0f32; BINS]; tx * ty];
+    for (i, (lab, &weight)) in labs.iter().zip(&weights).enumerate() {
+        let (x, y) = (i % w, i / w);
+        let t =
+            ((y as f32 / th) as usize).min(ty - 1) * tx + ((x as f32 / tw) as usize).min(tx - 1);
+        hists[t][bin(lab.l)] += weight;
+    }
+    let mappings: Vec<Mapping> = hists
+        .iter()
+        .map(|hist| Mapping::from_histogram(hist, clip))
+        .collect();
+
+    let strength = finite_or(p.strength, 1.0).clamp(0.0, 1.0);
+    // タイル中心の間で写像を双線形補間してタイル境界の段差を消す
+    let cell = |v: f32, size: f32, n: usize| {
+        let f = (v / size - 0.5).clamp(0.0, (n - 1) as f32);
+        let i = (f as usize).min(n.saturating_sub(2));
+        (i, (i + 1).min(n - 1), f - i as f32)
+    };
+    for (i, lab) in labs.iter().enumerate() {
+        let (x, y) = (i % w, i / w);
+        let (x0, x1, fx) = cell(x as f32 + 0.5, tw, tx);
+        let (y0, y1, fy) = cell(y as f32 + 0.5, th, ty);
+        let l = finite_or(lab.l, 0.0);
+        let at = |cx: usize, cy: usize| mappings[cy * tx + cx].apply(l);
+        let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * fx;
+        let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * fx;
+        let mapped = top + (bottom - top) * fy;
+        // 0..1 の外 (HDR) はヒストグラム外の差分をそのまま残す
+        let equalized = mapped + (l - l.clamp(0.0, 1.0));
+
+        let rgb = Oklab {
+            l: l + (equalized - l) * strength,
+            ..*lab
+        }
+        .to_srgb();
+        let src = input.data[i];
+        out.data[i] = PixelF32 {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            alpha: src.alpha,
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct HistogramEqualize;
+
+impl RenderCore for HistogramEqualize {
+    type Params = EqualizeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        EqualizeParams {
+            method: if rng.chance(0.5) {
+                Method::Global
+            } else {
+                Method::Clahe
+            },
+            tile_size: rng.slider(8.0, MAX_TILE_SIZE),
+            clip_limit: rng.slider(1.0, MAX_CLIP_LIMIT),
+            strength: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = equalize(input, params);
+    }
+}
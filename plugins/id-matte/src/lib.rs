@@ -0,0 +1,593 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    IdLayer,
+    Match,
+    QuantizeLevels,
+    IdCount,
+    Id1Color,
+    Id2Color,
+    Id3Color,
+    Id4Color,
+    Id5Color,
+    Id6Color,
+    Id7Color,
+    Id8Color,
+    EdgeReconstruction,
+    Invert,
+    Output,
+}
+
+/// Color parameter ids of the ID slots, in UI order.
+const SLOTS: [Params; 8] = [
+    Params::Id1Color,
+    Params::Id2Color,
+    Params::Id3Color,
+    Params::Id4Color,
+    Params::Id5Color,
+    Params::Id6Color,
+    Params::Id7Color,
+    Params::Id8Color,
+];
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Extracts a matte from ID pass colors.";
+
+/// Checkout id of the ID layer (the input uses 0).
+const ID_CHECKOUT_ID: i32 = 1;
+const MAX_LEVELS: i32 = 256;
+/// Per-channel tolerance of an exact match: half an 8-bit step.
+const EXACT_TOLERANCE: f32 = 0.5 / 255.0;
+/// Squared RGB distance within which a pixel counts as a blend of two colors.
+const BLEND_TOLERANCE: f32 = 2e-3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Match {
+    /// Same 8-bit color.
+    Exact,
+    /// Same cell after quantizing every channel to `Quantize Levels` steps.
+    Quantized,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// White matte on black.
+    Matte,
+    /// The layer with the matte multiplied into its alpha.
+    CutOut,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // ID Layer: the ID pass; without one the layer itself is used
+        params.add(Params::IdLayer, tr("ID Layer"), LayerDef::new())?;
+
+        // Match: switches Quantize Levels on and off (see update_param_visibility)
+        params.add_with_flags(
+            Params::Match,
+            tr("Match"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Exact"), tr("Quantized")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Quantize Levels: steps per channel; colors in the same cell match
+        params.add(
+            Params::QuantizeLevels,
+            tr("Quantize Levels"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_LEVELS);
+                d.set_slider_min(2);
+                d.set_slider_max(64);
+                d.set_default(16);
+            }),
+        )?;
+
+        // ID Count: slots above the count are hidden (see update_param_visibility)
+        params.add_with_flags(
+            Params::IdCount,
+            tr("ID Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(SLOTS.len() as i32);
+                d.set_slider_min(1);
+                d.set_slider_max(SLOTS.len() as i32);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Id1Color,
+            tr("ID 1 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id2Color,
+            tr("ID 2 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 255,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id3Color,
+            tr("ID 3 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id4Color,
+            tr("ID 4 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id5Color,
+            tr("ID 5 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id6Color,
+            tr("ID 6 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 0,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id7Color,
+            tr("ID 7 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 128,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+        params.add(
+            Params::Id8Color,
+            tr("ID 8 Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 128,
+                    green: 0,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Edge Reconstruction: recovers partial coverage of anti-aliased ID edges
+        params.add(
+            Params::EdgeReconstruction,
+            tr("Edge Reconstruction"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        params.add(
+            Params::Invert,
+            tr("Invert"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Matte"), tr("Cut Out")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_IdMatte - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(params.type_at(param_index), Params::Match | Params::IdCount) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では ID レイヤーをパラメータから直接取得する
+                let ids = params
+                    .checkout_at(
+                        Params::IdLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_layer, ids.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::IdLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        ID_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let ids = cb
+                    .checkout_layer_pixels(ID_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, ids.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(ID_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let quantized = params.get(Params::Match)?.as_popup()?.value() == 2;
+    let count = params.get(Params::IdCount)?.as_slider()?.value() as usize;
+    let slots = SLOTS.iter().enumerate().map(|(i, &id)| (id, i < count));
+    for (id, visible) in [(Params::QuantizeLevels, quantized)]
+        .into_iter()
+        .chain(slots)
+    {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        id_layer: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let count = params.get(Params::IdCount)?.as_slider()?.value() as usize;
+        let mut ids = Vec::with_capacity(count);
+        for id in SLOTS.iter().take(count) {
+            let c = params.get(*id)?.as_color()?.float_value()?;
+            ids.push([c.red, c.green, c.blue]);
+        }
+
+        let settings = IdMatteParams {
+            matching: match params.get(Params::Match)?.as_popup()?.value() {
+                2 => Match::Quantized,
+                _ => Match::Exact,
+            },
+            levels: params.get(Params::QuantizeLevels)?.as_slider()?.value() as u32,
+            ids,
+            edge_reconstruction: params
+                .get(Params::EdgeReconstruction)?
+                .as_checkbox()?
+                .value(),
+            invert: params.get(Params::Invert)?.as_checkbox()?.value(),
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::CutOut,
+                _ => Output::Matte,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        id_matte(&input, id_layer, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct IdMatteParams {
+    pub matching: Match,
+    /// Steps per channel of the quantized match.
+    pub levels: u32,
+    /// Encoded RGB of the picked IDs.
+    pub ids: Vec<[f32; 3]>,
+    pub edge_reconstruction: bool,
+    pub invert: bool,
+    pub output: Output,
+}
+
+fn rgb(px: PixelF32) -> [f32; 3] {
+    [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0))
+}
+
+/// Whether `color` matches the ID `id`.
+fn matches(color: [f32; 3], id: [f32; 3], matching: Match, levels: u32) -> bool {
+    match matching {
+        Match::Exact => (0..3).all(|c| (color[c] - id[c]).abs() <= EXACT_TOLERANCE),
+        Match::Quantized => {
+            let steps = (levels.clamp(2, MAX_LEVELS as u32) - 1) as f32;
+            let cell = |v: f32| (v.clamp(0.0, 1.0) * steps).round();
+            (0..3).all(|c| cell(color[c]) == cell(id[c]))
+        }
+    }
+}
+
+/// Coverage of the ID color `inside` in `color`, read as a blend of `inside`
+/// and `outside`, along with the squared distance of `color` from that blend.
+fn blend_coverage(color: [f32; 3], inside: [f32; 3], outside: [f32; 3]) -> (f32, f32) {
+    let axis: [f32; 3] = std::array::from_fn(|c| inside[c] - outside[c]);
+    let len2: f32 = axis.iter().map(|a| a * a).sum();
+    if len2 <= f32::EPSILON {
+        return (0.0, f32::INFINITY);
+    }
+    let t = ((0..3)
+        .map(|c| (color[c] - outside[c]) * axis[c])
+        .sum::<f32>()
+        / len2)
+        .clamp(0.0, 1.0);
+    let residual = (0..3)
+        .map(|c| (color[c] - (outside[c] + axis[c] * t)).powi(2))
+        .sum();
+    (t, residual)
+}
+
+/// Matte of the ID layer: 1 on matching pixels, 0 elsewhere. With edge
+/// reconstruction, pixels on the border of a matched region that match no ID
+/// themselves are explained as a blend of the ID of a matching neighbour and
+/// the color of another neighbour, and get the largest ID share among the
+/// blends that fit.
+fn matte(ids: &ImageBuf, p: &IdMatteParams) -> Vec<f32> {
+    let (width, height) = (ids.width, ids.height);
+    let colors: Vec<[f32; 3]> = ids.data.iter().map(|&px| rgb(px)).collect();
+    // 透明な画素はどの ID にも一致しない
+    let matched: Vec<Option<[f32; 3]>> = ids
+        .data
+        .iter()
+        .zip(&colors)
+        .map(|(px, &color)| {
+            if finite_or(px.alpha, 0.0) <= 0.0 {
+                return None;
+            }
+            p.ids
+                .iter()
+                .copied()
+                .find(|&id| matches(color, id, p.matching, p.levels))
+        })
+        .collect();
+
+    let mut matte: Vec<f32> = matched
+        .iter()
+        .map(|m| if m.is_some() { 1.0 } else { 0.0 })
+        .collect();
+    if !p.edge_reconstruction {
+        return matte;
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            if matched[i].is_some() || finite_or(ids.data[i].alpha, 0.0) <= 0.0 {
+                continue;
+            }
+            let mut neighbours = [0usize; 8];
+            let mut n = 0;
+            for (dx, dy) in [
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ] {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height {
+                    neighbours[n] = ny as usize * width + nx as usize;
+                    n += 1;
+                }
+            }
+            let neighbours = &neighbours[..n];
+            // 混色の隣接画素も説明に使えてしまうので、許容誤差内で最大の被覆率を採る
+            let mut coverage = 0.0f32;
+            for &a in neighbours {
+                let Some(inside) = matched[a] else { continue };
+                for &b in neighbours {
+                    if matched[b].is_some() {
+                        continue;
+                    }
+                    let (t, residual) = blend_coverage(colors[i], inside, colors[b]);
+                    if residual <= BLEND_TOLERANCE {
+                        coverage = coverage.max(t);
+                    }
+                }
+            }
+            matte[i] = coverage;
+        }
+    }
+    matte
+}
+
+fn id_matte(input: &ImageBuf, id_layer: Option<&ImageBuf>, p: &IdMatteParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+    // サイズの異なる ID レイヤーは無視して入力を使う
+    let ids = match id_layer {
+        Some(ids) if ids.width == width && ids.height == height => ids,
+        _ => input,
+    };
+    let matte = matte(ids, p);
+    for ((o, &px), &m) in out.data.iter_mut().zip(&input.data).zip(&matte) {
+        let m = if p.invert { 1.0 - m } else { m };
+        *o = match p.output {
+            Output::Matte => PixelF32 {
+                red: m,
+                green: m,
+                blue: m,
+                alpha: 1.0,
+            },
+            Output::CutOut => PixelF32 {
+                alpha: finite_or(px.alpha, 0.0) * m,
+                ..px
+            },
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use a copy of the input as
+/// the ID layer.
+pub struct IdMatte;
+
+impl RenderCore for IdMatte {
+    type Params = IdMatteParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let count = rng.popup(1, SLOTS.len() as i32) as usize;
+        IdMatteParams {
+            matching: if rng.chance(0.5) {
+                Match::Exact
+            } else {
+                Match::Quantized
+            },
+            levels: rng.popup(2, MAX_LEVELS) as u32,
+            ids: (0..count)
+                .map(|_| [rng.unit(), rng.unit(), rng.unit()])
+                .collect(),
+            edge_reconstruction: rng.chance(0.5),
+            invert: rng.chance(0.5),
+            output: if rng.chance(0.5) {
+                Output::Matte
+            } else {
+                Output::CutOut
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let ids = (frame % 2 == 1).then(|| input.clone());
+        *output = id_matte(input, ids.as_ref(), params);
+    }
+}
@@ -0,0 +1,469 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::mask::distance_field;
+use utils::normal::{self, NormalFormat};
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NormalLayer,
+    CreaseAngle,
+    DepthLayer,
+    DepthThreshold,
+    Silhouette,
+    LineWidth,
+    LineColor,
+    Output,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Draws ink lines from normal and depth maps.";
+
+/// Checkout ids of the normal and depth layers (the input uses 0).
+const NORMAL_CHECKOUT_ID: i32 = 1;
+const DEPTH_CHECKOUT_ID: i32 = 2;
+const MAX_LINE_WIDTH: f32 = 100.0;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(Params::NormalLayer, tr("Normal Layer"), LayerDef::new())?;
+
+        // Crease Angle: neighbouring normals further apart than this get a line
+        params.add(
+            Params::CreaseAngle,
+            tr("Crease Angle"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(180.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(180.0);
+                d.set_default(40.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(Params::DepthLayer, tr("Depth Layer"), LayerDef::new())?;
+
+        // Depth Threshold: luminance step between neighbours that gets a line
+        params.add(
+            Params::DepthThreshold,
+            tr("Depth Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.05);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Silhouette: also outline where the normal/depth layers become transparent
+        params.add(
+            Params::Silhouette,
+            tr("Silhouette"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        params.add(
+            Params::LineWidth,
+            tr("Line Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_LINE_WIDTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LineColor,
+            tr("Line Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Lines Over Input"), tr("Lines Only")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_InkOutline - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では法線・深度レイヤーをパラメータから直接取得する
+                let mut layer = |id| -> Result<Option<ImageBuf>, Error> {
+                    Ok(params
+                        .checkout_at(
+                            id,
+                            Some(in_data.current_time()),
+                            Some(in_data.time_step()),
+                            Some(in_data.time_scale()),
+                        )?
+                        .as_layer()?
+                        .value()
+                        .map(|layer| ImageBuf::from_layer(&layer)))
+                };
+                let normals = layer(Params::NormalLayer)?;
+                let depth = layer(Params::DepthLayer)?;
+                let maps = Maps {
+                    normals: normals.as_ref(),
+                    depth: depth.as_ref(),
+                };
+                self.do_render(in_data, in_layer, maps, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                for (id, checkout_id) in [
+                    (Params::NormalLayer, NORMAL_CHECKOUT_ID),
+                    (Params::DepthLayer, DEPTH_CHECKOUT_ID),
+                ] {
+                    if let Some(index) = params.index(id) {
+                        let _ = extra.callbacks().checkout_layer(
+                            index as i32,
+                            checkout_id,
+                            &req,
+                            in_data.current_time(),
+                            in_data.time_step(),
+                            in_data.time_scale(),
+                        );
+                    }
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let normals = cb
+                    .checkout_layer_pixels(NORMAL_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let depth = cb
+                    .checkout_layer_pixels(DEPTH_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    let maps = Maps {
+                        normals: normals.as_ref(),
+                        depth: depth.as_ref(),
+                    };
+                    self.do_render(in_data, in_layer, maps, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(NORMAL_CHECKOUT_ID as u32)?;
+                cb.checkin_layer_pixels(DEPTH_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        maps: Maps,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 線幅はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let color = params.get(Params::LineColor)?.as_color()?.float_value()?;
+        let settings = OutlineParams {
+            crease_angle: (params.get(Params::CreaseAngle)?.as_float_slider()?.value() as f32)
+                .to_radians(),
+            depth_threshold: params
+                .get(Params::DepthThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            silhouette: params.get(Params::Silhouette)?.as_checkbox()?.value(),
+            line_width: ds.length(params.get(Params::LineWidth)?.as_float_slider()?.value() as f32),
+            line_color: [color.red, color.green, color.blue],
+            lines_only: params.get(Params::Output)?.as_popup()?.value() == 2,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        outline(&input, maps, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Optional normal and depth map layers.
+#[derive(Clone, Copy, Default)]
+pub struct Maps<'a> {
+    pub normals: Option<&'a ImageBuf>,
+    pub depth: Option<&'a ImageBuf>,
+}
+
+#[derive(Debug)]
+pub struct OutlineParams {
+    /// Angle between neighbouring normals that makes a crease, radians.
+    pub crease_angle: f32,
+    /// Depth step between neighbouring pixels that makes an edge.
+    pub depth_threshold: f32,
+    pub silhouette: bool,
+    /// Line width in render pixels.
+    pub line_width: f32,
+    pub line_color: [f32; 3],
+    /// Transparent background instead of the input.
+    pub lines_only: bool,
+}
+
+/// Map pixel at input pixel (`x`, `y`), stretching maps of another size.
+fn map_pixel(map: &ImageBuf, x: usize, y: usize, width: usize, height: usize) -> PixelF32 {
+    let mx = x * map.width / width.max(1);
+    let my = y * map.height / height.max(1);
+    map.get_clamped(mx as isize, my as isize)
+}
+
+/// Per-pixel surface samples used for edge detection.
+#[derive(Clone, Copy)]
+struct Surface {
+    normal: Option<[f32; 3]>,
+    depth: Option<f32>,
+    covered: bool,
+}
+
+fn surfaces(maps: Maps, width: usize, height: usize) -> Vec<Surface> {
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let n = maps
+                .normals
+                .filter(|m| !m.is_empty())
+                .map(|m| map_pixel(m, x, y, width, height));
+            let d = maps
+                .depth
+                .filter(|m| !m.is_empty())
+                .map(|m| map_pixel(m, x, y, width, height));
+            // 透明な画素は深度・法線なしとして扱い、シルエット判定に回す
+            let depth = d
+                .filter(|px| px.alpha >= 0.5)
+                .map(|px| finite_or(luminance(px), 0.0).clamp(0.0, 1.0));
+            out.push(Surface {
+                normal: n
+                    .filter(|px| px.alpha >= 0.5)
+                    .and_then(|px| normal::decode(px, NormalFormat::OpenGl)),
+                depth,
+                covered: n.is_some_and(|px| px.alpha >= 0.5) || d.is_some_and(|px| px.alpha >= 0.5),
+            });
+        }
+    }
+    out
+}
+
+/// Whether the boundary between two neighbouring surfaces gets a line.
+fn is_edge(
+    a: Surface,
+    b: Surface,
+    cos_crease: f32,
+    depth_threshold: f32,
+    silhouette: bool,
+) -> bool {
+    if silhouette && a.covered != b.covered {
+        return true;
+    }
+    if let (Some(na), Some(nb)) = (a.normal, b.normal) {
+        let dot = na[0] * nb[0] + na[1] * nb[1] + na[2] * nb[2];
+        if dot < cos_crease {
+            return true;
+        }
+    }
+    match (a.depth, b.depth) {
+        (Some(da), Some(db)) => (da - db).abs() > depth_threshold,
+        _ => false,
+    }
+}
+
+fn outline(input: &ImageBuf, maps: Maps, p: &OutlineParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+
+    let cos_crease = finite_or(p.crease_angle, 0.0)
+        .clamp(0.0, std::f32::consts::PI)
+        .cos();
+    let depth_threshold = finite_or(p.depth_threshold, 0.0).clamp(0.0, 1.0);
+    let surfaces = surfaces(maps, width, height);
+
+    // 境界をまたぐ両側の画素に印を付け、線が境界の中央に来るようにする
+    let mut marks = vec![false; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let neighbours = [
+                (x + 1 < width).then_some(i + 1),
+                (y + 1 < height).then_some(i + width),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if is_edge(
+                    surfaces[i],
+                    surfaces[j],
+                    cos_crease,
+                    depth_threshold,
+                    p.silhouette,
+                ) {
+                    marks[i] = true;
+                    marks[j] = true;
+                }
+            }
+        }
+    }
+    let dist = distance_field(&marks, width, height);
+
+    let half_width = finite_or(p.line_width, 0.0).clamp(0.0, MAX_LINE_WIDTH) * 0.5;
+    let color = p.line_color.map(|c| finite_or(c, 0.0));
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            // 印の付いた画素の中心は境界から 0.5px 離れている
+            let coverage = (half_width - dist[i].sqrt() as f32).clamp(0.0, 1.0);
+            let base = if p.lines_only {
+                PixelF32 {
+                    red: color[0],
+                    green: color[1],
+                    blue: color[2],
+                    alpha: 0.0,
+                }
+            } else {
+                input.data[i]
+            };
+            // ストレートアルファ同士の over 合成
+            let alpha = coverage + base.alpha * (1.0 - coverage);
+            let mix = |line: f32, under: f32| {
+                if alpha > 0.0 {
+                    (line * coverage + under * base.alpha * (1.0 - coverage)) / alpha
+                } else {
+                    under
+                }
+            };
+            output.set(
+                x,
+                y,
+                PixelF32 {
+                    red: mix(color[0], base.red),
+                    green: mix(color[1], base.green),
+                    blue: mix(color[2], base.blue),
+                    alpha,
+                },
+            );
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no extra layers, so odd frames use the input itself as
+/// both the normal and the depth map.
+pub struct InkOutline;
+
+impl RenderCore for InkOutline {
+    type Params = OutlineParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        OutlineParams {
+            crease_angle: rng.slider(0.0, 180.0).to_radians(),
+            depth_threshold: rng.slider(0.0, 1.0),
+            silhouette: rng.chance(0.5),
+            line_width: rng.slider(0.0, MAX_LINE_WIDTH),
+            line_color: [rng.unit(), rng.unit(), rng.unit()],
+            lines_only: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let maps = if frame % 2 == 1 {
+            Maps {
+                normals: Some(input),
+                depth: Some(input),
+            }
+        } else {
+            Maps::default()
+        };
+        *output = outline(input, maps, params);
+    }
+}
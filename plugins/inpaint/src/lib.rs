@@ -0,0 +1,633 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::VecDeque;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::color::luminance;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    HoleSource,
+    MaskLayer,
+    Threshold,
+    Method,
+    Iterations,
+    Feather,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Fills transparent holes or a masked region from their surroundings.";
+
+/// Checkout id of the mask layer (the input uses 0).
+const MASK_CHECKOUT_ID: i32 = 1;
+const MAX_ITERATIONS: i32 = 500;
+/// Upper bound of `Feather` (full-resolution pixels).
+const MAX_FEATHER: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HoleSource {
+    /// Transparent regions that do not touch the layer edge.
+    Transparent,
+    /// Where the mask layer's luminance x alpha exceeds the threshold.
+    MaskLayer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    PushPull,
+    /// Push-pull followed by Laplace relaxation (smoother, slower).
+    Diffusion,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Hole Source: Mask Layer shows the layer picker (see update_param_visibility)
+        params.add_with_flags(
+            Params::HoleSource,
+            tr("Hole Source"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Transparent Holes"), tr("Mask Layer")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(Params::MaskLayer, tr("Mask Layer"), LayerDef::new())?;
+
+        // Threshold: alpha below (Transparent Holes) or mask above (Mask Layer) is a hole
+        params.add(
+            Params::Threshold,
+            tr("Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Method: Diffusion shows Iterations
+        params.add_with_flags(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Push-Pull"), tr("Diffusion")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS);
+                d.set_slider_min(1);
+                d.set_slider_max(200);
+                d.set_default(50);
+            }),
+        )?;
+
+        // Feather: width of the blend between the original and the fill around holes
+        params.add(
+            Params::Feather,
+            tr("Feather"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_FEATHER);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(1.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Inpaint - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::HoleSource | Params::Method
+                ) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではマスクレイヤーをパラメータから直接取得する
+                let mask = if hole_source(params)? == HoleSource::MaskLayer {
+                    params
+                        .checkout_at(
+                            Params::MaskLayer,
+                            Some(in_data.current_time()),
+                            Some(in_data.time_step()),
+                            Some(in_data.time_scale()),
+                        )?
+                        .as_layer()?
+                        .value()
+                        .map(|layer| ImageBuf::from_layer(&layer))
+                } else {
+                    None
+                };
+                self.do_render(in_data, in_layer, mask.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if hole_source(params)? == HoleSource::MaskLayer
+                    && let Some(index) = params.index(Params::MaskLayer)
+                {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        MASK_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let masked = hole_source(params)? == HoleSource::MaskLayer;
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let mask = if masked {
+                    cb.checkout_layer_pixels(MASK_CHECKOUT_ID as u32)?
+                        .map(|layer| ImageBuf::from_layer(&layer))
+                } else {
+                    None
+                };
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, mask.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                if masked {
+                    cb.checkin_layer_pixels(MASK_CHECKOUT_ID as u32)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn hole_source(params: &Parameters<Params>) -> Result<HoleSource, Error> {
+    Ok(match params.get(Params::HoleSource)?.as_popup()?.value() {
+        2 => HoleSource::MaskLayer,
+        _ => HoleSource::Transparent,
+    })
+}
+
+fn method(params: &Parameters<Params>) -> Result<Method, Error> {
+    Ok(match params.get(Params::Method)?.as_popup()?.value() {
+        2 => Method::Diffusion,
+        _ => Method::PushPull,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let masked = hole_source(params)? == HoleSource::MaskLayer;
+    let diffusion = method(params)? == Method::Diffusion;
+    for (id, visible) in [(Params::MaskLayer, masked), (Params::Iterations, diffusion)] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mask: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // ぼかし幅はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = InpaintParams {
+            source: hole_source(params)?,
+            threshold: params.get(Params::Threshold)?.as_float_slider()?.value() as f32,
+            method: method(params)?,
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as usize,
+            feather: ds.length(params.get(Params::Feather)?.as_float_slider()?.value() as f32),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = inpaint(&input, mask, &settings);
+
+        let progress_final = out_layer.height() as i32;
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let (x, y) = (x as usize, y as usize);
+            let px = if x < output.width && y < output.height {
+                output.data[output.index(x, y)]
+            } else {
+                utils::image::TRANSPARENT
+            };
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct InpaintParams {
+    pub source: HoleSource,
+    pub threshold: f32,
+    pub method: Method,
+    pub iterations: usize,
+    /// Seam blend width in pixels (already scaled for downsampling).
+    pub feather: f32,
+}
+
+/// Straight RGBA with non-finite channels as 0.
+fn sanitize(px: PixelF32) -> [f32; 4] {
+    let f = |v: f32| if v.is_finite() { v } else { 0.0 };
+    [
+        f(px.red),
+        f(px.green),
+        f(px.blue),
+        f(px.alpha).clamp(0.0, 1.0),
+    ]
+}
+
+/// Hole mask (`true` = fill) of a `width` x `height` frame.
+fn hole_mask(input: &ImageBuf, mask: Option<&ImageBuf>, p: &InpaintParams) -> Vec<bool> {
+    let (w, h) = (input.width, input.height);
+    let threshold = if p.threshold.is_nan() {
+        0.5
+    } else {
+        p.threshold.clamp(0.0, 1.0)
+    };
+
+    match p.source {
+        HoleSource::MaskLayer => {
+            let Some(mask) = mask.filter(|m| !m.is_empty()) else {
+                return vec![false; w * h];
+            };
+            let mut holes = Vec::with_capacity(w * h);
+            for y in 0..h {
+                for x in 0..w {
+                    // マスクレイヤーのサイズが異なる場合は引き伸ばして対応させる
+                    let mx = x * mask.width / w.max(1);
+                    let my = y * mask.height / h.max(1);
+                    let px = mask.get_clamped(mx as isize, my as isize);
+                    let v = luminance(px) * px.alpha;
+                    holes.push(v > threshold);
+                }
+            }
+            holes
+        }
+        HoleSource::Transparent => {
+            let transparent: Vec<bool> = input
+                .data
+                .iter()
+                .map(|&px| sanitize(px)[3] < threshold)
+                .collect();
+            // 端につながる透明領域は背景なので穴として扱わない
+            let mut outside = vec![false; w * h];
+            let mut queue = VecDeque::new();
+            for y in 0..h {
+                for x in 0..w {
+                    let i = y * w + x;
+                    if (x == 0 || y == 0 || x + 1 == w || y + 1 == h) && transparent[i] {
+                        outside[i] = true;
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+            while let Some((x, y)) = queue.pop_front() {
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx >= w || ny >= h {
+                        continue;
+                    }
+                    let j = ny * w + nx;
+                    if transparent[j] && !outside[j] {
+                        outside[j] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            transparent
+                .iter()
+                .zip(&outside)
+                .map(|(&t, &o)| t && !o)
+                .collect()
+        }
+    }
+}
+
+/// Push-pull fill: known pixels are averaged down a 2x pyramid until every
+/// level is covered, then the coarse colors are pushed back up into the gaps.
+fn push_pull(colors: &[[f32; 4]], known: &[bool], width: usize, height: usize) -> Vec<[f32; 4]> {
+    let mut levels = vec![(
+        width,
+        height,
+        colors.to_vec(),
+        known
+            .iter()
+            .map(|&k| if k { 1.0 } else { 0.0 })
+            .collect::<Vec<f32>>(),
+    )];
+
+    // pull: 重み付き平均で縮小
+    while let Some((w, h, c, wt)) = levels.last()
+        && (*w > 1 || *h > 1)
+    {
+        let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+        let mut cc = vec![[0.0f32; 4]; cw * ch];
+        let mut cwt = vec![0.0f32; cw * ch];
+        for y in 0..ch {
+            for x in 0..cw {
+                let mut acc = [0.0f32; 4];
+                let mut total = 0.0f32;
+                for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let (fx, fy) = (2 * x + sx, 2 * y + sy);
+                    if fx >= *w || fy >= *h {
+                        continue;
+                    }
+                    let i = fy * w + fx;
+                    for k in 0..4 {
+                        acc[k] += c[i][k] * wt[i];
+                    }
+                    total += wt[i];
+                }
+                if total > 0.0 {
+                    cc[y * cw + x] = acc.map(|v| v / total);
+                }
+                cwt[y * cw + x] = total.min(1.0);
+            }
+        }
+        levels.push((cw, ch, cc, cwt));
+    }
+
+    // push: 粗いレベルの双線形補間で未知部分を埋める
+    for l in (0..levels.len() - 1).rev() {
+        let (finer, coarser) = levels.split_at_mut(l + 1);
+        let (w, h, fine, wt) = &mut finer[l];
+        let (cw, ch, coarse, _) = &coarser[0];
+        let (cw, ch) = (*cw, *ch);
+        for y in 0..*h {
+            for x in 0..*w {
+                let i = y * *w + x;
+                let a = wt[i];
+                if a >= 1.0 {
+                    continue;
+                }
+                let gx = ((x as f32 + 0.5) * 0.5 - 0.5).clamp(0.0, (cw - 1) as f32);
+                let gy = ((y as f32 + 0.5) * 0.5 - 0.5).clamp(0.0, (ch - 1) as f32);
+                let (x0, y0) = (gx.floor() as usize, gy.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(cw - 1), (y0 + 1).min(ch - 1));
+                let (tx, ty) = (gx - x0 as f32, gy - y0 as f32);
+                let (c00, c10) = (coarse[y0 * cw + x0], coarse[y0 * cw + x1]);
+                let (c01, c11) = (coarse[y1 * cw + x0], coarse[y1 * cw + x1]);
+                fine[i] = std::array::from_fn(|k| {
+                    let top = c00[k] + (c10[k] - c00[k]) * tx;
+                    let bottom = c01[k] + (c11[k] - c01[k]) * tx;
+                    let up = top + (bottom - top) * ty;
+                    fine[i][k] * a + up * (1.0 - a)
+                });
+            }
+        }
+    }
+    levels.swap_remove(0).2
+}
+
+/// Jacobi relaxation of the Laplace equation inside the holes.
+fn diffuse(
+    colors: &mut Vec<[f32; 4]>,
+    holes: &[bool],
+    width: usize,
+    height: usize,
+    iterations: usize,
+) {
+    let mut next = colors.clone();
+    for _ in 0..iterations {
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if !holes[i] {
+                    continue;
+                }
+                let l = colors[y * width + x.saturating_sub(1)];
+                let r = colors[y * width + (x + 1).min(width - 1)];
+                let u = colors[y.saturating_sub(1) * width + x];
+                let d = colors[(y + 1).min(height - 1) * width + x];
+                next[i] = std::array::from_fn(|k| (l[k] + r[k] + u[k] + d[k]) * 0.25);
+            }
+        }
+        std::mem::swap(colors, &mut next);
+    }
+}
+
+/// Separable box blur of a 0..1 mask, applied twice (tent profile).
+fn feather_mask(mask: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    let mut v = mask.to_vec();
+    for _ in 0..2 {
+        for horizontal in [true, false] {
+            let mut out = vec![0.0; v.len()];
+            let (n, m) = if horizontal {
+                (width, height)
+            } else {
+                (height, width)
+            };
+            for j in 0..m {
+                let idx = |i: usize| {
+                    if horizontal {
+                        j * width + i
+                    } else {
+                        i * width + j
+                    }
+                };
+                // 累積和で半径に依存しない計算量にする
+                let mut prefix = vec![0.0f32; n + 1];
+                for i in 0..n {
+                    prefix[i + 1] = prefix[i] + v[idx(i)];
+                }
+                for i in 0..n {
+                    let lo = i.saturating_sub(radius);
+                    let hi = (i + radius + 1).min(n);
+                    out[idx(i)] = (prefix[hi] - prefix[lo]) / (hi - lo) as f32;
+                }
+            }
+            v = out;
+        }
+    }
+    v
+}
+
+fn inpaint(input: &ImageBuf, mask: Option<&ImageBuf>, p: &InpaintParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+
+    let holes = hole_mask(input, mask, p);
+    let colors: Vec<[f32; 4]> = input.data.iter().map(|&px| sanitize(px)).collect();
+    let known: Vec<bool> = holes.iter().map(|&hole| !hole).collect();
+
+    let mut fill = if known.iter().any(|&k| k) {
+        push_pull(&colors, &known, w, h)
+    } else {
+        colors.clone()
+    };
+    if p.method == Method::Diffusion {
+        diffuse(
+            &mut fill,
+            &holes,
+            w,
+            h,
+            p.iterations.clamp(1, MAX_ITERATIONS as usize),
+        );
+    }
+
+    let feather = if p.feather.is_nan() {
+        0.0
+    } else {
+        p.feather.clamp(0.0, MAX_FEATHER)
+    };
+    let radius = (feather * 0.5).round() as usize;
+    let blend = if radius > 0 {
+        let hole_f: Vec<f32> = holes.iter().map(|&h| if h { 1.0 } else { 0.0 }).collect();
+        feather_mask(&hole_f, w, h, radius)
+    } else {
+        vec![0.0; w * h]
+    };
+
+    for i in 0..w * h {
+        // 穴の内側は常に補間結果、外側は境界付近のみぼかして混ぜる
+        let t = if holes[i] { 1.0 } else { blend[i] };
+        let c: [f32; 4] = std::array::from_fn(|k| colors[i][k] + (fill[i][k] - colors[i][k]) * t);
+        out.data[i] = PixelF32 {
+            red: c[0],
+            green: c[1],
+            blue: c[2],
+            alpha: c[3].clamp(0.0, 1.0),
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so Mask Layer mode uses the input itself.
+pub struct Inpaint;
+
+impl RenderCore for Inpaint {
+    type Params = InpaintParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        InpaintParams {
+            source: if rng.chance(0.5) {
+                HoleSource::Transparent
+            } else {
+                HoleSource::MaskLayer
+            },
+            threshold: rng.slider(0.0, 1.0),
+            method: if rng.chance(0.5) {
+                Method::PushPull
+            } else {
+                Method::Diffusion
+            },
+            iterations: rng.popup(1, MAX_ITERATIONS) as usize,
+            feather: rng.slider(0.0, MAX_FEATHER),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = inpaint(input, Some(input), params);
+    }
+}
@@ -0,0 +1,478 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Edge, Filter};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Segments,
+    Mode,
+    Center,
+    Rotation,
+    Zoom,
+    SeamSoftness,
+    SecondaryReflection,
+    ReflectionRadius,
+    EdgeMode,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Folds the layer into kaleidoscope segments.";
+
+const MAX_SEGMENTS: i32 = 64;
+/// Upper bound of `Seam Softness` (full-resolution pixels).
+const MAX_SEAM_SOFTNESS: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Each segment is a wedge and its mirror image, so neighbours always match.
+    Mirror,
+    /// Each segment is a rotated copy of the same wedge.
+    Rotate,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Segments: repeats of the motif around the center
+        params.add(
+            Params::Segments,
+            tr("Segments"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_SEGMENTS);
+                d.set_slider_min(1);
+                d.set_slider_max(24);
+                d.set_default(6);
+            }),
+        )?;
+
+        params.add(
+            Params::Mode,
+            tr("Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Mirror"), tr("Rotate")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Rotation: turns the mirrors over the layer
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Zoom: percent; above 100 magnifies the sampled wedge
+        params.add(
+            Params::Zoom,
+            tr("Zoom"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Seam Softness: width in pixels over which neighbouring segments are blended
+        params.add(
+            Params::SeamSoftness,
+            tr("Seam Softness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SEAM_SOFTNESS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Secondary Reflection: a third mirror that folds the radius (see update_reflection_visibility)
+        params.add_with_flags(
+            Params::SecondaryReflection,
+            tr("Secondary Reflection"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Reflection Radius: distance from the center of the secondary mirror in pixels
+        params.add(
+            Params::ReflectionRadius,
+            tr("Reflection Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(1000.0);
+                d.set_default(200.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::EdgeMode,
+            tr("Edge Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("None"), tr("Clamp"), tr("Repeat"), tr("Mirror")]);
+                d.set_default(4);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Kaleidoscope - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_reflection_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::SecondaryReflection =>
+            {
+                update_reflection_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_reflection_visibility(
+    in_data: &InData,
+    params: &mut Parameters<Params>,
+) -> Result<(), Error> {
+    let secondary = params
+        .get(Params::SecondaryReflection)?
+        .as_checkbox()?
+        .value();
+
+    ui::set_param_visible(in_data, params, Params::ReflectionRadius, secondary)?;
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+
+        let secondary = params
+            .get(Params::SecondaryReflection)?
+            .as_checkbox()?
+            .value();
+        let reflection_radius = params
+            .get(Params::ReflectionRadius)?
+            .as_float_slider()?
+            .value() as f32;
+        let settings = KaleidoscopeParams {
+            segments: params.get(Params::Segments)?.as_slider()?.value() as u32,
+            mode: match params.get(Params::Mode)?.as_popup()?.value() {
+                2 => Mode::Rotate,
+                _ => Mode::Mirror,
+            },
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            rotation: params
+                .get(Params::Rotation)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            zoom: params.get(Params::Zoom)?.as_float_slider()?.value() as f32 / 100.0,
+            seam_softness: ds
+                .length(params.get(Params::SeamSoftness)?.as_float_slider()?.value() as f32),
+            reflection_radius: secondary.then(|| ds.length(reflection_radius)),
+            edge: match params.get(Params::EdgeMode)?.as_popup()?.value() {
+                1 => Edge::None,
+                2 => Edge::Clamp,
+                3 => Edge::Repeat,
+                _ => Edge::Mirror,
+            },
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = kaleidoscope(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct KaleidoscopeParams {
+    pub segments: u32,
+    pub mode: Mode,
+    /// Center in render pixels.
+    pub center: (f32, f32),
+    /// Mirror rotation in radians.
+    pub rotation: f32,
+    /// Magnification of the sampled wedge (1 = unchanged).
+    pub zoom: f32,
+    /// Seam blend width in render pixels.
+    pub seam_softness: f32,
+    /// Radius of the secondary mirror in render pixels, when enabled.
+    pub reflection_radius: Option<f32>,
+    pub edge: Edge,
+    pub filter: Filter,
+}
+
+/// Output -> source mapping of the mirror rig.
+struct Rig {
+    center: (f32, f32),
+    rotation: f32,
+    mirror: bool,
+    /// Angular size of one slice (half a segment in Mirror mode).
+    slice: f32,
+    zoom: f32,
+    softness: f32,
+    fold: Option<f32>,
+}
+
+impl Rig {
+    fn new(width: usize, height: usize, p: &KaleidoscopeParams) -> Self {
+        let (w, h) = (width as f32, height as f32);
+        let segments = p.segments.clamp(1, MAX_SEGMENTS as u32) as f32;
+        let mirror = p.mode == Mode::Mirror;
+        Self {
+            center: (
+                finite_or(p.center.0, w * 0.5).clamp(-1e6, 1e6),
+                finite_or(p.center.1, h * 0.5).clamp(-1e6, 1e6),
+            ),
+            rotation: finite_or(p.rotation, 0.0).rem_euclid(TAU),
+            mirror,
+            slice: if mirror {
+                TAU / (2.0 * segments)
+            } else {
+                TAU / segments
+            },
+            zoom: finite_or(p.zoom, 1.0).clamp(0.01, 100.0),
+            softness: finite_or(p.seam_softness, 0.0).clamp(0.0, MAX_SEAM_SOFTNESS),
+            fold: p
+                .reflection_radius
+                .map(|r| finite_or(r, 200.0).clamp(1.0, 1e6)),
+        }
+    }
+
+    /// Source position for radius `r` at offset `phi` into slice `k`.
+    fn source(&self, r: f32, k: i64, phi: f32) -> (f32, f32) {
+        // 鏡映モードでは奇数番目のスライスを反転させて隣同士を連続させる
+        let angle = if self.mirror && k.rem_euclid(2) == 1 {
+            self.slice - phi
+        } else {
+            phi
+        } + self.rotation;
+        let mut r = r / self.zoom;
+        if let Some(radius) = self.fold {
+            // 二次反射: 半径方向にも三角波で折り返す
+            let m = r.rem_euclid(2.0 * radius);
+            r = if m > radius { 2.0 * radius - m } else { m };
+        }
+        (
+            self.center.0 + r * angle.cos(),
+            self.center.1 + r * angle.sin(),
+        )
+    }
+}
+
+fn kaleidoscope(input: &ImageBuf, p: &KaleidoscopeParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let premul = sampling::premultiply(input);
+    let rig = Rig::new(width, height, p);
+    let edges = (p.edge, p.edge);
+    let fetch = |(sx, sy): (f32, f32)| {
+        let sx = finite_or(sx, 0.0).clamp(-1e7, 1e7);
+        let sy = finite_or(sy, 0.0).clamp(-1e7, 1e7);
+        sampling::sample_edge(&premul, sx, sy, p.filter, edges)
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 - rig.center.0, y as f32 - rig.center.1);
+            let r = dx.hypot(dy);
+            let theta = (dy.atan2(dx) - rig.rotation).rem_euclid(TAU);
+            let k = (theta / rig.slice).floor() as i64;
+            let phi = (theta - k as f32 * rig.slice).clamp(0.0, rig.slice);
+            let own = fetch(rig.source(r, k, phi));
+
+            // 近い方の継ぎ目までの距離で隣のスライスとブレンドしてアンチエイリアスする
+            let seam = |a: f32| r * a.min(FRAC_PI_2).sin();
+            let (d, k_next, phi_next) = if phi <= rig.slice - phi {
+                (seam(phi), k - 1, phi + rig.slice)
+            } else {
+                (seam(rig.slice - phi), k + 1, phi - rig.slice)
+            };
+            let half = rig.softness * 0.5;
+            let w = if half > 0.0 {
+                (0.5 - 0.5 * d / half).max(0.0)
+            } else {
+                0.0
+            };
+            let px = if w > 0.0 {
+                let other = fetch(rig.source(r, k_next, phi_next));
+                PixelF32 {
+                    red: own.red + (other.red - own.red) * w,
+                    green: own.green + (other.green - own.green) * w,
+                    blue: own.blue + (other.blue - own.blue) * w,
+                    alpha: own.alpha + (other.alpha - own.alpha) * w,
+                }
+            } else {
+                own
+            };
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct Kaleidoscope;
+
+impl RenderCore for Kaleidoscope {
+    type Params = KaleidoscopeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        KaleidoscopeParams {
+            segments: rng.int(1, MAX_SEGMENTS) as u32,
+            mode: if rng.chance(0.5) {
+                Mode::Mirror
+            } else {
+                Mode::Rotate
+            },
+            center: (rng.slider(-100.0, 300.0), rng.slider(-100.0, 300.0)),
+            rotation: rng.slider(-720.0, 720.0).to_radians(),
+            zoom: rng.slider(1.0, 10000.0) / 100.0,
+            seam_softness: rng.slider(0.0, MAX_SEAM_SOFTNESS),
+            reflection_radius: rng.chance(0.5).then(|| rng.slider(1.0, 10000.0)),
+            edge: match rng.popup(1, 4) {
+                1 => Edge::None,
+                2 => Edge::Clamp,
+                3 => Edge::Repeat,
+                _ => Edge::Mirror,
+            },
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = kaleidoscope(input, params);
+    }
+}
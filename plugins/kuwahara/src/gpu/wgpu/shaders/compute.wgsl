@@ -0,0 +1,113 @@
+// Keep in sync with `RenderParams` in ../mod.rs and `kuwahara_pixel` in lib.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    sectors: u32,
+    _pad: u32,
+    radius: f32,
+    sharpness: f32,
+    _pad1: vec2<f32>,
+};
+
+@group(0) @binding(0) var<storage, read> input: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+// (tangent.x, tangent.y, anisotropy, 0) per pixel
+@group(0) @binding(3) var<storage, read> orientation: array<vec4<f32>>;
+
+const MAX_SECTORS: u32 = 8u;
+const PI: f32 = 3.14159265;
+const TAU: f32 = 6.28318531;
+
+fn fetch(x: i32, y: i32) -> vec4<f32> {
+    let cx = clamp(x, 0, i32(params.width) - 1);
+    let cy = clamp(y, 0, i32(params.height) - 1);
+    return input[u32(cy) * params.width + u32(cx)];
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+    let o = orientation[i];
+    let n = min(params.sectors, MAX_SECTORS);
+    let nf = f32(n);
+
+    let aniso = clamp(o.z, 0.0, 1.0);
+    let a = max(params.radius * (1.0 + aniso), 0.5);
+    let b = max(params.radius / (1.0 + aniso), 0.5);
+    let c = o.x;
+    let s = o.y;
+    let ext_x = i32(ceil(sqrt(a * a * c * c + b * b * s * s)));
+    let ext_y = i32(ceil(sqrt(a * a * s * s + b * b * c * c)));
+
+    var m: array<vec4<f32>, 8>;
+    var sq: array<vec3<f32>, 8>;
+    var w: array<f32, 8>;
+    for (var k = 0u; k < MAX_SECTORS; k++) {
+        m[k] = vec4<f32>(0.0);
+        sq[k] = vec3<f32>(0.0);
+        w[k] = 0.0;
+    }
+
+    for (var dy = -ext_y; dy <= ext_y; dy++) {
+        for (var dx = -ext_x; dx <= ext_x; dx++) {
+            let fx = f32(dx);
+            let fy = f32(dy);
+            let u = (fx * c + fy * s) / a;
+            let v = (-fx * s + fy * c) / b;
+            let r2 = u * u + v * v;
+            if (r2 > 1.0) {
+                continue;
+            }
+            let g = exp(-2.0 * r2);
+            let px = fetch(i32(gid.x) + dx, i32(gid.y) + dy);
+            let phi = atan2(v, u);
+            for (var k = 0u; k < n; k++) {
+                var wk: f32;
+                if (dx == 0 && dy == 0) {
+                    wk = g / nf;
+                } else {
+                    var d = phi - TAU * f32(k) / nf;
+                    d = d - TAU * floor((d + PI) / TAU);
+                    if (abs(d) >= TAU / nf) {
+                        continue;
+                    }
+                    let cw = cos(d * nf * 0.25);
+                    wk = cw * cw * g;
+                }
+                m[k] += px * wk;
+                sq[k] += px.rgb * px.rgb * wk;
+                w[k] += wk;
+            }
+        }
+    }
+
+    var acc = vec4<f32>(0.0);
+    var plain = vec4<f32>(0.0);
+    var total = 0.0;
+    var count = 0.0;
+    for (var k = 0u; k < n; k++) {
+        if (w[k] <= 0.0) {
+            continue;
+        }
+        let mean = m[k] / w[k];
+        let variance = max(sq[k] / w[k] - mean.rgb * mean.rgb, vec3<f32>(0.0));
+        let sigma2 = variance.r + variance.g + variance.b;
+        let alpha = 1.0 / (1.0 + pow(65025.0 * sigma2, 0.5 * params.sharpness));
+        acc += mean * alpha;
+        total += alpha;
+        plain += mean;
+        count += 1.0;
+    }
+
+    if (total > 1e-20) {
+        output[i] = acc / total;
+    } else if (count > 0.0) {
+        output[i] = plain / count;
+    } else {
+        output[i] = input[i];
+    }
+}
@@ -0,0 +1,380 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::tensor::{Orientation, structure_tensor};
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Radius,
+    Sharpness,
+    Sectors,
+}
+
+/// Upper bound of `Sectors` (the GPU path uses fixed-size arrays of this length).
+const MAX_SECTORS: usize = 8;
+
+/// Gaussian sigma (full-resolution pixels) used to smooth the structure tensor.
+const TENSOR_SIGMA: f32 = 2.0;
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Painterly anisotropic Kuwahara filter guided by the structure tensor.";
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(32.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(16.0);
+                d.set_default(6.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Sharpness,
+            tr("Sharpness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(16.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(16.0);
+                d.set_default(8.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Sectors,
+            tr("Sectors"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(4);
+                d.set_valid_max(MAX_SECTORS as i32);
+                d.set_slider_min(4);
+                d.set_slider_max(MAX_SECTORS as i32);
+                d.set_default(8);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Kuwahara - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 半径はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = KuwaharaParams {
+            radius: ds.length(params.get(Params::Radius)?.as_float_slider()?.value() as f32),
+            sharpness: params.get(Params::Sharpness)?.as_float_slider()?.value() as f32,
+            sectors: params.get(Params::Sectors)?.as_slider()?.value() as usize,
+            tensor_sigma: ds.length(TENSOR_SIGMA),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let orientation: Vec<Orientation> = structure_tensor(&input, settings.tensor_sigma)
+            .iter()
+            .map(|t| t.orientation())
+            .collect();
+
+        #[cfg(feature = "gpu_wgpu")]
+        if let Some(ctx) = wgpu_context() {
+            let gpu_params = RenderParams {
+                width: input.width as u32,
+                height: input.height as u32,
+                sectors: settings.sectors.clamp(1, MAX_SECTORS) as u32,
+                _pad: 0,
+                radius: settings.radius,
+                sharpness: settings.sharpness,
+                _pad1: [0.0; 2],
+            };
+            let guide: Vec<f32> = orientation
+                .iter()
+                .flat_map(|o| [o.tangent.0, o.tangent.1, o.anisotropy, 0.0])
+                .collect();
+            if let Ok(rgba) = ctx.render(&gpu_params, &input.to_rgba(), &guide) {
+                return ImageBuf::from_rgba(input.width, input.height, &rgba)
+                    .write_to_layer(&mut out_layer);
+            }
+            // Fall through to the CPU path when the GPU dispatch fails.
+        }
+
+        let progress_final = out_layer.height() as i32;
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let (x, y) = (x as usize, y as usize);
+            let px = if x < input.width && y < input.height {
+                kuwahara_pixel(&input, &orientation, x, y, &settings)
+            } else {
+                utils::image::TRANSPARENT
+            };
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct KuwaharaParams {
+    /// Ellipse radius in pixels (already scaled for downsampling).
+    pub radius: f32,
+    /// Exponent of the variance weighting; higher keeps edges crisper.
+    pub sharpness: f32,
+    pub sectors: usize,
+    pub tensor_sigma: f32,
+}
+
+/// Generalized anisotropic Kuwahara filter (Kyprianidis et al.) at one pixel:
+/// an ellipse aligned with the local edge is split into smoothly overlapping
+/// sectors, and their means are blended by inverse variance.
+fn kuwahara_pixel(
+    input: &ImageBuf,
+    orientation: &[Orientation],
+    x: usize,
+    y: usize,
+    p: &KuwaharaParams,
+) -> PixelF32 {
+    use std::f32::consts::{PI, TAU};
+
+    let o = orientation[input.index(x, y)];
+    let n = p.sectors.clamp(1, MAX_SECTORS);
+    let nf = n as f32;
+
+    // NaN の異方性は等方として扱う
+    let aniso = if o.anisotropy.is_nan() {
+        0.0
+    } else {
+        o.anisotropy.clamp(0.0, 1.0)
+    };
+    let a = (p.radius * (1.0 + aniso)).max(0.5);
+    let b = (p.radius / (1.0 + aniso)).max(0.5);
+    let (c, s) = o.tangent;
+    let ext_x = (a * a * c * c + b * b * s * s).sqrt().ceil() as isize;
+    let ext_y = (a * a * s * s + b * b * c * c).sqrt().ceil() as isize;
+
+    let mut m = [[0.0f32; 4]; MAX_SECTORS];
+    let mut sq = [[0.0f32; 3]; MAX_SECTORS];
+    let mut w = [0.0f32; MAX_SECTORS];
+
+    for dy in -ext_y..=ext_y {
+        for dx in -ext_x..=ext_x {
+            let (fx, fy) = (dx as f32, dy as f32);
+            let u = (fx * c + fy * s) / a;
+            let v = (-fx * s + fy * c) / b;
+            let r2 = u * u + v * v;
+            if r2 > 1.0 {
+                continue;
+            }
+            let g = (-2.0 * r2).exp();
+            let px = input.get_clamped(x as isize + dx, y as isize + dy);
+            let rgba = [px.red, px.green, px.blue, px.alpha];
+            let phi = v.atan2(u);
+            for k in 0..n {
+                let wk = if dx == 0 && dy == 0 {
+                    // 中心画素は全セクタに均等に寄与させる
+                    g / nf
+                } else {
+                    let d = (phi - TAU * k as f32 / nf + PI).rem_euclid(TAU) - PI;
+                    if d.abs() >= TAU / nf {
+                        continue;
+                    }
+                    (d * nf * 0.25).cos().powi(2) * g
+                };
+                for ch in 0..4 {
+                    m[k][ch] += rgba[ch] * wk;
+                }
+                for ch in 0..3 {
+                    sq[k][ch] += rgba[ch] * rgba[ch] * wk;
+                }
+                w[k] += wk;
+            }
+        }
+    }
+
+    let mut acc = [0.0f32; 4];
+    let mut plain = [0.0f32; 4];
+    let mut total = 0.0f32;
+    let mut count = 0.0f32;
+    for k in 0..n {
+        if w[k] <= 0.0 {
+            continue;
+        }
+        let mean = m[k].map(|v| v / w[k]);
+        let sigma2: f32 = (0..3)
+            .map(|ch| (sq[k][ch] / w[k] - mean[ch] * mean[ch]).max(0.0))
+            .sum();
+        let alpha = 1.0 / (1.0 + (65025.0 * sigma2).powf(0.5 * p.sharpness));
+        for ch in 0..4 {
+            acc[ch] += mean[ch] * alpha;
+            plain[ch] += mean[ch];
+        }
+        total += alpha;
+        count += 1.0;
+    }
+
+    let out = if total > 1e-20 {
+        acc.map(|v| v / total)
+    } else if count > 0.0 {
+        plain.map(|v| v / count)
+    } else {
+        let px = input.data[input.index(x, y)];
+        [px.red, px.green, px.blue, px.alpha]
+    };
+    PixelF32 {
+        red: out[0],
+        green: out[1],
+        blue: out[2],
+        alpha: out[3],
+    }
+}
+
+/// Host-independent render core (see `utils::harness`), CPU path.
+pub struct Kuwahara;
+
+impl RenderCore for Kuwahara {
+    type Params = KuwaharaParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        KuwaharaParams {
+            radius: rng.slider(1.0, 32.0),
+            sharpness: rng.slider(1.0, 16.0),
+            sectors: rng.popup(4, MAX_SECTORS as i32) as usize,
+            tensor_sigma: TENSOR_SIGMA,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let orientation: Vec<Orientation> = structure_tensor(input, params.tensor_sigma)
+            .iter()
+            .map(|t| t.orientation())
+            .collect();
+        for y in 0..input.height {
+            for x in 0..input.width {
+                output.set(x, y, kuwahara_pixel(input, &orientation, x, y, params));
+            }
+        }
+    }
+}
@@ -0,0 +1,535 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Edge, Filter};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Direction,
+    K1,
+    K2,
+    K3,
+    P1,
+    P2,
+    Center,
+    Normalization,
+    FocalLength,
+    ScaleToFit,
+    Scale,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Applies or removes Brown-Conrady lens distortion.";
+
+/// Newton iterations when inverting the distortion model.
+const INVERSE_ITERATIONS: usize = 20;
+/// Border samples per edge when searching the fit scale.
+const FIT_SAMPLES: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Adds the lens distortion (for matching clean CG to the plate).
+    Distort,
+    /// Removes the lens distortion from the plate.
+    Undistort,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    HalfWidth,
+    HalfDiagonal,
+    /// Focal length in pixels, as in OpenCV-style camera solves.
+    FocalLength,
+}
+
+/// Brown-Conrady coefficients over normalized coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct Lens {
+    pub k1: f32,
+    pub k2: f32,
+    pub k3: f32,
+    pub p1: f32,
+    pub p2: f32,
+}
+
+impl Lens {
+    /// Undistorted -> distorted.
+    fn distort(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        (
+            x * radial + 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x),
+            y * radial + self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y,
+        )
+    }
+
+    /// Distorted -> undistorted by Newton's method; `None` where the model folds
+    /// over or does not converge.
+    fn undistort(&self, d: (f32, f32)) -> Option<(f32, f32)> {
+        let (mut x, mut y) = d;
+        for _ in 0..INVERSE_ITERATIONS {
+            let (fx, fy) = self.distort((x, y));
+            let (ex, ey) = (fx - d.0, fy - d.1);
+            if ex * ex + ey * ey < 1e-12 {
+                return Some((x, y));
+            }
+
+            let r2 = x * x + y * y;
+            let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+            // d(radial)/d(r²) の 2 倍（∂radial/∂x = dr * x）
+            let dr = 2.0 * self.k1 + r2 * (4.0 * self.k2 + 6.0 * self.k3 * r2);
+            let jxx = radial + dr * x * x + 2.0 * self.p1 * y + 6.0 * self.p2 * x;
+            let jxy = dr * x * y + 2.0 * self.p1 * x + 2.0 * self.p2 * y;
+            let jyy = radial + dr * y * y + 6.0 * self.p1 * y + 2.0 * self.p2 * x;
+            let det = jxx * jyy - jxy * jxy;
+            if !det.is_finite() || det.abs() < 1e-9 {
+                return None;
+            }
+            x -= (jyy * ex - jxy * ey) / det;
+            y -= (jxx * ey - jxy * ex) / det;
+            if !x.is_finite() || !y.is_finite() {
+                return None;
+            }
+        }
+        let (fx, fy) = self.distort((x, y));
+        ((fx - d.0).powi(2) + (fy - d.1).powi(2) < 1e-6).then_some((x, y))
+    }
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Direction,
+            tr("Direction"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Distort"), tr("Undistort")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // K1 - K3: radial coefficients (negative = barrel, positive = pincushion)
+        for (id, name) in [
+            (Params::K1, tr("K1")),
+            (Params::K2, tr("K2")),
+            (Params::K3, tr("K3")),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-10.0);
+                    d.set_valid_max(10.0);
+                    d.set_slider_min(-1.0);
+                    d.set_slider_max(1.0);
+                    d.set_default(0.0);
+                    d.set_precision(4);
+                }),
+            )?;
+        }
+
+        // P1 / P2: tangential (decentering) coefficients
+        for (id, name) in [(Params::P1, tr("P1")), (Params::P2, tr("P2"))] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-1.0);
+                    d.set_valid_max(1.0);
+                    d.set_slider_min(-0.1);
+                    d.set_slider_max(0.1);
+                    d.set_default(0.0);
+                    d.set_precision(5);
+                }),
+            )?;
+        }
+
+        // Center: optical center (principal point)
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Normalization: radius that maps to 1 in the model; match the tracker's convention
+        params.add_with_flags(
+            Params::Normalization,
+            tr("Normalization"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Half Width"), tr("Half Diagonal"), tr("Focal Length")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Focal Length: in full-resolution pixels (fx of the camera solve)
+        params.add(
+            Params::FocalLength,
+            tr("Focal Length"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(100000.0);
+                d.set_slider_min(100.0);
+                d.set_slider_max(5000.0);
+                d.set_default(1920.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Scale To Fit: zoom so no empty border is left
+        params.add(
+            Params::ScaleToFit,
+            tr("Scale To Fit"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Scale: extra zoom in percent, on top of Scale To Fit
+        params.add(
+            Params::Scale,
+            tr("Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(10.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(50.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_LensDistortion - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Normalization =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let focal = params.get(Params::Normalization)?.as_popup()?.value() == 3;
+
+    ui::set_param_visible(in_data, params, Params::FocalLength, focal)?;
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 中心と焦点距離はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let coefficient = |id: Params| -> Result<f32, Error> {
+            Ok(params.get(id)?.as_float_slider()?.value() as f32)
+        };
+        let settings = LensParams {
+            direction: match params.get(Params::Direction)?.as_popup()?.value() {
+                1 => Direction::Distort,
+                _ => Direction::Undistort,
+            },
+            lens: Lens {
+                k1: coefficient(Params::K1)?,
+                k2: coefficient(Params::K2)?,
+                k3: coefficient(Params::K3)?,
+                p1: coefficient(Params::P1)?,
+                p2: coefficient(Params::P2)?,
+            },
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            normalization: match params.get(Params::Normalization)?.as_popup()?.value() {
+                2 => Normalization::HalfDiagonal,
+                3 => Normalization::FocalLength,
+                _ => Normalization::HalfWidth,
+            },
+            focal_length: ds.length(coefficient(Params::FocalLength)?),
+            scale_to_fit: params.get(Params::ScaleToFit)?.as_checkbox()?.value(),
+            scale: coefficient(Params::Scale)? / 100.0,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = remap(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LensParams {
+    pub direction: Direction,
+    pub lens: Lens,
+    /// Optical center in render pixels.
+    pub center: (f32, f32),
+    pub normalization: Normalization,
+    /// Focal length in render pixels (used by [`Normalization::FocalLength`]).
+    pub focal_length: f32,
+    pub scale_to_fit: bool,
+    /// Extra zoom factor (1 = none).
+    pub scale: f32,
+    pub filter: Filter,
+}
+
+/// Output -> source mapping in render pixels.
+struct Mapping {
+    lens: Lens,
+    direction: Direction,
+    center: (f32, f32),
+    norm: f32,
+    /// Output coordinates are divided by this before the lens model (zoom).
+    zoom: f32,
+}
+
+impl Mapping {
+    fn source(&self, (x, y): (f32, f32)) -> Option<(f32, f32)> {
+        let scale = self.norm * self.zoom;
+        let q = ((x - self.center.0) / scale, (y - self.center.1) / scale);
+        // 出力画素が参照する入力側の位置: 歪みを除く場合は順変換、付ける場合は逆変換
+        let (sx, sy) = match self.direction {
+            Direction::Undistort => self.lens.distort(q),
+            Direction::Distort => self.lens.undistort(q)?,
+        };
+        let p = (
+            self.center.0 + sx * self.norm,
+            self.center.1 + sy * self.norm,
+        );
+        (p.0.is_finite() && p.1.is_finite()).then_some(p)
+    }
+
+    /// How far the frame border overshoots the source frame (> 1 leaves empty
+    /// border) relative to the optical center.
+    fn overshoot(&self, width: f32, height: f32) -> f32 {
+        let mut worst = 0.0f32;
+        for i in 0..=FIT_SAMPLES {
+            let t = i as f32 / FIT_SAMPLES as f32;
+            let border = [
+                (t * width, 0.0),
+                (t * width, height),
+                (0.0, t * height),
+                (width, t * height),
+            ];
+            for p in border {
+                let Some((sx, sy)) = self.source(p) else {
+                    continue;
+                };
+                let (dx, dy) = (sx - self.center.0, sy - self.center.1);
+                let limit_x = if dx > 0.0 {
+                    width - self.center.0
+                } else {
+                    self.center.0
+                };
+                let limit_y = if dy > 0.0 {
+                    height - self.center.1
+                } else {
+                    self.center.1
+                };
+                if limit_x > 0.0 {
+                    worst = worst.max(dx.abs() / limit_x);
+                }
+                if limit_y > 0.0 {
+                    worst = worst.max(dy.abs() / limit_y);
+                }
+            }
+        }
+        worst
+    }
+}
+
+fn mapping(width: usize, height: usize, p: &LensParams) -> Mapping {
+    let (w, h) = (width as f32, height as f32);
+    let lens = Lens {
+        k1: finite_or(p.lens.k1, 0.0),
+        k2: finite_or(p.lens.k2, 0.0),
+        k3: finite_or(p.lens.k3, 0.0),
+        p1: finite_or(p.lens.p1, 0.0),
+        p2: finite_or(p.lens.p2, 0.0),
+    };
+    let norm = match p.normalization {
+        Normalization::HalfWidth => w * 0.5,
+        Normalization::HalfDiagonal => w.hypot(h) * 0.5,
+        Normalization::FocalLength => finite_or(p.focal_length, w),
+    }
+    .max(1.0);
+
+    let mut mapping = Mapping {
+        lens,
+        direction: p.direction,
+        center: (
+            finite_or(p.center.0, w * 0.5),
+            finite_or(p.center.1, h * 0.5),
+        ),
+        norm,
+        zoom: 1.0,
+    };
+    if p.scale_to_fit {
+        // 写像はほぼ線形なので、はみ出し率で割り戻すのを数回繰り返せば収束する
+        for _ in 0..4 {
+            let overshoot = mapping.overshoot(w, h);
+            if !overshoot.is_finite() || overshoot <= 0.0 {
+                break;
+            }
+            mapping.zoom = (mapping.zoom * overshoot).clamp(0.01, 100.0);
+        }
+    }
+    mapping.zoom *= finite_or(p.scale, 1.0).clamp(0.1, 10.0);
+    mapping
+}
+
+fn remap(input: &ImageBuf, p: &LensParams) -> ImageBuf {
+    let mapping = mapping(input.width, input.height, p);
+    // 入力フレームの外は透明
+    sampling::remap(input, p.filter, (Edge::None, Edge::None), |q| {
+        mapping.source(q)
+    })
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct LensDistortion;
+
+impl RenderCore for LensDistortion {
+    type Params = LensParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        LensParams {
+            direction: if rng.chance(0.5) {
+                Direction::Distort
+            } else {
+                Direction::Undistort
+            },
+            lens: Lens {
+                k1: rng.slider(-10.0, 10.0),
+                k2: rng.slider(-10.0, 10.0),
+                k3: rng.slider(-10.0, 10.0),
+                p1: rng.slider(-1.0, 1.0),
+                p2: rng.slider(-1.0, 1.0),
+            },
+            center: (rng.slider(-100.0, 300.0), rng.slider(-100.0, 300.0)),
+            normalization: match rng.popup(1, 3) {
+                2 => Normalization::HalfDiagonal,
+                3 => Normalization::FocalLength,
+                _ => Normalization::HalfWidth,
+            },
+            focal_length: rng.slider(1.0, 100000.0),
+            scale_to_fit: rng.chance(0.5),
+            scale: rng.slider(0.1, 10.0),
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = remap(input, params);
+    }
+}
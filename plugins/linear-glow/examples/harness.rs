@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<linear_glow::LinearGlow>();
+}
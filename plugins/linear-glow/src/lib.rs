@@ -0,0 +1,425 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::blur::blur;
+use utils::color::{linear_to_srgb, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, Edge};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Placement,
+    LinearWorkingSpace,
+    Threshold,
+    Knee,
+    Radius,
+    Intensity,
+    Tint,
+    Composite,
+    GlowOnly,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Adds a glow computed in linear light.";
+
+/// Upper bound of `Radius` (full-resolution pixels).
+const MAX_RADIUS: f32 = 2000.0;
+/// Blur octaves averaged into the glow, each half the width of the previous,
+/// for a bright core with a long soft tail.
+const OCTAVES: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The glow spills past the layer's edges.
+    Outer,
+    /// The glow is clipped to the layer's alpha.
+    Inner,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Composite {
+    Add,
+    /// Adds less light where the layer is already bright.
+    Screen,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Placement: Outer lets the glow spill past the layer, Inner keeps it within the alpha
+        params.add(
+            Params::Placement,
+            tr("Placement"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Outer"), tr("Inner")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Linear Working Space: values are already linear light (otherwise sRGB is linearized first)
+        params.add(
+            Params::LinearWorkingSpace,
+            tr("Linear Working Space"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Threshold: linear luminance where highlights start to glow
+        params.add(
+            Params::Threshold,
+            tr("Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Knee: softens the threshold over this share of it
+        params.add(
+            Params::Knee,
+            tr("Knee"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Radius: reach of the widest blur octave in pixels
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(300.0);
+                d.set_default(60.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Intensity,
+            tr("Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Tint: multiplies the glow color
+        params.add(
+            Params::Tint,
+            tr("Tint"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Composite,
+            tr("Composite"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Add"), tr("Screen")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Glow Only: outputs the glow without the layer
+        params.add(
+            Params::GlowOnly,
+            tr("Glow Only"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_LinearGlow - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let tint = params.get(Params::Tint)?.as_color()?.float_value()?;
+
+        let settings = GlowParams {
+            placement: match params.get(Params::Placement)?.as_popup()?.value() {
+                2 => Placement::Inner,
+                _ => Placement::Outer,
+            },
+            linear: params
+                .get(Params::LinearWorkingSpace)?
+                .as_checkbox()?
+                .value(),
+            threshold: slider(Params::Threshold)? / 100.0,
+            knee: slider(Params::Knee)? / 100.0,
+            radius: ds.length(slider(Params::Radius)?),
+            intensity: slider(Params::Intensity)? / 100.0,
+            tint: [tint.red, tint.green, tint.blue],
+            composite: match params.get(Params::Composite)?.as_popup()?.value() {
+                2 => Composite::Screen,
+                _ => Composite::Add,
+            },
+            glow_only: params.get(Params::GlowOnly)?.as_checkbox()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        glow(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct GlowParams {
+    pub placement: Placement,
+    /// Input values are linear light rather than sRGB encoded.
+    pub linear: bool,
+    /// Linear luminance.
+    pub threshold: f32,
+    /// Fraction of the threshold softened, 0..1.
+    pub knee: f32,
+    /// Render pixels.
+    pub radius: f32,
+    pub intensity: f32,
+    pub tint: [f32; 3],
+    pub composite: Composite,
+    pub glow_only: bool,
+}
+
+/// Share of a linear color that glows, with a quadratic knee below the threshold.
+fn bright_share(luma: f32, threshold: f32, knee: f32) -> f32 {
+    let soft = (luma - threshold + knee).clamp(0.0, 2.0 * knee);
+    let soft = soft * soft / (4.0 * knee + 1e-5);
+    soft.max(luma - threshold) / luma.max(1e-5)
+}
+
+pub fn glow(input: &ImageBuf, p: &GlowParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+
+    let decode = |c: f32| {
+        let c = finite_or(c, 0.0);
+        if p.linear { c } else { srgb_to_linear(c) }
+    };
+    // リニアライトの乗算済みカラーで扱う
+    let alpha: Vec<f32> = input
+        .data
+        .iter()
+        .map(|px| finite_or(px.alpha, 0.0).clamp(0.0, 1.0))
+        .collect();
+    let colors: Vec<[f32; 3]> = input
+        .data
+        .iter()
+        .zip(&alpha)
+        .map(|(px, &a)| [px.red, px.green, px.blue].map(|c| decode(c) * a))
+        .collect();
+
+    let threshold = finite_or(p.threshold, 0.5).max(0.0);
+    let knee = threshold * finite_or(p.knee, 0.0).clamp(0.0, 1.0);
+    let bright: Vec<[f32; 3]> = colors
+        .iter()
+        .map(|&c| {
+            let luma = 0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2];
+            let share = bright_share(luma.max(0.0), threshold, knee);
+            c.map(|v| v.max(0.0) * share)
+        })
+        .collect();
+
+    let radius = finite_or(p.radius, 0.0).clamp(0.0, MAX_RADIUS);
+    let intensity = finite_or(p.intensity, 1.0).clamp(0.0, 10.0);
+    let tint = p.tint.map(|c| finite_or(c, 1.0).max(0.0));
+    let mut glow = vec![[0.0f32; 3]; w * h];
+    for (c, &t) in tint.iter().enumerate() {
+        let plane: Vec<f32> = bright.iter().map(|b| b[c]).collect();
+        let mut sum = vec![0.0f32; w * h];
+        // 幅を半分ずつにしたブラーを平均し、芯の明るい長い裾にする
+        // (範囲外は 0 として、グローがフレームの外へ抜けるようにする)
+        for octave in 0..OCTAVES {
+            let sigma = radius * 0.5 / (1 << octave) as f32;
+            for (s, v) in sum.iter_mut().zip(blur(&plane, w, h, sigma, Edge::None)) {
+                *s += v;
+            }
+        }
+        for (g, s) in glow.iter_mut().zip(sum) {
+            g[c] = s / OCTAVES as f32 * intensity * t;
+        }
+    }
+
+    for i in 0..w * h {
+        let clip = match p.placement {
+            Placement::Outer => 1.0,
+            Placement::Inner => alpha[i],
+        };
+        let g = glow[i].map(|v| v * clip);
+        let (base, a) = if p.glow_only {
+            ([0.0; 3], 0.0)
+        } else {
+            (colors[i], alpha[i])
+        };
+        let rgb: [f32; 3] = std::array::from_fn(|c| match p.composite {
+            Composite::Add => base[c] + g[c],
+            Composite::Screen => base[c] + g[c] * (1.0 - base[c].clamp(0.0, 1.0)),
+        });
+        // 透明部分に広がった光は最も明るいチャンネルぶんの不透明度を持たせる
+        let glow_alpha = g.iter().fold(0.0f32, |m, &v| m.max(v)).clamp(0.0, 1.0);
+        let out_alpha = a + glow_alpha * (1.0 - a);
+        let encode = |v: f32| {
+            let v = if out_alpha > 0.0 {
+                (v / out_alpha).max(0.0)
+            } else {
+                0.0
+            };
+            if p.linear { v } else { linear_to_srgb(v) }
+        };
+        out.data[i] = PixelF32 {
+            red: encode(rgb[0]),
+            green: encode(rgb[1]),
+            blue: encode(rgb[2]),
+            alpha: out_alpha,
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct LinearGlow;
+
+impl RenderCore for LinearGlow {
+    type Params = GlowParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        GlowParams {
+            placement: if rng.chance(0.5) {
+                Placement::Outer
+            } else {
+                Placement::Inner
+            },
+            linear: rng.chance(0.5),
+            threshold: rng.slider(0.0, 2.0),
+            knee: rng.unit(),
+            radius: rng.slider(0.0, 300.0),
+            intensity: rng.slider(0.0, 4.0),
+            tint: [rng.unit(), rng.unit(), rng.unit()],
+            composite: if rng.chance(0.5) {
+                Composite::Add
+            } else {
+                Composite::Screen
+            },
+            glow_only: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = glow(input, params);
+    }
+}
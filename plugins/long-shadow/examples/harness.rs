@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<long_shadow::LongShadow>();
+}
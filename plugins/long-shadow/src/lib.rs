@@ -0,0 +1,414 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Direction,
+    Length,
+    Falloff,
+    ShadowColor,
+    Opacity,
+    ShadowOnly,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Casts a flat-design long shadow from the layer's alpha.";
+
+/// Upper bound of `Length` (full-resolution pixels).
+const MAX_LENGTH: f32 = 4000.0;
+/// Alpha above which a pixel starts a shadow for the falloff distance.
+const SOLID_ALPHA: f32 = 1e-3;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Direction: where the shadow points, 0 = right, clockwise
+        params.add(
+            Params::Direction,
+            tr("Direction"),
+            AngleDef::setup(|d| {
+                d.set_default(45.0);
+            }),
+        )?;
+
+        // Length: extrusion distance in pixels
+        params.add(
+            Params::Length,
+            tr("Length"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_LENGTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1000.0);
+                d.set_default(200.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Falloff: how much the shadow fades by its tip
+        params.add(
+            Params::Falloff,
+            tr("Falloff"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::ShadowColor,
+            tr("Shadow Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Opacity,
+            tr("Opacity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(40.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Shadow Only: outputs the shadow without the layer on top
+        params.add(
+            Params::ShadowOnly,
+            tr("Shadow Only"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_LongShadow - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let color = params.get(Params::ShadowColor)?.as_color()?.float_value()?;
+
+        let settings = ShadowParams {
+            direction: params.get(Params::Direction)?.as_angle()?.value(),
+            length: ds.length(slider(Params::Length)?),
+            falloff: slider(Params::Falloff)? / 100.0,
+            color: [color.red, color.green, color.blue],
+            opacity: slider(Params::Opacity)? / 100.0,
+            shadow_only: params.get(Params::ShadowOnly)?.as_checkbox()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        long_shadow(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ShadowParams {
+    /// Degrees, 0 = right, clockwise on screen.
+    pub direction: f32,
+    /// Render pixels.
+    pub length: f32,
+    /// Opacity lost by the tip, 0..1.
+    pub falloff: f32,
+    pub color: [f32; 3],
+    /// 0..1.
+    pub opacity: f32,
+    pub shadow_only: bool,
+}
+
+/// Bilinear sample of a scalar field at pixel-center coordinates, zero outside.
+fn sample(field: &[f32], width: usize, height: usize, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let at = |xi: f32, yi: f32| {
+        if xi < 0.0 || yi < 0.0 || xi >= width as f32 || yi >= height as f32 {
+            0.0
+        } else {
+            field[yi as usize * width + xi as usize]
+        }
+    };
+    let top = at(x0, y0) + (at(x0 + 1.0, y0) - at(x0, y0)) * tx;
+    let bottom = at(x0, y0 + 1.0) + (at(x0 + 1.0, y0 + 1.0) - at(x0, y0 + 1.0)) * tx;
+    top + (bottom - top) * ty
+}
+
+/// Largest alpha within `steps` steps of `step` upstream of each pixel
+/// (the extruded silhouette), by doubling the window each pass.
+fn extrude(
+    alpha: &[f32],
+    width: usize,
+    height: usize,
+    (sx, sy): (f32, f32),
+    steps: f32,
+) -> Vec<f32> {
+    let shifted_max = |field: &[f32], offset: f32| -> Vec<f32> {
+        (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as f32, (i / width) as f32);
+                field[i].max(sample(
+                    field,
+                    width,
+                    height,
+                    x - sx * offset,
+                    y - sy * offset,
+                ))
+            })
+            .collect()
+    };
+
+    // window は upstream 方向に 0..window-1 歩ぶんの最大値を持つ
+    let mut field = alpha.to_vec();
+    let mut window = 1.0f32;
+    while window * 2.0 <= steps + 1.0 {
+        field = shifted_max(&field, window);
+        window *= 2.0;
+    }
+    // 残りは端数を含めて末尾に揃えた窓と重ねる
+    if steps + 1.0 > window {
+        field = shifted_max(&field, steps + 1.0 - window);
+    }
+    field
+}
+
+/// Distance along the shadow direction from each pixel back to the nearest
+/// silhouette pixel, scanning lines along the major axis of `step`.
+fn upstream_distance(alpha: &[f32], width: usize, height: usize, (sx, sy): (f32, f32)) -> Vec<f32> {
+    let step_length = (sx * sx + sy * sy).sqrt();
+    let major_x = sx.abs() >= sy.abs();
+    // 主軸を u、副軸を v として走査する
+    let (nu, nv, su, sv) = if major_x {
+        (width, height, sx, sy)
+    } else {
+        (height, width, sy, sx)
+    };
+    let index = |u: usize, v: usize| {
+        if major_x {
+            v * width + u
+        } else {
+            u * width + v
+        }
+    };
+    let order: Vec<usize> = if su > 0.0 {
+        (0..nu).collect()
+    } else {
+        (0..nu).rev().collect()
+    };
+
+    let mut dist = vec![f32::INFINITY; width * height];
+    for (k, &u) in order.iter().enumerate() {
+        for v in 0..nv {
+            let i = index(u, v);
+            if alpha[i] > SOLID_ALPHA {
+                dist[i] = 0.0;
+                continue;
+            }
+            let Some(&pu) = k.checked_sub(1).map(|k| &order[k]) else {
+                continue;
+            };
+            // 一つ手前の列の副軸方向は端数になるので、有効な隣接 2 画素を補間する
+            let pv = v as f32 - sv;
+            let v0 = pv.floor();
+            let t = pv - v0;
+            let (mut sum, mut weight) = (0.0, 0.0);
+            for (vv, w) in [(v0, 1.0 - t), (v0 + 1.0, t)] {
+                if w > 0.0 && vv >= 0.0 && (vv as usize) < nv {
+                    let d = dist[index(pu, vv as usize)];
+                    if d.is_finite() {
+                        sum += d * w;
+                        weight += w;
+                    }
+                }
+            }
+            if weight > 0.0 {
+                dist[i] = sum / weight + step_length;
+            }
+        }
+    }
+    dist
+}
+
+pub fn long_shadow(input: &ImageBuf, p: &ShadowParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+
+    let alpha: Vec<f32> = input
+        .data
+        .iter()
+        .map(|px| finite_or(px.alpha, 0.0).clamp(0.0, 1.0))
+        .collect();
+    let (sin, cos) = finite_or(p.direction, 0.0).to_radians().sin_cos();
+    // 主軸方向に 1 画素ずつ進む歩幅にする
+    let major = cos.abs().max(sin.abs());
+    let step = (cos / major, sin / major);
+    let length = finite_or(p.length, 0.0).clamp(0.0, MAX_LENGTH);
+    let steps = length * major;
+
+    // 影の被覆はシルエットの押し出しで、先端への減衰は最寄りのシルエットからの距離で決める
+    let coverage = extrude(&alpha, width, height, step, steps);
+    let falloff = finite_or(p.falloff, 0.0).clamp(0.0, 1.0);
+    let dist = if falloff > 0.0 && length > 0.0 {
+        upstream_distance(&alpha, width, height, step)
+    } else {
+        Vec::new()
+    };
+    let opacity = finite_or(p.opacity, 1.0).clamp(0.0, 1.0);
+    let color = p.color.map(|c| finite_or(c, 0.0));
+
+    for (i, dst) in out.data.iter_mut().enumerate() {
+        let fade = match dist.get(i) {
+            Some(&d) => 1.0 - falloff * (d / length).clamp(0.0, 1.0),
+            None => 1.0,
+        };
+        let shadow = coverage[i] * fade * opacity;
+        let src = input.data[i];
+        let a = if p.shadow_only { 0.0 } else { alpha[i] };
+        // レイヤーを影の上に重ねる (ストレートアルファ)
+        let out_alpha = a + shadow * (1.0 - a);
+        let mix = |c: f32, s: f32| {
+            if out_alpha > 0.0 {
+                (finite_or(c, 0.0) * a + s * shadow * (1.0 - a)) / out_alpha
+            } else {
+                0.0
+            }
+        };
+        *dst = PixelF32 {
+            red: mix(src.red, color[0]),
+            green: mix(src.green, color[1]),
+            blue: mix(src.blue, color[2]),
+            alpha: out_alpha,
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct LongShadow;
+
+impl RenderCore for LongShadow {
+    type Params = ShadowParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ShadowParams {
+            direction: rng.slider(-360.0, 360.0),
+            length: rng.slider(0.0, 500.0),
+            falloff: rng.unit(),
+            color: [rng.unit(), rng.unit(), rng.unit()],
+            opacity: rng.unit(),
+            shadow_only: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = long_shadow(input, params);
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<low_poly::LowPoly>();
+}
@@ -0,0 +1,621 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::HashMap;
+use std::env;
+
+use ae::pf::*;
+use noise::hash_unit;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::poisson::PoissonGrid;
+use utils::sampling::{self, Downsample};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    PointCount,
+    EdgeBias,
+    Seed,
+    Fill,
+    StrokeWidth,
+    StrokeColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Turns the layer into a low-poly triangle mosaic.";
+
+const MIN_POINTS: i32 = 16;
+const MAX_POINTS: i32 = 20000;
+const MAX_SEED: i32 = 10000;
+const MAX_STROKE: f32 = 50.0;
+/// Dart-throwing candidates per requested point.
+const CANDIDATES_PER_POINT: u32 = 8;
+/// Poisson disk radius at the strongest edge relative to flat areas, at full bias.
+const EDGE_RADIUS: f32 = 0.3;
+/// Share of the area a random Poisson disk set covers with disks of its radius.
+const PACKING: f32 = 0.7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fill {
+    /// Mean color of the pixels inside the triangle.
+    Average,
+    /// Color sampled at the triangle's centroid.
+    Centroid,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Point Count: target number of sample points over the layer
+        params.add(
+            Params::PointCount,
+            tr("Point Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_POINTS);
+                d.set_valid_max(MAX_POINTS);
+                d.set_slider_min(MIN_POINTS);
+                d.set_slider_max(5000);
+                d.set_default(800);
+            }),
+        )?;
+
+        // Edge Bias: how much denser points get along edges of the image
+        params.add(
+            Params::EdgeBias,
+            tr("Edge Bias"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.6);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(MAX_SEED);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        params.add(
+            Params::Fill,
+            tr("Fill"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Average"), tr("Centroid")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Stroke Width: triangle outlines in pixels (0 = none)
+        params.add(
+            Params::StrokeWidth,
+            tr("Stroke Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_STROKE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(5.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::StrokeColor,
+            tr("Stroke Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_LowPoly - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 線幅はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let stroke = params.get(Params::StrokeColor)?.as_color()?.float_value()?;
+
+        let settings = LowPolyParams {
+            point_count: params.get(Params::PointCount)?.as_slider()?.value() as u32,
+            edge_bias: params.get(Params::EdgeBias)?.as_float_slider()?.value() as f32,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            fill: match params.get(Params::Fill)?.as_popup()?.value() {
+                2 => Fill::Centroid,
+                _ => Fill::Average,
+            },
+            stroke_width: ds
+                .length(params.get(Params::StrokeWidth)?.as_float_slider()?.value() as f32),
+            stroke_color: [stroke.red, stroke.green, stroke.blue, stroke.alpha],
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        low_poly(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LowPolyParams {
+    pub point_count: u32,
+    pub edge_bias: f32,
+    pub seed: u32,
+    pub fill: Fill,
+    /// Stroke width in render pixels.
+    pub stroke_width: f32,
+    /// Straight stroke color with alpha.
+    pub stroke_color: [f32; 4],
+}
+
+/// Gradient magnitude of the luminance per pixel, normalized to 0..1.
+fn edge_strength(input: &ImageBuf) -> Vec<f32> {
+    let (w, h) = (input.width as isize, input.height as isize);
+    let lum = |x: isize, y: isize| finite_or(luminance(input.get_clamped(x, y)), 0.0);
+    let mut edges = Vec::with_capacity(input.data.len());
+    for y in 0..h {
+        for x in 0..w {
+            let gx = lum(x + 1, y) - lum(x - 1, y);
+            let gy = lum(x, y + 1) - lum(x, y - 1);
+            edges.push(gx.hypot(gy));
+        }
+    }
+    let max = edges.iter().copied().fold(0.0f32, f32::max);
+    if max > 1e-6 {
+        for e in edges.iter_mut() {
+            // 弱いエッジも拾えるよう平方根で持ち上げる
+            *e = (*e / max).sqrt();
+        }
+    }
+    edges
+}
+
+/// Edge-weighted Poisson disk points by dart throwing: candidates are hashed
+/// from the seed in normalized layer space (so the pattern survives a change
+/// of resolution) and accepted when no point lies within their local radius.
+/// The layer's corners and evenly spaced border points are always included.
+fn sample_points(input: &ImageBuf, p: &LowPolyParams) -> Vec<(f64, f64)> {
+    let (width, height) = (input.width as f32, input.height as f32);
+    let count = p.point_count.clamp(MIN_POINTS as u32, MAX_POINTS as u32);
+    let bias = finite_or(p.edge_bias, 0.0).clamp(0.0, 1.0);
+    let radius = (PACKING * width * height / count as f32).sqrt().max(1.0);
+    let min_radius = radius * (1.0 - (1.0 - EDGE_RADIUS) * bias);
+    let edges = edge_strength(input);
+
+    let mut points = Vec::with_capacity(count as usize + 64);
+    // 外周: 四隅と辺上の等間隔の点で凸包をレイヤーの矩形にする
+    let border = |len: f32| (len / radius).ceil().max(1.0) as usize;
+    let (nx, ny) = (border(width), border(height));
+    for i in 0..nx {
+        let x = width as f64 * i as f64 / nx as f64;
+        points.push((x, 0.0));
+        points.push((width as f64 - x, height as f64));
+    }
+    for i in 0..ny {
+        let y = height as f64 * i as f64 / ny as f64;
+        points.push((width as f64, y));
+        points.push((0.0, height as f64 - y));
+    }
+
+    // 近傍探索用の格子 (セルは最小半径)
+    let mut grid = PoissonGrid::new(width, height, min_radius);
+    for &(x, y) in &points {
+        grid.insert(x as f32, y as f32);
+    }
+
+    let border_points = points.len();
+    for i in 0..count * CANDIDATES_PER_POINT {
+        if points.len() - border_points >= count as usize {
+            break;
+        }
+        let u = hash_unit(p.seed, i, 0);
+        let v = hash_unit(p.seed, i, 1);
+        let (x, y) = (u * width, v * height);
+        let e = edges
+            [(y as usize).min(input.height - 1) * input.width + (x as usize).min(input.width - 1)];
+        let r = radius * (1.0 - (1.0 - EDGE_RADIUS) * bias * e);
+        if grid.is_free(x, y, r) {
+            grid.insert(x, y);
+            points.push((x as f64, y as f64));
+        }
+    }
+    points
+}
+
+/// Triangle of the incremental triangulation, counter-clockwise; `next[i]` is
+/// the neighbour across the edge opposite `v[i]`.
+#[derive(Clone, Copy, Debug)]
+struct Triangle {
+    v: [usize; 3],
+    next: [Option<usize>; 3],
+    alive: bool,
+}
+
+/// Twice the signed area of (a, b, c); positive when counter-clockwise.
+fn orient(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Whether `d` lies inside the circumcircle of the counter-clockwise (a, b, c).
+fn in_circumcircle(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let (ax, ay) = (a.0 - d.0, a.1 - d.1);
+    let (bx, by) = (b.0 - d.0, b.1 - d.1);
+    let (cx, cy) = (c.0 - d.0, c.1 - d.1);
+    let det = (ax * ax + ay * ay) * (bx * cy - cx * by) - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+    det > 0.0
+}
+
+/// Bowyer-Watson Delaunay triangulation. Each point is located by walking the
+/// neighbour links from the last inserted triangle, and its cavity is grown
+/// through the links, so no step scans the whole mesh. Returns the triangles as
+/// counter-clockwise vertex indices into `points`.
+fn delaunay(points: &[(f64, f64)]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for &(x, y) in points {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    // 全点を内包する十分大きな超三角形から始める
+    let size = (max_x - min_x).max(max_y - min_y).max(1.0) * 64.0;
+    let (mx, my) = ((min_x + max_x) * 0.5, (min_y + max_y) * 0.5);
+    let mut verts = points.to_vec();
+    verts.push((mx - size, my - size));
+    verts.push((mx + size, my - size));
+    verts.push((mx, my + size));
+    let mut tris = vec![Triangle {
+        v: [n, n + 1, n + 2],
+        next: [None; 3],
+        alive: true,
+    }];
+
+    let mut last = 0;
+    let mut in_cavity: Vec<bool> = Vec::new();
+    for (i, &p) in points.iter().enumerate() {
+        // 隣接をたどって p を含む三角形を探す
+        let mut t = last;
+        let mut guard = 0;
+        let found = loop {
+            let tri = tris[t];
+            let mut step = None;
+            for k in 0..3 {
+                let a = verts[tri.v[(k + 1) % 3]];
+                let b = verts[tri.v[(k + 2) % 3]];
+                if orient(a, b, p) < 0.0 {
+                    step = tri.next[k];
+                    break;
+                }
+            }
+            match step {
+                Some(s) => t = s,
+                None => break true,
+            }
+            guard += 1;
+            if guard > tris.len() {
+                break false;
+            }
+        };
+        if !found {
+            continue;
+        }
+
+        // 外接円に p を含む三角形を隣接から広げて空洞にする
+        in_cavity.resize(tris.len(), false);
+        let mut cavity = vec![t];
+        in_cavity[t] = true;
+        let mut k = 0;
+        while k < cavity.len() {
+            let tri = tris[cavity[k]];
+            for &nb in tri.next.iter().flatten() {
+                if in_cavity[nb] {
+                    continue;
+                }
+                let [a, b, c] = tris[nb].v.map(|v| verts[v]);
+                if in_circumcircle(a, b, c, p) {
+                    in_cavity[nb] = true;
+                    cavity.push(nb);
+                }
+            }
+            k += 1;
+        }
+
+        // 空洞の境界辺と p で新しい三角形を張る
+        let mut boundary = Vec::new();
+        for &c in &cavity {
+            let tri = tris[c];
+            for k in 0..3 {
+                let outer = tri.next[k];
+                if outer.is_none_or(|o| !in_cavity[o]) {
+                    boundary.push((tri.v[(k + 1) % 3], tri.v[(k + 2) % 3], outer));
+                }
+            }
+        }
+        for &c in &cavity {
+            tris[c].alive = false;
+            in_cavity[c] = false;
+        }
+        let first = tris.len();
+        let mut by_start = HashMap::with_capacity(boundary.len());
+        let mut by_end = HashMap::with_capacity(boundary.len());
+        for (j, &(a, b, outer)) in boundary.iter().enumerate() {
+            let id = first + j;
+            tris.push(Triangle {
+                v: [i, a, b],
+                next: [outer, None, None],
+                alive: true,
+            });
+            by_start.insert(a, id);
+            by_end.insert(b, id);
+            if let Some(o) = outer {
+                for s in 0..3 {
+                    let ot = tris[o];
+                    if ot.v[(s + 1) % 3] == b && ot.v[(s + 2) % 3] == a {
+                        tris[o].next[s] = Some(id);
+                    }
+                }
+            }
+        }
+        for (j, &(a, b, _)) in boundary.iter().enumerate() {
+            let id = first + j;
+            // (p, b) を共有するのは b から始まる三角形、(a, p) は a で終わる三角形
+            tris[id].next[1] = by_start.get(&b).copied();
+            tris[id].next[2] = by_end.get(&a).copied();
+        }
+        last = first;
+    }
+
+    tris.iter()
+        .filter(|t| t.alive && t.v.iter().all(|&v| v < n))
+        .map(|t| t.v)
+        .collect()
+}
+
+/// Distance from `p` to the segment `a`–`b`.
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.0 - a.0 - dx * t).hypot(p.1 - a.1 - dy * t)
+}
+
+fn low_poly(input: &ImageBuf, p: &LowPolyParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let points = sample_points(input, p);
+    let triangles = delaunay(&points);
+    let premul = sampling::premultiply(input);
+
+    // 画素中心を含む三角形を割り当てる (どれにも入らない画素は入力のまま)
+    let mut owner = vec![usize::MAX; width * height];
+    for (t, tri) in triangles.iter().enumerate() {
+        let [a, b, c] = tri.map(|v| points[v]);
+        let x0 = a.0.min(b.0).min(c.0).floor().max(0.0) as usize;
+        let y0 = a.1.min(b.1).min(c.1).floor().max(0.0) as usize;
+        let x1 = (a.0.max(b.0).max(c.0).ceil() as usize).min(width);
+        let y1 = (a.1.max(b.1).max(c.1).ceil() as usize).min(height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let q = (x as f64 + 0.5, y as f64 + 0.5);
+                if orient(a, b, q) >= 0.0 && orient(b, c, q) >= 0.0 && orient(c, a, q) >= 0.0 {
+                    owner[y * width + x] = t;
+                }
+            }
+        }
+    }
+
+    let colors: Vec<PixelF32> = match p.fill {
+        Fill::Average => {
+            let mut sums = vec![[0.0f32; 5]; triangles.len()];
+            for (i, &t) in owner.iter().enumerate() {
+                if t != usize::MAX {
+                    let px = premul.data[i];
+                    let s = &mut sums[t];
+                    s[0] += px.red;
+                    s[1] += px.green;
+                    s[2] += px.blue;
+                    s[3] += px.alpha;
+                    s[4] += 1.0;
+                }
+            }
+            sums.iter()
+                .zip(&triangles)
+                .map(|(s, tri)| {
+                    if s[4] > 0.0 {
+                        PixelF32 {
+                            red: s[0] / s[4],
+                            green: s[1] / s[4],
+                            blue: s[2] / s[4],
+                            alpha: s[3] / s[4],
+                        }
+                    } else {
+                        centroid_color(&premul, tri.map(|v| points[v]))
+                    }
+                })
+                .collect()
+        }
+        Fill::Centroid => triangles
+            .iter()
+            .map(|tri| centroid_color(&premul, tri.map(|v| points[v])))
+            .collect(),
+    };
+
+    let stroke_width = finite_or(p.stroke_width, 0.0).clamp(0.0, MAX_STROKE);
+    let stroke = p.stroke_color.map(|c| finite_or(c, 0.0).clamp(0.0, 1.0));
+    let stroke = PixelF32 {
+        red: stroke[0] * stroke[3],
+        green: stroke[1] * stroke[3],
+        blue: stroke[2] * stroke[3],
+        alpha: stroke[3],
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let t = owner[y * width + x];
+            if t == usize::MAX {
+                output.set(x, y, input.data[y * width + x]);
+                continue;
+            }
+            let mut px = colors[t];
+            if stroke_width > 0.0 {
+                // 自分の三角形の辺までの距離で線を描く (隣も同じ辺を描くので幅は両側の合計)
+                let q = (x as f32 + 0.5, y as f32 + 0.5);
+                let [a, b, c] = triangles[t].map(|v| (points[v].0 as f32, points[v].1 as f32));
+                let d = segment_distance(q, a, b)
+                    .min(segment_distance(q, b, c))
+                    .min(segment_distance(q, c, a));
+                let k = (stroke_width * 0.5 - d + 0.5).clamp(0.0, 1.0);
+                let over = |s: f32, b: f32| s * k + b * (1.0 - k * stroke.alpha);
+                px = PixelF32 {
+                    red: over(stroke.red, px.red),
+                    green: over(stroke.green, px.green),
+                    blue: over(stroke.blue, px.blue),
+                    alpha: over(stroke.alpha, px.alpha),
+                };
+            }
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Premultiplied color at the centroid of the triangle.
+fn centroid_color(premul: &ImageBuf, [a, b, c]: [(f64, f64); 3]) -> PixelF32 {
+    let x = (a.0 + b.0 + c.0) / 3.0;
+    let y = (a.1 + b.1 + c.1) / 3.0;
+    sampling::bilinear(premul, x as f32 - 0.5, y as f32 - 0.5)
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct LowPoly;
+
+impl RenderCore for LowPoly {
+    type Params = LowPolyParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        LowPolyParams {
+            point_count: rng.popup(MIN_POINTS, MAX_POINTS) as u32,
+            edge_bias: rng.slider(0.0, 1.0),
+            seed: rng.popup(0, MAX_SEED) as u32,
+            fill: if rng.chance(0.5) {
+                Fill::Average
+            } else {
+                Fill::Centroid
+            },
+            stroke_width: rng.slider(0.0, MAX_STROKE),
+            stroke_color: [rng.unit(), rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = low_poly(input, params);
+    }
+}
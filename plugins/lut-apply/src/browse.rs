@@ -0,0 +1,22 @@
+//! Native "open file" dialog for the `Browse` button.
+//!
+//! The SDK has no file dialog, so this opens the platform's own (common item
+//! dialog / `NSOpenPanel`) through `rfd`. The call blocks until the dialog
+//! closes, which is what AE expects of a modal dialog opened from a button.
+
+use std::path::PathBuf;
+
+/// Asks the user for a `.cube` file; `None` when cancelled or unavailable.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub fn pick_cube_file() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .set_title("Choose a .cube LUT")
+        .add_filter("Cube LUT", &["cube"])
+        .pick_file()
+}
+
+/// Asks the user for a `.cube` file; `None` when cancelled or unavailable.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn pick_cube_file() -> Option<PathBuf> {
+    None
+}
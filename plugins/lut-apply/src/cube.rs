@@ -0,0 +1,299 @@
+//! `.cube` LUT parsing (Adobe / Resolve flavours) and evaluation.
+//!
+//! A file holds a 1D table, a 3D table, or (Resolve) a 1D shaper followed by
+//! a 3D table. Table rows list red fastest, then green, then blue.
+
+use std::fmt;
+use std::path::Path;
+
+/// Largest accepted `LUT_1D_SIZE`.
+const MAX_1D_SIZE: usize = 65536;
+/// Largest accepted `LUT_3D_SIZE`.
+const MAX_3D_SIZE: usize = 256;
+
+#[derive(Debug)]
+pub struct CubeError {
+    /// 1-based line of the problem, or 0 for the file as a whole.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CubeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: {}", self.line, self.message)
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+fn error(line: usize, message: impl Into<String>) -> CubeError {
+    CubeError {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Per-channel input range a table covers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Domain {
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+impl Domain {
+    const UNIT: Self = Self {
+        min: [0.0; 3],
+        max: [1.0; 3],
+    };
+
+    /// `v` as a 0..1 position within the domain (clamped).
+    fn normalize(&self, v: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|c| {
+            let span = self.max[c] - self.min[c];
+            if span > 0.0 {
+                ((v[c] - self.min[c]) / span).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Table1d {
+    domain: Domain,
+    rows: Vec<[f32; 3]>,
+}
+
+impl Table1d {
+    fn apply(&self, v: [f32; 3]) -> [f32; 3] {
+        let n = self.rows.len();
+        let t = self.domain.normalize(v);
+        std::array::from_fn(|c| {
+            let x = t[c] * (n - 1) as f32;
+            let i = (x as usize).min(n - 2);
+            let f = x - i as f32;
+            self.rows[i][c] + (self.rows[i + 1][c] - self.rows[i][c]) * f
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Table3d {
+    domain: Domain,
+    size: usize,
+    rows: Vec<[f32; 3]>,
+}
+
+impl Table3d {
+    fn at(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.rows[(b * self.size + g) * self.size + r]
+    }
+
+    /// Tetrahedral interpolation: the lattice cube is split into six
+    /// tetrahedra along its gray diagonal, so neutrals stay neutral.
+    fn apply(&self, v: [f32; 3]) -> [f32; 3] {
+        let n = self.size;
+        let t = self.domain.normalize(v);
+        let cell = t.map(|t| {
+            let x = t * (n - 1) as f32;
+            let i = (x as usize).min(n - 2);
+            (i, x - i as f32)
+        });
+        let [(r, fr), (g, fg), (b, fb)] = cell;
+        let c000 = self.at(r, g, b);
+        let c111 = self.at(r + 1, g + 1, b + 1);
+        // 大きい順に辿る頂点: 各軸の重みの大小で 6 つの四面体から選ぶ
+        let (c1, c2, w) = if fr >= fg {
+            if fg >= fb {
+                (self.at(r + 1, g, b), self.at(r + 1, g + 1, b), [fr, fg, fb])
+            } else if fr >= fb {
+                (self.at(r + 1, g, b), self.at(r + 1, g, b + 1), [fr, fb, fg])
+            } else {
+                (self.at(r, g, b + 1), self.at(r + 1, g, b + 1), [fb, fr, fg])
+            }
+        } else if fb >= fg {
+            (self.at(r, g, b + 1), self.at(r, g + 1, b + 1), [fb, fg, fr])
+        } else if fb >= fr {
+            (self.at(r, g + 1, b), self.at(r, g + 1, b + 1), [fg, fb, fr])
+        } else {
+            (self.at(r, g + 1, b), self.at(r + 1, g + 1, b), [fg, fr, fb])
+        };
+        std::array::from_fn(|c| {
+            c000[c] + (c1[c] - c000[c]) * w[0] + (c2[c] - c1[c]) * w[1] + (c111[c] - c2[c]) * w[2]
+        })
+    }
+}
+
+/// A parsed `.cube` file.
+#[derive(Clone, Debug)]
+pub struct Lut {
+    pub title: Option<String>,
+    shaper: Option<Table1d>,
+    cube: Option<Table3d>,
+}
+
+impl Lut {
+    pub fn load(path: &Path) -> Result<Self, CubeError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| error(0, format!("cannot read {}: {e}", path.display())))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CubeError> {
+        let mut title = None;
+        let (mut size_1d, mut size_3d) = (None, None);
+        let mut domain = Domain::UNIT;
+        let (mut range_1d, mut range_3d) = (None, None);
+        let mut rows: Vec<[f32; 3]> = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = index + 1;
+            let content = raw.split('#').next().unwrap_or("").trim();
+            if content.is_empty() {
+                continue;
+            }
+            let mut words = content.split_whitespace();
+            let keyword = words.next().unwrap_or("");
+            let rest: Vec<&str> = words.collect();
+            let numbers = |count: usize| -> Result<Vec<f32>, CubeError> {
+                if rest.len() != count {
+                    return Err(error(line, format!("{keyword} expects {count} values")));
+                }
+                rest.iter()
+                    .map(|w| {
+                        w.parse::<f32>()
+                            .ok()
+                            .filter(|v| v.is_finite())
+                            .ok_or_else(|| error(line, format!("invalid number \"{w}\"")))
+                    })
+                    .collect()
+            };
+            let size = |max: usize| -> Result<usize, CubeError> {
+                let n = rest
+                    .first()
+                    .and_then(|w| w.parse::<usize>().ok())
+                    .filter(|_| rest.len() == 1)
+                    .ok_or_else(|| error(line, format!("{keyword} expects one integer")))?;
+                if !(2..=max).contains(&n) {
+                    return Err(error(line, format!("{keyword} must be 2..{max}")));
+                }
+                Ok(n)
+            };
+            let keyword_after_data = || {
+                if rows.is_empty() {
+                    Ok(())
+                } else {
+                    Err(error(line, format!("{keyword} after table data")))
+                }
+            };
+
+            match keyword {
+                "TITLE" => {
+                    keyword_after_data()?;
+                    let t = content["TITLE".len()..].trim().trim_matches('"');
+                    title = Some(t.to_string());
+                }
+                "LUT_1D_SIZE" => {
+                    keyword_after_data()?;
+                    size_1d = Some(size(MAX_1D_SIZE)?);
+                }
+                "LUT_3D_SIZE" => {
+                    keyword_after_data()?;
+                    size_3d = Some(size(MAX_3D_SIZE)?);
+                }
+                "DOMAIN_MIN" => {
+                    keyword_after_data()?;
+                    let v = numbers(3)?;
+                    domain.min = [v[0], v[1], v[2]];
+                }
+                "DOMAIN_MAX" => {
+                    keyword_after_data()?;
+                    let v = numbers(3)?;
+                    domain.max = [v[0], v[1], v[2]];
+                }
+                // Resolve: 同じ範囲を全チャンネルに使う
+                "LUT_1D_INPUT_RANGE" => {
+                    keyword_after_data()?;
+                    let v = numbers(2)?;
+                    range_1d = Some(Domain {
+                        min: [v[0]; 3],
+                        max: [v[1]; 3],
+                    });
+                }
+                "LUT_3D_INPUT_RANGE" => {
+                    keyword_after_data()?;
+                    let v = numbers(2)?;
+                    range_3d = Some(Domain {
+                        min: [v[0]; 3],
+                        max: [v[1]; 3],
+                    });
+                }
+                _ if keyword
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.')) =>
+                {
+                    let mut values = vec![keyword];
+                    values.extend(&rest);
+                    if values.len() != 3 {
+                        return Err(error(line, "table rows need 3 values"));
+                    }
+                    let mut row = [0.0; 3];
+                    for (dst, w) in row.iter_mut().zip(values) {
+                        *dst = w
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|v| v.is_finite())
+                            .ok_or_else(|| error(line, format!("invalid number \"{w}\"")))?;
+                    }
+                    rows.push(row);
+                }
+                // 未知のキーワードは他のアプリ拡張として無視する
+                _ => {}
+            }
+        }
+
+        if size_1d.is_none() && size_3d.is_none() {
+            return Err(error(0, "missing LUT_1D_SIZE or LUT_3D_SIZE"));
+        }
+        for (min, max) in domain.min.iter().zip(&domain.max) {
+            if min >= max {
+                return Err(error(0, "DOMAIN_MIN must be below DOMAIN_MAX"));
+            }
+        }
+        let expected = size_1d.unwrap_or(0) + size_3d.map_or(0, |n| n * n * n);
+        if rows.len() != expected {
+            return Err(error(
+                0,
+                format!("expected {expected} table rows, found {}", rows.len()),
+            ));
+        }
+
+        let cube_rows = rows.split_off(size_1d.unwrap_or(0));
+        Ok(Self {
+            title,
+            shaper: size_1d.map(|_| Table1d {
+                domain: range_1d.unwrap_or(domain),
+                rows,
+            }),
+            cube: size_3d.map(|size| Table3d {
+                // シェーパーの後段の 3D は既定で 0..1 を受け取る
+                domain: range_3d.unwrap_or(if size_1d.is_some() {
+                    Domain::UNIT
+                } else {
+                    domain
+                }),
+                size,
+                rows: cube_rows,
+            }),
+        })
+    }
+
+    pub fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let rgb = self.shaper.as_ref().map_or(rgb, |t| t.apply(rgb));
+        self.cube.as_ref().map_or(rgb, |t| t.apply(rgb))
+    }
+}
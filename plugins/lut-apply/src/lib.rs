@@ -0,0 +1,407 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+
+mod browse;
+mod cube;
+mod strings;
+
+pub use cube::{CubeError, Lut};
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    LutFile,
+    InputTransfer,
+    OutputTransfer,
+    Strength,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: the chosen LUT file, saved with the project.
+#[derive(Default)]
+struct Instance {
+    path: Option<PathBuf>,
+    /// Parsed table, `None` until a file loads successfully.
+    lut: Option<Arc<Lut>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Applies a .cube LUT file to the layer.";
+
+/// Sequence data layout version (UTF-8 path bytes).
+const SEQUENCE_VERSION: u16 = 1;
+
+const TRANSFER_OPTIONS: [&str; 5] = ["None", "Linear", "Rec.709", "Gamma 2.2", "Gamma 2.4"];
+
+/// Encoding curve on one side of the LUT. Layer values are treated as sRGB
+/// (see `utils::color`); `None` hands them to the LUT unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    None,
+    Linear,
+    Rec709,
+    Gamma22,
+    Gamma24,
+}
+
+impl Transfer {
+    fn from_popup(value: i32) -> Self {
+        match value {
+            2 => Transfer::Linear,
+            3 => Transfer::Rec709,
+            4 => Transfer::Gamma22,
+            5 => Transfer::Gamma24,
+            _ => Transfer::None,
+        }
+    }
+
+    /// Linear light -> this encoding (mirrored for negative values).
+    fn encode(self, v: f32) -> f32 {
+        let a = v.abs();
+        let e = match self {
+            Transfer::None | Transfer::Linear => a,
+            Transfer::Rec709 => {
+                if a < 0.018 {
+                    4.5 * a
+                } else {
+                    1.099 * a.powf(0.45) - 0.099
+                }
+            }
+            Transfer::Gamma22 => a.powf(1.0 / 2.2),
+            Transfer::Gamma24 => a.powf(1.0 / 2.4),
+        };
+        e.copysign(v)
+    }
+
+    /// This encoding -> linear light.
+    fn decode(self, v: f32) -> f32 {
+        let a = v.abs();
+        let l = match self {
+            Transfer::None | Transfer::Linear => a,
+            Transfer::Rec709 => {
+                if a < 0.081 {
+                    a / 4.5
+                } else {
+                    ((a + 0.099) / 1.099).powf(1.0 / 0.45)
+                }
+            }
+            Transfer::Gamma22 => a.powf(2.2),
+            Transfer::Gamma24 => a.powf(2.4),
+        };
+        l.copysign(v)
+    }
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // LUT File: opens a file dialog; the path is kept in sequence data
+        params.add_with_flags(
+            Params::LutFile,
+            tr("LUT File"),
+            ButtonDef::setup(|d| {
+                d.set_label(tr("Browse..."));
+            }),
+            ParamFlag::SUPERVISE | ParamFlag::CANNOT_TIME_VARY,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Input Transfer: encoding the LUT expects its input in
+        params.add(
+            Params::InputTransfer,
+            tr("Input Transfer"),
+            PopupDef::setup(|d| {
+                d.set_options(&TRANSFER_OPTIONS);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Output Transfer: encoding the LUT writes its output in
+        params.add(
+            Params::OutputTransfer,
+            tr("Output Transfer"),
+            PopupDef::setup(|d| {
+                d.set_options(&TRANSFER_OPTIONS);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_LutApply - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SequenceDataNeedsFlattening, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // LUT 本体は保存せず、パスだけ保存して開くときに読み直す
+        let bytes = self
+            .path
+            .as_ref()
+            .map(|p| p.to_string_lossy().into_owned().into_bytes())
+            .unwrap_or_default();
+        Ok((SEQUENCE_VERSION, bytes))
+    }
+
+    fn unflatten(version: u16, serialized: &[u8]) -> Result<Self, Error> {
+        if version != SEQUENCE_VERSION || serialized.is_empty() {
+            return Ok(Self::default());
+        }
+        let path = PathBuf::from(String::from_utf8_lossy(serialized).into_owned());
+        // ファイルが移動されていても読み込みは失敗扱いにせず素通しにする
+        let lut = Lut::load(&path).ok().map(Arc::new);
+        Ok(Self {
+            path: Some(path),
+            lut,
+        })
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(&input, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::UserChangedParam { param_index }
+                if plugin.params.type_at(param_index) == Params::LutFile =>
+            {
+                if let Some(path) = browse::pick_cube_file() {
+                    match Lut::load(&path) {
+                        Ok(lut) => {
+                            self.path = Some(path);
+                            self.lut = Some(Arc::new(lut));
+                            plugin.out_data.set_out_flag(OutFlags::ForceRerender, true);
+                        }
+                        Err(e) => {
+                            plugin.out_data.set_error_msg(&format!("AOD_LutApply: {e}"));
+                        }
+                    }
+                }
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let input = cb
+                    .checkout_layer_pixels(0)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+
+                if let Some(input) = input
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(&input, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        input: &ImageBuf,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = LutApplyParams {
+            lut: self.lut.clone(),
+            input_transfer: Transfer::from_popup(
+                params.get(Params::InputTransfer)?.as_popup()?.value(),
+            ),
+            output_transfer: Transfer::from_popup(
+                params.get(Params::OutputTransfer)?.as_popup()?.value(),
+            ),
+            strength: params.get(Params::Strength)?.as_float_slider()?.value() as f32 / 100.0,
+        };
+
+        apply_lut(input, &settings).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LutApplyParams {
+    /// Without a LUT the layer passes through.
+    pub lut: Option<Arc<Lut>>,
+    pub input_transfer: Transfer,
+    pub output_transfer: Transfer,
+    /// 0..1 blend from the input to the LUT result.
+    pub strength: f32,
+}
+
+pub fn apply_lut(input: &ImageBuf, p: &LutApplyParams) -> ImageBuf {
+    let mut out = input.clone();
+    let strength = finite_or(p.strength, 1.0).clamp(0.0, 1.0);
+    let Some(lut) = p.lut.as_deref() else {
+        return out;
+    };
+    if strength <= 0.0 {
+        return out;
+    }
+
+    for px in out.data.iter_mut() {
+        let rgb = [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0));
+        let lut_in = rgb.map(|c| match p.input_transfer {
+            Transfer::None => c,
+            t => t.encode(srgb_to_linear(c)),
+        });
+        let lut_out = lut.apply(lut_in).map(|c| match p.output_transfer {
+            Transfer::None => c,
+            t => linear_to_srgb(t.decode(c)),
+        });
+        let mixed: [f32; 3] =
+            std::array::from_fn(|c| finite_or(rgb[c] + (lut_out[c] - rgb[c]) * strength, 0.0));
+        (px.red, px.green, px.blue) = (mixed[0], mixed[1], mixed[2]);
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness cannot browse for files, so each parameter set carries a
+/// random `.cube` text (1D, 3D or shaper + 3D) parsed like a loaded file.
+pub struct LutApply;
+
+fn random_cube(rng: &mut Rng) -> String {
+    let mut text = String::from("TITLE \"harness\"\n# random table\n");
+    let kind = rng.popup(1, 3);
+    let size_1d = rng.int(2, 64) as usize;
+    let size_3d = rng.int(2, 17) as usize;
+    if kind != 2 {
+        text += &format!("LUT_1D_SIZE {size_1d}\n");
+    }
+    if kind != 1 {
+        text += &format!("LUT_3D_SIZE {size_3d}\n");
+    }
+    if rng.chance(0.3) {
+        text += &format!(
+            "DOMAIN_MIN {} {} {}\nDOMAIN_MAX {} {} {}\n",
+            rng.slider(-1.0, 0.0),
+            rng.slider(-1.0, 0.0),
+            rng.slider(-1.0, 0.0),
+            rng.slider(0.5, 4.0),
+            rng.slider(0.5, 4.0),
+            rng.slider(0.5, 4.0),
+        );
+    }
+    let rows = if kind != 2 { size_1d } else { 0 } + if kind != 1 { size_3d.pow(3) } else { 0 };
+    for _ in 0..rows {
+        text += &format!(
+            "{} {} {}\n",
+            rng.slider(-0.5, 1.5),
+            rng.slider(-0.5, 1.5),
+            rng.slider(-0.5, 1.5)
+        );
+    }
+    text
+}
+
+impl RenderCore for LutApply {
+    type Params = LutApplyParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let lut = if rng.chance(0.9) {
+            let text = random_cube(rng);
+            Some(Arc::new(
+                Lut::parse(&text).expect("harness cube should parse"),
+            ))
+        } else {
+            None
+        };
+        LutApplyParams {
+            lut,
+            input_transfer: Transfer::from_popup(rng.popup(1, 5)),
+            output_transfer: Transfer::from_popup(rng.popup(1, 5)),
+            strength: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = apply_lut(input, params);
+    }
+}
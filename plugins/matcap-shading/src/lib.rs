@@ -0,0 +1,403 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::normal::{self, NormalFormat};
+use utils::sampling;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NormalLayer,
+    NormalFormat,
+    MatcapLayer,
+    Rotation,
+    Intensity,
+    BlendMode,
+    Mix,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Shades a normal map with a matcap layer.";
+
+/// Checkout id of the normal map layer (the input uses 0).
+const NORMAL_CHECKOUT_ID: i32 = 1;
+/// Checkout id of the matcap layer.
+const MATCAP_CHECKOUT_ID: i32 = 2;
+/// Fraction of the matcap disc used, keeping lookups off its anti-aliased rim.
+const RIM_INSET: f32 = 0.98;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The matcap color replaces the layer.
+    Replace,
+    Multiply,
+    Screen,
+    Overlay,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Normal Layer: without one the layer itself is read as the normal map
+        params.add(Params::NormalLayer, tr("Normal Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::NormalFormat,
+            tr("Normal Format"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("OpenGL (Y+)"), tr("DirectX (Y-)")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Matcap Layer: lit sphere filling its centered square; without one a clay matcap is used
+        params.add(Params::MatcapLayer, tr("Matcap Layer"), LayerDef::new())?;
+
+        // Rotation: turns the matcap around the view axis
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Intensity,
+            tr("Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(400.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BlendMode,
+            tr("Blend Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Replace"), tr("Multiply"), tr("Screen"), tr("Overlay")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Mix: amount of the blended result over the original
+        params.add(
+            Params::Mix,
+            tr("Mix"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_MatcapShading - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では法線・マットキャップレイヤーをパラメータから直接取得する
+                let mut layer = |id| -> Result<Option<ImageBuf>, Error> {
+                    Ok(params
+                        .checkout_at(
+                            id,
+                            Some(in_data.current_time()),
+                            Some(in_data.time_step()),
+                            Some(in_data.time_scale()),
+                        )?
+                        .as_layer()?
+                        .value()
+                        .map(|layer| ImageBuf::from_layer(&layer)))
+                };
+                let normals = layer(Params::NormalLayer)?;
+                let matcap = layer(Params::MatcapLayer)?;
+                let layers = Layers {
+                    normals: normals.as_ref(),
+                    matcap: matcap.as_ref(),
+                };
+                self.do_render(in_layer, layers, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                for (id, checkout_id) in [
+                    (Params::NormalLayer, NORMAL_CHECKOUT_ID),
+                    (Params::MatcapLayer, MATCAP_CHECKOUT_ID),
+                ] {
+                    if let Some(index) = params.index(id) {
+                        let _ = extra.callbacks().checkout_layer(
+                            index as i32,
+                            checkout_id,
+                            &req,
+                            in_data.current_time(),
+                            in_data.time_step(),
+                            in_data.time_scale(),
+                        );
+                    }
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let normals = cb
+                    .checkout_layer_pixels(NORMAL_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let matcap = cb
+                    .checkout_layer_pixels(MATCAP_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    let layers = Layers {
+                        normals: normals.as_ref(),
+                        matcap: matcap.as_ref(),
+                    };
+                    self.do_render(in_layer, layers, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(NORMAL_CHECKOUT_ID as u32)?;
+                cb.checkin_layer_pixels(MATCAP_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        layers: Layers,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = MatcapParams {
+            normal_format: match params.get(Params::NormalFormat)?.as_popup()?.value() {
+                2 => NormalFormat::DirectX,
+                _ => NormalFormat::OpenGl,
+            },
+            rotation: params.get(Params::Rotation)?.as_angle()?.value(),
+            intensity: slider(Params::Intensity)? / 100.0,
+            blend_mode: match params.get(Params::BlendMode)?.as_popup()?.value() {
+                2 => BlendMode::Multiply,
+                3 => BlendMode::Screen,
+                4 => BlendMode::Overlay,
+                _ => BlendMode::Replace,
+            },
+            mix: slider(Params::Mix)? / 100.0,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        shade(&input, layers, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Optional normal map and matcap layers.
+#[derive(Clone, Copy, Default)]
+pub struct Layers<'a> {
+    pub normals: Option<&'a ImageBuf>,
+    pub matcap: Option<&'a ImageBuf>,
+}
+
+#[derive(Debug)]
+pub struct MatcapParams {
+    pub normal_format: NormalFormat,
+    /// Degrees, clockwise on screen.
+    pub rotation: f32,
+    /// 0..4.
+    pub intensity: f32,
+    pub blend_mode: BlendMode,
+    /// 0..1.
+    pub mix: f32,
+}
+
+type Vec3 = [f32; 3];
+
+const FLAT: Vec3 = [0.0, 0.0, 1.0];
+
+/// Built-in clay matcap: a warm grey sphere with an upper-left key light,
+/// a soft highlight and a faint rim.
+fn clay(n: Vec3) -> Vec3 {
+    const KEY: Vec3 = [-0.48, 0.58, 0.66];
+    const HALF: Vec3 = [-0.26, 0.32, 0.91];
+    let dot = |a: Vec3, b: Vec3| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let diffuse = dot(n, KEY).max(0.0);
+    let highlight = dot(n, HALF).max(0.0).powi(40) * 0.35;
+    let rim = (1.0 - n[2].clamp(0.0, 1.0)).powi(3) * 0.2;
+    [0.78, 0.74, 0.69].map(|c| c * (0.18 + 0.82 * diffuse) + highlight + rim)
+}
+
+fn blend(base: f32, top: f32, mode: BlendMode) -> f32 {
+    match mode {
+        BlendMode::Replace => top,
+        BlendMode::Multiply => base * top,
+        BlendMode::Screen => base + top - base * top,
+        BlendMode::Overlay => {
+            if base <= 0.5 {
+                2.0 * base * top
+            } else {
+                1.0 - 2.0 * (1.0 - base) * (1.0 - top)
+            }
+        }
+    }
+}
+
+pub fn shade(input: &ImageBuf, layers: Layers, p: &MatcapParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+    let normals = layers.normals.unwrap_or(input);
+    let matcap = layers
+        .matcap
+        .filter(|m| !m.is_empty())
+        .map(sampling::premultiply);
+    let intensity = finite_or(p.intensity, 1.0).clamp(0.0, 4.0);
+    let mix = finite_or(p.mix, 1.0).clamp(0.0, 1.0);
+    // 画面座標 (y 下向き) で時計回りに回すので、参照は逆回転させる
+    let (sin, cos) = finite_or(p.rotation, 0.0).to_radians().sin_cos();
+
+    for y in 0..h {
+        for x in 0..w {
+            let src = input.data[y * w + x];
+            let n = normal::decode(normals.get(x as isize, y as isize), p.normal_format)
+                .unwrap_or(FLAT);
+            let (sx, sy) = (n[0], -n[1]);
+            let (sx, sy) = (cos * sx + sin * sy, -sin * sx + cos * sy);
+
+            let color = match &matcap {
+                Some(m) => {
+                    // 中央の正方形に内接する球として参照する
+                    let side = m.width.min(m.height) as f32;
+                    let u = (m.width as f32 - side) * 0.5 + (0.5 + 0.5 * RIM_INSET * sx) * side;
+                    let v = (m.height as f32 - side) * 0.5 + (0.5 + 0.5 * RIM_INSET * sy) * side;
+                    let s = sampling::unpremultiply(sampling::bilinear(m, u - 0.5, v - 0.5));
+                    [s.red, s.green, s.blue]
+                }
+                None => clay([sx, -sy, n[2]]),
+            };
+
+            let base = [src.red, src.green, src.blue].map(|c| finite_or(c, 0.0));
+            let mut rgb = [0.0f32; 3];
+            for k in 0..3 {
+                let top = finite_or(color[k], 0.0).max(0.0) * intensity;
+                rgb[k] = base[k] + (blend(base[k], top, p.blend_mode) - base[k]) * mix;
+            }
+            out.data[y * w + x] = PixelF32 {
+                red: rgb[0],
+                green: rgb[1],
+                blue: rgb[2],
+                alpha: finite_or(src.alpha, 0.0).clamp(0.0, 1.0),
+            };
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so the input is its own normal map and
+/// odd frames also use it as the matcap.
+pub struct MatcapShading;
+
+impl RenderCore for MatcapShading {
+    type Params = MatcapParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        MatcapParams {
+            normal_format: if rng.chance(0.5) {
+                NormalFormat::OpenGl
+            } else {
+                NormalFormat::DirectX
+            },
+            rotation: rng.slider(-360.0, 360.0),
+            intensity: rng.slider(0.0, 4.0),
+            blend_mode: match rng.popup(1, 4) {
+                2 => BlendMode::Multiply,
+                3 => BlendMode::Screen,
+                4 => BlendMode::Overlay,
+                _ => BlendMode::Replace,
+            },
+            mix: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let layers = Layers {
+            normals: None,
+            matcap: (frame % 2 == 1).then_some(input),
+        };
+        *output = shade(input, layers, params);
+    }
+}
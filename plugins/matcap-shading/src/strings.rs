@@ -0,0 +1,15 @@
+// @generated by `cargo xtask localize` from locales/*.toml. Do not edit by hand.
+
+use utils::i18n::{self, Language};
+
+#[rustfmt::skip]
+const JA: &[(&str, &str)] = &[
+];
+
+/// Localized form of a user-visible English string.
+pub fn tr(text: &'static str) -> &'static str {
+    match i18n::current() {
+        Language::Ja => i18n::lookup(JA, text),
+        _ => text,
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<moire_pattern::MoirePattern>();
+}
@@ -0,0 +1,523 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::TAU;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::i18n;
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Combine,
+    Output,
+    Grating1Type,
+    Grating1Waveform,
+    Grating1Frequency,
+    Grating1Angle,
+    Grating1Center,
+    Grating1Phase,
+    Grating2Type,
+    Grating2Waveform,
+    Grating2Frequency,
+    Grating2Angle,
+    Grating2Center,
+    Grating2Phase,
+    Grating3Type,
+    Grating3Waveform,
+    Grating3Frequency,
+    Grating3Angle,
+    Grating3Center,
+    Grating3Phase,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Generates moiré patterns from overlapping gratings.";
+
+/// Per-grating parameters: type, waveform, frequency, angle, center, phase.
+const GRATING_PARAMS: [[Params; 6]; 3] = [
+    [
+        Params::Grating1Type,
+        Params::Grating1Waveform,
+        Params::Grating1Frequency,
+        Params::Grating1Angle,
+        Params::Grating1Center,
+        Params::Grating1Phase,
+    ],
+    [
+        Params::Grating2Type,
+        Params::Grating2Waveform,
+        Params::Grating2Frequency,
+        Params::Grating2Angle,
+        Params::Grating2Center,
+        Params::Grating2Phase,
+    ],
+    [
+        Params::Grating3Type,
+        Params::Grating3Waveform,
+        Params::Grating3Frequency,
+        Params::Grating3Angle,
+        Params::Grating3Center,
+        Params::Grating3Phase,
+    ],
+];
+/// Upper bound of `Frequency` (cycles per 100 full-resolution pixels).
+const MAX_FREQUENCY: f32 = 1000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combine {
+    /// Product of the gratings, like stacked transparencies.
+    Multiply,
+    Average,
+    /// Absolute difference, folded over the gratings in order.
+    Difference,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Combined gratings in gray.
+    Luma,
+    /// Gratings 1 / 2 / 3 in red / green / blue, 50 % gray where off.
+    Channels,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GratingType {
+    Off,
+    /// Straight stripes across `angle`.
+    Lines,
+    /// Concentric rings around `center`.
+    Circles,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    /// Half on, half off.
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Grating {
+    pub kind: GratingType,
+    pub waveform: Waveform,
+    /// Render pixels per cycle.
+    pub period: f32,
+    /// Degrees, clockwise on screen; stripes run perpendicular to it.
+    pub angle: f32,
+    /// Ring center in render pixels.
+    pub center: (f32, f32),
+    /// Cycles.
+    pub phase: f32,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Combine,
+            tr("Combine"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Multiply"), tr("Average"), tr("Difference")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Output: Per Grating (RGB) keeps each grating in its own channel for displacement
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Luma"), tr("Per Grating (RGB)")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Gratings: Off hides the grating's controls, Lines its center, Circles its angle
+        for (n, [kind, waveform, frequency, angle, center, phase]) in
+            GRATING_PARAMS.into_iter().enumerate()
+        {
+            params.add_with_flags(
+                kind,
+                &i18n::fill(tr("Grating {} Type"), n + 1),
+                PopupDef::setup(|d| {
+                    d.set_options(&[tr("Off"), tr("Lines"), tr("Circles")]);
+                    d.set_default(if n < 2 { 2 } else { 1 });
+                }),
+                ParamFlag::SUPERVISE,
+                ParamUIFlags::empty(),
+            )?;
+
+            params.add(
+                waveform,
+                &i18n::fill(tr("Grating {} Waveform"), n + 1),
+                PopupDef::setup(|d| {
+                    d.set_options(&[tr("Sine"), tr("Square"), tr("Triangle"), tr("Sawtooth")]);
+                    d.set_default(1);
+                }),
+            )?;
+
+            // Frequency: cycles per 100 pixels
+            params.add(
+                frequency,
+                &i18n::fill(tr("Grating {} Frequency"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(0.1);
+                    d.set_valid_max(MAX_FREQUENCY);
+                    d.set_slider_min(1.0);
+                    d.set_slider_max(50.0);
+                    d.set_default(10.0);
+                    d.set_precision(2);
+                }),
+            )?;
+
+            params.add(
+                angle,
+                &i18n::fill(tr("Grating {} Angle"), n + 1),
+                AngleDef::setup(|d| {
+                    d.set_default(if n == 1 { 5.0 } else { 0.0 });
+                }),
+            )?;
+
+            params.add(
+                center,
+                &i18n::fill(tr("Grating {} Center"), n + 1),
+                PointDef::setup(|d| {
+                    d.set_default((50.0, 50.0));
+                }),
+            )?;
+
+            // Phase: shifts the stripes by a fraction of a cycle, one turn = one cycle
+            params.add(
+                phase,
+                &i18n::fill(tr("Grating {} Phase"), n + 1),
+                AngleDef::setup(|d| {
+                    d.set_default(0.0);
+                }),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_MoirePattern - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if GRATING_PARAMS
+                    .iter()
+                    .any(|p| p[0] == params.type_at(param_index)) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer: _,
+                out_layer,
+            } => {
+                self.do_render(in_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let Some(out_layer) = out_layer_opt {
+                    self.do_render(in_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn grating_type(params: &Parameters<Params>, id: Params) -> Result<GratingType, Error> {
+    Ok(match params.get(id)?.as_popup()?.value() {
+        2 => GratingType::Lines,
+        3 => GratingType::Circles,
+        _ => GratingType::Off,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let mut visibility = Vec::new();
+    for [kind, waveform, frequency, angle, center, phase] in GRATING_PARAMS {
+        let kind = grating_type(params, kind)?;
+        let on = kind != GratingType::Off;
+        visibility.extend([
+            (waveform, on),
+            (frequency, on),
+            (angle, kind == GratingType::Lines),
+            (center, kind == GratingType::Circles),
+            (phase, on),
+        ]);
+    }
+    for (id, visible) in visibility {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 周波数と中心はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let mut gratings = Vec::with_capacity(GRATING_PARAMS.len());
+        for [kind, waveform, frequency, angle, center, phase] in GRATING_PARAMS {
+            let frequency = params.get(frequency)?.as_float_slider()?.value() as f32;
+            gratings.push(Grating {
+                kind: grating_type(params, kind)?,
+                waveform: match params.get(waveform)?.as_popup()?.value() {
+                    2 => Waveform::Square,
+                    3 => Waveform::Triangle,
+                    4 => Waveform::Sawtooth,
+                    _ => Waveform::Sine,
+                },
+                period: ds.length(100.0 / frequency.max(0.1)),
+                angle: params.get(angle)?.as_angle()?.value(),
+                center: ds.point(params.get(center)?.as_point()?.value()),
+                phase: params.get(phase)?.as_angle()?.value() / 360.0,
+            });
+        }
+        let settings = MoireParams {
+            combine: match params.get(Params::Combine)?.as_popup()?.value() {
+                2 => Combine::Average,
+                3 => Combine::Difference,
+                _ => Combine::Multiply,
+            },
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::Channels,
+                _ => Output::Luma,
+            },
+            gratings,
+        };
+
+        let (width, height) = (out_layer.width(), out_layer.height());
+        moire(width, height, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct MoireParams {
+    pub combine: Combine,
+    pub output: Output,
+    pub gratings: Vec<Grating>,
+}
+
+/// Integral of the 0..1 `waveform` from 0 to `t` (cycles).
+fn antiderivative(waveform: Waveform, t: f32) -> f32 {
+    let (whole, f) = (t.floor(), t - t.floor());
+    // 1 周期の積分はどの波形も 0.5
+    whole * 0.5
+        + match waveform {
+            Waveform::Sine => 0.5 * f - (TAU * f).sin() / (2.0 * TAU),
+            Waveform::Square => f.min(0.5),
+            Waveform::Triangle => {
+                if f < 0.5 {
+                    f * f
+                } else {
+                    2.0 * f - f * f - 0.5
+                }
+            }
+            Waveform::Sawtooth => 0.5 * f * f,
+        }
+}
+
+/// `waveform` at `t` box-filtered over `width` cycles, so gratings finer than a
+/// pixel settle to their mean instead of aliasing.
+fn filtered(waveform: Waveform, t: f32, width: f32) -> f32 {
+    if width < 1e-4 {
+        let f = t - t.floor();
+        return match waveform {
+            Waveform::Sine => 0.5 - 0.5 * (TAU * f).cos(),
+            Waveform::Square => (f < 0.5) as u8 as f32,
+            Waveform::Triangle => 1.0 - (2.0 * f - 1.0).abs(),
+            Waveform::Sawtooth => f,
+        };
+    }
+    // 整数周期を引いてから積分し、大きな t での桁落ちを防ぐ
+    let start = t - 0.5 * width;
+    let start = start - start.floor();
+    let a = antiderivative(waveform, start);
+    let b = antiderivative(waveform, start + width);
+    ((b - a) / width).clamp(0.0, 1.0)
+}
+
+/// Value 0..1 of `grating` at pixel center (`x`, `y`), or `None` when off.
+fn grating(grating: &Grating, x: f32, y: f32) -> Option<f32> {
+    let period = finite_or(grating.period, 10.0).clamp(0.05, 1e6);
+    let phase = finite_or(grating.phase, 0.0);
+    let t = match grating.kind {
+        GratingType::Off => return None,
+        GratingType::Lines => {
+            let (sin, cos) = finite_or(grating.angle, 0.0).to_radians().sin_cos();
+            (x * cos + y * sin) / period
+        }
+        GratingType::Circles => {
+            let cx = finite_or(grating.center.0, 0.0);
+            let cy = finite_or(grating.center.1, 0.0);
+            (x - cx).hypot(y - cy) / period
+        }
+    };
+    // 1 画素あたりの周期数を箱の幅にする (どちらの格子も勾配の大きさは 1 / period)
+    Some(filtered(grating.waveform, t + phase, 1.0 / period))
+}
+
+pub fn moire(width: usize, height: usize, p: &MoireParams) -> ImageBuf {
+    let mut out = ImageBuf::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let (fx, fy) = (x as f32 + 0.5, y as f32 + 0.5);
+            let values: Vec<Option<f32>> = p.gratings.iter().map(|g| grating(g, fx, fy)).collect();
+            let px = match p.output {
+                Output::Luma => {
+                    let mut active = values.iter().flatten().copied();
+                    let v = match active.next() {
+                        None => 0.5,
+                        Some(first) => match p.combine {
+                            Combine::Multiply => active.fold(first, |a, v| a * v),
+                            Combine::Difference => active.fold(first, |a, v| (a - v).abs()),
+                            Combine::Average => {
+                                let (sum, n) =
+                                    active.fold((first, 1.0), |(s, n), v| (s + v, n + 1.0));
+                                sum / n
+                            }
+                        },
+                    };
+                    PixelF32 {
+                        red: v,
+                        green: v,
+                        blue: v,
+                        alpha: 1.0,
+                    }
+                }
+                Output::Channels => {
+                    let channel = |i: usize| values.get(i).copied().flatten().unwrap_or(0.5);
+                    PixelF32 {
+                        red: channel(0),
+                        green: channel(1),
+                        blue: channel(2),
+                        alpha: 1.0,
+                    }
+                }
+            };
+            out.set(x, y, px);
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct MoirePattern;
+
+impl RenderCore for MoirePattern {
+    type Params = MoireParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let gratings = (0..GRATING_PARAMS.len())
+            .map(|_| Grating {
+                kind: match rng.popup(1, 3) {
+                    2 => GratingType::Lines,
+                    3 => GratingType::Circles,
+                    _ => GratingType::Off,
+                },
+                waveform: match rng.popup(1, 4) {
+                    2 => Waveform::Square,
+                    3 => Waveform::Triangle,
+                    4 => Waveform::Sawtooth,
+                    _ => Waveform::Sine,
+                },
+                period: 100.0 / rng.slider(0.1, MAX_FREQUENCY),
+                angle: rng.slider(-360.0, 360.0),
+                center: (rng.slider(-1000.0, 1000.0), rng.slider(-1000.0, 1000.0)),
+                phase: rng.slider(-10.0, 10.0),
+            })
+            .collect();
+        MoireParams {
+            combine: match rng.popup(1, 3) {
+                2 => Combine::Average,
+                3 => Combine::Difference,
+                _ => Combine::Multiply,
+            },
+            output: if rng.chance(0.5) {
+                Output::Luma
+            } else {
+                Output::Channels
+            },
+            gratings,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = moire(input.width, input.height, params);
+    }
+}
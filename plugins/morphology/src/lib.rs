@@ -0,0 +1,396 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::color::luminance;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Operation,
+    Channel,
+    Shape,
+    Radius,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Applies morphological operations to alpha or luminance.";
+
+/// Upper bound of `Radius` (full-resolution pixels).
+const MAX_RADIUS: f32 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Erode,
+    Dilate,
+    /// Erode then dilate: removes specks smaller than the element.
+    Open,
+    /// Dilate then erode: fills holes smaller than the element.
+    Close,
+    /// Input minus its opening: keeps only the specks.
+    TopHat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Alpha,
+    Luminance,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Disk,
+    Box,
+    Diamond,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Operation,
+            tr("Operation"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Erode"),
+                    tr("Dilate"),
+                    tr("Open"),
+                    tr("Close"),
+                    tr("Top Hat"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Channel: Alpha replaces the alpha of the layer; Luminance outputs a gray matte
+        params.add(
+            Params::Channel,
+            tr("Channel"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Alpha"), tr("Luminance")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Shape,
+            tr("Shape"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Disk"), tr("Box"), tr("Diamond")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Radius: fractional values give anti-aliased edges
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(2.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Morphology - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 半径はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = MorphologyParams {
+            operation: match params.get(Params::Operation)?.as_popup()?.value() {
+                2 => Operation::Dilate,
+                3 => Operation::Open,
+                4 => Operation::Close,
+                5 => Operation::TopHat,
+                _ => Operation::Erode,
+            },
+            channel: match params.get(Params::Channel)?.as_popup()?.value() {
+                2 => Channel::Luminance,
+                _ => Channel::Alpha,
+            },
+            shape: match params.get(Params::Shape)?.as_popup()?.value() {
+                2 => Shape::Box,
+                3 => Shape::Diamond,
+                _ => Shape::Disk,
+            },
+            radius: ds.length(params.get(Params::Radius)?.as_float_slider()?.value() as f32),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = morphology(&input, &settings);
+
+        let progress_final = out_layer.height() as i32;
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let (x, y) = (x as usize, y as usize);
+            let px = if x < output.width && y < output.height {
+                output.data[output.index(x, y)]
+            } else {
+                utils::image::TRANSPARENT
+            };
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct MorphologyParams {
+    pub operation: Operation,
+    pub channel: Channel,
+    pub shape: Shape,
+    /// Element radius in pixels (already scaled for downsampling).
+    pub radius: f32,
+}
+
+/// Non-flat structuring element: offsets with their anti-aliased coverage.
+struct Element {
+    taps: Vec<(isize, isize, f32)>,
+}
+
+impl Element {
+    fn new(shape: Shape, radius: f32) -> Self {
+        let r = if radius.is_nan() {
+            0.0
+        } else {
+            radius.clamp(0.0, MAX_RADIUS)
+        };
+        let reach = (r + 0.5).ceil() as isize;
+        let edge = |d: f32| (r - d + 0.5).clamp(0.0, 1.0);
+
+        let mut taps = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let (fx, fy) = (dx.abs() as f32, dy.abs() as f32);
+                let cover = if dx == 0 && dy == 0 {
+                    1.0
+                } else {
+                    match shape {
+                        Shape::Disk => edge(fx.hypot(fy)),
+                        Shape::Box => edge(fx) * edge(fy),
+                        // 斜辺までの距離は L1 距離 / √2
+                        Shape::Diamond => {
+                            ((r - fx - fy) * std::f32::consts::FRAC_1_SQRT_2 + 0.5).clamp(0.0, 1.0)
+                        }
+                    }
+                };
+                if cover > 0.0 {
+                    taps.push((dx, dy, cover));
+                }
+            }
+        }
+        Self { taps }
+    }
+}
+
+/// Grayscale dilation with a non-flat element: each neighbour counts scaled by
+/// its coverage, so a fractional radius grows edges by a fraction of a pixel.
+fn dilate(v: &[f32], width: usize, height: usize, element: &Element) -> Vec<f32> {
+    let mut out = vec![0.0; v.len()];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut m = 0.0f32;
+            for &(dx, dy, cover) in &element.taps {
+                let (qx, qy) = (x + dx, y + dy);
+                if qx < 0 || qy < 0 || qx >= width as isize || qy >= height as isize {
+                    continue;
+                }
+                m = m.max(v[qy as usize * width + qx as usize] * cover);
+            }
+            out[y as usize * width + x as usize] = m;
+        }
+    }
+    out
+}
+
+/// Dual of [`dilate`] on values in `[0, 1]`.
+fn erode(v: &[f32], width: usize, height: usize, element: &Element) -> Vec<f32> {
+    let inverted: Vec<f32> = v.iter().map(|v| 1.0 - v).collect();
+    dilate(&inverted, width, height, element)
+        .into_iter()
+        .map(|v| 1.0 - v)
+        .collect()
+}
+
+/// Applies the operation to the chosen channel (clamped to `[0, 1]`, NaN as 0).
+fn morphology(input: &ImageBuf, p: &MorphologyParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let unit = |v: f32| if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+    let values: Vec<f32> = input
+        .data
+        .iter()
+        .map(|&px| match p.channel {
+            Channel::Alpha => unit(px.alpha),
+            Channel::Luminance => unit(luminance(px)),
+        })
+        .collect();
+
+    let element = Element::new(p.shape, p.radius);
+    let result = match p.operation {
+        Operation::Erode => erode(&values, w, h, &element),
+        Operation::Dilate => dilate(&values, w, h, &element),
+        Operation::Open => dilate(&erode(&values, w, h, &element), w, h, &element),
+        Operation::Close => erode(&dilate(&values, w, h, &element), w, h, &element),
+        Operation::TopHat => {
+            let opened = dilate(&erode(&values, w, h, &element), w, h, &element);
+            values
+                .iter()
+                .zip(opened)
+                .map(|(v, o)| (v - o).max(0.0))
+                .collect()
+        }
+    };
+
+    let mut out = ImageBuf::new(w, h);
+    for (i, (&px, v)) in input.data.iter().zip(result).enumerate() {
+        out.data[i] = match p.channel {
+            Channel::Alpha => PixelF32 { alpha: v, ..px },
+            Channel::Luminance => PixelF32 {
+                red: v,
+                green: v,
+                blue: v,
+                alpha: unit(px.alpha),
+            },
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct Morphology;
+
+impl RenderCore for Morphology {
+    type Params = MorphologyParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        MorphologyParams {
+            operation: match rng.popup(1, 5) {
+                2 => Operation::Dilate,
+                3 => Operation::Open,
+                4 => Operation::Close,
+                5 => Operation::TopHat,
+                _ => Operation::Erode,
+            },
+            channel: if rng.chance(0.5) {
+                Channel::Alpha
+            } else {
+                Channel::Luminance
+            },
+            shape: match rng.popup(1, 3) {
+                2 => Shape::Box,
+                3 => Shape::Diamond,
+                _ => Shape::Disk,
+            },
+            radius: rng.slider(0.0, MAX_RADIUS),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = morphology(input, params);
+    }
+}
@@ -0,0 +1,273 @@
+// Keep in sync with `RenderParams` in ../mod.rs, `noise_pixel` in lib.rs and
+// `crates/noise` (hash, noise3, Fbm::sample).
+struct Params {
+    width: u32,
+    height: u32,
+    kind: u32,
+    seed: u32,
+    period_x: u32,
+    period_y: u32,
+    color: u32,
+    _pad: u32,
+    size: f32,
+    detail: f32,
+    roughness: f32,
+    lacunarity: f32,
+    distortion: f32,
+    evolution: f32,
+    _pad1: vec2<f32>,
+};
+
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+const KIND_PERLIN: u32 = 0u;
+const KIND_SIMPLEX: u32 = 1u;
+const KIND_VALUE: u32 = 2u;
+const MAX_DETAIL: f32 = 15.0;
+
+fn hash(x: u32, y: u32, z: u32) -> u32 {
+    var v = (x * 73856093u) ^ (y * 19349663u) ^ (z * 83492791u);
+    v ^= v >> 16u;
+    v *= 0x7feb352du;
+    v ^= v >> 15u;
+    v *= 0x846ca68bu;
+    return v ^ (v >> 16u);
+}
+
+fn unit(v: u32) -> f32 {
+    return f32(v >> 8u) / 16777216.0;
+}
+
+fn lattice(ix: i32, iy: i32, iz: i32, seed: u32) -> u32 {
+    return hash(u32(ix), u32(iy), u32(iz) ^ (seed * 0x9e3779b9u));
+}
+
+fn fade(t: vec3<f32>) -> vec3<f32> {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn grad(hv: u32, x: f32, y: f32, z: f32) -> f32 {
+    let h = hv & 15u;
+    let u = select(y, x, h < 8u);
+    var v = z;
+    if (h < 4u) {
+        v = y;
+    } else if (h == 12u || h == 14u) {
+        v = x;
+    }
+    return select(-u, u, (h & 1u) == 0u) + select(-v, v, (h & 2u) == 0u);
+}
+
+fn wrap(i: i32, period: u32) -> i32 {
+    if (period == 0u) {
+        return i;
+    }
+    let p = i32(min(period, 0x7fffffffu));
+    return ((i % p) + p) % p;
+}
+
+fn corner(kind: u32, i: vec3<i32>, t: vec3<f32>, d: vec3<i32>, seed: u32, period: vec2<u32>) -> f32 {
+    let h = lattice(wrap(i.x + d.x, period.x), wrap(i.y + d.y, period.y), i.z + d.z, seed);
+    if (kind == KIND_VALUE) {
+        return unit(h) * 2.0 - 1.0;
+    }
+    let o = t - vec3<f32>(d);
+    return grad(h, o.x, o.y, o.z);
+}
+
+fn lattice_noise(kind: u32, p: vec3<f32>, seed: u32, period: vec2<u32>) -> f32 {
+    let f = floor(p);
+    let t = p - f;
+    let i = vec3<i32>(f);
+    let u = fade(t);
+
+    let x00 = mix(corner(kind, i, t, vec3<i32>(0, 0, 0), seed, period), corner(kind, i, t, vec3<i32>(1, 0, 0), seed, period), u.x);
+    let x10 = mix(corner(kind, i, t, vec3<i32>(0, 1, 0), seed, period), corner(kind, i, t, vec3<i32>(1, 1, 0), seed, period), u.x);
+    let x01 = mix(corner(kind, i, t, vec3<i32>(0, 0, 1), seed, period), corner(kind, i, t, vec3<i32>(1, 0, 1), seed, period), u.x);
+    let x11 = mix(corner(kind, i, t, vec3<i32>(0, 1, 1), seed, period), corner(kind, i, t, vec3<i32>(1, 1, 1), seed, period), u.x);
+    let n = mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+    if (kind == KIND_VALUE) {
+        return n;
+    }
+    return n * 0.982;
+}
+
+fn simplex_corner(x0: vec3<f32>, i: vec3<i32>, offset: vec3<i32>, c: f32, seed: u32) -> f32 {
+    let d = x0 - vec3<f32>(offset) + vec3<f32>(c);
+    let t = 0.6 - dot(d, d);
+    if (t <= 0.0) {
+        return 0.0;
+    }
+    let h = lattice(i.x + offset.x, i.y + offset.y, i.z + offset.z, seed);
+    return t * t * t * t * grad(h, d.x, d.y, d.z);
+}
+
+fn simplex(p: vec3<f32>, seed: u32) -> f32 {
+    let F3 = 1.0 / 3.0;
+    let G3 = 1.0 / 6.0;
+
+    let s = (p.x + p.y + p.z) * F3;
+    let fi = floor(p + vec3<f32>(s));
+    let t = (fi.x + fi.y + fi.z) * G3;
+    let x0 = p - (fi - vec3<f32>(t));
+
+    var o1: vec3<i32>;
+    var o2: vec3<i32>;
+    if (x0.x >= x0.y) {
+        if (x0.y >= x0.z) {
+            o1 = vec3<i32>(1, 0, 0);
+            o2 = vec3<i32>(1, 1, 0);
+        } else if (x0.x >= x0.z) {
+            o1 = vec3<i32>(1, 0, 0);
+            o2 = vec3<i32>(1, 0, 1);
+        } else {
+            o1 = vec3<i32>(0, 0, 1);
+            o2 = vec3<i32>(1, 0, 1);
+        }
+    } else if (x0.y < x0.z) {
+        o1 = vec3<i32>(0, 0, 1);
+        o2 = vec3<i32>(0, 1, 1);
+    } else if (x0.x < x0.z) {
+        o1 = vec3<i32>(0, 1, 0);
+        o2 = vec3<i32>(0, 1, 1);
+    } else {
+        o1 = vec3<i32>(0, 1, 0);
+        o2 = vec3<i32>(1, 1, 0);
+    }
+
+    let i = vec3<i32>(fi);
+    var n = simplex_corner(x0, i, vec3<i32>(0, 0, 0), 0.0, seed);
+    n += simplex_corner(x0, i, o1, G3, seed);
+    n += simplex_corner(x0, i, o2, 2.0 * G3, seed);
+    n += simplex_corner(x0, i, vec3<i32>(1, 1, 1), 3.0 * G3, seed);
+    return 32.0 * n;
+}
+
+fn rem_euclid(x: f32, m: f32) -> f32 {
+    let r = x - m * trunc(x / m);
+    return select(r, r + m, r < 0.0);
+}
+
+fn noise3(kind: u32, p: vec3<f32>, seed: u32, period: vec2<u32>) -> f32 {
+    if (kind != KIND_SIMPLEX) {
+        return lattice_noise(kind, p, seed, period);
+    }
+    if (period.x == 0u && period.y == 0u) {
+        return simplex(p, seed);
+    }
+    // 傾いた格子は周期化できないので 4 つの周期コピーをクロスフェード
+    let tx = f32(max(period.x, 1u));
+    let ty = f32(max(period.y, 1u));
+    var x = p.x;
+    var y = p.y;
+    var u = 0.0;
+    var v = 0.0;
+    if (period.x != 0u) {
+        x = rem_euclid(p.x, tx);
+        u = x / tx;
+    }
+    if (period.y != 0u) {
+        y = rem_euclid(p.y, ty);
+        v = y / ty;
+    }
+    let n00 = simplex(vec3<f32>(x, y, p.z), seed);
+    let n10 = simplex(vec3<f32>(x - tx, y, p.z), seed);
+    let n01 = simplex(vec3<f32>(x, y - ty, p.z), seed);
+    let n11 = simplex(vec3<f32>(x - tx, y - ty, p.z), seed);
+    return mix(mix(n00, n10, u), mix(n01, n11, u), v);
+}
+
+fn finite_or(v: f32, fallback: f32) -> f32 {
+    // NaN / Inf は既定値に置き換える
+    if (v != v || abs(v) > 3.4e38) {
+        return fallback;
+    }
+    return v;
+}
+
+fn octave(p: vec3<f32>, o: u32, freq: f32, seed: u32) -> f32 {
+    let base = vec2<u32>(params.period_x, params.period_y);
+    if (base.x == 0u && base.y == 0u) {
+        return noise3(params.kind, p * freq, seed + o, vec2<u32>(0u));
+    }
+    var period = vec2<u32>(0u);
+    var scale = vec2<f32>(freq);
+    if (base.x != 0u) {
+        period.x = u32(max(round(f32(base.x) * freq), 1.0));
+        scale.x = f32(period.x) / f32(base.x);
+    }
+    if (base.y != 0u) {
+        period.y = u32(max(round(f32(base.y) * freq), 1.0));
+        scale.y = f32(period.y) / f32(base.y);
+    }
+    let q = vec3<f32>(p.x * scale.x, p.y * scale.y, p.z * freq);
+    return noise3(params.kind, q, seed + o, period);
+}
+
+fn fbm(p: vec3<f32>, seed: u32) -> f32 {
+    let detail = clamp(finite_or(params.detail, 0.0), 0.0, MAX_DETAIL);
+    let roughness = clamp(finite_or(params.roughness, 0.5), 0.0, 1.0);
+    let lacunarity = clamp(finite_or(params.lacunarity, 2.0), 0.0, 1000.0);
+
+    var freq = 1.0;
+    var amp = 1.0;
+    var max_amp = 0.0;
+    var sum = 0.0;
+    let whole = u32(floor(detail));
+    for (var o = 0u; o <= whole; o++) {
+        sum += octave(p, o, freq, seed) * amp;
+        max_amp += amp;
+        amp *= roughness;
+        freq *= lacunarity;
+    }
+
+    let rmd = detail - f32(whole);
+    var v = sum / max_amp;
+    if (rmd > 0.0) {
+        let last = octave(p, whole + 1u, freq, seed) * amp;
+        v = mix(v, (sum + last) / (max_amp + amp), rmd);
+    }
+    return clamp(0.5 + 0.5 * v, 0.0, 1.0);
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+    let period = vec2<u32>(params.period_x, params.period_y);
+
+    var cell = vec2<f32>(finite_or(params.size, 100.0));
+    cell = max(cell, vec2<f32>(0.01));
+    if (period.x != 0u) {
+        cell.x = f32(max(params.width, 1u)) / f32(period.x);
+    }
+    if (period.y != 0u) {
+        cell.y = f32(max(params.height, 1u)) / f32(period.y);
+    }
+    var q = vec3<f32>(
+        (f32(gid.x) + 0.5) / cell.x,
+        (f32(gid.y) + 0.5) / cell.y,
+        finite_or(params.evolution, 0.0),
+    );
+
+    let distortion = finite_or(params.distortion, 0.0);
+    if (distortion != 0.0) {
+        let warp = vec3<f32>(
+            noise3(params.kind, q, hash(params.seed, 0u, 0xd157u), period),
+            noise3(params.kind, q, hash(params.seed, 1u, 0xd157u), period),
+            noise3(params.kind, q, hash(params.seed, 2u, 0xd157u), period),
+        );
+        q += warp * distortion;
+    }
+
+    let fac = fbm(q, params.seed);
+    var rgb = vec3<f32>(fac);
+    if (params.color != 0u) {
+        rgb.y = fbm(q, hash(params.seed, 1u, 0xc010u));
+        rgb.z = fbm(q, hash(params.seed, 2u, 0xc010u));
+    }
+    output[i] = vec4<f32>(rgb, 1.0);
+}
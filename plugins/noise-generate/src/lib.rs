@@ -0,0 +1,486 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{Fbm, MAX_DETAIL, NoiseType, hash, noise3};
+use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NoiseType,
+    Size,
+    Detail,
+    Roughness,
+    Lacunarity,
+    Distortion,
+    Evolution,
+    EvolutionSpeed,
+    Tileable,
+    Output,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Generates Perlin, simplex or value fBm noise with domain warp, evolution and tiling.";
+
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::NoiseType,
+            tr("Noise Type"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Perlin"), tr("Simplex"), tr("Value")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Size: base octave cell size in pixels
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(4.0);
+                d.set_slider_max(1000.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Detail,
+            tr("Detail"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DETAIL);
+                d.set_slider_min(0.0);
+                d.set_slider_max(MAX_DETAIL);
+                d.set_default(2.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Roughness,
+            tr("Roughness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Lacunarity,
+            tr("Lacunarity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(4.0);
+                d.set_default(2.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Distortion: domain warp amount in base cells
+        params.add(
+            Params::Distortion,
+            tr("Distortion"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(5.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Evolution,
+            tr("Evolution"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100000.0);
+                d.set_valid_max(100000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Evolution Speed: evolution added per second of layer time
+        params.add(
+            Params::EvolutionSpeed,
+            tr("Evolution Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-1000.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(-2.0);
+                d.set_slider_max(2.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Tileable: rounds Size so the layer holds a whole number of cells
+        params.add(
+            Params::Tileable,
+            tr("Tileable"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Grayscale"), tr("Color")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_NoiseGenerate - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer: _,
+                out_layer,
+            } => {
+                self.do_render(in_data, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let Some(out_layer) = out_layer_opt {
+                    self.do_render(in_data, out_data, out_layer, params)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let seconds = in_data.current_time() as f64 / in_data.time_scale().max(1) as f64;
+
+        let evolution = params.get(Params::Evolution)?.as_float_slider()?.value();
+        let speed = params
+            .get(Params::EvolutionSpeed)?
+            .as_float_slider()?
+            .value();
+        let settings = NoiseParams {
+            kind: match params.get(Params::NoiseType)?.as_popup()?.value() {
+                2 => NoiseType::Simplex,
+                3 => NoiseType::Value,
+                _ => NoiseType::Perlin,
+            },
+            size: ds.length(params.get(Params::Size)?.as_float_slider()?.value() as f32),
+            detail: params.get(Params::Detail)?.as_float_slider()?.value() as f32,
+            roughness: params.get(Params::Roughness)?.as_float_slider()?.value() as f32,
+            lacunarity: params.get(Params::Lacunarity)?.as_float_slider()?.value() as f32,
+            distortion: params.get(Params::Distortion)?.as_float_slider()?.value() as f32,
+            evolution: (evolution + speed * seconds) as f32,
+            tileable: params.get(Params::Tileable)?.as_checkbox()?.value(),
+            color: params.get(Params::Output)?.as_popup()?.value() == 2,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+        };
+
+        let (width, height) = (out_layer.width(), out_layer.height());
+        let period = settings.period(width, height);
+
+        #[cfg(feature = "gpu_wgpu")]
+        if let Some(ctx) = wgpu_context() {
+            let gpu_params = RenderParams {
+                width: width as u32,
+                height: height as u32,
+                kind: match settings.kind {
+                    NoiseType::Perlin => 0,
+                    NoiseType::Simplex => 1,
+                    NoiseType::Value => 2,
+                },
+                seed: settings.seed,
+                period_x: period[0],
+                period_y: period[1],
+                color: settings.color as u32,
+                _pad: 0,
+                size: settings.size,
+                detail: settings.detail,
+                roughness: settings.roughness,
+                lacunarity: settings.lacunarity,
+                distortion: settings.distortion,
+                evolution: settings.evolution,
+                _pad1: [0.0; 2],
+            };
+            if let Ok(rgba) = ctx.render(&gpu_params) {
+                return ImageBuf::from_rgba(width, height, &rgba).write_to_layer(&mut out_layer);
+            }
+            // Fall through to the CPU path when the GPU dispatch fails.
+        }
+
+        let progress_final = height as i32;
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = noise_pixel(x as usize, y as usize, width, height, period, &settings);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct NoiseParams {
+    pub kind: NoiseType,
+    /// Base cell size in render pixels.
+    pub size: f32,
+    pub detail: f32,
+    pub roughness: f32,
+    pub lacunarity: f32,
+    /// Domain warp amount in base cells.
+    pub distortion: f32,
+    /// Position along the third noise axis.
+    pub evolution: f32,
+    pub tileable: bool,
+    /// Three independent noises in R/G/B instead of grayscale.
+    pub color: bool,
+    pub seed: u32,
+}
+
+impl NoiseParams {
+    fn cell_size(&self) -> f32 {
+        if self.size.is_finite() {
+            self.size.max(0.01)
+        } else {
+            100.0
+        }
+    }
+
+    /// Tile period in cells for a `width` x `height` layer (`[0, 0]` when not tileable).
+    fn period(&self, width: usize, height: usize) -> [u32; 2] {
+        if !self.tileable {
+            return [0, 0];
+        }
+        let cells = |n: usize| (n as f32 / self.cell_size()).round().clamp(1.0, 65536.0) as u32;
+        [cells(width), cells(height)]
+    }
+}
+
+/// Noise color at pixel (`x`, `y`); must match `shaders/compute.wgsl`.
+fn noise_pixel(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    period: [u32; 2],
+    p: &NoiseParams,
+) -> PixelF32 {
+    // タイル時はレイヤー幅にちょうど period セルが収まるように拡大率を合わせる
+    let cell = |n: usize, t: u32| {
+        if t == 0 {
+            p.cell_size()
+        } else {
+            n.max(1) as f32 / t as f32
+        }
+    };
+    let evolution = if p.evolution.is_finite() {
+        p.evolution
+    } else {
+        0.0
+    };
+    let mut q = [
+        (x as f32 + 0.5) / cell(width, period[0]),
+        (y as f32 + 0.5) / cell(height, period[1]),
+        evolution,
+    ];
+
+    let distortion = if p.distortion.is_finite() {
+        p.distortion
+    } else {
+        0.0
+    };
+    if distortion != 0.0 {
+        let warp: [f32; 3] =
+            std::array::from_fn(|a| noise3(p.kind, q, hash(p.seed, a as u32, 0xd157), period));
+        for (c, w) in q.iter_mut().zip(warp) {
+            *c += w * distortion;
+        }
+    }
+
+    let fbm = |seed: u32| {
+        let v = Fbm {
+            kind: p.kind,
+            detail: p.detail,
+            roughness: p.roughness,
+            lacunarity: p.lacunarity,
+            seed,
+            period,
+        }
+        .sample(q);
+        let v = 0.5 + 0.5 * v;
+        if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) }
+    };
+
+    let fac = fbm(p.seed);
+    let (g, b) = if p.color {
+        (fbm(hash(p.seed, 1, 0xc010)), fbm(hash(p.seed, 2, 0xc010)))
+    } else {
+        (fac, fac)
+    };
+    PixelF32 {
+        red: fac,
+        green: g,
+        blue: b,
+        alpha: 1.0,
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct NoiseGenerate;
+
+impl RenderCore for NoiseGenerate {
+    type Params = NoiseParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        NoiseParams {
+            kind: match rng.popup(1, 3) {
+                2 => NoiseType::Simplex,
+                3 => NoiseType::Value,
+                _ => NoiseType::Perlin,
+            },
+            size: rng.slider(1.0, 10000.0),
+            detail: rng.slider(0.0, MAX_DETAIL),
+            roughness: rng.slider(0.0, 1.0),
+            lacunarity: rng.slider(0.0, 1000.0),
+            distortion: rng.slider(-1000.0, 1000.0),
+            evolution: rng.slider(-100000.0, 100000.0),
+            tileable: rng.chance(0.5),
+            color: rng.chance(0.5),
+            seed: rng.popup(0, 10000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let (w, h) = (input.width, input.height);
+        let period = params.period(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                output.set(x, y, noise_pixel(x, y, w, h, period, params));
+            }
+        }
+    }
+}
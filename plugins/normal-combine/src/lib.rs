@@ -0,0 +1,433 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    DetailLayer,
+    Method,
+    BaseStrength,
+    DetailStrength,
+    BaseFormat,
+    DetailFormat,
+    OutputFormat,
+    Renormalize,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Blends a detail normal map onto a base normal map.";
+
+/// Checkout id of the detail normal map layer (the input uses 0).
+const DETAIL_CHECKOUT_ID: i32 = 1;
+/// Largest per-layer strength (as a factor).
+const MAX_STRENGTH: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Reoriented Normal Mapping: rotates the detail onto the base normal.
+    Reoriented,
+    /// Unreal Developer Network: adds the detail slopes, keeps the base z.
+    Udn,
+    /// Adds the slopes and multiplies z, keeping more of both maps.
+    Whiteout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalFormat {
+    /// Green points up the layer.
+    OpenGl,
+    /// Green points down the layer.
+    DirectX,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Detail Layer: without one only strength and format are applied to the layer
+        params.add(Params::DetailLayer, tr("Detail Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Reoriented (RNM)"), tr("UDN"), tr("Whiteout")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Base Strength / Detail Strength: slope scale of each map in percent
+        params.add(
+            Params::BaseStrength,
+            tr("Base Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_STRENGTH * 100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::DetailStrength,
+            tr("Detail Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_STRENGTH * 100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        for (id, name) in [
+            (Params::BaseFormat, tr("Base Format")),
+            (Params::DetailFormat, tr("Detail Format")),
+            (Params::OutputFormat, tr("Output Format")),
+        ] {
+            params.add(
+                id,
+                name,
+                PopupDef::setup(|d| {
+                    d.set_options(&[tr("OpenGL (Y+)"), tr("DirectX (Y-)")]);
+                    d.set_default(1);
+                }),
+            )?;
+        }
+
+        // Renormalize: rescale the decoded normals and the result to unit length
+        params.add(
+            Params::Renormalize,
+            tr("Renormalize"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_NormalCombine - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではディテールレイヤーをパラメータから直接取得する
+                let detail = params
+                    .checkout_at(
+                        Params::DetailLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_layer, detail.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::DetailLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        DETAIL_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let detail = cb
+                    .checkout_layer_pixels(DETAIL_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, detail.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(DETAIL_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn normal_format(params: &Parameters<Params>, id: Params) -> Result<NormalFormat, Error> {
+    Ok(match params.get(id)?.as_popup()?.value() {
+        2 => NormalFormat::DirectX,
+        _ => NormalFormat::OpenGl,
+    })
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        detail: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = NormalCombineParams {
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                2 => Method::Udn,
+                3 => Method::Whiteout,
+                _ => Method::Reoriented,
+            },
+            base_strength: params.get(Params::BaseStrength)?.as_float_slider()?.value() as f32
+                / 100.0,
+            detail_strength: params
+                .get(Params::DetailStrength)?
+                .as_float_slider()?
+                .value() as f32
+                / 100.0,
+            base_format: normal_format(params, Params::BaseFormat)?,
+            detail_format: normal_format(params, Params::DetailFormat)?,
+            output_format: normal_format(params, Params::OutputFormat)?,
+            renormalize: params.get(Params::Renormalize)?.as_checkbox()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        combine(&input, detail, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct NormalCombineParams {
+    pub method: Method,
+    /// Slope scale of the base map, 0..4.
+    pub base_strength: f32,
+    /// Slope scale of the detail map, 0..4.
+    pub detail_strength: f32,
+    pub base_format: NormalFormat,
+    pub detail_format: NormalFormat,
+    pub output_format: NormalFormat,
+    /// Normalizes the decoded inputs and the result (8-bit maps are rarely
+    /// unit length); off keeps the raw vectors, clamped when encoded.
+    pub renormalize: bool,
+}
+
+type Vec3 = [f32; 3];
+
+const FLAT: Vec3 = [0.0, 0.0, 1.0];
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(a: Vec3) -> Option<Vec3> {
+    let l = dot(a, a).sqrt();
+    (l > 1e-6 && l.is_finite()).then(|| [a[0] / l, a[1] / l, a[2] / l])
+}
+
+/// Tangent-space normal of an encoded pixel with +Y up the layer; transparent
+/// pixels are flat. Normals facing away (z < 0) are folded onto the surface.
+fn decode(px: PixelF32, format: NormalFormat, strength: f32, renormalize: bool) -> Vec3 {
+    let alpha = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+    if alpha <= 0.0 {
+        return FLAT;
+    }
+    let x = finite_or(px.red, 0.5) * 2.0 - 1.0;
+    let y = finite_or(px.green, 0.5) * 2.0 - 1.0;
+    let z = (finite_or(px.blue, 1.0) * 2.0 - 1.0).max(0.0);
+    let y = match format {
+        NormalFormat::OpenGl => y,
+        NormalFormat::DirectX => -y,
+    };
+    // 強さは傾き (xy) の倍率、部分的に透明な所は平らな法線へ寄せる
+    let k = strength * alpha;
+    let n = [x * k, y * k, z + (1.0 - alpha)];
+    if renormalize {
+        normalize(n).unwrap_or(FLAT)
+    } else {
+        n
+    }
+}
+
+fn encode(n: Vec3, format: NormalFormat, alpha: f32) -> PixelF32 {
+    let y = match format {
+        NormalFormat::OpenGl => n[1],
+        NormalFormat::DirectX => -n[1],
+    };
+    PixelF32 {
+        red: n[0].clamp(-1.0, 1.0) * 0.5 + 0.5,
+        green: y.clamp(-1.0, 1.0) * 0.5 + 0.5,
+        blue: n[2].clamp(-1.0, 1.0) * 0.5 + 0.5,
+        alpha,
+    }
+}
+
+/// Blends normals `base` and `detail` (both +Z out of the surface).
+fn blend(base: Vec3, detail: Vec3, method: Method) -> Vec3 {
+    match method {
+        Method::Reoriented => {
+            // Barré-Brisebois & Hill: detail を base の向きへ回転する
+            let t = [base[0], base[1], base[2] + 1.0];
+            let u = [-detail[0], -detail[1], detail[2]];
+            let k = dot(t, u) / t[2].max(1e-6);
+            [t[0] * k - u[0], t[1] * k - u[1], t[2] * k - u[2]]
+        }
+        Method::Udn => [base[0] + detail[0], base[1] + detail[1], base[2]],
+        Method::Whiteout => [
+            base[0] + detail[0],
+            base[1] + detail[1],
+            base[2] * detail[2],
+        ],
+    }
+}
+
+pub fn combine(input: &ImageBuf, detail: Option<&ImageBuf>, p: &NormalCombineParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    let base_strength = finite_or(p.base_strength, 1.0).clamp(0.0, MAX_STRENGTH);
+    let detail_strength = finite_or(p.detail_strength, 1.0).clamp(0.0, MAX_STRENGTH);
+
+    for y in 0..h {
+        for x in 0..w {
+            let px = input.get(x as isize, y as isize);
+            let alpha = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+            // ベースの透明部分は平らな法線として合成し、ディテールは残す
+            let base = if alpha > 0.0 {
+                decode(
+                    PixelF32 { alpha: 1.0, ..px },
+                    p.base_format,
+                    base_strength,
+                    p.renormalize,
+                )
+            } else {
+                FLAT
+            };
+            let n = match detail {
+                Some(map) => {
+                    let d = decode(
+                        map.get(x as isize, y as isize),
+                        p.detail_format,
+                        detail_strength,
+                        p.renormalize,
+                    );
+                    blend(base, d, p.method)
+                }
+                None => base,
+            };
+            let n = if p.renormalize {
+                normalize(n).unwrap_or(base)
+            } else {
+                n.map(|c| finite_or(c, 0.0))
+            };
+            out.set(x, y, encode(n, p.output_format, alpha));
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use the input flipped
+/// horizontally as the detail map.
+pub struct NormalCombine;
+
+impl RenderCore for NormalCombine {
+    type Params = NormalCombineParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let format = |rng: &mut Rng| {
+            if rng.chance(0.5) {
+                NormalFormat::OpenGl
+            } else {
+                NormalFormat::DirectX
+            }
+        };
+        NormalCombineParams {
+            method: match rng.popup(1, 3) {
+                2 => Method::Udn,
+                3 => Method::Whiteout,
+                _ => Method::Reoriented,
+            },
+            base_strength: rng.slider(0.0, MAX_STRENGTH),
+            detail_strength: rng.slider(0.0, MAX_STRENGTH),
+            base_format: format(rng),
+            detail_format: format(rng),
+            output_format: format(rng),
+            renormalize: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let detail = (frame % 2 == 1).then(|| {
+            let mut flipped = ImageBuf::new(input.width, input.height);
+            for y in 0..input.height {
+                for x in 0..input.width {
+                    let px = input.get((input.width - 1 - x) as isize, y as isize);
+                    flipped.set(x, y, px);
+                }
+            }
+            flipped
+        });
+        *output = combine(input, detail.as_ref(), params);
+    }
+}
@@ -0,0 +1,692 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::i18n;
+use utils::image::ImageBuf;
+use utils::normal::{self, NormalFormat};
+use utils::sampling::Downsample;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    NormalLayer,
+    NormalFormat,
+    Shading,
+    ToonSteps,
+    Specular,
+    Shininess,
+    AmbientColor,
+    AmbientIntensity,
+    Light1Type,
+    Light1Position,
+    Light1Height,
+    Light1Color,
+    Light1Intensity,
+    Light1Falloff,
+    Light2Type,
+    Light2Position,
+    Light2Height,
+    Light2Color,
+    Light2Intensity,
+    Light2Falloff,
+    Light3Type,
+    Light3Position,
+    Light3Height,
+    Light3Color,
+    Light3Intensity,
+    Light3Falloff,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Relights the layer from a normal map layer.";
+
+/// Checkout id of the normal map layer (the input uses 0).
+const NORMAL_CHECKOUT_ID: i32 = 1;
+const MAX_TOON_STEPS: i32 = 8;
+
+/// Per-light parameters: type, position, height, color, intensity, falloff.
+const LIGHT_PARAMS: [[Params; 6]; 3] = [
+    [
+        Params::Light1Type,
+        Params::Light1Position,
+        Params::Light1Height,
+        Params::Light1Color,
+        Params::Light1Intensity,
+        Params::Light1Falloff,
+    ],
+    [
+        Params::Light2Type,
+        Params::Light2Position,
+        Params::Light2Height,
+        Params::Light2Color,
+        Params::Light2Intensity,
+        Params::Light2Falloff,
+    ],
+    [
+        Params::Light3Type,
+        Params::Light3Position,
+        Params::Light3Height,
+        Params::Light3Color,
+        Params::Light3Intensity,
+        Params::Light3Falloff,
+    ],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shading {
+    BlinnPhong,
+    /// Diffuse quantized into `Toon Steps` bands with a hard specular.
+    Toon,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightType {
+    Off,
+    /// Light at (`Position`, `Height`) with distance falloff.
+    Point,
+    /// Parallel light from the layer center towards (`Position`, `Height`).
+    Directional,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub kind: LightType,
+    /// Position in render pixels.
+    pub position: (f32, f32),
+    /// Height above the layer in render pixels.
+    pub height: f32,
+    /// Encoded light color.
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Distance in render pixels at which a point light has half its intensity.
+    pub falloff: f32,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(Params::NormalLayer, tr("Normal Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::NormalFormat,
+            tr("Normal Format"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("OpenGL (Y+)"), tr("DirectX (Y-)")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Shading: Toon shows Toon Steps (see update_param_visibility)
+        params.add_with_flags(
+            Params::Shading,
+            tr("Shading"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Blinn-Phong"), tr("Toon")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::ToonSteps,
+            tr("Toon Steps"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_TOON_STEPS);
+                d.set_slider_min(2);
+                d.set_slider_max(MAX_TOON_STEPS);
+                d.set_default(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Specular,
+            tr("Specular"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(4.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(2.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Shininess,
+            tr("Shininess"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(1024.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(256.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::AmbientColor,
+            tr("Ambient Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::AmbientIntensity,
+            tr("Ambient Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(4.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.2);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Lights: Off hides the light's controls, Directional its falloff
+        for (n, [kind, position, height, color, intensity, falloff]) in
+            LIGHT_PARAMS.into_iter().enumerate()
+        {
+            params.add_with_flags(
+                kind,
+                &i18n::fill(tr("Light {} Type"), n + 1),
+                PopupDef::setup(|d| {
+                    d.set_options(&[tr("Off"), tr("Point"), tr("Directional")]);
+                    d.set_default(if n == 0 { 2 } else { 1 });
+                }),
+                ParamFlag::SUPERVISE,
+                ParamUIFlags::empty(),
+            )?;
+
+            params.add(
+                position,
+                &i18n::fill(tr("Light {} Position"), n + 1),
+                PointDef::setup(|d| {
+                    d.set_default((25.0, 25.0));
+                }),
+            )?;
+
+            // Height: distance above the layer in pixels
+            params.add(
+                height,
+                &i18n::fill(tr("Light {} Height"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(0.0);
+                    d.set_valid_max(10000.0);
+                    d.set_slider_min(0.0);
+                    d.set_slider_max(1000.0);
+                    d.set_default(200.0);
+                    d.set_precision(1);
+                }),
+            )?;
+
+            params.add(
+                color,
+                &i18n::fill(tr("Light {} Color"), n + 1),
+                ColorDef::setup(|d| {
+                    d.set_default(Pixel8 {
+                        red: 255,
+                        green: 255,
+                        blue: 255,
+                        alpha: 255,
+                    });
+                }),
+            )?;
+
+            params.add(
+                intensity,
+                &i18n::fill(tr("Light {} Intensity"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(0.0);
+                    d.set_valid_max(100.0);
+                    d.set_slider_min(0.0);
+                    d.set_slider_max(4.0);
+                    d.set_default(1.0);
+                    d.set_precision(2);
+                }),
+            )?;
+
+            // Falloff: distance at which a point light has half its intensity
+            params.add(
+                falloff,
+                &i18n::fill(tr("Light {} Falloff"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(1.0);
+                    d.set_valid_max(100000.0);
+                    d.set_slider_min(10.0);
+                    d.set_slider_max(4000.0);
+                    d.set_default(1000.0);
+                    d.set_precision(1);
+                }),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_NormalRelight - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Shading
+                    || LIGHT_PARAMS
+                        .iter()
+                        .any(|l| l[0] == params.type_at(param_index)) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では法線レイヤーをパラメータから直接取得する
+                let normals = params
+                    .checkout_at(
+                        Params::NormalLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, normals.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::NormalLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        NORMAL_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let normals = cb
+                    .checkout_layer_pixels(NORMAL_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, normals.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(NORMAL_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn light_type(params: &Parameters<Params>, id: Params) -> Result<LightType, Error> {
+    Ok(match params.get(id)?.as_popup()?.value() {
+        2 => LightType::Point,
+        3 => LightType::Directional,
+        _ => LightType::Off,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let toon = params.get(Params::Shading)?.as_popup()?.value() == 2;
+    let mut visibility = vec![(Params::ToonSteps, toon)];
+    for [kind, position, height, color, intensity, falloff] in LIGHT_PARAMS {
+        let kind = light_type(params, kind)?;
+        let on = kind != LightType::Off;
+        visibility.extend([
+            (position, on),
+            (height, on),
+            (color, on),
+            (intensity, on),
+            (falloff, kind == LightType::Point),
+        ]);
+    }
+    for (id, visible) in visibility {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        normals: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 位置・高さ・減衰はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let mut lights = Vec::with_capacity(LIGHT_PARAMS.len());
+        for [kind, position, height, color, intensity, falloff] in LIGHT_PARAMS {
+            let c = params.get(color)?.as_color()?.float_value()?;
+            lights.push(Light {
+                kind: light_type(params, kind)?,
+                position: ds.point(params.get(position)?.as_point()?.value()),
+                height: ds.length(params.get(height)?.as_float_slider()?.value() as f32),
+                color: [c.red, c.green, c.blue],
+                intensity: params.get(intensity)?.as_float_slider()?.value() as f32,
+                falloff: ds.length(params.get(falloff)?.as_float_slider()?.value() as f32),
+            });
+        }
+        let ambient = params
+            .get(Params::AmbientColor)?
+            .as_color()?
+            .float_value()?;
+        let settings = RelightParams {
+            normal_format: match params.get(Params::NormalFormat)?.as_popup()?.value() {
+                2 => NormalFormat::DirectX,
+                _ => NormalFormat::OpenGl,
+            },
+            shading: match params.get(Params::Shading)?.as_popup()?.value() {
+                2 => Shading::Toon,
+                _ => Shading::BlinnPhong,
+            },
+            toon_steps: params.get(Params::ToonSteps)?.as_slider()?.value() as u32,
+            specular: params.get(Params::Specular)?.as_float_slider()?.value() as f32,
+            shininess: params.get(Params::Shininess)?.as_float_slider()?.value() as f32,
+            ambient_color: [ambient.red, ambient.green, ambient.blue],
+            ambient_intensity: params
+                .get(Params::AmbientIntensity)?
+                .as_float_slider()?
+                .value() as f32,
+            lights,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        relight(&input, normals, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct RelightParams {
+    pub normal_format: NormalFormat,
+    pub shading: Shading,
+    pub toon_steps: u32,
+    pub specular: f32,
+    pub shininess: f32,
+    /// Encoded ambient color.
+    pub ambient_color: [f32; 3],
+    pub ambient_intensity: f32,
+    pub lights: Vec<Light>,
+}
+
+type Vec3 = [f32; 3];
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(a: Vec3) -> Option<Vec3> {
+    let l = dot(a, a).sqrt();
+    (l > 1e-6 && l.is_finite()).then(|| [a[0] / l, a[1] / l, a[2] / l])
+}
+
+/// Light with sanitized values and linear color premultiplied by intensity.
+struct LightRig {
+    kind: LightType,
+    position: Vec3,
+    /// Unit vector towards a directional light.
+    direction: Vec3,
+    radiance: Vec3,
+    falloff: f32,
+}
+
+impl LightRig {
+    fn new(light: &Light, width: usize, height: usize) -> Self {
+        let position = [
+            finite_or(light.position.0, 0.0).clamp(-1e6, 1e6),
+            finite_or(light.position.1, 0.0).clamp(-1e6, 1e6),
+            finite_or(light.height, 0.0).clamp(0.0, 1e6),
+        ];
+        // ディレクショナルはレイヤー中心から Position / Height へ向かう方向
+        let center = [width as f32 * 0.5, height as f32 * 0.5, 0.0];
+        let direction = normalize([
+            position[0] - center[0],
+            position[1] - center[1],
+            position[2],
+        ])
+        .unwrap_or([0.0, 0.0, 1.0]);
+        let intensity = finite_or(light.intensity, 0.0).clamp(0.0, 100.0);
+        Self {
+            kind: light.kind,
+            position,
+            direction,
+            radiance: light
+                .color
+                .map(|c| srgb_to_linear(finite_or(c, 0.0).clamp(0.0, 1.0)) * intensity),
+            falloff: finite_or(light.falloff, 1000.0).clamp(1.0, 1e6),
+        }
+    }
+
+    /// Unit vector towards the light and its attenuation at surface point `p`.
+    fn incidence(&self, p: Vec3) -> Option<(Vec3, f32)> {
+        match self.kind {
+            LightType::Off => None,
+            LightType::Directional => Some((self.direction, 1.0)),
+            LightType::Point => {
+                let d = [
+                    self.position[0] - p[0],
+                    self.position[1] - p[1],
+                    self.position[2] - p[2],
+                ];
+                let dist2 = dot(d, d);
+                // Falloff の距離で強度が半分になる逆二乗風の減衰
+                let attenuation = 1.0 / (1.0 + dist2 / (self.falloff * self.falloff));
+                normalize(d).map(|l| (l, attenuation))
+            }
+        }
+    }
+}
+
+/// Surface normal of an encoded normal-map pixel in layer space (y down, z
+/// towards the viewer); transparent or degenerate pixels face the viewer.
+fn layer_normal(px: PixelF32, format: NormalFormat) -> Vec3 {
+    normal::decode(px, format).map_or([0.0, 0.0, 1.0], |[x, y, z]| [x, -y, z])
+}
+
+fn relight(input: &ImageBuf, normals: Option<&ImageBuf>, p: &RelightParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    let rigs: Vec<LightRig> = p
+        .lights
+        .iter()
+        .filter(|l| l.kind != LightType::Off)
+        .map(|l| LightRig::new(l, width, height))
+        .collect();
+    let ambient_intensity = finite_or(p.ambient_intensity, 0.0).clamp(0.0, 4.0);
+    let ambient = p
+        .ambient_color
+        .map(|c| srgb_to_linear(finite_or(c, 0.0).clamp(0.0, 1.0)) * ambient_intensity);
+    let specular = finite_or(p.specular, 0.0).clamp(0.0, 4.0);
+    let shininess = finite_or(p.shininess, 32.0).clamp(1.0, 1024.0);
+    let steps = p.toon_steps.clamp(2, MAX_TOON_STEPS as u32) as f32;
+    // 正射影なので視線は常に +Z
+    let view = [0.0, 0.0, 1.0];
+
+    for y in 0..height {
+        for x in 0..width {
+            let px = input.get(x as isize, y as isize);
+            let n = match normals {
+                Some(map) => layer_normal(map.get(x as isize, y as isize), p.normal_format),
+                None => [0.0, 0.0, 1.0],
+            };
+            let surface = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+
+            let mut diffuse = ambient;
+            let mut highlight = [0.0f32; 3];
+            for rig in &rigs {
+                let Some((l, attenuation)) = rig.incidence(surface) else {
+                    continue;
+                };
+                let ndl = dot(n, l).max(0.0);
+                let half =
+                    normalize([l[0] + view[0], l[1] + view[1], l[2] + view[2]]).unwrap_or(view);
+                let ndh = dot(n, half).max(0.0);
+                let (d, s) = match p.shading {
+                    Shading::BlinnPhong => {
+                        let s = if ndl > 0.0 { ndh.powf(shininess) } else { 0.0 };
+                        (ndl, s)
+                    }
+                    Shading::Toon => {
+                        // 拡散光を段階化し、ハイライトは閾値で切る
+                        let band = (ndl * steps).floor().min(steps - 1.0) / (steps - 1.0);
+                        let s = if ndl > 0.0 && ndh.powf(shininess) > 0.5 {
+                            1.0
+                        } else {
+                            0.0
+                        };
+                        (band, s)
+                    }
+                };
+                for k in 0..3 {
+                    diffuse[k] += rig.radiance[k] * d * attenuation;
+                    highlight[k] += rig.radiance[k] * s * specular * attenuation;
+                }
+            }
+
+            let albedo = [px.red, px.green, px.blue].map(|c| srgb_to_linear(finite_or(c, 0.0)));
+            let lit: Vec3 = std::array::from_fn(|k| {
+                linear_to_srgb((albedo[k] * diffuse[k] + highlight[k]).max(0.0))
+            });
+            output.set(
+                x,
+                y,
+                PixelF32 {
+                    red: lit[0],
+                    green: lit[1],
+                    blue: lit[2],
+                    alpha: px.alpha,
+                },
+            );
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so the input itself is used as the normal
+/// map on odd frames.
+pub struct NormalRelight;
+
+impl RenderCore for NormalRelight {
+    type Params = RelightParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let lights = (0..LIGHT_PARAMS.len())
+            .map(|_| Light {
+                kind: match rng.popup(1, 3) {
+                    2 => LightType::Point,
+                    3 => LightType::Directional,
+                    _ => LightType::Off,
+                },
+                position: (rng.slider(-1000.0, 1000.0), rng.slider(-1000.0, 1000.0)),
+                height: rng.slider(0.0, 10000.0),
+                color: [rng.unit(), rng.unit(), rng.unit()],
+                intensity: rng.slider(0.0, 100.0),
+                falloff: rng.slider(1.0, 100000.0),
+            })
+            .collect();
+        RelightParams {
+            normal_format: if rng.chance(0.5) {
+                NormalFormat::OpenGl
+            } else {
+                NormalFormat::DirectX
+            },
+            shading: if rng.chance(0.5) {
+                Shading::BlinnPhong
+            } else {
+                Shading::Toon
+            },
+            toon_steps: rng.int(2, MAX_TOON_STEPS) as u32,
+            specular: rng.slider(0.0, 4.0),
+            shininess: rng.slider(1.0, 1024.0),
+            ambient_color: [rng.unit(), rng.unit(), rng.unit()],
+            ambient_intensity: rng.slider(0.0, 4.0),
+            lights,
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let normals = (frame % 2 == 1).then_some(input);
+        *output = relight(input, normals, params);
+    }
+}
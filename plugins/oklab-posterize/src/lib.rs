@@ -0,0 +1,331 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::{Oklab, Oklch};
+use utils::dither::{BLUE_NOISE_SIZE, bayer, blue_noise};
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    LightnessLevels,
+    ChromaLevels,
+    HueLevels,
+    HueOffset,
+    Dither,
+    DitherStrength,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Posterizes lightness, chroma and hue independently in OKLCH with optional ordered dithering.";
+
+const MAX_LEVELS: i32 = 64;
+/// Chroma mapped to the top chroma level (a little above the sRGB gamut).
+const MAX_CHROMA: f32 = 0.4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dither {
+    None,
+    Bayer8,
+    BlueNoise,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Lightness / Chroma Levels: output values evenly spaced from 0 to white / MAX_CHROMA
+        params.add(
+            Params::LightnessLevels,
+            tr("Lightness Levels"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_LEVELS);
+                d.set_slider_min(2);
+                d.set_slider_max(16);
+                d.set_default(5);
+            }),
+        )?;
+
+        params.add(
+            Params::ChromaLevels,
+            tr("Chroma Levels"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_LEVELS);
+                d.set_slider_min(2);
+                d.set_slider_max(16);
+                d.set_default(4);
+            }),
+        )?;
+
+        // Hue Levels: hue sectors around the color wheel
+        params.add(
+            Params::HueLevels,
+            tr("Hue Levels"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_LEVELS);
+                d.set_slider_min(1);
+                d.set_slider_max(24);
+                d.set_default(8);
+            }),
+        )?;
+
+        // Hue Offset: rotates the hue sector centers
+        params.add(
+            Params::HueOffset,
+            tr("Hue Offset"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Dither,
+            tr("Dither"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("None"), tr("Bayer 8x8"), tr("Blue Noise")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Dither Strength: 1 = thresholds spread over one full level step
+        params.add(
+            Params::DitherStrength,
+            tr("Dither Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(2.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_OklabPosterize - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        _in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = PosterizeParams {
+            lightness_levels: params.get(Params::LightnessLevels)?.as_slider()?.value() as u32,
+            chroma_levels: params.get(Params::ChromaLevels)?.as_slider()?.value() as u32,
+            hue_levels: params.get(Params::HueLevels)?.as_slider()?.value() as u32,
+            hue_offset: params
+                .get(Params::HueOffset)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            dither: match params.get(Params::Dither)?.as_popup()?.value() {
+                2 => Dither::Bayer8,
+                3 => Dither::BlueNoise,
+                _ => Dither::None,
+            },
+            dither_strength: params
+                .get(Params::DitherStrength)?
+                .as_float_slider()?
+                .value() as f32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = posterize(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct PosterizeParams {
+    pub lightness_levels: u32,
+    pub chroma_levels: u32,
+    pub hue_levels: u32,
+    /// Hue sector rotation in radians.
+    pub hue_offset: f32,
+    pub dither: Dither,
+    pub dither_strength: f32,
+}
+
+/// Ordered-dither offset in `-0.5..0.5` at (`x`, `y`) for `axis` (0 = L, 1 = C, 2 = H).
+fn dither_bias(dither: Dither, x: usize, y: usize, axis: usize) -> f32 {
+    // 軸ごとに閾値マップをずらし、3 軸が同時に段差を越えないようにする
+    let (ox, oy) = [(0, 0), (3, 5), (6, 2)][axis];
+    match dither {
+        Dither::None => 0.0,
+        Dither::Bayer8 => bayer((x + ox) % 8, (y + oy) % 8, 3) - 0.5,
+        Dither::BlueNoise => {
+            let n = BLUE_NOISE_SIZE;
+            let (ox, oy) = (ox * 11, oy * 11);
+            blue_noise()[((y + oy) % n) * n + (x + ox) % n] - 0.5
+        }
+    }
+}
+
+/// Rounds `v` (in level steps) to a level after adding the dither bias.
+fn quantize(v: f32, bias: f32) -> f32 {
+    (v + bias).round()
+}
+
+fn posterize_pixel(px: PixelF32, x: usize, y: usize, p: &PosterizeParams) -> PixelF32 {
+    use std::f32::consts::TAU;
+
+    let finite = |v: f32| if v.is_finite() { v } else { 0.0 };
+    let rgb = [finite(px.red), finite(px.green), finite(px.blue)];
+    let lch = Oklab::from_srgb(rgb).to_oklch();
+
+    let strength = if p.dither_strength.is_nan() {
+        0.0
+    } else {
+        p.dither_strength.clamp(0.0, 2.0)
+    };
+    let bias = |axis: usize| dither_bias(p.dither, x, y, axis) * strength;
+
+    let l_steps = (p.lightness_levels.clamp(2, MAX_LEVELS as u32) - 1) as f32;
+    let c_steps = (p.chroma_levels.clamp(2, MAX_LEVELS as u32) - 1) as f32;
+    let sectors = p.hue_levels.clamp(1, MAX_LEVELS as u32) as f32;
+    let offset = if p.hue_offset.is_finite() {
+        p.hue_offset
+    } else {
+        0.0
+    };
+
+    let l = quantize(finite(lch.l) * l_steps, bias(0)).max(0.0) / l_steps;
+    let c = quantize(finite(lch.c) / MAX_CHROMA * c_steps, bias(1)).clamp(0.0, c_steps) / c_steps
+        * MAX_CHROMA;
+    let sector = TAU / sectors;
+    let h = quantize((finite(lch.h) - offset) / sector, bias(2)) * sector + offset;
+
+    let out = Oklch { l, c, h }.to_oklab().to_srgb();
+    // 量子化で色域外に出た負の値は切り捨てる
+    let clean = |v: f32| if v.is_nan() { 0.0 } else { v.max(0.0) };
+    PixelF32 {
+        red: clean(out[0]),
+        green: clean(out[1]),
+        blue: clean(out[2]),
+        alpha: px.alpha,
+    }
+}
+
+fn posterize(input: &ImageBuf, p: &PosterizeParams) -> ImageBuf {
+    let mut out = ImageBuf::new(input.width, input.height);
+    for y in 0..input.height {
+        for x in 0..input.width {
+            let px = input.data[input.index(x, y)];
+            out.set(x, y, posterize_pixel(px, x, y, p));
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct OklabPosterize;
+
+impl RenderCore for OklabPosterize {
+    type Params = PosterizeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        PosterizeParams {
+            lightness_levels: rng.popup(2, MAX_LEVELS) as u32,
+            chroma_levels: rng.popup(2, MAX_LEVELS) as u32,
+            hue_levels: rng.popup(1, MAX_LEVELS) as u32,
+            hue_offset: rng.slider(-3600.0, 3600.0).to_radians(),
+            dither: match rng.popup(1, 3) {
+                2 => Dither::Bayer8,
+                3 => Dither::BlueNoise,
+                _ => Dither::None,
+            },
+            dither_strength: rng.slider(0.0, 2.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = posterize(input, params);
+    }
+}
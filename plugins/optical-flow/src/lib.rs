@@ -0,0 +1,329 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::flow::{self, Flow, LucasKanade, MAX_ITERATIONS, MAX_LEVELS, MAX_WINDOW_RADIUS};
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    VectorScale,
+    PyramidLevels,
+    WindowRadius,
+    Iterations,
+    Output,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Outputs optical flow motion vectors from the previous frame.";
+
+/// Checkout id of the previous frame (the current frame uses 0).
+const PREVIOUS_CHECKOUT_ID: i32 = 1;
+const MAX_VECTOR_SCALE: f32 = 4096.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// RG = motion since the previous frame, 0.5 = still.
+    Vectors,
+    /// Hue = direction, saturation = speed relative to `Vector Scale`.
+    ColorWheel,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Vector Scale: displacement in pixels encoded by a full-range (0 or 1) channel
+        params.add(
+            Params::VectorScale,
+            tr("Vector Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_VECTOR_SCALE);
+                d.set_slider_min(1.0);
+                d.set_slider_max(256.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Pyramid Levels: each level halves the resolution and doubles the trackable motion
+        params.add(
+            Params::PyramidLevels,
+            tr("Pyramid Levels"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_LEVELS as i32);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_LEVELS as i32);
+                d.set_default(4);
+            }),
+        )?;
+
+        // Window Radius: neighbourhood assumed to move together, in pixels per level
+        params.add(
+            Params::WindowRadius,
+            tr("Window Radius"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_WINDOW_RADIUS as i32);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_WINDOW_RADIUS as i32);
+                d.set_default(4);
+            }),
+        )?;
+
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS as i32);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_ITERATIONS as i32);
+                d.set_default(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Motion Vectors"), tr("Color Wheel")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_OpticalFlow - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では前フレームを取得できないので静止として扱う
+                self.do_render(in_data, in_layer, None, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                let _ = extra.callbacks().checkout_layer(
+                    0,
+                    PREVIOUS_CHECKOUT_ID,
+                    &req,
+                    in_data.current_time() - in_data.time_step(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                );
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let previous = cb
+                    .checkout_layer_pixels(PREVIOUS_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, previous.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(PREVIOUS_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        previous: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // ベクトルの倍率はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = FlowParams {
+            vector_scale: ds
+                .length(params.get(Params::VectorScale)?.as_float_slider()?.value() as f32),
+            levels: params.get(Params::PyramidLevels)?.as_slider()?.value() as u32,
+            window_radius: params.get(Params::WindowRadius)?.as_slider()?.value() as u32,
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as u32,
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::ColorWheel,
+                _ => Output::Vectors,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        optical_flow(&input, previous, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FlowParams {
+    /// Render pixels encoded by a full-range channel.
+    pub vector_scale: f32,
+    pub levels: u32,
+    pub window_radius: u32,
+    pub iterations: u32,
+    pub output: Output,
+}
+
+/// Color wheel of a vector of length `speed` (0..1) and direction `angle`.
+fn wheel(angle: f32, speed: f32) -> [f32; 3] {
+    let hue = (angle / std::f32::consts::TAU).rem_euclid(1.0) * 6.0;
+    let s = speed.clamp(0.0, 1.0);
+    let channel = |k: f32| {
+        let t = (hue + k).rem_euclid(6.0);
+        let c = (t - 3.0).abs() - 1.0;
+        1.0 - s * (1.0 - c.clamp(0.0, 1.0))
+    };
+    [channel(0.0), channel(4.0), channel(2.0)]
+}
+
+fn optical_flow(input: &ImageBuf, previous: Option<&ImageBuf>, p: &FlowParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let field = match previous {
+        Some(previous) if !previous.is_empty() => {
+            let settings = LucasKanade {
+                levels: p.levels,
+                window_radius: p.window_radius,
+                iterations: p.iterations,
+            };
+            flow::lucas_kanade(input, previous, &settings)
+        }
+        _ => Flow::zero(width, height),
+    };
+    let scale = finite_or(p.vector_scale, 32.0).clamp(1e-3, MAX_VECTOR_SCALE);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (vx, vy) = field.vectors[y * width + x];
+            let px = match p.output {
+                Output::Vectors => {
+                    let (red, green) = flow::encode(vx, vy, scale);
+                    PixelF32 {
+                        red,
+                        green,
+                        blue: 0.5,
+                        alpha: 1.0,
+                    }
+                }
+                Output::ColorWheel => {
+                    let [red, green, blue] = wheel(vy.atan2(vx), vx.hypot(vy) / scale);
+                    PixelF32 {
+                        red,
+                        green,
+                        blue,
+                        alpha: 1.0,
+                    }
+                }
+            };
+            output.set(x, y, px);
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no access to other frames, so on odd frames the previous
+/// frame is simulated by the input shifted two pixels left and one up.
+pub struct OpticalFlow;
+
+impl RenderCore for OpticalFlow {
+    type Params = FlowParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        FlowParams {
+            vector_scale: rng.slider(1.0, MAX_VECTOR_SCALE),
+            levels: rng.popup(1, MAX_LEVELS as i32) as u32,
+            window_radius: rng.popup(1, MAX_WINDOW_RADIUS as i32) as u32,
+            iterations: rng.popup(1, MAX_ITERATIONS as i32) as u32,
+            output: if rng.chance(0.5) {
+                Output::Vectors
+            } else {
+                Output::ColorWheel
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let previous = (frame % 2 == 1).then(|| {
+            let mut shifted = ImageBuf::new(input.width, input.height);
+            for y in 0..input.height {
+                for x in 0..input.width {
+                    shifted.set(x, y, input.get_clamped(x as isize + 2, y as isize + 1));
+                }
+            }
+            shifted
+        });
+        *output = optical_flow(input, previous.as_ref(), params);
+    }
+}
@@ -0,0 +1,658 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::VecDeque;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Colors,
+    Method,
+    Iterations,
+    Seed,
+    Stabilize,
+    Inertia,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: palettes of recently rendered frames for temporal stabilization.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Reduces the frame to a limited color palette.";
+
+const MAX_COLORS: i32 = 64;
+const MAX_ITERATIONS: i32 = 50;
+/// Upper bound of pixels fed to the palette search; larger frames are strided.
+const MAX_SAMPLES: usize = 1 << 16;
+/// Palettes kept per sequence: the previous frame and the current one, so
+/// re-rendering a frame still finds its predecessor.
+const HISTORY_FRAMES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Lloyd iterations from a seeded k-means++ start (or the previous palette).
+    KMeans,
+    /// Recursive splits of the widest box at its median.
+    MedianCut,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Colors,
+            tr("Colors"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_COLORS);
+                d.set_slider_min(2);
+                d.set_slider_max(32);
+                d.set_default(8);
+            }),
+        )?;
+
+        params.add(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("K-Means"), tr("Median Cut")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Iterations: Lloyd refinement passes (K-Means only)
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS);
+                d.set_slider_min(1);
+                d.set_slider_max(20);
+                d.set_default(8);
+            }),
+        )?;
+
+        // Seed: k-means++ start when there is no previous palette to continue from
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        // Stabilize: continue from the previous frame's palette to avoid flicker
+        params.add(
+            Params::Stabilize,
+            tr("Stabilize"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+                d.set_label(tr("Over Time"));
+            }),
+        )?;
+
+        // Inertia: how much of the previous palette each color keeps
+        params.add(
+            Params::Inertia,
+            tr("Inertia"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_PaletteQuantize - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // パレットは再計算できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(plugin.in_data, &input, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup | ae::Command::UserChangedParam { .. } => {
+                // 設定が変わったら前フレームのパレットは引き継がない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let input = cb
+                    .checkout_layer_pixels(0)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+
+                if let Some(input) = input
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(in_data, &input, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let settings = QuantizeParams {
+            colors: params.get(Params::Colors)?.as_slider()?.value() as usize,
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                2 => Method::MedianCut,
+                _ => Method::KMeans,
+            },
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as usize,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            inertia: params.get(Params::Inertia)?.as_float_slider()?.value() as f32,
+        };
+        let stabilize = params.get(Params::Stabilize)?.as_checkbox()?.value();
+
+        let key = HistoryKey {
+            colors: settings.colors,
+            method: settings.method,
+            downsample: Downsample::from_in_data(&in_data),
+            time_step: in_data.time_step(),
+        };
+        let time = in_data.current_time();
+
+        let palette = {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            let previous = if stabilize {
+                history.previous(key, time)
+            } else {
+                None
+            };
+            let palette = build_palette(input, previous, &settings);
+            if stabilize {
+                history.store(key, time, palette.clone());
+            }
+            palette
+        };
+
+        apply_palette(input, &palette).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Settings a stored palette is only valid for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct HistoryKey {
+    colors: usize,
+    method: Method,
+    downsample: Downsample,
+    time_step: i32,
+}
+
+/// Palettes (OKLab) of recently rendered frames, newest last.
+#[derive(Default)]
+struct History {
+    key: Option<HistoryKey>,
+    frames: VecDeque<(i32, Vec<[f32; 3]>)>,
+}
+
+impl History {
+    fn clear(&mut self) {
+        self.key = None;
+        self.frames.clear();
+    }
+
+    /// Palette of the frame one `time_step` before `time`, if it was built with `key`.
+    fn previous(&self, key: HistoryKey, time: i32) -> Option<&[[f32; 3]]> {
+        if self.key != Some(key) {
+            return None;
+        }
+        let previous = time.checked_sub(key.time_step)?;
+        self.frames
+            .iter()
+            .find(|(t, _)| *t == previous)
+            .map(|(_, palette)| palette.as_slice())
+    }
+
+    fn store(&mut self, key: HistoryKey, time: i32, palette: Vec<[f32; 3]>) {
+        if self.key != Some(key) {
+            self.clear();
+            self.key = Some(key);
+        }
+        self.frames.retain(|(t, _)| *t != time);
+        self.frames.push_back((time, palette));
+        while self.frames.len() > HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct QuantizeParams {
+    pub colors: usize,
+    pub method: Method,
+    pub iterations: usize,
+    pub seed: u32,
+    /// Share of the previous palette kept per color (0 = follow the frame).
+    pub inertia: f32,
+}
+
+/// Weighted OKLab samples of the visible pixels.
+struct Samples {
+    lab: Vec<[f32; 3]>,
+    weight: Vec<f32>,
+}
+
+impl Samples {
+    fn new(input: &ImageBuf) -> Self {
+        // 大きなフレームは間引いて探索コストを一定に抑える
+        let stride = input.data.len().div_ceil(MAX_SAMPLES).max(1);
+        let mut lab = Vec::new();
+        let mut weight = Vec::new();
+        for px in input.data.iter().step_by(stride) {
+            let alpha = if px.alpha.is_nan() {
+                0.0
+            } else {
+                px.alpha.clamp(0.0, 1.0)
+            };
+            if alpha <= 0.0 {
+                continue;
+            }
+            lab.push(to_lab(*px));
+            weight.push(alpha);
+        }
+        Self { lab, weight }
+    }
+
+    fn len(&self) -> usize {
+        self.lab.len()
+    }
+}
+
+fn to_lab(px: PixelF32) -> [f32; 3] {
+    let finite = |v: f32| if v.is_finite() { v } else { 0.0 };
+    let lab = Oklab::from_srgb([finite(px.red), finite(px.green), finite(px.blue)]);
+    [finite(lab.l), finite(lab.a), finite(lab.b)]
+}
+
+fn distance2(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+fn nearest(palette: &[[f32; 3]], lab: [f32; 3]) -> usize {
+    let mut best = (0, f32::INFINITY);
+    for (i, &c) in palette.iter().enumerate() {
+        let d = distance2(c, lab);
+        if d < best.1 {
+            best = (i, d);
+        }
+    }
+    best.0
+}
+
+/// Seeded k-means++: each next center is drawn with probability proportional
+/// to its squared distance from the centers picked so far.
+fn kmeans_plus_plus(samples: &Samples, k: usize, seed: u32) -> Vec<[f32; 3]> {
+    let n = samples.len();
+    let first = noise::hash(seed, 0, 0x6b6d) as usize % n;
+    let mut centers = vec![samples.lab[first]];
+    let mut d2: Vec<f32> = samples
+        .lab
+        .iter()
+        .map(|&s| distance2(s, centers[0]))
+        .collect();
+
+    while centers.len() < k {
+        let total: f64 = d2
+            .iter()
+            .zip(&samples.weight)
+            .map(|(&d, &w)| (d * w) as f64)
+            .sum();
+        // 全サンプルが既存の中心と一致したらそれ以上色は増やせない
+        if total <= 0.0 {
+            break;
+        }
+        let mut target = noise::hash_unit(seed, centers.len() as u32, 0x6b6d) as f64 * total;
+        let mut pick = n - 1;
+        for (i, (&d, &w)) in d2.iter().zip(&samples.weight).enumerate() {
+            target -= (d * w) as f64;
+            if target < 0.0 {
+                pick = i;
+                break;
+            }
+        }
+        let center = samples.lab[pick];
+        for (d, &s) in d2.iter_mut().zip(&samples.lab) {
+            *d = d.min(distance2(s, center));
+        }
+        centers.push(center);
+    }
+    centers
+}
+
+/// Lloyd iterations; a center that loses all its samples stays where it was.
+fn kmeans(samples: &Samples, mut centers: Vec<[f32; 3]>, iterations: usize) -> Vec<[f32; 3]> {
+    for _ in 0..iterations {
+        let mut sum = vec![[0.0f64; 4]; centers.len()];
+        for (&s, &w) in samples.lab.iter().zip(&samples.weight) {
+            let acc = &mut sum[nearest(&centers, s)];
+            let w = w as f64;
+            acc[0] += s[0] as f64 * w;
+            acc[1] += s[1] as f64 * w;
+            acc[2] += s[2] as f64 * w;
+            acc[3] += w;
+        }
+
+        let mut moved = false;
+        for (c, acc) in centers.iter_mut().zip(&sum) {
+            if acc[3] <= 0.0 {
+                continue;
+            }
+            let next = [
+                (acc[0] / acc[3]) as f32,
+                (acc[1] / acc[3]) as f32,
+                (acc[2] / acc[3]) as f32,
+            ];
+            moved |= distance2(*c, next) > 1e-12;
+            *c = next;
+        }
+        if !moved {
+            break;
+        }
+    }
+    centers
+}
+
+/// Median cut: splits the box with the widest extent at the weighted median of
+/// that axis until there are `k` boxes, then returns the box means.
+fn median_cut(samples: &Samples, k: usize) -> Vec<[f32; 3]> {
+    let extent = |indices: &[usize]| {
+        let mut lo = [f32::INFINITY; 3];
+        let mut hi = [f32::NEG_INFINITY; 3];
+        for &i in indices {
+            for c in 0..3 {
+                lo[c] = lo[c].min(samples.lab[i][c]);
+                hi[c] = hi[c].max(samples.lab[i][c]);
+            }
+        }
+        (0..3)
+            .map(|c| (hi[c] - lo[c], c))
+            .fold((0.0f32, 0), |a, b| if b.0 > a.0 { b } else { a })
+    };
+
+    let mut boxes = vec![(0..samples.len()).collect::<Vec<usize>>()];
+    while boxes.len() < k {
+        let Some((index, (size, axis))) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, extent(b)))
+            .max_by(|a, b| a.1.0.total_cmp(&b.1.0))
+        else {
+            break;
+        };
+        if size <= 0.0 {
+            break;
+        }
+
+        let mut indices = boxes.swap_remove(index);
+        indices.sort_by(|&a, &b| samples.lab[a][axis].total_cmp(&samples.lab[b][axis]));
+        let half: f32 = indices.iter().map(|&i| samples.weight[i]).sum::<f32>() * 0.5;
+        let mut acc = 0.0;
+        let mut split = 1;
+        for (n, &i) in indices.iter().enumerate() {
+            acc += samples.weight[i];
+            if acc >= half {
+                split = n + 1;
+                break;
+            }
+        }
+        // 両側に必ず 1 サンプル以上残す
+        let split = split.clamp(1, indices.len() - 1);
+        let upper = indices.split_off(split);
+        boxes.push(indices);
+        boxes.push(upper);
+    }
+
+    boxes
+        .iter()
+        .map(|indices| {
+            let mut acc = [0.0f64; 4];
+            for &i in indices {
+                let (s, w) = (samples.lab[i], samples.weight[i] as f64);
+                acc[0] += s[0] as f64 * w;
+                acc[1] += s[1] as f64 * w;
+                acc[2] += s[2] as f64 * w;
+                acc[3] += w;
+            }
+            let total = acc[3].max(f64::MIN_POSITIVE);
+            [
+                (acc[0] / total) as f32,
+                (acc[1] / total) as f32,
+                (acc[2] / total) as f32,
+            ]
+        })
+        .collect()
+}
+
+/// Pulls each new color toward the nearest unclaimed color of `previous`, so
+/// palette entries drift instead of jumping between frames.
+fn blend_with_previous(palette: &mut [[f32; 3]], previous: &[[f32; 3]], inertia: f32) {
+    let mut claimed = vec![false; previous.len()];
+    for c in palette.iter_mut() {
+        let Some((j, _)) = previous
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| !claimed[*j])
+            .map(|(j, &p)| (j, distance2(*c, p)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            break;
+        };
+        claimed[j] = true;
+        for ch in 0..3 {
+            c[ch] += (previous[j][ch] - c[ch]) * inertia;
+        }
+    }
+}
+
+/// Builds the OKLab palette of `input`, continuing from `previous` when given.
+fn build_palette(
+    input: &ImageBuf,
+    previous: Option<&[[f32; 3]]>,
+    p: &QuantizeParams,
+) -> Vec<[f32; 3]> {
+    let samples = Samples::new(input);
+    if samples.len() == 0 {
+        return previous.map(<[_]>::to_vec).unwrap_or_default();
+    }
+    let k = p.colors.clamp(2, MAX_COLORS as usize);
+    let iterations = p.iterations.clamp(1, MAX_ITERATIONS as usize);
+    let inertia = if p.inertia.is_nan() {
+        0.0
+    } else {
+        p.inertia.clamp(0.0, 1.0)
+    };
+
+    let mut palette = match p.method {
+        Method::KMeans => {
+            // 前フレームのパレットから反復を始めると色の割り当てが安定する
+            let start = match previous {
+                Some(prev) if prev.len() == k => prev.to_vec(),
+                _ => kmeans_plus_plus(&samples, k, p.seed),
+            };
+            kmeans(&samples, start, iterations)
+        }
+        Method::MedianCut => median_cut(&samples, k),
+    };
+    if let Some(prev) = previous {
+        blend_with_previous(&mut palette, prev, inertia);
+    }
+    palette
+}
+
+/// Maps every pixel to its nearest palette color, keeping alpha.
+fn apply_palette(input: &ImageBuf, palette: &[[f32; 3]]) -> ImageBuf {
+    let mut out = ImageBuf::new(input.width, input.height);
+    let colors: Vec<[f32; 3]> = palette
+        .iter()
+        .map(|&[l, a, b]| {
+            let rgb = Oklab { l, a, b }.to_srgb();
+            rgb.map(|v| if v.is_nan() { 0.0 } else { v.max(0.0) })
+        })
+        .collect();
+
+    for (dst, &px) in out.data.iter_mut().zip(&input.data) {
+        let alpha = if px.alpha.is_nan() { 0.0 } else { px.alpha };
+        *dst = if colors.is_empty() {
+            PixelF32 {
+                red: 0.0,
+                green: 0.0,
+                blue: 0.0,
+                alpha,
+            }
+        } else {
+            let [red, green, blue] = colors[nearest(palette, to_lab(px))];
+            PixelF32 {
+                red,
+                green,
+                blue,
+                alpha,
+            }
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no sequence data, so odd frames continue from the palette
+/// of the same input (exercising the stabilized path) and even frames start fresh.
+pub struct PaletteQuantize;
+
+impl RenderCore for PaletteQuantize {
+    type Params = QuantizeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        QuantizeParams {
+            colors: rng.popup(2, MAX_COLORS) as usize,
+            method: if rng.chance(0.5) {
+                Method::KMeans
+            } else {
+                Method::MedianCut
+            },
+            iterations: rng.popup(1, MAX_ITERATIONS) as usize,
+            seed: rng.popup(0, 10000) as u32,
+            inertia: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let mut palette = build_palette(input, None, params);
+        if frame % 2 == 1 {
+            palette = build_palette(input, Some(&palette), params);
+        }
+        *output = apply_palette(input, &palette);
+    }
+}
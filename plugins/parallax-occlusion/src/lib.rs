@@ -0,0 +1,528 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Filter};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    HeightLayer,
+    Depth,
+    InvertHeight,
+    ViewDirection,
+    ViewTilt,
+    Steps,
+    Filter,
+    Shadows,
+    LightDirection,
+    LightElevation,
+    ShadowStrength,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Fakes 3D depth from a height map layer with parallax occlusion mapping.";
+
+/// Checkout id of the height map layer (the input uses 0).
+const HEIGHT_CHECKOUT_ID: i32 = 1;
+const MAX_DEPTH: f32 = 1000.0;
+/// Grazing views need unbounded offsets, so the tilt stops short of 90°.
+const MAX_TILT: f32 = 80.0;
+const MIN_STEPS: i32 = 4;
+const MAX_STEPS: i32 = 256;
+/// Penetration depth in pixels at which a shadow ray is fully occluded.
+const SHADOW_SOFTNESS: f32 = 2.0;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Height Layer: luminance × alpha, white is the top surface
+        params.add(Params::HeightLayer, tr("Height Layer"), LayerDef::new())?;
+
+        // Depth: how far below the top surface black lies, in pixels
+        params.add(
+            Params::Depth,
+            tr("Depth"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_DEPTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(20.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::InvertHeight,
+            tr("Invert Height"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // View Direction / Tilt: the viewer leans towards the direction by the tilt
+        params.add(
+            Params::ViewDirection,
+            tr("View Direction"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::ViewTilt,
+            tr("View Tilt"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_TILT);
+                d.set_slider_min(0.0);
+                d.set_slider_max(MAX_TILT);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Steps,
+            tr("Steps"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_STEPS);
+                d.set_valid_max(MAX_STEPS);
+                d.set_slider_min(MIN_STEPS);
+                d.set_slider_max(128);
+                d.set_default(32);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Shadows: shows the light controls (see update_param_visibility)
+        params.add_with_flags(
+            Params::Shadows,
+            tr("Shadows"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::LightDirection,
+            tr("Light Direction"),
+            AngleDef::setup(|d| {
+                d.set_default(-45.0);
+            }),
+        )?;
+
+        params.add(
+            Params::LightElevation,
+            tr("Light Elevation"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(90.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(90.0);
+                d.set_default(45.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::ShadowStrength,
+            tr("Shadow Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.6);
+                d.set_precision(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ParallaxOcclusion - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Shadows =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では高さレイヤーをパラメータから直接取得する
+                let heights = params
+                    .checkout_at(
+                        Params::HeightLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, heights.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::HeightLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        HEIGHT_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let heights = cb
+                    .checkout_layer_pixels(HEIGHT_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, heights.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(HEIGHT_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let shadows = params.get(Params::Shadows)?.as_checkbox()?.value();
+    for id in [
+        Params::LightDirection,
+        Params::LightElevation,
+        Params::ShadowStrength,
+    ] {
+        ui::set_param_visible(in_data, params, id, shadows)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        heights: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 深さはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let angle =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_angle()?.value().to_radians()) };
+
+        let settings = ParallaxParams {
+            depth: ds.length(params.get(Params::Depth)?.as_float_slider()?.value() as f32),
+            invert_height: params.get(Params::InvertHeight)?.as_checkbox()?.value(),
+            view_direction: angle(Params::ViewDirection)?,
+            view_tilt: (params.get(Params::ViewTilt)?.as_float_slider()?.value() as f32)
+                .to_radians(),
+            steps: params.get(Params::Steps)?.as_slider()?.value() as u32,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+            shadows: params.get(Params::Shadows)?.as_checkbox()?.value(),
+            light_direction: angle(Params::LightDirection)?,
+            light_elevation: (params
+                .get(Params::LightElevation)?
+                .as_float_slider()?
+                .value() as f32)
+                .to_radians(),
+            shadow_strength: params
+                .get(Params::ShadowStrength)?
+                .as_float_slider()?
+                .value() as f32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        parallax(&input, heights, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ParallaxParams {
+    /// Depth of a black height in render pixels.
+    pub depth: f32,
+    pub invert_height: bool,
+    /// Compass direction the viewer leans towards (0 = up, clockwise), radians.
+    pub view_direction: f32,
+    /// View angle from straight on, radians.
+    pub view_tilt: f32,
+    pub steps: u32,
+    pub filter: Filter,
+    pub shadows: bool,
+    /// Compass direction of the light, radians.
+    pub light_direction: f32,
+    /// Light angle above the layer plane, radians.
+    pub light_elevation: f32,
+    pub shadow_strength: f32,
+}
+
+/// Unit vector in layer space (y down) of a compass angle (0 = up, clockwise).
+fn compass(angle: f32) -> (f32, f32) {
+    let (s, c) = finite_or(angle, 0.0).sin_cos();
+    (s, -c)
+}
+
+/// Depth below the top surface in 0..1 per pixel, sampled bilinearly with
+/// clamped edges.
+struct DepthField {
+    width: usize,
+    height: usize,
+    depth: Vec<f32>,
+}
+
+impl DepthField {
+    fn new(map: &ImageBuf, invert: bool) -> Self {
+        let depth = map
+            .data
+            .iter()
+            .map(|&px| {
+                let h = finite_or(luminance(px) * px.alpha, 0.0).clamp(0.0, 1.0);
+                if invert { h } else { 1.0 - h }
+            })
+            .collect();
+        Self {
+            width: map.width,
+            height: map.height,
+            depth,
+        }
+    }
+
+    fn at(&self, x: f32, y: f32) -> f32 {
+        if self.depth.is_empty() {
+            return 0.0;
+        }
+        let fetch = |xi: isize, yi: isize| {
+            let xi = xi.clamp(0, self.width as isize - 1) as usize;
+            let yi = yi.clamp(0, self.height as isize - 1) as usize;
+            self.depth[yi * self.width + xi]
+        };
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (xi, yi) = (x0 as isize, y0 as isize);
+        let top = fetch(xi, yi) + (fetch(xi + 1, yi) - fetch(xi, yi)) * tx;
+        let bottom = fetch(xi, yi + 1) + (fetch(xi + 1, yi + 1) - fetch(xi, yi + 1)) * tx;
+        top + (bottom - top) * ty
+    }
+}
+
+/// Where the view ray through (`x`, `y`) meets the height field: the texture
+/// position and the depth (0..1) of the hit.
+fn trace_view(
+    field: &DepthField,
+    (x, y): (f32, f32),
+    (sx, sy): (f32, f32),
+    steps: u32,
+) -> ((f32, f32), f32) {
+    let layer = 1.0 / steps as f32;
+    let mut ray = 0.0;
+    let mut map = field.at(x, y);
+    let (mut prev_ray, mut prev_map) = (ray, map);
+    for _ in 0..steps {
+        if ray >= map {
+            break;
+        }
+        (prev_ray, prev_map) = (ray, map);
+        ray += layer;
+        map = field.at(x + sx * ray, y + sy * ray);
+    }
+    // 最後の 2 サンプル間で交点を線形補間する
+    let after = map - ray;
+    let before = prev_map - prev_ray;
+    let t = if ray > 0.0 && before - after > 1e-6 {
+        (before / (before - after)).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let hit = prev_ray + (ray - prev_ray) * t;
+    ((x + sx * hit, y + sy * hit), hit)
+}
+
+/// Occlusion (0..1) of the light at the surface point `at` of depth `hit`.
+fn trace_shadow(
+    field: &DepthField,
+    (x, y): (f32, f32),
+    hit: f32,
+    (lx, ly): (f32, f32),
+    depth: f32,
+    steps: u32,
+) -> f32 {
+    let n = ((hit * steps as f32).ceil() as u32).max(1);
+    let mut occlusion = 0.0f32;
+    for i in 1..n {
+        // 光源へ向かって浮上しながら、レイより上にある高さを探す
+        let climbed = hit * i as f32 / n as f32;
+        let ray = hit - climbed;
+        let map = field.at(x + lx * climbed, y + ly * climbed);
+        occlusion = occlusion.max((ray - map) * depth / SHADOW_SOFTNESS);
+        if occlusion >= 1.0 {
+            return 1.0;
+        }
+    }
+    occlusion.clamp(0.0, 1.0)
+}
+
+fn parallax(input: &ImageBuf, heights: Option<&ImageBuf>, p: &ParallaxParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let premul = sampling::premultiply(input);
+    // 高さレイヤーがなければ入力自身の輝度を高さとして使う
+    let field = DepthField::new(heights.unwrap_or(input), p.invert_height);
+
+    let depth = finite_or(p.depth, 0.0).clamp(0.0, MAX_DEPTH);
+    let tilt = finite_or(p.view_tilt, 0.0).clamp(0.0, MAX_TILT.to_radians());
+    let steps = p.steps.clamp(MIN_STEPS as u32, MAX_STEPS as u32);
+    // 視線は傾けた方向から入射するので、深い点ほど反対側へずれる
+    let (vx, vy) = compass(p.view_direction);
+    let reach = tilt.tan() * depth;
+    let view_shift = (-vx * reach, -vy * reach);
+
+    let elevation = finite_or(p.light_elevation, 0.0).clamp(1f32.to_radians(), 90f32.to_radians());
+    let (lx, ly) = compass(p.light_direction);
+    let climb = depth / elevation.tan();
+    let light_shift = (lx * climb, ly * climb);
+    let strength = finite_or(p.shadow_strength, 0.0).clamp(0.0, 1.0);
+    let shadows = p.shadows && strength > 0.0 && depth > 0.0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (uv, hit) = trace_view(&field, (x as f32, y as f32), view_shift, steps);
+            let mut px = sampling::sample(&premul, uv.0, uv.1, p.filter);
+            if shadows && hit > 0.0 {
+                let occlusion = trace_shadow(&field, uv, hit, light_shift, depth, steps);
+                let shade = 1.0 - strength * occlusion;
+                px.red *= shade;
+                px.green *= shade;
+                px.blue *= shade;
+            }
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use the input itself as the
+/// height map (as does a missing Height Layer).
+pub struct ParallaxOcclusion;
+
+impl RenderCore for ParallaxOcclusion {
+    type Params = ParallaxParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ParallaxParams {
+            depth: rng.slider(0.0, MAX_DEPTH),
+            invert_height: rng.chance(0.5),
+            view_direction: rng.slider(-720.0, 720.0).to_radians(),
+            view_tilt: rng.slider(0.0, MAX_TILT).to_radians(),
+            steps: rng.int(MIN_STEPS, MAX_STEPS) as u32,
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+            shadows: rng.chance(0.5),
+            light_direction: rng.slider(-720.0, 720.0).to_radians(),
+            light_elevation: rng.slider(1.0, 90.0).to_radians(),
+            shadow_strength: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let heights = (frame % 2 == 1).then_some(input);
+        *output = parallax(input, heights, params);
+    }
+}
@@ -0,0 +1,793 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::TAU;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, coverage, unpremultiply};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Pattern,
+    Count,
+    Radius,
+    Center,
+    Rotation,
+    Divergence,
+    PetalsNumerator,
+    PetalsDenominator,
+    FrequencyX,
+    FrequencyY,
+    Phase,
+    PhaseSpeed,
+    Draw,
+    DotSize,
+    EndSize,
+    LineWidth,
+    Progress,
+    StartColor,
+    EndColor,
+    Background,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Generates phyllotaxis, rose and Lissajous patterns.";
+
+const MAX_COUNT: i32 = 5000;
+/// Upper bound of `Dot Size` and `Line Width` (full-resolution pixels).
+const MAX_SIZE: f32 = 200.0;
+const MAX_FREQUENCY: i32 = 32;
+/// Divergence angle of sunflower seeds, 360° / φ².
+const GOLDEN_ANGLE: f64 = 137.507_764;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Points at a fixed divergence angle with radius growing as √i.
+    Phyllotaxis,
+    /// r = cos(k θ) with k = numerator / denominator.
+    Rose,
+    /// (sin(a t + phase), sin(b t)).
+    Lissajous,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Draw {
+    Dots,
+    /// Segments joining consecutive points.
+    Lines,
+    DotsAndLines,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Transparent,
+    /// Drawn over the layer.
+    Input,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Pattern: shows only the shape controls of the chosen pattern
+        params.add_with_flags(
+            Params::Pattern,
+            tr("Pattern"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Phyllotaxis"), tr("Rose Curve"), tr("Lissajous")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Count,
+            tr("Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_COUNT);
+                d.set_slider_min(2);
+                d.set_slider_max(2000);
+                d.set_default(500);
+            }),
+        )?;
+
+        // Radius: outer radius of the pattern in pixels
+        params.add(
+            Params::Radius,
+            tr("Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1000.0);
+                d.set_default(300.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Divergence: turn between consecutive phyllotaxis points, golden angle by default
+        params.add(
+            Params::Divergence,
+            tr("Divergence"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(360.0);
+                d.set_slider_min(130.0);
+                d.set_slider_max(145.0);
+                d.set_default(GOLDEN_ANGLE);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Petals Numerator / Denominator: rose frequency k = n / d
+        for (id, name, default) in [
+            (Params::PetalsNumerator, tr("Petals Numerator"), 5),
+            (Params::PetalsDenominator, tr("Petals Denominator"), 1),
+        ] {
+            params.add(
+                id,
+                name,
+                SliderDef::setup(|d| {
+                    d.set_valid_min(1);
+                    d.set_valid_max(MAX_FREQUENCY);
+                    d.set_slider_min(1);
+                    d.set_slider_max(12);
+                    d.set_default(default);
+                }),
+            )?;
+        }
+
+        // Frequency X / Y: Lissajous cycles per loop on each axis
+        for (id, name, default) in [
+            (Params::FrequencyX, tr("Frequency X"), 3),
+            (Params::FrequencyY, tr("Frequency Y"), 2),
+        ] {
+            params.add(
+                id,
+                name,
+                SliderDef::setup(|d| {
+                    d.set_valid_min(1);
+                    d.set_valid_max(MAX_FREQUENCY);
+                    d.set_slider_min(1);
+                    d.set_slider_max(12);
+                    d.set_default(default);
+                }),
+            )?;
+        }
+
+        // Phase: twists the spiral, turns the petals or shifts the Lissajous X axis
+        params.add(
+            Params::Phase,
+            tr("Phase"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Phase Speed: degrees of phase added per second
+        params.add(
+            Params::PhaseSpeed,
+            tr("Phase Speed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-3600.0);
+                d.set_valid_max(3600.0);
+                d.set_slider_min(-180.0);
+                d.set_slider_max(180.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add_with_flags(
+            Params::Draw,
+            tr("Draw"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Dots"), tr("Lines"), tr("Dots and Lines")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Dot Size: diameter of the first dot in pixels
+        params.add(
+            Params::DotSize,
+            tr("Dot Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SIZE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(40.0);
+                d.set_default(8.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // End Size: size of the last dot and line end as a percentage of the first
+        params.add(
+            Params::EndSize,
+            tr("End Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LineWidth,
+            tr("Line Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SIZE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Progress: share of the points drawn, in order
+        params.add(
+            Params::Progress,
+            tr("Progress"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Start / End Color: blended in OKLab along the point order
+        params.add(
+            Params::StartColor,
+            tr("Start Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 200,
+                    blue: 60,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::EndColor,
+            tr("End Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 220,
+                    green: 40,
+                    blue: 120,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Background,
+            tr("Background"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Transparent"), tr("Input")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ParametricPattern - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(params.type_at(param_index), Params::Pattern | Params::Draw) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn pattern(params: &Parameters<Params>) -> Result<Pattern, Error> {
+    Ok(match params.get(Params::Pattern)?.as_popup()?.value() {
+        2 => Pattern::Rose,
+        3 => Pattern::Lissajous,
+        _ => Pattern::Phyllotaxis,
+    })
+}
+
+fn draw(params: &Parameters<Params>) -> Result<Draw, Error> {
+    Ok(match params.get(Params::Draw)?.as_popup()?.value() {
+        2 => Draw::Lines,
+        3 => Draw::DotsAndLines,
+        _ => Draw::Dots,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let pattern = pattern(params)?;
+    let draw = draw(params)?;
+    let visibility = [
+        (Params::Divergence, pattern == Pattern::Phyllotaxis),
+        (Params::PetalsNumerator, pattern == Pattern::Rose),
+        (Params::PetalsDenominator, pattern == Pattern::Rose),
+        (Params::FrequencyX, pattern == Pattern::Lissajous),
+        (Params::FrequencyY, pattern == Pattern::Lissajous),
+        (Params::DotSize, draw != Draw::Lines),
+        (Params::LineWidth, draw != Draw::Dots),
+    ];
+    for (id, visible) in visibility {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 半径・中心・サイズはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let seconds = in_data.current_time() as f64 / in_data.time_scale().max(1) as f64;
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let int = |id| -> Result<u32, Error> { Ok(params.get(id)?.as_slider()?.value() as u32) };
+
+        let phase = params.get(Params::Phase)?.as_angle()?.value() as f64;
+        let speed = params.get(Params::PhaseSpeed)?.as_float_slider()?.value();
+        let start = params.get(Params::StartColor)?.as_color()?.float_value()?;
+        let end = params.get(Params::EndColor)?.as_color()?.float_value()?;
+        let settings = PatternParams {
+            pattern: pattern(params)?,
+            count: int(Params::Count)?,
+            radius: ds.length(slider(Params::Radius)?),
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            rotation: params.get(Params::Rotation)?.as_angle()?.value(),
+            divergence: slider(Params::Divergence)?,
+            petals: (
+                int(Params::PetalsNumerator)?,
+                int(Params::PetalsDenominator)?,
+            ),
+            frequency: (int(Params::FrequencyX)?, int(Params::FrequencyY)?),
+            // 角度は 1 回転ごとに丸めて、長い時間でも精度を保つ
+            phase: ((phase + speed * seconds).rem_euclid(360.0)) as f32,
+            draw: draw(params)?,
+            dot_size: ds.length(slider(Params::DotSize)?),
+            end_size: slider(Params::EndSize)? / 100.0,
+            line_width: ds.length(slider(Params::LineWidth)?),
+            progress: slider(Params::Progress)? / 100.0,
+            start_color: [start.red, start.green, start.blue],
+            end_color: [end.red, end.green, end.blue],
+            background: match params.get(Params::Background)?.as_popup()?.value() {
+                2 => Background::Input,
+                _ => Background::Transparent,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        render_pattern(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct PatternParams {
+    pub pattern: Pattern,
+    pub count: u32,
+    /// Render pixels.
+    pub radius: f32,
+    /// Render pixels.
+    pub center: (f32, f32),
+    /// Degrees, clockwise on screen.
+    pub rotation: f32,
+    /// Degrees between phyllotaxis points.
+    pub divergence: f32,
+    /// Rose numerator and denominator.
+    pub petals: (u32, u32),
+    /// Lissajous X and Y frequency.
+    pub frequency: (u32, u32),
+    /// Degrees.
+    pub phase: f32,
+    pub draw: Draw,
+    /// Diameter of the first dot in render pixels.
+    pub dot_size: f32,
+    /// Last size relative to the first.
+    pub end_size: f32,
+    /// Render pixels.
+    pub line_width: f32,
+    /// 0..1.
+    pub progress: f32,
+    /// Encoded color of the first point.
+    pub start_color: [f32; 3],
+    /// Encoded color of the last point.
+    pub end_color: [f32; 3],
+    pub background: Background,
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Pattern positions relative to the center (unit radius, y down), in order.
+fn positions(p: &PatternParams, count: usize) -> Vec<(f32, f32)> {
+    let phase = finite_or(p.phase, 0.0).to_radians();
+    let last = (count - 1).max(1) as f32;
+    (0..count)
+        .map(|i| {
+            let s = i as f32 / last;
+            match p.pattern {
+                Pattern::Phyllotaxis => {
+                    // 位相は半径に比例してねじる (中心は動かさない)
+                    let divergence = finite_or(p.divergence, GOLDEN_ANGLE as f32).to_radians();
+                    let r = s.sqrt();
+                    let theta = (i as f32 * divergence).rem_euclid(TAU) + phase * r;
+                    (r * theta.cos(), r * theta.sin())
+                }
+                Pattern::Rose => {
+                    // k = n / d の曲線は d 周 (n と d の偶奇で半分) で閉じる
+                    let (n, d) = (p.petals.0.max(1), p.petals.1.max(1));
+                    let g = gcd(n, d);
+                    let (n, d) = (n / g, d / g);
+                    let turns = if n % 2 == 1 && d % 2 == 1 { 0.5 } else { 1.0 } * d as f32;
+                    let theta = s * TAU * turns;
+                    let r = (n as f32 / d as f32 * theta + phase).cos();
+                    (r * theta.cos(), r * theta.sin())
+                }
+                Pattern::Lissajous => {
+                    let (a, b) = (p.frequency.0.max(1) as f32, p.frequency.1.max(1) as f32);
+                    let t = s * TAU;
+                    ((a * t + phase).sin(), (b * t).sin())
+                }
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct Vertex {
+    x: f32,
+    y: f32,
+    radius: f32,
+    color: [f32; 3],
+}
+
+impl Vertex {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            radius: self.radius + (other.radius - self.radius) * t,
+            color: std::array::from_fn(|c| self.color[c] + (other.color[c] - self.color[c]) * t),
+        }
+    }
+}
+
+/// Pixel bounds of a shape around `a`..`b` reaching `reach` pixels further.
+fn bounds(
+    a: Vertex,
+    b: Vertex,
+    reach: f32,
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize, usize, usize)> {
+    let (x0, x1) = (a.x.min(b.x) - reach, a.x.max(b.x) + reach);
+    let (y0, y1) = (a.y.min(b.y) - reach, a.y.max(b.y) + reach);
+    if x1 < 0.0 || y1 < 0.0 || x0 >= width as f32 || y0 >= height as f32 {
+        return None;
+    }
+    Some((
+        x0.floor().max(0.0) as usize,
+        y0.floor().max(0.0) as usize,
+        (x1.ceil() as usize).min(width - 1),
+        (y1.ceil() as usize).min(height - 1),
+    ))
+}
+
+/// Rasterizes the capsule from `a` to `b`, keeping the strongest coverage per
+/// pixel so joints do not double up.
+fn draw_segment(
+    a: Vertex,
+    b: Vertex,
+    width: usize,
+    height: usize,
+    cover: &mut [f32],
+    colors: &mut [[f32; 3]],
+) {
+    let Some((x0, y0, x1, y1)) = bounds(a, b, a.radius.max(b.radius) + 1.0, width, height) else {
+        return;
+    };
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let (px, py) = (x as f32 + 0.5 - a.x, y as f32 + 0.5 - a.y);
+            let t = if len2 > 0.0 {
+                ((px * dx + py * dy) / len2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let d = (px - dx * t).hypot(py - dy * t);
+            let k = coverage(d, a.radius + (b.radius - a.radius) * t);
+            let i = y * width + x;
+            if k > cover[i] {
+                cover[i] = k;
+                colors[i] = a.lerp(b, t).color;
+            }
+        }
+    }
+}
+
+/// Composites an anti-aliased dot over the premultiplied `out`.
+fn draw_dot(v: Vertex, opacity: f32, out: &mut ImageBuf) {
+    let (width, height) = (out.width, out.height);
+    let Some((x0, y0, x1, y1)) = bounds(v, v, v.radius + 1.0, width, height) else {
+        return;
+    };
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let d = (x as f32 + 0.5 - v.x).hypot(y as f32 + 0.5 - v.y);
+            // 円の縁は半径方向の 1 画素幅で覆う割合にする
+            let k = (v.radius - d + 0.5).clamp(0.0, 1.0).min(v.radius * 2.0) * opacity;
+            if k <= 0.0 {
+                continue;
+            }
+            let dst = &mut out.data[y * width + x];
+            dst.red = v.color[0] * k + dst.red * (1.0 - k);
+            dst.green = v.color[1] * k + dst.green * (1.0 - k);
+            dst.blue = v.color[2] * k + dst.blue * (1.0 - k);
+            dst.alpha = k + dst.alpha * (1.0 - k);
+        }
+    }
+}
+
+pub fn render_pattern(input: &ImageBuf, p: &PatternParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+    // 背景は乗算済みで持ち、点と線を上に重ねる
+    if p.background == Background::Input {
+        for (dst, &src) in out.data.iter_mut().zip(&input.data) {
+            let a = finite_or(src.alpha, 0.0).clamp(0.0, 1.0);
+            *dst = PixelF32 {
+                red: finite_or(src.red, 0.0) * a,
+                green: finite_or(src.green, 0.0) * a,
+                blue: finite_or(src.blue, 0.0) * a,
+                alpha: a,
+            };
+        }
+    }
+
+    let count = p.count.clamp(2, MAX_COUNT as u32) as usize;
+    let radius = finite_or(p.radius, 0.0).clamp(0.0, 1e5);
+    let (cx, cy) = (
+        finite_or(p.center.0, 0.0).clamp(-1e5, 1e5),
+        finite_or(p.center.1, 0.0).clamp(-1e5, 1e5),
+    );
+    let (sin, cos) = finite_or(p.rotation, 0.0).to_radians().sin_cos();
+    let dot_size = finite_or(p.dot_size, 0.0).clamp(0.0, MAX_SIZE);
+    let line_width = finite_or(p.line_width, 0.0).clamp(0.0, MAX_SIZE);
+    let end_size = finite_or(p.end_size, 1.0).clamp(0.0, 10.0);
+    let start = Oklab::from_srgb(p.start_color.map(|c| finite_or(c, 0.0).clamp(0.0, 1.0)));
+    let end = Oklab::from_srgb(p.end_color.map(|c| finite_or(c, 0.0).clamp(0.0, 1.0)));
+
+    let last = (count - 1) as f32;
+    let vertices: Vec<Vertex> = positions(p, count)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (ux, uy))| {
+            let s = i as f32 / last;
+            let (x, y) = (ux * radius, uy * radius);
+            Vertex {
+                x: cx + x * cos - y * sin,
+                y: cy + x * sin + y * cos,
+                radius: 1.0 + (end_size - 1.0) * s,
+                color: start.lerp(end, s).to_srgb().map(|c| c.clamp(0.0, 1.0)),
+            }
+        })
+        .collect();
+
+    // 進行度は点の個数で数え、最後の 1 点は途中まで (線は途中まで伸び、点は薄く) 描く
+    let drawn = finite_or(p.progress, 1.0).clamp(0.0, 1.0) * count as f32;
+    let full = drawn.floor() as usize;
+    let partial = drawn - full as f32;
+
+    if p.draw != Draw::Dots && line_width > 0.0 {
+        let half = line_width * 0.5;
+        let scaled = |v: Vertex| Vertex {
+            radius: v.radius * half,
+            ..v
+        };
+        let mut cover = vec![0.0; width * height];
+        let mut colors = vec![[0.0; 3]; width * height];
+        for i in 1..count.min(full + 1) {
+            let (a, b) = (scaled(vertices[i - 1]), scaled(vertices[i]));
+            let b = if i == full { a.lerp(b, partial) } else { b };
+            draw_segment(a, b, width, height, &mut cover, &mut colors);
+        }
+        for ((dst, &k), color) in out.data.iter_mut().zip(&cover).zip(&colors) {
+            if k > 0.0 {
+                dst.red = color[0] * k + dst.red * (1.0 - k);
+                dst.green = color[1] * k + dst.green * (1.0 - k);
+                dst.blue = color[2] * k + dst.blue * (1.0 - k);
+                dst.alpha = k + dst.alpha * (1.0 - k);
+            }
+        }
+    }
+
+    if p.draw != Draw::Lines && dot_size > 0.0 {
+        let half = dot_size * 0.5;
+        for (i, v) in vertices.iter().enumerate().take((full + 1).min(count)) {
+            let opacity = if i == full { partial } else { 1.0 };
+            let v = Vertex {
+                radius: v.radius * half,
+                ..*v
+            };
+            draw_dot(v, opacity, &mut out);
+        }
+    }
+
+    for px in out.data.iter_mut() {
+        *px = unpremultiply(*px);
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ParametricPattern;
+
+impl RenderCore for ParametricPattern {
+    type Params = PatternParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        PatternParams {
+            pattern: match rng.popup(1, 3) {
+                2 => Pattern::Rose,
+                3 => Pattern::Lissajous,
+                _ => Pattern::Phyllotaxis,
+            },
+            count: rng.int(2, MAX_COUNT) as u32,
+            radius: rng.slider(0.0, 1000.0),
+            center: (rng.slider(-200.0, 400.0), rng.slider(-200.0, 400.0)),
+            rotation: rng.slider(-360.0, 360.0),
+            divergence: rng.slider(0.0, 360.0),
+            petals: (
+                rng.int(1, MAX_FREQUENCY) as u32,
+                rng.int(1, MAX_FREQUENCY) as u32,
+            ),
+            frequency: (
+                rng.int(1, MAX_FREQUENCY) as u32,
+                rng.int(1, MAX_FREQUENCY) as u32,
+            ),
+            phase: rng.slider(-360.0, 360.0),
+            draw: match rng.popup(1, 3) {
+                2 => Draw::Lines,
+                3 => Draw::DotsAndLines,
+                _ => Draw::Dots,
+            },
+            dot_size: rng.slider(0.0, 40.0),
+            end_size: rng.slider(0.0, 10.0),
+            line_width: rng.slider(0.0, 20.0),
+            progress: rng.slider(0.0, 1.0),
+            start_color: [rng.unit(), rng.unit(), rng.unit()],
+            end_color: [rng.unit(), rng.unit(), rng.unit()],
+            background: if rng.chance(0.5) {
+                Background::Transparent
+            } else {
+                Background::Input
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = render_pattern(input, params);
+    }
+}
@@ -0,0 +1,377 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::hash_unit;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Amount,
+    Center,
+    Size,
+    Feather,
+    Roundness,
+    Aspect,
+    HighlightProtection,
+    Grain,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Adds a vignette that keeps hue and chroma.";
+
+/// Superellipse exponent at `Roundness` 0 (nearly the frame's rectangle).
+const MAX_EXPONENT: f32 = 12.0;
+/// Lightness from which `Highlight Protection` starts to hold back the effect.
+const PROTECT_START: f32 = 0.5;
+/// Peak dither amplitude (lightness) at 100 % `Grain`.
+const GRAIN_STRENGTH: f32 = 0.02;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Amount: positive darkens the edges, negative brightens them
+        params.add(
+            Params::Amount,
+            tr("Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(-100.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Size: where the falloff starts, in percent of the distance to the frame edge
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(200.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(150.0);
+                d.set_default(60.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Feather: falloff width in the same units as Size
+        params.add(
+            Params::Feather,
+            tr("Feather"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(200.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(150.0);
+                d.set_default(70.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Roundness: 100 = ellipse, 0 = rounded rectangle
+        params.add(
+            Params::Roundness,
+            tr("Roundness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Aspect: 0 fits the shape to the frame; positive widens, negative heightens
+        params.add(
+            Params::Aspect,
+            tr("Aspect"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-100.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(-100.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Highlight Protection: keeps bright areas from being dimmed
+        params.add(
+            Params::HighlightProtection,
+            tr("Highlight Protection"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(0.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Grain: dither noise inside the falloff against banding
+        params.add(
+            Params::Grain,
+            tr("Grain"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(10.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_PerceptualVignette - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let percent = |id| -> Result<f32, Error> {
+            Ok(params.get(id)?.as_float_slider()?.value() as f32 / 100.0)
+        };
+
+        let settings = VignetteParams {
+            amount: percent(Params::Amount)?,
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            size: percent(Params::Size)?,
+            feather: percent(Params::Feather)?,
+            roundness: percent(Params::Roundness)?,
+            aspect: percent(Params::Aspect)?,
+            highlight_protection: percent(Params::HighlightProtection)?,
+            grain: percent(Params::Grain)?,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        vignette(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct VignetteParams {
+    /// -1..1; positive darkens toward the edges.
+    pub amount: f32,
+    /// Render pixels.
+    pub center: (f32, f32),
+    /// 0..2 distance (1 = frame edge) where the falloff starts.
+    pub size: f32,
+    /// 0..2 falloff width.
+    pub feather: f32,
+    /// 0..1, 1 = ellipse.
+    pub roundness: f32,
+    /// -1..1 horizontal (+) or vertical (-) stretch.
+    pub aspect: f32,
+    /// 0..1.
+    pub highlight_protection: f32,
+    /// 0..1 dither amount.
+    pub grain: f32,
+}
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Vignette strength 0..1 at every pixel.
+fn mask(width: usize, height: usize, p: &VignetteParams) -> Vec<f32> {
+    let cx = finite_or(p.center.0, width as f32 * 0.5);
+    let cy = finite_or(p.center.1, height as f32 * 0.5);
+    // 縦横比 0 でフレームに内接する形、正で横長・負で縦長
+    let stretch = 2f32.powf(finite_or(p.aspect, 0.0).clamp(-1.0, 1.0));
+    let rx = (width as f32 * 0.5).max(1.0) * stretch;
+    let ry = (height as f32 * 0.5).max(1.0) / stretch;
+    let roundness = finite_or(p.roundness, 1.0).clamp(0.0, 1.0);
+    let exponent = 2.0 + (MAX_EXPONENT - 2.0) * (1.0 - roundness) * (1.0 - roundness);
+    let start = finite_or(p.size, 0.6).clamp(0.0, 2.0);
+    let feather = finite_or(p.feather, 0.7).clamp(0.0, 2.0).max(1e-3);
+
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let u = ((x as f32 + 0.5 - cx) / rx).abs();
+            let v = ((y as f32 + 0.5 - cy) / ry).abs();
+            // 超楕円距離 (指数が大きいほど角の立った四角に近づく)
+            let m = u.max(v);
+            let d = if m > 0.0 {
+                m * ((u / m).powf(exponent) + (v / m).powf(exponent)).powf(1.0 / exponent)
+            } else {
+                0.0
+            };
+            out.push(smoothstep(start, start + feather, d));
+        }
+    }
+    out
+}
+
+pub fn vignette(input: &ImageBuf, p: &VignetteParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = input.clone();
+    let amount = finite_or(p.amount, 0.0).clamp(-1.0, 1.0);
+    let protection = finite_or(p.highlight_protection, 0.0).clamp(0.0, 1.0);
+    let grain = finite_or(p.grain, 0.0).clamp(0.0, 1.0) * GRAIN_STRENGTH;
+    if amount == 0.0 {
+        return out;
+    }
+
+    let mask = mask(w, h, p);
+    for (i, (px, &m)) in out.data.iter_mut().zip(&mask).enumerate() {
+        if m <= 0.0 {
+            continue;
+        }
+        let rgb = [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0));
+        let lab = Oklab::from_srgb(rgb);
+        let l = finite_or(lab.l, 0.0);
+
+        // 明るい部分ほど効きを弱める
+        let protect = 1.0 - protection * smoothstep(PROTECT_START, 1.0, l);
+        let k = (amount.abs() * m * protect).clamp(0.0, 1.0);
+        let mut target = if amount > 0.0 {
+            l * (1.0 - k)
+        } else {
+            l + (1.0 - l).max(0.0) * k
+        };
+        // 三角分布のディザーをフォールオフの途中ほど強く加える
+        if grain > 0.0 {
+            let (x, y) = ((i % w) as u32, (i / w) as u32);
+            let dither = hash_unit(x, y, 0x716e) + hash_unit(x, y, 0x716f) - 1.0;
+            target += dither * grain * 4.0 * m * (1.0 - m);
+        }
+
+        // a / b はそのまま (OKLCH の色相と彩度を保つ)
+        let rgb = Oklab { l: target, ..lab }
+            .to_srgb()
+            .map(|c| finite_or(c, 0.0));
+        px.red = rgb[0];
+        px.green = rgb[1];
+        px.blue = rgb[2];
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct PerceptualVignette;
+
+impl RenderCore for PerceptualVignette {
+    type Params = VignetteParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        VignetteParams {
+            amount: rng.slider(-1.0, 1.0),
+            center: (rng.slider(-64.0, 320.0), rng.slider(-64.0, 320.0)),
+            size: rng.slider(0.0, 2.0),
+            feather: rng.slider(0.0, 2.0),
+            roundness: rng.slider(0.0, 1.0),
+            aspect: rng.slider(-1.0, 1.0),
+            highlight_protection: rng.slider(0.0, 1.0),
+            grain: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = vignette(input, params);
+    }
+}
@@ -0,0 +1,743 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, coverage, unpremultiply};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    GridType,
+    Origin,
+    VanishingPoint1,
+    VanishingPoint2,
+    VanishingPoint3,
+    CellSize,
+    Rotation,
+    Extent,
+    Walls,
+    Subdivisions,
+    SubdivisionOpacity,
+    HorizonLine,
+    Crosshairs,
+    CrosshairSize,
+    LineWidth,
+    Color,
+    Opacity,
+    Background,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Draws a 2D or one-, two- or three-point perspective grid.";
+
+/// Upper bound of `Cell Size` (full-resolution pixels).
+const MAX_CELL_SIZE: f32 = 5000.0;
+const MAX_EXTENT: i32 = 200;
+const MAX_SUBDIVISIONS: i32 = 16;
+/// Upper bound of `Line Width` and `Crosshair Size` (full-resolution pixels).
+const MAX_LINE_WIDTH: f32 = 50.0;
+const MAX_CROSSHAIR_SIZE: f32 = 1000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridType {
+    /// Square cells in the layer plane, rotated around the origin.
+    Flat,
+    /// Ground plane receding to vanishing point 1, with horizontal crossings.
+    OnePoint,
+    /// Ground plane between vanishing points 1 and 2, with upright walls.
+    TwoPoint,
+    /// Like two-point, with the walls converging to vanishing point 3.
+    ThreePoint,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Transparent,
+    /// Drawn over the layer.
+    Input,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Grid Type: 2D shows Rotation, the perspective types their vanishing points (see update_param_visibility)
+        params.add_with_flags(
+            Params::GridType,
+            tr("Grid Type"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("2D"),
+                    tr("One-Point"),
+                    tr("Two-Point"),
+                    tr("Three-Point"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Origin: a grid corner; the near corner of the planes in perspective
+        params.add(
+            Params::Origin,
+            tr("Origin"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 80.0));
+            }),
+        )?;
+
+        params.add(
+            Params::VanishingPoint1,
+            tr("Vanishing Point 1"),
+            PointDef::setup(|d| {
+                d.set_default((10.0, 40.0));
+            }),
+        )?;
+
+        params.add(
+            Params::VanishingPoint2,
+            tr("Vanishing Point 2"),
+            PointDef::setup(|d| {
+                d.set_default((90.0, 40.0));
+            }),
+        )?;
+
+        params.add(
+            Params::VanishingPoint3,
+            tr("Vanishing Point 3"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, -200.0));
+            }),
+        )?;
+
+        // Cell Size: pixels of the cell at the origin
+        params.add(
+            Params::CellSize,
+            tr("Cell Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_CELL_SIZE);
+                d.set_slider_min(4.0);
+                d.set_slider_max(400.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Extent: cells drawn along each edge of the perspective planes
+        params.add(
+            Params::Extent,
+            tr("Extent"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_EXTENT);
+                d.set_slider_min(1);
+                d.set_slider_max(50);
+                d.set_default(10);
+            }),
+        )?;
+
+        // Walls: adds the two upright planes meeting at the origin
+        params.add(
+            Params::Walls,
+            tr("Walls"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        // Subdivisions: minor lines per cell, 1 = none
+        params.add(
+            Params::Subdivisions,
+            tr("Subdivisions"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_SUBDIVISIONS);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_SUBDIVISIONS);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Subdivision Opacity: of the minor lines, relative to Opacity
+        params.add(
+            Params::SubdivisionOpacity,
+            tr("Subdivision Opacity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(40.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Horizon Line: through the vanishing points on the horizon
+        params.add(
+            Params::HorizonLine,
+            tr("Horizon Line"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        // Crosshairs: marks the origin and the vanishing points
+        params.add(
+            Params::Crosshairs,
+            tr("Crosshairs"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Crosshair Size: arm length in pixels
+        params.add(
+            Params::CrosshairSize,
+            tr("Crosshair Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_CROSSHAIR_SIZE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(20.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LineWidth,
+            tr("Line Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_LINE_WIDTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Color,
+            tr("Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Opacity,
+            tr("Opacity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Background,
+            tr("Background"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Transparent"), tr("Input")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_PerspectiveGrid - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::GridType =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn grid_type(params: &Parameters<Params>) -> Result<GridType, Error> {
+    Ok(match params.get(Params::GridType)?.as_popup()?.value() {
+        2 => GridType::OnePoint,
+        3 => GridType::TwoPoint,
+        4 => GridType::ThreePoint,
+        _ => GridType::Flat,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let grid_type = grid_type(params)?;
+    let perspective = grid_type != GridType::Flat;
+    let visibility = [
+        (Params::VanishingPoint1, perspective),
+        (
+            Params::VanishingPoint2,
+            matches!(grid_type, GridType::TwoPoint | GridType::ThreePoint),
+        ),
+        (Params::VanishingPoint3, grid_type == GridType::ThreePoint),
+        (Params::Rotation, !perspective),
+        (Params::Extent, perspective),
+        (Params::Walls, perspective),
+        (Params::HorizonLine, perspective),
+    ];
+    for (id, visible) in visibility {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let point =
+            |id| -> Result<(f32, f32), Error> { Ok(ds.point(params.get(id)?.as_point()?.value())) };
+        let color = params.get(Params::Color)?.as_color()?.float_value()?;
+
+        let settings = GridParams {
+            grid_type: grid_type(params)?,
+            origin: point(Params::Origin)?,
+            vanishing_points: [
+                point(Params::VanishingPoint1)?,
+                point(Params::VanishingPoint2)?,
+                point(Params::VanishingPoint3)?,
+            ],
+            cell_size: ds.length(slider(Params::CellSize)?),
+            rotation: params.get(Params::Rotation)?.as_angle()?.value(),
+            extent: params.get(Params::Extent)?.as_slider()?.value() as u32,
+            walls: params.get(Params::Walls)?.as_checkbox()?.value(),
+            subdivisions: params.get(Params::Subdivisions)?.as_slider()?.value() as u32,
+            subdivision_opacity: slider(Params::SubdivisionOpacity)? / 100.0,
+            horizon_line: params.get(Params::HorizonLine)?.as_checkbox()?.value(),
+            crosshairs: params.get(Params::Crosshairs)?.as_checkbox()?.value(),
+            crosshair_size: ds.length(slider(Params::CrosshairSize)?),
+            line_width: ds.length(slider(Params::LineWidth)?),
+            color: [color.red, color.green, color.blue],
+            opacity: slider(Params::Opacity)? / 100.0,
+            background: match params.get(Params::Background)?.as_popup()?.value() {
+                2 => Background::Input,
+                _ => Background::Transparent,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        render_grid(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct GridParams {
+    pub grid_type: GridType,
+    /// Render pixels.
+    pub origin: (f32, f32),
+    /// Render pixels; only as many as the grid type uses are read.
+    pub vanishing_points: [(f32, f32); 3],
+    /// Render pixels.
+    pub cell_size: f32,
+    /// Degrees, clockwise on screen (2D grid only).
+    pub rotation: f32,
+    /// Cells along each edge of the perspective planes.
+    pub extent: u32,
+    pub walls: bool,
+    pub subdivisions: u32,
+    /// 0..1
+    pub subdivision_opacity: f32,
+    pub horizon_line: bool,
+    pub crosshairs: bool,
+    /// Render pixels.
+    pub crosshair_size: f32,
+    /// Render pixels.
+    pub line_width: f32,
+    pub color: [f32; 3],
+    /// 0..1
+    pub opacity: f32,
+    pub background: Background,
+}
+
+type Mat3 = [[f64; 3]; 3];
+/// Homogeneous screen direction of a plane axis: the vanishing point it runs
+/// toward, or a point at infinity (`w = 0`) for parallel lines.
+type Axis = [f64; 3];
+
+/// A grid plane seen through a homography: maps screen pixels back to plane
+/// coordinates in cells.
+struct Plane {
+    inverse: Mat3,
+    /// Cells drawn along each axis from 0, or unbounded.
+    extent: Option<f64>,
+}
+
+impl Plane {
+    /// Plane whose axes run along `u` and `v` from `origin`.
+    fn new(u: Axis, v: Axis, origin: (f64, f64), extent: Option<f64>) -> Option<Self> {
+        let m = [
+            [u[0], v[0], origin.0],
+            [u[1], v[1], origin.1],
+            [u[2], v[2], 1.0],
+        ];
+        let cofactor = |r: usize, c: usize| {
+            let (r0, r1) = ((r + 1) % 3, (r + 2) % 3);
+            let (c0, c1) = ((c + 1) % 3, (c + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let det = m[0][0] * cofactor(0, 0) + m[0][1] * cofactor(0, 1) + m[0][2] * cofactor(0, 2);
+        // 消失点と原点が一直線に並ぶと面がつぶれる
+        if !det.is_finite() || det.abs() < 1e-9 {
+            return None;
+        }
+        let inverse = std::array::from_fn(|r| std::array::from_fn(|c| cofactor(c, r) / det));
+        Some(Self { inverse, extent })
+    }
+
+    /// Screen distance in pixels from (`x`, `y`) to the plane line where
+    /// coordinate `axis` (0 = u, 1 = v) equals `c`.
+    fn distance(&self, x: f64, y: f64, axis: usize, c: f64) -> f64 {
+        // 射影変換は直線を直線に写すので、面上の u = c は画面上でも直線になる
+        let m = &self.inverse;
+        let line: [f64; 3] = std::array::from_fn(|i| m[axis][i] - c * m[2][i]);
+        (line[0] * x + line[1] * y + line[2]).abs() / line[0].hypot(line[1]).max(1e-12)
+    }
+
+    /// Coverage of the major and minor lines at pixel center (`x`, `y`) for
+    /// cells of `cell` pixels at the origin. Lines closer than a few pixels fade
+    /// to the density they have at the origin, so the grid settles into an even
+    /// tone toward the horizon, like a mipmapped texture.
+    fn coverage(&self, x: f64, y: f64, radius: f32, cell: f32, subdivisions: u32) -> (f32, f32) {
+        let m = &self.inverse;
+        let q: [f64; 3] = std::array::from_fn(|r| m[r][0] * x + m[r][1] * y + m[r][2]);
+        // 同次座標の w が負の画素は地平線の向こう側で、面の上にない
+        if q[2] <= 1e-12 {
+            return (0.0, 0.0);
+        }
+        let uv = [q[0] / q[2], q[1] / q[2]];
+        // 面の範囲の外なら、近い方の縁の線までの距離
+        let past = |axis: usize| match self.extent {
+            Some(_) if uv[axis] < 0.0 => self.distance(x, y, axis, 0.0),
+            Some(n) if uv[axis] > n => self.distance(x, y, axis, n),
+            _ => 0.0,
+        };
+        let past = [past(0), past(1)];
+
+        // axis 一定の線を 1 セルに scale 本ずつ並べたときの被覆率
+        let family = |axis: usize, scale: f64| {
+            let c = uv[axis] * scale;
+            let k = self
+                .extent
+                .map_or(c.round(), |n| c.round().clamp(0.0, n * scale));
+            let along = past[1 - axis];
+            let d = self.distance(x, y, axis, k / scale).hypot(along) as f32;
+            let sharp = coverage(d, radius);
+
+            // 商の微分で、1 画素あたりに進むセル数から線の間隔を求める
+            let gradient = ((m[axis][0] - uv[axis] * m[2][0]) / q[2])
+                .hypot((m[axis][1] - uv[axis] * m[2][1]) / q[2]);
+            let spacing = (1.0 / (gradient * scale).max(1e-12)) as f32;
+            let fade = ((spacing - 2.0) / 2.0).clamp(0.0, 1.0);
+            if fade >= 1.0 {
+                return sharp;
+            }
+            // 平均の濃さは原点のセルでの線の濃さを上限にして、地平線が塗りつぶされないようにする。
+            // 線の並ぶ範囲の中だけに置き、縁は 1 画素で落とす
+            let near = 2.0 * radius * scale as f32 / cell;
+            let inside = (1.0 - past[axis].hypot(along) as f32).clamp(0.0, 1.0);
+            let mean = (2.0 * radius / spacing).min(near).min(1.0) * inside;
+            mean + (sharp - mean) * fade
+        };
+
+        let major = family(0, 1.0).max(family(1, 1.0));
+        if subdivisions <= 1 {
+            return (major, 0.0);
+        }
+        let s = subdivisions as f64;
+        (major, family(0, s).max(family(1, s)))
+    }
+}
+
+/// Plane axis from `origin` toward the
+/// vanishing point `vp`, whose first cell spans `cell` pixels.
+fn toward(origin: (f64, f64), vp: (f64, f64), cell: f64) -> Axis {
+    let length = (vp.0 - origin.0).hypot(vp.1 - origin.1);
+    // u = 1 の点は原点から消失点までの t の位置に来る (t = a / (1 + a))
+    let t = (cell / length.max(1e-9)).min(0.95);
+    let a = t / (1.0 - t);
+    [a * vp.0, a * vp.1, a]
+}
+
+/// Plane axis of parallel lines along
+/// (`dx`, `dy`), `cell` pixels per cell.
+fn parallel(dx: f64, dy: f64, cell: f64) -> Axis {
+    [dx * cell, dy * cell, 0.0]
+}
+
+pub fn render_grid(input: &ImageBuf, p: &GridParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+
+    let clamp_point = |(x, y): (f32, f32)| {
+        (
+            finite_or(x, 0.0).clamp(-1e6, 1e6) as f64,
+            finite_or(y, 0.0).clamp(-1e6, 1e6) as f64,
+        )
+    };
+    let origin = clamp_point(p.origin);
+    let [vp1, vp2, vp3] = p.vanishing_points.map(clamp_point);
+    let cell = finite_or(p.cell_size, 50.0).clamp(1.0, MAX_CELL_SIZE) as f64;
+    let extent = Some(p.extent.clamp(1, MAX_EXTENT as u32) as f64);
+    let up = parallel(0.0, -1.0, cell);
+
+    // 各面の 2 軸: 消失点へ向かう軸は有限の点、平行線の軸は無限遠点 (w = 0)
+    let axes: Vec<(Axis, Axis)> = match p.grid_type {
+        GridType::Flat => {
+            let (sin, cos) = (finite_or(p.rotation, 0.0) as f64).to_radians().sin_cos();
+            vec![(parallel(cos, sin, cell), parallel(-sin, cos, cell))]
+        }
+        GridType::OnePoint => {
+            let (across, depth) = (parallel(1.0, 0.0, cell), toward(origin, vp1, cell));
+            vec![(across, depth), (across, up), (depth, up)]
+        }
+        GridType::TwoPoint => {
+            let (a, b) = (toward(origin, vp1, cell), toward(origin, vp2, cell));
+            vec![(a, b), (a, up), (b, up)]
+        }
+        GridType::ThreePoint => {
+            let (a, b, c) = (
+                toward(origin, vp1, cell),
+                toward(origin, vp2, cell),
+                toward(origin, vp3, cell),
+            );
+            vec![(a, b), (a, c), (b, c)]
+        }
+    };
+    let vanishing = match p.grid_type {
+        GridType::Flat => Vec::new(),
+        GridType::OnePoint => vec![vp1],
+        GridType::TwoPoint => vec![vp1, vp2],
+        GridType::ThreePoint => vec![vp1, vp2, vp3],
+    };
+    let perspective = p.grid_type != GridType::Flat;
+    let planes: Vec<Plane> = axes
+        .into_iter()
+        .take(if perspective && p.walls { 3 } else { 1 })
+        .filter_map(|(u, v)| Plane::new(u, v, origin, if perspective { extent } else { None }))
+        .collect();
+
+    // 水平線は消失点 1 を通り、2 点目があればそれとも結ぶ
+    let horizon = (perspective && p.horizon_line).then(|| {
+        let (dx, dy) = match vanishing.get(1) {
+            Some(&(x, y)) if (x - vp1.0).hypot(y - vp1.1) > 1e-6 => (x - vp1.0, y - vp1.1),
+            _ => (1.0, 0.0),
+        };
+        let length = dx.hypot(dy);
+        (vp1, (dx / length, dy / length))
+    });
+    let marks: Vec<(f64, f64)> = if p.crosshairs {
+        std::iter::once(origin).chain(vanishing).collect()
+    } else {
+        Vec::new()
+    };
+
+    let radius = finite_or(p.line_width, 1.0).clamp(0.0, MAX_LINE_WIDTH) * 0.5;
+    let arm = finite_or(p.crosshair_size, 0.0).clamp(0.0, MAX_CROSSHAIR_SIZE) as f64;
+    let subdivisions = p.subdivisions.clamp(1, MAX_SUBDIVISIONS as u32);
+    let minor_opacity = finite_or(p.subdivision_opacity, 0.0).clamp(0.0, 1.0);
+    let opacity = finite_or(p.opacity, 1.0).clamp(0.0, 1.0);
+    let color = p.color.map(|c| finite_or(c, 0.0).clamp(0.0, 1.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let (fx, fy) = (x as f64 + 0.5, y as f64 + 0.5);
+            let mut k = 0.0f32;
+            for plane in &planes {
+                let (major, minor) = plane.coverage(fx, fy, radius, cell as f32, subdivisions);
+                k = k.max(major).max(minor * minor_opacity);
+            }
+            if let Some(((hx, hy), (dx, dy))) = horizon {
+                let d = ((fx - hx) * dy - (fy - hy) * dx).abs();
+                k = k.max(coverage(d as f32, radius));
+            }
+            for &(cx, cy) in &marks {
+                let (dx, dy) = ((fx - cx).abs(), (fy - cy).abs());
+                let d = ((dx - arm).max(0.0).hypot(dy)).min((dy - arm).max(0.0).hypot(dx));
+                k = k.max(coverage(d as f32, radius));
+            }
+
+            // 背景は乗算済みで扱い、線を上に重ねる
+            let dst = if p.background == Background::Input {
+                let src = input.data[y * width + x];
+                let a = finite_or(src.alpha, 0.0).clamp(0.0, 1.0);
+                PixelF32 {
+                    red: finite_or(src.red, 0.0) * a,
+                    green: finite_or(src.green, 0.0) * a,
+                    blue: finite_or(src.blue, 0.0) * a,
+                    alpha: a,
+                }
+            } else {
+                PixelF32 {
+                    red: 0.0,
+                    green: 0.0,
+                    blue: 0.0,
+                    alpha: 0.0,
+                }
+            };
+            let k = k * opacity;
+            out.data[y * width + x] = unpremultiply(PixelF32 {
+                red: color[0] * k + dst.red * (1.0 - k),
+                green: color[1] * k + dst.green * (1.0 - k),
+                blue: color[2] * k + dst.blue * (1.0 - k),
+                alpha: k + dst.alpha * (1.0 - k),
+            });
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct PerspectiveGrid;
+
+impl RenderCore for PerspectiveGrid {
+    type Params = GridParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let point = |rng: &mut Rng| (rng.slider(-500.0, 800.0), rng.slider(-500.0, 800.0));
+        GridParams {
+            grid_type: match rng.popup(1, 4) {
+                2 => GridType::OnePoint,
+                3 => GridType::TwoPoint,
+                4 => GridType::ThreePoint,
+                _ => GridType::Flat,
+            },
+            origin: point(rng),
+            vanishing_points: [point(rng), point(rng), point(rng)],
+            cell_size: rng.slider(1.0, 400.0),
+            rotation: rng.slider(-360.0, 360.0),
+            extent: rng.popup(1, MAX_EXTENT) as u32,
+            walls: rng.chance(0.5),
+            subdivisions: rng.popup(1, MAX_SUBDIVISIONS) as u32,
+            subdivision_opacity: rng.unit(),
+            horizon_line: rng.chance(0.5),
+            crosshairs: rng.chance(0.5),
+            crosshair_size: rng.slider(0.0, 100.0),
+            line_width: rng.slider(0.0, 10.0),
+            color: [rng.unit(), rng.unit(), rng.unit()],
+            opacity: rng.unit(),
+            background: if rng.chance(0.5) {
+                Background::Transparent
+            } else {
+                Background::Input
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = render_grid(input, params);
+    }
+}
@@ -0,0 +1,506 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{hash, unit};
+use utils::color::{Oklab, luminance};
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Angle,
+    MaskMode,
+    LowerThreshold,
+    UpperThreshold,
+    SortKey,
+    Reverse,
+    MinSpanLength,
+    MaxSpanLength,
+    Randomness,
+    Seed,
+    AnimateSeed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Sorts pixel spans along an angle where a luminance or edge mask falls within thresholds.";
+
+#[derive(Clone, Copy, Debug)]
+pub enum MaskMode {
+    Luminance,
+    Edge,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum SortKey {
+    Luminance,
+    Hue,
+    Saturation,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Angle,
+            tr("Angle"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::MaskMode,
+            tr("Mask Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Luminance"), tr("Edge")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LowerThreshold,
+            tr("Lower Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.25);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::UpperThreshold,
+            tr("Upper Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.8);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::SortKey,
+            tr("Sort Key"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Luminance"),
+                    tr("Hue"),
+                    tr("Saturation"),
+                    tr("Red"),
+                    tr("Green"),
+                    tr("Blue"),
+                    tr("Alpha"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Reverse,
+            tr("Reverse"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::MinSpanLength,
+            tr("Min Span Length"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(1024);
+                d.set_slider_min(1);
+                d.set_slider_max(256);
+                d.set_default(4);
+            }),
+        )?;
+
+        params.add(
+            Params::MaxSpanLength,
+            tr("Max Span Length"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(8192);
+                d.set_slider_min(1);
+                d.set_slider_max(2048);
+                d.set_default(512);
+            }),
+        )?;
+
+        params.add(
+            Params::Randomness,
+            tr("Randomness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        params.add(
+            Params::AnimateSeed,
+            tr("Animate Seed"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+                d.set_label(tr("Per Frame"));
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_PixelSort - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // スパン長はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let span_len = |id| -> Result<usize, Error> {
+            let v = ds.length(params.get(id)?.as_slider()?.value() as f32);
+            Ok((v.round() as usize).max(1))
+        };
+
+        let settings = PixelSortParams {
+            angle: params.get(Params::Angle)?.as_angle()?.value(),
+            mask: match params.get(Params::MaskMode)?.as_popup()?.value() {
+                2 => MaskMode::Edge,
+                _ => MaskMode::Luminance,
+            },
+            lower: params
+                .get(Params::LowerThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            upper: params
+                .get(Params::UpperThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            key: match params.get(Params::SortKey)?.as_popup()?.value() {
+                2 => SortKey::Hue,
+                3 => SortKey::Saturation,
+                4 => SortKey::Red,
+                5 => SortKey::Green,
+                6 => SortKey::Blue,
+                7 => SortKey::Alpha,
+                _ => SortKey::Luminance,
+            },
+            reverse: params.get(Params::Reverse)?.as_checkbox()?.value(),
+            min_len: span_len(Params::MinSpanLength)?,
+            max_len: span_len(Params::MaxSpanLength)?,
+            randomness: params.get(Params::Randomness)?.as_float_slider()?.value() as f32,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            animate_seed: params.get(Params::AnimateSeed)?.as_checkbox()?.value(),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let frame = in_data.current_frame() as u32;
+        pixel_sort(&input, &settings, frame).write_to_layer(&mut out_layer)
+    }
+}
+
+#[derive(Debug)]
+pub struct PixelSortParams {
+    /// Sort direction in degrees.
+    pub angle: f32,
+    pub mask: MaskMode,
+    pub lower: f32,
+    pub upper: f32,
+    pub key: SortKey,
+    pub reverse: bool,
+    pub min_len: usize,
+    pub max_len: usize,
+    /// 0..1; shortens spans by a random fraction of `max_len`.
+    pub randomness: f32,
+    pub seed: u32,
+    /// Re-roll the randomness every frame instead of holding it.
+    pub animate_seed: bool,
+}
+
+/// Sorts every masked span of `input` along the sort direction.
+pub fn pixel_sort(input: &ImageBuf, p: &PixelSortParams, frame: u32) -> ImageBuf {
+    let mut output = input.clone();
+    if input.is_empty() {
+        return output;
+    }
+    let mask = mask_values(input, p.mask);
+    let seed = if p.animate_seed {
+        hash(p.seed, frame, 0x5eed)
+    } else {
+        p.seed
+    };
+
+    let mut keyed: Vec<(f32, PixelF32)> = Vec::new();
+    for (line_id, line) in lines(input.width, input.height, p.angle).enumerate() {
+        let mut i = 0;
+        while i < line.len() {
+            if !in_range(mask[line[i]], p.lower, p.upper) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < line.len() && in_range(mask[line[i]], p.lower, p.upper) {
+                i += 1;
+            }
+
+            // 連続区間を最大長（ランダムに短縮）ごとに分割してソート
+            let mut pos = start;
+            while pos < i {
+                let r = unit(hash(seed, line_id as u32, pos as u32));
+                let limit =
+                    ((p.max_len as f32 * (1.0 - p.randomness.clamp(0.0, 1.0) * r)) as usize).max(1);
+                let end = (pos + limit).min(i);
+                let span = &line[pos..end];
+                if span.len() >= p.min_len && span.len() > 1 {
+                    keyed.clear();
+                    keyed.extend(
+                        span.iter()
+                            .map(|&idx| (sort_key(input.data[idx], p.key), input.data[idx])),
+                    );
+                    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    if p.reverse {
+                        keyed.reverse();
+                    }
+                    for (&idx, (_, px)) in span.iter().zip(&keyed) {
+                        output.data[idx] = *px;
+                    }
+                }
+                pos = end;
+            }
+        }
+    }
+    output
+}
+
+fn in_range(v: f32, lower: f32, upper: f32) -> bool {
+    v >= lower && v <= upper
+}
+
+fn mask_values(input: &ImageBuf, mode: MaskMode) -> Vec<f32> {
+    let lum: Vec<f32> = input.data.iter().map(|p| luminance(*p)).collect();
+    match mode {
+        MaskMode::Luminance => lum,
+        MaskMode::Edge => {
+            let (w, h) = (input.width as isize, input.height as isize);
+            let at = |x: isize, y: isize| lum[(y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) as usize];
+            let mut out = Vec::with_capacity(lum.len());
+            for y in 0..h {
+                for x in 0..w {
+                    let gx = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                        - at(x - 1, y - 1)
+                        - 2.0 * at(x - 1, y)
+                        - at(x - 1, y + 1))
+                        * 0.25;
+                    let gy = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                        - at(x - 1, y - 1)
+                        - 2.0 * at(x, y - 1)
+                        - at(x + 1, y - 1))
+                        * 0.25;
+                    out.push(gx.hypot(gy));
+                }
+            }
+            out
+        }
+    }
+}
+
+fn sort_key(px: PixelF32, key: SortKey) -> f32 {
+    match key {
+        SortKey::Luminance => luminance(px),
+        SortKey::Hue => {
+            let lch = Oklab::from_srgb([px.red, px.green, px.blue]).to_oklch();
+            lch.h.rem_euclid(std::f32::consts::TAU)
+        }
+        SortKey::Saturation => Oklab::from_srgb([px.red, px.green, px.blue]).to_oklch().c,
+        SortKey::Red => px.red,
+        SortKey::Green => px.green,
+        SortKey::Blue => px.blue,
+        SortKey::Alpha => px.alpha,
+    }
+}
+
+/// Pixel indices of the rasterized lines along `angle` (degrees), each ordered in the
+/// sort direction. Every pixel belongs to exactly one line.
+fn lines(w: usize, h: usize, angle: f32) -> impl Iterator<Item = Vec<usize>> {
+    let rad = if angle.is_finite() {
+        angle.to_radians()
+    } else {
+        0.0
+    };
+    let (c, s) = (rad.cos(), rad.sin());
+    // 主軸方向に 1px ずつ進み、副軸は丸めたオフセットで決める
+    let horizontal = c.abs() >= s.abs();
+    let (major, minor, slope, backwards) = if horizontal {
+        (w, h, s / c, c < 0.0)
+    } else {
+        (h, w, c / s, s < 0.0)
+    };
+    let offsets: Vec<isize> = (0..major)
+        .map(|i| (i as f32 * slope).round() as isize)
+        .collect();
+    let min_o = offsets.iter().copied().min().unwrap_or(0);
+    let max_o = offsets.iter().copied().max().unwrap_or(0);
+
+    (-max_o..minor as isize - min_o).map(move |k| {
+        let mut line: Vec<usize> = (0..major)
+            .filter_map(|i| {
+                let m = k + offsets[i];
+                if m < 0 || m >= minor as isize {
+                    return None;
+                }
+                let m = m as usize;
+                Some(if horizontal { m * w + i } else { i * w + m })
+            })
+            .collect();
+        if backwards {
+            line.reverse();
+        }
+        line
+    })
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct PixelSort;
+
+impl RenderCore for PixelSort {
+    type Params = PixelSortParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        PixelSortParams {
+            angle: rng.slider(-720.0, 720.0),
+            mask: if rng.chance(0.5) {
+                MaskMode::Luminance
+            } else {
+                MaskMode::Edge
+            },
+            lower: rng.slider(0.0, 1.0),
+            upper: rng.slider(0.0, 1.0),
+            key: match rng.popup(1, 7) {
+                2 => SortKey::Hue,
+                3 => SortKey::Saturation,
+                4 => SortKey::Red,
+                5 => SortKey::Green,
+                6 => SortKey::Blue,
+                7 => SortKey::Alpha,
+                _ => SortKey::Luminance,
+            },
+            reverse: rng.chance(0.5),
+            min_len: rng.popup(1, 1024) as usize,
+            max_len: rng.popup(1, 8192) as usize,
+            randomness: rng.slider(0.0, 1.0),
+            seed: rng.popup(0, 10000) as u32,
+            animate_seed: rng.chance(0.5),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = pixel_sort(input, params, frame);
+    }
+}
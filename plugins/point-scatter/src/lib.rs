@@ -0,0 +1,660 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{hash, hash_unit};
+use utils::density::{density_at, density_map};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::poisson::PoissonGrid;
+use utils::sampling::{self, Downsample, Edge, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    SourceLayer,
+    DensityLayer,
+    InvertDensity,
+    Distribution,
+    Spacing,
+    GridJitter,
+    Scale,
+    ScaleJitter,
+    Rotation,
+    RotationJitter,
+    BlendMode,
+    StampsOnly,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Stamps a source layer at scattered points.";
+
+/// Checkout id of the source layer (the input uses 0).
+const SOURCE_CHECKOUT_ID: i32 = 1;
+/// Checkout id of the density layer.
+const DENSITY_CHECKOUT_ID: i32 = 2;
+/// Bounds of `Spacing` (full-resolution pixels).
+const MIN_SPACING: f32 = 2.0;
+const MAX_SPACING: f32 = 1000.0;
+/// Upper bound of `Scale` in percent.
+const MAX_SCALE: f32 = 1000.0;
+/// Dart-throwing candidates per spacing-sized cell.
+const CANDIDATES_PER_CELL: f32 = 6.0;
+/// Largest Poisson radius as a multiple of the spacing; sparser areas are thinned randomly.
+const MAX_RADIUS_SCALE: f32 = 4.0;
+/// Upper bound of the dart-throwing candidates per frame.
+const MAX_CANDIDATES: usize = 1 << 22;
+/// Radius of the built-in soft dot stamped without a source layer.
+const DOT_RADIUS: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Distribution {
+    /// Dart throwing with a density-dependent minimum distance.
+    PoissonDisk,
+    /// One candidate per spacing cell, kept with the local density as probability.
+    JitteredGrid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    Normal,
+    Add,
+    Screen,
+    Multiply,
+    Lighten,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Source Layer: stamped centered on each point; without one a soft white dot is used
+        params.add(Params::SourceLayer, tr("Source Layer"), LayerDef::new())?;
+
+        // Density Layer: luminance times alpha sets how many stamps land there; without one the density is uniform
+        params.add(Params::DensityLayer, tr("Density Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::InvertDensity,
+            tr("Invert Density"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Distribution,
+            tr("Distribution"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Poisson Disk"), tr("Jittered Grid")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Spacing: distance between stamps at full density in pixels
+        params.add(
+            Params::Spacing,
+            tr("Spacing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(MIN_SPACING);
+                d.set_valid_max(MAX_SPACING);
+                d.set_slider_min(MIN_SPACING);
+                d.set_slider_max(200.0);
+                d.set_default(60.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Grid Jitter: random offset of jittered-grid points as a percentage of the spacing
+        params.add(
+            Params::GridJitter,
+            tr("Grid Jitter"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Scale: stamp size relative to the source layer
+        params.add(
+            Params::Scale,
+            tr("Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SCALE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Scale Jitter: each stamp shrinks by a random share of up to this percentage
+        params.add(
+            Params::ScaleJitter,
+            tr("Scale Jitter"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Rotation,
+            tr("Rotation"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Rotation Jitter: each stamp turns by a random angle up to ± this many degrees
+        params.add(
+            Params::RotationJitter,
+            tr("Rotation Jitter"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(180.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(180.0);
+                d.set_default(180.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BlendMode,
+            tr("Blend Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Normal"),
+                    tr("Add"),
+                    tr("Screen"),
+                    tr("Multiply"),
+                    tr("Lighten"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Stamps Only: drops the layer and keeps the stamps on transparency
+        params.add(
+            Params::StampsOnly,
+            tr("Stamps Only"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_PointScatter - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではソース・密度レイヤーをパラメータから直接取得する
+                let mut layer = |id| -> Result<Option<ImageBuf>, Error> {
+                    Ok(params
+                        .checkout_at(
+                            id,
+                            Some(in_data.current_time()),
+                            Some(in_data.time_step()),
+                            Some(in_data.time_scale()),
+                        )?
+                        .as_layer()?
+                        .value()
+                        .map(|layer| ImageBuf::from_layer(&layer)))
+                };
+                let source = layer(Params::SourceLayer)?;
+                let density = layer(Params::DensityLayer)?;
+                let layers = Layers {
+                    source: source.as_ref(),
+                    density: density.as_ref(),
+                };
+                self.do_render(in_data, in_layer, layers, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                for (id, checkout_id) in [
+                    (Params::SourceLayer, SOURCE_CHECKOUT_ID),
+                    (Params::DensityLayer, DENSITY_CHECKOUT_ID),
+                ] {
+                    if let Some(index) = params.index(id) {
+                        let _ = extra.callbacks().checkout_layer(
+                            index as i32,
+                            checkout_id,
+                            &req,
+                            in_data.current_time(),
+                            in_data.time_step(),
+                            in_data.time_scale(),
+                        );
+                    }
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let source = cb
+                    .checkout_layer_pixels(SOURCE_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let density = cb
+                    .checkout_layer_pixels(DENSITY_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    let layers = Layers {
+                        source: source.as_ref(),
+                        density: density.as_ref(),
+                    };
+                    self.do_render(in_data, in_layer, layers, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(SOURCE_CHECKOUT_ID as u32)?;
+                cb.checkin_layer_pixels(DENSITY_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        layers: Layers,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = ScatterParams {
+            invert_density: params.get(Params::InvertDensity)?.as_checkbox()?.value(),
+            distribution: match params.get(Params::Distribution)?.as_popup()?.value() {
+                2 => Distribution::JitteredGrid,
+                _ => Distribution::PoissonDisk,
+            },
+            spacing: ds.length(slider(Params::Spacing)?),
+            grid_jitter: slider(Params::GridJitter)? / 100.0,
+            scale: slider(Params::Scale)? / 100.0,
+            scale_jitter: slider(Params::ScaleJitter)? / 100.0,
+            rotation: params.get(Params::Rotation)?.as_angle()?.value(),
+            rotation_jitter: slider(Params::RotationJitter)?,
+            blend_mode: match params.get(Params::BlendMode)?.as_popup()?.value() {
+                2 => BlendMode::Add,
+                3 => BlendMode::Screen,
+                4 => BlendMode::Multiply,
+                5 => BlendMode::Lighten,
+                _ => BlendMode::Normal,
+            },
+            stamps_only: params.get(Params::StampsOnly)?.as_checkbox()?.value(),
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            // 内蔵のドットは解像度に合わせて縮める
+            dot_radius: ds.length(DOT_RADIUS),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        scatter(&input, layers, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Optional source and density layers.
+#[derive(Clone, Copy, Default)]
+pub struct Layers<'a> {
+    pub source: Option<&'a ImageBuf>,
+    pub density: Option<&'a ImageBuf>,
+}
+
+#[derive(Debug)]
+pub struct ScatterParams {
+    pub invert_density: bool,
+    pub distribution: Distribution,
+    /// Render pixels.
+    pub spacing: f32,
+    /// Fraction of the spacing.
+    pub grid_jitter: f32,
+    /// 1 = source size.
+    pub scale: f32,
+    /// 0..1.
+    pub scale_jitter: f32,
+    /// Degrees, clockwise on screen.
+    pub rotation: f32,
+    /// Degrees either way.
+    pub rotation_jitter: f32,
+    pub blend_mode: BlendMode,
+    pub stamps_only: bool,
+    pub seed: u32,
+    /// Render pixels; radius of the dot used without a source layer.
+    pub dot_radius: f32,
+}
+
+/// A stamp position; `key` seeds its scale and rotation.
+struct Point {
+    x: f32,
+    y: f32,
+    key: u32,
+}
+
+/// Dart throwing with a density-dependent radius: a candidate is kept when no
+/// earlier point lies within `spacing / sqrt(density)` of it.
+fn poisson_points(
+    density: &[f32],
+    width: usize,
+    height: usize,
+    spacing: f32,
+    seed: u32,
+) -> Vec<Point> {
+    let (w, h) = (width as f32, height as f32);
+    let cells = (w / spacing) * (h / spacing);
+    let count = ((cells * CANDIDATES_PER_CELL) as usize).min(MAX_CANDIDATES);
+    let min_density = 1.0 / (MAX_RADIUS_SCALE * MAX_RADIUS_SCALE);
+
+    // 近傍探索用の格子 (セルは最小半径)
+    let mut grid = PoissonGrid::new(w, h, spacing);
+    let mut points: Vec<Point> = Vec::new();
+
+    for i in 0..count as u32 {
+        let (x, y) = (hash_unit(seed, i, 0) * w, hash_unit(seed, i, 1) * h);
+        let d = density_at(density, width, height, x, y);
+        if d <= 0.0 {
+            continue;
+        }
+        // 最大半径より疎な領域はランダムに間引いて密度を保つ
+        if d < min_density && hash_unit(seed, i, 2) * min_density >= d {
+            continue;
+        }
+        let r = spacing / d.max(min_density).sqrt();
+        if grid.is_free(x, y, r) {
+            grid.insert(x, y);
+            points.push(Point {
+                x,
+                y,
+                key: hash(seed, i, 0x57a),
+            });
+        }
+    }
+    points
+}
+
+/// One jittered candidate per spacing cell, kept with the local density as probability.
+fn grid_points(
+    density: &[f32],
+    width: usize,
+    height: usize,
+    spacing: f32,
+    jitter: f32,
+    seed: u32,
+) -> Vec<Point> {
+    let (nx, ny) = (
+        (width as f32 / spacing).ceil() as usize,
+        (height as f32 / spacing).ceil() as usize,
+    );
+    let mut points = Vec::new();
+    for j in 0..ny {
+        for i in 0..nx {
+            let key = hash(seed, i as u32, j as u32);
+            let offset = |salt: u32| (hash_unit(key, salt, 0x9e1) - 0.5) * jitter;
+            let x = (i as f32 + 0.5 + offset(0)) * spacing;
+            let y = (j as f32 + 0.5 + offset(1)) * spacing;
+            if hash_unit(key, 2, 0x9e1) < density_at(density, width, height, x, y) {
+                points.push(Point { x, y, key });
+            }
+        }
+    }
+    points
+}
+
+/// Soft white dot of radius `r` (premultiplied).
+fn dot(r: f32) -> ImageBuf {
+    let side = (2.0 * r).ceil().max(1.0) as usize;
+    let mut out = ImageBuf::new(side, side);
+    let c = side as f32 * 0.5;
+    for y in 0..side {
+        for x in 0..side {
+            let d = ((x as f32 + 0.5 - c).powi(2) + (y as f32 + 0.5 - c).powi(2)).sqrt() / r;
+            // 中心から縁へ滑らかに消える
+            let a = (1.0 - d).clamp(0.0, 1.0);
+            let a = a * a * (3.0 - 2.0 * a);
+            out.set(
+                x,
+                y,
+                PixelF32 {
+                    red: a,
+                    green: a,
+                    blue: a,
+                    alpha: a,
+                },
+            );
+        }
+    }
+    out
+}
+
+/// Composites premultiplied `src` onto premultiplied `dst`.
+fn blend(dst: PixelF32, src: PixelF32, mode: BlendMode) -> PixelF32 {
+    let (da, sa) = (dst.alpha, src.alpha);
+    // 重なった部分だけにモードを適用し、残りは通常の重ね合わせと同じにする
+    let channel = |d: f32, s: f32| match mode {
+        BlendMode::Normal => s + d * (1.0 - sa),
+        BlendMode::Add => d + s,
+        BlendMode::Screen => d + s - d * s,
+        BlendMode::Multiply => s * d + s * (1.0 - da) + d * (1.0 - sa),
+        BlendMode::Lighten => (s * da).max(d * sa) + s * (1.0 - da) + d * (1.0 - sa),
+    };
+    PixelF32 {
+        red: channel(dst.red, src.red),
+        green: channel(dst.green, src.green),
+        blue: channel(dst.blue, src.blue),
+        alpha: sa + da * (1.0 - sa),
+    }
+}
+
+pub fn scatter(input: &ImageBuf, layers: Layers, p: &ScatterParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    if input.is_empty() {
+        return ImageBuf::new(width, height);
+    }
+
+    // 乗算済みで合成し、最後にストレートへ戻す
+    let mut out = if p.stamps_only {
+        ImageBuf::new(width, height)
+    } else {
+        sampling::premultiply(input)
+    };
+    let stamp = match layers.source.filter(|s| !s.is_empty()) {
+        Some(source) => sampling::premultiply(source),
+        None => dot(finite_or(p.dot_radius, DOT_RADIUS).clamp(0.5, DOT_RADIUS)),
+    };
+
+    let spacing = finite_or(p.spacing, MAX_SPACING).clamp(MIN_SPACING, MAX_SPACING);
+    // コントロールレイヤーは画素位置をそのまま対応させ、範囲外は端を伸ばす
+    let density = match layers.density.filter(|l| !l.is_empty()) {
+        Some(layer) => density_map(layer, width, height, !p.invert_density),
+        None => vec![1.0; width * height],
+    };
+    let points = match p.distribution {
+        Distribution::PoissonDisk => poisson_points(&density, width, height, spacing, p.seed),
+        Distribution::JitteredGrid => grid_points(
+            &density,
+            width,
+            height,
+            spacing,
+            finite_or(p.grid_jitter, 0.0).clamp(0.0, 1.0),
+            p.seed,
+        ),
+    };
+
+    let scale = finite_or(p.scale, 1.0).clamp(0.0, MAX_SCALE / 100.0);
+    let scale_jitter = finite_or(p.scale_jitter, 0.0).clamp(0.0, 1.0);
+    let rotation = finite_or(p.rotation, 0.0);
+    let rotation_jitter = finite_or(p.rotation_jitter, 0.0).clamp(0.0, 180.0);
+    let (sw, sh) = (stamp.width as f32, stamp.height as f32);
+    let half_diagonal = 0.5 * (sw * sw + sh * sh).sqrt();
+
+    for point in &points {
+        let s = scale * (1.0 - scale_jitter * hash_unit(point.key, 1, 0));
+        if s * half_diagonal < 0.25 {
+            continue;
+        }
+        let angle = rotation + (hash_unit(point.key, 2, 0) * 2.0 - 1.0) * rotation_jitter;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let reach = s * half_diagonal + 1.0;
+        let x0 = (point.x - reach).floor().max(0.0) as usize;
+        let y0 = (point.y - reach).floor().max(0.0) as usize;
+        let x1 = ((point.x + reach).ceil().max(0.0) as usize).min(width);
+        let y1 = ((point.y + reach).ceil().max(0.0) as usize).min(height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                // 出力画素からスタンプ内の位置へ逆変換する
+                let (dx, dy) = (x as f32 + 0.5 - point.x, y as f32 + 0.5 - point.y);
+                let u = (cos * dx + sin * dy) / s + sw * 0.5;
+                let v = (-sin * dx + cos * dy) / s + sh * 0.5;
+                let src = sampling::sample_edge(
+                    &stamp,
+                    u - 0.5,
+                    v - 0.5,
+                    Filter::Bilinear,
+                    (Edge::None, Edge::None),
+                );
+                if src.alpha <= 0.0 {
+                    continue;
+                }
+                let i = y * width + x;
+                out.data[i] = blend(out.data[i], src, p.blend_mode);
+            }
+        }
+    }
+
+    for px in out.data.iter_mut() {
+        *px = sampling::unpremultiply(*px);
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames stamp the input itself
+/// and use it as the density layer; even frames use the built-in dot.
+pub struct PointScatter;
+
+impl RenderCore for PointScatter {
+    type Params = ScatterParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ScatterParams {
+            invert_density: rng.chance(0.5),
+            distribution: if rng.chance(0.5) {
+                Distribution::PoissonDisk
+            } else {
+                Distribution::JitteredGrid
+            },
+            spacing: rng.slider(MIN_SPACING, 200.0),
+            grid_jitter: rng.unit(),
+            scale: rng.slider(0.0, 2.0),
+            scale_jitter: rng.unit(),
+            rotation: rng.slider(-360.0, 360.0),
+            rotation_jitter: rng.slider(0.0, 180.0),
+            blend_mode: match rng.popup(1, 5) {
+                2 => BlendMode::Add,
+                3 => BlendMode::Screen,
+                4 => BlendMode::Multiply,
+                5 => BlendMode::Lighten,
+                _ => BlendMode::Normal,
+            },
+            stamps_only: rng.chance(0.5),
+            seed: rng.popup(0, 10000) as u32,
+            dot_radius: rng.slider(0.5, DOT_RADIUS),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let layers = if frame % 2 == 1 {
+            Layers {
+                source: Some(input),
+                density: Some(input),
+            }
+        } else {
+            Layers::default()
+        };
+        *output = scatter(input, layers, params);
+    }
+}
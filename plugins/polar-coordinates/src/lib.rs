@@ -0,0 +1,392 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::TAU;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample, Edge, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Conversion,
+    Center,
+    AngleOffset,
+    RadiusScale,
+    EdgeMode,
+    Supersampling,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Converts the layer between rectangular and polar coordinates.";
+
+/// Upper bound of `Supersampling` (samples per axis).
+const MAX_SUPERSAMPLING: i32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Conversion {
+    /// Wraps the layer around the center: x becomes the angle, y the radius.
+    RectToPolar,
+    /// Unrolls the layer around the center into angle (x) and radius (y).
+    PolarToRect,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Conversion,
+            tr("Conversion"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Rect to Polar"), tr("Polar to Rect")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Center,
+            tr("Center"),
+            PointDef::setup(|d| {
+                d.set_default((50.0, 50.0));
+            }),
+        )?;
+
+        // Angle Offset: rotates where the left / right edges meet (0 = up)
+        params.add(
+            Params::AngleOffset,
+            tr("Angle Offset"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Radius Scale: percent of half the shorter side mapped to the full height
+        params.add(
+            Params::RadiusScale,
+            tr("Radius Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(200.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Edge Mode: how the layer continues past its edges; in Rect to Polar, Mirror
+        // wraps the layer and a flipped copy around the circle to hide the seam
+        params.add(
+            Params::EdgeMode,
+            tr("Edge Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("None"), tr("Clamp"), tr("Repeat"), tr("Mirror")]);
+                d.set_default(3);
+            }),
+        )?;
+
+        // Supersampling: samples per axis and pixel (the center is heavily minified)
+        params.add(
+            Params::Supersampling,
+            tr("Supersampling"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_SUPERSAMPLING);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_SUPERSAMPLING);
+                d.set_default(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_PolarCoordinates - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = PolarParams {
+            conversion: match params.get(Params::Conversion)?.as_popup()?.value() {
+                2 => Conversion::PolarToRect,
+                _ => Conversion::RectToPolar,
+            },
+            center: ds.point(params.get(Params::Center)?.as_point()?.value()),
+            angle_offset: params
+                .get(Params::AngleOffset)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            radius_scale: params.get(Params::RadiusScale)?.as_float_slider()?.value() as f32
+                / 100.0,
+            edge: match params.get(Params::EdgeMode)?.as_popup()?.value() {
+                1 => Edge::None,
+                2 => Edge::Clamp,
+                4 => Edge::Mirror,
+                _ => Edge::Repeat,
+            },
+            supersampling: params.get(Params::Supersampling)?.as_slider()?.value() as u32,
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = polar(&input, &settings);
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct PolarParams {
+    pub conversion: Conversion,
+    /// Pole in render pixels.
+    pub center: (f32, f32),
+    /// Angle of the left / right edge seam in radians, clockwise from up.
+    pub angle_offset: f32,
+    /// Radius at the bottom edge as a fraction of half the shorter side.
+    pub radius_scale: f32,
+    pub edge: Edge,
+    /// Samples per axis and pixel.
+    pub supersampling: u32,
+    pub filter: Filter,
+}
+
+/// Output -> source mapping in render pixels.
+struct Mapping {
+    conversion: Conversion,
+    width: f32,
+    height: f32,
+    center: (f32, f32),
+    offset: f32,
+    radius: f32,
+    mirror: bool,
+}
+
+impl Mapping {
+    fn new(width: usize, height: usize, p: &PolarParams) -> Self {
+        let (w, h) = (width as f32, height as f32);
+        let scale = finite_or(p.radius_scale, 1.0).clamp(0.01, 10.0);
+        Self {
+            conversion: p.conversion,
+            width: w,
+            height: h,
+            center: (
+                finite_or(p.center.0, w * 0.5).clamp(-1e6, 1e6),
+                finite_or(p.center.1, h * 0.5).clamp(-1e6, 1e6),
+            ),
+            offset: finite_or(p.angle_offset, 0.0).rem_euclid(TAU),
+            radius: (0.5 * w.min(h) * scale).max(1e-3),
+            mirror: p.edge == Edge::Mirror,
+        }
+    }
+
+    /// Source position of output position (`x`, `y`) (pixel centers at +0.5).
+    fn source(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.conversion {
+            Conversion::RectToPolar => {
+                let (dx, dy) = (x - self.center.0, y - self.center.1);
+                let u = (dx.atan2(-dy) - self.offset).rem_euclid(TAU) / TAU;
+                // ミラー時は一周で 2 枚分（反転コピー込み）を回して継ぎ目を消す
+                let span = if self.mirror { 2.0 } else { 1.0 };
+                (
+                    u * span * self.width,
+                    dx.hypot(dy) / self.radius * self.height,
+                )
+            }
+            Conversion::PolarToRect => {
+                let theta = x / self.width * TAU + self.offset;
+                let r = y / self.height * self.radius;
+                (
+                    self.center.0 + r * theta.sin(),
+                    self.center.1 - r * theta.cos(),
+                )
+            }
+        }
+    }
+
+    /// Edge handling per axis: the angular axis of Rect to Polar always wraps.
+    fn edges(&self, edge: Edge) -> (Edge, Edge) {
+        match self.conversion {
+            Conversion::RectToPolar if edge == Edge::Mirror => (Edge::Mirror, edge),
+            Conversion::RectToPolar => (Edge::Repeat, edge),
+            Conversion::PolarToRect => (edge, edge),
+        }
+    }
+}
+
+fn polar(input: &ImageBuf, p: &PolarParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    let premul = sampling::premultiply(input);
+    let mapping = Mapping::new(width, height, p);
+    let edges = mapping.edges(p.edge);
+    let n = p.supersampling.clamp(1, MAX_SUPERSAMPLING as u32);
+    let weight = 1.0 / (n * n) as f32;
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut acc = [0.0f32; 4];
+            for j in 0..n {
+                for i in 0..n {
+                    let ox = x as f32 + (i as f32 + 0.5) / n as f32;
+                    let oy = y as f32 + (j as f32 + 0.5) / n as f32;
+                    let (sx, sy) = mapping.source(ox, oy);
+                    // 画素中心は整数座標なので 0.5 戻してからサンプリング
+                    let sx = finite_or(sx - 0.5, 0.0).clamp(-1e7, 1e7);
+                    let sy = finite_or(sy - 0.5, 0.0).clamp(-1e7, 1e7);
+                    let px = sampling::sample_edge(&premul, sx, sy, p.filter, edges);
+                    acc[0] += px.red * weight;
+                    acc[1] += px.green * weight;
+                    acc[2] += px.blue * weight;
+                    acc[3] += px.alpha * weight;
+                }
+            }
+            let px = PixelF32 {
+                red: acc[0],
+                green: acc[1],
+                blue: acc[2],
+                alpha: acc[3],
+            };
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct PolarCoordinates;
+
+impl RenderCore for PolarCoordinates {
+    type Params = PolarParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        PolarParams {
+            conversion: if rng.chance(0.5) {
+                Conversion::RectToPolar
+            } else {
+                Conversion::PolarToRect
+            },
+            center: (rng.slider(-100.0, 300.0), rng.slider(-100.0, 300.0)),
+            angle_offset: rng.slider(-720.0, 720.0).to_radians(),
+            radius_scale: rng.slider(1.0, 1000.0) / 100.0,
+            edge: match rng.popup(1, 4) {
+                1 => Edge::None,
+                2 => Edge::Clamp,
+                4 => Edge::Mirror,
+                _ => Edge::Repeat,
+            },
+            supersampling: rng.int(1, MAX_SUPERSAMPLING) as u32,
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                3 => Filter::Lanczos3,
+                _ => Filter::Bicubic,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = polar(input, params);
+    }
+}
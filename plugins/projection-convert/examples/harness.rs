@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<projection_convert::ProjectionConvert>();
+}
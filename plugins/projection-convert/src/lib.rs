@@ -0,0 +1,380 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::projection::{FisheyeModel, Orientation, Projection};
+use utils::sampling::{self, Edge, Filter};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    InputProjection,
+    InputFov,
+    OutputProjection,
+    OutputFov,
+    FisheyeModel,
+    Yaw,
+    Pitch,
+    Roll,
+    Filter,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Converts between rectilinear, fisheye and equirectangular projections.";
+
+/// Upper bound of the field of view sliders (degrees).
+const MAX_FOV: f32 = 360.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Rectilinear,
+    Fisheye,
+    /// Full 360x180 panorama; ignores the field of view.
+    Equirectangular,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add_with_flags(
+            Params::InputProjection,
+            tr("Input Projection"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Rectilinear"), tr("Fisheye"), tr("Equirectangular")]);
+                d.set_default(2);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Input FOV: horizontal field of view across the layer width
+        params.add(
+            Params::InputFov,
+            tr("Input FOV"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_FOV);
+                d.set_slider_min(10.0);
+                d.set_slider_max(MAX_FOV);
+                d.set_default(180.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add_with_flags(
+            Params::OutputProjection,
+            tr("Output Projection"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Rectilinear"), tr("Fisheye"), tr("Equirectangular")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::OutputFov,
+            tr("Output FOV"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_FOV);
+                d.set_slider_min(10.0);
+                d.set_slider_max(MAX_FOV);
+                d.set_default(90.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Fisheye Model: lens mapping used by whichever side is a fisheye
+        params.add(
+            Params::FisheyeModel,
+            tr("Fisheye Model"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Equidistant"),
+                    tr("Equisolid"),
+                    tr("Stereographic"),
+                    tr("Orthographic"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Yaw / Pitch / Roll: orientation of the output view within the input
+        params.add(
+            Params::Yaw,
+            tr("Yaw"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Pitch,
+            tr("Pitch"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Roll,
+            tr("Roll"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Filter,
+            tr("Filter"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Bilinear"), tr("Bicubic"), tr("Lanczos")]);
+                d.set_default(3);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ProjectionConvert - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::InputProjection | Params::OutputProjection
+                ) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn projection_kind(params: &Parameters<Params>, id: Params) -> Result<Kind, Error> {
+    Ok(match params.get(id)?.as_popup()?.value() {
+        2 => Kind::Fisheye,
+        3 => Kind::Equirectangular,
+        _ => Kind::Rectilinear,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let input = projection_kind(params, Params::InputProjection)?;
+    let output = projection_kind(params, Params::OutputProjection)?;
+    for (id, visible) in [
+        (Params::InputFov, input != Kind::Equirectangular),
+        (Params::OutputFov, output != Kind::Equirectangular),
+        (
+            Params::FisheyeModel,
+            input == Kind::Fisheye || output == Kind::Fisheye,
+        ),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 画角はレイヤー幅に対して定義するので、ダウンサンプル時も換算は不要
+        let degrees = |id: Params| -> Result<f32, Error> {
+            Ok(params.get(id)?.as_float_slider()?.value() as f32)
+        };
+        let settings = ProjectionParams {
+            input: projection_kind(params, Params::InputProjection)?,
+            input_fov: degrees(Params::InputFov)?,
+            output: projection_kind(params, Params::OutputProjection)?,
+            output_fov: degrees(Params::OutputFov)?,
+            fisheye_model: match params.get(Params::FisheyeModel)?.as_popup()?.value() {
+                2 => FisheyeModel::Equisolid,
+                3 => FisheyeModel::Stereographic,
+                4 => FisheyeModel::Orthographic,
+                _ => FisheyeModel::Equidistant,
+            },
+            yaw: params.get(Params::Yaw)?.as_angle()?.value(),
+            pitch: params.get(Params::Pitch)?.as_angle()?.value(),
+            roll: params.get(Params::Roll)?.as_angle()?.value(),
+            filter: match params.get(Params::Filter)?.as_popup()?.value() {
+                1 => Filter::Bilinear,
+                2 => Filter::Bicubic,
+                _ => Filter::Lanczos3,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        convert(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ProjectionParams {
+    pub input: Kind,
+    /// Horizontal field of view of the input in degrees.
+    pub input_fov: f32,
+    pub output: Kind,
+    /// Horizontal field of view of the output in degrees.
+    pub output_fov: f32,
+    pub fisheye_model: FisheyeModel,
+    /// Degrees.
+    pub yaw: f32,
+    /// Degrees.
+    pub pitch: f32,
+    /// Degrees.
+    pub roll: f32,
+    pub filter: Filter,
+}
+
+fn projection(kind: Kind, fov: f32, model: FisheyeModel, width: f32, height: f32) -> Projection {
+    let fov = finite_or(fov, 90.0).clamp(1.0, MAX_FOV).to_radians();
+    match kind {
+        Kind::Rectilinear => Projection::rectilinear(fov, width),
+        Kind::Fisheye => Projection::fisheye(model, fov, width),
+        Kind::Equirectangular => Projection::Equirectangular { width, height },
+    }
+}
+
+pub fn convert(input: &ImageBuf, p: &ProjectionParams) -> ImageBuf {
+    let (w, h) = (input.width as f32, input.height as f32);
+    let source = projection(p.input, p.input_fov, p.fisheye_model, w, h);
+    let target = projection(p.output, p.output_fov, p.fisheye_model, w, h);
+    let angle = |v: f32| finite_or(v, 0.0).to_radians();
+    let orientation = Orientation::from_yaw_pitch_roll(angle(p.yaw), angle(p.pitch), angle(p.roll));
+
+    // 正距円筒の入力は経度方向に一周つながっている
+    let edge = match p.input {
+        Kind::Equirectangular => (Edge::Repeat, Edge::Clamp),
+        _ => (Edge::None, Edge::None),
+    };
+    sampling::remap(input, p.filter, edge, |(x, y)| {
+        // 出力画素の視線を回転し、入力側の投影で画素位置に戻す
+        let ray = target.ray((x - w * 0.5, y - h * 0.5))?;
+        let (sx, sy) = source.project(orientation.apply(ray))?;
+        Some((sx + w * 0.5, sy + h * 0.5))
+    })
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ProjectionConvert;
+
+impl RenderCore for ProjectionConvert {
+    type Params = ProjectionParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let kind = |rng: &mut Rng| match rng.popup(1, 3) {
+            2 => Kind::Fisheye,
+            3 => Kind::Equirectangular,
+            _ => Kind::Rectilinear,
+        };
+        ProjectionParams {
+            input: kind(rng),
+            input_fov: rng.slider(1.0, MAX_FOV),
+            output: kind(rng),
+            output_fov: rng.slider(1.0, MAX_FOV),
+            fisheye_model: match rng.popup(1, 4) {
+                2 => FisheyeModel::Equisolid,
+                3 => FisheyeModel::Stereographic,
+                4 => FisheyeModel::Orthographic,
+                _ => FisheyeModel::Equidistant,
+            },
+            yaw: rng.slider(-360.0, 360.0),
+            pitch: rng.slider(-360.0, 360.0),
+            roll: rng.slider(-360.0, 360.0),
+            filter: match rng.popup(1, 3) {
+                1 => Filter::Bilinear,
+                2 => Filter::Bicubic,
+                _ => Filter::Lanczos3,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = convert(input, params);
+    }
+}
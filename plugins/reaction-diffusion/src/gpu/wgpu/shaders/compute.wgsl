@@ -0,0 +1,48 @@
+// Keep in sync with `RenderParams` in ../mod.rs and `step_cpu` in lib.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    wrap: u32,
+    _pad: u32,
+    feed: f32,
+    kill: f32,
+    diffusion_a: f32,
+    diffusion_b: f32,
+};
+
+// (A, B) concentrations per cell
+@group(0) @binding(0) var<storage, read> src: array<vec2<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> dst: array<vec2<f32>>;
+
+fn wrap_index(i: i32, n: u32) -> u32 {
+    let ni = i32(n);
+    if (params.wrap != 0u) {
+        return u32(((i % ni) + ni) % ni);
+    }
+    return u32(clamp(i, 0, ni - 1));
+}
+
+fn fetch(x: i32, y: i32) -> vec2<f32> {
+    return src[wrap_index(y, params.height) * params.width + wrap_index(x, params.width)];
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let x = i32(gid.x);
+    let y = i32(gid.y);
+    let c = fetch(x, y);
+
+    // 9-point Laplacian: 0.2 for edges, 0.05 for corners
+    let edges = fetch(x - 1, y) + fetch(x + 1, y) + fetch(x, y - 1) + fetch(x, y + 1);
+    let corners = fetch(x - 1, y - 1) + fetch(x + 1, y - 1) + fetch(x - 1, y + 1) + fetch(x + 1, y + 1);
+    let lap = edges * 0.2 + corners * 0.05 - c;
+
+    let reaction = c.x * c.y * c.y;
+    let a = c.x + params.diffusion_a * lap.x - reaction + params.feed * (1.0 - c.x);
+    let b = c.y + params.diffusion_b * lap.y + reaction - (params.kill + params.feed) * c.y;
+    dst[gid.y * params.width + gid.x] = clamp(vec2<f32>(a, b), vec2<f32>(0.0), vec2<f32>(1.0));
+}
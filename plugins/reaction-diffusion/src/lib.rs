@@ -0,0 +1,606 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::history::{History, HistoryKey};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, Edge};
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    FeedRate,
+    KillRate,
+    DiffusionA,
+    DiffusionB,
+    StepsPerFrame,
+    CellSize,
+    SeedThreshold,
+    WrapEdges,
+    Output,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: simulation states of recently rendered frames.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History<Field>>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Grows reaction-diffusion patterns from the layer.";
+
+/// Upper bound of `Steps Per Frame`.
+const MAX_STEPS_PER_FRAME: i32 = 500;
+/// Upper bound of `Cell Size` in full-resolution pixels.
+const MAX_CELL_SIZE: f32 = 16.0;
+/// Steps simulated at most when a frame is rendered without its predecessor
+/// (scrubbing, multi-frame rendering), growing it from the current input.
+const MAX_CATCH_UP_STEPS: u64 = 20_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// White where B outweighs A, black where A remains.
+    Pattern,
+    /// The input with its alpha multiplied by the pattern.
+    InputMatte,
+}
+
+// Created on first use and shared by all render threads; `None` when no adapter is available.
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Feed: rate at which A is replenished
+        params.add(
+            Params::FeedRate,
+            tr("Feed Rate"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(0.1);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.1);
+                d.set_default(0.055);
+                d.set_precision(4);
+            }),
+        )?;
+
+        // Kill: rate at which B is removed
+        params.add(
+            Params::KillRate,
+            tr("Kill Rate"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(0.1);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.1);
+                d.set_default(0.062);
+                d.set_precision(4);
+            }),
+        )?;
+
+        // Diffusion: per-step diffusion rates in cells (above 1 the explicit step becomes unstable)
+        params.add(
+            Params::DiffusionA,
+            tr("Diffusion A"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::DiffusionB,
+            tr("Diffusion B"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::StepsPerFrame,
+            tr("Steps Per Frame"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_STEPS_PER_FRAME);
+                d.set_slider_min(1);
+                d.set_slider_max(100);
+                d.set_default(20);
+            }),
+        )?;
+
+        // Cell Size: simulation cell in full-resolution pixels (larger cells grow larger patterns)
+        params.add(
+            Params::CellSize,
+            tr("Cell Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_CELL_SIZE);
+                d.set_slider_min(1.0);
+                d.set_slider_max(8.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Seed Threshold: luminance above which the first frame is seeded with B
+        params.add(
+            Params::SeedThreshold,
+            tr("Seed Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::WrapEdges,
+            tr("Wrap Edges"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(true);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Pattern"), tr("Input Matte")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ReactionDiffusion - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // シミュレーション状態は再計算できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(plugin.in_data, &input, out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup => {
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::UserChangedParam { .. } => {
+                // 設定が変わったら以前の状態は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(mut out_layer)) = (in_layer_opt, out_layer_opt) {
+                    let input = ImageBuf::from_layer(&in_layer);
+                    self.do_render(in_data, &input, &mut out_layer, plugin.params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let settings = DiffusionParams {
+            feed: params.get(Params::FeedRate)?.as_float_slider()?.value() as f32,
+            kill: params.get(Params::KillRate)?.as_float_slider()?.value() as f32,
+            diffusion_a: params.get(Params::DiffusionA)?.as_float_slider()?.value() as f32,
+            diffusion_b: params.get(Params::DiffusionB)?.as_float_slider()?.value() as f32,
+            steps_per_frame: params.get(Params::StepsPerFrame)?.as_slider()?.value() as u32,
+            cell_size: ds.length(params.get(Params::CellSize)?.as_float_slider()?.value() as f32),
+            seed_threshold: params
+                .get(Params::SeedThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            wrap: params.get(Params::WrapEdges)?.as_checkbox()?.value(),
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::InputMatte,
+                _ => Output::Pattern,
+            },
+        };
+
+        let time = in_data.current_time();
+        let time_step = in_data.time_step();
+        let (width, height) = grid_size(input.width, input.height, settings.cell_size);
+        let key = HistoryKey {
+            width,
+            height,
+            downsample: ds,
+            time_step,
+        };
+
+        let field = {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            // レイヤーの先頭フレームでは常にリセットする
+            let previous = if time <= 0 {
+                None
+            } else {
+                history.previous(key, time).cloned()
+            };
+            let field = match previous {
+                Some(previous) => advance(previous, &settings, settings.steps_per_frame as u64),
+                None => {
+                    let frame = if time_step > 0 { time / time_step } else { 0 };
+                    simulate_from_seed(input, &settings, frame.max(0) as u64)
+                }
+            };
+            history.store(key, time, field.clone());
+            field
+        };
+
+        shade(&field, input, &settings).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct DiffusionParams {
+    pub feed: f32,
+    pub kill: f32,
+    pub diffusion_a: f32,
+    pub diffusion_b: f32,
+    pub steps_per_frame: u32,
+    /// Simulation cell size in render pixels.
+    pub cell_size: f32,
+    /// Luminance above which B is seeded.
+    pub seed_threshold: f32,
+    /// Toroidal neighbourhood instead of clamped edges.
+    pub wrap: bool,
+    pub output: Output,
+}
+
+/// Simulation grid: (A, B) concentrations per cell, row-major.
+#[derive(Clone, Debug)]
+struct Field {
+    width: usize,
+    height: usize,
+    cells: Vec<[f32; 2]>,
+}
+
+impl Field {
+    #[inline]
+    fn get(&self, x: isize, y: isize, wrap: bool) -> [f32; 2] {
+        let edge = if wrap { Edge::Repeat } else { Edge::Clamp };
+        match (edge.wrap(x, self.width), edge.wrap(y, self.height)) {
+            (Some(x), Some(y)) => self.cells[y * self.width + x],
+            _ => [1.0, 0.0],
+        }
+    }
+}
+
+fn cell_size(p: &DiffusionParams) -> f32 {
+    finite_or(p.cell_size, 1.0).clamp(1.0, MAX_CELL_SIZE)
+}
+
+/// Grid dimensions covering a `width`×`height` image with `cell`-pixel cells.
+fn grid_size(width: usize, height: usize, cell: f32) -> (usize, usize) {
+    let cell = finite_or(cell, 1.0).clamp(1.0, MAX_CELL_SIZE);
+    let n = |v: usize| ((v as f32 / cell).ceil() as usize).max(1);
+    (n(width), n(height))
+}
+
+/// Initial state: A everywhere, B where the input is brighter than the threshold.
+fn seed(input: &ImageBuf, p: &DiffusionParams) -> Field {
+    let cell = cell_size(p);
+    let (width, height) = grid_size(input.width, input.height, cell);
+    let threshold = finite_or(p.seed_threshold, 0.5);
+    let mut cells = vec![[1.0, 0.0]; width * height];
+    if !input.is_empty() {
+        for (i, c) in cells.iter_mut().enumerate() {
+            // セル中心のピクセルで判定する
+            let x = (((i % width) as f32 + 0.5) * cell) as isize;
+            let y = (((i / width) as f32 + 0.5) * cell) as isize;
+            let px = input.get_clamped(x, y);
+            let alpha = if px.alpha.is_finite() {
+                px.alpha.clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            if luminance(px) > threshold {
+                c[1] = alpha;
+            }
+        }
+    }
+    Field {
+        width,
+        height,
+        cells,
+    }
+}
+
+/// One explicit Gray-Scott step (see `shaders/compute.wgsl`).
+fn step_cpu(field: &Field, p: &DiffusionParams) -> Field {
+    let feed = finite_or(p.feed, 0.055).clamp(0.0, 0.1);
+    let kill = finite_or(p.kill, 0.062).clamp(0.0, 0.1);
+    let da = finite_or(p.diffusion_a, 1.0).clamp(0.0, 1.0);
+    let db = finite_or(p.diffusion_b, 0.5).clamp(0.0, 1.0);
+    let mut cells = Vec::with_capacity(field.cells.len());
+
+    for y in 0..field.height as isize {
+        for x in 0..field.width as isize {
+            let at = |dx: isize, dy: isize| field.get(x + dx, y + dy, p.wrap);
+            let c = at(0, 0);
+            // 9 点ラプラシアン (辺 0.2 / 角 0.05)
+            let mut lap = [-c[0], -c[1]];
+            for (dx, dy, w) in [
+                (-1, 0, 0.2),
+                (1, 0, 0.2),
+                (0, -1, 0.2),
+                (0, 1, 0.2),
+                (-1, -1, 0.05),
+                (1, -1, 0.05),
+                (-1, 1, 0.05),
+                (1, 1, 0.05),
+            ] {
+                let n = at(dx, dy);
+                lap[0] += n[0] * w;
+                lap[1] += n[1] * w;
+            }
+            let reaction = c[0] * c[1] * c[1];
+            let a = c[0] + da * lap[0] - reaction + feed * (1.0 - c[0]);
+            let b = c[1] + db * lap[1] + reaction - (kill + feed) * c[1];
+            cells.push([a.clamp(0.0, 1.0), b.clamp(0.0, 1.0)]);
+        }
+    }
+    Field {
+        width: field.width,
+        height: field.height,
+        cells,
+    }
+}
+
+/// Advances `field` by `steps` steps, on the GPU when available.
+fn advance(field: Field, p: &DiffusionParams, steps: u64) -> Field {
+    let steps = steps.min(MAX_CATCH_UP_STEPS) as u32;
+    if steps == 0 || field.cells.is_empty() {
+        return field;
+    }
+
+    #[cfg(feature = "gpu_wgpu")]
+    if let Some(ctx) = wgpu_context() {
+        let gpu_params = RenderParams {
+            width: field.width as u32,
+            height: field.height as u32,
+            wrap: p.wrap as u32,
+            _pad: 0,
+            feed: finite_or(p.feed, 0.055).clamp(0.0, 0.1),
+            kill: finite_or(p.kill, 0.062).clamp(0.0, 0.1),
+            diffusion_a: finite_or(p.diffusion_a, 1.0).clamp(0.0, 1.0),
+            diffusion_b: finite_or(p.diffusion_b, 0.5).clamp(0.0, 1.0),
+        };
+        let flat: Vec<f32> = field.cells.iter().flatten().copied().collect();
+        if let Ok(flat) = ctx.simulate(&gpu_params, &flat, steps) {
+            let cells = flat.chunks_exact(2).map(|c| [c[0], c[1]]).collect();
+            return Field {
+                width: field.width,
+                height: field.height,
+                cells,
+            };
+        }
+        // Fall through to the CPU path when the GPU dispatch fails.
+    }
+
+    let mut field = field;
+    for _ in 0..steps {
+        field = step_cpu(&field, p);
+    }
+    field
+}
+
+/// State of frame `frame` (0 = first) grown from the current input.
+fn simulate_from_seed(input: &ImageBuf, p: &DiffusionParams, frame: u64) -> Field {
+    let steps = (p.steps_per_frame.clamp(1, MAX_STEPS_PER_FRAME as u32) as u64)
+        .saturating_mul(frame.saturating_add(1));
+    advance(seed(input, p), p, steps)
+}
+
+/// Pattern value in [0, 1] of `field` at render pixel (`x`, `y`), bilinear between cells.
+fn pattern(field: &Field, x: usize, y: usize, cell: f32, wrap: bool) -> f32 {
+    let gx = (x as f32 + 0.5) / cell - 0.5;
+    let gy = (y as f32 + 0.5) / cell - 0.5;
+    let (x0, y0) = (gx.floor(), gy.floor());
+    let (tx, ty) = (gx - x0, gy - y0);
+    let (x0, y0) = (x0 as isize, y0 as isize);
+    let value = |x: isize, y: isize| {
+        let [a, b] = field.get(x, y, wrap);
+        (b - a + 1.0).clamp(0.0, 1.0)
+    };
+    let top = value(x0, y0) * (1.0 - tx) + value(x0 + 1, y0) * tx;
+    let bottom = value(x0, y0 + 1) * (1.0 - tx) + value(x0 + 1, y0 + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+fn shade(field: &Field, input: &ImageBuf, p: &DiffusionParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if field.cells.is_empty() {
+        return output;
+    }
+    let cell = cell_size(p);
+
+    for y in 0..height {
+        for x in 0..width {
+            let v = pattern(field, x, y, cell, p.wrap);
+            let px = match p.output {
+                Output::Pattern => PixelF32 {
+                    red: v,
+                    green: v,
+                    blue: v,
+                    alpha: 1.0,
+                },
+                Output::InputMatte => {
+                    let px = input.get(x as isize, y as isize);
+                    PixelF32 {
+                        alpha: px.alpha * v,
+                        ..px
+                    }
+                }
+            };
+            output.set(x, y, px);
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no sequence data, so every frame is grown from the input
+/// over `frame % 4 + 1` frames' worth of steps.
+pub struct ReactionDiffusion;
+
+impl RenderCore for ReactionDiffusion {
+    type Params = DiffusionParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        DiffusionParams {
+            feed: rng.slider(0.0, 0.1),
+            kill: rng.slider(0.0, 0.1),
+            diffusion_a: rng.slider(0.0, 1.0),
+            diffusion_b: rng.slider(0.0, 1.0),
+            steps_per_frame: rng.int(1, 40) as u32,
+            cell_size: rng.slider(1.0, MAX_CELL_SIZE),
+            seed_threshold: rng.slider(0.0, 1.0),
+            wrap: rng.chance(0.5),
+            output: if rng.chance(0.5) {
+                Output::Pattern
+            } else {
+                Output::InputMatte
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let field = simulate_from_seed(input, params, (frame % 4) as u64);
+        *output = shade(&field, input, params);
+    }
+}
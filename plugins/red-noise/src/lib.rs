@@ -5,6 +5,11 @@ use std::env;
 
 use ae::pf::*;
 use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+
+mod strings;
+use strings::tr;
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 enum Params {
@@ -27,7 +32,7 @@ impl AdobePluginGlobal for Plugin {
     ) -> Result<(), Error> {
         params.add(
             Params::NoiseStrength,
-            "Noise Strength",
+            tr("Noise Strength"),
             FloatSliderDef::setup(|d| {
                 d.set_valid_min(0.0);
                 d.set_valid_max(1.0);
@@ -130,15 +135,8 @@ impl Plugin {
             let y = y as usize;
 
             // 入力ピクセル（アルファ付き）を取得
-            let mut px = read_pixel_f32(&in_layer, in_world_type, x, y);
-
-            // フレーム＋座標ベースの赤ノイズ
-            let n = pseudo_random(x, y, frame_num);
-            let noise = (n * 2.0 - 1.0) * strength;
-
-            // αはそのまま、赤チャンネルだけにノイズを載せる（0..α の範囲にクランプ）
-            let max_red = px.alpha.clamp(0.0, 1.0);
-            px.red = (px.red + noise).clamp(0.0, max_red);
+            let px = read_pixel_f32(&in_layer, in_world_type, x, y);
+            let px = apply_red_noise(px, x, y, frame_num, strength);
 
             match out_world_type {
                 ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
@@ -155,6 +153,53 @@ impl Plugin {
     }
 }
 
+/// Host-independent render core (see `utils::harness`).
+pub struct RedNoise;
+
+#[derive(Debug)]
+pub struct RedNoiseParams {
+    pub strength: f32,
+}
+
+impl RenderCore for RedNoise {
+    type Params = RedNoiseParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        RedNoiseParams {
+            strength: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let px = input.data[input.index(x, y)];
+                output.set(
+                    x,
+                    y,
+                    apply_red_noise(px, x, y, frame as usize, params.strength),
+                );
+            }
+        }
+    }
+}
+
+fn apply_red_noise(mut px: PixelF32, x: usize, y: usize, frame: usize, strength: f32) -> PixelF32 {
+    // フレーム＋座標ベースの赤ノイズ
+    let n = pseudo_random(x, y, frame);
+    let noise = (n * 2.0 - 1.0) * strength;
+
+    // αはそのまま、赤チャンネルだけにノイズを載せる（0..α の範囲にクランプ）
+    // 32bpc の NaN α を上限に渡すと clamp が panic するため 0 として扱う
+    let max_red = if px.alpha.is_nan() {
+        0.0
+    } else {
+        px.alpha.clamp(0.0, 1.0)
+    };
+    px.red = (px.red + noise).clamp(0.0, max_red);
+    px
+}
+
 fn read_pixel_f32(layer: &Layer, world_type: ae::aegp::WorldType, x: usize, y: usize) -> PixelF32 {
     match world_type {
         ae::aegp::WorldType::U8 => layer.as_pixel8(x, y).to_pixel32(),
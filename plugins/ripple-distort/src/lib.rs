@@ -0,0 +1,523 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+use std::f32::consts::TAU;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::i18n;
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, bicubic, premultiply, unpremultiply};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Phase,
+    Emitter1Type,
+    Emitter1Center,
+    Emitter1Amplitude,
+    Emitter1Wavelength,
+    Emitter1Speed,
+    Emitter1Decay,
+    Emitter1StartTime,
+    Emitter2Type,
+    Emitter2Center,
+    Emitter2Amplitude,
+    Emitter2Wavelength,
+    Emitter2Speed,
+    Emitter2Decay,
+    Emitter2StartTime,
+    Emitter3Type,
+    Emitter3Center,
+    Emitter3Amplitude,
+    Emitter3Wavelength,
+    Emitter3Speed,
+    Emitter3Decay,
+    Emitter3StartTime,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Distorts the layer with expanding ripples.";
+
+/// Upper bound of `Amplitude` (full-resolution pixels).
+const MAX_AMPLITUDE: f32 = 1000.0;
+/// Upper bound of `Wavelength` (full-resolution pixels).
+const MAX_WAVELENGTH: f32 = 10000.0;
+/// Upper bound of `Speed` magnitude (full-resolution pixels per second).
+const MAX_SPEED: f32 = 10000.0;
+const MAX_DECAY: f32 = 10.0;
+
+/// Per-emitter parameters: type, center, amplitude, wavelength, speed, decay, start time.
+const EMITTER_PARAMS: [[Params; 7]; 3] = [
+    [
+        Params::Emitter1Type,
+        Params::Emitter1Center,
+        Params::Emitter1Amplitude,
+        Params::Emitter1Wavelength,
+        Params::Emitter1Speed,
+        Params::Emitter1Decay,
+        Params::Emitter1StartTime,
+    ],
+    [
+        Params::Emitter2Type,
+        Params::Emitter2Center,
+        Params::Emitter2Amplitude,
+        Params::Emitter2Wavelength,
+        Params::Emitter2Speed,
+        Params::Emitter2Decay,
+        Params::Emitter2StartTime,
+    ],
+    [
+        Params::Emitter3Type,
+        Params::Emitter3Center,
+        Params::Emitter3Amplitude,
+        Params::Emitter3Wavelength,
+        Params::Emitter3Speed,
+        Params::Emitter3Decay,
+        Params::Emitter3StartTime,
+    ],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmitterType {
+    Off,
+    /// Wave train filling the disc reached since `Start Time`.
+    Continuous,
+    /// A single wave packet travelling outwards from `Start Time`.
+    ShockWave,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Emitter {
+    pub kind: EmitterType,
+    /// Center in render pixels.
+    pub center: (f32, f32),
+    /// Peak radial displacement in render pixels.
+    pub amplitude: f32,
+    /// Render pixels.
+    pub wavelength: f32,
+    /// Render pixels per second; negative speeds make continuous waves travel inwards.
+    pub speed: f32,
+    /// Amplitude falls by `e` every `1 / decay` wavelengths from the center.
+    pub decay: f32,
+    /// Seconds.
+    pub start_time: f32,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Phase: offset added to every wave, for animating without time
+        params.add(
+            Params::Phase,
+            tr("Phase"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Emitters: Off hides the emitter's controls
+        for (
+            n,
+            [
+                kind,
+                center,
+                amplitude,
+                wavelength,
+                speed,
+                decay,
+                start_time,
+            ],
+        ) in EMITTER_PARAMS.into_iter().enumerate()
+        {
+            params.add_with_flags(
+                kind,
+                &i18n::fill(tr("Emitter {} Type"), n + 1),
+                PopupDef::setup(|d| {
+                    d.set_options(&[tr("Off"), tr("Continuous"), tr("Shock Wave")]);
+                    d.set_default(if n == 0 { 2 } else { 1 });
+                }),
+                ParamFlag::SUPERVISE,
+                ParamUIFlags::empty(),
+            )?;
+
+            params.add(
+                center,
+                &i18n::fill(tr("Emitter {} Center"), n + 1),
+                PointDef::setup(|d| {
+                    d.set_default((50.0, 50.0));
+                }),
+            )?;
+
+            params.add(
+                amplitude,
+                &i18n::fill(tr("Emitter {} Amplitude"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-MAX_AMPLITUDE);
+                    d.set_valid_max(MAX_AMPLITUDE);
+                    d.set_slider_min(-50.0);
+                    d.set_slider_max(50.0);
+                    d.set_default(10.0);
+                    d.set_precision(1);
+                }),
+            )?;
+
+            params.add(
+                wavelength,
+                &i18n::fill(tr("Emitter {} Wavelength"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(2.0);
+                    d.set_valid_max(MAX_WAVELENGTH);
+                    d.set_slider_min(4.0);
+                    d.set_slider_max(500.0);
+                    d.set_default(60.0);
+                    d.set_precision(1);
+                }),
+            )?;
+
+            // Speed: pixels per second the waves travel outwards
+            params.add(
+                speed,
+                &i18n::fill(tr("Emitter {} Speed"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-MAX_SPEED);
+                    d.set_valid_max(MAX_SPEED);
+                    d.set_slider_min(-500.0);
+                    d.set_slider_max(500.0);
+                    d.set_default(120.0);
+                    d.set_precision(1);
+                }),
+            )?;
+
+            // Decay: amplitude falls by e every 1 / decay wavelengths
+            params.add(
+                decay,
+                &i18n::fill(tr("Emitter {} Decay"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(0.0);
+                    d.set_valid_max(MAX_DECAY);
+                    d.set_slider_min(0.0);
+                    d.set_slider_max(2.0);
+                    d.set_default(0.2);
+                    d.set_precision(2);
+                }),
+            )?;
+
+            // Start Time: layer time in seconds at which the emitter starts
+            params.add(
+                start_time,
+                &i18n::fill(tr("Emitter {} Start Time"), n + 1),
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-3600.0);
+                    d.set_valid_max(3600.0);
+                    d.set_slider_min(0.0);
+                    d.set_slider_max(30.0);
+                    d.set_default(0.0);
+                    d.set_precision(2);
+                }),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_RippleDistort - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if EMITTER_PARAMS
+                    .iter()
+                    .any(|e| e[0] == params.type_at(param_index)) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn emitter_type(params: &Parameters<Params>, id: Params) -> Result<EmitterType, Error> {
+    Ok(match params.get(id)?.as_popup()?.value() {
+        2 => EmitterType::Continuous,
+        3 => EmitterType::ShockWave,
+        _ => EmitterType::Off,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let mut visibility = Vec::new();
+    for [
+        kind,
+        center,
+        amplitude,
+        wavelength,
+        speed,
+        decay,
+        start_time,
+    ] in EMITTER_PARAMS
+    {
+        let on = emitter_type(params, kind)? != EmitterType::Off;
+        visibility
+            .extend([center, amplitude, wavelength, speed, decay, start_time].map(|id| (id, on)));
+    }
+    for (id, visible) in visibility {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 位置・振幅・波長・速度はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let seconds = in_data.current_time() as f64 / in_data.time_scale().max(1) as f64;
+
+        let mut emitters = Vec::with_capacity(EMITTER_PARAMS.len());
+        for [
+            kind,
+            center,
+            amplitude,
+            wavelength,
+            speed,
+            decay,
+            start_time,
+        ] in EMITTER_PARAMS
+        {
+            let length = |id: Params| -> Result<f32, Error> {
+                Ok(ds.length(params.get(id)?.as_float_slider()?.value() as f32))
+            };
+            emitters.push(Emitter {
+                kind: emitter_type(params, kind)?,
+                center: ds.point(params.get(center)?.as_point()?.value()),
+                amplitude: length(amplitude)?,
+                wavelength: length(wavelength)?,
+                speed: length(speed)?,
+                decay: params.get(decay)?.as_float_slider()?.value() as f32,
+                start_time: params.get(start_time)?.as_float_slider()?.value() as f32,
+            });
+        }
+        let settings = RippleParams {
+            phase: params.get(Params::Phase)?.as_angle()?.value(),
+            time: seconds as f32,
+            emitters,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        ripple(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct RippleParams {
+    /// Degrees.
+    pub phase: f32,
+    /// Layer time in seconds.
+    pub time: f32,
+    pub emitters: Vec<Emitter>,
+}
+
+/// Emitter with sanitized values, the wave front radius and the phase at `time`.
+struct Wave {
+    kind: EmitterType,
+    center: (f32, f32),
+    amplitude: f32,
+    wavelength: f32,
+    decay: f32,
+    /// Distance travelled since the start time (infinite for standing or inward waves).
+    front: f32,
+    phase: f32,
+}
+
+impl Wave {
+    fn new(e: &Emitter, p: &RippleParams) -> Self {
+        let wavelength = finite_or(e.wavelength, 1.0).clamp(1.0, MAX_WAVELENGTH);
+        let speed = finite_or(e.speed, 0.0).clamp(-MAX_SPEED, MAX_SPEED);
+        let elapsed = finite_or(p.time, 0.0) - finite_or(e.start_time, 0.0);
+        // 内向き・静止した連続波は開始前から全体に広がっているものとする
+        let front = if e.kind == EmitterType::Continuous && speed <= 0.0 {
+            f32::INFINITY
+        } else {
+            speed * elapsed
+        };
+        Self {
+            kind: e.kind,
+            center: (finite_or(e.center.0, 0.0), finite_or(e.center.1, 0.0)),
+            amplitude: finite_or(e.amplitude, 0.0).clamp(-MAX_AMPLITUDE, MAX_AMPLITUDE),
+            wavelength,
+            decay: finite_or(e.decay, 0.0).clamp(0.0, MAX_DECAY),
+            front,
+            phase: finite_or(p.phase, 0.0).to_radians() - TAU * speed * elapsed / wavelength,
+        }
+    }
+
+    /// Radial displacement in render pixels at distance `r` from the center.
+    fn displacement(&self, r: f32) -> f32 {
+        let envelope = match self.kind {
+            EmitterType::Off => return 0.0,
+            // 波面の手前 1 波長でなめらかに立ち上げる
+            EmitterType::Continuous => ((self.front - r) / self.wavelength).clamp(0.0, 1.0),
+            EmitterType::ShockWave => {
+                if self.front < 0.0 {
+                    return 0.0;
+                }
+                let t = (r - self.front) / self.wavelength;
+                (-2.0 * t * t).exp()
+            }
+        };
+        // 中心付近は方向が定まらないので半波長で 0 に落とす
+        let center = (2.0 * r / self.wavelength).min(1.0);
+        let decay = (-self.decay * r / self.wavelength).exp();
+        self.amplitude * envelope * center * decay * (TAU * r / self.wavelength + self.phase).sin()
+    }
+}
+
+pub fn ripple(input: &ImageBuf, p: &RippleParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+
+    let waves: Vec<Wave> = p
+        .emitters
+        .iter()
+        .filter(|e| e.kind != EmitterType::Off)
+        .map(|e| Wave::new(e, p))
+        .collect();
+    let source = premultiply(input);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (mut dx, mut dy) = (0.0, 0.0);
+            for wave in &waves {
+                let (ox, oy) = (x as f32 - wave.center.0, y as f32 - wave.center.1);
+                let r = ox.hypot(oy);
+                if r <= 0.0 {
+                    continue;
+                }
+                let d = wave.displacement(r) / r;
+                dx += ox * d;
+                dy += oy * d;
+            }
+            // 変位した位置から逆向きに読み出す
+            let px = bicubic(&source, x as f32 - dx, y as f32 - dy);
+            out.set(x, y, unpremultiply(px));
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct RippleDistort;
+
+impl RenderCore for RippleDistort {
+    type Params = RippleParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        let emitters = (0..EMITTER_PARAMS.len())
+            .map(|_| Emitter {
+                kind: match rng.popup(1, 3) {
+                    2 => EmitterType::Continuous,
+                    3 => EmitterType::ShockWave,
+                    _ => EmitterType::Off,
+                },
+                center: (rng.slider(-200.0, 300.0), rng.slider(-200.0, 300.0)),
+                amplitude: rng.slider(-MAX_AMPLITUDE, MAX_AMPLITUDE),
+                wavelength: rng.slider(2.0, MAX_WAVELENGTH),
+                speed: rng.slider(-MAX_SPEED, MAX_SPEED),
+                decay: rng.slider(0.0, MAX_DECAY),
+                start_time: rng.slider(-3600.0, 3600.0),
+            })
+            .collect();
+        RippleParams {
+            phase: rng.slider(-360.0, 360.0),
+            time: rng.slider(0.0, 60.0),
+            emitters,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = ripple(input, params);
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<scribble_art::ScribbleArt>();
+}
@@ -0,0 +1,650 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{hash, hash_unit};
+use utils::density::{density_at, density_map};
+use utils::dither::{BLUE_NOISE_SIZE, blue_noise};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, coverage, unpremultiply};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    PointSpacing,
+    MinWidth,
+    MaxWidth,
+    Smoothing,
+    Progress,
+    LineColor,
+    InkColor,
+    InvertDensity,
+    Background,
+    PaperColor,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Draws the image as one continuous scribble line.";
+
+/// Upper bound of `Point Spacing` (full-resolution pixels).
+const MAX_SPACING: f32 = 200.0;
+/// Upper bound of the line widths (full-resolution pixels).
+const MAX_WIDTH: f32 = 50.0;
+/// Upper bound of the Chaikin smoothing passes.
+const MAX_SMOOTHING: u32 = 4;
+/// Upper bound of the stippled points; the spacing grows to stay below it.
+const MAX_POINTS: f32 = 60000.0;
+/// Stipple sites are jittered by up to this fraction of the spacing.
+const SITE_JITTER: f32 = 0.35;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineColor {
+    Ink,
+    Source,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Paper,
+    Transparent,
+    Input,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Point Spacing: distance between tour points where the image is fully dark
+        params.add(
+            Params::PointSpacing,
+            tr("Point Spacing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(2.0);
+                d.set_valid_max(MAX_SPACING);
+                d.set_slider_min(2.0);
+                d.set_slider_max(50.0);
+                d.set_default(8.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Min Width / Max Width: line width in bright and dark areas
+        params.add(
+            Params::MinWidth,
+            tr("Min Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_WIDTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::MaxWidth,
+            tr("Max Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_WIDTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(2.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Smoothing: Chaikin corner-cutting passes over the tour
+        params.add(
+            Params::Smoothing,
+            tr("Smoothing"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(MAX_SMOOTHING as i32);
+                d.set_slider_min(0);
+                d.set_slider_max(MAX_SMOOTHING as i32);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Progress: drawn length of the line in percent, for draw-on animation
+        params.add(
+            Params::Progress,
+            tr("Progress"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LineColor,
+            tr("Line Color"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Ink"), tr("Source")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::InkColor,
+            tr("Ink Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Invert Density: trace bright areas instead of dark ones
+        params.add(
+            Params::InvertDensity,
+            tr("Invert Density"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Background,
+            tr("Background"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Paper"), tr("Transparent"), tr("Input")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::PaperColor,
+            tr("Paper Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ScribbleArt - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 間隔と線幅はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let ink = params.get(Params::InkColor)?.as_color()?.float_value()?;
+        let paper = params.get(Params::PaperColor)?.as_color()?.float_value()?;
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = ScribbleParams {
+            spacing: ds.length(slider(Params::PointSpacing)?),
+            min_width: ds.length(slider(Params::MinWidth)?),
+            max_width: ds.length(slider(Params::MaxWidth)?),
+            smoothing: params.get(Params::Smoothing)?.as_slider()?.value() as u32,
+            progress: slider(Params::Progress)? / 100.0,
+            line_color: match params.get(Params::LineColor)?.as_popup()?.value() {
+                2 => LineColor::Source,
+                _ => LineColor::Ink,
+            },
+            ink_color: [ink.red, ink.green, ink.blue],
+            invert_density: params.get(Params::InvertDensity)?.as_checkbox()?.value(),
+            background: match params.get(Params::Background)?.as_popup()?.value() {
+                2 => Background::Transparent,
+                3 => Background::Input,
+                _ => Background::Paper,
+            },
+            paper_color: [paper.red, paper.green, paper.blue],
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        scribble(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ScribbleParams {
+    /// Point spacing at full density, in render pixels.
+    pub spacing: f32,
+    /// Line width in bright areas, in render pixels.
+    pub min_width: f32,
+    /// Line width in dark areas, in render pixels.
+    pub max_width: f32,
+    pub smoothing: u32,
+    /// 0..1 of the tour length.
+    pub progress: f32,
+    pub line_color: LineColor,
+    pub ink_color: [f32; 3],
+    pub invert_density: bool,
+    pub background: Background,
+    pub paper_color: [f32; 3],
+    pub seed: u32,
+}
+
+/// A tour vertex with the half-width and color of the line there.
+#[derive(Clone, Copy, Debug)]
+struct Vertex {
+    x: f32,
+    y: f32,
+    radius: f32,
+    color: [f32; 3],
+}
+
+impl Vertex {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            x: mix(self.x, other.x),
+            y: mix(self.y, other.y),
+            radius: mix(self.radius, other.radius),
+            color: std::array::from_fn(|c| mix(self.color[c], other.color[c])),
+        }
+    }
+}
+
+/// One jittered site per spacing cell, kept where the tiled blue-noise
+/// threshold is below the density.
+fn stipple_points(
+    density: &[f32],
+    width: usize,
+    height: usize,
+    spacing: f32,
+    seed: u32,
+) -> Vec<(f32, f32)> {
+    const N: usize = BLUE_NOISE_SIZE;
+    let map = blue_noise();
+    let (ox, oy) = (
+        hash(seed, 0, 0xb1) as usize % N,
+        hash(seed, 1, 0xb1) as usize % N,
+    );
+    let (nx, ny) = (
+        (width as f32 / spacing).ceil() as usize,
+        (height as f32 / spacing).ceil() as usize,
+    );
+
+    let mut points = Vec::new();
+    for j in 0..ny {
+        for i in 0..nx {
+            let key = hash(seed, i as u32, j as u32);
+            let jitter = |salt: u32| (hash_unit(key, salt, 0xb2) * 2.0 - 1.0) * SITE_JITTER;
+            let x = ((i as f32 + 0.5 + jitter(0)) * spacing).clamp(0.0, width as f32);
+            let y = ((j as f32 + 0.5 + jitter(1)) * spacing).clamp(0.0, height as f32);
+            let threshold = map[((j + oy) % N) * N + (i + ox) % N];
+            if threshold < density_at(density, width, height, x, y) {
+                points.push((x, y));
+            }
+        }
+    }
+    points
+}
+
+/// Greedy nearest-neighbour tour from the top-left point, with a uniform grid
+/// (cells of `cell` pixels) searched in growing rings.
+fn greedy_tour(points: &[(f32, f32)], width: usize, height: usize, cell: f32) -> Vec<usize> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let gw = (width as f32 / cell).ceil() as usize + 1;
+    let gh = (height as f32 / cell).ceil() as usize + 1;
+    let cell_of = |(x, y): (f32, f32)| {
+        (
+            ((x / cell) as usize).min(gw - 1),
+            ((y / cell) as usize).min(gh - 1),
+        )
+    };
+    let mut grid: Vec<Vec<usize>> = vec![Vec::new(); gw * gh];
+    for (i, &p) in points.iter().enumerate() {
+        let (cx, cy) = cell_of(p);
+        grid[cy * gw + cx].push(i);
+    }
+
+    let start = (0..points.len())
+        .min_by(|&a, &b| {
+            let key = |i: usize| points[i].0 + points[i].1;
+            key(a).total_cmp(&key(b))
+        })
+        .unwrap_or(0);
+    let mut tour = Vec::with_capacity(points.len());
+    let mut current = start;
+    loop {
+        let (cx, cy) = cell_of(points[current]);
+        let slot = &mut grid[cy * gw + cx];
+        if let Some(k) = slot.iter().position(|&i| i == current) {
+            slot.swap_remove(k);
+        }
+        tour.push(current);
+        if tour.len() == points.len() {
+            break;
+        }
+
+        // リング r の点は (r - 1) * cell より近くならないので、最良点より遠ければ打ち切る
+        let (px, py) = points[current];
+        let mut best: Option<(f32, usize)> = None;
+        for r in 0..gw.max(gh) as isize {
+            if let Some((d2, _)) = best
+                && r > 0
+                && ((r - 1) as f32 * cell).powi(2) >= d2
+            {
+                break;
+            }
+            for gy in cy as isize - r..=cy as isize + r {
+                if gy < 0 || gy >= gh as isize {
+                    continue;
+                }
+                let on_edge = gy == cy as isize - r || gy == cy as isize + r;
+                let step = if on_edge { 1 } else { (2 * r).max(1) as usize };
+                for gx in (cx as isize - r..=cx as isize + r).step_by(step) {
+                    if gx < 0 || gx >= gw as isize {
+                        continue;
+                    }
+                    for &i in &grid[gy as usize * gw + gx as usize] {
+                        let d2 = (points[i].0 - px).powi(2) + (points[i].1 - py).powi(2);
+                        if best.is_none_or(|(b, _)| d2 < b) {
+                            best = Some((d2, i));
+                        }
+                    }
+                }
+            }
+        }
+        match best {
+            Some((_, i)) => current = i,
+            None => break,
+        }
+    }
+    tour
+}
+
+/// Chaikin corner cutting; the end points stay in place.
+fn chaikin(path: &[Vertex]) -> Vec<Vertex> {
+    if path.len() < 3 {
+        return path.to_vec();
+    }
+    let mut out = Vec::with_capacity(path.len() * 2);
+    out.push(path[0]);
+    for pair in path.windows(2) {
+        out.push(pair[0].lerp(pair[1], 0.25));
+        out.push(pair[0].lerp(pair[1], 0.75));
+    }
+    out.push(path[path.len() - 1]);
+    out
+}
+
+/// Rasterizes the capsule from `a` to `b` (radius and color interpolated),
+/// keeping the strongest coverage per pixel so joints do not double up.
+fn draw_segment(
+    a: Vertex,
+    b: Vertex,
+    width: usize,
+    height: usize,
+    cover: &mut [f32],
+    colors: &mut [[f32; 3]],
+) {
+    let reach = a.radius.max(b.radius) + 1.0;
+    let x0 = (a.x.min(b.x) - reach).floor().max(0.0) as usize;
+    let y0 = (a.y.min(b.y) - reach).floor().max(0.0) as usize;
+    let x1 = ((a.x.max(b.x) + reach).ceil().max(0.0) as usize).min(width - 1);
+    let y1 = ((a.y.max(b.y) + reach).ceil().max(0.0) as usize).min(height - 1);
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let (px, py) = (x as f32 + 0.5 - a.x, y as f32 + 0.5 - a.y);
+            let t = if len2 > 0.0 {
+                ((px * dx + py * dy) / len2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let d = (px - dx * t).hypot(py - dy * t);
+            let k = coverage(d, a.radius + (b.radius - a.radius) * t);
+            let i = y * width + x;
+            if k > cover[i] {
+                cover[i] = k;
+                colors[i] = std::array::from_fn(|c| a.color[c] + (b.color[c] - a.color[c]) * t);
+            }
+        }
+    }
+}
+
+pub fn scribble(input: &ImageBuf, p: &ScribbleParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+
+    // 背景は乗算済みで持ち、線を上に重ねる
+    let paper = p.paper_color.map(|c| finite_or(c, 1.0));
+    for (dst, &src) in out.data.iter_mut().zip(&input.data) {
+        *dst = match p.background {
+            Background::Paper => PixelF32 {
+                red: paper[0],
+                green: paper[1],
+                blue: paper[2],
+                alpha: 1.0,
+            },
+            Background::Transparent => PixelF32::default(),
+            Background::Input => {
+                let a = finite_or(src.alpha, 0.0).clamp(0.0, 1.0);
+                PixelF32 {
+                    red: finite_or(src.red, 0.0) * a,
+                    green: finite_or(src.green, 0.0) * a,
+                    blue: finite_or(src.blue, 0.0) * a,
+                    alpha: a,
+                }
+            }
+        };
+    }
+
+    // 点数が上限を超えないよう間隔を広げる
+    let min_spacing = ((width * height) as f32 / MAX_POINTS).sqrt();
+    let spacing = finite_or(p.spacing, MAX_SPACING)
+        .clamp(1.0, MAX_SPACING)
+        .max(min_spacing);
+    let density = density_map(input, input.width, input.height, p.invert_density);
+    let points = stipple_points(&density, width, height, spacing, p.seed);
+    let tour = greedy_tour(&points, width, height, spacing);
+
+    let min_width = finite_or(p.min_width, 0.0).clamp(0.0, MAX_WIDTH);
+    let max_width = finite_or(p.max_width, 0.0).clamp(0.0, MAX_WIDTH);
+    let ink = p.ink_color.map(|c| finite_or(c, 0.0));
+    let mut path: Vec<Vertex> = tour
+        .iter()
+        .map(|&i| {
+            let (x, y) = points[i];
+            let d = density_at(&density, width, height, x, y);
+            let color = match p.line_color {
+                LineColor::Ink => ink,
+                LineColor::Source => {
+                    let px = input.get_clamped(x as isize, y as isize);
+                    [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0))
+                }
+            };
+            Vertex {
+                x,
+                y,
+                radius: (min_width + (max_width - min_width) * d) * 0.5,
+                color,
+            }
+        })
+        .collect();
+    for _ in 0..p.smoothing.min(MAX_SMOOTHING) {
+        path = chaikin(&path);
+    }
+
+    // 描画済みの長さで線を途中まで引く
+    let total: f32 = path
+        .windows(2)
+        .map(|s| (s[1].x - s[0].x).hypot(s[1].y - s[0].y))
+        .sum();
+    let mut remaining = finite_or(p.progress, 1.0).clamp(0.0, 1.0) * total;
+    let mut cover = vec![0.0; width * height];
+    let mut colors = vec![[0.0; 3]; width * height];
+    for s in path.windows(2) {
+        if remaining <= 0.0 {
+            break;
+        }
+        let len = (s[1].x - s[0].x).hypot(s[1].y - s[0].y);
+        let end = if len > remaining {
+            s[0].lerp(s[1], remaining / len)
+        } else {
+            s[1]
+        };
+        draw_segment(s[0], end, width, height, &mut cover, &mut colors);
+        remaining -= len;
+    }
+
+    for ((dst, &k), color) in out.data.iter_mut().zip(&cover).zip(&colors) {
+        if k > 0.0 {
+            dst.red = color[0] * k + dst.red * (1.0 - k);
+            dst.green = color[1] * k + dst.green * (1.0 - k);
+            dst.blue = color[2] * k + dst.blue * (1.0 - k);
+            dst.alpha = k + dst.alpha * (1.0 - k);
+        }
+        *dst = unpremultiply(*dst);
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct ScribbleArt;
+
+impl RenderCore for ScribbleArt {
+    type Params = ScribbleParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ScribbleParams {
+            spacing: rng.slider(2.0, MAX_SPACING),
+            min_width: rng.slider(0.0, MAX_WIDTH),
+            max_width: rng.slider(0.0, MAX_WIDTH),
+            smoothing: rng.int(0, MAX_SMOOTHING as i32) as u32,
+            progress: rng.slider(0.0, 1.0),
+            line_color: if rng.chance(0.5) {
+                LineColor::Ink
+            } else {
+                LineColor::Source
+            },
+            ink_color: [rng.unit(), rng.unit(), rng.unit()],
+            invert_density: rng.chance(0.5),
+            background: match rng.popup(1, 3) {
+                2 => Background::Transparent,
+                3 => Background::Input,
+                _ => Background::Paper,
+            },
+            paper_color: [rng.unit(), rng.unit(), rng.unit()],
+            seed: rng.popup(0, 10000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = scribble(input, params);
+    }
+}
@@ -0,0 +1,578 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::blur::blur;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::mask::distance_field;
+use utils::sampling::{Downsample, Edge};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Style,
+    Profile,
+    Width,
+    Depth,
+    Soften,
+    LightDirection,
+    LightElevation,
+    HighlightColor,
+    HighlightOpacity,
+    ShadowColor,
+    ShadowOpacity,
+    Specular,
+    Shininess,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Bevels and embosses the layer from its alpha.";
+
+/// Upper bound of `Width` (full-resolution pixels).
+const MAX_WIDTH: f32 = 500.0;
+/// Upper bound of `Soften` (full-resolution pixels).
+const MAX_SOFTEN: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// The bevel rises inward from the layer's edge.
+    InnerBevel,
+    /// The bevel rises from outside the layer up to its edge.
+    OuterBevel,
+    /// The bevel straddles the edge, half inside and half outside.
+    Emboss,
+}
+
+/// Cross-section of the bevel from its foot (0) to its top (1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// Straight slope.
+    Chamfer,
+    /// Quarter circle, steep at the foot and flat on top.
+    Round,
+    /// Smoothstep, flat at both ends.
+    Smooth,
+    /// Concave quarter circle, flat at the foot and steep on top.
+    Cove,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Style: where the bevel sits relative to the layer's edge
+        params.add(
+            Params::Style,
+            tr("Style"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Inner Bevel"), tr("Outer Bevel"), tr("Emboss")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Profile: cross-section curve of the bevel
+        params.add(
+            Params::Profile,
+            tr("Profile"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Chamfer"), tr("Round"), tr("Smooth"), tr("Cove")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Width: horizontal extent of the bevel in pixels
+        params.add(
+            Params::Width,
+            tr("Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_WIDTH);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(12.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Depth: bevel height relative to its width, 100 = 45 degree chamfer
+        params.add(
+            Params::Depth,
+            tr("Depth"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(300.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Soften: blurs the distance field in pixels to round off corners
+        params.add(
+            Params::Soften,
+            tr("Soften"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SOFTEN);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(1.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Light Direction: degrees, 0 = light from the right, clockwise on screen
+        params.add(
+            Params::LightDirection,
+            tr("Light Direction"),
+            AngleDef::setup(|d| {
+                d.set_default(-120.0);
+            }),
+        )?;
+
+        // Light Elevation: degrees above the layer plane
+        params.add(
+            Params::LightElevation,
+            tr("Light Elevation"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(90.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(90.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Highlight Color: screened over slopes facing the light
+        params.add(
+            Params::HighlightColor,
+            tr("Highlight Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::HighlightOpacity,
+            tr("Highlight Opacity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(75.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Shadow Color: multiplied over slopes facing away from the light
+        params.add(
+            Params::ShadowColor,
+            tr("Shadow Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::ShadowOpacity,
+            tr("Shadow Opacity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(75.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Specular: Blinn-Phong glint added to the highlight, 0 = off
+        params.add(
+            Params::Specular,
+            tr("Specular"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(400.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(200.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Shininess,
+            tr("Shininess"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(1024.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(256.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_SdfBevel - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let highlight = params
+            .get(Params::HighlightColor)?
+            .as_color()?
+            .float_value()?;
+        let shadow = params.get(Params::ShadowColor)?.as_color()?.float_value()?;
+
+        let settings = BevelParams {
+            style: match params.get(Params::Style)?.as_popup()?.value() {
+                2 => Style::OuterBevel,
+                3 => Style::Emboss,
+                _ => Style::InnerBevel,
+            },
+            profile: match params.get(Params::Profile)?.as_popup()?.value() {
+                2 => Profile::Round,
+                3 => Profile::Smooth,
+                4 => Profile::Cove,
+                _ => Profile::Chamfer,
+            },
+            width: ds.length(slider(Params::Width)?),
+            depth: slider(Params::Depth)? / 100.0,
+            soften: ds.length(slider(Params::Soften)?),
+            light_direction: params.get(Params::LightDirection)?.as_angle()?.value(),
+            light_elevation: slider(Params::LightElevation)?,
+            highlight_color: [highlight.red, highlight.green, highlight.blue],
+            highlight_opacity: slider(Params::HighlightOpacity)? / 100.0,
+            shadow_color: [shadow.red, shadow.green, shadow.blue],
+            shadow_opacity: slider(Params::ShadowOpacity)? / 100.0,
+            specular: slider(Params::Specular)? / 100.0,
+            shininess: slider(Params::Shininess)?,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        bevel(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BevelParams {
+    pub style: Style,
+    pub profile: Profile,
+    /// Render pixels.
+    pub width: f32,
+    /// Bevel height over width, 1 = 45 degree chamfer.
+    pub depth: f32,
+    /// Render pixels.
+    pub soften: f32,
+    /// Degrees, 0 = light from the right, clockwise on screen.
+    pub light_direction: f32,
+    /// Degrees above the layer plane.
+    pub light_elevation: f32,
+    pub highlight_color: [f32; 3],
+    /// 0..1
+    pub highlight_opacity: f32,
+    pub shadow_color: [f32; 3],
+    /// 0..1
+    pub shadow_opacity: f32,
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl Profile {
+    fn height(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Profile::Chamfer => t,
+            Profile::Round => (1.0 - (1.0 - t) * (1.0 - t)).sqrt(),
+            Profile::Smooth => t * t * (3.0 - 2.0 * t),
+            Profile::Cove => 1.0 - (1.0 - t * t).sqrt(),
+        }
+    }
+}
+
+/// Signed distance to the alpha edge in pixels, positive inside the layer.
+/// Anti-aliased edge pixels take their offset from alpha, so the field moves
+/// smoothly as shapes animate instead of snapping to whole pixels.
+fn signed_distance(alpha: &[f32], width: usize, height: usize, reach: f32) -> Vec<f32> {
+    let inside: Vec<bool> = alpha.iter().map(|&a| a >= 0.5).collect();
+    let outside: Vec<bool> = inside.iter().map(|&m| !m).collect();
+    let to_outside = distance_field(&outside, width, height);
+    let to_inside = distance_field(&inside, width, height);
+
+    (0..width * height)
+        .map(|i| {
+            // 画素中心どうしの距離なので、境界はその半画素手前にある
+            let d = if inside[i] {
+                to_outside[i].sqrt() as f32 - 0.5
+            } else {
+                0.5 - to_inside[i].sqrt() as f32
+            };
+            let a = alpha[i];
+            let d = if d.abs() < 1.0 && a > 0.0 && a < 1.0 {
+                a - 0.5
+            } else {
+                d
+            };
+            // 全面が内側 (外側) のときの無限大もここで有限にする
+            d.clamp(-reach, reach)
+        })
+        .collect()
+}
+
+pub fn bevel(input: &ImageBuf, p: &BevelParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+
+    let width = finite_or(p.width, 0.0).clamp(0.0, MAX_WIDTH);
+    let soften = finite_or(p.soften, 0.0).clamp(0.0, MAX_SOFTEN);
+    let alpha: Vec<f32> = input
+        .data
+        .iter()
+        .map(|px| finite_or(px.alpha, 0.0).clamp(0.0, 1.0))
+        .collect();
+    if width <= 0.0 {
+        return input.clone();
+    }
+
+    // ベベルとぼかしの届く範囲より先の距離は高さに効かない
+    let reach = width + 3.0 * soften + 2.0;
+    // 範囲外は端の値が続くものとして、フレーム端で距離場が曲がらないようにする
+    let dist = blur(
+        &signed_distance(&alpha, w, h, reach),
+        w,
+        h,
+        soften,
+        Edge::Clamp,
+    );
+
+    // 裾 (0) から頂上 (1) までの位置を距離から求め、断面カーブで高さにする
+    let depth = finite_or(p.depth, 1.0).clamp(0.0, 10.0);
+    let foot = match p.style {
+        Style::InnerBevel => 0.0,
+        Style::OuterBevel => -width,
+        Style::Emboss => -0.5 * width,
+    };
+    let heights: Vec<f32> = dist
+        .iter()
+        .map(|&d| p.profile.height((d - foot) / width) * width * depth)
+        .collect();
+
+    let direction = finite_or(p.light_direction, 0.0).to_radians();
+    let elevation = finite_or(p.light_elevation, 30.0)
+        .clamp(1.0, 90.0)
+        .to_radians();
+    let (sin_d, cos_d) = direction.sin_cos();
+    let (sin_e, cos_e) = elevation.sin_cos();
+    // 画面座標 (y 下向き) の光源方向と、視線 (0, 0, 1) とのハーフベクトル
+    let light = [cos_e * cos_d, cos_e * sin_d, sin_e];
+    let half = {
+        let v = [light[0], light[1], light[2] + 1.0];
+        let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        v.map(|c| c / len)
+    };
+    let shininess = finite_or(p.shininess, 32.0).clamp(1.0, 1024.0);
+    let specular = finite_or(p.specular, 0.0).clamp(0.0, 4.0);
+    let flat_glint = half[2].powf(shininess);
+
+    let highlight_color = p.highlight_color.map(|c| finite_or(c, 1.0).clamp(0.0, 1.0));
+    let shadow_color = p.shadow_color.map(|c| finite_or(c, 0.0).clamp(0.0, 1.0));
+    let highlight_opacity = finite_or(p.highlight_opacity, 0.0).clamp(0.0, 1.0);
+    let shadow_opacity = finite_or(p.shadow_opacity, 0.0).clamp(0.0, 1.0);
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let at = |x: usize, y: usize| heights[y * w + x];
+            let gx = (at((x + 1).min(w - 1), y) - at(x.saturating_sub(1), y)) * 0.5;
+            let gy = (at(x, (y + 1).min(h - 1)) - at(x, y.saturating_sub(1))) * 0.5;
+            let len = (gx * gx + gy * gy + 1.0).sqrt();
+            let normal = [-gx / len, -gy / len, 1.0 / len];
+
+            // 平らな面を基準に、光へ向く斜面はハイライト、背く斜面はシャドウにする
+            let ndl = normal[0] * light[0] + normal[1] * light[1] + normal[2] * light[2];
+            let lit = ((ndl - sin_e) / (1.0 - sin_e).max(1e-3)).clamp(0.0, 1.0);
+            let shade = ((sin_e - ndl) / sin_e).clamp(0.0, 1.0);
+            let ndh = normal[0] * half[0] + normal[1] * half[1] + normal[2] * half[2];
+            let glint = if ndl > 0.0 {
+                (ndh.max(0.0).powf(shininess) - flat_glint).max(0.0) * specular
+            } else {
+                0.0
+            };
+
+            let a = alpha[i];
+            let clip = match p.style {
+                Style::InnerBevel => a,
+                Style::OuterBevel => 1.0 - a,
+                Style::Emboss => 1.0,
+            };
+            let shadow_a = shade * shadow_opacity * clip;
+            let highlight_a = ((lit * highlight_opacity + glint) * clip).clamp(0.0, 1.0);
+
+            // 乗算済みで、シャドウを乗算、ハイライトをスクリーンで重ねる
+            let src = input.data[i];
+            let mut rgb = [src.red, src.green, src.blue].map(|c| finite_or(c, 0.0) * a);
+            let mut out_a = a;
+            for (c, v) in rgb.iter_mut().enumerate() {
+                let s = shadow_color[c] * shadow_a;
+                *v = s * *v + s * (1.0 - out_a) + *v * (1.0 - shadow_a);
+            }
+            out_a = shadow_a + out_a * (1.0 - shadow_a);
+            for (c, v) in rgb.iter_mut().enumerate() {
+                let s = highlight_color[c] * highlight_a;
+                *v = s + *v - s * *v;
+            }
+            out_a = highlight_a + out_a * (1.0 - highlight_a);
+
+            let unpremultiply = |v: f32| if out_a > 0.0 { v / out_a } else { 0.0 };
+            out.data[i] = PixelF32 {
+                red: unpremultiply(rgb[0]),
+                green: unpremultiply(rgb[1]),
+                blue: unpremultiply(rgb[2]),
+                alpha: out_a,
+            };
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct SdfBevel;
+
+impl RenderCore for SdfBevel {
+    type Params = BevelParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        BevelParams {
+            style: match rng.popup(1, 3) {
+                1 => Style::InnerBevel,
+                2 => Style::OuterBevel,
+                _ => Style::Emboss,
+            },
+            profile: match rng.popup(1, 4) {
+                1 => Profile::Chamfer,
+                2 => Profile::Round,
+                3 => Profile::Smooth,
+                _ => Profile::Cove,
+            },
+            width: rng.slider(0.0, 100.0),
+            depth: rng.slider(0.0, 3.0),
+            soften: rng.slider(0.0, 20.0),
+            light_direction: rng.slider(-360.0, 360.0),
+            light_elevation: rng.slider(1.0, 90.0),
+            highlight_color: [rng.unit(), rng.unit(), rng.unit()],
+            highlight_opacity: rng.unit(),
+            shadow_color: [rng.unit(), rng.unit(), rng.unit()],
+            shadow_opacity: rng.unit(),
+            specular: rng.slider(0.0, 2.0),
+            shininess: rng.slider(1.0, 1024.0),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = bevel(input, params);
+    }
+}
@@ -0,0 +1,232 @@
+// Keep in sync with `RenderParams` in ../mod.rs and `Scene` / `primitive_sdf` in lib.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    primitive: u32,
+    iterations: u32,
+    render_pass: u32,
+    samples: u32,
+    _pad: vec2<u32>,
+    // xyz: camera position, w: tan(fov / 2)
+    camera: vec4<f32>,
+    // xyz: view direction, w: object size
+    forward: vec4<f32>,
+    // xyz: screen right, w: ambient occlusion strength
+    right: vec4<f32>,
+    // xyz: screen up, w: depth range
+    up: vec4<f32>,
+    light: vec4<f32>,
+    albedo: vec4<f32>,
+    rotation: array<vec4<f32>, 3>,
+};
+
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+const PRIMITIVE_SPHERE: u32 = 0u;
+const PRIMITIVE_BOX: u32 = 1u;
+const PRIMITIVE_TORUS: u32 = 2u;
+const PRIMITIVE_GYROID: u32 = 3u;
+const PASS_NORMAL: u32 = 1u;
+const PASS_DEPTH: u32 = 2u;
+const MAX_STEPS: u32 = 160u;
+const BOUNDS: f32 = 1.5;
+const GYROID_FREQUENCY: f32 = 9.0;
+const GYROID_THICKNESS: f32 = 0.03;
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if (c <= 0.0031308) {
+        return c * 12.92;
+    }
+    return 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+}
+
+fn sd_box(p: vec3<f32>, b: f32) -> f32 {
+    let q = abs(p) - vec3<f32>(b);
+    return length(max(q, vec3<f32>(0.0))) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+fn primitive_sdf(p: vec3<f32>) -> f32 {
+    switch (params.primitive) {
+        case PRIMITIVE_SPHERE: {
+            return length(p) - 1.0;
+        }
+        case PRIMITIVE_BOX: {
+            return sd_box(p, 0.75);
+        }
+        case PRIMITIVE_TORUS: {
+            let q = vec2<f32>(length(p.xz) - 0.75, p.y);
+            return length(q) - 0.3;
+        }
+        case PRIMITIVE_GYROID: {
+            let f = GYROID_FREQUENCY;
+            let g = dot(sin(p * f), cos(p.zxy * f)) / f;
+            let shell = (abs(g) - GYROID_THICKNESS) * 0.6;
+            return max(length(p) - 1.0, shell);
+        }
+        default: {
+            let size = 0.75;
+            let q = p / size;
+            var d = sd_box(q, 1.0);
+            var s = 1.0;
+            for (var i = 0u; i < params.iterations; i = i + 1u) {
+                // GLSL の mod と同じ (負数でも 0..2)
+                let qs = q * s;
+                let a = qs - 2.0 * floor(qs / 2.0) - 1.0;
+                s = s * 3.0;
+                let r = abs(1.0 - 3.0 * abs(a));
+                let da = max(r.x, r.y);
+                let db = max(r.y, r.z);
+                let dc = max(r.z, r.x);
+                let c = (min(da, min(db, dc)) - 1.0) / s;
+                d = max(d, c);
+            }
+            return d * size;
+        }
+    }
+}
+
+fn map(p: vec3<f32>) -> f32 {
+    let size = params.forward.w;
+    let q = vec3<f32>(
+        dot(params.rotation[0].xyz, p),
+        dot(params.rotation[1].xyz, p),
+        dot(params.rotation[2].xyz, p),
+    ) / size;
+    return primitive_sdf(q) * size;
+}
+
+fn normal_at(p: vec3<f32>) -> vec3<f32> {
+    let h = 1e-3 * params.forward.w;
+    let k0 = vec3<f32>(1.0, -1.0, -1.0);
+    let k1 = vec3<f32>(-1.0, -1.0, 1.0);
+    let k2 = vec3<f32>(-1.0, 1.0, -1.0);
+    let k3 = vec3<f32>(1.0, 1.0, 1.0);
+    let n = k0 * map(p + k0 * h) + k1 * map(p + k1 * h) + k2 * map(p + k2 * h) + k3 * map(p + k3 * h);
+    let l = length(n);
+    if (l > 0.0) {
+        return n / l;
+    }
+    return n;
+}
+
+// Distance along `rd` to the surface, negative on a miss.
+fn march(rd: vec3<f32>) -> f32 {
+    let ro = params.camera.xyz;
+    let size = params.forward.w;
+    let radius = BOUNDS * size;
+    let b = dot(ro, rd);
+    let disc = b * b - (dot(ro, ro) - radius * radius);
+    if (disc < 0.0) {
+        return -1.0;
+    }
+    let s = sqrt(disc);
+    var t = max(-b - s, 0.0);
+    let far = -b + s;
+    if (far < 0.0) {
+        return -1.0;
+    }
+    for (var i = 0u; i < MAX_STEPS; i = i + 1u) {
+        let d = map(ro + rd * t);
+        if (d < 5e-4 * max(t, size)) {
+            return t;
+        }
+        t = t + d;
+        if (t > far) {
+            return -1.0;
+        }
+    }
+    return -1.0;
+}
+
+fn ambient_occlusion(p: vec3<f32>, n: vec3<f32>) -> f32 {
+    let size = params.forward.w;
+    var occlusion = 0.0;
+    var weight = 1.0;
+    for (var i = 0; i < 5; i = i + 1) {
+        let h = size * (0.01 + 0.12 * f32(i) / 4.0);
+        let d = map(p + n * h);
+        occlusion = occlusion + (h - d) * weight;
+        weight = weight * 0.95;
+    }
+    return clamp(1.0 - 3.0 * occlusion / size, 0.0, 1.0);
+}
+
+fn soft_shadow(p: vec3<f32>) -> f32 {
+    let size = params.forward.w;
+    let l = params.light.xyz;
+    var shadow = 1.0;
+    var t = 0.02 * size;
+    for (var i = 0; i < 48; i = i + 1) {
+        let h = map(p + l * t);
+        shadow = min(shadow, 8.0 * h / t);
+        t = t + clamp(h, 0.01 * size, 0.2 * size);
+        if (shadow < 1e-3 || t > 2.0 * BOUNDS * size) {
+            break;
+        }
+    }
+    return clamp(shadow, 0.0, 1.0);
+}
+
+fn trace(u: f32, v: f32) -> vec4<f32> {
+    let tan_half_fov = params.camera.w;
+    let rd = normalize(params.forward.xyz + params.right.xyz * (u * tan_half_fov) + params.up.xyz * (v * tan_half_fov));
+    let t = march(rd);
+    if (t < 0.0) {
+        return vec4<f32>(0.0);
+    }
+    let size = params.forward.w;
+    let p = params.camera.xyz + rd * t;
+    let n = normal_at(p);
+    if (params.render_pass == PASS_NORMAL) {
+        return vec4<f32>(n * 0.5 + 0.5, 1.0);
+    }
+    if (params.render_pass == PASS_DEPTH) {
+        let d = t / params.up.w;
+        return vec4<f32>(d, d, d, 1.0);
+    }
+
+    let l = params.light.xyz;
+    let ao = 1.0 - params.right.w * (1.0 - ambient_occlusion(p, n));
+    let diffuse = max(dot(n, l), 0.0);
+    var shadow = 0.0;
+    if (diffuse > 0.0) {
+        shadow = soft_shadow(p + n * (1e-3 * size));
+    }
+    let half_dir = normalize(l - rd);
+    let specular = 0.3 * pow(max(dot(n, half_dir), 0.0), 32.0) * diffuse * shadow;
+    let ambient = (0.2 + 0.1 * n.y) * ao;
+    let lit = diffuse * shadow + ambient;
+    let c = params.albedo.xyz * lit + vec3<f32>(specular);
+    return vec4<f32>(linear_to_srgb(c.x), linear_to_srgb(c.y), linear_to_srgb(c.z), 1.0);
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let w = f32(params.width);
+    let h = f32(params.height);
+    let aspect = w / max(h, 1.0);
+    let n = params.samples;
+
+    // カバレッジで重み付けして平均する
+    var acc = vec4<f32>(0.0);
+    for (var j = 0u; j < n; j = j + 1u) {
+        for (var i = 0u; i < n; i = i + 1u) {
+            let sx = f32(gid.x) + (f32(i) + 0.5) / f32(n);
+            let sy = f32(gid.y) + (f32(j) + 0.5) / f32(n);
+            let u = (2.0 * sx / w - 1.0) * aspect;
+            let v = 1.0 - 2.0 * sy / h;
+            let c = trace(u, v);
+            acc = acc + vec4<f32>(c.rgb * c.a, c.a);
+        }
+    }
+    acc = acc / f32(n * n);
+    var out = vec4<f32>(0.0);
+    if (acc.a > 0.0) {
+        out = vec4<f32>(max(acc.rgb / acc.a, vec3<f32>(0.0)), min(acc.a, 1.0));
+    }
+    output[gid.y * params.width + gid.x] = out;
+}
@@ -0,0 +1,912 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Filter};
+use utils::ui;
+
+#[cfg(feature = "gpu_wgpu")]
+mod gpu;
+#[cfg(feature = "gpu_wgpu")]
+use gpu::wgpu::{RenderParams, WgpuContext};
+#[cfg(feature = "gpu_wgpu")]
+use std::sync::OnceLock;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Primitive,
+    Iterations,
+    Size,
+    RotationX,
+    RotationY,
+    CameraDistance,
+    CameraOrbit,
+    CameraElevation,
+    FieldOfView,
+    LightAzimuth,
+    LightElevation,
+    Color,
+    AmbientOcclusion,
+    Pass,
+    DepthRange,
+    Antialiasing,
+    CpuResolution,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Raymarches 3D signed distance field shapes.";
+
+/// Upper bound of the Menger sponge `Iterations`.
+const MAX_ITERATIONS: i32 = 5;
+/// Upper bound of `Antialiasing` (samples per axis).
+const MAX_ANTIALIASING: i32 = 4;
+/// Raymarching steps per ray (keep in sync with `MAX_STEPS` in compute.wgsl).
+const MAX_STEPS: u32 = 160;
+/// Radius of the sphere bounding every primitive, in object sizes.
+const BOUNDS: f32 = 1.5;
+/// Gyroid frequency and half shell thickness in object space.
+const GYROID_FREQUENCY: f32 = 9.0;
+const GYROID_THICKNESS: f32 = 0.03;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Primitive {
+    Sphere,
+    Box,
+    Torus,
+    /// Gyroid shell clipped to a sphere.
+    Gyroid,
+    MengerSponge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pass {
+    /// Shaded color with transparent background.
+    Beauty,
+    /// World-space normal encoded as `n * 0.5 + 0.5`.
+    Normal,
+    /// Distance along the ray divided by `Depth Range` (near is dark).
+    Depth,
+}
+
+#[cfg(feature = "gpu_wgpu")]
+static WGPU_CONTEXT: OnceLock<Option<WgpuContext>> = OnceLock::new();
+
+#[cfg(feature = "gpu_wgpu")]
+fn wgpu_context() -> Option<&'static WgpuContext> {
+    WGPU_CONTEXT
+        .get_or_init(|| WgpuContext::new().ok())
+        .as_ref()
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Primitive: Menger Sponge shows Iterations (see update_param_visibility)
+        params.add_with_flags(
+            Params::Primitive,
+            tr("Primitive"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Sphere"),
+                    tr("Box"),
+                    tr("Torus"),
+                    tr("Gyroid"),
+                    tr("Menger Sponge"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_ITERATIONS);
+                d.set_default(3);
+            }),
+        )?;
+
+        // Size: object radius in scene units
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.01);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.1);
+                d.set_slider_max(4.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::RotationX,
+            tr("Rotation X"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        params.add(
+            Params::RotationY,
+            tr("Rotation Y"),
+            AngleDef::setup(|d| {
+                d.set_default(0.0);
+            }),
+        )?;
+
+        // Camera: orbits the origin at Camera Distance, looking at it
+        params.add(
+            Params::CameraDistance,
+            tr("Camera Distance"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.1);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(20.0);
+                d.set_default(4.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::CameraOrbit,
+            tr("Camera Orbit"),
+            AngleDef::setup(|d| {
+                d.set_default(30.0);
+            }),
+        )?;
+
+        // Camera Elevation: clamped to ±89° so the view never flips
+        params.add(
+            Params::CameraElevation,
+            tr("Camera Elevation"),
+            AngleDef::setup(|d| {
+                d.set_default(20.0);
+            }),
+        )?;
+
+        // Field of View: vertical, in degrees
+        params.add(
+            Params::FieldOfView,
+            tr("Field Of View"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(170.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(120.0);
+                d.set_default(45.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LightAzimuth,
+            tr("Light Azimuth"),
+            AngleDef::setup(|d| {
+                d.set_default(-45.0);
+            }),
+        )?;
+
+        params.add(
+            Params::LightElevation,
+            tr("Light Elevation"),
+            AngleDef::setup(|d| {
+                d.set_default(45.0);
+            }),
+        )?;
+
+        params.add(
+            Params::Color,
+            tr("Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 230,
+                    green: 160,
+                    blue: 100,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::AmbientOcclusion,
+            tr("Ambient Occlusion"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.8);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Pass: Depth shows Depth Range (see update_param_visibility)
+        params.add_with_flags(
+            Params::Pass,
+            tr("Pass"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Beauty"), tr("Normal"), tr("Depth")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Depth Range: distance mapped to white in the depth pass
+        params.add(
+            Params::DepthRange,
+            tr("Depth Range"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.01);
+                d.set_valid_max(10000.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(50.0);
+                d.set_default(10.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Antialiasing: samples per axis and pixel
+        params.add(
+            Params::Antialiasing,
+            tr("Antialiasing"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ANTIALIASING);
+                d.set_slider_min(1);
+                d.set_slider_max(MAX_ANTIALIASING);
+                d.set_default(2);
+            }),
+        )?;
+
+        // CPU Resolution: render scale when no GPU is available
+        params.add(
+            Params::CpuResolution,
+            tr("CPU Resolution"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Full"), tr("Half"), tr("Quarter")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_SdfRaymarch - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::Primitive | Params::Pass
+                ) =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer: _,
+                out_layer,
+            } => {
+                self.do_render(in_data, out_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let Some(out_layer) = out_layer_opt {
+                    self.do_render(in_data, out_data, out_layer, params)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn primitive(params: &Parameters<Params>) -> Result<Primitive, Error> {
+    Ok(match params.get(Params::Primitive)?.as_popup()?.value() {
+        2 => Primitive::Box,
+        3 => Primitive::Torus,
+        4 => Primitive::Gyroid,
+        5 => Primitive::MengerSponge,
+        _ => Primitive::Sphere,
+    })
+}
+
+fn pass(params: &Parameters<Params>) -> Result<Pass, Error> {
+    Ok(match params.get(Params::Pass)?.as_popup()?.value() {
+        2 => Pass::Normal,
+        3 => Pass::Depth,
+        _ => Pass::Beauty,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let menger = primitive(params)? == Primitive::MengerSponge;
+    let depth = pass(params)? == Pass::Depth;
+    for (id, visible) in [(Params::Iterations, menger), (Params::DepthRange, depth)] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        _in_data: InData,
+        _out_data: OutData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let angle =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_angle()?.value().to_radians()) };
+        let color = params.get(Params::Color)?.as_color()?.float_value()?;
+        let settings = SdfParams {
+            primitive: primitive(params)?,
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as u32,
+            size: params.get(Params::Size)?.as_float_slider()?.value() as f32,
+            rotation: [angle(Params::RotationX)?, angle(Params::RotationY)?],
+            camera_distance: params
+                .get(Params::CameraDistance)?
+                .as_float_slider()?
+                .value() as f32,
+            camera_orbit: angle(Params::CameraOrbit)?,
+            camera_elevation: angle(Params::CameraElevation)?,
+            field_of_view: params
+                .get(Params::FieldOfView)?
+                .as_float_slider()?
+                .value()
+                .to_radians() as f32,
+            light: [angle(Params::LightAzimuth)?, angle(Params::LightElevation)?],
+            color: [color.red, color.green, color.blue],
+            ambient_occlusion: params
+                .get(Params::AmbientOcclusion)?
+                .as_float_slider()?
+                .value() as f32,
+            pass: pass(params)?,
+            depth_range: params.get(Params::DepthRange)?.as_float_slider()?.value() as f32,
+            antialiasing: params.get(Params::Antialiasing)?.as_slider()?.value() as u32,
+            cpu_scale: match params.get(Params::CpuResolution)?.as_popup()?.value() {
+                1 => 1,
+                3 => 4,
+                _ => 2,
+            },
+        };
+
+        let (width, height) = (out_layer.width(), out_layer.height());
+        let scene = Scene::new(&settings);
+
+        #[cfg(feature = "gpu_wgpu")]
+        if let Some(ctx) = wgpu_context() {
+            let gpu_params = scene.render_params(width, height);
+            if let Ok(rgba) = ctx.render(&gpu_params) {
+                return ImageBuf::from_rgba(width, height, &rgba).write_to_layer(&mut out_layer);
+            }
+            // Fall through to the CPU path when the GPU dispatch fails.
+        }
+
+        render_cpu(&scene, width, height, settings.cpu_scale).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct SdfParams {
+    pub primitive: Primitive,
+    /// Menger sponge subdivisions.
+    pub iterations: u32,
+    /// Object radius in scene units.
+    pub size: f32,
+    /// Object rotation around X, then Y, in radians.
+    pub rotation: [f32; 2],
+    pub camera_distance: f32,
+    /// Camera yaw around the Y axis in radians.
+    pub camera_orbit: f32,
+    /// Camera pitch in radians.
+    pub camera_elevation: f32,
+    /// Vertical field of view in radians.
+    pub field_of_view: f32,
+    /// Light azimuth and elevation in radians.
+    pub light: [f32; 2],
+    /// Encoded object color.
+    pub color: [f32; 3],
+    /// Strength of the ambient occlusion in [0, 1].
+    pub ambient_occlusion: f32,
+    pub pass: Pass,
+    /// Distance mapped to white in the depth pass.
+    pub depth_range: f32,
+    /// Samples per axis and pixel.
+    pub antialiasing: u32,
+    /// Downscale factor of the CPU fallback.
+    pub cpu_scale: u32,
+}
+
+type Vec3 = [f32; 3];
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: Vec3, k: f32) -> Vec3 {
+    [a[0] * k, a[1] * k, a[2] * k]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: Vec3) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Vec3) -> Vec3 {
+    let l = length(a);
+    if l > 0.0 { scale(a, 1.0 / l) } else { a }
+}
+
+/// Unit vector from azimuth (around Y, 0 = +Z) and elevation.
+fn spherical(azimuth: f32, elevation: f32) -> Vec3 {
+    [
+        elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+        elevation.cos() * azimuth.cos(),
+    ]
+}
+
+/// Sanitized render settings shared by the CPU path and the GPU uniforms.
+struct Scene {
+    primitive: Primitive,
+    iterations: u32,
+    size: f32,
+    /// Rows of the world-to-object rotation.
+    rotation: [Vec3; 3],
+    camera: Vec3,
+    forward: Vec3,
+    right: Vec3,
+    up: Vec3,
+    tan_half_fov: f32,
+    light: Vec3,
+    /// Linear object color.
+    albedo: Vec3,
+    ambient_occlusion: f32,
+    pass: Pass,
+    depth_range: f32,
+    samples: u32,
+}
+
+impl Scene {
+    fn new(p: &SdfParams) -> Self {
+        let size = finite_or(p.size, 1.0).clamp(0.01, 100.0);
+        let (rx, ry) = (finite_or(p.rotation[0], 0.0), finite_or(p.rotation[1], 0.0));
+        // ワールド→オブジェクト: Y 回転の逆、続いて X 回転の逆
+        let (sx, cx) = (-rx).sin_cos();
+        let (sy, cy) = (-ry).sin_cos();
+        let rotation = [
+            [cy, 0.0, sy],
+            [sx * sy, cx, -sx * cy],
+            [-cx * sy, sx, cx * cy],
+        ];
+
+        let distance = finite_or(p.camera_distance, 4.0).clamp(0.1, 1000.0);
+        let limit = 89f32.to_radians();
+        let elevation = finite_or(p.camera_elevation, 0.0).clamp(-limit, limit);
+        let camera = scale(
+            spherical(finite_or(p.camera_orbit, 0.0), elevation),
+            distance,
+        );
+        let forward = normalize(scale(camera, -1.0));
+        let right = normalize(cross(forward, [0.0, 1.0, 0.0]));
+        let up = cross(right, forward);
+        let fov = finite_or(p.field_of_view, 45f32.to_radians())
+            .clamp(1f32.to_radians(), 170f32.to_radians());
+
+        Self {
+            primitive: p.primitive,
+            iterations: p.iterations.clamp(1, MAX_ITERATIONS as u32),
+            size,
+            rotation,
+            camera,
+            forward,
+            right,
+            up,
+            tan_half_fov: (fov * 0.5).tan(),
+            light: spherical(finite_or(p.light[0], 0.0), finite_or(p.light[1], 0.0)),
+            albedo: p
+                .color
+                .map(|c| srgb_to_linear(finite_or(c, 0.0).clamp(0.0, 1.0))),
+            ambient_occlusion: finite_or(p.ambient_occlusion, 0.0).clamp(0.0, 1.0),
+            pass: p.pass,
+            depth_range: finite_or(p.depth_range, 10.0).clamp(0.01, 10000.0),
+            samples: p.antialiasing.clamp(1, MAX_ANTIALIASING as u32),
+        }
+    }
+
+    #[cfg(feature = "gpu_wgpu")]
+    fn render_params(&self, width: usize, height: usize) -> RenderParams {
+        let v4 = |v: Vec3, w: f32| [v[0], v[1], v[2], w];
+        RenderParams {
+            width: width as u32,
+            height: height as u32,
+            primitive: match self.primitive {
+                Primitive::Sphere => 0,
+                Primitive::Box => 1,
+                Primitive::Torus => 2,
+                Primitive::Gyroid => 3,
+                Primitive::MengerSponge => 4,
+            },
+            iterations: self.iterations,
+            render_pass: match self.pass {
+                Pass::Beauty => 0,
+                Pass::Normal => 1,
+                Pass::Depth => 2,
+            },
+            samples: self.samples,
+            _pad: [0; 2],
+            camera: v4(self.camera, self.tan_half_fov),
+            forward: v4(self.forward, self.size),
+            right: v4(self.right, self.ambient_occlusion),
+            up: v4(self.up, self.depth_range),
+            light: v4(self.light, 0.0),
+            albedo: v4(self.albedo, 0.0),
+            rotation: [
+                v4(self.rotation[0], 0.0),
+                v4(self.rotation[1], 0.0),
+                v4(self.rotation[2], 0.0),
+            ],
+        }
+    }
+
+    /// Signed distance of world point `p` to the object.
+    fn map(&self, p: Vec3) -> f32 {
+        let q = scale(
+            [
+                dot(self.rotation[0], p),
+                dot(self.rotation[1], p),
+                dot(self.rotation[2], p),
+            ],
+            1.0 / self.size,
+        );
+        primitive_sdf(self.primitive, self.iterations, q) * self.size
+    }
+
+    fn normal(&self, p: Vec3) -> Vec3 {
+        // 四面体サンプリングによる勾配
+        let h = 1e-3 * self.size;
+        let mut n = [0.0; 3];
+        for k in [
+            [1.0, -1.0, -1.0],
+            [-1.0, -1.0, 1.0],
+            [-1.0, 1.0, -1.0],
+            [1.0, 1.0, 1.0],
+        ] {
+            n = add(n, scale(k, self.map(add(p, scale(k, h)))));
+        }
+        normalize(n)
+    }
+
+    /// Distance along `rd` to the surface, `None` on a miss.
+    fn march(&self, rd: Vec3) -> Option<f32> {
+        let ro = self.camera;
+        // 境界球との交差で探索区間を絞る
+        let radius = BOUNDS * self.size;
+        let b = dot(ro, rd);
+        let disc = b * b - (dot(ro, ro) - radius * radius);
+        if disc < 0.0 {
+            return None;
+        }
+        let s = disc.sqrt();
+        let (mut t, far) = ((-b - s).max(0.0), -b + s);
+        if far < 0.0 {
+            return None;
+        }
+        for _ in 0..MAX_STEPS {
+            let d = self.map(add(ro, scale(rd, t)));
+            if d < 5e-4 * t.max(self.size) {
+                return Some(t);
+            }
+            t += d;
+            if t > far {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn ambient_occlusion(&self, p: Vec3, n: Vec3) -> f32 {
+        let mut occlusion = 0.0;
+        let mut weight = 1.0;
+        for i in 0..5 {
+            let h = self.size * (0.01 + 0.12 * i as f32 / 4.0);
+            let d = self.map(add(p, scale(n, h)));
+            occlusion += (h - d) * weight;
+            weight *= 0.95;
+        }
+        (1.0 - 3.0 * occlusion / self.size).clamp(0.0, 1.0)
+    }
+
+    fn soft_shadow(&self, p: Vec3) -> f32 {
+        let mut shadow = 1.0f32;
+        let mut t = 0.02 * self.size;
+        for _ in 0..48 {
+            let h = self.map(add(p, scale(self.light, t)));
+            shadow = shadow.min(8.0 * h / t);
+            t += h.clamp(0.01 * self.size, 0.2 * self.size);
+            if shadow < 1e-3 || t > 2.0 * BOUNDS * self.size {
+                break;
+            }
+        }
+        shadow.clamp(0.0, 1.0)
+    }
+
+    /// Straight color and coverage of the ray through normalized screen
+    /// coordinates (`u`, `v`), `v` pointing up.
+    fn trace(&self, u: f32, v: f32) -> [f32; 4] {
+        let rd = normalize(add(
+            self.forward,
+            add(
+                scale(self.right, u * self.tan_half_fov),
+                scale(self.up, v * self.tan_half_fov),
+            ),
+        ));
+        let Some(t) = self.march(rd) else {
+            return [0.0; 4];
+        };
+        let p = add(self.camera, scale(rd, t));
+        let n = self.normal(p);
+        match self.pass {
+            Pass::Normal => [n[0] * 0.5 + 0.5, n[1] * 0.5 + 0.5, n[2] * 0.5 + 0.5, 1.0],
+            Pass::Depth => {
+                let d = t / self.depth_range;
+                [d, d, d, 1.0]
+            }
+            Pass::Beauty => {
+                let ao = 1.0 - self.ambient_occlusion * (1.0 - self.ambient_occlusion(p, n));
+                let diffuse = dot(n, self.light).max(0.0);
+                let shadow = if diffuse > 0.0 {
+                    self.soft_shadow(add(p, scale(n, 1e-3 * self.size)))
+                } else {
+                    0.0
+                };
+                let half = normalize(sub(self.light, rd));
+                let specular = 0.3 * dot(n, half).max(0.0).powi(32) * diffuse * shadow;
+                let ambient = (0.2 + 0.1 * n[1]) * ao;
+                let lit = diffuse * shadow + ambient;
+                let c = self.albedo.map(|a| linear_to_srgb(a * lit + specular));
+                [c[0], c[1], c[2], 1.0]
+            }
+        }
+    }
+
+    /// Antialiased straight pixel at (`x`, `y`) of a `width`×`height` frame.
+    fn pixel(&self, x: usize, y: usize, width: usize, height: usize) -> PixelF32 {
+        let n = self.samples;
+        let aspect = width as f32 / height.max(1) as f32;
+        let mut acc = [0.0f32; 4];
+        for j in 0..n {
+            for i in 0..n {
+                let sx = x as f32 + (i as f32 + 0.5) / n as f32;
+                let sy = y as f32 + (j as f32 + 0.5) / n as f32;
+                let u = (2.0 * sx / width as f32 - 1.0) * aspect;
+                let v = 1.0 - 2.0 * sy / height as f32;
+                let c = self.trace(u, v);
+                // カバレッジで重み付けして平均する
+                for (a, c) in acc
+                    .iter_mut()
+                    .zip([c[0] * c[3], c[1] * c[3], c[2] * c[3], c[3]])
+                {
+                    *a += c;
+                }
+            }
+        }
+        let weight = 1.0 / (n * n) as f32;
+        sampling::unpremultiply(PixelF32 {
+            red: acc[0] * weight,
+            green: acc[1] * weight,
+            blue: acc[2] * weight,
+            alpha: acc[3] * weight,
+        })
+    }
+}
+
+fn sd_box(p: Vec3, b: f32) -> f32 {
+    let q = p.map(|c| c.abs() - b);
+    let outside = length(q.map(|c| c.max(0.0)));
+    outside + q[0].max(q[1]).max(q[2]).min(0.0)
+}
+
+/// Signed distance in object space (object radius about 1).
+fn primitive_sdf(primitive: Primitive, iterations: u32, p: Vec3) -> f32 {
+    match primitive {
+        Primitive::Sphere => length(p) - 1.0,
+        Primitive::Box => sd_box(p, 0.75),
+        Primitive::Torus => {
+            let q = [(p[0] * p[0] + p[2] * p[2]).sqrt() - 0.75, p[1]];
+            (q[0] * q[0] + q[1] * q[1]).sqrt() - 0.3
+        }
+        Primitive::Gyroid => {
+            let f = GYROID_FREQUENCY;
+            let s = p.map(|c| (c * f).sin());
+            let c = [(p[2] * f).cos(), (p[0] * f).cos(), (p[1] * f).cos()];
+            // ジャイロイドは厳密な距離ではないので係数で歩幅を抑える
+            let shell = ((dot(s, c) / f).abs() - GYROID_THICKNESS) * 0.6;
+            (length(p) - 1.0).max(shell)
+        }
+        Primitive::MengerSponge => {
+            let size = 0.75;
+            let p = scale(p, 1.0 / size);
+            let mut d = sd_box(p, 1.0);
+            let mut s = 1.0;
+            for _ in 0..iterations {
+                let a = p.map(|c| (c * s).rem_euclid(2.0) - 1.0);
+                s *= 3.0;
+                let r = a.map(|c| (1.0 - 3.0 * c.abs()).abs());
+                let da = r[0].max(r[1]);
+                let db = r[1].max(r[2]);
+                let dc = r[2].max(r[0]);
+                let c = (da.min(db).min(dc) - 1.0) / s;
+                d = d.max(c);
+            }
+            d * size
+        }
+    }
+}
+
+/// CPU path: renders at `1 / cpu_scale` of the frame and upscales bilinearly.
+fn render_cpu(scene: &Scene, width: usize, height: usize, cpu_scale: u32) -> ImageBuf {
+    let k = cpu_scale.clamp(1, 4) as usize;
+    let (lw, lh) = (width.div_ceil(k), height.div_ceil(k));
+    let mut low = ImageBuf::new(lw, lh);
+    for y in 0..lh {
+        for x in 0..lw {
+            low.set(x, y, scene.pixel(x, y, lw, lh));
+        }
+    }
+    if k == 1 {
+        return low;
+    }
+
+    let premul = sampling::premultiply(&low);
+    let mut output = ImageBuf::new(width, height);
+    let (fx, fy) = (lw as f32 / width as f32, lh as f32 / height as f32);
+    for y in 0..height {
+        for x in 0..width {
+            let sx = (x as f32 + 0.5) * fx - 0.5;
+            let sy = (y as f32 + 0.5) * fy - 0.5;
+            let px = sampling::sample(&premul, sx, sy, Filter::Bilinear);
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`), exercising the CPU path.
+pub struct SdfRaymarch;
+
+impl RenderCore for SdfRaymarch {
+    type Params = SdfParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        SdfParams {
+            primitive: match rng.popup(1, 5) {
+                2 => Primitive::Box,
+                3 => Primitive::Torus,
+                4 => Primitive::Gyroid,
+                5 => Primitive::MengerSponge,
+                _ => Primitive::Sphere,
+            },
+            iterations: rng.int(1, MAX_ITERATIONS) as u32,
+            size: rng.slider(0.01, 100.0),
+            rotation: [
+                rng.slider(-720.0, 720.0).to_radians(),
+                rng.slider(-720.0, 720.0).to_radians(),
+            ],
+            camera_distance: rng.slider(0.1, 1000.0),
+            camera_orbit: rng.slider(-720.0, 720.0).to_radians(),
+            camera_elevation: rng.slider(-720.0, 720.0).to_radians(),
+            field_of_view: rng.slider(1.0, 170.0).to_radians(),
+            light: [
+                rng.slider(-720.0, 720.0).to_radians(),
+                rng.slider(-720.0, 720.0).to_radians(),
+            ],
+            color: [rng.unit(), rng.unit(), rng.unit()],
+            ambient_occlusion: rng.slider(0.0, 1.0),
+            pass: match rng.popup(1, 3) {
+                2 => Pass::Normal,
+                3 => Pass::Depth,
+                _ => Pass::Beauty,
+            },
+            depth_range: rng.slider(0.01, 10000.0),
+            antialiasing: rng.int(1, MAX_ANTIALIASING) as u32,
+            cpu_scale: match rng.popup(1, 3) {
+                1 => 1,
+                3 => 4,
+                _ => 2,
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let scene = Scene::new(params);
+        *output = render_cpu(&scene, input.width, input.height, params.cpu_scale);
+    }
+}
@@ -0,0 +1,531 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::hash_unit;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::mask::distance_field;
+use utils::sampling::{Downsample, unpremultiply};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Method,
+    BlendWidth,
+    SeamWidth,
+    PatchSize,
+    Seed,
+    View,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Makes the layer tile seamlessly.";
+
+/// Upper bound of `Seam Width` (full-resolution pixels).
+const MAX_SEAM_WIDTH: f32 = 1000.0;
+/// Upper bound of `Patch Size` (full-resolution pixels).
+const MAX_PATCH_SIZE: f32 = 1000.0;
+/// Source positions tried for every seam patch.
+const CANDIDATES: u32 = 96;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Blends the layer with copies offset by half a tile towards the edges.
+    CrossFade,
+    /// Offsets by half a tile and covers the resulting seams with matching patches.
+    PatchRepair,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Tile,
+    /// Four copies of the tile at half size.
+    Preview,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add_with_flags(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Cross-Fade"), tr("Patch Repair")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Blend Width: cross-fade ramp as a percentage of half the layer
+        params.add(
+            Params::BlendWidth,
+            tr("Blend Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(1.0);
+                d.set_slider_max(100.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Seam Width: band around the seams replaced by patches
+        params.add(
+            Params::SeamWidth,
+            tr("Seam Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(2.0);
+                d.set_valid_max(MAX_SEAM_WIDTH);
+                d.set_slider_min(2.0);
+                d.set_slider_max(200.0);
+                d.set_default(24.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::PatchSize,
+            tr("Patch Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(4.0);
+                d.set_valid_max(MAX_PATCH_SIZE);
+                d.set_slider_min(4.0);
+                d.set_slider_max(256.0);
+                d.set_default(48.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        params.add(
+            Params::View,
+            tr("View"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Tile"), tr("2x2 Preview")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_SeamlessTile - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Method =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let patch = params.get(Params::Method)?.as_popup()?.value() == 2;
+    for (id, visible) in [
+        (Params::BlendWidth, !patch),
+        (Params::SeamWidth, patch),
+        (Params::PatchSize, patch),
+        (Params::Seed, patch),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 継ぎ目幅とパッチサイズはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = TileParams {
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                2 => Method::PatchRepair,
+                _ => Method::CrossFade,
+            },
+            blend_width: params.get(Params::BlendWidth)?.as_float_slider()?.value() as f32 / 100.0,
+            seam_width: ds.length(params.get(Params::SeamWidth)?.as_float_slider()?.value() as f32),
+            patch_size: ds.length(params.get(Params::PatchSize)?.as_float_slider()?.value() as f32),
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            view: match params.get(Params::View)?.as_popup()?.value() {
+                2 => View::Preview,
+                _ => View::Tile,
+            },
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        seamless_tile(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TileParams {
+    pub method: Method,
+    /// 0..1 of half the layer size.
+    pub blend_width: f32,
+    /// Render pixels.
+    pub seam_width: f32,
+    /// Render pixels.
+    pub patch_size: f32,
+    pub seed: u32,
+    pub view: View,
+}
+
+/// Premultiplied RGBA with non-finite channels as 0.
+fn premultiplied(px: PixelF32) -> [f32; 4] {
+    let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+    let c = |v: f32| finite_or(v, 0.0) * a;
+    [c(px.red), c(px.green), c(px.blue), a]
+}
+
+/// Wrapped premultiplied image addressed with signed coordinates.
+struct Tiled {
+    width: usize,
+    height: usize,
+    data: Vec<[f32; 4]>,
+}
+
+impl Tiled {
+    fn get(&self, x: isize, y: isize) -> [f32; 4] {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.data[y * self.width + x]
+    }
+
+    /// Copy rolled by (`dx`, `dy`): `out(x, y) = self(x + dx, y + dy)`.
+    fn rolled(&self, dx: isize, dy: isize) -> Self {
+        let data = (0..self.width * self.height)
+            .map(|i| {
+                self.get(
+                    (i % self.width) as isize + dx,
+                    (i / self.width) as isize + dy,
+                )
+            })
+            .collect();
+        Self {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Blends the layer with its half-offset copies: the original in the middle,
+/// the offset copies (whose seams lie in the middle) towards the edges.
+fn cross_fade(image: &Tiled, blend: f32) -> Tiled {
+    let (w, h) = (image.width, image.height);
+    let (hx, hy) = ((w / 2) as isize, (h / 2) as isize);
+    let blend = finite_or(blend, 0.3).clamp(0.01, 1.0);
+    // 端からの距離で、元画像の重みを 0 → 1 に立ち上げる
+    let ramp = |i: usize, n: usize| {
+        let edge = (i as f32 + 0.5).min(n as f32 - i as f32 - 0.5);
+        smoothstep(edge / (blend * n as f32 * 0.5))
+    };
+    let data = (0..w * h)
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            let (ax, ay) = (ramp(x, w), ramp(y, h));
+            let (xi, yi) = (x as isize, y as isize);
+            let taps = [
+                (image.get(xi, yi), ax * ay),
+                (image.get(xi + hx, yi), (1.0 - ax) * ay),
+                (image.get(xi, yi + hy), ax * (1.0 - ay)),
+                (image.get(xi + hx, yi + hy), (1.0 - ax) * (1.0 - ay)),
+            ];
+            std::array::from_fn(|k| taps.iter().map(|(c, wt)| c[k] * wt).sum())
+        })
+        .collect();
+    Tiled {
+        width: w,
+        height: h,
+        data,
+    }
+}
+
+/// Offsets the layer by half a tile, then covers the cross-shaped seam with
+/// patches copied from the best-matching places of the layer, feathered by
+/// the distance to the seam.
+fn patch_repair(image: &Tiled, p: &TileParams) -> Tiled {
+    let (w, h) = (image.width, image.height);
+    let (hx, hy) = ((w / 2) as isize, (h / 2) as isize);
+    let mut offset = image.rolled(hx, hy);
+    // ロールした画像の継ぎ目は x = w - w/2, y = h - h/2 の手前
+    let (sx, sy) = (w - w / 2, h - h / 2);
+
+    let marks: Vec<bool> = (0..w * h)
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            x + 1 == sx || x == sx || y + 1 == sy || y == sy
+        })
+        .collect();
+    let dist: Vec<f32> = distance_field(&marks, w, h)
+        .into_iter()
+        .map(|d| d.sqrt() as f32)
+        .collect();
+
+    let limit = (w.min(h) as f32 * 0.5).max(2.0);
+    let half = (finite_or(p.seam_width, 24.0) * 0.5).clamp(1.0, limit);
+    // パッチは継ぎ目の帯とぼかし幅を覆う大きさ以上にする
+    let radius = (finite_or(p.patch_size, 48.0) * 0.5)
+        .clamp(2.0, limit)
+        .max(2.0 * half + 1.0)
+        .round() as isize;
+    // 継ぎ目の帯は 1、その外側の同じ幅でなめらかに 0 へ
+    let weight = |i: usize| (2.0 - dist[i] / half).clamp(0.0, 1.0);
+
+    let mut centers = Vec::new();
+    let step = (radius as usize).max(1);
+    for y in (0..h).step_by(step) {
+        centers.push((sx as isize, y as isize));
+    }
+    for x in (0..w).step_by(step) {
+        centers.push((x as isize, sy as isize));
+    }
+
+    // パッチ自身が継ぎ目をまたがないよう、候補は継ぎ目から離れた位置に限る
+    let clear = radius as f32 + 2.0 * half;
+    let away =
+        |x: f32, c: usize, n: usize| (x - c as f32).abs().min(n as f32 - (x - c as f32).abs());
+    let mut acc = vec![[0.0f32; 5]; w * h];
+    for (n, &(tx, ty)) in centers.iter().enumerate() {
+        let mut best = (f32::INFINITY, (tx, ty));
+        for k in 0..CANDIDATES {
+            let cx = hash_unit(p.seed, n as u32, 2 * k) * w as f32;
+            let cy = hash_unit(p.seed, n as u32, 2 * k + 1) * h as f32;
+            if (away(cx, sx, w) < clear || away(cy, sy, h) < clear) && k + 1 < CANDIDATES {
+                continue;
+            }
+            let (cx, cy) = (cx as isize, cy as isize);
+            // 帯の外側 (文脈) だけを比べる
+            let mut cost = 0.0;
+            let mut count = 0usize;
+            for oy in (-radius..=radius).step_by(2) {
+                for ox in (-radius..=radius).step_by(2) {
+                    let (x, y) = (
+                        (tx + ox).rem_euclid(w as isize),
+                        (ty + oy).rem_euclid(h as isize),
+                    );
+                    let i = y as usize * w + x as usize;
+                    if weight(i) >= 1.0 {
+                        continue;
+                    }
+                    let a = offset.data[i];
+                    let b = offset.get(cx + ox, cy + oy);
+                    cost += (0..4).map(|c| (a[c] - b[c]).powi(2)).sum::<f32>();
+                    count += 1;
+                }
+            }
+            let cost = if count > 0 { cost / count as f32 } else { 0.0 };
+            if cost < best.0 {
+                best = (cost, (cx, cy));
+            }
+        }
+
+        let (cx, cy) = best.1;
+        for oy in -radius..=radius {
+            for ox in -radius..=radius {
+                let (x, y) = (
+                    (tx + ox).rem_euclid(w as isize),
+                    (ty + oy).rem_euclid(h as isize),
+                );
+                let i = y as usize * w + x as usize;
+                // 隣のパッチと重なる部分はテント窓で混ぜる
+                let window = (1.0 - ox.abs() as f32 / (radius + 1) as f32)
+                    * (1.0 - oy.abs() as f32 / (radius + 1) as f32);
+                let c = offset.get(cx + ox, cy + oy);
+                for k in 0..4 {
+                    acc[i][k] += c[k] * window;
+                }
+                acc[i][4] += window;
+            }
+        }
+    }
+
+    for (i, px) in offset.data.iter_mut().enumerate() {
+        let t = weight(i);
+        if t <= 0.0 || acc[i][4] <= 0.0 {
+            continue;
+        }
+        for k in 0..4 {
+            px[k] += (acc[i][k] / acc[i][4] - px[k]) * t;
+        }
+    }
+    // 元画像と同じ位置関係に戻す (継ぎ目の修復箇所はレイヤーの端に来る)
+    offset.rolled(sx as isize, sy as isize)
+}
+
+pub fn seamless_tile(input: &ImageBuf, p: &TileParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+    let image = Tiled {
+        width: w,
+        height: h,
+        data: input.data.iter().map(|&px| premultiplied(px)).collect(),
+    };
+    let tile = match p.method {
+        Method::CrossFade => cross_fade(&image, p.blend_width),
+        Method::PatchRepair => patch_repair(&image, p),
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let c = match p.view {
+                View::Tile => tile.data[y * w + x],
+                View::Preview => {
+                    // 半分の大きさのタイルを 2x2 に並べる (2x2 画素の平均で縮小)
+                    let (x2, y2) = (2 * x as isize, 2 * y as isize);
+                    let taps =
+                        [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(i, j)| tile.get(x2 + i, y2 + j));
+                    std::array::from_fn(|k| taps.iter().map(|c| c[k]).sum::<f32>() * 0.25)
+                }
+            };
+            out.set(
+                x,
+                y,
+                unpremultiply(PixelF32 {
+                    red: c[0],
+                    green: c[1],
+                    blue: c[2],
+                    alpha: c[3],
+                }),
+            );
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct SeamlessTile;
+
+impl RenderCore for SeamlessTile {
+    type Params = TileParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        TileParams {
+            method: if rng.chance(0.5) {
+                Method::CrossFade
+            } else {
+                Method::PatchRepair
+            },
+            blend_width: rng.slider(0.01, 1.0),
+            seam_width: rng.slider(2.0, MAX_SEAM_WIDTH),
+            patch_size: rng.slider(4.0, MAX_PATCH_SIZE),
+            seed: rng.popup(0, 10000) as u32,
+            view: if rng.chance(0.5) {
+                View::Tile
+            } else {
+                View::Preview
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = seamless_tile(input, params);
+    }
+}
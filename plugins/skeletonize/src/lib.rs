@@ -0,0 +1,406 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::mask;
+use utils::sampling::Downsample;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Channel,
+    Threshold,
+    Method,
+    Output,
+    LineWidth,
+    LineColor,
+    DistanceRange,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Reduces a silhouette to its skeleton lines.";
+
+/// Upper bound of `Line Width` (full-resolution pixels).
+const MAX_LINE_WIDTH: f32 = 100.0;
+/// Upper bound of `Distance Range` (full-resolution pixels).
+const MAX_DISTANCE_RANGE: f32 = 4000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Alpha,
+    Luminance,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Zhang–Suen thinning: connected, one pixel wide.
+    Thinning,
+    /// Ridges of the distance to the silhouette edge: centered, may break up.
+    MedialAxis,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    SkeletonOverInput,
+    SkeletonOnly,
+    /// Red: skeleton, green: distance to the skeleton, blue: distance to the
+    /// silhouette edge (medial radius on the skeleton), both over `Distance Range`.
+    DistanceChannels,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Channel,
+            tr("Channel"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Alpha"), tr("Luminance")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Threshold: pixels whose channel is above it belong to the silhouette
+        params.add(
+            Params::Threshold,
+            tr("Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Method,
+            tr("Method"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Thinning"), tr("Medial Axis")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Skeleton Over Input"),
+                    tr("Skeleton Only"),
+                    tr("Distance Channels"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LineWidth,
+            tr("Line Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_LINE_WIDTH);
+                d.set_slider_min(1.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LineColor,
+            tr("Line Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Distance Range: distance mapped to 1 in the Distance Channels output
+        params.add(
+            Params::DistanceRange,
+            tr("Distance Range"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_DISTANCE_RANGE);
+                d.set_slider_min(1.0);
+                d.set_slider_max(200.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Skeletonize - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 線幅と距離レンジはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let color = params.get(Params::LineColor)?.as_color()?.float_value()?;
+
+        let settings = SkeletonParams {
+            channel: match params.get(Params::Channel)?.as_popup()?.value() {
+                2 => Channel::Luminance,
+                _ => Channel::Alpha,
+            },
+            threshold: params.get(Params::Threshold)?.as_float_slider()?.value() as f32,
+            method: match params.get(Params::Method)?.as_popup()?.value() {
+                2 => Method::MedialAxis,
+                _ => Method::Thinning,
+            },
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => Output::SkeletonOnly,
+                3 => Output::DistanceChannels,
+                _ => Output::SkeletonOverInput,
+            },
+            line_width: ds.length(params.get(Params::LineWidth)?.as_float_slider()?.value() as f32),
+            line_color: [color.red, color.green, color.blue],
+            distance_range: ds.length(
+                params
+                    .get(Params::DistanceRange)?
+                    .as_float_slider()?
+                    .value() as f32,
+            ),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        skeletonize(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct SkeletonParams {
+    pub channel: Channel,
+    pub threshold: f32,
+    pub method: Method,
+    pub output: Output,
+    /// Line width in render pixels.
+    pub line_width: f32,
+    /// Encoded line color.
+    pub line_color: [f32; 3],
+    /// Distance in render pixels mapped to 1 by the distance channels.
+    pub distance_range: f32,
+}
+
+/// Ridge pixels of the distance field `dist` (squared distance to the
+/// background): at least as far from the edge as both neighbours along some
+/// axis or diagonal, and farther than one of them.
+fn medial_axis(dist: &[f64], width: usize, height: usize) -> Vec<bool> {
+    let at = |x: isize, y: isize| {
+        if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            0.0
+        } else {
+            dist[y as usize * width + x as usize]
+        }
+    };
+    let mut axis = vec![false; dist.len()];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let d = at(x, y);
+            if d <= 0.0 {
+                continue;
+            }
+            axis[y as usize * width + x as usize] =
+                [(1, 0), (0, 1), (1, 1), (1, -1)].iter().any(|&(dx, dy)| {
+                    let (a, b) = (at(x - dx, y - dy), at(x + dx, y + dy));
+                    d >= a && d >= b && (d > a || d > b)
+                });
+        }
+    }
+    axis
+}
+
+fn skeletonize(input: &ImageBuf, p: &SkeletonParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+    let threshold = finite_or(p.threshold, 0.5);
+    let silhouette: Vec<bool> = input
+        .data
+        .iter()
+        .map(|&px| {
+            let v = match p.channel {
+                Channel::Alpha => px.alpha,
+                Channel::Luminance => luminance(px),
+            };
+            finite_or(v, 0.0) > threshold
+        })
+        .collect();
+    let background: Vec<bool> = silhouette.iter().map(|&s| !s).collect();
+    // 輪郭までの距離 (二乗)。前景がない場合は無限大
+    let edge_dist = mask::distance_field(&background, width, height);
+    let skeleton = match p.method {
+        Method::Thinning => mask::thin(&silhouette, width, height),
+        Method::MedialAxis => medial_axis(&edge_dist, width, height),
+    };
+    let skeleton_dist = mask::distance_field(&skeleton, width, height);
+
+    let half_width = finite_or(p.line_width, 1.0).clamp(1.0, MAX_LINE_WIDTH) * 0.5;
+    let range = finite_or(p.distance_range, 1.0).clamp(1e-3, MAX_DISTANCE_RANGE) as f64;
+    let color = p.line_color.map(|c| finite_or(c, 1.0));
+    for (i, o) in out.data.iter_mut().enumerate() {
+        let d = skeleton_dist[i].sqrt() as f32;
+        let line = (half_width - d + 0.5).clamp(0.0, 1.0);
+        let px = input.data[i];
+        *o = match p.output {
+            Output::SkeletonOverInput => PixelF32 {
+                red: px.red + (color[0] - px.red) * line,
+                green: px.green + (color[1] - px.green) * line,
+                blue: px.blue + (color[2] - px.blue) * line,
+                alpha: px.alpha.max(line),
+            },
+            Output::SkeletonOnly => PixelF32 {
+                red: color[0],
+                green: color[1],
+                blue: color[2],
+                alpha: line,
+            },
+            Output::DistanceChannels => {
+                let edge = if silhouette[i] {
+                    edge_dist[i].sqrt()
+                } else {
+                    0.0
+                };
+                PixelF32 {
+                    red: line,
+                    green: (skeleton_dist[i].sqrt() / range).min(1.0) as f32,
+                    blue: (edge / range).min(1.0) as f32,
+                    alpha: 1.0,
+                }
+            }
+        };
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct Skeletonize;
+
+impl RenderCore for Skeletonize {
+    type Params = SkeletonParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        SkeletonParams {
+            channel: if rng.chance(0.5) {
+                Channel::Alpha
+            } else {
+                Channel::Luminance
+            },
+            threshold: rng.slider(0.0, 1.0),
+            method: if rng.chance(0.5) {
+                Method::Thinning
+            } else {
+                Method::MedialAxis
+            },
+            output: match rng.popup(1, 3) {
+                2 => Output::SkeletonOnly,
+                3 => Output::DistanceChannels,
+                _ => Output::SkeletonOverInput,
+            },
+            line_width: rng.slider(1.0, MAX_LINE_WIDTH),
+            line_color: [rng.unit(), rng.unit(), rng.unit()],
+            distance_range: rng.slider(1.0, MAX_DISTANCE_RANGE),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = skeletonize(input, params);
+    }
+}
@@ -0,0 +1,441 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::VecDeque;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::regions::{self, RegionOutput};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    RegionCount,
+    Compactness,
+    Iterations,
+    Output,
+    BoundaryColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Segments the frame into SLIC superpixels.";
+
+const MIN_REGIONS: i32 = 2;
+const MAX_REGIONS: i32 = 20000;
+const MIN_COMPACTNESS: f32 = 0.1;
+const MAX_COMPACTNESS: f32 = 100.0;
+const MAX_ITERATIONS: i32 = 30;
+/// OKLab (and alpha) is scaled to roughly 0..100 so `Compactness` matches the
+/// usual CIELAB range of SLIC.
+const LAB_SCALE: f32 = 100.0;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Region Count: approximate number of superpixels over the frame
+        params.add(
+            Params::RegionCount,
+            tr("Region Count"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_REGIONS);
+                d.set_valid_max(MAX_REGIONS);
+                d.set_slider_min(MIN_REGIONS);
+                d.set_slider_max(2000);
+                d.set_default(400);
+            }),
+        )?;
+
+        // Compactness: higher favors square regions over following color edges
+        params.add(
+            Params::Compactness,
+            tr("Compactness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(MIN_COMPACTNESS);
+                d.set_valid_max(MAX_COMPACTNESS);
+                d.set_slider_min(1.0);
+                d.set_slider_max(40.0);
+                d.set_default(10.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Iterations,
+            tr("Iterations"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(MAX_ITERATIONS);
+                d.set_slider_min(1);
+                d.set_slider_max(20);
+                d.set_default(10);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Mean Color"), tr("Region ID"), tr("Boundaries")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Boundary Color: used by the Boundaries output
+        params.add(
+            Params::BoundaryColor,
+            tr("Boundary Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_SlicSuperpixels - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let boundary = params
+            .get(Params::BoundaryColor)?
+            .as_color()?
+            .float_value()?;
+
+        let settings = SlicParams {
+            region_count: params.get(Params::RegionCount)?.as_slider()?.value() as u32,
+            compactness: params.get(Params::Compactness)?.as_float_slider()?.value() as f32,
+            iterations: params.get(Params::Iterations)?.as_slider()?.value() as u32,
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => RegionOutput::RegionId,
+                3 => RegionOutput::Boundaries,
+                _ => RegionOutput::MeanColor,
+            },
+            boundary_color: [boundary.red, boundary.green, boundary.blue],
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        superpixels(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct SlicParams {
+    pub region_count: u32,
+    pub compactness: f32,
+    pub iterations: u32,
+    pub output: RegionOutput,
+    /// Encoded boundary color.
+    pub boundary_color: [f32; 3],
+}
+
+/// Cluster center: scaled OKLab, alpha and position.
+#[derive(Clone, Copy, Debug, Default)]
+struct Center {
+    color: [f32; 4],
+    x: f32,
+    y: f32,
+}
+
+/// Scaled OKLab and alpha of every pixel.
+fn features(input: &ImageBuf) -> Vec<[f32; 4]> {
+    input
+        .data
+        .iter()
+        .map(|px| {
+            let rgb = [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0).max(0.0));
+            let lab = Oklab::from_srgb(rgb);
+            [
+                lab.l * LAB_SCALE,
+                lab.a * LAB_SCALE,
+                lab.b * LAB_SCALE,
+                finite_or(px.alpha, 0.0).clamp(0.0, 1.0) * LAB_SCALE,
+            ]
+        })
+        .collect()
+}
+
+fn color_distance2(a: [f32; 4], b: [f32; 4]) -> f32 {
+    (0..4).map(|k| (a[k] - b[k]).powi(2)).sum()
+}
+
+/// SLIC (Achanta et al.): k-means in color + position, each center searching
+/// only a 2S × 2S window around itself. Returns the label of every pixel.
+fn slic(feat: &[[f32; 4]], width: usize, height: usize, p: &SlicParams) -> Vec<u32> {
+    let count = p.region_count.clamp(MIN_REGIONS as u32, MAX_REGIONS as u32) as f32;
+    let step = ((width * height) as f32 / count).sqrt().max(1.0);
+    let compactness = finite_or(p.compactness, 10.0).clamp(MIN_COMPACTNESS, MAX_COMPACTNESS);
+    let weight = (compactness / step).powi(2);
+    let at = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, height as isize - 1) as usize;
+        feat[y * width + x]
+    };
+
+    // 格子状に配置し、3×3 近傍で勾配の最も小さい画素へずらす
+    let (cols, rows) = (
+        ((width as f32 / step).round() as usize).max(1),
+        ((height as f32 / step).round() as usize).max(1),
+    );
+    let mut centers = Vec::with_capacity(cols * rows);
+    for j in 0..rows {
+        for i in 0..cols {
+            let cx = ((i as f32 + 0.5) * width as f32 / cols as f32) as isize;
+            let cy = ((j as f32 + 0.5) * height as f32 / rows as f32) as isize;
+            let gradient = |x: isize, y: isize| {
+                color_distance2(at(x + 1, y), at(x - 1, y))
+                    + color_distance2(at(x, y + 1), at(x, y - 1))
+            };
+            let (mut best, mut best_g) = ((cx, cy), f32::INFINITY);
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (x, y) = (
+                        (cx + dx).clamp(0, width as isize - 1),
+                        (cy + dy).clamp(0, height as isize - 1),
+                    );
+                    let g = gradient(x, y);
+                    if g < best_g {
+                        (best, best_g) = ((x, y), g);
+                    }
+                }
+            }
+            centers.push(Center {
+                color: at(best.0, best.1),
+                x: best.0 as f32,
+                y: best.1 as f32,
+            });
+        }
+    }
+
+    let mut labels = vec![0u32; width * height];
+    let mut distance = vec![f32::INFINITY; width * height];
+    let reach = step.ceil() as isize;
+    for _ in 0..p.iterations.clamp(1, MAX_ITERATIONS as u32) {
+        distance.fill(f32::INFINITY);
+        for (k, c) in centers.iter().enumerate() {
+            let (cx, cy) = (c.x.round() as isize, c.y.round() as isize);
+            for y in (cy - reach).max(0)..(cy + reach + 1).min(height as isize) {
+                for x in (cx - reach).max(0)..(cx + reach + 1).min(width as isize) {
+                    let i = y as usize * width + x as usize;
+                    let ds = (x as f32 - c.x).powi(2) + (y as f32 - c.y).powi(2);
+                    let d = color_distance2(feat[i], c.color) + ds * weight;
+                    if d < distance[i] {
+                        distance[i] = d;
+                        labels[i] = k as u32;
+                    }
+                }
+            }
+        }
+
+        // 割り当てられた画素の平均へ中心を移す (空になった中心はそのまま)
+        let mut sums = vec![[0.0f64; 7]; centers.len()];
+        for (i, &l) in labels.iter().enumerate() {
+            if !distance[i].is_finite() {
+                continue;
+            }
+            let s = &mut sums[l as usize];
+            for k in 0..4 {
+                s[k] += feat[i][k] as f64;
+            }
+            s[4] += (i % width) as f64;
+            s[5] += (i / width) as f64;
+            s[6] += 1.0;
+        }
+        for (c, s) in centers.iter_mut().zip(&sums) {
+            if s[6] > 0.0 {
+                c.color = std::array::from_fn(|k| (s[k] / s[6]) as f32);
+                c.x = (s[4] / s[6]) as f32;
+                c.y = (s[5] / s[6]) as f32;
+            }
+        }
+    }
+
+    // 窓の届かなかった画素は最も近い中心に入れる
+    for (i, l) in labels.iter_mut().enumerate() {
+        if !distance[i].is_finite() {
+            let (x, y) = ((i % width) as f32, (i / width) as f32);
+            *l = centers
+                .iter()
+                .enumerate()
+                .min_by(|a, b| {
+                    let da = (a.1.x - x).powi(2) + (a.1.y - y).powi(2);
+                    let db = (b.1.x - x).powi(2) + (b.1.y - y).powi(2);
+                    da.total_cmp(&db)
+                })
+                .map_or(0, |(k, _)| k as u32);
+        }
+    }
+    enforce_connectivity(&labels, width, height, (step * step / 4.0) as usize)
+}
+
+/// Splits every label into its 4-connected components, merges components
+/// smaller than `min_size` into a neighbouring one and renumbers the regions
+/// 0, 1, 2, … in scan order.
+fn enforce_connectivity(labels: &[u32], width: usize, height: usize, min_size: usize) -> Vec<u32> {
+    let mut out = vec![u32::MAX; labels.len()];
+    let mut next = 0u32;
+    let mut queue = VecDeque::new();
+    let mut component = Vec::new();
+    for start in 0..labels.len() {
+        if out[start] != u32::MAX {
+            continue;
+        }
+        // 走査順で先に確定した隣接領域 (小さな断片の吸収先)
+        let (sx, sy) = (start % width, start / width);
+        let adjacent = [(sx > 0).then(|| start - 1), (sy > 0).then(|| start - width)]
+            .into_iter()
+            .flatten()
+            .map(|j| out[j])
+            .find(|&l| l != u32::MAX);
+
+        component.clear();
+        queue.push_back(start);
+        out[start] = next;
+        while let Some(i) = queue.pop_front() {
+            component.push(i);
+            let (x, y) = (i % width, i / width);
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then(|| i + 1),
+                (y > 0).then(|| i - width),
+                (y + 1 < height).then(|| i + width),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if out[j] == u32::MAX && labels[j] == labels[start] {
+                    out[j] = next;
+                    queue.push_back(j);
+                }
+            }
+        }
+        match adjacent {
+            Some(l) if component.len() < min_size => {
+                for &i in &component {
+                    out[i] = l;
+                }
+            }
+            _ => next += 1,
+        }
+    }
+    out
+}
+
+fn superpixels(input: &ImageBuf, p: &SlicParams) -> ImageBuf {
+    if input.is_empty() {
+        return ImageBuf::new(input.width, input.height);
+    }
+    let feat = features(input);
+    let labels = slic(&feat, input.width, input.height, p);
+    regions::render_labels(input, &labels, p.output, p.boundary_color)
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct SlicSuperpixels;
+
+impl RenderCore for SlicSuperpixels {
+    type Params = SlicParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        SlicParams {
+            region_count: rng.popup(MIN_REGIONS, MAX_REGIONS) as u32,
+            compactness: rng.slider(MIN_COMPACTNESS, MAX_COMPACTNESS),
+            iterations: rng.popup(1, MAX_ITERATIONS) as u32,
+            output: match rng.popup(1, 3) {
+                2 => RegionOutput::RegionId,
+                3 => RegionOutput::Boundaries,
+                _ => RegionOutput::MeanColor,
+            },
+            boundary_color: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = superpixels(input, params);
+    }
+}
@@ -0,0 +1,615 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{hash, hash_unit};
+use utils::density::{density_at, density_map};
+use utils::dither::{BLUE_NOISE_SIZE, blue_noise};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::poisson::PoissonGrid;
+use utils::sampling::{Downsample, unpremultiply};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Placement,
+    DotSpacing,
+    DotSize,
+    SizeJitter,
+    Shape,
+    RotationJitter,
+    DotColor,
+    InkColor,
+    InvertDensity,
+    Background,
+    PaperColor,
+    Seed,
+    AnimateSeed,
+    HoldFrames,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Redraws the layer as stippled dots.";
+
+/// Upper bound of `Dot Spacing` (full-resolution pixels).
+const MAX_SPACING: f32 = 200.0;
+/// Upper bound of `Dot Size` (full-resolution pixels).
+const MAX_DOT_SIZE: f32 = 100.0;
+/// Dart-throwing candidates per spacing-sized cell.
+const CANDIDATES_PER_CELL: f32 = 6.0;
+/// Largest Poisson radius as a multiple of the spacing; sparser areas are thinned randomly.
+const MAX_RADIUS_SCALE: f32 = 4.0;
+/// Upper bound of the dart-throwing candidates per frame.
+const MAX_CANDIDATES: usize = 1 << 23;
+/// Blue-noise sites are jittered by up to this fraction of the spacing.
+const SITE_JITTER: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    PoissonDisk,
+    BlueNoise,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Circle,
+    Square,
+    Diamond,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DotColor {
+    Source,
+    Ink,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Paper,
+    Transparent,
+    Input,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Placement,
+            tr("Placement"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Poisson Disk"), tr("Blue Noise")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Dot Spacing: distance between dots where the image is fully dark
+        params.add(
+            Params::DotSpacing,
+            tr("Dot Spacing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(2.0);
+                d.set_valid_max(MAX_SPACING);
+                d.set_slider_min(2.0);
+                d.set_slider_max(50.0);
+                d.set_default(6.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Dot Size: dot radius
+        params.add(
+            Params::DotSize,
+            tr("Dot Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.5);
+                d.set_valid_max(MAX_DOT_SIZE);
+                d.set_slider_min(0.5);
+                d.set_slider_max(25.0);
+                d.set_default(2.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::SizeJitter,
+            tr("Size Jitter"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.2);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Shape,
+            tr("Shape"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Circle"), tr("Square"), tr("Diamond")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Rotation Jitter: random turn of each square or diamond, 1 = up to a quarter turn
+        params.add(
+            Params::RotationJitter,
+            tr("Rotation Jitter"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::DotColor,
+            tr("Dot Color"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Source"), tr("Ink")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::InkColor,
+            tr("Ink Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Invert Density: place dots by brightness instead of darkness
+        params.add(
+            Params::InvertDensity,
+            tr("Invert Density"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        params.add(
+            Params::Background,
+            tr("Background"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Paper"), tr("Transparent"), tr("Input")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::PaperColor,
+            tr("Paper Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        params.add(
+            Params::AnimateSeed,
+            tr("Animate Seed"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+                d.set_label(tr("Per Frame"));
+            }),
+        )?;
+
+        // Hold Frames: keep each dot pattern for this many frames
+        params.add(
+            Params::HoldFrames,
+            tr("Hold Frames"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(1000);
+                d.set_slider_min(1);
+                d.set_slider_max(30);
+                d.set_default(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_Stipple - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 間隔とサイズはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let ink = params.get(Params::InkColor)?.as_color()?.float_value()?;
+        let paper = params.get(Params::PaperColor)?.as_color()?.float_value()?;
+
+        let settings = StippleParams {
+            placement: match params.get(Params::Placement)?.as_popup()?.value() {
+                2 => Placement::BlueNoise,
+                _ => Placement::PoissonDisk,
+            },
+            spacing: ds.length(params.get(Params::DotSpacing)?.as_float_slider()?.value() as f32),
+            dot_size: ds.length(params.get(Params::DotSize)?.as_float_slider()?.value() as f32),
+            size_jitter: params.get(Params::SizeJitter)?.as_float_slider()?.value() as f32,
+            shape: match params.get(Params::Shape)?.as_popup()?.value() {
+                2 => Shape::Square,
+                3 => Shape::Diamond,
+                _ => Shape::Circle,
+            },
+            rotation_jitter: params
+                .get(Params::RotationJitter)?
+                .as_float_slider()?
+                .value() as f32,
+            dot_color: match params.get(Params::DotColor)?.as_popup()?.value() {
+                2 => DotColor::Ink,
+                _ => DotColor::Source,
+            },
+            ink_color: [ink.red, ink.green, ink.blue],
+            invert_density: params.get(Params::InvertDensity)?.as_checkbox()?.value(),
+            background: match params.get(Params::Background)?.as_popup()?.value() {
+                2 => Background::Transparent,
+                3 => Background::Input,
+                _ => Background::Paper,
+            },
+            paper_color: [paper.red, paper.green, paper.blue],
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            animate_seed: params.get(Params::AnimateSeed)?.as_checkbox()?.value(),
+            hold_frames: params.get(Params::HoldFrames)?.as_slider()?.value() as u32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let frame = in_data.current_frame() as u32;
+        stipple(&input, &settings, frame).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct StippleParams {
+    pub placement: Placement,
+    /// Dot spacing at full density, in render pixels.
+    pub spacing: f32,
+    /// Dot radius in render pixels.
+    pub dot_size: f32,
+    pub size_jitter: f32,
+    pub shape: Shape,
+    /// 0..1 of a quarter turn.
+    pub rotation_jitter: f32,
+    pub dot_color: DotColor,
+    pub ink_color: [f32; 3],
+    pub invert_density: bool,
+    pub background: Background,
+    pub paper_color: [f32; 3],
+    pub seed: u32,
+    /// Re-roll the dots every `hold_frames` frames instead of holding them.
+    pub animate_seed: bool,
+    pub hold_frames: u32,
+}
+
+/// A placed dot; `key` seeds its size and rotation.
+struct Dot {
+    x: f32,
+    y: f32,
+    key: u32,
+}
+
+/// Dart throwing with a density-dependent radius: a candidate is kept when no
+/// earlier dot lies within `spacing / sqrt(density)` of it.
+fn poisson_dots(density: &[f32], width: usize, height: usize, spacing: f32, seed: u32) -> Vec<Dot> {
+    let (w, h) = (width as f32, height as f32);
+    let cells = (w / spacing) * (h / spacing);
+    let count = ((cells * CANDIDATES_PER_CELL) as usize).min(MAX_CANDIDATES);
+    let min_density = 1.0 / (MAX_RADIUS_SCALE * MAX_RADIUS_SCALE);
+
+    // 近傍探索用の格子 (セルは最小半径)
+    let mut grid = PoissonGrid::new(w, h, spacing);
+    let mut dots: Vec<Dot> = Vec::new();
+
+    for i in 0..count as u32 {
+        let (x, y) = (hash_unit(seed, i, 0) * w, hash_unit(seed, i, 1) * h);
+        let d = density_at(density, width, height, x, y);
+        if d <= 0.0 {
+            continue;
+        }
+        // 最大半径より疎な領域はランダムに間引いて濃度を保つ
+        if d < min_density && hash_unit(seed, i, 2) * min_density >= d {
+            continue;
+        }
+        let r = spacing / d.max(min_density).sqrt();
+        if grid.is_free(x, y, r) {
+            grid.insert(x, y);
+            dots.push(Dot {
+                x,
+                y,
+                key: hash(seed, i, 0xd07),
+            });
+        }
+    }
+    dots
+}
+
+/// One site per spacing cell, kept where the tiled blue-noise threshold is below the density.
+fn blue_noise_dots(
+    density: &[f32],
+    width: usize,
+    height: usize,
+    spacing: f32,
+    seed: u32,
+) -> Vec<Dot> {
+    const N: usize = BLUE_NOISE_SIZE;
+    let map = blue_noise();
+    let (ox, oy) = (
+        hash(seed, 0, 0xb1) as usize % N,
+        hash(seed, 1, 0xb1) as usize % N,
+    );
+    let (nx, ny) = (
+        (width as f32 / spacing).ceil() as usize,
+        (height as f32 / spacing).ceil() as usize,
+    );
+
+    let mut dots = Vec::new();
+    for j in 0..ny {
+        for i in 0..nx {
+            let key = hash(seed, i as u32, j as u32);
+            let jitter = |salt: u32| (hash_unit(key, salt, 0xb2) * 2.0 - 1.0) * SITE_JITTER;
+            let x = (i as f32 + 0.5 + jitter(0)) * spacing;
+            let y = (j as f32 + 0.5 + jitter(1)) * spacing;
+            let threshold = map[((j + oy) % N) * N + (i + ox) % N];
+            if threshold < density_at(density, width, height, x, y) {
+                dots.push(Dot { x, y, key });
+            }
+        }
+    }
+    dots
+}
+
+pub fn stipple(input: &ImageBuf, p: &StippleParams, frame: u32) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut out = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return out;
+    }
+
+    // 背景は乗算済みで持ち、ドットを上に重ねていく
+    let paper = p.paper_color.map(|c| finite_or(c, 1.0));
+    for (dst, &src) in out.data.iter_mut().zip(&input.data) {
+        *dst = match p.background {
+            Background::Paper => PixelF32 {
+                red: paper[0],
+                green: paper[1],
+                blue: paper[2],
+                alpha: 1.0,
+            },
+            Background::Transparent => PixelF32::default(),
+            Background::Input => {
+                let a = finite_or(src.alpha, 0.0).clamp(0.0, 1.0);
+                PixelF32 {
+                    red: finite_or(src.red, 0.0) * a,
+                    green: finite_or(src.green, 0.0) * a,
+                    blue: finite_or(src.blue, 0.0) * a,
+                    alpha: a,
+                }
+            }
+        };
+    }
+
+    let seed = if p.animate_seed {
+        hash(p.seed, frame / p.hold_frames.max(1), 0x5eed)
+    } else {
+        p.seed
+    };
+    let spacing = finite_or(p.spacing, MAX_SPACING).clamp(1.0, MAX_SPACING);
+    let density = density_map(input, input.width, input.height, p.invert_density);
+    let dots = match p.placement {
+        Placement::PoissonDisk => poisson_dots(&density, width, height, spacing, seed),
+        Placement::BlueNoise => blue_noise_dots(&density, width, height, spacing, seed),
+    };
+
+    let dot_size = finite_or(p.dot_size, 0.0).clamp(0.0, MAX_DOT_SIZE);
+    let size_jitter = finite_or(p.size_jitter, 0.0).clamp(0.0, 1.0);
+    let rotation_jitter = finite_or(p.rotation_jitter, 0.0).clamp(0.0, 1.0);
+    let ink = p.ink_color.map(|c| finite_or(c, 0.0));
+
+    for dot in &dots {
+        let radius = dot_size * (1.0 - size_jitter * hash_unit(dot.key, 1, 0));
+        if radius <= 0.0 {
+            continue;
+        }
+        let color = match p.dot_color {
+            DotColor::Source => {
+                let px = input.get_clamped(dot.x as isize, dot.y as isize);
+                [px.red, px.green, px.blue].map(|c| finite_or(c, 0.0))
+            }
+            DotColor::Ink => ink,
+        };
+        let angle =
+            (hash_unit(dot.key, 2, 0) - 0.5) * rotation_jitter * std::f32::consts::FRAC_PI_2;
+        let (sin, cos) = angle.sin_cos();
+
+        // 形状ごとの符号付き距離から 1px 幅のアンチエイリアス被覆率を求める
+        let reach = radius * std::f32::consts::SQRT_2 + 1.0;
+        let x0 = (dot.x - reach).floor().max(0.0) as usize;
+        let y0 = (dot.y - reach).floor().max(0.0) as usize;
+        let x1 = ((dot.x + reach).ceil().max(0.0) as usize).min(width - 1);
+        let y1 = ((dot.y + reach).ceil().max(0.0) as usize).min(height - 1);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let (dx, dy) = (x as f32 + 0.5 - dot.x, y as f32 + 0.5 - dot.y);
+                let (u, v) = (dx * cos + dy * sin, dy * cos - dx * sin);
+                let inside = match p.shape {
+                    Shape::Circle => radius - dx.hypot(dy),
+                    Shape::Square => radius - u.abs().max(v.abs()),
+                    Shape::Diamond => {
+                        (radius - (u.abs() + v.abs())) * std::f32::consts::FRAC_1_SQRT_2
+                    }
+                };
+                let k = (inside + 0.5).clamp(0.0, 1.0);
+                if k <= 0.0 {
+                    continue;
+                }
+                let i = out.index(x, y);
+                let dst = &mut out.data[i];
+                dst.red = color[0] * k + dst.red * (1.0 - k);
+                dst.green = color[1] * k + dst.green * (1.0 - k);
+                dst.blue = color[2] * k + dst.blue * (1.0 - k);
+                dst.alpha = k + dst.alpha * (1.0 - k);
+            }
+        }
+    }
+
+    for px in out.data.iter_mut() {
+        *px = unpremultiply(*px);
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct Stipple;
+
+impl RenderCore for Stipple {
+    type Params = StippleParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        StippleParams {
+            placement: if rng.chance(0.5) {
+                Placement::PoissonDisk
+            } else {
+                Placement::BlueNoise
+            },
+            spacing: rng.slider(2.0, MAX_SPACING),
+            dot_size: rng.slider(0.5, MAX_DOT_SIZE),
+            size_jitter: rng.slider(0.0, 1.0),
+            shape: match rng.popup(1, 3) {
+                2 => Shape::Square,
+                3 => Shape::Diamond,
+                _ => Shape::Circle,
+            },
+            rotation_jitter: rng.slider(0.0, 1.0),
+            dot_color: if rng.chance(0.5) {
+                DotColor::Source
+            } else {
+                DotColor::Ink
+            },
+            ink_color: [rng.unit(), rng.unit(), rng.unit()],
+            invert_density: rng.chance(0.5),
+            background: match rng.popup(1, 3) {
+                2 => Background::Transparent,
+                3 => Background::Input,
+                _ => Background::Paper,
+            },
+            paper_color: [rng.unit(), rng.unit(), rng.unit()],
+            seed: rng.popup(0, 10000) as u32,
+            animate_seed: rng.chance(0.5),
+            hold_frames: rng.int(1, 1000) as u32,
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = stipple(input, params, frame);
+    }
+}
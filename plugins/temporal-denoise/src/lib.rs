@@ -0,0 +1,636 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::VecDeque;
+use std::env;
+use std::sync::Mutex;
+
+use ae::pf::*;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::flow;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Strength,
+    NoiseThreshold,
+    Motion,
+    BlockSize,
+    SearchRadius,
+    MotionLayer,
+    VectorScale,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// Sequence data: the accumulated result of the previous frames.
+#[derive(Default)]
+struct Instance {
+    history: Mutex<History>,
+}
+
+ae::define_effect!(Plugin, Instance, Params);
+
+const PLUGIN_DESCRIPTION: &str = "Reduces noise by blending motion-compensated previous frames.";
+
+/// Checkout id of the motion vector layer (the input uses 0).
+const MOTION_CHECKOUT_ID: i32 = 1;
+/// Accumulated results kept per sequence: the previous frame and the current
+/// one, so re-rendering a frame still finds its predecessor.
+const HISTORY_FRAMES: usize = 2;
+const MIN_BLOCK_SIZE: i32 = 4;
+const MAX_BLOCK_SIZE: i32 = 64;
+const MAX_SEARCH_RADIUS: f32 = 64.0;
+const MAX_VECTOR_SCALE: f32 = 4096.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    /// Assumes a static shot.
+    Off,
+    /// Per-block displacement searched against the previous result.
+    BlockMatching,
+    /// Per-pixel displacement read from the Motion Layer.
+    VectorLayer,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Strength: weight of the accumulated history in the blend
+        params.add(
+            Params::Strength,
+            tr("Strength"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.8);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Noise Threshold: history may differ from the current frame by at
+        // most this much per channel; larger changes are kept as real motion
+        params.add(
+            Params::NoiseThreshold,
+            tr("Noise Threshold"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.1);
+                d.set_precision(3);
+            }),
+        )?;
+
+        // Motion: shows the matching or vector controls (see update_param_visibility)
+        params.add_with_flags(
+            Params::Motion,
+            tr("Motion"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Off"), tr("Block Matching"), tr("Motion Vector Layer")]);
+                d.set_default(2);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(
+            Params::BlockSize,
+            tr("Block Size"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_BLOCK_SIZE);
+                d.set_valid_max(MAX_BLOCK_SIZE);
+                d.set_slider_min(MIN_BLOCK_SIZE);
+                d.set_slider_max(32);
+                d.set_default(8);
+            }),
+        )?;
+
+        // Search Radius: largest displacement per frame found by matching, in pixels
+        params.add(
+            Params::SearchRadius,
+            tr("Search Radius"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SEARCH_RADIUS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(32.0);
+                d.set_default(8.0);
+                d.set_precision(0);
+            }),
+        )?;
+
+        // Motion Layer: RG = motion since the previous frame, 0.5 = still
+        params.add(Params::MotionLayer, tr("Motion Layer"), LayerDef::new())?;
+
+        // Vector Scale: displacement in pixels of a full-range (0 or 1) channel
+        params.add(
+            Params::VectorScale,
+            tr("Vector Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_VECTOR_SCALE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(256.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        _in_data: InData,
+        mut out_data: OutData,
+        _params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_TemporalDenoise - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::NonParamVary, true);
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+                out_data.set_out_flag2(OutFlags2::MutableRenderSequenceDataSlower, true);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn motion(params: &Parameters<Params>) -> Result<Motion, Error> {
+    Ok(match params.get(Params::Motion)?.as_popup()?.value() {
+        1 => Motion::Off,
+        3 => Motion::VectorLayer,
+        _ => Motion::BlockMatching,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let motion = motion(params)?;
+    for (id, visible) in [
+        (Params::BlockSize, motion == Motion::BlockMatching),
+        (Params::SearchRadius, motion == Motion::BlockMatching),
+        (Params::MotionLayer, motion == Motion::VectorLayer),
+        (Params::VectorScale, motion == Motion::VectorLayer),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl AdobePluginInstance for Instance {
+    fn flatten(&self) -> Result<(u16, Vec<u8>), Error> {
+        // 蓄積バッファは再生成できるキャッシュなので保存しない
+        Ok((1, Vec::new()))
+    }
+
+    fn unflatten(_version: u16, _serialized: &[u8]) -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    fn render(
+        &self,
+        plugin: &mut PluginState,
+        in_layer: &Layer,
+        out_layer: &mut Layer,
+    ) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        // 非 SmartRender ではモーションレイヤーをパラメータから直接取得する
+        let vectors = match motion(plugin.params)? {
+            Motion::VectorLayer => plugin
+                .params
+                .checkout_at(
+                    Params::MotionLayer,
+                    Some(in_data.current_time()),
+                    Some(in_data.time_step()),
+                    Some(in_data.time_scale()),
+                )?
+                .as_layer()?
+                .value()
+                .map(|layer| ImageBuf::from_layer(&layer)),
+            _ => None,
+        };
+        let input = ImageBuf::from_layer(in_layer);
+        self.do_render(in_data, &input, vectors.as_ref(), out_layer, plugin.params)
+    }
+
+    fn handle_command(&mut self, plugin: &mut PluginState, cmd: ae::Command) -> Result<(), Error> {
+        let in_data = plugin.in_data;
+        match cmd {
+            ae::Command::SequenceResetup => {
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+            }
+
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&plugin.in_data, plugin.params)?;
+            }
+
+            ae::Command::UserChangedParam { param_index } => {
+                // 設定が変わったら過去の蓄積は使えない
+                self.history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+                if plugin.params.type_at(param_index) == Params::Motion {
+                    update_param_visibility(&plugin.in_data, plugin.params)?;
+                }
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if motion(plugin.params)? == Motion::VectorLayer
+                    && let Some(index) = plugin.params.index(Params::MotionLayer)
+                {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        MOTION_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let vector_layer = motion(plugin.params)? == Motion::VectorLayer;
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let vectors = if vector_layer {
+                    cb.checkout_layer_pixels(MOTION_CHECKOUT_ID as u32)?
+                        .map(|layer| ImageBuf::from_layer(&layer))
+                } else {
+                    None
+                };
+
+                if let Some(in_layer) = in_layer_opt
+                    && let Some(mut out_layer) = cb.checkout_output()?
+                {
+                    let input = ImageBuf::from_layer(&in_layer);
+                    self.do_render(
+                        in_data,
+                        &input,
+                        vectors.as_ref(),
+                        &mut out_layer,
+                        plugin.params,
+                    )?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                if vector_layer {
+                    cb.checkin_layer_pixels(MOTION_CHECKOUT_ID as u32)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    fn do_render(
+        &self,
+        in_data: InData,
+        input: &ImageBuf,
+        vectors: Option<&ImageBuf>,
+        out_layer: &mut Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 探索半径とベクトルの倍率はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let settings = DenoiseParams {
+            strength: params.get(Params::Strength)?.as_float_slider()?.value() as f32,
+            noise_threshold: params
+                .get(Params::NoiseThreshold)?
+                .as_float_slider()?
+                .value() as f32,
+            motion: motion(params)?,
+            block_size: params.get(Params::BlockSize)?.as_slider()?.value() as usize,
+            search_radius: ds
+                .length(params.get(Params::SearchRadius)?.as_float_slider()?.value() as f32),
+            vector_scale: ds
+                .length(params.get(Params::VectorScale)?.as_float_slider()?.value() as f32),
+        };
+
+        let key = HistoryKey {
+            width: input.width,
+            height: input.height,
+            downsample: ds,
+            time_step: in_data.time_step(),
+        };
+        let time = in_data.current_time();
+
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let state = denoise(input, history.previous(key, time), vectors, &settings);
+        history.store(key, time, state.clone());
+        drop(history);
+
+        to_straight(&state).write_to_layer(out_layer)?;
+
+        Ok(())
+    }
+}
+
+/// Render geometry an accumulated result is only valid for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct HistoryKey {
+    width: usize,
+    height: usize,
+    downsample: Downsample,
+    time_step: i32,
+}
+
+/// Recently rendered results (premultiplied), newest last.
+#[derive(Default)]
+struct History {
+    key: Option<HistoryKey>,
+    frames: VecDeque<(i32, ImageBuf)>,
+}
+
+impl History {
+    fn clear(&mut self) {
+        self.key = None;
+        self.frames.clear();
+    }
+
+    /// Result of the frame one `time_step` before `time`, if it was rendered with `key`.
+    fn previous(&self, key: HistoryKey, time: i32) -> Option<&ImageBuf> {
+        if self.key != Some(key) {
+            return None;
+        }
+        let previous = time.checked_sub(key.time_step)?;
+        self.frames
+            .iter()
+            .find(|(t, _)| *t == previous)
+            .map(|(_, image)| image)
+    }
+
+    fn store(&mut self, key: HistoryKey, time: i32, state: ImageBuf) {
+        if self.key != Some(key) {
+            self.clear();
+            self.key = Some(key);
+        }
+        self.frames.retain(|(t, _)| *t != time);
+        self.frames.push_back((time, state));
+        while self.frames.len() > HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DenoiseParams {
+    /// Weight of the motion-compensated history.
+    pub strength: f32,
+    /// Largest per-channel difference from the current frame the history may keep.
+    pub noise_threshold: f32,
+    pub motion: Motion,
+    pub block_size: usize,
+    /// Largest block displacement in render pixels.
+    pub search_radius: f32,
+    /// Render pixels per unit of the decoded motion vector.
+    pub vector_scale: f32,
+}
+
+/// Premultiplied copy of `image` with non-finite channels zeroed.
+fn sanitize(image: &ImageBuf) -> ImageBuf {
+    let mut out = sampling::premultiply(image);
+    for px in out.data.iter_mut() {
+        *px = PixelF32 {
+            red: finite_or(px.red, 0.0),
+            green: finite_or(px.green, 0.0),
+            blue: finite_or(px.blue, 0.0),
+            alpha: finite_or(px.alpha, 0.0).clamp(0.0, 1.0),
+        };
+    }
+    out
+}
+
+/// Straight-alpha copy of a premultiplied result.
+fn to_straight(state: &ImageBuf) -> ImageBuf {
+    let mut out = state.clone();
+    for px in out.data.iter_mut() {
+        *px = sampling::unpremultiply(*px);
+    }
+    out
+}
+
+/// Premultiplied luminance per pixel, the block matching signal.
+fn luma(image: &ImageBuf) -> Vec<f32> {
+    image.data.iter().map(|&px| luminance(px)).collect()
+}
+
+/// Integer displacement (`dx`, `dy`) per block such that the block at `p` in
+/// `current` best matches `p + (dx, dy)` in `previous` (sum of absolute differences).
+fn match_blocks(
+    current: &[f32],
+    previous: &[f32],
+    width: usize,
+    height: usize,
+    block: usize,
+    radius: i32,
+) -> Vec<(i32, i32)> {
+    let (bw, bh) = (width.div_ceil(block), height.div_ceil(block));
+    let mut out = Vec::with_capacity(bw * bh);
+    for by in 0..bh {
+        for bx in 0..bw {
+            let (x0, y0) = (bx * block, by * block);
+            let (x1, y1) = ((x0 + block).min(width), (y0 + block).min(height));
+            let sad = |dx: i32, dy: i32, limit: f32| {
+                let mut sum = 0.0;
+                for y in y0..y1 {
+                    let sy = (y as i32 + dy).clamp(0, height as i32 - 1) as usize;
+                    for x in x0..x1 {
+                        let sx = (x as i32 + dx).clamp(0, width as i32 - 1) as usize;
+                        sum += (current[y * width + x] - previous[sy * width + sx]).abs();
+                    }
+                    if sum >= limit {
+                        break;
+                    }
+                }
+                sum
+            };
+            // 静止を優先し、同点なら移動量の小さい候補を残す
+            let mut best = (0, 0);
+            let mut best_sad = sad(0, 0, f32::INFINITY);
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if (dx, dy) == (0, 0) {
+                        continue;
+                    }
+                    let s = sad(dx, dy, best_sad);
+                    if s < best_sad {
+                        best_sad = s;
+                        best = (dx, dy);
+                    }
+                }
+            }
+            out.push(best);
+        }
+    }
+    out
+}
+
+/// Next accumulated result (premultiplied): `input` blended with the
+/// motion-compensated `previous` result, clamped to the noise threshold.
+fn denoise(
+    input: &ImageBuf,
+    previous: Option<&ImageBuf>,
+    vectors: Option<&ImageBuf>,
+    p: &DenoiseParams,
+) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let current = sanitize(input);
+    let previous = match previous {
+        Some(previous) if previous.width == width && previous.height == height && width > 0 => {
+            previous
+        }
+        _ => return current,
+    };
+
+    let strength = finite_or(p.strength, 0.0).clamp(0.0, 1.0);
+    let threshold = finite_or(p.noise_threshold, 0.0).clamp(0.0, 1.0);
+    let block = p
+        .block_size
+        .clamp(MIN_BLOCK_SIZE as usize, MAX_BLOCK_SIZE as usize);
+    let radius = finite_or(p.search_radius, 0.0)
+        .clamp(0.0, MAX_SEARCH_RADIUS)
+        .round() as i32;
+    let scale = finite_or(p.vector_scale, 0.0).clamp(0.0, MAX_VECTOR_SCALE);
+
+    let blocks = match p.motion {
+        Motion::BlockMatching if radius > 0 => match_blocks(
+            &luma(&current),
+            &luma(previous),
+            width,
+            height,
+            block,
+            radius,
+        ),
+        _ => Vec::new(),
+    };
+    let blocks_per_row = width.div_ceil(block);
+
+    let mut output = ImageBuf::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let cur = current.data[y * width + x];
+            // 前フレームで同じ内容があった位置から履歴を取り出す
+            let hist = match p.motion {
+                Motion::BlockMatching if !blocks.is_empty() => {
+                    let (dx, dy) = blocks[(y / block) * blocks_per_row + x / block];
+                    previous.get_clamped(x as isize + dx as isize, y as isize + dy as isize)
+                }
+                Motion::VectorLayer => match vectors {
+                    Some(map) => {
+                        let (vx, vy) = flow::vector_at(map, x, y, width, height, scale);
+                        sampling::bilinear(previous, x as f32 - vx, y as f32 - vy)
+                    }
+                    None => previous.data[y * width + x],
+                },
+                _ => previous.data[y * width + x],
+            };
+            // 閾値を超える差は本当の変化とみなし、履歴を現在値の近傍に抑える
+            let blend = |c: f32, h: f32| c + strength * (h - c).clamp(-threshold, threshold);
+            output.set(
+                x,
+                y,
+                PixelF32 {
+                    red: blend(cur.red, hist.red),
+                    green: blend(cur.green, hist.green),
+                    blue: blend(cur.blue, hist.blue),
+                    alpha: blend(cur.alpha, hist.alpha).clamp(0.0, 1.0),
+                },
+            );
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no access to other frames, so on odd frames the previous
+/// result is simulated by the input shifted one pixel to the right, and the
+/// input itself stands in for the motion layer.
+pub struct TemporalDenoise;
+
+impl RenderCore for TemporalDenoise {
+    type Params = DenoiseParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        DenoiseParams {
+            strength: rng.slider(0.0, 1.0),
+            noise_threshold: rng.slider(0.0, 1.0),
+            motion: match rng.popup(1, 3) {
+                1 => Motion::Off,
+                3 => Motion::VectorLayer,
+                _ => Motion::BlockMatching,
+            },
+            block_size: rng.popup(MIN_BLOCK_SIZE, MAX_BLOCK_SIZE) as usize,
+            search_radius: rng.slider(0.0, MAX_SEARCH_RADIUS),
+            vector_scale: rng.slider(0.0, MAX_VECTOR_SCALE),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let previous = (frame % 2 == 1).then(|| {
+            let mut shifted = ImageBuf::new(input.width, input.height);
+            for y in 0..input.height {
+                for x in 0..input.width {
+                    shifted.set(x, y, input.get_clamped(x as isize - 1, y as isize));
+                }
+            }
+            sanitize(&shifted)
+        });
+        let vectors = (frame % 2 == 1).then_some(input);
+        let state = denoise(input, previous.as_ref(), vectors, params);
+        *output = to_straight(&state);
+    }
+}
@@ -0,0 +1,435 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::ToPixel;
+use utils::color::luminance;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Mode,
+    MapLayer,
+    InvertMap,
+    MaxDelay,
+    TimeSamples,
+    Interpolation,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+/// State handed from SmartPreRender to SmartRender.
+struct PreRender {
+    /// Whether the map layer was checked out and has to be checked in again.
+    map_checked_out: bool,
+}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str =
+    "Slit-scan time displacement driven by a gradient or a map layer's luminance.";
+
+/// Upper bound of delayed frames checked out per render.
+const MAX_TIME_SAMPLES: usize = 16;
+/// Checkout id of the map layer (frames use `0..MAX_TIME_SAMPLES`).
+const MAP_CHECKOUT_ID: usize = MAX_TIME_SAMPLES;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapMode {
+    Horizontal,
+    Vertical,
+    Radial,
+    Layer,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Interpolation {
+    Nearest,
+    Linear,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Mode: Map Layer shows the layer picker (see update_map_visibility)
+        params.add_with_flags(
+            Params::Mode,
+            tr("Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Horizontal"),
+                    tr("Vertical"),
+                    tr("Radial"),
+                    tr("Map Layer"),
+                ]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        params.add(Params::MapLayer, tr("Map Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::InvertMap,
+            tr("Invert Map"),
+            CheckBoxDef::setup(|d| {
+                d.set_default(false);
+            }),
+        )?;
+
+        // Max Delay: frames looked back where the map is white
+        params.add(
+            Params::MaxDelay,
+            tr("Max Delay"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(600.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(60.0);
+                d.set_default(10.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Time Samples: distinct frames checked out between 0 and Max Delay
+        params.add(
+            Params::TimeSamples,
+            tr("Time Samples"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(2);
+                d.set_valid_max(MAX_TIME_SAMPLES as i32);
+                d.set_slider_min(2);
+                d.set_slider_max(MAX_TIME_SAMPLES as i32);
+                d.set_default(8);
+            }),
+        )?;
+
+        params.add(
+            Params::Interpolation,
+            tr("Interpolation"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Nearest"), tr("Linear")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_TimeDisplacement - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_map_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Mode =>
+            {
+                update_map_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では過去フレームを取得できないので現在フレームのみ
+                let frames = [ImageBuf::from_layer(&in_layer)];
+                self.do_render(&frames, None, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+                let samples = time_samples(params)?;
+                let max_delay = params.get(Params::MaxDelay)?.as_float_slider()?.value();
+
+                // 遅延 0 から Max Delay までを等間隔にチェックアウト
+                for k in 0..samples {
+                    let delay = max_delay * k as f64 / (samples - 1) as f64;
+                    let time = in_data.current_time()
+                        - (delay * in_data.time_step() as f64).round() as i32;
+                    let in_result = extra.callbacks().checkout_layer(
+                        0,
+                        k as i32,
+                        &req,
+                        time,
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    )?;
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                }
+
+                // マップレイヤーが未指定のときはチェックアウトに失敗するので、
+                // 成功したかどうかを SmartRender に渡す
+                let mut map_checked_out = false;
+                if map_mode(params)? == MapMode::Layer
+                    && let Some(index) = params.index(Params::MapLayer)
+                {
+                    map_checked_out = extra
+                        .callbacks()
+                        .checkout_layer(
+                            index as i32,
+                            MAP_CHECKOUT_ID as i32,
+                            &req,
+                            in_data.current_time(),
+                            in_data.time_step(),
+                            in_data.time_scale(),
+                        )
+                        .is_ok();
+                }
+                extra.set_pre_render_data(PreRender { map_checked_out });
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let samples = time_samples(params)?;
+
+                let mut frames = Vec::with_capacity(samples);
+                for k in 0..samples {
+                    if let Some(layer) = cb.checkout_layer_pixels(k as u32)? {
+                        frames.push(ImageBuf::from_layer(&layer));
+                    }
+                }
+                let map_checked_out = extra
+                    .pre_render_data::<PreRender>()
+                    .is_some_and(|p| p.map_checked_out);
+                let map = if map_checked_out {
+                    cb.checkout_layer_pixels(MAP_CHECKOUT_ID as u32)?
+                        .map(|layer| ImageBuf::from_layer(&layer))
+                } else {
+                    None
+                };
+
+                if frames.len() == samples
+                    && let Some(out_layer) = cb.checkout_output()?
+                {
+                    self.do_render(&frames, map.as_ref(), out_layer, params)?;
+                }
+
+                for k in 0..samples {
+                    cb.checkin_layer_pixels(k as u32)?;
+                }
+                if map_checked_out {
+                    cb.checkin_layer_pixels(MAP_CHECKOUT_ID as u32)?;
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn map_mode(params: &Parameters<Params>) -> Result<MapMode, Error> {
+    Ok(match params.get(Params::Mode)?.as_popup()?.value() {
+        2 => MapMode::Vertical,
+        3 => MapMode::Radial,
+        4 => MapMode::Layer,
+        _ => MapMode::Horizontal,
+    })
+}
+
+fn time_samples(params: &Parameters<Params>) -> Result<usize, Error> {
+    let n = params.get(Params::TimeSamples)?.as_slider()?.value() as usize;
+    Ok(n.clamp(2, MAX_TIME_SAMPLES))
+}
+
+fn update_map_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let layer = map_mode(params)? == MapMode::Layer;
+    ui::set_param_visible(in_data, params, Params::MapLayer, layer)?;
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        frames: &[ImageBuf],
+        map: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let progress_final = out_layer.height() as i32;
+
+        let settings = TimeDisplacementParams {
+            mode: map_mode(params)?,
+            invert: params.get(Params::InvertMap)?.as_checkbox()?.value(),
+            interpolation: match params.get(Params::Interpolation)?.as_popup()?.value() {
+                1 => Interpolation::Nearest,
+                _ => Interpolation::Linear,
+            },
+        };
+        let out_world_type = out_layer.world_type();
+
+        out_layer.iterate(0, progress_final, None, |x, y, mut dst| {
+            let px = displace_pixel(frames, map, x as usize, y as usize, &settings);
+
+            match out_world_type {
+                ae::aegp::WorldType::U8 => dst.set_from_u8(px.to_pixel8()),
+                ae::aegp::WorldType::U15 => dst.set_from_u16(px.to_pixel16()),
+                ae::aegp::WorldType::F32 | ae::aegp::WorldType::None => {
+                    dst.set_from_f32(px);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TimeDisplacementParams {
+    pub mode: MapMode,
+    pub invert: bool,
+    pub interpolation: Interpolation,
+}
+
+/// Delay amount (0..1) at (`x`, `y`) of a `width` x `height` frame.
+fn map_value(
+    map: Option<&ImageBuf>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    p: &TimeDisplacementParams,
+) -> f32 {
+    let norm = |v: usize, n: usize| {
+        if n > 1 {
+            v as f32 / (n - 1) as f32
+        } else {
+            0.0
+        }
+    };
+    let v = match p.mode {
+        MapMode::Horizontal => norm(x, width),
+        MapMode::Vertical => norm(y, height),
+        MapMode::Radial => {
+            let (dx, dy) = (norm(x, width) - 0.5, norm(y, height) - 0.5);
+            (dx.hypot(dy) * std::f32::consts::SQRT_2).min(1.0)
+        }
+        MapMode::Layer => match map {
+            // マップレイヤーのサイズが異なる場合は引き伸ばして対応させる
+            Some(map) if !map.is_empty() => {
+                let mx = x * map.width / width.max(1);
+                let my = y * map.height / height.max(1);
+                luminance(map.get_clamped(mx as isize, my as isize))
+            }
+            _ => 0.0,
+        },
+    };
+    let v = if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+    if p.invert { 1.0 - v } else { v }
+}
+
+/// Pixel at (`x`, `y`) taken from `frames` (index = increasing delay).
+fn displace_pixel(
+    frames: &[ImageBuf],
+    map: Option<&ImageBuf>,
+    x: usize,
+    y: usize,
+    p: &TimeDisplacementParams,
+) -> PixelF32 {
+    let Some(current) = frames.first() else {
+        return utils::image::TRANSPARENT;
+    };
+    let t = map_value(map, x, y, current.width, current.height, p);
+    let f = t * (frames.len() - 1) as f32;
+    let at = |k: usize| frames[k.min(frames.len() - 1)].get(x as isize, y as isize);
+
+    match p.interpolation {
+        Interpolation::Nearest => at(f.round() as usize),
+        Interpolation::Linear => {
+            let k = f.floor() as usize;
+            let s = f - k as f32;
+            let (a, b) = (at(k), at(k + 1));
+            PixelF32 {
+                red: a.red + (b.red - a.red) * s,
+                green: a.green + (b.green - a.green) * s,
+                blue: a.blue + (b.blue - a.blue) * s,
+                alpha: a.alpha + (b.alpha - a.alpha) * s,
+            }
+        }
+    }
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no access to other frames, so delayed frames are simulated
+/// by shifting the input one pixel to the right per delay step.
+pub struct TimeDisplacement;
+
+impl RenderCore for TimeDisplacement {
+    type Params = TimeDisplacementParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        TimeDisplacementParams {
+            mode: match rng.popup(1, 4) {
+                2 => MapMode::Vertical,
+                3 => MapMode::Radial,
+                4 => MapMode::Layer,
+                _ => MapMode::Horizontal,
+            },
+            invert: rng.chance(0.5),
+            interpolation: if rng.chance(0.5) {
+                Interpolation::Nearest
+            } else {
+                Interpolation::Linear
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let samples = 2 + frame as usize % (MAX_TIME_SAMPLES - 1);
+        let frames: Vec<ImageBuf> = (0..samples)
+            .map(|k| {
+                let mut shifted = ImageBuf::new(input.width, input.height);
+                for y in 0..input.height {
+                    for x in 0..input.width {
+                        let px = input.get_clamped(x as isize - k as isize, y as isize);
+                        shifted.set(x, y, px);
+                    }
+                }
+                shifted
+            })
+            .collect();
+
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let px = displace_pixel(&frames, Some(input), x, y, params);
+                output.set(x, y, px);
+            }
+        }
+    }
+}
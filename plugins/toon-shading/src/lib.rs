@@ -0,0 +1,572 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::Oklab;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::i18n;
+use utils::image::ImageBuf;
+use utils::normal::{self, NormalFormat};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Bands,
+    Softness,
+    TintMode,
+    ShadowTint,
+    HighlightTint,
+    Band1Tint,
+    Band2Tint,
+    Band3Tint,
+    Band4Tint,
+    Band5Tint,
+    Band6Tint,
+    Band7Tint,
+    Band8Tint,
+    Band9Tint,
+    Band10Tint,
+    Band11Tint,
+    Band12Tint,
+    Band13Tint,
+    Band14Tint,
+    Band15Tint,
+    Band16Tint,
+    TintAmount,
+    NormalLayer,
+    NormalFormat,
+    LightDirection,
+    LightElevation,
+    LightInfluence,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Cel-shades the layer into lighting bands.";
+
+/// Checkout id of the normal map layer (the input uses 0).
+const NORMAL_CHECKOUT_ID: i32 = 1;
+const MIN_BANDS: i32 = 2;
+const MAX_BANDS: i32 = 16;
+
+/// Tint parameter of each band, darkest first.
+const BAND_TINTS: [Params; MAX_BANDS as usize] = [
+    Params::Band1Tint,
+    Params::Band2Tint,
+    Params::Band3Tint,
+    Params::Band4Tint,
+    Params::Band5Tint,
+    Params::Band6Tint,
+    Params::Band7Tint,
+    Params::Band8Tint,
+    Params::Band9Tint,
+    Params::Band10Tint,
+    Params::Band11Tint,
+    Params::Band12Tint,
+    Params::Band13Tint,
+    Params::Band14Tint,
+    Params::Band15Tint,
+    Params::Band16Tint,
+];
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add_with_flags(
+            Params::Bands,
+            tr("Bands"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_BANDS);
+                d.set_valid_max(MAX_BANDS);
+                d.set_slider_min(MIN_BANDS);
+                d.set_slider_max(8);
+                d.set_default(3);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Softness: width of each band edge as a fraction of one band
+        params.add(
+            Params::Softness,
+            tr("Softness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.1);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Tint Mode: Ramp blends Shadow → Highlight Tint, Per Band uses Band N Tint
+        params.add_with_flags(
+            Params::TintMode,
+            tr("Tint Mode"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Ramp"), tr("Per Band")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Shadow / Highlight Tint: blended across the bands from darkest to lightest
+        params.add(
+            Params::ShadowTint,
+            tr("Shadow Tint"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 90,
+                    green: 96,
+                    blue: 170,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::HighlightTint,
+            tr("Highlight Tint"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 246,
+                    blue: 228,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        // Band N Tint: white leaves the band untinted
+        for (n, id) in BAND_TINTS.into_iter().enumerate() {
+            params.add(
+                id,
+                &i18n::fill(tr("Band {} Tint"), n + 1),
+                ColorDef::setup(|d| {
+                    d.set_default(Pixel8 {
+                        red: 255,
+                        green: 255,
+                        blue: 255,
+                        alpha: 255,
+                    });
+                }),
+            )?;
+        }
+
+        params.add(
+            Params::TintAmount,
+            tr("Tint Amount"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        // Normal Layer: when set, the bands follow the lighting of the normal map
+        params.add(Params::NormalLayer, tr("Normal Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::NormalFormat,
+            tr("Normal Format"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("OpenGL (Y+)"), tr("DirectX (Y-)")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LightDirection,
+            tr("Light Direction"),
+            AngleDef::setup(|d| {
+                d.set_default(-45.0);
+            }),
+        )?;
+
+        params.add(
+            Params::LightElevation,
+            tr("Light Elevation"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(90.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(90.0);
+                d.set_default(45.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Light Influence: 0 bands the flat lightness, 1 fully multiplies it by N·L
+        params.add(
+            Params::LightInfluence,
+            tr("Light Influence"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(1.0);
+                d.set_default(1.0);
+                d.set_precision(2);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_ToonShading - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_tint_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if matches!(
+                    params.type_at(param_index),
+                    Params::Bands | Params::TintMode
+                ) =>
+            {
+                update_tint_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender では法線レイヤーをパラメータから直接取得する
+                let normals = params
+                    .checkout_at(
+                        Params::NormalLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_layer, normals.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::NormalLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        NORMAL_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let normals = cb
+                    .checkout_layer_pixels(NORMAL_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, normals.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(NORMAL_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn update_tint_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let per_band = params.get(Params::TintMode)?.as_popup()?.value() == 2;
+    let bands = params.get(Params::Bands)?.as_slider()?.value() as usize;
+    let mut visibility = vec![
+        (Params::ShadowTint, !per_band),
+        (Params::HighlightTint, !per_band),
+    ];
+    for (i, id) in BAND_TINTS.into_iter().enumerate() {
+        visibility.push((id, per_band && i < bands));
+    }
+    for (id, visible) in visibility {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        normals: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let color = |id| -> Result<[f32; 3], Error> {
+            let c = params.get(id)?.as_color()?.float_value()?;
+            Ok([c.red, c.green, c.blue])
+        };
+        let shadow_tint = color(Params::ShadowTint)?;
+        let highlight_tint = color(Params::HighlightTint)?;
+        let mut band_tints = [[1.0; 3]; MAX_BANDS as usize];
+        for (tint, id) in band_tints.iter_mut().zip(BAND_TINTS) {
+            *tint = color(id)?;
+        }
+
+        let settings = ToonParams {
+            bands: params.get(Params::Bands)?.as_slider()?.value() as u32,
+            softness: params.get(Params::Softness)?.as_float_slider()?.value() as f32,
+            tint_mode: match params.get(Params::TintMode)?.as_popup()?.value() {
+                2 => TintMode::PerBand,
+                _ => TintMode::Ramp,
+            },
+            shadow_tint,
+            highlight_tint,
+            band_tints,
+            tint_amount: params.get(Params::TintAmount)?.as_float_slider()?.value() as f32,
+            normal_format: match params.get(Params::NormalFormat)?.as_popup()?.value() {
+                2 => NormalFormat::DirectX,
+                _ => NormalFormat::OpenGl,
+            },
+            light_direction: params
+                .get(Params::LightDirection)?
+                .as_angle()?
+                .value()
+                .to_radians(),
+            light_elevation: (params
+                .get(Params::LightElevation)?
+                .as_float_slider()?
+                .value() as f32)
+                .to_radians(),
+            light_influence: params
+                .get(Params::LightInfluence)?
+                .as_float_slider()?
+                .value() as f32,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        toon_shade(&input, normals, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TintMode {
+    /// Shadow tint on the darkest band blending to the highlight tint on the lightest.
+    Ramp,
+    /// Each band takes its own tint.
+    PerBand,
+}
+
+#[derive(Debug)]
+pub struct ToonParams {
+    pub bands: u32,
+    /// Band edge width as a fraction of one band.
+    pub softness: f32,
+    pub tint_mode: TintMode,
+    /// Encoded tint of the darkest band in `Ramp` mode.
+    pub shadow_tint: [f32; 3],
+    /// Encoded tint of the lightest band in `Ramp` mode.
+    pub highlight_tint: [f32; 3],
+    /// Encoded tint of each band, darkest first, in `PerBand` mode.
+    pub band_tints: [[f32; 3]; MAX_BANDS as usize],
+    pub tint_amount: f32,
+    pub normal_format: NormalFormat,
+    /// Compass direction of the light (0 = up, clockwise), radians.
+    pub light_direction: f32,
+    /// Light angle above the layer plane, radians.
+    pub light_elevation: f32,
+    pub light_influence: f32,
+}
+
+type Vec3 = [f32; 3];
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Surface normal of an encoded normal-map pixel in layer space (y down, z
+/// towards the viewer); transparent or degenerate pixels face the viewer.
+fn layer_normal(px: PixelF32, format: NormalFormat) -> Vec3 {
+    normal::decode(px, format).map_or([0.0, 0.0, 1.0], |[x, y, z]| [x, -y, z])
+}
+
+/// Smooth 0→1 ramp over `edge ± width / 2`; a hard step when `width` is 0.
+fn soft_step(edge: f32, width: f32, v: f32) -> f32 {
+    if width <= 0.0 {
+        return if v >= edge { 1.0 } else { 0.0 };
+    }
+    let t = ((v - edge) / width + 0.5).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Continuous band index (0..bands-1) of `shade` (0..1), ramping between
+/// neighbouring bands over `softness` of a band around each edge.
+fn band_index(shade: f32, bands: f32, softness: f32) -> f32 {
+    let v = shade.clamp(0.0, 1.0) * bands;
+    let base = v.floor().min(bands - 1.0);
+    let f = v - base;
+    // 下側と上側の境界それぞれの寄与を足す (softness 0 なら floor と一致)
+    let index = base - 1.0 + soft_step(0.0, softness, f) + soft_step(1.0, softness, f);
+    index.clamp(0.0, bands - 1.0)
+}
+
+fn toon_shade(input: &ImageBuf, normals: Option<&ImageBuf>, p: &ToonParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    let bands = p.bands.clamp(MIN_BANDS as u32, MAX_BANDS as u32) as f32;
+    let softness = finite_or(p.softness, 0.0).clamp(0.0, 1.0);
+    let amount = finite_or(p.tint_amount, 0.0).clamp(0.0, 1.0);
+    let influence = finite_or(p.light_influence, 0.0).clamp(0.0, 1.0);
+    let tint = |c: [f32; 3]| Oklab::from_srgb(c.map(|v| finite_or(v, 0.0).clamp(0.0, 1.0)));
+    let (shadow, highlight) = (tint(p.shadow_tint), tint(p.highlight_tint));
+    let band_tints = p.band_tints.map(tint);
+    let (s, c) = finite_or(p.light_direction, 0.0).sin_cos();
+    let (es, ec) = finite_or(p.light_elevation, 0.0).sin_cos();
+    let light = [s * ec, -c * ec, es];
+
+    for y in 0..height {
+        for x in 0..width {
+            let px = input.get(x as isize, y as isize);
+            let rgb = [px.red, px.green, px.blue].map(|v| finite_or(v, 0.0).max(0.0));
+            let lab = Oklab::from_srgb(rgb);
+
+            // 法線マップがあれば N·L で明度を落としてから段に分ける
+            let lambert = match normals {
+                Some(map) => {
+                    let n = layer_normal(map.get(x as isize, y as isize), p.normal_format);
+                    dot(n, light).max(0.0)
+                }
+                None => 1.0,
+            };
+            let shade = finite_or(lab.l, 0.0) * (1.0 + (lambert - 1.0) * influence);
+            let index = band_index(shade, bands, softness);
+
+            // 段の中央の明度に置き換え、段の色味を a/b のずれと明度の倍率で乗せる
+            let tint = match p.tint_mode {
+                TintMode::Ramp => shadow.lerp(highlight, index / (bands - 1.0)),
+                // 境界のぼかし中は隣り合う段の色味を混ぜる
+                TintMode::PerBand => {
+                    let lower = index.floor();
+                    let upper = (lower + 1.0).min(bands - 1.0);
+                    band_tints[lower as usize].lerp(band_tints[upper as usize], index - lower)
+                }
+            };
+            let band_l = (index + 0.5) / bands;
+            let shaded = Oklab {
+                l: band_l * (1.0 + (tint.l - 1.0) * amount),
+                a: finite_or(lab.a, 0.0) + tint.a * amount,
+                b: finite_or(lab.b, 0.0) + tint.b * amount,
+            };
+            let out = shaded.to_srgb();
+            // 色域外に出た負の値は切り捨てる
+            let clean = |v: f32| if v.is_nan() { 0.0 } else { v.max(0.0) };
+            output.set(
+                x,
+                y,
+                PixelF32 {
+                    red: clean(out[0]),
+                    green: clean(out[1]),
+                    blue: clean(out[2]),
+                    alpha: px.alpha,
+                },
+            );
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so the input itself is used as the normal
+/// map on odd frames.
+pub struct ToonShading;
+
+impl RenderCore for ToonShading {
+    type Params = ToonParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ToonParams {
+            bands: rng.popup(MIN_BANDS, MAX_BANDS) as u32,
+            softness: rng.slider(0.0, 1.0),
+            tint_mode: if rng.chance(0.5) {
+                TintMode::Ramp
+            } else {
+                TintMode::PerBand
+            },
+            shadow_tint: [rng.unit(), rng.unit(), rng.unit()],
+            highlight_tint: [rng.unit(), rng.unit(), rng.unit()],
+            band_tints: std::array::from_fn(|_| [rng.unit(), rng.unit(), rng.unit()]),
+            tint_amount: rng.slider(0.0, 1.0),
+            normal_format: if rng.chance(0.5) {
+                NormalFormat::OpenGl
+            } else {
+                NormalFormat::DirectX
+            },
+            light_direction: rng.slider(-3600.0, 3600.0).to_radians(),
+            light_elevation: rng.slider(0.0, 90.0).to_radians(),
+            light_influence: rng.slider(0.0, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let normals = (frame % 2 == 1).then_some(input);
+        *output = toon_shade(input, normals, params);
+    }
+}
@@ -0,0 +1,443 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::normal::{self, NormalFormat};
+use utils::sampling::{self, Downsample, Edge, Filter};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    TextureLayer,
+    NormalFormat,
+    Scale,
+    BlendSharpness,
+    XOffset,
+    YOffset,
+    ZOffset,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Projects a texture onto a normal map with triplanar blending.";
+
+/// Checkout id of the texture layer (the input uses 0).
+const TEXTURE_CHECKOUT_ID: i32 = 1;
+/// Cell size of the preview checker used without a texture layer
+/// (full-resolution pixels).
+const CHECKER_CELL: f32 = 32.0;
+/// Steepest slope (depth per pixel) taken from the normal map when
+/// reconstructing depth; keeps grazing normals from blowing up.
+const MAX_SLOPE: f32 = 8.0;
+const MAX_SHARPNESS: f32 = 32.0;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Texture Layer: tiled over every projection; without one a checker is projected
+        params.add(Params::TextureLayer, tr("Texture Layer"), LayerDef::new())?;
+
+        params.add(
+            Params::NormalFormat,
+            tr("Normal Format"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("OpenGL (Y+)"), tr("DirectX (Y-)")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Scale: texture size in percent, 100 = one texture pixel per layer pixel
+        params.add(
+            Params::Scale,
+            tr("Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Blend Sharpness: exponent on the normal axes, higher = narrower seams
+        params.add(
+            Params::BlendSharpness,
+            tr("Blend Sharpness"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(1.0);
+                d.set_valid_max(MAX_SHARPNESS);
+                d.set_slider_min(1.0);
+                d.set_slider_max(16.0);
+                d.set_default(4.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // X / Y / Z Offset: moves the surface through the texture along each axis, in pixels
+        for (id, name) in [
+            (Params::XOffset, tr("X Offset")),
+            (Params::YOffset, tr("Y Offset")),
+            (Params::ZOffset, tr("Z Offset")),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-10000.0);
+                    d.set_valid_max(10000.0);
+                    d.set_slider_min(-500.0);
+                    d.set_slider_max(500.0);
+                    d.set_default(0.0);
+                    d.set_precision(1);
+                }),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_TriplanarProjector - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではテクスチャレイヤーをパラメータから直接取得する
+                let texture = params
+                    .checkout_at(
+                        Params::TextureLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, texture.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::TextureLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        TEXTURE_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let texture = cb
+                    .checkout_layer_pixels(TEXTURE_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, texture.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(TEXTURE_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        texture: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // オフセットはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = TriplanarParams {
+            normal_format: match params.get(Params::NormalFormat)?.as_popup()?.value() {
+                2 => NormalFormat::DirectX,
+                _ => NormalFormat::OpenGl,
+            },
+            scale: slider(Params::Scale)? / 100.0,
+            sharpness: slider(Params::BlendSharpness)?,
+            offset: [
+                ds.length(slider(Params::XOffset)?),
+                ds.length(slider(Params::YOffset)?),
+                ds.length(slider(Params::ZOffset)?),
+            ],
+            checker_cell: ds.length(CHECKER_CELL),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        project(&input, texture, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TriplanarParams {
+    pub normal_format: NormalFormat,
+    /// Texture scale factor.
+    pub scale: f32,
+    /// Blend weight exponent, 1..32.
+    pub sharpness: f32,
+    /// Render pixels along layer right, layer down and towards the viewer.
+    pub offset: [f32; 3],
+    /// Render pixels per cell of the fallback checker.
+    pub checker_cell: f32,
+}
+
+type Vec3 = [f32; 3];
+
+const FLAT: Vec3 = [0.0, 0.0, 1.0];
+
+/// Depth towards the viewer integrated from `slopes` along one line, as the
+/// mean of a forward and a backward pass that both start at zero.
+fn integrate(slopes: &[f32], depth: &mut [f32]) {
+    let n = slopes.len();
+    if n == 0 {
+        return;
+    }
+    let mut forward = 0.0;
+    depth[0] = 0.0;
+    for i in 1..n {
+        forward += 0.5 * (slopes[i - 1] + slopes[i]);
+        depth[i] = forward;
+    }
+    let mut backward = 0.0;
+    depth[n - 1] *= 0.5;
+    for i in (0..n - 1).rev() {
+        backward -= 0.5 * (slopes[i] + slopes[i + 1]);
+        depth[i] = 0.5 * (depth[i] + backward);
+    }
+}
+
+/// Checker of `cell`-pixel squares, two cells per side.
+fn checker(cell: f32) -> ImageBuf {
+    let cell = (finite_or(cell, CHECKER_CELL).round() as usize).clamp(1, 1024);
+    let mut image = ImageBuf::new(cell * 2, cell * 2);
+    for y in 0..cell * 2 {
+        for x in 0..cell * 2 {
+            let v = if (x / cell + y / cell).is_multiple_of(2) {
+                0.8
+            } else {
+                0.2
+            };
+            image.set(
+                x,
+                y,
+                PixelF32 {
+                    red: v,
+                    green: v,
+                    blue: v,
+                    alpha: 1.0,
+                },
+            );
+        }
+    }
+    image
+}
+
+pub fn project(input: &ImageBuf, texture: Option<&ImageBuf>, p: &TriplanarParams) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+    let texture = match texture {
+        Some(texture) if !texture.is_empty() => sampling::premultiply(texture),
+        _ => checker(p.checker_cell),
+    };
+    let scale = finite_or(p.scale, 1.0).clamp(0.01, 10.0);
+    let sharpness = finite_or(p.sharpness, 4.0).clamp(1.0, MAX_SHARPNESS);
+    let offset = p.offset.map(|v| finite_or(v, 0.0).clamp(-1e5, 1e5));
+
+    let normals: Vec<Vec3> = input
+        .data
+        .iter()
+        .map(|&px| normal::decode(px, p.normal_format).unwrap_or(FLAT))
+        .collect();
+
+    // 法線の傾きを行・列ごとに積分して奥行きを作る。X 投影は横方向、
+    // Y 投影は縦方向の奥行きだけを使うので、行間・列間のずれは目立たない
+    let slope = |n: Vec3, axis: f32| (axis / n[2].max(1e-3)).clamp(-MAX_SLOPE, MAX_SLOPE);
+    let mut depth_x = vec![0.0f32; w * h];
+    let mut line = Vec::with_capacity(w.max(h));
+    for y in 0..h {
+        line.clear();
+        // 右を向く面 (x > 0) ほど右へ行くと奥へ下がる
+        line.extend(normals[y * w..(y + 1) * w].iter().map(|&n| slope(n, -n[0])));
+        integrate(&line, &mut depth_x[y * w..(y + 1) * w]);
+    }
+    let mut depth_y = vec![0.0f32; w * h];
+    let mut column = vec![0.0f32; h];
+    for x in 0..w {
+        line.clear();
+        // 上を向く面 (y > 0) ほど下へ行くと手前へ出る
+        line.extend((0..h).map(|y| slope(normals[y * w + x], normals[y * w + x][1])));
+        integrate(&line, &mut column);
+        for (y, &z) in column.iter().enumerate() {
+            depth_y[y * w + x] = z;
+        }
+    }
+
+    let (cx, cy) = (w as f32 * 0.5, h as f32 * 0.5);
+    let (tx, ty) = (texture.width as f32 * 0.5, texture.height as f32 * 0.5);
+    let fetch = |u: f32, v: f32| {
+        sampling::sample_edge(
+            &texture,
+            u / scale + tx - 0.5,
+            v / scale + ty - 0.5,
+            Filter::Bilinear,
+            (Edge::Repeat, Edge::Repeat),
+        )
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let src = input.data[i];
+            let alpha = finite_or(src.alpha, 0.0).clamp(0.0, 1.0);
+            if alpha <= 0.0 {
+                continue;
+            }
+            let n = normals[i];
+            let px = x as f32 + 0.5 - cx + offset[0];
+            let py = y as f32 + 0.5 - cy + offset[1];
+            let zx = depth_x[i] + offset[2];
+            let zy = depth_y[i] + offset[2];
+
+            let weights = n.map(|c| c.abs().powf(sharpness));
+            let total = weights[0] + weights[1] + weights[2];
+            let weights = if total > 1e-12 {
+                weights.map(|v| v / total)
+            } else {
+                FLAT
+            };
+
+            // 各投影面の向きで奥行きの符号を変え、裏側から見た鏡像にならないようにする
+            let mut sum = [0.0f32; 4];
+            for (axis, &weight) in weights.iter().enumerate() {
+                if weight <= 1e-4 {
+                    continue;
+                }
+                let s = match axis {
+                    0 => fetch(zx * n[0].signum(), py),
+                    1 => fetch(px, zy * n[1].signum()),
+                    _ => fetch(px, py),
+                };
+                sum[0] += s.red * weight;
+                sum[1] += s.green * weight;
+                sum[2] += s.blue * weight;
+                sum[3] += s.alpha * weight;
+            }
+            let px = sampling::unpremultiply(PixelF32 {
+                red: sum[0],
+                green: sum[1],
+                blue: sum[2],
+                alpha: sum[3],
+            });
+            out.data[i] = PixelF32 {
+                alpha: px.alpha * alpha,
+                ..px
+            };
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use the input itself as the
+/// texture and even frames project the checker.
+pub struct TriplanarProjector;
+
+impl RenderCore for TriplanarProjector {
+    type Params = TriplanarParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        TriplanarParams {
+            normal_format: if rng.chance(0.5) {
+                NormalFormat::OpenGl
+            } else {
+                NormalFormat::DirectX
+            },
+            scale: rng.slider(0.01, 10.0),
+            sharpness: rng.slider(1.0, MAX_SHARPNESS),
+            offset: [
+                rng.slider(-1000.0, 1000.0),
+                rng.slider(-1000.0, 1000.0),
+                rng.slider(-1000.0, 1000.0),
+            ],
+            checker_cell: rng.slider(1.0, 64.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let texture = (frame % 2 == 1).then(|| input.clone());
+        *output = project(input, texture.as_ref(), params);
+    }
+}
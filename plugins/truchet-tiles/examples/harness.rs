@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<truchet_tiles::TruchetTiles>();
+}
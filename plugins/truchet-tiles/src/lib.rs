@@ -0,0 +1,437 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::{hash, hash_unit};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, coverage};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Style,
+    TileSize,
+    LineWidth,
+    Subdivisions,
+    SubdivisionChance,
+    Seed,
+    LineColor,
+    Background,
+    BackgroundColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Generates Truchet tile patterns.";
+
+/// Upper bound of `Subdivisions`; each level halves the tile.
+const MAX_SUBDIVISIONS: i32 = 4;
+/// Hash salts keeping the split and orientation draws independent.
+const SPLIT_SALT: u32 = 0x5b1d;
+const TILE_SALT: u32 = 0x7c4e;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// Two quarter circles joining edge midpoints (Smith tiles).
+    Arcs,
+    /// One corner-to-corner diagonal per tile.
+    Diagonals,
+    /// Crossing bands with one passing under the other.
+    Weave,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Color,
+    Transparent,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Style,
+            tr("Style"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Arcs"), tr("Diagonals"), tr("Weave")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Tile Size: rounded so the layer holds a whole number of tiles
+        params.add(
+            Params::TileSize,
+            tr("Tile Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(2.0);
+                d.set_valid_max(2000.0);
+                d.set_slider_min(8.0);
+                d.set_slider_max(400.0);
+                d.set_default(64.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Line Width: percentage of the tile, so subdivided tiles draw thinner lines
+        params.add(
+            Params::LineWidth,
+            tr("Line Width"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(50.0);
+                d.set_default(12.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Subdivisions: how many times a tile may split into four
+        params.add(
+            Params::Subdivisions,
+            tr("Subdivisions"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(MAX_SUBDIVISIONS);
+                d.set_slider_min(0);
+                d.set_slider_max(MAX_SUBDIVISIONS);
+                d.set_default(0);
+            }),
+        )?;
+
+        // Subdivision Chance: probability of each split
+        params.add(
+            Params::SubdivisionChance,
+            tr("Subdivision Chance"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(30.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        params.add(
+            Params::LineColor,
+            tr("Line Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        params.add(
+            Params::Background,
+            tr("Background"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Color"), tr("Transparent")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BackgroundColor,
+            tr("Background Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 20,
+                    green: 24,
+                    blue: 40,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_TruchetTiles - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer: _,
+                out_layer,
+            } => {
+                self.do_render(in_data, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let Some(out_layer) = out_layer_opt {
+                    self.do_render(in_data, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // タイルサイズはフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let line = params.get(Params::LineColor)?.as_color()?.float_value()?;
+        let background = params
+            .get(Params::BackgroundColor)?
+            .as_color()?
+            .float_value()?;
+        let settings = TruchetParams {
+            style: match params.get(Params::Style)?.as_popup()?.value() {
+                2 => Style::Diagonals,
+                3 => Style::Weave,
+                _ => Style::Arcs,
+            },
+            tile_size: ds.length(params.get(Params::TileSize)?.as_float_slider()?.value() as f32),
+            line_width: params.get(Params::LineWidth)?.as_float_slider()?.value() as f32 / 100.0,
+            subdivisions: params.get(Params::Subdivisions)?.as_slider()?.value() as u32,
+            subdivision_chance: params
+                .get(Params::SubdivisionChance)?
+                .as_float_slider()?
+                .value() as f32
+                / 100.0,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            line_color: [line.red, line.green, line.blue],
+            background: match params.get(Params::Background)?.as_popup()?.value() {
+                2 => Background::Transparent,
+                _ => Background::Color,
+            },
+            background_color: [background.red, background.green, background.blue],
+        };
+
+        let (width, height) = (out_layer.width(), out_layer.height());
+        truchet(width, height, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TruchetParams {
+    pub style: Style,
+    /// Base tile edge in render pixels, before rounding to the layer.
+    pub tile_size: f32,
+    /// Line width as a fraction of the tile it is drawn in.
+    pub line_width: f32,
+    pub subdivisions: u32,
+    /// 0..1.
+    pub subdivision_chance: f32,
+    pub seed: u32,
+    pub line_color: [f32; 3],
+    pub background: Background,
+    pub background_color: [f32; 3],
+}
+
+/// Line coverage at (`u`, `v`) inside one tile of `scale` pixels; `bits`
+/// picks the orientation (and for weaves which band is on top).
+fn tile_coverage(style: Style, u: f32, v: f32, bits: u32, scale: f32, half: f32) -> f32 {
+    // 反転ビットで左右を入れ替えると、残りの 1 通りの向きになる
+    let u = if bits & 1 == 1 { 1.0 - u } else { u };
+    match style {
+        Style::Arcs => {
+            let d = (u.hypot(v) - 0.5)
+                .abs()
+                .min(((1.0 - u).hypot(1.0 - v) - 0.5).abs());
+            coverage(d * scale, half)
+        }
+        Style::Diagonals => coverage(
+            (u - v).abs() * std::f32::consts::FRAC_1_SQRT_2 * scale,
+            half,
+        ),
+        Style::Weave => {
+            let horizontal = (v - 0.5).abs() * scale;
+            let vertical = (u - 0.5).abs() * scale;
+            let (over, under) = if bits & 2 == 0 {
+                (horizontal, vertical)
+            } else {
+                (vertical, horizontal)
+            };
+            // 下をくぐる帯は上の帯の両脇に隙間を空けて切る
+            let gap = (half * 0.5).max(1.0);
+            let cut = coverage(over, half + gap);
+            coverage(over, half).max(coverage(under, half) * (1.0 - cut))
+        }
+    }
+}
+
+pub fn truchet(width: usize, height: usize, p: &TruchetParams) -> ImageBuf {
+    let mut out = ImageBuf::new(width, height);
+    if width == 0 || height == 0 {
+        return out;
+    }
+    // レイヤーにちょうど整数枚のタイルが収まるよう縦横それぞれ丸める
+    let size = finite_or(p.tile_size, 64.0).max(1.0);
+    let tiles = |n: usize| (n as f32 / size).round().clamp(1.0, 65536.0) as u32;
+    let (tiles_x, tiles_y) = (tiles(width), tiles(height));
+    let (tile_w, tile_h) = (
+        width as f32 / tiles_x as f32,
+        height as f32 / tiles_y as f32,
+    );
+    let line_width = finite_or(p.line_width, 0.12).clamp(0.0, 1.0);
+    let levels = p.subdivisions.min(MAX_SUBDIVISIONS as u32);
+    let chance = finite_or(p.subdivision_chance, 0.0).clamp(0.0, 1.0);
+    let color = p.line_color.map(|c| finite_or(c, 1.0));
+    let background = p.background_color.map(|c| finite_or(c, 0.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut fx = (x as f32 + 0.5) / tile_w;
+            let mut fy = (y as f32 + 0.5) / tile_h;
+            let mut scale = tile_w.min(tile_h);
+            let mut level = 0;
+            // 丸め誤差で右端・下端のタイル番号がはみ出さないよう各段の枚数で抑える
+            let cell = |f: f32, n: u32, level: u32| (f.floor() as u32).min((n << level) - 1);
+            while level < levels
+                && hash_unit(
+                    cell(fx, tiles_x, level),
+                    cell(fy, tiles_y, level),
+                    hash(p.seed, level, SPLIT_SALT),
+                ) < chance
+            {
+                fx *= 2.0;
+                fy *= 2.0;
+                scale *= 0.5;
+                level += 1;
+            }
+            let bits = hash(
+                cell(fx, tiles_x, level),
+                cell(fy, tiles_y, level),
+                hash(p.seed, level, TILE_SALT),
+            );
+            let k = tile_coverage(
+                p.style,
+                fx.fract(),
+                fy.fract(),
+                bits,
+                scale,
+                line_width * scale * 0.5,
+            );
+            let px = match p.background {
+                Background::Color => PixelF32 {
+                    red: background[0] + (color[0] - background[0]) * k,
+                    green: background[1] + (color[1] - background[1]) * k,
+                    blue: background[2] + (color[2] - background[2]) * k,
+                    alpha: 1.0,
+                },
+                Background::Transparent => PixelF32 {
+                    red: color[0],
+                    green: color[1],
+                    blue: color[2],
+                    alpha: k,
+                },
+            };
+            out.set(x, y, px);
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct TruchetTiles;
+
+impl RenderCore for TruchetTiles {
+    type Params = TruchetParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        TruchetParams {
+            style: match rng.popup(1, 3) {
+                2 => Style::Diagonals,
+                3 => Style::Weave,
+                _ => Style::Arcs,
+            },
+            tile_size: rng.slider(2.0, 400.0),
+            line_width: rng.slider(0.0, 1.0),
+            subdivisions: rng.int(0, MAX_SUBDIVISIONS) as u32,
+            subdivision_chance: rng.unit(),
+            seed: rng.popup(0, 10000) as u32,
+            line_color: [rng.unit(), rng.unit(), rng.unit()],
+            background: if rng.chance(0.5) {
+                Background::Color
+            } else {
+                Background::Transparent
+            },
+            background_color: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = truchet(input.width, input.height, params);
+    }
+}
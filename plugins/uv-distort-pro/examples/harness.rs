@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<uv_distort_pro::UvDistort>();
+}
@@ -5,6 +5,8 @@ use std::env;
 
 use ae::pf::*;
 use utils::ToPixel;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 enum Params {
@@ -220,6 +222,13 @@ impl Plugin {
             2 => WrapMode::Repeat,
             _ => WrapMode::Clamp,
         };
+        let settings = UvDistortParams {
+            intensity_x,
+            intensity_y,
+            u_offset,
+            v_offset,
+            wrap_mode,
+        };
 
         let tex_world_type = texture_layer.world_type();
         let uv_world_type = uv_layer.world_type();
@@ -243,32 +252,14 @@ impl Plugin {
             let x_dist = x.min(dist_w.saturating_sub(1));
             let y_dist = y.min(dist_h.saturating_sub(1));
 
-            // Base UV from UV map (R=U, G=V).
             let uv_px = read_pixel_f32(uv_layer, uv_world_type, x_uv, y_uv);
-            let u_base = uv_px.red;
-            let v_base = uv_px.green;
-
-            // Distort luminance from Distort map.
             let dist_px = read_pixel_f32(distort_layer, dist_world_type, x_dist, y_dist);
-            let l = luminance(dist_px); // 0..1
-
-            // UV distortion formula.
-            let u_final = u_base + (l - 0.5) * intensity_x + u_offset;
-            let v_final = v_base + (l - 0.5) * intensity_y + v_offset;
-
-            // Apply wrap mode in normalized 0..1 space.
-            let u_wrapped = wrap_coord(u_final, wrap_mode);
-            let v_wrapped = wrap_coord(v_final, wrap_mode);
+            let (u, v) = settings.distorted_uv(uv_px, dist_px);
 
             // Sample texture with bilinear interpolation (high-quality sampling).
-            let tex_px = sample_layer_f32(
-                texture_layer,
-                tex_world_type,
-                tex_w,
-                tex_h,
-                u_wrapped,
-                v_wrapped,
-            );
+            let tex_px = sample_bilinear(tex_w, tex_h, u, v, |x, y| {
+                read_pixel_f32(texture_layer, tex_world_type, x, y)
+            });
 
             // Write to output with correct bit depth.
             match out_world_type {
@@ -286,6 +277,72 @@ impl Plugin {
     }
 }
 
+#[derive(Debug)]
+pub struct UvDistortParams {
+    intensity_x: f32,
+    intensity_y: f32,
+    u_offset: f32,
+    v_offset: f32,
+    wrap_mode: WrapMode,
+}
+
+impl UvDistortParams {
+    /// Final texture coordinate (wrapped to 0..1) for a UV-map and distort-map pixel.
+    fn distorted_uv(&self, uv_px: PixelF32, dist_px: PixelF32) -> (f32, f32) {
+        // Base UV from UV map (R=U, G=V).
+        let u_base = uv_px.red;
+        let v_base = uv_px.green;
+
+        // Distort luminance from Distort map.
+        let l = luminance(dist_px); // 0..1
+
+        // UV distortion formula.
+        let u_final = u_base + (l - 0.5) * self.intensity_x + self.u_offset;
+        let v_final = v_base + (l - 0.5) * self.intensity_y + self.v_offset;
+
+        // Apply wrap mode in normalized 0..1 space.
+        (
+            wrap_coord(u_final, self.wrap_mode),
+            wrap_coord(v_final, self.wrap_mode),
+        )
+    }
+}
+
+/// Host-independent render core (see `utils::harness`). Like the legacy `Render`
+/// path, the input doubles as texture, UV map and distort map.
+pub struct UvDistort;
+
+impl RenderCore for UvDistort {
+    type Params = UvDistortParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        UvDistortParams {
+            intensity_x: rng.slider(-1.0, 1.0),
+            intensity_y: rng.slider(-1.0, 1.0),
+            u_offset: rng.slider(-1.0, 1.0),
+            v_offset: rng.slider(-1.0, 1.0),
+            wrap_mode: if rng.chance(0.5) {
+                WrapMode::Clamp
+            } else {
+                WrapMode::Repeat
+            },
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let px = input.data[input.index(x, y)];
+                let (u, v) = params.distorted_uv(px, px);
+                let tex_px = sample_bilinear(input.width, input.height, u, v, |x, y| {
+                    input.data[input.index(x, y)]
+                });
+                output.set(x, y, tex_px);
+            }
+        }
+    }
+}
+
 fn read_pixel_f32(layer: &Layer, world_type: ae::aegp::WorldType, x: usize, y: usize) -> PixelF32 {
     match world_type {
         ae::aegp::WorldType::U8 => layer.as_pixel8(x, y).to_pixel32(),
@@ -309,13 +366,12 @@ fn wrap_coord(v: f32, mode: WrapMode) -> f32 {
     }
 }
 
-fn sample_layer_f32(
-    layer: &Layer,
-    world_type: ae::aegp::WorldType,
+fn sample_bilinear(
     width: usize,
     height: usize,
     u: f32,
     v: f32,
+    read: impl Fn(usize, usize) -> PixelF32,
 ) -> PixelF32 {
     if width == 0 || height == 0 {
         return PixelF32 {
@@ -337,10 +393,10 @@ fn sample_layer_f32(
     let sx = fx - x0 as f32;
     let sy = fy - y0 as f32;
 
-    let c00 = read_pixel_f32(layer, world_type, x0 as usize, y0 as usize);
-    let c10 = read_pixel_f32(layer, world_type, x1 as usize, y0 as usize);
-    let c01 = read_pixel_f32(layer, world_type, x0 as usize, y1 as usize);
-    let c11 = read_pixel_f32(layer, world_type, x1 as usize, y1 as usize);
+    let c00 = read(x0 as usize, y0 as usize);
+    let c10 = read(x1 as usize, y0 as usize);
+    let c01 = read(x0 as usize, y1 as usize);
+    let c11 = read(x1 as usize, y1 as usize);
 
     // Bilinear interpolation.
     let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
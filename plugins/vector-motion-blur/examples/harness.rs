@@ -0,0 +1,3 @@
+fn main() {
+    utils::harness::main::<vector_motion_blur::VectorMotionBlur>();
+}
@@ -0,0 +1,316 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::finite_or;
+use utils::flow;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{self, Downsample};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    MotionLayer,
+    VectorScale,
+    ShutterScale,
+    Samples,
+    MaxBlur,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Blurs the layer along motion vectors from a motion layer.";
+
+/// Checkout id of the motion vector layer (the input uses 0).
+const MOTION_CHECKOUT_ID: i32 = 1;
+const MAX_VECTOR_SCALE: f32 = 4096.0;
+const MAX_SHUTTER_SCALE: f32 = 10.0;
+const MIN_SAMPLES: i32 = 2;
+const MAX_SAMPLES: i32 = 128;
+const MAX_BLUR: f32 = 2000.0;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Motion Layer: RG = motion since the previous frame, 0.5 = still
+        params.add(Params::MotionLayer, tr("Motion Layer"), LayerDef::new())?;
+
+        // Vector Scale: displacement in pixels of a full-range (0 or 1) channel
+        params.add(
+            Params::VectorScale,
+            tr("Vector Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_VECTOR_SCALE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(256.0);
+                d.set_default(32.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Shutter Scale: fraction of the frame's motion the shutter is open for (0.5 = 180°)
+        params.add(
+            Params::ShutterScale,
+            tr("Shutter Scale"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SHUTTER_SCALE);
+                d.set_slider_min(0.0);
+                d.set_slider_max(2.0);
+                d.set_default(0.5);
+                d.set_precision(2);
+            }),
+        )?;
+
+        params.add(
+            Params::Samples,
+            tr("Samples"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(MIN_SAMPLES);
+                d.set_valid_max(MAX_SAMPLES);
+                d.set_slider_min(MIN_SAMPLES);
+                d.set_slider_max(64);
+                d.set_default(16);
+            }),
+        )?;
+
+        // Max Blur: longest smear in pixels, whatever the vectors say
+        params.add(
+            Params::MaxBlur,
+            tr("Max Blur"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_BLUR);
+                d.set_slider_min(0.0);
+                d.set_slider_max(256.0);
+                d.set_default(64.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_VectorMotionBlur - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではモーションレイヤーをパラメータから直接取得する
+                let vectors = params
+                    .checkout_at(
+                        Params::MotionLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, vectors.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::MotionLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        MOTION_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let vectors = cb
+                    .checkout_layer_pixels(MOTION_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, vectors.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(MOTION_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        vectors: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // ベクトルの倍率と最大長はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+
+        let settings = BlurParams {
+            vector_scale: ds
+                .length(params.get(Params::VectorScale)?.as_float_slider()?.value() as f32),
+            shutter_scale: params.get(Params::ShutterScale)?.as_float_slider()?.value() as f32,
+            samples: params.get(Params::Samples)?.as_slider()?.value() as u32,
+            max_blur: ds.length(params.get(Params::MaxBlur)?.as_float_slider()?.value() as f32),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let output = match vectors {
+            Some(vectors) => motion_blur(&input, vectors, &settings),
+            None => input,
+        };
+        output.write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BlurParams {
+    /// Render pixels per unit of the decoded motion vector.
+    pub vector_scale: f32,
+    /// Fraction of the vector covered by the smear.
+    pub shutter_scale: f32,
+    pub samples: u32,
+    /// Longest smear in render pixels.
+    pub max_blur: f32,
+}
+
+fn motion_blur(input: &ImageBuf, vectors: &ImageBuf, p: &BlurParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    let mut output = ImageBuf::new(width, height);
+    if input.is_empty() {
+        return output;
+    }
+    if vectors.is_empty() {
+        return input.clone();
+    }
+    let premul = sampling::premultiply(input);
+    let scale = finite_or(p.vector_scale, 0.0).clamp(0.0, MAX_VECTOR_SCALE);
+    let shutter = finite_or(p.shutter_scale, 0.0).clamp(0.0, MAX_SHUTTER_SCALE);
+    let max_blur = finite_or(p.max_blur, 0.0).clamp(0.0, MAX_BLUR);
+    let samples = p.samples.clamp(MIN_SAMPLES as u32, MAX_SAMPLES as u32);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (vx, vy) = flow::vector_at(vectors, x, y, width, height, scale);
+            let (mut sx, mut sy) = (vx * shutter, vy * shutter);
+            let length = sx.hypot(sy);
+            if length > max_blur {
+                let k = max_blur / length;
+                sx *= k;
+                sy *= k;
+            }
+            if sx.hypot(sy) < 1e-3 {
+                output.set(x, y, input.data[y * width + x]);
+                continue;
+            }
+            // 露光の中心を現在フレームに置き、ベクトルの前後へ均等にサンプルする
+            let mut acc = [0.0f32; 4];
+            for i in 0..samples {
+                let t = (i as f32 + 0.5) / samples as f32 - 0.5;
+                let px = sampling::bilinear(&premul, x as f32 + sx * t, y as f32 + sy * t);
+                acc[0] += px.red;
+                acc[1] += px.green;
+                acc[2] += px.blue;
+                acc[3] += px.alpha;
+            }
+            let k = 1.0 / samples as f32;
+            let px = PixelF32 {
+                red: acc[0] * k,
+                green: acc[1] * k,
+                blue: acc[2] * k,
+                alpha: acc[3] * k,
+            };
+            output.set(x, y, sampling::unpremultiply(px));
+        }
+    }
+    output
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use the input itself as the
+/// motion layer.
+pub struct VectorMotionBlur;
+
+impl RenderCore for VectorMotionBlur {
+    type Params = BlurParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        BlurParams {
+            vector_scale: rng.slider(0.0, MAX_VECTOR_SCALE),
+            shutter_scale: rng.slider(0.0, MAX_SHUTTER_SCALE),
+            samples: rng.popup(MIN_SAMPLES, MAX_SAMPLES) as u32,
+            max_blur: rng.slider(0.0, MAX_BLUR),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = if frame % 2 == 1 {
+            motion_blur(input, input, params)
+        } else {
+            input.clone()
+        };
+    }
+}
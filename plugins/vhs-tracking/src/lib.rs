@@ -0,0 +1,508 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use noise::hash_unit;
+use utils::blur::blur_line;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::sampling::{Downsample, Edge};
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Damage,
+    DamageVariation,
+    BurstLength,
+    Jitter,
+    HeadSwitching,
+    HeadSwitchHeight,
+    ChromaBleed,
+    ChromaDelay,
+    LumaNoise,
+    Dropouts,
+    Seed,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Emulates VHS tracking errors.";
+
+/// Upper bound of the pixel-sized parameters (full-resolution pixels).
+const MAX_PIXELS: f32 = 500.0;
+/// Lines (full resolution) per step of the slow tracking wobble.
+const WOBBLE_LINES: f32 = 24.0;
+/// Luma noise amplitude at 100 %.
+const NOISE_STRENGTH: f32 = 0.25;
+/// Chance per line of a dropout at 100 % `Dropouts`.
+const DROPOUT_RATE: f32 = 0.04;
+/// Longest dropout streak as a fraction of the width.
+const DROPOUT_LENGTH: f32 = 0.3;
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Damage: scales every artifact, meant to be keyframed
+        params.add(
+            Params::Damage,
+            tr("Damage"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Damage Variation: how much the tape damage comes and goes in bursts over time
+        params.add(
+            Params::DamageVariation,
+            tr("Damage Variation"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Burst Length: frames between changes of the damage envelope
+        params.add(
+            Params::BurstLength,
+            tr("Burst Length"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(1);
+                d.set_valid_max(1000);
+                d.set_slider_min(1);
+                d.set_slider_max(120);
+                d.set_default(24);
+            }),
+        )?;
+
+        // Jitter: horizontal line displacement in pixels
+        params.add(
+            Params::Jitter,
+            tr("Jitter"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_PIXELS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(3.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Head Switching: skew and noise of the band at the bottom of the frame
+        params.add(
+            Params::HeadSwitching,
+            tr("Head Switching"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(50.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::HeadSwitchHeight,
+            tr("Head Switch Height"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_PIXELS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(60.0);
+                d.set_default(12.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Chroma Bleed: horizontal blur of the color signal in pixels
+        params.add(
+            Params::ChromaBleed,
+            tr("Chroma Bleed"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_PIXELS);
+                d.set_slider_min(0.0);
+                d.set_slider_max(20.0);
+                d.set_default(4.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Chroma Delay: color signal shifted right (or left) of the luma in pixels
+        params.add(
+            Params::ChromaDelay,
+            tr("Chroma Delay"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(-MAX_PIXELS);
+                d.set_valid_max(MAX_PIXELS);
+                d.set_slider_min(-20.0);
+                d.set_slider_max(20.0);
+                d.set_default(3.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::LumaNoise,
+            tr("Luma Noise"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(15.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Dropouts: bright horizontal streaks where the tape lost its signal
+        params.add(
+            Params::Dropouts,
+            tr("Dropouts"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(20.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Seed,
+            tr("Seed"),
+            SliderDef::setup(|d| {
+                d.set_valid_min(0);
+                d.set_valid_max(10000);
+                d.set_slider_min(0);
+                d.set_slider_max(1000);
+                d.set_default(0);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_VhsTracking - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_data, in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        let ds = Downsample::from_in_data(&in_data);
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+
+        let settings = VhsParams {
+            damage: slider(Params::Damage)? / 100.0,
+            damage_variation: slider(Params::DamageVariation)? / 100.0,
+            burst_length: params.get(Params::BurstLength)?.as_slider()?.value() as u32,
+            jitter: ds.length(slider(Params::Jitter)?),
+            head_switching: slider(Params::HeadSwitching)? / 100.0,
+            head_switch_height: ds.length(slider(Params::HeadSwitchHeight)?),
+            chroma_bleed: ds.length(slider(Params::ChromaBleed)?),
+            chroma_delay: ds.length(slider(Params::ChromaDelay)?),
+            luma_noise: slider(Params::LumaNoise)? / 100.0,
+            dropouts: slider(Params::Dropouts)? / 100.0,
+            seed: params.get(Params::Seed)?.as_slider()?.value() as u32,
+            // 走査線の乱数はフル解像度の行で決め、プレビュー解像度で模様が変わらないようにする
+            line_height: ds.length(1.0),
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        let frame = in_data.current_frame() as u32;
+        vhs(&input, &settings, frame).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct VhsParams {
+    /// 0..1.
+    pub damage: f32,
+    /// 0..1 share of the damage that comes in bursts.
+    pub damage_variation: f32,
+    /// Frames per envelope step.
+    pub burst_length: u32,
+    /// Render pixels.
+    pub jitter: f32,
+    /// 0..1.
+    pub head_switching: f32,
+    /// Render pixels.
+    pub head_switch_height: f32,
+    /// Render pixels.
+    pub chroma_bleed: f32,
+    /// Render pixels (signed).
+    pub chroma_delay: f32,
+    /// 0..1.
+    pub luma_noise: f32,
+    /// 0..1.
+    pub dropouts: f32,
+    pub seed: u32,
+    /// Render pixels per full-resolution line.
+    pub line_height: f32,
+}
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Smoothly interpolated 1D value noise in -1..1.
+fn value_noise(t: f32, seed: u32, salt: u32) -> f32 {
+    let i = t.floor();
+    let f = t - i;
+    let at = |k: f32| hash_unit(seed, k as i64 as u32, salt) * 2.0 - 1.0;
+    let s = f * f * (3.0 - 2.0 * f);
+    at(i) + (at(i + 1.0) - at(i)) * s
+}
+
+fn to_yiq([r, g, b]: [f32; 3]) -> [f32; 3] {
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        0.596 * r - 0.274 * g - 0.322 * b,
+        0.211 * r - 0.523 * g + 0.312 * b,
+    ]
+}
+
+fn from_yiq([y, i, q]: [f32; 3]) -> [f32; 3] {
+    [
+        y + 0.956 * i + 0.621 * q,
+        y - 0.272 * i - 0.647 * q,
+        y - 1.106 * i + 1.703 * q,
+    ]
+}
+
+/// Linear sample of `row` at `x` (pixel centers at `+0.5`, edges clamped).
+fn sample_row(row: &[[f32; 4]], x: f32) -> [f32; 4] {
+    let n = row.len();
+    let p = (x - 0.5).clamp(0.0, (n - 1) as f32);
+    let i = (p as usize).min(n - 1);
+    let j = (i + 1).min(n - 1);
+    let f = p - i as f32;
+    std::array::from_fn(|c| row[i][c] + (row[j][c] - row[i][c]) * f)
+}
+
+/// Tape damage at `frame` in 0..1: a constant part plus bursts from slow value noise.
+fn envelope(p: &VhsParams, frame: u32) -> f32 {
+    let damage = finite_or(p.damage, 0.0).clamp(0.0, 1.0);
+    let variation = finite_or(p.damage_variation, 0.0).clamp(0.0, 1.0);
+    let t = frame as f32 / p.burst_length.max(1) as f32;
+    let burst = smoothstep(0.0, 0.8, value_noise(t, p.seed, 0xda3a));
+    damage * (1.0 - variation + variation * burst)
+}
+
+pub fn vhs(input: &ImageBuf, p: &VhsParams, frame: u32) -> ImageBuf {
+    let (w, h) = (input.width, input.height);
+    let mut out = ImageBuf::new(w, h);
+    if input.is_empty() {
+        return out;
+    }
+    let damage = envelope(p, frame);
+    if damage <= 0.0 {
+        return input.clone();
+    }
+
+    let line_height = finite_or(p.line_height, 1.0).clamp(1e-3, 1e3);
+    let jitter = finite_or(p.jitter, 0.0).clamp(0.0, 1e4) * damage;
+    let head = finite_or(p.head_switching, 0.0).clamp(0.0, 1.0) * damage;
+    let band = finite_or(p.head_switch_height, 0.0).clamp(0.0, h as f32);
+    let bleed = finite_or(p.chroma_bleed, 0.0).clamp(0.0, 1e3) * damage.sqrt();
+    let delay = finite_or(p.chroma_delay, 0.0).clamp(-1e4, 1e4) * damage.sqrt();
+    let luma_noise = finite_or(p.luma_noise, 0.0).clamp(0.0, 1.0) * damage * NOISE_STRENGTH;
+    let dropouts = finite_or(p.dropouts, 0.0).clamp(0.0, 1.0) * damage * DROPOUT_RATE;
+    let frame_seed = noise::hash(p.seed, frame, 0x0f4a);
+
+    let mut row = vec![[0.0; 4]; w];
+    for y in 0..h {
+        let line = (y as f32 / line_height) as u32;
+        let yf = y as f32 + 0.5;
+
+        // 行ごとのランダムな揺れとゆっくりしたうねり
+        let mut shift = (hash_unit(frame_seed, line, 1) * 2.0 - 1.0) * jitter * 0.35
+            + value_noise(line as f32 / WOBBLE_LINES, frame_seed, 2) * jitter;
+        // ヘッド切替: 下端の帯は下へ行くほど大きく右にずれ、ノイズが乗る
+        let in_band = band > 0.0 && yf > h as f32 - band;
+        let band_t = if in_band {
+            ((yf - (h as f32 - band)) / band).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        if in_band {
+            shift += head * band_t * band_t * (band * 2.0 + w as f32 * 0.02)
+                + (hash_unit(frame_seed, line, 3) - 0.5) * head * band;
+        }
+
+        for (x, dst) in row.iter_mut().enumerate() {
+            let px = input.get_clamped(x as isize, y as isize);
+            *dst = [px.red, px.green, px.blue, px.alpha].map(|c| finite_or(c, 0.0));
+        }
+        let shifted: Vec<[f32; 4]> = (0..w)
+            .map(|x| sample_row(&row, x as f32 + 0.5 - shift))
+            .collect();
+
+        // 色信号 (I / Q) は遅れてにじむ
+        let yiq: Vec<[f32; 3]> = shifted.iter().map(|c| to_yiq([c[0], c[1], c[2]])).collect();
+        let chroma = |k: usize| -> Vec<f32> {
+            let delayed: Vec<f32> = (0..w)
+                .map(|x| {
+                    let sx = (x as f32 - delay).clamp(0.0, (w - 1) as f32);
+                    let (i, f) = (sx as usize, sx.fract());
+                    let j = (i + 1).min(w - 1);
+                    yiq[i][k] + (yiq[j][k] - yiq[i][k]) * f
+                })
+                .collect();
+            blur_line(&delayed, bleed * 0.5, Edge::Clamp)
+        };
+        let (ci, cq) = (chroma(1), chroma(2));
+
+        // ドロップアウト: 行の一部が白い筋になる
+        let dropout = (hash_unit(frame_seed, line, 4) < dropouts).then(|| {
+            let start = hash_unit(frame_seed, line, 5) * w as f32;
+            let length = (0.1 + 0.9 * hash_unit(frame_seed, line, 6)) * w as f32 * DROPOUT_LENGTH;
+            (start, length)
+        });
+        let band_noise = if in_band { head * band_t } else { 0.0 };
+
+        for x in 0..w {
+            let mut luma = yiq[x][0];
+            let n = hash_unit(frame_seed ^ line, x as u32, 7) * 2.0 - 1.0;
+            luma += n * (luma_noise + band_noise * 0.5);
+            if let Some((start, length)) = dropout {
+                let t = (x as f32 + 0.5 - start) / length.max(1.0);
+                if (0.0..1.0).contains(&t) {
+                    // 先頭が明るく、尾を引いて消える
+                    let k = (1.0 - t) * (1.0 - t);
+                    luma += (1.0 - luma) * k;
+                }
+            }
+            let rgb = from_yiq([luma, ci[x], cq[x]]);
+            let i = out.index(x, y);
+            out.data[i] = PixelF32 {
+                red: finite_or(rgb[0], 0.0),
+                green: finite_or(rgb[1], 0.0),
+                blue: finite_or(rgb[2], 0.0),
+                alpha: shifted[x][3].clamp(0.0, 1.0),
+            };
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct VhsTracking;
+
+impl RenderCore for VhsTracking {
+    type Params = VhsParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        VhsParams {
+            damage: rng.slider(0.0, 1.0),
+            damage_variation: rng.slider(0.0, 1.0),
+            burst_length: rng.int(1, 1000) as u32,
+            jitter: rng.slider(0.0, MAX_PIXELS),
+            head_switching: rng.slider(0.0, 1.0),
+            head_switch_height: rng.slider(0.0, MAX_PIXELS),
+            chroma_bleed: rng.slider(0.0, 50.0),
+            chroma_delay: rng.slider(-MAX_PIXELS, MAX_PIXELS),
+            luma_noise: rng.slider(0.0, 1.0),
+            dropouts: rng.slider(0.0, 1.0),
+            seed: rng.popup(0, 10000) as u32,
+            line_height: rng.slider(0.25, 1.0),
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = vhs(input, params, frame);
+    }
+}
@@ -0,0 +1,737 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::env;
+
+use ae::pf::*;
+use utils::color::{linear_to_srgb, luminance, srgb_to_linear};
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Scope,
+    Placement,
+    Size,
+    RangeMin,
+    RangeMax,
+    WorkingSpace,
+    Intensity,
+    BackgroundOpacity,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Overlays a waveform, parade, histogram or vectorscope.";
+
+/// Gap between a corner panel and the layer edge, as a fraction of the shorter side.
+const MARGIN: f32 = 0.02;
+/// Trace colors of the luma waveform and the vectorscope.
+const TRACE: [f32; 3] = [0.55, 1.0, 0.6];
+/// Colors of values above / below the displayed range.
+const OVER: [f32; 3] = [1.0, 0.2, 0.2];
+const UNDER: [f32; 3] = [0.3, 0.5, 1.0];
+const GRATICULE: f32 = 0.2;
+/// Graticule brightness at 0 and 1.
+const GRATICULE_MAJOR: f32 = 0.45;
+/// Rec. 709 chroma scale: Cb = (B - Y) / 1.8556, Cr = (R - Y) / 1.5748.
+const CB_SCALE: f32 = 1.8556;
+const CR_SCALE: f32 = 1.5748;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Luma per column against value.
+    Waveform,
+    /// Red, green and blue waveforms side by side.
+    Parade,
+    /// Pixel counts per value for each channel.
+    Histogram,
+    /// Rec. 709 Cb / Cr distribution.
+    Vectorscope,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+    FullFrame,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkingSpace {
+    /// Values as AE hands them over.
+    AsIs,
+    /// sRGB-encoded values shown as linear light.
+    Linearize,
+    /// Linear values shown sRGB-encoded.
+    Encode,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        params.add(
+            Params::Scope,
+            tr("Scope"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Waveform"),
+                    tr("RGB Parade"),
+                    tr("Histogram"),
+                    tr("Vectorscope"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Placement,
+            tr("Placement"),
+            PopupDef::setup(|d| {
+                d.set_options(&[
+                    tr("Bottom Left"),
+                    tr("Bottom Right"),
+                    tr("Top Left"),
+                    tr("Top Right"),
+                    tr("Full Frame"),
+                ]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Size: corner panel size as a percentage of the layer
+        params.add(
+            Params::Size,
+            tr("Size"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(5.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(10.0);
+                d.set_slider_max(100.0);
+                d.set_default(35.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        // Range Min / Max: values mapped to the bottom and top of the scope; values
+        // outside are pinned to the edge in a warning color
+        for (id, name, default) in [
+            (Params::RangeMin, tr("Range Min"), 0.0),
+            (Params::RangeMax, tr("Range Max"), 1.0),
+        ] {
+            params.add(
+                id,
+                name,
+                FloatSliderDef::setup(|d| {
+                    d.set_valid_min(-1000.0);
+                    d.set_valid_max(1000.0);
+                    d.set_slider_min(-1.0);
+                    d.set_slider_max(4.0);
+                    d.set_default(default);
+                    d.set_precision(2);
+                }),
+            )?;
+        }
+
+        // Working Space: converts the values before measuring them
+        params.add(
+            Params::WorkingSpace,
+            tr("Working Space"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("As Is"), tr("sRGB to Linear"), tr("Linear to sRGB")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Intensity: trace brightness
+        params.add(
+            Params::Intensity,
+            tr("Intensity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1000.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(400.0);
+                d.set_default(100.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::BackgroundOpacity,
+            tr("Background Opacity"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(100.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(100.0);
+                d.set_default(80.0);
+                d.set_precision(1);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_VideoScopes - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                self.do_render(in_layer, out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_layer, out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_layer: Layer,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // パネルの大きさはレイヤーに対する割合なので、ダウンサンプル時も同じ配置になる
+        let slider =
+            |id| -> Result<f32, Error> { Ok(params.get(id)?.as_float_slider()?.value() as f32) };
+        let settings = ScopeParams {
+            scope: match params.get(Params::Scope)?.as_popup()?.value() {
+                2 => Scope::Parade,
+                3 => Scope::Histogram,
+                4 => Scope::Vectorscope,
+                _ => Scope::Waveform,
+            },
+            placement: match params.get(Params::Placement)?.as_popup()?.value() {
+                2 => Placement::BottomRight,
+                3 => Placement::TopLeft,
+                4 => Placement::TopRight,
+                5 => Placement::FullFrame,
+                _ => Placement::BottomLeft,
+            },
+            size: slider(Params::Size)? / 100.0,
+            range: (slider(Params::RangeMin)?, slider(Params::RangeMax)?),
+            working_space: match params.get(Params::WorkingSpace)?.as_popup()?.value() {
+                2 => WorkingSpace::Linearize,
+                3 => WorkingSpace::Encode,
+                _ => WorkingSpace::AsIs,
+            },
+            intensity: slider(Params::Intensity)? / 100.0,
+            opacity: slider(Params::BackgroundOpacity)? / 100.0,
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        overlay_scope(&input, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ScopeParams {
+    pub scope: Scope,
+    pub placement: Placement,
+    /// Corner panel size as a fraction of the layer.
+    pub size: f32,
+    /// Values at the bottom and top of the scope.
+    pub range: (f32, f32),
+    pub working_space: WorkingSpace,
+    /// Trace gain (1 = default).
+    pub intensity: f32,
+    /// Darkening of the layer behind the panel, 0..1.
+    pub opacity: f32,
+}
+
+/// Additive light drawn over the darkened panel background.
+struct Canvas {
+    width: usize,
+    height: usize,
+    light: Vec<[f32; 3]>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            light: vec![[0.0; 3]; width * height],
+        }
+    }
+
+    /// Adds `color` at `strength` to the pixel at (`x`, `y`), y up.
+    fn add(&mut self, x: usize, y: usize, color: [f32; 3], strength: f32) {
+        if x < self.width && y < self.height {
+            let dst = &mut self.light[(self.height - 1 - y) * self.width + x];
+            for (d, c) in dst.iter_mut().zip(color) {
+                *d = (*d + c * strength).min(1.0);
+            }
+        }
+    }
+
+    fn hline(&mut self, y: usize, x0: usize, x1: usize, level: f32) {
+        for x in x0..x1.min(self.width) {
+            self.add(x, y, [1.0; 3], level);
+        }
+    }
+
+    fn vline(&mut self, x: usize, y0: usize, y1: usize, level: f32) {
+        for y in y0..y1.min(self.height) {
+            self.add(x, y, [1.0; 3], level);
+        }
+    }
+}
+
+/// Value range of the scope with a readable graticule step.
+struct Range {
+    min: f32,
+    max: f32,
+}
+
+impl Range {
+    fn new((min, max): (f32, f32)) -> Self {
+        let (min, max) = (
+            finite_or(min, 0.0).clamp(-1000.0, 1000.0),
+            finite_or(max, 1.0).clamp(-1000.0, 1000.0),
+        );
+        // 範囲が潰れたときは最小幅を確保する
+        let (min, max) = (min.min(max), max.max(min));
+        Self {
+            min,
+            max: max.max(min + 1e-3),
+        }
+    }
+
+    /// Position of `v` in `0..1`, or `Err(true)` above and `Err(false)` below the range.
+    fn unit(&self, v: f32) -> Result<f32, bool> {
+        let t = (v - self.min) / (self.max - self.min);
+        if t > 1.0 {
+            Err(true)
+        } else if t < 0.0 || t.is_nan() {
+            Err(false)
+        } else {
+            Ok(t)
+        }
+    }
+
+    /// Graticule values (at most about ten) and whether each is 0 or 1.
+    fn ticks(&self) -> Vec<(f32, bool)> {
+        let span = self.max - self.min;
+        let step = [
+            0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0,
+        ]
+        .into_iter()
+        .find(|s| span / s <= 10.0)
+        .unwrap_or(500.0);
+        let first = (self.min / step).ceil() as i32;
+        let last = (self.max / step).floor() as i32;
+        (first..=last)
+            .map(|i| {
+                let v = i as f32 * step;
+                (v, v == 0.0 || v == 1.0)
+            })
+            .collect()
+    }
+}
+
+fn luma([red, green, blue]: [f32; 3]) -> f32 {
+    luminance(PixelF32 {
+        red,
+        green,
+        blue,
+        alpha: 1.0,
+    })
+}
+
+fn convert(c: f32, space: WorkingSpace) -> f32 {
+    match space {
+        WorkingSpace::AsIs => c,
+        WorkingSpace::Linearize => srgb_to_linear(c),
+        WorkingSpace::Encode => linear_to_srgb(c),
+    }
+}
+
+/// Panel rectangle `(x, y, width, height)` in layer pixels.
+fn panel_rect(width: usize, height: usize, p: &ScopeParams) -> (usize, usize, usize, usize) {
+    if p.placement == Placement::FullFrame {
+        return (0, 0, width, height);
+    }
+    let size = finite_or(p.size, 0.35).clamp(0.05, 1.0);
+    let margin = (width.min(height) as f32 * MARGIN).round() as usize;
+    let w = ((width as f32 * size).round() as usize).clamp(1, width);
+    let h = ((height as f32 * size).round() as usize).clamp(1, height);
+    let (left, top) = (margin.min(width - w), margin.min(height - h));
+    let (right, bottom) = (width - w - left, height - h - top);
+    match p.placement {
+        Placement::TopLeft => (left, top, w, h),
+        Placement::TopRight => (right, top, w, h),
+        Placement::BottomRight => (right, bottom, w, h),
+        _ => (left, bottom, w, h),
+    }
+}
+
+/// Trace brightness for `count` hits where `expected` would be an even spread.
+fn trace(count: f32, expected: f32, intensity: f32) -> f32 {
+    1.0 - (-intensity * count / expected.max(1e-6)).exp()
+}
+
+/// Waveform of one channel (`None` for luma) into columns `x0..x0 + w` of `canvas`.
+fn waveform(
+    input: &ImageBuf,
+    values: &[[f32; 3]],
+    channel: Option<usize>,
+    canvas: &mut Canvas,
+    (x0, w): (usize, usize),
+    range: &Range,
+    p: &ScopeParams,
+) {
+    let h = canvas.height;
+    // 行 0 と h + 1 は範囲外の値の数
+    let mut bins = vec![0.0f32; w * (h + 2)];
+    for (i, (v, px)) in values.iter().zip(&input.data).enumerate() {
+        let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+        if a <= 0.0 {
+            continue;
+        }
+        let value = match channel {
+            Some(c) => v[c],
+            None => luma(*v),
+        };
+        let column = (i % input.width) * w / input.width;
+        let row = match range.unit(value) {
+            Ok(t) => 1 + ((t * h as f32) as usize).min(h - 1),
+            Err(true) => h + 1,
+            Err(false) => 0,
+        };
+        bins[row * w + column] += a;
+    }
+
+    let color = match channel {
+        Some(c) => std::array::from_fn(|i| if i == c { 1.0 } else { 0.15 }),
+        None => TRACE,
+    };
+    let expected = input.height as f32 * (input.width as f32 / w as f32) / h as f32;
+    let intensity = finite_or(p.intensity, 1.0).max(0.0);
+    for column in 0..w {
+        for y in 0..h {
+            let k = trace(bins[(y + 1) * w + column], expected, intensity);
+            canvas.add(x0 + column, y, color, k);
+        }
+        // 範囲外の値は上下端の画素に警告色で重ねる
+        let under = trace(bins[column], expected, intensity * h as f32);
+        let over = trace(bins[(h + 1) * w + column], expected, intensity * h as f32);
+        canvas.add(x0 + column, 0, UNDER, under);
+        canvas.add(x0 + column, h - 1, OVER, over);
+    }
+    for (v, major) in range.ticks() {
+        if let Ok(t) = range.unit(v) {
+            let y = ((t * h as f32) as usize).min(h - 1);
+            canvas.hline(
+                y,
+                x0,
+                x0 + w,
+                if major { GRATICULE_MAJOR } else { GRATICULE },
+            );
+        }
+    }
+}
+
+fn histogram(
+    input: &ImageBuf,
+    values: &[[f32; 3]],
+    canvas: &mut Canvas,
+    range: &Range,
+    p: &ScopeParams,
+) {
+    let (w, h) = (canvas.width, canvas.height);
+    let mut bins = vec![[0.0f32; 3]; w];
+    let (mut under, mut over) = (0.0f32, 0.0f32);
+    for (v, px) in values.iter().zip(&input.data) {
+        let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+        if a <= 0.0 {
+            continue;
+        }
+        for (c, &value) in v.iter().enumerate() {
+            match range.unit(value) {
+                Ok(t) => bins[((t * w as f32) as usize).min(w - 1)][c] += a,
+                Err(true) => over += a,
+                Err(false) => under += a,
+            }
+        }
+    }
+    // 一番高い棒がパネルの高さの 90% に収まるよう正規化する
+    let peak = bins.iter().flatten().fold(0.0f32, |m, &v| m.max(v));
+    let gain = finite_or(p.intensity, 1.0).max(0.0);
+    for (x, bin) in bins.iter().enumerate() {
+        for (c, &count) in bin.iter().enumerate() {
+            if count <= 0.0 {
+                continue;
+            }
+            let top = ((count / peak * gain * 0.9).min(1.0) * h as f32).ceil() as usize;
+            let color = std::array::from_fn(|i| if i == c { 1.0 } else { 0.0 });
+            for y in 0..top.min(h) {
+                canvas.add(x, y, color, 0.8);
+            }
+        }
+    }
+    let total = (input.width * input.height * 3).max(1) as f32;
+    // 範囲外の画素がある側の端に、割合に応じた高さの警告帯を描く
+    for (x, amount, color) in [(0, under, UNDER), (w - 1, over, OVER)] {
+        if amount > 0.0 {
+            let top = (((amount / total).sqrt() * h as f32).ceil() as usize).clamp(1, h);
+            for y in 0..top {
+                canvas.add(x, y, color, 1.0);
+            }
+        }
+    }
+    for (v, major) in range.ticks() {
+        if let Ok(t) = range.unit(v) {
+            let x = ((t * w as f32) as usize).min(w - 1);
+            canvas.vline(x, 0, h, if major { GRATICULE_MAJOR } else { GRATICULE });
+        }
+    }
+}
+
+fn vectorscope(input: &ImageBuf, values: &[[f32; 3]], canvas: &mut Canvas, p: &ScopeParams) {
+    // 正方形の領域に Cb / Cr の ±0.5 を収める
+    let side = canvas.width.min(canvas.height);
+    let (x0, y0) = ((canvas.width - side) / 2, (canvas.height - side) / 2);
+    let position = |v: [f32; 3]| {
+        let y = luma(v);
+        let (cb, cr) = ((v[2] - y) / CB_SCALE, (v[0] - y) / CR_SCALE);
+        ((cb + 0.5) * side as f32, (cr + 0.5) * side as f32)
+    };
+    let mut bins = vec![0.0f32; side * side];
+    let mut total = 0.0;
+    for (v, px) in values.iter().zip(&input.data) {
+        let a = finite_or(px.alpha, 0.0).clamp(0.0, 1.0);
+        let (x, y) = position(*v);
+        if a <= 0.0 || !(x.is_finite() && y.is_finite()) {
+            continue;
+        }
+        let (x, y) = (
+            x.clamp(0.0, side as f32 - 1.0) as usize,
+            y.clamp(0.0, side as f32 - 1.0) as usize,
+        );
+        bins[y * side + x] += a;
+        total += a;
+    }
+
+    let expected = total / (side * side) as f32 * 16.0;
+    let intensity = finite_or(p.intensity, 1.0).max(0.0);
+    for y in 0..side {
+        for x in 0..side {
+            let k = trace(bins[y * side + x], expected, intensity);
+            canvas.add(x0 + x, y0 + y, TRACE, k);
+        }
+    }
+
+    // 中心の十字、100% の彩度の円、75% の原色・補色の目標枠
+    let center = side / 2;
+    canvas.hline(y0 + center, x0, x0 + side, GRATICULE);
+    canvas.vline(x0 + center, y0, y0 + side, GRATICULE);
+    let radius = position([1.0, 0.0, 0.0]);
+    let radius = (radius.0 - side as f32 * 0.5).hypot(radius.1 - side as f32 * 0.5);
+    let steps = (radius * 8.0).max(16.0) as usize;
+    for i in 0..steps {
+        let (s, c) = (i as f32 / steps as f32 * std::f32::consts::TAU).sin_cos();
+        let (x, y) = (
+            side as f32 * 0.5 + radius * c,
+            side as f32 * 0.5 + radius * s,
+        );
+        if x >= 0.0 && y >= 0.0 {
+            canvas.add(x0 + x as usize, y0 + y as usize, [1.0; 3], GRATICULE);
+        }
+    }
+    for target in [
+        [0.75, 0.0, 0.0],
+        [0.75, 0.75, 0.0],
+        [0.0, 0.75, 0.0],
+        [0.0, 0.75, 0.75],
+        [0.0, 0.0, 0.75],
+        [0.75, 0.0, 0.75],
+    ] {
+        let (x, y) = position(target);
+        let r = (side / 40).max(1) as isize;
+        for d in -r..=r {
+            for (dx, dy) in [(d, -r), (d, r), (-r, d), (r, d)] {
+                let (tx, ty) = (x as isize + dx, y as isize + dy);
+                if tx >= 0 && ty >= 0 {
+                    canvas.add(
+                        x0 + tx as usize,
+                        y0 + ty as usize,
+                        target.map(|c| c / 0.75),
+                        GRATICULE_MAJOR,
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn overlay_scope(input: &ImageBuf, p: &ScopeParams) -> ImageBuf {
+    let mut out = input.clone();
+    if input.is_empty() {
+        return out;
+    }
+    let (px, py, w, h) = panel_rect(input.width, input.height, p);
+    let range = Range::new(p.range);
+    let values: Vec<[f32; 3]> = input
+        .data
+        .iter()
+        .map(|px| [px.red, px.green, px.blue].map(|c| convert(finite_or(c, 0.0), p.working_space)))
+        .collect();
+
+    let mut canvas = Canvas::new(w, h);
+    match p.scope {
+        Scope::Waveform => waveform(input, &values, None, &mut canvas, (0, w), &range, p),
+        Scope::Parade => {
+            // 3 等分した列にそれぞれのチャンネルを描く
+            for c in 0..3 {
+                let (x0, x1) = (w * c / 3, w * (c + 1) / 3);
+                if x1 > x0 {
+                    waveform(
+                        input,
+                        &values,
+                        Some(c),
+                        &mut canvas,
+                        (x0, x1 - x0),
+                        &range,
+                        p,
+                    );
+                }
+            }
+        }
+        Scope::Histogram => histogram(input, &values, &mut canvas, &range, p),
+        Scope::Vectorscope => vectorscope(input, &values, &mut canvas, p),
+    }
+
+    let opacity = finite_or(p.opacity, 0.8).clamp(0.0, 1.0);
+    for y in 0..h {
+        for x in 0..w {
+            let light = canvas.light[y * w + x];
+            let dst = &mut out.data[(py + y) * input.width + px + x];
+            // 背景を暗くしてからトレースの光を重ねる
+            let cover = light.iter().fold(0.0f32, |m, &v| m.max(v));
+            let keep = (1.0 - opacity) * (1.0 - cover);
+            let alpha = finite_or(dst.alpha, 0.0).clamp(0.0, 1.0);
+            let out_alpha = alpha + (1.0 - alpha) * (opacity + (1.0 - opacity) * cover);
+            let blend = |c: f32, l: f32| {
+                if out_alpha > 0.0 {
+                    (finite_or(c, 0.0) * alpha * keep + l) / out_alpha
+                } else {
+                    0.0
+                }
+            };
+            *dst = PixelF32 {
+                red: blend(dst.red, light[0]),
+                green: blend(dst.green, light[1]),
+                blue: blend(dst.blue, light[2]),
+                alpha: out_alpha,
+            };
+        }
+    }
+    out
+}
+
+/// Host-independent render core (see `utils::harness`).
+pub struct VideoScopes;
+
+impl RenderCore for VideoScopes {
+    type Params = ScopeParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        ScopeParams {
+            scope: match rng.popup(1, 4) {
+                2 => Scope::Parade,
+                3 => Scope::Histogram,
+                4 => Scope::Vectorscope,
+                _ => Scope::Waveform,
+            },
+            placement: match rng.popup(1, 5) {
+                2 => Placement::BottomRight,
+                3 => Placement::TopLeft,
+                4 => Placement::TopRight,
+                5 => Placement::FullFrame,
+                _ => Placement::BottomLeft,
+            },
+            size: rng.slider(0.05, 1.0),
+            range: (rng.slider(-2.0, 1.0), rng.slider(0.0, 4.0)),
+            working_space: match rng.popup(1, 3) {
+                2 => WorkingSpace::Linearize,
+                3 => WorkingSpace::Encode,
+                _ => WorkingSpace::AsIs,
+            },
+            intensity: rng.slider(0.0, 10.0),
+            opacity: rng.unit(),
+        }
+    }
+
+    fn render(params: &Self::Params, _frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        *output = overlay_scope(input, params);
+    }
+}
@@ -0,0 +1,522 @@
+#![allow(clippy::drop_non_drop, clippy::question_mark)]
+
+use after_effects as ae;
+use std::collections::VecDeque;
+use std::env;
+
+use ae::pf::*;
+use utils::blur;
+use utils::color::luminance;
+use utils::finite_or;
+use utils::harness::{RenderCore, Rng};
+use utils::image::ImageBuf;
+use utils::regions::{self, RegionOutput};
+use utils::sampling::{Downsample, Edge};
+use utils::ui;
+
+mod strings;
+use strings::tr;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Markers,
+    MarkerLayer,
+    MinimaDepth,
+    Relief,
+    Smoothing,
+    Output,
+    BoundaryColor,
+}
+
+#[derive(Default)]
+struct Plugin {}
+
+ae::define_effect!(Plugin, (), Params);
+
+const PLUGIN_DESCRIPTION: &str = "Segments the layer into regions by watershed flooding.";
+
+/// Checkout id of the marker layer (the input uses 0).
+const MARKER_CHECKOUT_ID: i32 = 1;
+const MAX_SMOOTHING: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Markers {
+    /// Every basin at least `Minima Depth` deep seeds a region.
+    RegionalMinima,
+    /// Every connected blob of bright, opaque marker-layer pixels seeds a region.
+    MarkerLayer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relief {
+    /// Edge strength: regions meet along edges of the layer.
+    Gradient,
+    /// Luminance itself: regions grow out of dark areas and meet on ridges.
+    Luminance,
+}
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(
+        &self,
+        params: &mut ae::Parameters<Params>,
+        _in_data: InData,
+        _: OutData,
+    ) -> Result<(), Error> {
+        // Markers: switches between the marker layer and minima depth (see update_param_visibility)
+        params.add_with_flags(
+            Params::Markers,
+            tr("Markers"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Regional Minima"), tr("Marker Layer")]);
+                d.set_default(1);
+            }),
+            ParamFlag::SUPERVISE,
+            ParamUIFlags::empty(),
+        )?;
+
+        // Marker Layer: white (opaque) blobs on black, one region per blob
+        params.add(Params::MarkerLayer, tr("Marker Layer"), LayerDef::new())?;
+
+        // Minima Depth: how much the relief must rise around a basin for it to stay separate
+        params.add(
+            Params::MinimaDepth,
+            tr("Minima Depth"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(1.0);
+                d.set_slider_min(0.0);
+                d.set_slider_max(0.5);
+                d.set_default(0.05);
+                d.set_precision(3);
+            }),
+        )?;
+
+        params.add(
+            Params::Relief,
+            tr("Relief"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Gradient"), tr("Luminance")]);
+                d.set_default(1);
+            }),
+        )?;
+
+        // Smoothing: Gaussian sigma applied to the luminance before flooding, in pixels
+        params.add(
+            Params::Smoothing,
+            tr("Smoothing"),
+            FloatSliderDef::setup(|d| {
+                d.set_valid_min(0.0);
+                d.set_valid_max(MAX_SMOOTHING);
+                d.set_slider_min(0.0);
+                d.set_slider_max(10.0);
+                d.set_default(1.5);
+                d.set_precision(1);
+            }),
+        )?;
+
+        params.add(
+            Params::Output,
+            tr("Output"),
+            PopupDef::setup(|d| {
+                d.set_options(&[tr("Mean Color"), tr("Region ID"), tr("Boundaries")]);
+                d.set_default(2);
+            }),
+        )?;
+
+        // Boundary Color: used by the Boundaries output
+        params.add(
+            Params::BoundaryColor,
+            tr("Boundary Color"),
+            ColorDef::setup(|d| {
+                d.set_default(Pixel8 {
+                    red: 255,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                });
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_command(
+        &mut self,
+        cmd: ae::Command,
+        in_data: InData,
+        mut out_data: OutData,
+        params: &mut ae::Parameters<Params>,
+    ) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg(
+                    format!(
+                        "AOD_WatershedSegment - {version}\r\r{PLUGIN_DESCRIPTION}\rCopyright (c) 2026-{build_year} Aodaruma",
+                        version = env!("CARGO_PKG_VERSION"),
+                        build_year = env!("BUILD_YEAR")
+                    )
+                    .as_str(),
+                );
+            }
+            ae::Command::GlobalSetup => {
+                out_data.set_out_flag(OutFlags::SendUpdateParamsUi, true);
+                out_data.set_out_flag2(OutFlags2::SupportsSmartRender, true);
+            }
+            ae::Command::UpdateParamsUi => {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::UserChangedParam { param_index }
+                if params.type_at(param_index) == Params::Markers =>
+            {
+                update_param_visibility(&in_data, params)?;
+            }
+            ae::Command::Render {
+                in_layer,
+                out_layer,
+            } => {
+                // 非 SmartRender ではマーカーレイヤーをパラメータから直接取得する
+                let markers = params
+                    .checkout_at(
+                        Params::MarkerLayer,
+                        Some(in_data.current_time()),
+                        Some(in_data.time_step()),
+                        Some(in_data.time_scale()),
+                    )?
+                    .as_layer()?
+                    .value()
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                self.do_render(in_data, in_layer, markers.as_ref(), out_layer, params)?;
+            }
+
+            ae::Command::SmartPreRender { mut extra } => {
+                let req = extra.output_request();
+
+                if let Ok(in_result) = extra.callbacks().checkout_layer(
+                    0,
+                    0,
+                    &req,
+                    in_data.current_time(),
+                    in_data.time_step(),
+                    in_data.time_scale(),
+                ) {
+                    let _ = extra.union_result_rect(in_result.result_rect.into());
+                    let _ = extra.union_max_result_rect(in_result.max_result_rect.into());
+                } else {
+                    return Err(Error::InterruptCancel);
+                }
+
+                if let Some(index) = params.index(Params::MarkerLayer) {
+                    let _ = extra.callbacks().checkout_layer(
+                        index as i32,
+                        MARKER_CHECKOUT_ID,
+                        &req,
+                        in_data.current_time(),
+                        in_data.time_step(),
+                        in_data.time_scale(),
+                    );
+                }
+            }
+
+            ae::Command::SmartRender { extra } => {
+                let cb = extra.callbacks();
+                let in_layer_opt = cb.checkout_layer_pixels(0)?;
+                let markers = cb
+                    .checkout_layer_pixels(MARKER_CHECKOUT_ID as u32)?
+                    .map(|layer| ImageBuf::from_layer(&layer));
+                let out_layer_opt = cb.checkout_output()?;
+
+                if let (Some(in_layer), Some(out_layer)) = (in_layer_opt, out_layer_opt) {
+                    self.do_render(in_data, in_layer, markers.as_ref(), out_layer, params)?;
+                }
+
+                cb.checkin_layer_pixels(0)?;
+                cb.checkin_layer_pixels(MARKER_CHECKOUT_ID as u32)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn markers(params: &Parameters<Params>) -> Result<Markers, Error> {
+    Ok(match params.get(Params::Markers)?.as_popup()?.value() {
+        2 => Markers::MarkerLayer,
+        _ => Markers::RegionalMinima,
+    })
+}
+
+fn update_param_visibility(in_data: &InData, params: &mut Parameters<Params>) -> Result<(), Error> {
+    let markers = markers(params)?;
+    for (id, visible) in [
+        (Params::MarkerLayer, markers == Markers::MarkerLayer),
+        (Params::MinimaDepth, markers == Markers::RegionalMinima),
+    ] {
+        ui::set_param_visible(in_data, params, id, visible)?;
+    }
+    Ok(())
+}
+
+impl Plugin {
+    fn do_render(
+        &self,
+        in_data: InData,
+        in_layer: Layer,
+        marker_layer: Option<&ImageBuf>,
+        mut out_layer: Layer,
+        params: &mut Parameters<Params>,
+    ) -> Result<(), Error> {
+        // 平滑化はフル解像度基準なのでダウンサンプル率を掛ける
+        let ds = Downsample::from_in_data(&in_data);
+        let boundary = params
+            .get(Params::BoundaryColor)?
+            .as_color()?
+            .float_value()?;
+
+        let settings = WatershedParams {
+            markers: markers(params)?,
+            minima_depth: params.get(Params::MinimaDepth)?.as_float_slider()?.value() as f32,
+            relief: match params.get(Params::Relief)?.as_popup()?.value() {
+                2 => Relief::Luminance,
+                _ => Relief::Gradient,
+            },
+            smoothing: ds.length(params.get(Params::Smoothing)?.as_float_slider()?.value() as f32),
+            output: match params.get(Params::Output)?.as_popup()?.value() {
+                2 => RegionOutput::RegionId,
+                3 => RegionOutput::Boundaries,
+                _ => RegionOutput::MeanColor,
+            },
+            boundary_color: [boundary.red, boundary.green, boundary.blue],
+        };
+
+        let input = ImageBuf::from_layer(&in_layer);
+        segment(&input, marker_layer, &settings).write_to_layer(&mut out_layer)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct WatershedParams {
+    pub markers: Markers,
+    pub minima_depth: f32,
+    pub relief: Relief,
+    /// Gaussian sigma in render pixels.
+    pub smoothing: f32,
+    pub output: RegionOutput,
+    /// Encoded boundary color.
+    pub boundary_color: [f32; 3],
+}
+
+/// Height field flooded by the watershed, roughly 0..1.
+fn relief(input: &ImageBuf, p: &WatershedParams) -> Vec<f32> {
+    let (w, h) = (input.width, input.height);
+    let lum: Vec<f32> = input
+        .data
+        .iter()
+        .map(|&px| finite_or(luminance(px), 0.0).clamp(0.0, 1.0))
+        .collect();
+    let sigma = finite_or(p.smoothing, 0.0).clamp(0.0, MAX_SMOOTHING);
+    let lum = blur::gaussian(&lum, w, h, sigma, Edge::Clamp);
+    match p.relief {
+        Relief::Luminance => lum,
+        Relief::Gradient => {
+            let at = |x: isize, y: isize| {
+                lum[y.clamp(0, h as isize - 1) as usize * w + x.clamp(0, w as isize - 1) as usize]
+            };
+            let mut grad = Vec::with_capacity(w * h);
+            for y in 0..h as isize {
+                for x in 0..w as isize {
+                    let gx = (at(x + 1, y) - at(x - 1, y)) * 0.5;
+                    let gy = (at(x, y + 1) - at(x, y - 1)) * 0.5;
+                    grad.push(gx.hypot(gy));
+                }
+            }
+            grad
+        }
+    }
+}
+
+/// Marker id per pixel: 8-connected blobs of marker pixels (luminance × alpha
+/// of at least one half), numbered in scan order.
+fn marker_blobs(map: &ImageBuf, width: usize, height: usize) -> Vec<Option<u32>> {
+    let on = |x: usize, y: usize| {
+        let px = map.get(x as isize, y as isize);
+        finite_or(luminance(px) * px.alpha, 0.0) >= 0.5
+    };
+    let mut ids = vec![None; width * height];
+    let mut next = 0;
+    let mut queue = VecDeque::new();
+    for start in 0..width * height {
+        if ids[start].is_some() || !on(start % width, start / width) {
+            continue;
+        }
+        ids[start] = Some(next);
+        queue.push_back(start);
+        while let Some(i) = queue.pop_front() {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                        continue;
+                    }
+                    let j = ny as usize * width + nx as usize;
+                    if ids[j].is_none() && on(nx as usize, ny as usize) {
+                        ids[j] = Some(next);
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        next += 1;
+    }
+    ids
+}
+
+/// Flooding basin tracked by the union-find.
+#[derive(Clone, Copy, Debug)]
+struct Basin {
+    /// Lowest relief in the basin.
+    floor: f32,
+    marker: Option<u32>,
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Watershed by flooding: pixels are added from the lowest relief up and
+/// joined with their already flooded neighbours. Two basins stay separate when
+/// both carry different markers or, without markers, when both are deeper than
+/// `Minima Depth` below the current level; anything else merges. Returns the
+/// region of every pixel, numbered in scan order.
+fn watershed(
+    relief: &[f32],
+    markers: Option<&[Option<u32>]>,
+    width: usize,
+    depth: f32,
+) -> Vec<u32> {
+    let n = relief.len();
+    // マーカー画素は起伏に関係なく最初に浸水させる
+    let level = |i: usize| match markers {
+        Some(m) if m[i].is_some() => f32::NEG_INFINITY,
+        _ => relief[i],
+    };
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| level(a).total_cmp(&level(b)));
+
+    let mut parent: Vec<usize> = (0..n).collect();
+    let mut basins: Vec<Basin> = (0..n)
+        .map(|i| Basin {
+            floor: relief[i],
+            marker: markers.and_then(|m| m[i]),
+        })
+        .collect();
+    let mut flooded = vec![false; n];
+    for &i in &order {
+        flooded[i] = true;
+        let v = relief[i];
+        let (x, y) = (i % width, i / width);
+        let neighbours = [
+            (x > 0).then(|| i - 1),
+            (x + 1 < width).then(|| i + 1),
+            (y > 0).then(|| i - width),
+            (i + width < n).then(|| i + width),
+        ];
+        for j in neighbours.into_iter().flatten() {
+            if !flooded[j] {
+                continue;
+            }
+            let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+            if a == b {
+                continue;
+            }
+            let (ba, bb) = (basins[a], basins[b]);
+            let separate = match (ba.marker, bb.marker) {
+                (Some(ma), Some(mb)) => ma != mb,
+                _ if markers.is_some() => false,
+                _ => v - ba.floor > depth && v - bb.floor > depth,
+            };
+            if separate {
+                continue;
+            }
+            // 底の低い方を根にする
+            let (root, child) = if ba.floor <= bb.floor { (a, b) } else { (b, a) };
+            parent[child] = root;
+            basins[root].marker = ba.marker.or(bb.marker);
+        }
+    }
+
+    let mut ids = vec![u32::MAX; n];
+    let mut labels = Vec::with_capacity(n);
+    let mut next = 0;
+    for i in 0..n {
+        let root = find(&mut parent, i);
+        if ids[root] == u32::MAX {
+            ids[root] = next;
+            next += 1;
+        }
+        labels.push(ids[root]);
+    }
+    labels
+}
+
+fn segment(input: &ImageBuf, marker_layer: Option<&ImageBuf>, p: &WatershedParams) -> ImageBuf {
+    let (width, height) = (input.width, input.height);
+    if input.is_empty() {
+        return ImageBuf::new(width, height);
+    }
+    let relief = relief(input, p);
+    let blobs = match (p.markers, marker_layer) {
+        (Markers::MarkerLayer, Some(map)) if !map.is_empty() => {
+            Some(marker_blobs(map, width, height))
+        }
+        _ => None,
+    };
+    let depth = finite_or(p.minima_depth, 0.0).clamp(0.0, 1.0);
+    let labels = watershed(&relief, blobs.as_deref(), width, depth);
+    regions::render_labels(input, &labels, p.output, p.boundary_color)
+}
+
+/// Host-independent render core (see `utils::harness`).
+///
+/// The harness has no second layer, so odd frames use the input itself as the
+/// marker layer.
+pub struct WatershedSegment;
+
+impl RenderCore for WatershedSegment {
+    type Params = WatershedParams;
+
+    fn random_params(rng: &mut Rng) -> Self::Params {
+        WatershedParams {
+            markers: if rng.chance(0.5) {
+                Markers::RegionalMinima
+            } else {
+                Markers::MarkerLayer
+            },
+            minima_depth: rng.slider(0.0, 1.0),
+            relief: if rng.chance(0.5) {
+                Relief::Gradient
+            } else {
+                Relief::Luminance
+            },
+            smoothing: rng.slider(0.0, MAX_SMOOTHING),
+            output: match rng.popup(1, 3) {
+                2 => RegionOutput::RegionId,
+                3 => RegionOutput::Boundaries,
+                _ => RegionOutput::MeanColor,
+            },
+            boundary_color: [rng.unit(), rng.unit(), rng.unit()],
+        }
+    }
+
+    fn render(params: &Self::Params, frame: u32, input: &ImageBuf, output: &mut ImageBuf) {
+        let markers = (frame % 2 == 1).then_some(input);
+        *output = segment(input, markers, params);
+    }
+}
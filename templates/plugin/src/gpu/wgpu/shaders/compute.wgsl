@@ -1,4 +1,22 @@
-// Placeholder WGSL shader. Replace with your compute/render logic.
-@compute @workgroup_size(1)
-fn main() {
+// Keep in sync with `RenderParams` in ../mod.rs.
+struct Params {
+    width: u32,
+    height: u32,
+    frame: u32,
+    _pad: u32,
+};
+
+@group(0) @binding(0) var<storage, read> input: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.width || gid.y >= params.height) {
+        return;
+    }
+    let i = gid.y * params.width + gid.x;
+
+    // Replace with your per-pixel logic. Pixels are straight RGBA floats.
+    output[i] = input[i];
 }
@@ -0,0 +1,20 @@
+// Builds red-noise.aep next to this script: a 128x128, 3-frame 8bpc comp with a
+// mid-grey solid and AOD_RedNoise at Noise Strength 0.5, queued as a PNG sequence.
+// Run once with File > Scripts > Run Script File and commit the saved project.
+(function () {
+    var folder = new File($.fileName).parent;
+
+    app.newProject();
+    app.project.bitsPerChannel = 8;
+
+    var comp = app.project.items.addComp("red-noise", 128, 128, 1, 3 / 24, 24);
+    var grey = 128 / 255;
+    var solid = comp.layers.addSolid([grey, grey, grey], "grey", 128, 128, 1);
+    var effect = solid.property("ADBE Effect Parade").addProperty("RedNoise");
+    effect.property(1).setValue(0.5);
+
+    var item = app.project.renderQueue.items.add(comp);
+    item.outputModule(1).setSettings({ "Format": "PNG Sequence" });
+
+    app.project.save(new File(folder.fsName + "/red-noise.aep"));
+})();
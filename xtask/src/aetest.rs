@@ -0,0 +1,318 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::plugins;
+use crate::{build, run_command, target_dir, workspace_root};
+
+/// Checked-in projects: `<case>.aep` plus `references/<case>/*.png`.
+const TEST_DIR: &str = "tests/aetest";
+/// Sub-folder of the AE `Plug-ins` directory the freshly built plugins are copied to.
+const INSTALL_DIR: &str = "AOD-aetest";
+/// Shared plug-in folder `just build` installs to; AE loads it alongside its own `Plug-ins`.
+const MEDIACORE_DIR: &str = if cfg!(target_os = "windows") {
+    r"C:\Program Files\Adobe\Common\Plug-ins\7.0\MediaCore"
+} else {
+    "/Library/Application Support/Adobe/Common/Plug-ins/7.0/MediaCore"
+};
+
+struct Options {
+    ae: PathBuf,
+    cases: Vec<String>,
+    tolerance: u8,
+    update: bool,
+    no_build: bool,
+}
+
+fn parse(args: &[String]) -> Result<Options> {
+    let mut ae = std::env::var_os("AE_PATH").map(PathBuf::from);
+    let mut opts = Options {
+        ae: PathBuf::new(),
+        cases: Vec::new(),
+        tolerance: 2,
+        update: false,
+        no_build: false,
+    };
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--ae" => ae = Some(it.next().context("`--ae` needs a path")?.into()),
+            "--case" => opts
+                .cases
+                .push(it.next().context("`--case` needs a name")?.clone()),
+            "--tolerance" => {
+                opts.tolerance = it
+                    .next()
+                    .context("`--tolerance` needs a value")?
+                    .parse()
+                    .context("`--tolerance` must be 0-255")?
+            }
+            "--update" => opts.update = true,
+            "--no-build" => opts.no_build = true,
+            other => bail!("unknown option `{other}` for `aetest`"),
+        }
+    }
+    opts.ae = ae.context("pass the After Effects install folder with `--ae PATH` (or AE_PATH)")?;
+    Ok(opts)
+}
+
+/// Renders every `tests/aetest/*.aep` with aerender against the freshly built release
+/// plugins and compares the output frames with the checked-in references.
+///
+/// Each project's first render-queue item must use a PNG sequence output module;
+/// the output path is overridden to `target/aetest/<case>/`.
+pub fn run(args: &[String]) -> Result<()> {
+    let opts = parse(args)?;
+    let root = workspace_root();
+    let (aerender, plugin_dir) = ae_layout(&opts.ae)?;
+    check_shadowing(&plugin_dir)?;
+
+    let mut cases: Vec<PathBuf> = fs::read_dir(root.join(TEST_DIR))
+        .with_context(|| format!("{TEST_DIR} does not exist"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "aep"))
+        .filter(|p| {
+            opts.cases.is_empty() || opts.cases.iter().any(|c| p.file_stem() == Some(c.as_ref()))
+        })
+        .collect();
+    cases.sort();
+    if let Some(script) = missing_projects(&root.join(TEST_DIR), &opts.cases)?.first() {
+        bail!(
+            "{} has no saved project; run it in After Effects (File > Scripts > Run Script File) \
+             to create the .aep (see {TEST_DIR}/README.md)",
+            script.display()
+        );
+    }
+    if cases.is_empty() {
+        bail!("no test projects found in {TEST_DIR} (see {TEST_DIR}/README.md)");
+    }
+
+    if !opts.no_build {
+        build::run(&["--release".to_string()])?;
+    }
+    install(&plugin_dir)?;
+    let result = render_and_compare(&opts, &aerender, &cases);
+    let _ = fs::remove_dir_all(&plugin_dir);
+    result
+}
+
+/// `aerender` executable and the plugin install folder of an AE installation.
+fn ae_layout(ae: &Path) -> Result<(PathBuf, PathBuf)> {
+    let (aerender, plugins) = if cfg!(target_os = "windows") {
+        let support = ae.join("Support Files");
+        (support.join("aerender.exe"), support.join("Plug-ins"))
+    } else {
+        (ae.join("aerender"), ae.join("Plug-ins"))
+    };
+    if !aerender.is_file() {
+        bail!("aerender not found at {}", aerender.display());
+    }
+    Ok((aerender, plugins.join(INSTALL_DIR)))
+}
+
+/// `<case>.jsx` project scripts whose `<case>.aep` has not been saved yet.
+fn missing_projects(dir: &Path, selected: &[String]) -> Result<Vec<PathBuf>> {
+    let mut scripts: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "jsx"))
+        .filter(|p| !p.with_extension("aep").is_file())
+        .filter(|p| {
+            selected.is_empty() || selected.iter().any(|c| p.file_stem() == Some(c.as_ref()))
+        })
+        .collect();
+    scripts.sort();
+    Ok(scripts)
+}
+
+/// Fails when copies of the plugins are already installed elsewhere: AE loads only one
+/// plugin per match name, so an older build in MediaCore or `Plug-ins` could be rendered
+/// instead of the one under test.
+fn check_shadowing(plugin_dir: &Path) -> Result<()> {
+    let names: Vec<String> = plugins::discover(&workspace_root())?
+        .into_iter()
+        .map(|p| p.plugin_name)
+        .collect();
+    let mut found = Vec::new();
+    let roots = [Path::new(MEDIACORE_DIR), plugin_dir.parent().unwrap()];
+    for root in roots {
+        find_bundles(root, plugin_dir, &names, &mut found);
+    }
+    if !found.is_empty() {
+        found.sort();
+        let list: Vec<String> = found.iter().map(|p| format!("  {}", p.display())).collect();
+        bail!(
+            "installed plugins would shadow the test build; move them out of the plug-in folders first:\n{}",
+            list.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Collects `<name>.aex` / `<name>.plugin` under `dir`, skipping `exclude` and bundle contents.
+fn find_bundles(dir: &Path, exclude: &Path, names: &[String], found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+        if path == exclude {
+            continue;
+        }
+        let is_bundle = path
+            .extension()
+            .is_some_and(|e| e == "aex" || e == "plugin");
+        if is_bundle {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            if names.iter().any(|n| *n == stem) {
+                found.push(path);
+            }
+        } else if path.is_dir() {
+            find_bundles(&path, exclude, names, found);
+        }
+    }
+}
+
+fn install(plugin_dir: &Path) -> Result<()> {
+    let release = target_dir().join("release");
+    let _ = fs::remove_dir_all(plugin_dir);
+    fs::create_dir_all(plugin_dir)
+        .with_context(|| format!("cannot write to {}", plugin_dir.display()))?;
+    for plugin in plugins::discover(&workspace_root())? {
+        if cfg!(target_os = "windows") {
+            let aex = format!("{}.aex", plugin.plugin_name);
+            fs::copy(release.join(&aex), plugin_dir.join(&aex))?;
+        } else {
+            let bundle = format!("{}.plugin", plugin.plugin_name);
+            run_command(
+                Command::new("cp")
+                    .arg("-R")
+                    .arg(release.join(&bundle))
+                    .arg(plugin_dir),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn render_and_compare(opts: &Options, aerender: &Path, cases: &[PathBuf]) -> Result<()> {
+    let mut failures = 0;
+    for project in cases {
+        let case = project.file_stem().unwrap().to_string_lossy().into_owned();
+        println!("== aetest {case}");
+        let out = target_dir().join("aetest").join(&case);
+        let _ = fs::remove_dir_all(&out);
+        fs::create_dir_all(&out)?;
+        run_command(
+            Command::new(aerender)
+                .arg("-project")
+                .arg(project)
+                .args(["-rqindex", "1", "-output"])
+                .arg(out.join("frame_[#####].png")),
+        )?;
+
+        let reference = project.with_file_name("references").join(&case);
+        if opts.update {
+            // 尺を縮めたときに古いフレームが残らないよう作り直す
+            let _ = fs::remove_dir_all(&reference);
+            fs::create_dir_all(&reference)?;
+            for frame in frames(&out)? {
+                fs::copy(&frame, reference.join(frame.file_name().unwrap()))?;
+            }
+            println!("updated references in {}", reference.display());
+            continue;
+        }
+        failures += compare(&case, &out, &reference, opts.tolerance)?;
+    }
+    if failures > 0 {
+        bail!("{failures} frame(s) differ from the references");
+    }
+    Ok(())
+}
+
+fn frames(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut frames: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "png"))
+        .collect();
+    frames.sort();
+    Ok(frames)
+}
+
+fn compare(case: &str, out: &Path, reference: &Path, tolerance: u8) -> Result<usize> {
+    let expected = frames(reference).unwrap_or_default();
+    if expected.is_empty() {
+        bail!(
+            "{case}: no references in {} (run with --update)",
+            reference.display()
+        );
+    }
+    let mut failures = 0;
+    for want in &expected {
+        let name = want.file_name().unwrap();
+        let got = out.join(name);
+        let name = name.to_string_lossy();
+        if !got.is_file() {
+            println!("  {name}: not rendered");
+            failures += 1;
+            continue;
+        }
+        let (a, b) = (read_rgba8(&got)?, read_rgba8(want)?);
+        if (a.0, a.1) != (b.0, b.1) {
+            println!(
+                "  {name}: size {}x{} != reference {}x{}",
+                a.0, a.1, b.0, b.1
+            );
+            failures += 1;
+            continue;
+        }
+        let diffs: Vec<u8> = a.2.iter().zip(&b.2).map(|(x, y)| x.abs_diff(*y)).collect();
+        let max = diffs.iter().copied().max().unwrap_or(0);
+        let over = diffs
+            .chunks(4)
+            .filter(|px| px.iter().any(|d| *d > tolerance))
+            .count();
+        if over > 0 {
+            println!("  {name}: {over} pixel(s) differ (max channel diff {max})");
+            failures += 1;
+        } else {
+            println!("  {name}: ok (max channel diff {max})");
+        }
+    }
+    // 参照にないフレーム (尺やワークエリアの変更) も不一致として扱う
+    for got in frames(out)? {
+        let name = got.file_name().unwrap();
+        if !reference.join(name).is_file() {
+            println!("  {}: not in references", name.to_string_lossy());
+            failures += 1;
+        }
+    }
+    Ok(failures)
+}
+
+/// Decodes a PNG to `(width, height, RGBA8)`.
+fn read_rgba8(path: &Path) -> Result<(u32, u32, Vec<u8>)> {
+    let file = fs::File::open(path)?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .with_context(|| format!("failed to decode {}", path.display()))?;
+    let mut buf = vec![0; reader.output_buffer_size().context("PNG too large")?];
+    let info = reader.next_frame(&mut buf)?;
+    let buf = &buf[..info.buffer_size()];
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buf.to_vec(),
+        png::ColorType::Rgb => buf
+            .chunks(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buf
+            .chunks(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => bail!("{}: indexed PNGs are not supported", path.display()),
+    };
+    Ok((info.width, info.height, rgba))
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::plugins::{self, PluginInfo};
+use crate::{run_command, target_dir, workspace_root};
+
+const MACOS_TARGETS: [&str; 2] = ["aarch64-apple-darwin", "x86_64-apple-darwin"];
+
+#[derive(Default)]
+struct Options {
+    release: bool,
+    universal: bool,
+    plugins: Vec<String>,
+}
+
+fn parse(args: &[String]) -> Result<Options> {
+    let mut opts = Options::default();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--release" => opts.release = true,
+            "--universal" => opts.universal = true,
+            "-p" | "--plugin" => opts
+                .plugins
+                .push(it.next().context("`--plugin` needs a value")?.clone()),
+            other => bail!("unknown option `{other}` for `build`"),
+        }
+    }
+    Ok(opts)
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let opts = parse(args)?;
+    let root = workspace_root();
+    let plugins = plugins::select(&root, &opts.plugins)?;
+    let profile = if opts.release { "release" } else { "debug" };
+
+    if opts.universal {
+        if !cfg!(target_os = "macos") {
+            bail!("`--universal` requires macOS (lipo and codesign are needed)");
+        }
+        run_command(
+            Command::new("rustup")
+                .args(["target", "add"])
+                .args(MACOS_TARGETS),
+        )?;
+        for target in MACOS_TARGETS {
+            cargo_build(&plugins, opts.release, Some(target))?;
+        }
+        for plugin in &plugins {
+            let slices: Vec<PathBuf> = MACOS_TARGETS
+                .iter()
+                .map(|t| target_dir().join(t).join(profile))
+                .collect();
+            let bundle = create_bundle(plugin, profile, &slices)?;
+            println!("Universal bundle: {}", bundle.display());
+        }
+        return Ok(());
+    }
+
+    cargo_build(&plugins, opts.release, None)?;
+    let out_dir = target_dir().join(profile);
+    for plugin in &plugins {
+        if cfg!(target_os = "macos") {
+            let bundle = create_bundle(plugin, profile, std::slice::from_ref(&out_dir))?;
+            println!("Bundle: {}", bundle.display());
+        } else if cfg!(target_os = "windows") {
+            let aex = out_dir.join(format!("{}.aex", plugin.plugin_name));
+            fs::copy(out_dir.join(format!("{}.dll", plugin.lib_name())), &aex)?;
+            println!("Plugin: {}", aex.display());
+        }
+    }
+    Ok(())
+}
+
+fn cargo_build(plugins: &[PluginInfo], release: bool, target: Option<&str>) -> Result<()> {
+    let mut cmd = Command::new(env!("CARGO"));
+    cmd.arg("build").current_dir(workspace_root());
+    for plugin in plugins {
+        cmd.args(["-p", &plugin.crate_name]);
+    }
+    if release {
+        cmd.arg("--release");
+    }
+    if let Some(target) = target {
+        cmd.args(["--target", target]);
+    }
+    run_command(&mut cmd)
+}
+
+/// Assembles `<PluginName>.plugin` in `target/<profile>/`.
+///
+/// `slices` are the cargo output directories to take the dylib from; more than one
+/// slice is merged with `lipo` into a universal binary. PiPL resources are
+/// architecture independent, so they are taken from the first slice.
+fn create_bundle(plugin: &PluginInfo, profile: &str, slices: &[PathBuf]) -> Result<PathBuf> {
+    let name = &plugin.plugin_name;
+    let bundle = target_dir().join(profile).join(format!("{name}.plugin"));
+    let contents = bundle.join("Contents");
+    if bundle.exists() {
+        fs::remove_dir_all(&bundle)?;
+    }
+    fs::create_dir_all(contents.join("Resources"))?;
+    fs::create_dir_all(contents.join("MacOS"))?;
+
+    let first = &slices[0];
+    let krate = &plugin.crate_name;
+    copy(
+        &first.join(format!("{krate}.rsrc")),
+        &contents.join("Resources").join(format!("{name}.rsrc")),
+    )?;
+    copy(
+        &first.join(format!("{krate}_PkgInfo")),
+        &contents.join("PkgInfo"),
+    )?;
+    let plist = contents.join("Info.plist");
+    copy(&first.join(format!("{krate}_Info.plist")), &plist)?;
+
+    let binary = contents.join("MacOS").join(name);
+    let dylib = format!("lib{}.dylib", plugin.lib_name());
+    if slices.len() == 1 {
+        copy(&first.join(&dylib), &binary)?;
+    } else {
+        let mut lipo = Command::new("lipo");
+        for slice in slices {
+            lipo.arg(slice.join(&dylib));
+        }
+        lipo.arg("-create").arg("-output").arg(&binary);
+        run_command(&mut lipo)?;
+    }
+
+    run_command(
+        Command::new("/usr/libexec/PlistBuddy")
+            .arg("-c")
+            .arg(format!(
+                "Set :CFBundleIdentifier \"{}\"",
+                plugin.bundle_identifier()
+            ))
+            .arg(&plist),
+    )?;
+    codesign(&bundle)?;
+    Ok(bundle)
+}
+
+/// Signs with the first "Apple Development" identity, falling back to an ad-hoc signature.
+/// As of AE 25.2 unsigned plugins fail to load on macOS.
+fn codesign(bundle: &Path) -> Result<()> {
+    let identities = Command::new("security")
+        .args(["find-identity", "-v", "-p", "codesigning"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        .unwrap_or_default();
+    let identity = identities
+        .lines()
+        .find(|l| l.contains("Apple Development"))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .unwrap_or("-")
+        .to_string();
+    if identity == "-" {
+        println!(
+            "Note: Using ad-hoc signature. For distribution, a valid Apple Developer certificate is recommended."
+        );
+    }
+    run_command(
+        Command::new("codesign")
+            .args(["--options", "runtime", "--timestamp", "-strict", "--sign"])
+            .arg(&identity)
+            .arg(bundle),
+    )
+}
+
+fn copy(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to)
+        .with_context(|| format!("failed to copy {} -> {}", from.display(), to.display()))?;
+    Ok(())
+}
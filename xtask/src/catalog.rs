@@ -0,0 +1,214 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::params::{self, ParamInfo};
+use crate::plugins::{self, PluginInfo, pascal_case};
+use crate::{target_dir, workspace_root};
+
+#[derive(Serialize)]
+struct Entry {
+    directory: String,
+    crate_name: String,
+    name: String,
+    match_name: String,
+    version: String,
+    description: String,
+    params: Vec<ParamInfo>,
+}
+
+struct Options {
+    out_dir: PathBuf,
+    json: bool,
+    markdown: bool,
+    check: bool,
+}
+
+fn parse(args: &[String]) -> Result<Options> {
+    let mut opts = Options {
+        out_dir: target_dir().join("catalog"),
+        json: true,
+        markdown: true,
+        check: false,
+    };
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--out" => opts.out_dir = PathBuf::from(it.next().context("`--out` needs a value")?),
+            "--format" => match it.next().map(String::as_str) {
+                Some("json") => opts.markdown = false,
+                Some("markdown" | "md") => opts.json = false,
+                Some("both") => {}
+                other => bail!("unknown format {other:?} (json, markdown, both)"),
+            },
+            "--check" => opts.check = true,
+            other => bail!("unknown option `{other}` for `catalog`"),
+        }
+    }
+    Ok(opts)
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let opts = parse(args)?;
+    let root = workspace_root();
+    let readme = fs::read_to_string(root.join("README.md")).unwrap_or_default();
+
+    let mut entries = Vec::new();
+    let mut issues = Vec::new();
+    for plugin in plugins::discover(&root)? {
+        let entry = collect(&plugin);
+        issues.extend(lint(&plugin, &entry, &readme));
+        entries.push(entry);
+    }
+
+    fs::create_dir_all(&opts.out_dir)?;
+    if opts.json {
+        let path = opts.out_dir.join("catalog.json");
+        fs::write(&path, serde_json::to_string_pretty(&entries)? + "\n")?;
+        println!("Wrote {}", path.display());
+    }
+    if opts.markdown {
+        let path = opts.out_dir.join("catalog.md");
+        fs::write(&path, markdown(&entries))?;
+        println!("Wrote {}", path.display());
+    }
+
+    for issue in &issues {
+        eprintln!("warning: {issue}");
+    }
+    if opts.check && !issues.is_empty() {
+        bail!("{} naming issue(s) found", issues.len());
+    }
+    Ok(())
+}
+
+fn collect(plugin: &PluginInfo) -> Entry {
+    let lib = plugin.read_source("src/lib.rs");
+    let build = plugin.read_source("build.rs");
+    Entry {
+        directory: plugin.dir_name.clone(),
+        crate_name: plugin.crate_name.clone(),
+        name: params::string_arg(&build, &["Property", "Name"])
+            .unwrap_or_else(|| plugin.plugin_name.clone()),
+        match_name: params::string_arg(&build, &["Property", "AE_Effect_Match_Name"])
+            .unwrap_or_default(),
+        version: plugin.version.clone(),
+        description: params::string_const(&lib, "PLUGIN_DESCRIPTION").unwrap_or_default(),
+        params: params::extract_params(&lib),
+    }
+}
+
+/// Checks the naming rules from AGENTS.md plus parameter naming consistency.
+fn lint(plugin: &PluginInfo, entry: &Entry, readme: &str) -> Vec<String> {
+    let dir = &entry.directory;
+    let pascal = pascal_case(&entry.crate_name);
+    let mut issues = Vec::new();
+
+    if entry.crate_name != dir.replace('-', "_") {
+        issues.push(format!(
+            "{dir}: crate name `{}` is not the snake_case directory name",
+            entry.crate_name
+        ));
+    }
+    if entry.name != format!("AOD_{pascal}") {
+        issues.push(format!(
+            "{dir}: AE name `{}` should be `AOD_{pascal}`",
+            entry.name
+        ));
+    }
+    if entry.name != plugin.plugin_name {
+        issues.push(format!(
+            "{dir}: Justfile PluginName `{}` differs from PiPL name `{}`",
+            plugin.plugin_name, entry.name
+        ));
+    }
+    if entry.match_name.is_empty() {
+        issues.push(format!("{dir}: AE_Effect_Match_Name not found in build.rs"));
+    }
+    if entry.description != plugin.description {
+        issues.push(format!(
+            "{dir}: PLUGIN_DESCRIPTION differs from Cargo.toml description"
+        ));
+    }
+    if !entry.description.ends_with('.') {
+        issues.push(format!("{dir}: description should end with a period"));
+    }
+    if !plugin.read_source("README.md").contains(&entry.description) {
+        issues.push(format!("{dir}: README.md does not contain the description"));
+    }
+    if !readme.contains(&entry.name) {
+        issues.push(format!(
+            "{dir}: `{}` is missing from the root README plugin list",
+            entry.name
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for p in &entry.params {
+        if !seen.insert(p.name.as_str()) {
+            issues.push(format!("{dir}: duplicate parameter name `{}`", p.name));
+        }
+        if p.name.trim() != p.name || p.name.ends_with(':') {
+            issues.push(format!(
+                "{dir}: parameter `{}` has stray whitespace or a trailing colon",
+                p.name
+            ));
+        }
+        if p.name
+            .split_whitespace()
+            .any(|w| w.starts_with(|c: char| c.is_lowercase()))
+        {
+            issues.push(format!("{dir}: parameter `{}` is not Title Case", p.name));
+        }
+        let squashed: String = p.name.chars().filter(|c| c.is_alphanumeric()).collect();
+        if !squashed.eq_ignore_ascii_case(&p.id) {
+            issues.push(format!(
+                "{dir}: `Params::{}` does not match its name `{}`",
+                p.id, p.name
+            ));
+        }
+    }
+    issues
+}
+
+fn markdown(entries: &[Entry]) -> String {
+    let mut md = String::from("# Plugin Catalog\n");
+    for e in entries {
+        let _ = write!(
+            md,
+            "\n## {name}\n\n{desc}\n\n- Version: {ver}\n- Match Name: `{mn}`\n- Crate: `{krate}` (`plugins/{dir}`)\n",
+            name = e.name,
+            desc = e.description,
+            ver = e.version,
+            mn = e.match_name,
+            krate = e.crate_name,
+            dir = e.directory,
+        );
+        if e.params.is_empty() {
+            continue;
+        }
+        md.push_str("\n| Parameter | Type | Default | Range / Options |\n|---|---|---|---|\n");
+        for p in &e.params {
+            let range = if !p.options.is_empty() {
+                p.options.join(", ")
+            } else {
+                match (&p.min, &p.max) {
+                    (Some(min), Some(max)) => format!("{min} .. {max}"),
+                    _ => String::new(),
+                }
+            };
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} |",
+                p.name,
+                p.kind,
+                p.default.as_deref().unwrap_or(""),
+                range
+            );
+        }
+    }
+    md
+}
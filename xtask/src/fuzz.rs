@@ -0,0 +1,63 @@
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+
+use crate::plugins::{self, PluginInfo};
+use crate::{run_command, workspace_root};
+
+/// Runs the plugin's host-independent render core (`examples/harness.rs`) against
+/// randomized parameters and degenerate inputs. Debug builds keep overflow checks on,
+/// so arithmetic overflow shows up as a panic.
+pub fn run(args: &[String]) -> Result<()> {
+    let mut plugin = None;
+    let mut iterations = "2000".to_string();
+    let mut seed = None;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--iterations" => {
+                iterations = it.next().context("`--iterations` needs a value")?.clone()
+            }
+            "--seed" => seed = Some(it.next().context("`--seed` needs a value")?.clone()),
+            other if other.starts_with('-') => bail!("unknown option `{other}` for `fuzz`"),
+            other => plugin = Some(other.to_string()),
+        }
+    }
+    let root = workspace_root();
+    let targets = match plugin {
+        Some(name) => plugins::select(&root, &[name])?,
+        None => plugins::discover(&root)?
+            .into_iter()
+            .filter(has_harness)
+            .collect(),
+    };
+
+    for plugin in &targets {
+        if !has_harness(plugin) {
+            bail!(
+                "{} has no render-core harness (add examples/harness.rs, see utils::harness)",
+                plugin.dir_name
+            );
+        }
+        println!("== fuzz {}", plugin.dir_name);
+        let mut cmd = Command::new(env!("CARGO"));
+        cmd.current_dir(&root)
+            .args([
+                "run",
+                "-p",
+                &plugin.crate_name,
+                "--example",
+                "harness",
+                "--",
+            ])
+            .args(["fuzz", "--iterations", &iterations]);
+        if let Some(seed) = &seed {
+            cmd.args(["--seed", seed]);
+        }
+        run_command(&mut cmd)?;
+    }
+    Ok(())
+}
+
+pub fn has_harness(plugin: &PluginInfo) -> bool {
+    plugin.dir.join("examples").join("harness.rs").is_file()
+}
@@ -0,0 +1,186 @@
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::params;
+use crate::plugins::{self, PluginInfo};
+use crate::workspace_root;
+
+/// Languages with a `utils::i18n::Language` variant (English is the source language).
+const LANGUAGES: [&str; 1] = ["ja"];
+
+struct Options {
+    plugins: Vec<String>,
+    init: bool,
+}
+
+fn parse(args: &[String]) -> Result<Options> {
+    let mut opts = Options {
+        plugins: Vec::new(),
+        init: false,
+    };
+    for arg in args {
+        match arg.as_str() {
+            "--init" => opts.init = true,
+            other if other.starts_with('-') => bail!("unknown option `{other}` for `localize`"),
+            other => opts.plugins.push(other.to_string()),
+        }
+    }
+    if opts.init && opts.plugins.is_empty() {
+        bail!("`--init` needs the plugin(s) to set up");
+    }
+    Ok(opts)
+}
+
+/// Extracts the user-visible strings of `params_setup` (and any other `tr("...")`)
+/// into `locales/<lang>.toml`, keeping existing translations, and regenerates
+/// `src/strings.rs`. Names built with `format!` are extracted as their template;
+/// localize them with `i18n::fill(tr("Light {} Type"), n)`.
+///
+/// Plugins opt in by having a `locales/` directory (`--init` creates it).
+pub fn run(args: &[String]) -> Result<()> {
+    let opts = parse(args)?;
+    let root = workspace_root();
+    let targets: Vec<PluginInfo> = if opts.plugins.is_empty() {
+        plugins::discover(&root)?
+            .into_iter()
+            .filter(|p| p.dir.join("locales").is_dir())
+            .collect()
+    } else {
+        plugins::select(&root, &opts.plugins)?
+    };
+
+    for plugin in &targets {
+        let locales = plugin.dir.join("locales");
+        if !locales.is_dir() {
+            if !opts.init {
+                bail!(
+                    "{} has no locales/ directory (run with --init to create it)",
+                    plugin.dir_name
+                );
+            }
+            fs::create_dir_all(&locales)?;
+        }
+        localize(plugin)?;
+    }
+    Ok(())
+}
+
+fn localize(plugin: &PluginInfo) -> Result<()> {
+    let source = plugin.read_source("src/lib.rs");
+    let strings = params::localizable_strings(&source);
+
+    let locales = plugin.dir.join("locales");
+    write_table(&locales.join("en.toml"), "en", &strings, |s| s.to_string())?;
+
+    let mut tables = BTreeMap::new();
+    for lang in LANGUAGES {
+        let path = locales.join(format!("{lang}.toml"));
+        let existing = read_table(&path)?;
+        let obsolete: Vec<&String> = existing.keys().filter(|k| !strings.contains(k)).collect();
+        for key in &obsolete {
+            println!(
+                "{}: dropping obsolete {lang} entry {key:?}",
+                plugin.dir_name
+            );
+        }
+        let missing = strings
+            .iter()
+            .filter(|s| existing.get(*s).is_none_or(|v| v.is_empty()))
+            .count();
+        write_table(&path, lang, &strings, |s| {
+            existing.get(s).cloned().unwrap_or_default()
+        })?;
+        println!(
+            "{}: {lang}.toml {} string(s), {missing} untranslated",
+            plugin.dir_name,
+            strings.len()
+        );
+        tables.insert(lang, (strings.clone(), existing));
+    }
+
+    fs::write(
+        plugin.dir.join("src").join("strings.rs"),
+        string_module(&tables),
+    )?;
+    Ok(())
+}
+
+fn read_table(path: &std::path::Path) -> Result<BTreeMap<String, String>> {
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let text = fs::read_to_string(path)?;
+    let table: toml::Table =
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(table
+        .get("strings")
+        .and_then(|v| v.as_table())
+        .map(|t| {
+            t.iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn write_table(
+    path: &std::path::Path,
+    lang: &str,
+    strings: &[String],
+    value: impl Fn(&String) -> String,
+) -> Result<()> {
+    let mut out = String::from(
+        "# Generated by `cargo xtask localize`; keys are the English source strings.\n",
+    );
+    if lang != "en" {
+        out.push_str("# Fill in the translations. Empty values fall back to English.\n");
+    }
+    out.push_str("\n[strings]\n");
+    for s in strings {
+        let _ = writeln!(
+            out,
+            "{} = {}",
+            toml::Value::String(s.clone()),
+            toml::Value::String(value(s))
+        );
+    }
+    fs::write(path, out)?;
+    Ok(())
+}
+
+type Tables<'a> = BTreeMap<&'a str, (Vec<String>, BTreeMap<String, String>)>;
+
+fn string_module(tables: &Tables) -> String {
+    let mut out = String::from(
+        "// @generated by `cargo xtask localize` from locales/*.toml. Do not edit by hand.\n\nuse utils::i18n::{self, Language};\n",
+    );
+    for (lang, (strings, translations)) in tables {
+        let _ = write!(
+            out,
+            "\n#[rustfmt::skip]\nconst {}: &[(&str, &str)] = &[\n",
+            lang.to_ascii_uppercase()
+        );
+        for s in strings {
+            if let Some(t) = translations.get(s).filter(|t| !t.is_empty()) {
+                let _ = writeln!(out, "    ({s:?}, {t:?}),");
+            }
+        }
+        out.push_str("];\n");
+    }
+    out.push_str(
+        "\n/// Localized form of a user-visible English string.\npub fn tr(text: &'static str) -> &'static str {\n    match i18n::current() {\n",
+    );
+    for lang in tables.keys() {
+        let mut variant = lang.to_string();
+        variant[..1].make_ascii_uppercase();
+        let _ = writeln!(
+            out,
+            "        Language::{variant} => i18n::lookup({}, text),",
+            lang.to_ascii_uppercase()
+        );
+    }
+    out.push_str("        _ => text,\n    }\n}\n");
+    out
+}
@@ -0,0 +1,106 @@
+//! Developer tasks for the workspace, invoked through `cargo xtask <command>`.
+
+mod aetest;
+mod build;
+mod catalog;
+mod fuzz;
+mod localize;
+mod new_plugin;
+mod params;
+mod plugins;
+mod presets;
+mod profile;
+
+use anyhow::{Result, bail};
+use std::env;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: cargo xtask <command> [options]
+
+Commands:
+  build      Build plugins and assemble the host bundle (.plugin / .aex)
+             --release          build with the release profile
+             --universal        (macOS) merge aarch64 + x86_64 into one binary
+             -p, --plugin NAME  limit to a plugin (directory or crate name), repeatable
+  aetest     Render tests/aetest/*.aep with aerender and diff against reference frames
+             --ae PATH          After Effects install folder (default: $AE_PATH)
+             --case NAME        limit to a project (file stem), repeatable
+             --tolerance N      allowed per-channel difference, 0-255 (default: 2)
+             --update           overwrite the references with the new output
+             --no-build         use the existing release bundles
+  catalog    Export a JSON/Markdown catalog of plugins and their parameters
+             --out DIR          output directory (default: target/catalog)
+             --format FMT       json, markdown or both (default: both)
+             --check            fail when naming inconsistencies are found
+  fuzz       Run a plugin's render core on random params and degenerate inputs
+             [PLUGIN]           plugin to fuzz (default: every plugin with a harness)
+             --iterations N     number of cases (default: 2000)
+             --seed S           RNG seed for reproducing a failure
+  profile    Record a flamegraph of a plugin's render core (requires cargo-flamegraph)
+             PLUGIN             plugin to profile
+             --frames N         frames to render (default: 10)
+             --width W / --height H  frame size (default: 1920x1080)
+  localize   Extract params_setup strings to locales/<lang>.toml and regenerate src/strings.rs
+             [PLUGIN...]        plugins to process (default: those with a locales/ directory)
+             --init             create locales/ for the given plugins
+  presets    Validate plugins/<name>/presets/*.json and copy them to target/<profile>/presets
+             --release          bundle into target/release (default: target/debug)
+             --check            only validate, copy nothing
+             -p, --plugin NAME  limit to a plugin (directory or crate name), repeatable
+  new-plugin Create plugins/<name> from templates/plugin (requires cargo-generate)
+             --description TEXT one-sentence description (see AGENTS.md)
+             --gpu              include the wgpu compute path (gpu_wgpu feature)
+             --feature NAME     extra template feature (imageproc, rustfft, nalgebra, rand)
+";
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprint!("{USAGE}");
+        std::process::exit(2);
+    };
+    let rest: Vec<String> = args.collect();
+
+    match command.as_str() {
+        "aetest" => aetest::run(&rest),
+        "build" => build::run(&rest),
+        "catalog" => catalog::run(&rest),
+        "fuzz" => fuzz::run(&rest),
+        "localize" => localize::run(&rest),
+        "new-plugin" => new_plugin::run(&rest),
+        "presets" => presets::run(&rest),
+        "profile" => profile::run(&rest),
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        other => bail!("unknown command `{other}`\n\n{USAGE}"),
+    }
+}
+
+/// Workspace root (the parent of this crate's manifest directory).
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask must live inside the workspace")
+        .to_path_buf()
+}
+
+/// Cargo target directory, honoring `CARGO_TARGET_DIR` like the Justfiles do.
+pub fn target_dir() -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| workspace_root().join("target"))
+}
+
+/// Runs a command, failing if it cannot be spawned or exits unsuccessfully.
+pub fn run_command(cmd: &mut std::process::Command) -> Result<()> {
+    let status = cmd
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to run {:?}: {e}", cmd.get_program()))?;
+    if !status.success() {
+        bail!("{:?} exited with {status}", cmd.get_program());
+    }
+    Ok(())
+}
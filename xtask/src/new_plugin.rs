@@ -0,0 +1,140 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::process::Command;
+
+use crate::{run_command, target_dir, workspace_root};
+
+const FEATURES: [&str; 5] = ["imageproc", "rustfft", "nalgebra", "rand", "wgpu"];
+
+struct Options {
+    name: String,
+    description: String,
+    features: Vec<String>,
+}
+
+fn parse(args: &[String]) -> Result<Options> {
+    let mut name = None;
+    let mut description = None;
+    let mut features = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--gpu" => features.push("wgpu".to_string()),
+            "--feature" => {
+                let f = it.next().context("`--feature` needs a value")?;
+                if !FEATURES.contains(&f.as_str()) {
+                    bail!("unknown feature `{f}` (available: {})", FEATURES.join(", "));
+                }
+                features.push(f.clone());
+            }
+            "--description" => {
+                description = Some(it.next().context("`--description` needs a value")?.clone())
+            }
+            other if other.starts_with('-') => bail!("unknown option `{other}` for `new-plugin`"),
+            other => name = Some(other.to_string()),
+        }
+    }
+    features.dedup();
+
+    let name = name.context("usage: cargo xtask new-plugin <kebab-name> [--gpu]")?;
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        bail!("plugin directory names must be kebab-case (e.g. `distance-generate`)");
+    }
+    Ok(Options {
+        name,
+        description: description.unwrap_or_else(|| "TODO: Describe the plugin.".to_string()),
+        features,
+    })
+}
+
+/// Generates `plugins/<name>` from `templates/plugin` non-interactively and registers it
+/// in the workspace. `--gpu` selects the `wgpu` feature, which keeps the template's
+/// `gpu::wgpu` module, WGSL stub and the cfg-gated GPU dispatch in `do_render`.
+pub fn run(args: &[String]) -> Result<()> {
+    let opts = parse(args)?;
+    let root = workspace_root();
+    let dest = root.join("plugins").join(&opts.name);
+    if dest.exists() {
+        bail!("{} already exists", dest.display());
+    }
+
+    let features = opts
+        .features
+        .iter()
+        .map(|f| format!("{f:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let values = format!(
+        "[values]\ndescription = {:?}\nfeatures = [{features}]\n",
+        opts.description
+    );
+    let values_file = target_dir().join("xtask-new-plugin-values.toml");
+    fs::create_dir_all(target_dir())?;
+    fs::write(&values_file, values)?;
+
+    run_command(
+        Command::new(env!("CARGO"))
+            .current_dir(&root)
+            .args([
+                "generate",
+                "--path",
+                "templates/plugin",
+                "--destination",
+                "plugins",
+            ])
+            .args(["--name", &opts.name, "--silent", "--template-values-file"])
+            .arg(&values_file),
+    )
+    .context("cargo-generate failed (install it with `cargo install cargo-generate`)")?;
+
+    add_workspace_member(&format!("plugins/{}", opts.name))?;
+
+    println!("Created {}", dest.display());
+    println!("Next: check build.rs Name/Match Name, then add the plugin to the root README list.");
+    Ok(())
+}
+
+fn add_workspace_member(member: &str) -> Result<()> {
+    let path = workspace_root().join("Cargo.toml");
+    let manifest = fs::read_to_string(&path)?;
+    let start = manifest
+        .find("members = [")
+        .context("workspace members not found")?
+        + "members = [".len();
+    let end = start
+        + manifest[start..]
+            .find(']')
+            .context("unterminated members")?;
+
+    let mut members: Vec<String> = manifest[start..end]
+        .split(',')
+        .map(|m| m.trim().trim_matches('"').to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    if members.iter().any(|m| m == member) {
+        return Ok(());
+    }
+    members.push(member.to_string());
+    // plugins first (alphabetical), then everything else in its original order
+    members.sort_by_key(|m| {
+        (
+            !m.starts_with("plugins/"),
+            m.starts_with("plugins/").then(|| m.clone()),
+        )
+    });
+
+    let list = members
+        .iter()
+        .map(|m| format!("{m:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    fs::write(
+        &path,
+        format!("{}{list}{}", &manifest[..start], &manifest[end..]),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,432 @@
+//! Lightweight extraction of plugin metadata from Rust sources.
+//!
+//! The plugins are `cdylib`s linked against the AE SDK, so rather than running
+//! them we scan `params_setup` for `params.add(...)` / `params.add_with_flags(...)`
+//! calls. Parameters must therefore be declared with a `Params::Variant` id and a
+//! string literal name to be picked up.
+
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(String),
+    Punct(char),
+}
+
+/// A parameter declared in `params_setup`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ParamInfo {
+    /// `Params` enum variant, e.g. `NoiseStrength`.
+    pub id: String,
+    /// User-visible name, e.g. `Noise Strength`.
+    pub name: String,
+    /// Definition type without the `Def` suffix, e.g. `FloatSlider`, `Popup`.
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    /// Checkbox / button label (`set_label`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Parameters declared in the `params_setup` function of `source`, in declaration order.
+pub fn extract_params(source: &str) -> Vec<ParamInfo> {
+    let tokens = tokenize(source);
+    let Some(body) = fn_body(&tokens, "params_setup") else {
+        return Vec::new();
+    };
+    add_calls(body)
+        .into_iter()
+        .filter_map(parse_add_args)
+        .collect()
+}
+
+/// User-visible strings to localize, in declaration order: the name, label and
+/// options of every `add` call in `params_setup` (also those in loops, whose id
+/// is a variable), with names built by `format!` taken as their template, then
+/// every other `tr("...")` in `source`.
+pub fn localizable_strings(source: &str) -> Vec<String> {
+    let tokens = tokenize(source);
+    let mut out: Vec<String> = Vec::new();
+    let mut push = |s: String| {
+        if !out.contains(&s) {
+            out.push(s);
+        }
+    };
+    if let Some(body) = fn_body(&tokens, "params_setup") {
+        for args in add_calls(body) {
+            let parts = split_top_level(args, ',');
+            if let Some(name) = parts.get(1).and_then(|t| name_str(t)) {
+                push(name);
+            }
+            let Some(def) = parts.get(2) else {
+                continue;
+            };
+            if let Some(label) = setter_tokens(def, "set_label").and_then(name_str) {
+                push(label);
+            }
+            for t in setter_tokens(def, "set_options").unwrap_or_default() {
+                if let Token::Str(s) = t {
+                    push(s.clone());
+                }
+            }
+        }
+    }
+    for w in tokens.windows(4) {
+        if let [
+            Token::Ident(f),
+            Token::Punct('('),
+            Token::Str(s),
+            Token::Punct(')'),
+        ] = w
+            && f == "tr"
+        {
+            push(s.clone());
+        }
+    }
+    out
+}
+
+/// Argument tokens of every `.add(...)` / `.add_with_flags(...)` call in `body`.
+fn add_calls(body: &[Token]) -> Vec<&[Token]> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i + 2 < body.len() {
+        let is_add = body[i] == Token::Punct('.')
+            && matches!(&body[i + 1], Token::Ident(m) if m == "add" || m == "add_with_flags")
+            && body[i + 2] == Token::Punct('(');
+        if !is_add {
+            i += 1;
+            continue;
+        }
+        let Some(close) = matching(body, i + 2) else {
+            break;
+        };
+        out.push(&body[i + 3..close]);
+        i = close + 1;
+    }
+    out
+}
+
+/// Value of the first string-literal argument of `call(...)`, e.g. `Property::Name("AOD_X")`.
+pub fn string_arg(source: &str, path: &[&str]) -> Option<String> {
+    let tokens = tokenize(source);
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(after) = match_path(&tokens, i, path)
+            && tokens.get(after) == Some(&Token::Punct('('))
+            && let Some(Token::Str(s)) = tokens.get(after + 1)
+        {
+            return Some(s.clone());
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Value of a `const NAME: &str = "...";` item.
+pub fn string_const(source: &str, name: &str) -> Option<String> {
+    let tokens = tokenize(source);
+    tokens.windows(2).enumerate().find_map(|(i, w)| {
+        if w[0] != Token::Ident("const".into()) || w[1] != Token::Ident(name.into()) {
+            return None;
+        }
+        tokens[i + 2..].iter().find_map(|t| match t {
+            Token::Str(s) => Some(s.clone()),
+            _ => None,
+        })
+    })
+}
+
+fn parse_add_args(args: &[Token]) -> Option<ParamInfo> {
+    let parts = split_top_level(args, ',');
+    let id = match parts.first()? {
+        [
+            Token::Ident(_),
+            Token::Punct(':'),
+            Token::Punct(':'),
+            Token::Ident(v),
+        ] => v.clone(),
+        _ => return None,
+    };
+    let name = localizable_str(parts.get(1)?)?;
+    let def = parts.get(2)?;
+    let kind = def.iter().find_map(|t| match t {
+        Token::Ident(s) if s.ends_with("Def") => Some(s.trim_end_matches("Def").to_string()),
+        _ => None,
+    })?;
+
+    Some(ParamInfo {
+        id,
+        name,
+        kind,
+        default: setter_arg(def, "set_default"),
+        min: setter_arg(def, "set_valid_min"),
+        max: setter_arg(def, "set_valid_max"),
+        options: setter_tokens(def, "set_options")
+            .map(|ts| {
+                ts.iter()
+                    .filter_map(|t| match t {
+                        Token::Str(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        label: setter_tokens(def, "set_label").and_then(localizable_str),
+    })
+}
+
+/// `"Text"` or `tr("Text")` (a string looked up in the generated string table).
+fn localizable_str(tokens: &[Token]) -> Option<String> {
+    match tokens {
+        [Token::Str(s)] => Some(s.clone()),
+        [
+            Token::Ident(f),
+            Token::Punct('('),
+            Token::Str(s),
+            Token::Punct(')'),
+        ] if f == "tr" => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Like [`localizable_str`], but also accepts names built at runtime: the
+/// template of `format!("Light {} Type", ..)` or the `tr("...")` inside an
+/// expression such as `i18n::fill(tr("Light {} Type"), n)`.
+fn name_str(tokens: &[Token]) -> Option<String> {
+    if let Some(s) = localizable_str(tokens) {
+        return Some(s);
+    }
+    let tokens = tokens.strip_prefix(&[Token::Punct('&')]).unwrap_or(tokens);
+    if let [
+        Token::Ident(f),
+        Token::Punct('!'),
+        Token::Punct('('),
+        Token::Str(s),
+        ..,
+    ] = tokens
+        && f == "format"
+    {
+        return Some(s.clone());
+    }
+    tokens.windows(4).find_map(|w| match w {
+        [
+            Token::Ident(f),
+            Token::Punct('('),
+            Token::Str(s),
+            Token::Punct(')'),
+        ] if f == "tr" => Some(s.clone()),
+        _ => None,
+    })
+}
+
+fn setter_tokens<'a>(def: &'a [Token], setter: &str) -> Option<&'a [Token]> {
+    let at = def
+        .windows(2)
+        .position(|w| w[0] == Token::Ident(setter.into()) && w[1] == Token::Punct('('))?;
+    let close = matching(def, at + 1)?;
+    Some(&def[at + 2..close])
+}
+
+fn setter_arg(def: &[Token], setter: &str) -> Option<String> {
+    setter_tokens(def, setter).map(render)
+}
+
+/// Re-assembles tokens into compact source text (`-1.0`, `Pixel8 { red: 255, .. }`).
+fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for t in tokens {
+        let piece = match t {
+            Token::Ident(s) | Token::Number(s) => s.clone(),
+            Token::Str(s) => format!("{s:?}"),
+            Token::Punct(c) => c.to_string(),
+        };
+        let needs_space = matches!(t, Token::Ident(_) | Token::Number(_) | Token::Str(_))
+            && out
+                .chars()
+                .last()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == ',' || c == '"');
+        if needs_space || (matches!(t, Token::Punct('{' | '}')) && !out.is_empty()) {
+            out.push(' ');
+        }
+        out.push_str(&piece);
+    }
+    out
+}
+
+fn match_path(tokens: &[Token], at: usize, path: &[&str]) -> Option<usize> {
+    let mut i = at;
+    for (n, seg) in path.iter().enumerate() {
+        if n > 0 {
+            if tokens.get(i) != Some(&Token::Punct(':'))
+                || tokens.get(i + 1) != Some(&Token::Punct(':'))
+            {
+                return None;
+            }
+            i += 2;
+        }
+        if tokens.get(i) != Some(&Token::Ident((*seg).into())) {
+            return None;
+        }
+        i += 1;
+    }
+    Some(i)
+}
+
+fn fn_body<'a>(tokens: &'a [Token], name: &str) -> Option<&'a [Token]> {
+    let at = tokens
+        .windows(2)
+        .position(|w| w[0] == Token::Ident("fn".into()) && w[1] == Token::Ident(name.into()))?;
+    let open = (at..tokens.len()).find(|&i| tokens[i] == Token::Punct('{'))?;
+    let close = matching(tokens, open)?;
+    Some(&tokens[open + 1..close])
+}
+
+/// Index of the bracket closing the one at `open`.
+fn matching(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, t) in tokens.iter().enumerate().skip(open) {
+        match t {
+            Token::Punct('(' | '[' | '{') => depth += 1,
+            Token::Punct(')' | ']' | '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(tokens: &[Token], sep: char) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, t) in tokens.iter().enumerate() {
+        match t {
+            Token::Punct('(' | '[' | '{') => depth += 1,
+            Token::Punct(')' | ']' | '}') => depth -= 1,
+            Token::Punct(c) if *c == sep && depth == 0 => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+fn tokenize(src: &str) -> Vec<Token> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                i += 1;
+            }
+            i += 2;
+        } else if c == 'r' && matches!(chars.get(i + 1), Some('"' | '#')) {
+            // raw string: r"..." / r#"..."#
+            let mut j = i + 1;
+            let mut hashes = 0;
+            while chars.get(j) == Some(&'#') {
+                hashes += 1;
+                j += 1;
+            }
+            if chars.get(j) != Some(&'"') {
+                tokens.push(Token::Ident("r".into()));
+                i += 1;
+                continue;
+            }
+            let start = j + 1;
+            let mut end = start;
+            while end < chars.len()
+                && !(chars[end] == '"' && (1..=hashes).all(|h| chars.get(end + h) == Some(&'#')))
+            {
+                end += 1;
+            }
+            tokens.push(Token::Str(
+                chars[start..end.min(chars.len())].iter().collect(),
+            ));
+            i = end + 1 + hashes;
+        } else if c == '"' {
+            let mut s = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                    s.push(match chars[i] {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        other => other,
+                    });
+                } else {
+                    s.push(chars[i]);
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(s));
+        } else if c == '\'' {
+            // char literal ('x', '\n') or lifetime ('a)
+            if chars.get(i + 2) == Some(&'\'') {
+                i += 3;
+            } else if chars.get(i + 1) == Some(&'\\') {
+                i += 3;
+                while i < chars.len() && chars[i] != '\'' {
+                    i += 1;
+                }
+                i += 1;
+            } else {
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+            }
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric()
+                    || chars[i] == '_'
+                    || (chars[i] == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())))
+            {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        }
+    }
+    tokens
+}
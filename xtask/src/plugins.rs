@@ -0,0 +1,137 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Metadata of a plugin crate under `plugins/`.
+#[derive(Clone, Debug)]
+pub struct PluginInfo {
+    /// Directory name (kebab-case), e.g. `red-noise`.
+    pub dir_name: String,
+    pub dir: PathBuf,
+    /// `package.name` (snake_case), e.g. `red_noise`.
+    pub crate_name: String,
+    pub version: String,
+    /// `package.description`.
+    pub description: String,
+    /// AE display name from the plugin Justfile, e.g. `AOD_RedNoise`.
+    pub plugin_name: String,
+}
+
+impl PluginInfo {
+    pub fn bundle_identifier(&self) -> String {
+        format!("com.aodaruma.{}", self.plugin_name)
+    }
+
+    /// File stem of the compiled library (`lib<stem>.dylib` / `<stem>.dll`).
+    pub fn lib_name(&self) -> String {
+        self.crate_name.replace('-', "_")
+    }
+
+    pub fn read_source(&self, relative: &str) -> String {
+        fs::read_to_string(self.dir.join(relative)).unwrap_or_default()
+    }
+}
+
+/// Lists every plugin crate in `plugins/`, sorted by directory name.
+pub fn discover(root: &Path) -> Result<Vec<PluginInfo>> {
+    let plugins_dir = root.join("plugins");
+    let mut out = Vec::new();
+    for entry in fs::read_dir(&plugins_dir)
+        .with_context(|| format!("failed to read {}", plugins_dir.display()))?
+    {
+        let dir = entry?.path();
+        if dir.join("Cargo.toml").is_file() {
+            out.push(load(&dir)?);
+        }
+    }
+    out.sort_by(|a, b| a.dir_name.cmp(&b.dir_name));
+    Ok(out)
+}
+
+/// Resolves `-p` selections (directory or crate names); an empty selection means all plugins.
+pub fn select(root: &Path, names: &[String]) -> Result<Vec<PluginInfo>> {
+    let all = discover(root)?;
+    if names.is_empty() {
+        return Ok(all);
+    }
+    names
+        .iter()
+        .map(|name| {
+            all.iter()
+                .find(|p| &p.dir_name == name || &p.crate_name == name || &p.plugin_name == name)
+                .cloned()
+                .with_context(|| format!("unknown plugin `{name}`"))
+        })
+        .collect()
+}
+
+fn load(dir: &Path) -> Result<PluginInfo> {
+    let manifest = fs::read_to_string(dir.join("Cargo.toml"))?;
+    let crate_name = package_field(&manifest, "name")
+        .with_context(|| format!("{}: missing package.name", dir.display()))?;
+    let version = package_field(&manifest, "version").unwrap_or_default();
+    let description = package_field(&manifest, "description").unwrap_or_default();
+
+    let justfile = fs::read_to_string(dir.join("Justfile")).unwrap_or_default();
+    let plugin_name = justfile
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(":=")?;
+            (key.trim() == "PluginName").then(|| value.trim().trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| format!("AOD_{}", pascal_case(&crate_name)));
+
+    let dir_name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if dir_name.is_empty() {
+        bail!("invalid plugin directory {}", dir.display());
+    }
+
+    Ok(PluginInfo {
+        dir_name,
+        dir: dir.to_path_buf(),
+        crate_name,
+        version,
+        description,
+        plugin_name,
+    })
+}
+
+/// Reads a string field from the `[package]` table (same approach as the Justfile awk script).
+fn package_field(manifest: &str, key: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if in_package {
+                break;
+            }
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=')
+            && k.trim() == key
+        {
+            return Some(v.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+pub fn pascal_case(name: &str) -> String {
+    name.split(['_', '-'])
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
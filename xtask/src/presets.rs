@@ -0,0 +1,184 @@
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::params::{self, ParamInfo};
+use crate::plugins;
+use crate::{target_dir, workspace_root};
+
+struct Options {
+    release: bool,
+    check: bool,
+    plugins: Vec<String>,
+}
+
+fn parse(args: &[String]) -> Result<Options> {
+    let mut opts = Options {
+        release: false,
+        check: false,
+        plugins: Vec::new(),
+    };
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--release" => opts.release = true,
+            "--check" => opts.check = true,
+            "-p" | "--plugin" => opts
+                .plugins
+                .push(it.next().context("`-p` needs a plugin name")?.clone()),
+            other => bail!("unknown option `{other}` for `presets`"),
+        }
+    }
+    Ok(opts)
+}
+
+/// Validates `plugins/<name>/presets/*.json` against the current `params_setup` and
+/// copies them to `target/<profile>/presets/<PluginName>/` for packaging.
+///
+/// A preset is `{ "name": "...", "params": { "<Param Name>": value, ... } }` where the
+/// value is a number (sliders, angle), a bool (checkbox), an option string or 1-based
+/// index (popup), `[r, g, b(, a)]` in 0-1 (color) or `[x, y]` (point).
+pub fn run(args: &[String]) -> Result<()> {
+    let opts = parse(args)?;
+    let root = workspace_root();
+    let targets = if opts.plugins.is_empty() {
+        plugins::discover(&root)?
+    } else {
+        plugins::select(&root, &opts.plugins)?
+    };
+    let out_root = target_dir()
+        .join(if opts.release { "release" } else { "debug" })
+        .join("presets");
+
+    let mut problems = Vec::new();
+    let mut bundled = 0;
+    for plugin in &targets {
+        let dir = plugin.dir.join("presets");
+        if !dir.is_dir() {
+            continue;
+        }
+        let schema = params::extract_params(&plugin.read_source("src/lib.rs"));
+        let mut files: Vec<_> = fs::read_dir(&dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .collect();
+        files.sort();
+
+        let out = out_root.join(&plugin.plugin_name);
+        for file in &files {
+            let errors = validate(file, &schema);
+            if !errors.is_empty() {
+                let rel = file.strip_prefix(&root).unwrap_or(file);
+                problems.extend(
+                    errors
+                        .into_iter()
+                        .map(|e| format!("{}: {e}", rel.display())),
+                );
+                continue;
+            }
+            if !opts.check {
+                fs::create_dir_all(&out)?;
+                fs::copy(file, out.join(file.file_name().unwrap()))?;
+            }
+            bundled += 1;
+        }
+    }
+
+    if !problems.is_empty() {
+        for p in &problems {
+            eprintln!("error: {p}");
+        }
+        bail!("{} preset problem(s) found", problems.len());
+    }
+    if opts.check {
+        println!("{bundled} preset(s) valid");
+    } else {
+        println!("{bundled} preset(s) bundled into {}", out_root.display());
+    }
+    Ok(())
+}
+
+fn validate(path: &Path, schema: &[ParamInfo]) -> Vec<String> {
+    let preset: Value = match fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| serde_json::from_str(&s).map_err(Into::into))
+    {
+        Ok(v) => v,
+        Err(e) => return vec![e.to_string()],
+    };
+
+    let mut errors = Vec::new();
+    if !preset.get("name").is_some_and(Value::is_string) {
+        errors.push("missing string field `name`".to_string());
+    }
+    let Some(values) = preset.get("params").and_then(Value::as_object) else {
+        errors.push("missing object field `params`".to_string());
+        return errors;
+    };
+    for (name, value) in values {
+        match schema.iter().find(|p| &p.name == name) {
+            Some(param) => {
+                if let Err(e) = check_value(param, value) {
+                    errors.push(format!("`{name}`: {e}"));
+                }
+            }
+            None => errors.push(format!("`{name}` is not a parameter of this plugin")),
+        }
+    }
+    errors
+}
+
+fn check_value(param: &ParamInfo, value: &Value) -> Result<(), String> {
+    let bound = |s: &Option<String>| s.as_deref().and_then(|s| s.parse::<f64>().ok());
+    let in_range = |v: f64| {
+        let (min, max) = (bound(&param.min), bound(&param.max));
+        if min.is_some_and(|m| v < m) || max.is_some_and(|m| v > m) {
+            Err(format!(
+                "{v} is outside {}..={}",
+                param.min.as_deref().unwrap_or("-inf"),
+                param.max.as_deref().unwrap_or("inf")
+            ))
+        } else {
+            Ok(())
+        }
+    };
+    let numbers = |len: &[usize]| -> Result<Vec<f64>, String> {
+        let items = value
+            .as_array()
+            .filter(|a| len.contains(&a.len()))
+            .ok_or_else(|| format!("expected an array of {len:?} numbers"))?;
+        items
+            .iter()
+            .map(|v| v.as_f64().ok_or_else(|| "expected numbers".to_string()))
+            .collect()
+    };
+
+    match param.kind.as_str() {
+        "FloatSlider" | "Angle" => in_range(value.as_f64().ok_or("expected a number")?),
+        "Slider" => in_range(value.as_i64().ok_or("expected an integer")? as f64),
+        "CheckBox" => value
+            .as_bool()
+            .map(|_| ())
+            .ok_or_else(|| "expected a bool".into()),
+        "Popup" => match value {
+            Value::String(s) if param.options.contains(s) => Ok(()),
+            Value::String(s) => Err(format!("`{s}` is not one of {:?}", param.options)),
+            Value::Number(n) => match n.as_u64() {
+                Some(i) if (1..=param.options.len() as u64).contains(&i) => Ok(()),
+                _ => Err(format!("index must be 1..={}", param.options.len())),
+            },
+            _ => Err("expected an option name or 1-based index".into()),
+        },
+        "Color" => {
+            let c = numbers(&[3, 4])?;
+            if c.iter().all(|v| (0.0..=1.0).contains(v)) {
+                Ok(())
+            } else {
+                Err("color components must be in 0..=1".into())
+            }
+        }
+        "Point" => numbers(&[2]).map(|_| ()),
+        other => Err(format!("{other} parameters cannot be stored in presets")),
+    }
+}
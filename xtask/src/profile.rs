@@ -0,0 +1,60 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::process::Command;
+
+use crate::fuzz::has_harness;
+use crate::plugins;
+use crate::{run_command, target_dir, workspace_root};
+
+/// Profiles a plugin's render core (`examples/harness.rs profile`) with
+/// `cargo flamegraph` (perf on Linux, dtrace on macOS) and writes
+/// `target/profile/<plugin>.svg`.
+pub fn run(args: &[String]) -> Result<()> {
+    let mut plugin = None;
+    let mut harness_args: Vec<String> = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--frames" | "--width" | "--height" | "--seed" => {
+                let value = it
+                    .next()
+                    .with_context(|| format!("`{arg}` needs a value"))?;
+                harness_args.extend([arg.clone(), value.clone()]);
+            }
+            other if other.starts_with('-') => bail!("unknown option `{other}` for `profile`"),
+            other => plugin = Some(other.to_string()),
+        }
+    }
+    let plugin = plugin.context(
+        "usage: cargo xtask profile <plugin> [--frames N] [--width W] [--height H] [--seed S]",
+    )?;
+
+    let root = workspace_root();
+    let plugin = plugins::select(&root, &[plugin])?.remove(0);
+    if !has_harness(&plugin) {
+        bail!(
+            "{} has no render-core harness (add examples/harness.rs, see utils::harness)",
+            plugin.dir_name
+        );
+    }
+
+    let out_dir = target_dir().join("profile");
+    fs::create_dir_all(&out_dir)?;
+    let svg = out_dir.join(format!("{}.svg", plugin.dir_name));
+
+    run_command(
+        Command::new(env!("CARGO"))
+            .current_dir(&root)
+            // symbols for the optimized build, otherwise the graph is all addresses
+            .env("CARGO_PROFILE_RELEASE_DEBUG", "true")
+            .args(["flamegraph", "--release", "-p", &plugin.crate_name])
+            .args(["--example", "harness", "-o"])
+            .arg(&svg)
+            .args(["--", "profile"])
+            .args(&harness_args),
+    )
+    .context("cargo flamegraph failed (install it with `cargo install flamegraph`)")?;
+
+    println!("Flamegraph: {}", svg.display());
+    Ok(())
+}